@@ -1,20 +1,37 @@
-use anyhow::{Context, Result};
-use axum::Json;
-use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use anyhow::{bail, ensure, Context, Result};
+use axum::{
+    body::Body,
+    extract::Query,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Datelike, Duration, DurationRound, TimeZone, Utc};
 use serde::de::{self, Deserializer};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::Infallible,
     sync::Arc,
 };
-use tokio::{sync::RwLock, time::interval};
-use tracing::{error, info};
-
-use crate::config::ActivityMonitoringConfig;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::{
+    aggregator::{aggregate, Event, Heatmap, Window, WindowStats},
+    checkpoint,
+    config::ActivityMonitoringConfig,
+    display::compact_count,
+    explorer::ExplorerClient,
+    polling::AdaptiveInterval,
+    tasks::{TaskRegistry, ACTIVITY_STATS_TASK},
+    user_op_history::{SharedUserOpHistory, StoredUserOp},
+    utils::{csv_line, parse_duration, redact_address},
+};
 
 /// Enum for activity statistics
-#[derive(Debug, Eq, PartialEq, Hash, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 enum ActivityStatName {
     #[serde(rename = "ACTIVITY_STATS__USER_OPS")]
@@ -25,8 +42,12 @@ enum ActivityStatName {
     UniqueActiveAccounts,
 }
 
+impl ActivityStatName {
+    const ALL: [Self; 3] = [Self::UserOps, Self::GasUsed, Self::UniqueActiveAccounts];
+}
+
 /// Enum for time windows
-#[derive(Debug, Eq, PartialEq, Hash, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 enum TimeWindow {
     #[serde(rename = "TIME_WINDOW__LAST_24_HOURS")]
@@ -38,19 +59,22 @@ enum TimeWindow {
 }
 
 impl TimeWindow {
+    const ALL: [Self; 3] = [Self::Last24Hours, Self::Last30Days, Self::YearToDate];
+
     fn to_duration(&self, now: DateTime<Utc>) -> Duration {
         match self {
             TimeWindow::Last24Hours => Duration::days(1),
             TimeWindow::Last30Days => Duration::days(30),
+            // Since Jan 1st 00:00 UTC
             TimeWindow::YearToDate => {
-                Duration::days(now.ordinal() as i64) // Days since Jan 1st
+                now - Utc.with_ymd_and_hms(now.year(), 1, 1, 0, 0, 0).unwrap()
             }
         }
     }
 }
 
 /// Enum for account selection criteria
-#[derive(Debug, Eq, PartialEq, Hash, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 enum SelectAccountsBy {
     #[serde(rename = "ACCOUNTS__RECENT")]
@@ -59,12 +83,182 @@ enum SelectAccountsBy {
     TopGasConsumers24h,
 }
 
-/// Struct for holding parsed JSON
+impl SelectAccountsBy {
+    const ALL: [Self; 2] = [Self::Recent, Self::TopGasConsumers24h];
+}
+
+/// Format version of `activity_keys.json` read by this backend
+const ACTIVITY_KEYS_VERSION: u64 = 2;
+
+/// Steps to migrate an `activity_keys.json` of an older format version
+const ACTIVITY_KEYS_MIGRATION: &str = "add `\"version\": 2` at the top level; the \
+    existing sections are unchanged, and the optional `custom_windows` and \
+    `account_groups` sections may be added";
+
+/// Struct for holding parsed JSON, see [`ActivityStatsKeys::parse`]
 #[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct ActivityStatsKeys {
     activity_stat_names: HashMap<ActivityStatName, String>,
     time_windows: HashMap<TimeWindow, String>,
     select_accounts_by: HashMap<SelectAccountsBy, String>,
+    /// Trailing windows next to `time_windows` by label, e.g. `"7d": "7d"`
+    #[serde(default, deserialize_with = "parse_custom_windows")]
+    custom_windows: BTreeMap<String, Duration>,
+    /// Senders of each group by group name, whose operations also get stats of their own
+    #[serde(default, deserialize_with = "lowercase_account_groups")]
+    account_groups: BTreeMap<String, HashSet<String>>,
+}
+
+fn parse_custom_windows<'de, D>(deserializer: D) -> Result<BTreeMap<String, Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    BTreeMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(label, duration)| {
+            let parsed = parse_duration(&duration)
+                .filter(|duration| *duration > Duration::zero())
+                .ok_or_else(|| {
+                    de::Error::custom(format!(
+                        "custom window `{}` has invalid duration `{}`, expected e.g. `7d`",
+                        label, duration
+                    ))
+                })?;
+            Ok((label, parsed))
+        })
+        .collect()
+}
+
+fn lowercase_account_groups<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, HashSet<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let groups = BTreeMap::<String, Vec<String>>::deserialize(deserializer)?;
+    Ok(groups
+        .into_iter()
+        .map(|(group, senders)| {
+            let senders = senders.iter().map(|s| s.to_lowercase()).collect();
+            (group, senders)
+        })
+        .collect())
+}
+
+/// Errs naming the keys of `all` missing from a section of `activity_keys.json`
+fn check_section<K: Eq + std::hash::Hash + Serialize>(
+    section: &str,
+    labels: &HashMap<K, String>,
+    all: &[K],
+) -> Result<()> {
+    let missing: Vec<String> = all
+        .iter()
+        .filter(|key| !labels.contains_key(key))
+        .filter_map(|key| serde_json::to_value(key).ok()?.as_str().map(str::to_string))
+        .collect();
+    ensure!(
+        missing.is_empty(),
+        "activity_keys.json `{}` is missing {}",
+        section,
+        missing.join(", ")
+    );
+    Ok(())
+}
+
+impl ActivityStatsKeys {
+    /// Parses `activity_keys.json`. Files of another format version are rejected
+    /// with the steps to migrate them, and sections missing keys or unknown to the
+    /// format with the offending names.
+    pub(crate) fn parse(data: &str) -> Result<Self> {
+        let mut value: Value =
+            serde_json::from_str(data).context("activity_keys.json is not valid JSON")?;
+        let version = value
+            .as_object_mut()
+            .and_then(|keys| keys.remove("version"));
+        match version.as_ref().map(Value::as_u64) {
+            None => bail!(
+                "activity_keys.json has no `version` and predates format version {}: {}",
+                ACTIVITY_KEYS_VERSION,
+                ACTIVITY_KEYS_MIGRATION
+            ),
+            Some(None) => bail!("activity_keys.json `version` is not an integer"),
+            Some(Some(version)) if version < ACTIVITY_KEYS_VERSION => bail!(
+                "activity_keys.json is format version {}, migrate to version {}: {}",
+                version,
+                ACTIVITY_KEYS_VERSION,
+                ACTIVITY_KEYS_MIGRATION
+            ),
+            Some(Some(version)) if version > ACTIVITY_KEYS_VERSION => bail!(
+                "activity_keys.json is format version {}, newer than the supported version {}",
+                version,
+                ACTIVITY_KEYS_VERSION
+            ),
+            Some(Some(_)) => {}
+        }
+
+        let keys: Self = serde_json::from_value(value).with_context(|| {
+            format!(
+                "activity_keys.json is not a valid format version {} file",
+                ACTIVITY_KEYS_VERSION
+            )
+        })?;
+        check_section(
+            "activity_stat_names",
+            &keys.activity_stat_names,
+            &ActivityStatName::ALL,
+        )?;
+        check_section("time_windows", &keys.time_windows, &TimeWindow::ALL)?;
+        check_section(
+            "select_accounts_by",
+            &keys.select_accounts_by,
+            &SelectAccountsBy::ALL,
+        )?;
+        // Labels key the stats, so one window would overwrite another
+        let mut labels = HashSet::new();
+        for label in keys.window_labels() {
+            ensure!(
+                labels.insert(label),
+                "activity_keys.json window label `{}` is used twice",
+                label
+            );
+        }
+        Ok(keys)
+    }
+
+    /// Labels of the time windows followed by the custom windows
+    fn window_labels(&self) -> impl Iterator<Item = &String> {
+        self.time_windows.values().chain(self.custom_windows.keys())
+    }
+
+    /// Time windows followed by the custom windows, trailing `now`
+    fn windows(&self, now: DateTime<Utc>) -> Vec<Window> {
+        self.time_windows
+            .iter()
+            .map(|(tw, label)| Window::trailing(label.clone(), now, tw.to_duration(now)))
+            .chain(
+                self.custom_windows
+                    .iter()
+                    .map(|(label, duration)| Window::trailing(label.clone(), now, *duration)),
+            )
+            .collect()
+    }
+
+    /// Windows whose active accounts are compared with those of the preceding window
+    /// of the same length. The year to date is left out, as the preceding one would
+    /// span the previous year.
+    fn churn_windows(&self, now: DateTime<Utc>) -> Vec<Window> {
+        let year_to_date = self.time_windows.get(&TimeWindow::YearToDate);
+        self.windows(now)
+            .into_iter()
+            .filter(|window| Some(&window.key) != year_to_date)
+            .collect()
+    }
+
+    /// Getter for `account_groups`
+    pub(crate) fn account_groups(&self) -> &BTreeMap<String, HashSet<String>> {
+        &self.account_groups
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -79,8 +273,18 @@ struct Account {
     gas_used: u64,
 }
 
+impl Account {
+    /// Account as shown on the dashboard, with its address redacted if configured
+    fn for_display(mut self, config: &ActivityMonitoringConfig) -> Self {
+        if config.redact_addresses() {
+            self.address = redact_address(&self.address);
+        }
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
-struct UserOp {
+pub(crate) struct UserOp {
     #[serde(rename = "address", deserialize_with = "get_address_hash")]
     sender: String,
 
@@ -89,11 +293,152 @@ struct UserOp {
     gas_used: u64,
 
     timestamp: String,
+
+    /// User operation hash, unique per entry point
+    #[serde(default)]
+    hash: Option<String>,
+
+    /// Contract called by the operation, if reported by the explorer
+    #[serde(
+        rename = "execute_target",
+        default,
+        deserialize_with = "get_optional_address_hash"
+    )]
+    target: Option<String>,
 }
 
-struct UserOpsResponse {
-    user_ops: Vec<UserOp>,
-    next_page_token: Option<String>,
+impl UserOp {
+    pub(crate) fn event(&self) -> Option<Event<'_>> {
+        let at = DateTime::parse_from_rfc3339(&self.timestamp).ok()?;
+        Some(Event {
+            at: at.with_timezone(&Utc),
+            account: &self.sender,
+            gas_used: self.gas_used,
+        })
+    }
+
+    /// The operation as stored, `None` if its timestamp is unparseable
+    fn stored(&self) -> Option<StoredUserOp> {
+        let event = self.event()?;
+        Some(StoredUserOp {
+            at: event.at,
+            sender: self.sender.clone(),
+            gas_used: self.gas_used,
+            hash: self.hash.clone(),
+            target: self.target.clone(),
+        })
+    }
+}
+
+/// Aggregate published in place of an account list
+#[derive(Serialize)]
+struct AccountsAggregate {
+    accounts: usize,
+    /// Withheld for lists with fewer accounts than the k-anonymity threshold
+    gas_used: Option<u64>,
+}
+
+/// Selected account lists, keyed by the names in SELECTED_ACCOUNTS.
+///
+/// With `ACCOUNTS_K_ANONYMITY` set, lists are serialized as aggregates only, so that
+/// no address leaves the backend regardless of what the frontend displays.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(from = "HashMap<String, Vec<Account>>")]
+struct SelectedAccounts {
+    lists: HashMap<String, Vec<Account>>,
+    k_anonymity: Option<usize>,
+}
+
+impl From<HashMap<String, Vec<Account>>> for SelectedAccounts {
+    fn from(lists: HashMap<String, Vec<Account>>) -> Self {
+        Self {
+            lists,
+            k_anonymity: None,
+        }
+    }
+}
+
+impl Serialize for SelectedAccounts {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(k) = self.k_anonymity else {
+            return self.lists.serialize(serializer);
+        };
+        let aggregates: HashMap<&str, AccountsAggregate> = self
+            .lists
+            .iter()
+            .map(|(key, accounts)| {
+                let aggregate = AccountsAggregate {
+                    accounts: accounts.len(),
+                    gas_used: (accounts.len() >= k)
+                        .then(|| accounts.iter().map(|account| account.gas_used).sum()),
+                };
+                (key.as_str(), aggregate)
+            })
+            .collect();
+        aggregates.serialize(serializer)
+    }
+}
+
+/// User operations and gas of an account over the scanned period
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+struct AccountTotals {
+    user_ops: u64,
+    gas_used: u64,
+}
+
+/// Row of the accounts export
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AccountRecord {
+    address: String,
+    /// Unset for accounts created before the scanned period
+    creation_timestamp: Option<String>,
+    gas_used: u64,
+    user_ops: u64,
+}
+
+impl AccountRecord {
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.address.clone(),
+            self.creation_timestamp.clone().unwrap_or_default(),
+            self.gas_used.to_string(),
+            self.user_ops.to_string(),
+        ]
+    }
+}
+
+/// Rows of the accounts export: every account that was created or sent user operations
+/// in the scanned period, sorted by address
+fn account_records(
+    totals: &HashMap<String, AccountTotals>,
+    created: &HashMap<String, String>,
+    config: &ActivityMonitoringConfig,
+) -> Vec<AccountRecord> {
+    let addresses: HashSet<&String> = totals.keys().chain(created.keys()).collect();
+    let mut records: Vec<AccountRecord> = addresses
+        .into_iter()
+        .filter(|address| !config.is_account_denied(address))
+        .map(|address| {
+            let account_totals = totals.get(address).cloned().unwrap_or_default();
+            AccountRecord {
+                address: if config.redact_addresses() {
+                    redact_address(address)
+                } else {
+                    address.clone()
+                },
+                creation_timestamp: created.get(address).cloned(),
+                gas_used: account_totals.gas_used,
+                user_ops: account_totals.user_ops,
+            }
+        })
+        .collect();
+    records.sort_by(|a, b| a.address.cmp(&b.address));
+    records
+}
+
+pub(crate) struct UserOpsResponse {
+    pub(crate) user_ops: Vec<UserOp>,
+    pub(crate) next_page_token: Option<String>,
 }
 
 struct AccountsResponse {
@@ -101,6 +446,45 @@ struct AccountsResponse {
     next_page_token: Option<String>,
 }
 
+/// Active accounts of a window split by whether they were created within it, along
+/// with the accounts active in the preceding window of the same length that no
+/// longer are
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AccountChurn {
+    new: u64,
+    returning: u64,
+    dormant: u64,
+}
+
+impl AccountChurn {
+    /// Churn between the accounts of the `previous` and `current` windows, given the
+    /// creation times of the accounts created within the scanned interval
+    fn new(
+        current: &WindowStats,
+        previous: &WindowStats,
+        start: DateTime<Utc>,
+        created: &HashMap<String, String>,
+    ) -> Self {
+        let is_new = |account: &String| {
+            created
+                .get(account)
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .is_some_and(|at| at >= start)
+        };
+        let new = current.gas_by_account.keys().filter(|a| is_new(a)).count() as u64;
+        let dormant = previous
+            .gas_by_account
+            .keys()
+            .filter(|account| !current.gas_by_account.contains_key(*account))
+            .count() as u64;
+        Self {
+            new,
+            returning: current.unique_accounts() - new,
+            dormant,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ActivityStats {
     /// Activity stats:
@@ -110,7 +494,60 @@ pub struct ActivityStats {
 
     /// Selected accounts: e.g. recently deployed, top gas consumers
     /// First level key is the name of stat. See SELECTED_ACCOUNTS in `activity_keys.json`.
-    selected_accounts: HashMap<String, Vec<Account>>,
+    selected_accounts: SelectedAccounts,
+
+    /// Accounts observed during the last scan, for the export; shared so that
+    /// cloning the stats does not copy them
+    #[serde(skip)]
+    accounts: Arc<Vec<AccountRecord>>,
+
+    /// Operations of the last `HEATMAP_DAYS` days of the last scan
+    #[serde(skip)]
+    heatmap: Heatmap,
+
+    /// Stats of the operations calling the contracts of each group of `DAPP_GROUPS`,
+    /// by group name, then keyed like `stats`
+    #[serde(default)]
+    dapp_stats: BTreeMap<String, HashMap<String, HashMap<String, u64>>>,
+
+    /// Stats of the operations sent by the accounts of each group of `account_groups`
+    /// in `activity_keys.json`, by group name, then keyed like `stats`
+    #[serde(default)]
+    account_group_stats: BTreeMap<String, HashMap<String, HashMap<String, u64>>>,
+
+    /// New, returning and dormant accounts by time period, see
+    /// [`ActivityStatsKeys::churn_windows`]
+    #[serde(default)]
+    account_churn: HashMap<String, AccountChurn>,
+}
+
+/// Activity stats along with compact display strings of the counts, e.g. `1.2M`
+#[derive(Serialize, Debug)]
+pub struct ActivityStatsResponse {
+    #[serde(flatten)]
+    stats: ActivityStats,
+    /// Same keys as `stats`
+    stats_display: HashMap<String, HashMap<String, String>>,
+}
+
+impl ActivityStatsResponse {
+    fn new(stats: ActivityStats) -> Self {
+        let stats_display = stats
+            .stats
+            .iter()
+            .map(|(name, windows)| {
+                let windows = windows
+                    .iter()
+                    .map(|(window, count)| (window.clone(), compact_count(*count as u128)))
+                    .collect();
+                (name.clone(), windows)
+            })
+            .collect();
+        Self {
+            stats,
+            stats_display,
+        }
+    }
 }
 
 impl ActivityStats {
@@ -122,8 +559,7 @@ impl ActivityStats {
             .map(|stat_name| {
                 let inner: HashMap<String, u64> = config
                     .activity_stats_keys()
-                    .time_windows
-                    .values()
+                    .window_labels()
                     .map(|window| (window.clone(), 0u64))
                     .collect();
                 (stat_name.clone(), inner)
@@ -139,217 +575,539 @@ impl ActivityStats {
 
         ActivityStats {
             stats,
-            selected_accounts,
+            selected_accounts: SelectedAccounts {
+                lists: selected_accounts,
+                k_anonymity: config.accounts_k_anonymity(),
+            },
+            accounts: Arc::default(),
+            heatmap: Heatmap::default(),
+            dapp_stats: BTreeMap::new(),
+            account_group_stats: BTreeMap::new(),
+            account_churn: HashMap::new(),
         }
     }
+
+    /// `(stat, time window, value)` of every stat, sorted by stat and window
+    pub fn stat_rows(&self) -> Vec<(&str, &str, u64)> {
+        let mut rows: Vec<(&str, &str, u64)> = self
+            .stats
+            .iter()
+            .flat_map(|(stat, windows)| {
+                windows
+                    .iter()
+                    .map(move |(window, value)| (stat.as_str(), window.as_str(), *value))
+            })
+            .collect();
+        rows.sort();
+        rows
+    }
 }
 
 /// Shared activity stats
 pub type SharedActivityStats = Arc<RwLock<ActivityStats>>;
 
-type UniqueAccounts = HashMap<String, HashSet<String>>;
-type AccountsGasUsage = HashMap<String, u64>;
-
-/// Periodically fetch user operations and accounts and compute activity stats
-pub async fn activity_monitoring_task(
-    shared_stats: SharedActivityStats,
-    config: &ActivityMonitoringConfig,
-) {
-    let mut interval = interval(tokio::time::Duration::from_secs(
-        config.stats_refetch_interval(),
-    ));
-
-    loop {
-        interval.tick().await;
-        let http_client = reqwest::Client::new();
+/// Max age of a user ops scan checkpoint that is still resumed after a restart
+const MAX_CHECKPOINT_AGE_HOURS: i64 = 24;
+
+/// Number of trailing days bucketed into the activity heatmap
+const HEATMAP_DAYS: i64 = 30;
+
+/// Progress of a paginated scan over user operations.
+///
+/// Persisted after every page so that a crash or deploy mid-scan resumes from
+/// the last page instead of starting the entire YTD scan over.
+#[derive(Serialize, Deserialize, Debug)]
+struct UserOpsScan {
+    /// End of the scanned interval; time windows are relative to it
+    now: DateTime<Utc>,
+    /// Start of the scanned interval
+    start_time: DateTime<Utc>,
+    /// Token of the next page to fetch
+    page_token: Option<String>,
+    /// Number of pages processed so far
+    pages_fetched: u64,
+    /// Partial stats per TIME_WINDOWS value and custom window label
+    windows: HashMap<String, WindowStats>,
+    /// Partial stats of the last 24 hours, for top gas consumers
+    last_24h: WindowStats,
+    /// Hashes of the operations of the last page, which consecutive pages can overlap
+    /// with when operations land during the scan
+    #[serde(default)]
+    last_page_hashes: HashSet<String>,
+    /// Totals per account over the whole scanned interval, for the accounts export
+    #[serde(default)]
+    accounts: HashMap<String, AccountTotals>,
+    /// Operations of the last `HEATMAP_DAYS` days by day of week and hour of day
+    #[serde(default)]
+    heatmap: Heatmap,
+    /// Partial stats per dApp group, then per TIME_WINDOWS value
+    #[serde(default)]
+    dapp_windows: HashMap<String, HashMap<String, WindowStats>>,
+    /// Partial stats per account group, then per TIME_WINDOWS value
+    #[serde(default)]
+    account_group_windows: HashMap<String, HashMap<String, WindowStats>>,
+    /// Partial stats of the window preceding each churn window, by its label
+    #[serde(default)]
+    previous_windows: HashMap<String, WindowStats>,
+    /// Time of the newest user operation stored before the scan started; only newer
+    /// ones are stored
+    #[serde(default)]
+    stored_until: Option<DateTime<Utc>>,
+    /// Operations to store once the scan completes
+    #[serde(default)]
+    unstored: Vec<StoredUserOp>,
+}
 
-        info!("Refresing activity stats...");
-        let now = Utc::now();
+impl UserOpsScan {
+    fn new(
+        now: DateTime<Utc>,
+        start_time: DateTime<Utc>,
+        stored_until: Option<DateTime<Utc>>,
+    ) -> Self {
+        UserOpsScan {
+            now,
+            start_time,
+            page_token: None,
+            pages_fetched: 0,
+            windows: HashMap::new(),
+            last_24h: WindowStats::default(),
+            last_page_hashes: HashSet::new(),
+            accounts: HashMap::new(),
+            heatmap: Heatmap::default(),
+            dapp_windows: HashMap::new(),
+            account_group_windows: HashMap::new(),
+            previous_windows: HashMap::new(),
+            stored_until,
+            unstored: Vec::new(),
+        }
+    }
 
-        // Determine the start_time for stats
-        let time_30d_earlier = now - Duration::days(30);
-        let mut start_time = Utc.with_ymd_and_hms(now.year(), 1, 1, 0, 0, 0).unwrap();
-        if time_30d_earlier < start_time {
-            start_time = time_30d_earlier;
+    /// Resumes a checkpointed scan if it is recent enough
+    fn resume(path: &str, now: DateTime<Utc>) -> Option<Self> {
+        let scan: UserOpsScan = checkpoint::load(path)?;
+        if now - scan.now > Duration::hours(MAX_CHECKPOINT_AGE_HOURS) {
+            info!(scan_time = %scan.now, "Discarding outdated user ops scan checkpoint");
+            return None;
         }
 
-        let mut locked_stats = shared_stats.write().await;
-        // Aggregate gas used per sender (in the last 24 hours)
-        let mut gas_usage: AccountsGasUsage = HashMap::new();
+        info!(
+            scan_time = %scan.now,
+            pages_fetched = scan.pages_fetched,
+            "Resuming user ops scan from checkpoint"
+        );
+        Some(scan)
+    }
 
-        let time_windows: Vec<(String, Duration)> = config
-            .activity_stats_keys()
-            .time_windows
+    /// Accounts a page of user operations into the stats of each time window.
+    ///
+    /// Operations already accounted with the previous page are skipped, so that an
+    /// overlap between pages is not counted twice.
+    fn add_page(
+        &mut self,
+        user_ops: &[UserOp],
+        time_windows: &[Window],
+        churn_windows: &[Window],
+        dapp_groups: &BTreeMap<String, HashSet<String>>,
+        account_groups: &BTreeMap<String, HashSet<String>>,
+    ) {
+        let new_ops: Vec<&UserOp> = user_ops
             .iter()
-            .map(|(tw, tw_value)| (tw_value.clone(), tw.to_duration(now)))
+            .filter(|op| {
+                op.hash
+                    .as_ref()
+                    .is_none_or(|hash| !self.last_page_hashes.contains(hash))
+            })
             .collect();
+        // Operations with unparseable timestamps are skipped, as are the ones that
+        // landed after the scan started, which the open-ended queries also return
+        let events: Vec<Event> = new_ops
+            .iter()
+            .filter_map(|op| op.event())
+            .filter(|event| event.at <= self.now)
+            .collect();
+        aggregate(
+            &mut self.windows,
+            events.iter().cloned(),
+            time_windows,
+            self.now,
+        );
 
-        // Initialize or reset stats
-        for (period, _) in &time_windows {
-            for stat_name in config.activity_stats_keys().activity_stat_names.values() {
-                locked_stats
-                    .stats
-                    .entry(stat_name.clone())
-                    .or_default()
-                    .insert(period.to_string(), 0);
-            }
+        for window in churn_windows {
+            let (previous, end) = window.preceding(self.now);
+            aggregate(
+                &mut self.previous_windows,
+                events.iter().cloned(),
+                &[previous],
+                end,
+            );
         }
 
-        // Create sets to track unique active accounts per period
-        let mut unique_accounts: UniqueAccounts = HashMap::new();
-        for (period, _) in &time_windows {
-            unique_accounts.insert(period.clone(), HashSet::new());
+        let last_24h = Window::trailing(String::new(), self.now, Duration::days(1));
+        for event in events
+            .iter()
+            .filter(|event| last_24h.contains(event.at, self.now))
+        {
+            self.last_24h.add(event);
+        }
+        let heatmap_window =
+            Window::trailing(String::new(), self.now, Duration::days(HEATMAP_DAYS));
+        for event in events
+            .iter()
+            .filter(|event| heatmap_window.contains(event.at, self.now))
+        {
+            self.heatmap.add(event.at);
+        }
+        for (group, contracts) in dapp_groups {
+            let group_events = new_ops
+                .iter()
+                .filter(|op| {
+                    op.target
+                        .as_ref()
+                        .is_some_and(|target| contracts.contains(&target.to_lowercase()))
+                })
+                .filter_map(|op| op.event());
+            aggregate(
+                self.dapp_windows.entry(group.clone()).or_default(),
+                group_events,
+                time_windows,
+                self.now,
+            );
+        }
+        for (group, senders) in account_groups {
+            let group_events = events
+                .iter()
+                .filter(|event| senders.contains(&event.account.to_lowercase()))
+                .cloned();
+            aggregate(
+                self.account_group_windows.entry(group.clone()).or_default(),
+                group_events,
+                time_windows,
+                self.now,
+            );
         }
+        for event in &events {
+            let totals = self.accounts.entry(event.account.to_string()).or_default();
+            totals.user_ops += 1;
+            totals.gas_used = totals.gas_used.saturating_add(event.gas_used);
+        }
+        self.unstored.extend(
+            new_ops
+                .iter()
+                .filter_map(|op| op.stored())
+                .filter(|op| op.at <= self.now)
+                .filter(|op| self.stored_until.is_none_or(|until| op.at > until)),
+        );
+        self.last_page_hashes = user_ops.iter().filter_map(|op| op.hash.clone()).collect();
+        self.pages_fetched += 1;
+    }
 
-        let mut more_items = true;
-        let mut page_token = None;
-        while more_items {
-            let result = fetch_user_ops(
-                &http_client,
-                config.user_ops_query_url(),
-                start_time,
-                now,
-                Some(config.query_page_size()),
-                page_token,
-            )
-            .await;
-
-            match result {
-                Ok(response) => {
-                    // compute stats for each TIME_WINDOW
-                    for entry in response.user_ops {
-                        if let Ok(op_time) = DateTime::parse_from_rfc3339(&entry.timestamp)
-                            .map(|dt| dt.with_timezone(&Utc))
-                        {
-                            for (period, duration) in &time_windows {
-                                if now - *duration <= op_time {
-                                    for (stat_key, stat_name) in
-                                        &config.activity_stats_keys().activity_stat_names
-                                    {
-                                        if matches!(
-                                            stat_key,
-                                            ActivityStatName::UserOps | ActivityStatName::GasUsed
-                                        ) {
-                                            *locked_stats
-                                                .stats
-                                                .entry(stat_name.clone()) // Get or insert HashMap entry
-                                                .or_default() // Insert default if missing
-                                                .entry(period.to_string()) // Get nested period entry
-                                                .or_insert(0) += 1; // Increment counter
-                                        }
-                                    }
-                                    // Track unique senders
-                                    unique_accounts
-                                        .get_mut(period)
-                                        .unwrap()
-                                        .insert(entry.sender.clone());
-                                }
-                            }
-                            // Update gas used by sender
-                            if now - Duration::days(1) <= op_time {
-                                *gas_usage.entry(entry.sender.clone()).or_insert(0) +=
-                                    entry.gas_used;
-                            }
-                        }
-                    }
+    /// Stats keyed like [`ActivityStats::stats`]
+    fn stats(&self, config: &ActivityMonitoringConfig) -> HashMap<String, HashMap<String, u64>> {
+        keyed_stats(&self.windows, config)
+    }
 
-                    page_token = response.next_page_token;
-                    more_items = page_token.is_some();
-                }
-                Err(e) => {
-                    error!(error = %e, "Fetch user ops failed");
+    /// Stats of each dApp group, keyed like [`ActivityStats::stats`]
+    fn dapp_stats(
+        &self,
+        config: &ActivityMonitoringConfig,
+    ) -> BTreeMap<String, HashMap<String, HashMap<String, u64>>> {
+        config
+            .dapp_groups()
+            .keys()
+            .map(|group| {
+                let windows = self.dapp_windows.get(group).cloned().unwrap_or_default();
+                (group.clone(), keyed_stats(&windows, config))
+            })
+            .collect()
+    }
+
+    /// Churn of the accounts of each churn window, by window label
+    fn account_churn(
+        &self,
+        churn_windows: &[Window],
+        created: &HashMap<String, String>,
+    ) -> HashMap<String, AccountChurn> {
+        churn_windows
+            .iter()
+            .map(|window| {
+                let current = self.windows.get(&window.key).cloned().unwrap_or_default();
+                let previous = self
+                    .previous_windows
+                    .get(&window.key)
+                    .cloned()
+                    .unwrap_or_default();
+                let churn = AccountChurn::new(&current, &previous, window.start, created);
+                (window.key.clone(), churn)
+            })
+            .collect()
+    }
+
+    /// Stats of each account group, keyed like [`ActivityStats::stats`]
+    fn account_group_stats(
+        &self,
+        config: &ActivityMonitoringConfig,
+    ) -> BTreeMap<String, HashMap<String, HashMap<String, u64>>> {
+        config
+            .activity_stats_keys()
+            .account_groups()
+            .keys()
+            .map(|group| {
+                let windows = self
+                    .account_group_windows
+                    .get(group)
+                    .cloned()
+                    .unwrap_or_default();
+                (group.clone(), keyed_stats(&windows, config))
+            })
+            .collect()
+    }
+}
+
+/// Stats of the given time windows keyed like [`ActivityStats::stats`]
+fn keyed_stats(
+    windows: &HashMap<String, WindowStats>,
+    config: &ActivityMonitoringConfig,
+) -> HashMap<String, HashMap<String, u64>> {
+    let keys = config.activity_stats_keys();
+    keys.activity_stat_names
+        .iter()
+        .map(|(stat_key, stat_name)| {
+            let inner: HashMap<String, u64> = keys
+                .window_labels()
+                .map(|period| {
+                    let window = windows.get(period).cloned().unwrap_or_default();
+                    let value = match stat_key {
+                        ActivityStatName::UserOps => window.events,
+                        ActivityStatName::GasUsed => window.gas_used,
+                        ActivityStatName::UniqueActiveAccounts => window.unique_accounts(),
+                    };
+                    (period.clone(), value)
+                })
+                .collect();
+            (stat_name.clone(), inner)
+        })
+        .collect()
+}
+
+/// Periodically fetch user operations and accounts and compute activity stats
+pub async fn activity_monitoring_task<E: ExplorerClient>(
+    shared_stats: SharedActivityStats,
+    user_ops: SharedUserOpHistory,
+    explorer: E,
+    tasks: TaskRegistry,
+    config: &ActivityMonitoringConfig,
+) {
+    tasks
+        .register(ACTIVITY_STATS_TASK, config.stats_refetch_interval())
+        .await;
+    let mut interval = AdaptiveInterval::new(ACTIVITY_STATS_TASK, config.stats_refetch_interval());
+
+    loop {
+        interval.tick().await;
+        tasks.start_refresh(ACTIVITY_STATS_TASK).await;
+        let upstream_healthy =
+            refresh_activity_stats(&shared_stats, &user_ops, &explorer, config).await;
+        interval.adapt(upstream_healthy, &tasks).await;
+        tasks.record_refresh(ACTIVITY_STATS_TASK).await;
+    }
+}
+
+/// Fetch user operations and accounts and update the shared activity stats, storing
+/// the user operations not stored yet once the scan completes.
+///
+/// Returns `false` if any explorer query failed.
+async fn refresh_activity_stats(
+    shared_stats: &SharedActivityStats,
+    user_ops: &SharedUserOpHistory,
+    explorer: &impl ExplorerClient,
+    config: &ActivityMonitoringConfig,
+) -> bool {
+    let checkpoint_path = config.checkpoint_path();
+
+    info!("Refresing activity stats...");
+    let now = Utc::now();
+
+    // Determine the start_time for stats
+    let time_30d_earlier = now - Duration::days(30);
+    let mut start_time = Utc.with_ymd_and_hms(now.year(), 1, 1, 0, 0, 0).unwrap();
+    if time_30d_earlier < start_time {
+        start_time = time_30d_earlier;
+    }
+    // Also cover the custom windows and the windows preceding the churn windows
+    let keys = config.activity_stats_keys();
+    for window in keys.churn_windows(now) {
+        start_time = start_time.min(window.preceding(now).0.start);
+    }
+    // Start at midnight so that the queries stay the same within a day
+    let start_time = start_time
+        .duration_trunc(Duration::days(1))
+        .unwrap_or(start_time);
+
+    // Pick up an interrupted scan where it left off
+    let stored_until = user_ops.read().await.stored_until();
+    let mut scan = checkpoint_path
+        .and_then(|path| UserOpsScan::resume(path, now))
+        .unwrap_or_else(|| UserOpsScan::new(now, start_time, stored_until));
+    let now = scan.now;
+    let start_time = scan.start_time;
+
+    let time_windows = keys.windows(now);
+    let churn_windows = keys.churn_windows(now);
+    let mut upstream_healthy = true;
+
+    loop {
+        let result = fetch_user_ops(
+            explorer,
+            config.user_ops_query_url(),
+            &[],
+            start_time,
+            None,
+            Some(config.query_page_size()),
+            scan.page_token.clone(),
+        )
+        .await;
+
+        match result {
+            Ok(response) => {
+                // compute stats for each TIME_WINDOW
+                scan.add_page(
+                    &response.user_ops,
+                    &time_windows,
+                    &churn_windows,
+                    config.dapp_groups(),
+                    keys.account_groups(),
+                );
+                scan.page_token = response.next_page_token;
+                if scan.page_token.is_none() {
+                    user_ops
+                        .write()
+                        .await
+                        .save(std::mem::take(&mut scan.unstored), start_time);
+                    // Scan complete, nothing left to resume
+                    if let Some(path) = checkpoint_path {
+                        checkpoint::clear(path);
+                    }
                     break;
                 }
-            }
-        }
 
-        // Store the count of unique active accounts
-        for (period, accounts_set) in unique_accounts {
-            locked_stats
-                .stats
-                .entry(
-                    config.activity_stats_keys().activity_stat_names
-                        [&ActivityStatName::UniqueActiveAccounts]
-                        .clone(),
-                ) // Use enum variant
-                .or_default()
-                .insert(period.to_string(), accounts_set.len() as u64);
+                if let Some(path) = checkpoint_path {
+                    if let Err(e) = checkpoint::save(path, &scan) {
+                        warn!(error = %e, "Failed to checkpoint user ops scan");
+                    }
+                }
+            }
+            Err(e) => {
+                // Keep the checkpoint so the next cycle resumes from the failed page
+                error!(error = %e, "Fetch user ops failed");
+                upstream_healthy = false;
+                break;
+            }
         }
+    }
 
-        let mut more_items = true;
-        let mut page_token = None;
-        while more_items {
-            let result = fetch_accounts(
-                &http_client,
-                config.accounts_query_url(),
-                start_time,
-                now,
-                Some(config.query_page_size()),
-                page_token,
-            )
-            .await;
-            match result {
-                Ok(response) => {
-                    // Sort accounts by creation_timestamp (most recent first)
-                    let mut sorted_accounts: Vec<Account> = response
+    let mut locked_stats = shared_stats.write().await;
+    locked_stats.stats = scan.stats(config);
+    locked_stats.dapp_stats = scan.dapp_stats(config);
+    locked_stats.account_group_stats = scan.account_group_stats(config);
+
+    // Creation time of the accounts created during the scanned interval
+    let mut created: HashMap<String, String> = HashMap::new();
+    let mut more_items = true;
+    let mut page_token = None;
+    while more_items {
+        let result = fetch_accounts(
+            explorer,
+            config.accounts_query_url(),
+            start_time,
+            None,
+            Some(config.query_page_size()),
+            page_token,
+        )
+        .await;
+        match result {
+            Ok(response) => {
+                created.extend(
+                    response
                         .accounts
                         .iter()
                         .filter(|acc| !acc.creation_timestamp.is_empty())
-                        .cloned()
-                        .collect();
-
-                    sorted_accounts.sort_by(|a, b| {
-                        let a_time = DateTime::parse_from_rfc3339(a.creation_timestamp.as_str())
-                            .map(|dt| dt.with_timezone(&Utc))
-                            .unwrap_or(Utc::now()); // Default to now if parsing fails
-                        let b_time = DateTime::parse_from_rfc3339(b.creation_timestamp.as_str())
-                            .map(|dt| dt.with_timezone(&Utc))
-                            .unwrap_or(Utc::now());
-
-                        b_time.cmp(&a_time) // Sort descending
-                    });
-
-                    // Take the top 5 most recent accounts
-                    let recent_accounts = sorted_accounts.into_iter().take(5).collect::<Vec<_>>();
-                    // Store in shared stats
-                    locked_stats.selected_accounts.insert(
-                        config.activity_stats_keys().select_accounts_by[&SelectAccountsBy::Recent]
-                            .clone(),
-                        recent_accounts,
-                    );
-
-                    page_token = response.next_page_token;
-                    more_items = page_token.is_some();
-                }
-                Err(e) => {
-                    error!(error = %e, "Fetch accounts failed");
-                    break;
-                }
+                        .map(|acc| (acc.address.clone(), acc.creation_timestamp.clone())),
+                );
+
+                // Sort accounts by creation_timestamp (most recent first)
+                let mut sorted_accounts: Vec<Account> = response
+                    .accounts
+                    .iter()
+                    .filter(|acc| !acc.creation_timestamp.is_empty())
+                    .filter(|acc| !config.is_account_denied(&acc.address))
+                    .cloned()
+                    .collect();
+
+                sorted_accounts.sort_by(|a, b| {
+                    let a_time = DateTime::parse_from_rfc3339(a.creation_timestamp.as_str())
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or(Utc::now()); // Default to now if parsing fails
+                    let b_time = DateTime::parse_from_rfc3339(b.creation_timestamp.as_str())
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or(Utc::now());
+
+                    b_time.cmp(&a_time) // Sort descending
+                });
+
+                // Take the top 5 most recent accounts
+                let recent_accounts = sorted_accounts
+                    .into_iter()
+                    .take(5)
+                    .map(|acc| acc.for_display(config))
+                    .collect::<Vec<_>>();
+                // Store in shared stats
+                locked_stats.selected_accounts.lists.insert(
+                    config.activity_stats_keys().select_accounts_by[&SelectAccountsBy::Recent]
+                        .clone(),
+                    recent_accounts,
+                );
+
+                page_token = response.next_page_token;
+                more_items = page_token.is_some();
+            }
+            Err(e) => {
+                error!(error = %e, "Fetch accounts failed");
+                upstream_healthy = false;
+                break;
             }
         }
+    }
 
-        // Top gas consumers: get from gas_usage and sort by gas used (descending)
-        let gas_usage_clone = gas_usage.clone();
-        let mut top_gas_consumers: Vec<Account> = gas_usage_clone
-            .into_iter()
-            .map(|(address, gas_used)| Account {
-                address,
+    // Top 5 gas consumers of the last 24 hours, ranked among all accounts so that
+    // denied accounts do not shorten the list
+    let top_gas_consumers: Vec<Account> = scan
+        .last_24h
+        .top_accounts(usize::MAX)
+        .into_iter()
+        .filter(|(address, _)| !config.is_account_denied(address))
+        .take(5)
+        .map(|(address, gas_used)| {
+            Account {
+                address: address.to_string(),
                 creation_timestamp: "".to_string(),
                 gas_used,
-            })
-            .collect();
-
-        top_gas_consumers.sort_by_key(|acc| gas_usage.get(&acc.address).cloned().unwrap_or(0));
-        top_gas_consumers.reverse();
-        top_gas_consumers.truncate(5); // Take top 5
-
-        // Store in shared stats
-        locked_stats.selected_accounts.insert(
-            config.activity_stats_keys().select_accounts_by[&SelectAccountsBy::TopGasConsumers24h]
-                .clone(),
-            top_gas_consumers,
-        );
-    }
+            }
+            .for_display(config)
+        })
+        .collect();
+
+    // Store in shared stats
+    locked_stats.selected_accounts.lists.insert(
+        config.activity_stats_keys().select_accounts_by[&SelectAccountsBy::TopGasConsumers24h]
+            .clone(),
+        top_gas_consumers,
+    );
+    locked_stats.account_churn = scan.account_churn(&churn_windows, &created);
+    locked_stats.accounts = Arc::new(account_records(&scan.accounts, &created, config));
+    locked_stats.heatmap = scan.heatmap.clone();
+    drop(locked_stats);
+
+    upstream_healthy
 }
 
 // Custom deserializer to extract "hash" from the "address" field
@@ -367,6 +1125,20 @@ where
     Err(de::Error::missing_field("address.hash"))
 }
 
+// Custom deserializer to extract "hash" from an optional address field
+fn get_optional_address_hash<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<Value> = Deserialize::deserialize(deserializer)?;
+    Ok(value.and_then(|value| {
+        value
+            .get("hash")
+            .and_then(|h| h.as_str())
+            .map(str::to_string)
+    }))
+}
+
 // Custom deserializer to convert a string to u64
 fn convert_to_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
 where
@@ -389,11 +1161,15 @@ where
     }
 }
 
+/// Fetch a page of an activity query. Without `end_time` the query is open ended,
+/// so that its URL stays the same across refreshes and the explorer can answer
+/// unchanged pages with `304 Not Modified`.
 async fn fetch_activity_common(
-    http_client: &reqwest::Client,
+    explorer: &impl ExplorerClient,
     query_url: &str,
+    filters: &[(&str, String)],
     start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
     page_size: Option<u64>,
     page_token: Option<String>,
 ) -> Result<serde_json::Value, anyhow::Error> {
@@ -402,9 +1178,11 @@ async fn fetch_activity_common(
         |time: DateTime<Utc>| -> String { time.format("%Y-%m-%d %H:%M:%S").to_string() };
 
     // Construct query parameters, only adding Some(_) values
-    let mut query_params: HashMap<&str, String> = HashMap::new();
+    let mut query_params: HashMap<&str, String> = filters.iter().cloned().collect();
     query_params.insert("start_time", format_time(start_time));
-    query_params.insert("end_time", format_time(end_time));
+    if let Some(end_time) = end_time {
+        query_params.insert("end_time", format_time(end_time));
+    }
     if let Some(size) = page_size {
         query_params.insert("page_size", size.to_string());
     }
@@ -415,35 +1193,23 @@ async fn fetch_activity_common(
     }
 
     // Send request with query parameters (browser-like format)
-    let response = http_client
-        .get(query_url)
-        .query(&query_params) // Use query parameters instead of JSON body
-        .send()
-        .await?
-        .error_for_status()? // Converts HTTP errors into Rust errors
-        .json::<serde_json::Value>()
-        .await?;
-
-    Ok(response)
+    explorer.get_json(query_url, &query_params).await
 }
 
-async fn fetch_user_ops(
-    http_client: &reqwest::Client,
+/// Fetch a page of user operations, e.g. filtered by `("paymaster", address)`
+pub(crate) async fn fetch_user_ops(
+    explorer: &impl ExplorerClient,
     query_url: &str,
+    filters: &[(&str, String)],
     start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
     page_size: Option<u64>,
     page_token: Option<String>,
 ) -> Result<UserOpsResponse, anyhow::Error> {
     info!("Fetching user operations");
 
     let data = fetch_activity_common(
-        http_client,
-        query_url,
-        start_time,
-        end_time,
-        page_size,
-        page_token,
+        explorer, query_url, filters, start_time, end_time, page_size, page_token,
     )
     .await
     .context("Failed to fetch user operations")?;
@@ -467,18 +1233,19 @@ async fn fetch_user_ops(
 }
 
 async fn fetch_accounts(
-    http_client: &reqwest::Client,
+    explorer: &impl ExplorerClient,
     query_url: &str,
     start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
     page_size: Option<u64>,
     page_token: Option<String>,
 ) -> Result<AccountsResponse, anyhow::Error> {
     info!("Fetching accounts");
 
     let data = fetch_activity_common(
-        http_client,
+        explorer,
         query_url,
+        &[],
         start_time,
         end_time,
         page_size,
@@ -507,21 +1274,117 @@ async fn fetch_accounts(
     })
 }
 
-pub async fn get_activity_stats(state: SharedActivityStats) -> Json<ActivityStats> {
+pub async fn get_activity_stats(state: SharedActivityStats) -> Json<ActivityStatsResponse> {
     let data = state.read().await.clone();
-    Json(data)
+    Json(ActivityStatsResponse::new(data))
+}
+
+/// User operations by time of day and day of week, to spot usage patterns and
+/// quiet maintenance windows
+#[derive(Serialize, Debug)]
+pub struct ActivityHeatmap {
+    window_days: i64,
+    /// Hours and days of week are in UTC
+    timezone: &'static str,
+    /// By day of week from Monday, then by hour of day
+    user_ops: [[u64; 24]; 7],
+    user_ops_by_hour: [u64; 24],
+    user_ops_by_weekday: [u64; 7],
+}
+
+/// Return user operations of the last `HEATMAP_DAYS` days bucketed by hour of day
+/// and day of week
+pub async fn get_activity_heatmap(state: SharedActivityStats) -> Json<ActivityHeatmap> {
+    let heatmap = state.read().await.heatmap.clone();
+    Json(ActivityHeatmap {
+        window_days: HEATMAP_DAYS,
+        timezone: "UTC",
+        user_ops: heatmap.events,
+        user_ops_by_hour: heatmap.by_hour(),
+        user_ops_by_weekday: heatmap.by_weekday(),
+    })
+}
+
+/// Output format of the accounts export
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountsExportFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Query parameters of the accounts export endpoint
+#[derive(Deserialize, Debug)]
+pub struct AccountsExportQuery {
+    #[serde(default)]
+    format: AccountsExportFormat,
+}
+
+/// Stream every account observed in the activity stats period, with its creation
+/// time, gas used and number of user operations.
+///
+/// Unavailable with `ACCOUNTS_K_ANONYMITY` set, as it would publish addresses.
+pub async fn get_accounts_export(
+    Query(query): Query<AccountsExportQuery>,
+    state: SharedActivityStats,
+    config: Arc<ActivityMonitoringConfig>,
+) -> Result<Response, StatusCode> {
+    if config.accounts_k_anonymity().is_some() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let accounts = Arc::clone(&state.read().await.accounts);
+
+    let (content_type, header) = match query.format {
+        AccountsExportFormat::Csv => (
+            "text/csv",
+            csv_line(&["address", "creation_timestamp", "gas_used", "user_ops"]),
+        ),
+        AccountsExportFormat::Ndjson => ("application/x-ndjson", String::new()),
+    };
+    // Rows are rendered as they are sent rather than buffered into a single body
+    let rows = (0..accounts.len()).map(move |i| {
+        let account = &accounts[i];
+        let line = match query.format {
+            AccountsExportFormat::Csv => csv_line(&account.csv_row()),
+            AccountsExportFormat::Ndjson => {
+                let mut line = serde_json::to_string(account).unwrap_or_default();
+                line.push('\n');
+                line
+            }
+        };
+        Ok::<_, Infallible>(line)
+    });
+    let body = Body::from_stream(tokio_stream::iter(std::iter::once(Ok(header)).chain(rows)));
+    Ok(([(CONTENT_TYPE, content_type)], body).into_response())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::activity::{
-        convert_to_u64, fetch_accounts, fetch_user_ops, get_address_hash, ActivityMonitoringConfig,
-        ActivityStats, TimeWindow,
+    use crate::{
+        activity::{
+            account_records, convert_to_u64, fetch_accounts, fetch_user_ops, get_address_hash,
+            refresh_activity_stats, Account, AccountChurn, AccountRecord, AccountTotals,
+            ActivityMonitoringConfig, ActivityStatName, ActivityStats, ActivityStatsKeys,
+            SelectAccountsBy, SelectedAccounts, TimeWindow, UserOp, UserOpsScan,
+        },
+        aggregator::Window,
+        explorer::{FakeExplorerClient, HttpExplorerClient},
+        rate_limit::HostRateLimiters,
+        retry_policy::ExponentialBackoff,
+        user_op_history::UserOpHistory,
     };
     use chrono::{Datelike, TimeZone, Utc};
     use mockito::{Matcher, Server};
+    use reqwest::header::HeaderMap;
     use serde::Deserialize;
-    use serde_json::json;
+    use serde_json::{json, Value};
+    use std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        sync::Arc,
+    };
+    use tokio::sync::RwLock;
 
     #[test]
     fn test_time_window_to_duration() {
@@ -536,8 +1399,8 @@ mod tests {
             chrono::Duration::days(30)
         );
 
-        // Year to date should return the number of days since Jan 1st
-        let expected_days = now.ordinal() as i64;
+        // Year to date should cover the time since Jan 1st 00:00
+        let expected_days = now.ordinal0() as i64;
         assert_eq!(
             TimeWindow::YearToDate.to_duration(now),
             chrono::Duration::days(expected_days)
@@ -565,6 +1428,7 @@ mod tests {
         for select_by in config.activity_stats_keys().select_accounts_by.values() {
             let accounts = stats
                 .selected_accounts
+                .lists
                 .get(select_by)
                 .expect("Missing selected_accounts key");
             assert!(
@@ -575,6 +1439,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_selected_accounts_k_anonymity() {
+        let account = |address: &str, gas_used| Account {
+            address: address.to_string(),
+            creation_timestamp: String::new(),
+            gas_used,
+        };
+        let mut selected_accounts = SelectedAccounts::from(HashMap::from([
+            (
+                "top".to_string(),
+                vec![account("0xaaa", 300), account("0xbbb", 100)],
+            ),
+            ("recent".to_string(), vec![account("0xccc", 0)]),
+        ]));
+        assert!(serde_json::to_string(&selected_accounts)
+            .unwrap()
+            .contains("0xaaa"));
+
+        selected_accounts.k_anonymity = Some(2);
+        let published = serde_json::to_value(&selected_accounts).unwrap();
+        assert_eq!(
+            published,
+            json!({
+                "top": { "accounts": 2, "gas_used": 400 },
+                "recent": { "accounts": 1, "gas_used": null },
+            })
+        );
+    }
+
     #[test]
     fn test_convert_to_u64() {
         #[derive(Deserialize)]
@@ -613,6 +1506,218 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_scan_skips_overlapping_pages() {
+        let now = Utc::now();
+        let op = |hash: &str| UserOp {
+            sender: "0xaaa".to_string(),
+            gas_used: 100,
+            timestamp: (now - chrono::Duration::hours(1)).to_rfc3339(),
+            hash: Some(hash.to_string()),
+            target: None,
+        };
+        let windows = [Window::trailing(
+            "24h".to_string(),
+            now,
+            chrono::Duration::days(1),
+        )];
+
+        let mut scan = UserOpsScan::new(now, now - chrono::Duration::days(1), None);
+        scan.add_page(
+            &[op("0x01"), op("0x02")],
+            &windows,
+            &[],
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+        );
+        // The next page starts with an operation of the previous one
+        scan.add_page(
+            &[op("0x02"), op("0x03")],
+            &windows,
+            &[],
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+        );
+
+        assert_eq!(scan.windows["24h"].events, 3);
+        assert_eq!(scan.last_24h.gas_used, 300);
+        assert_eq!(scan.pages_fetched, 2);
+    }
+
+    #[test]
+    fn test_scan_dapp_groups() {
+        let now = Utc::now();
+        let op = |sender: &str, target: Value| -> UserOp {
+            serde_json::from_value(json!({
+                "address": { "hash": sender },
+                "fee": "100",
+                "timestamp": (now - chrono::Duration::hours(1)).to_rfc3339(),
+                "execute_target": target,
+            }))
+            .unwrap()
+        };
+        let windows = [Window::trailing(
+            "24h".to_string(),
+            now,
+            chrono::Duration::days(1),
+        )];
+        let dapp_groups = BTreeMap::from([
+            ("dex".to_string(), HashSet::from(["0xd0d0".to_string()])),
+            ("game".to_string(), HashSet::from(["0x6a6e".to_string()])),
+        ]);
+
+        let mut scan = UserOpsScan::new(now, now - chrono::Duration::days(1), None);
+        scan.add_page(
+            &[
+                op("0xaaa", json!({ "hash": "0xD0D0" })),
+                op("0xbbb", json!({ "hash": "0xd0d0" })),
+                op("0xaaa", json!({ "hash": "0xcafe" })),
+                op("0xccc", Value::Null),
+            ],
+            &windows,
+            &[],
+            &dapp_groups,
+            &BTreeMap::from([("team".to_string(), HashSet::from(["0xaaa".to_string()]))]),
+        );
+
+        assert_eq!(scan.windows["24h"].events, 4);
+        assert_eq!(scan.dapp_windows["dex"]["24h"].events, 2);
+        assert_eq!(scan.dapp_windows["dex"]["24h"].gas_used, 200);
+        assert_eq!(scan.dapp_windows["dex"]["24h"].unique_accounts(), 2);
+        assert_eq!(scan.dapp_windows["game"]["24h"].events, 0);
+        assert_eq!(scan.account_group_windows["team"]["24h"].events, 2);
+    }
+
+    #[test]
+    fn test_account_churn() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let window = Window::trailing("24h".to_string(), now, chrono::Duration::days(1));
+        let op = |sender: &str, hours_ago: i64| -> UserOp {
+            serde_json::from_value(json!({
+                "address": { "hash": sender },
+                "fee": "100",
+                "timestamp": (now - chrono::Duration::hours(hours_ago)).to_rfc3339(),
+            }))
+            .unwrap()
+        };
+
+        let mut scan = UserOpsScan::new(now, now - chrono::Duration::days(2), None);
+        scan.add_page(
+            &[
+                op("0xnew", 1),
+                op("0xaaa", 2),
+                op("0xaaa", 30),
+                op("0xbbb", 40),
+            ],
+            std::slice::from_ref(&window),
+            std::slice::from_ref(&window),
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+        );
+        let created = HashMap::from([
+            (
+                "0xnew".to_string(),
+                (now - chrono::Duration::hours(3)).to_rfc3339(),
+            ),
+            (
+                "0xbbb".to_string(),
+                (now - chrono::Duration::hours(45)).to_rfc3339(),
+            ),
+        ]);
+
+        let churn = scan.account_churn(&[window], &created);
+        assert_eq!(
+            churn["24h"],
+            AccountChurn {
+                new: 1,
+                returning: 1,
+                dormant: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_activity_keys() {
+        let keys = ActivityStatsKeys::parse(include_str!("../activity_keys.json")).unwrap();
+        assert!(keys.custom_windows.is_empty());
+
+        let mut file: Value = serde_json::from_str(include_str!("../activity_keys.json")).unwrap();
+        file["custom_windows"] = json!({ "7d": "7d" });
+        file["account_groups"] = json!({ "team": ["0xAAA"] });
+        let keys = ActivityStatsKeys::parse(&file.to_string()).unwrap();
+        assert_eq!(keys.custom_windows["7d"], chrono::Duration::days(7));
+        assert!(keys.account_groups()["team"].contains("0xaaa"));
+        let now = Utc::now();
+        assert_eq!(keys.windows(now).len(), 4);
+
+        let error = |file: &Value| {
+            format!(
+                "{:#}",
+                ActivityStatsKeys::parse(&file.to_string()).unwrap_err()
+            )
+        };
+        let mut outdated = file.clone();
+        outdated.as_object_mut().unwrap().remove("version");
+        assert!(error(&outdated).contains("add `\"version\": 2`"));
+        outdated["version"] = json!(1);
+        assert!(error(&outdated).contains("migrate to version 2"));
+
+        let mut invalid = file.clone();
+        invalid["custom_windows"] = json!({ "7d": "a week" });
+        assert!(error(&invalid).contains("custom window `7d`"));
+        invalid["custom_windows"] = json!({ "24h": "1d" });
+        assert!(error(&invalid).contains("`24h` is used twice"));
+        let mut invalid = file.clone();
+        invalid["usage_stats"] = json!({});
+        assert!(error(&invalid).contains("unknown field `usage_stats`"));
+        let mut invalid = file;
+        invalid["time_windows"]
+            .as_object_mut()
+            .unwrap()
+            .remove("TIME_WINDOW__YEAR_TO_DATE");
+        assert!(error(&invalid).contains("`time_windows` is missing TIME_WINDOW__YEAR_TO_DATE"));
+    }
+
+    #[test]
+    fn test_account_records() {
+        let config = ActivityMonitoringConfig::new();
+        let totals = HashMap::from([(
+            "0xbbb".to_string(),
+            AccountTotals {
+                user_ops: 2,
+                gas_used: 300,
+            },
+        )]);
+        // Created during the period without sending any operation yet
+        let created = HashMap::from([
+            ("0xaaa".to_string(), "2025-02-17T00:00:00Z".to_string()),
+            ("0xbbb".to_string(), "2025-02-16T00:00:00Z".to_string()),
+        ]);
+
+        let records = account_records(&totals, &created, &config);
+        assert_eq!(
+            records,
+            vec![
+                AccountRecord {
+                    address: "0xaaa".to_string(),
+                    creation_timestamp: Some("2025-02-17T00:00:00Z".to_string()),
+                    gas_used: 0,
+                    user_ops: 0,
+                },
+                AccountRecord {
+                    address: "0xbbb".to_string(),
+                    creation_timestamp: Some("2025-02-16T00:00:00Z".to_string()),
+                    gas_used: 300,
+                    user_ops: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            records[1].csv_row(),
+            vec!["0xbbb", "2025-02-16T00:00:00Z", "300", "2"]
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn test_fetch_user_ops() {
         // Use the async version of mockito server
@@ -638,15 +1743,26 @@ mod tests {
 
         let url = format!("{}/user_ops", server.url());
 
-        // Use a persistent reqwest client
-        let client = reqwest::Client::new();
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(0, 0, 1.5),
+            HeaderMap::new(),
+        );
         let start_time = Utc::now() - chrono::Duration::days(1);
         let end_time = Utc::now();
 
         // Await the async call properly
-        let result = fetch_user_ops(&client, &url, start_time, end_time, Some(5), None)
-            .await
-            .unwrap();
+        let result = fetch_user_ops(
+            &client,
+            &url,
+            &[],
+            start_time,
+            Some(end_time),
+            Some(5),
+            None,
+        )
+        .await
+        .unwrap();
         // Ensures the request actually hit the mock server
         mock_endpoint.assert();
         let ops = result.user_ops;
@@ -657,6 +1773,61 @@ mod tests {
         assert!(page_token.is_none())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_unchanged_user_ops_page_is_not_downloaded_again() {
+        let mut server = Server::new_async().await;
+
+        let fresh = server
+            .mock("GET", Matcher::Regex(r"^/user_ops(\?.*)?$".to_string()))
+            .match_header("if-none-match", Matcher::Missing)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", "\"v1\"")
+            .with_body(
+                json!({
+                    "items": [
+                        {
+                            "address": { "hash": "0x123456789abcdef" },
+                            "fee": "100",
+                            "timestamp": "2024-03-10T12:00:00Z"
+                        }
+                    ]
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create();
+        let not_modified = server
+            .mock("GET", Matcher::Regex(r"^/user_ops(\?.*)?$".to_string()))
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create();
+
+        let url = format!("{}/user_ops", server.url());
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(0, 0, 1.5),
+            HeaderMap::new(),
+        );
+        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        // Like the scan of two refresh cycles, the open-ended query is sent twice
+        // and the second time answered from the cached page
+        let first = fetch_user_ops(&client, &url, &[], start_time, None, Some(5), None)
+            .await
+            .unwrap();
+        let second = fetch_user_ops(&client, &url, &[], start_time, None, Some(5), None)
+            .await
+            .unwrap();
+
+        fresh.assert();
+        not_modified.assert();
+        assert_eq!(first.user_ops.len(), 1);
+        assert_eq!(second.user_ops.len(), 1);
+        assert_eq!(second.user_ops[0].sender, "0x123456789abcdef");
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn test_fetch_accounts() {
         let mut server = Server::new_async().await;
@@ -681,11 +1852,15 @@ mod tests {
 
         let url = format!("{}/accounts", server.url());
 
-        let client = reqwest::Client::new();
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(0, 0, 1.5),
+            HeaderMap::new(),
+        );
         let start_time = Utc::now() - chrono::Duration::days(1);
         let end_time = Utc::now();
 
-        let result = fetch_accounts(&client, &url, start_time, end_time, Some(5), None)
+        let result = fetch_accounts(&client, &url, start_time, Some(end_time), Some(5), None)
             .await
             .unwrap();
         // Ensures the request actually hit the mock server
@@ -696,4 +1871,62 @@ mod tests {
         let page_token = result.next_page_token;
         assert!(page_token.is_none())
     }
+
+    #[tokio::test]
+    async fn test_refresh_activity_stats_with_fake_explorer() {
+        let config = ActivityMonitoringConfig::new();
+        let explorer = FakeExplorerClient::default();
+        let op_time = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        explorer.push_response(
+            config.user_ops_query_url(),
+            json!({
+                "items": [
+                    { "address": { "hash": "0xaaa" }, "fee": "300", "timestamp": op_time },
+                    { "address": { "hash": "0xbbb" }, "fee": "100", "timestamp": op_time },
+                ]
+            }),
+        );
+        explorer.push_response(
+            config.accounts_query_url(),
+            json!({
+                "items": [
+                    { "address": { "hash": "0xaaa" }, "creation_timestamp": op_time },
+                ]
+            }),
+        );
+
+        let shared_stats = Arc::new(RwLock::new(ActivityStats::default(&config)));
+        let user_ops = Arc::new(RwLock::new(UserOpHistory::load(None, None, 16)));
+        refresh_activity_stats(&shared_stats, &user_ops, &explorer, &config).await;
+
+        let keys = config.activity_stats_keys();
+        let last_24h = &keys.time_windows[&TimeWindow::Last24Hours];
+        let stats = shared_stats.read().await;
+        let stat = |name: ActivityStatName| stats.stats[&keys.activity_stat_names[&name]][last_24h];
+        assert_eq!(stat(ActivityStatName::UserOps), 2);
+        assert_eq!(stat(ActivityStatName::UniqueActiveAccounts), 2);
+
+        let top_gas_consumers = &stats.selected_accounts.lists
+            [&keys.select_accounts_by[&SelectAccountsBy::TopGasConsumers24h]];
+        let addresses: Vec<_> = top_gas_consumers
+            .iter()
+            .map(|acc| acc.address.as_str())
+            .collect();
+        assert_eq!(addresses, vec!["0xaaa", "0xbbb"]);
+        let recent_accounts =
+            &stats.selected_accounts.lists[&keys.select_accounts_by[&SelectAccountsBy::Recent]];
+        assert_eq!(recent_accounts.len(), 1);
+
+        let exported: Vec<_> = stats
+            .accounts
+            .iter()
+            .map(|acc| (acc.address.as_str(), acc.user_ops, acc.gas_used))
+            .collect();
+        assert_eq!(exported, vec![("0xaaa", 1, 300), ("0xbbb", 1, 100)]);
+        assert!(stats.accounts[0].creation_timestamp.is_some());
+
+        // The scanned user ops are stored for recomputations
+        let op_time = chrono::DateTime::parse_from_rfc3339(&op_time).unwrap();
+        assert_eq!(user_ops.read().await.stored_until(), Some(op_time.into()));
+    }
 }
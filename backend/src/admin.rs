@@ -0,0 +1,49 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    activity::ActivityStats, auth::AdminAuth, bridge::BridgeStatus, bundler::BundlerStats,
+    network::NetworkStatus, wallets::PaymasterWallets, SharedStates,
+};
+
+/// Snapshot of every shared state held by the backend, used for bug reports
+#[derive(Serialize, Debug)]
+pub struct StateDump {
+    /// Time at which the snapshot was taken
+    captured_at: DateTime<Utc>,
+    network_status: NetworkStatus,
+    paymaster_wallets: PaymasterWallets,
+    activity_stats: ActivityStats,
+    bridge_status: BridgeStatus,
+    bundler_stats: BundlerStats,
+}
+
+/// Handler to dump all shared states as a single JSON document, guarded by the
+/// admin token
+pub async fn get_state_dump(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    states: SharedStates,
+) -> Result<Json<StateDump>, StatusCode> {
+    auth.check(&headers)?;
+
+    // Clone each state while holding its lock only briefly
+    let network_status = states.network.read().await.clone();
+    let paymaster_wallets = states.wallets.read().await.clone();
+    let activity_stats = states.activity.read().await.clone();
+    let bridge_status = states.bridge.read().await.clone();
+    let bundler_stats = states.bundler.read().await.clone();
+
+    Ok(Json(StateDump {
+        captured_at: Utc::now(),
+        network_status,
+        paymaster_wallets,
+        activity_stats,
+        bridge_status,
+        bundler_stats,
+    }))
+}
@@ -0,0 +1,248 @@
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Timestamped event to aggregate, e.g. a user operation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event<'a> {
+    pub at: DateTime<Utc>,
+    pub account: &'a str,
+    pub gas_used: u64,
+}
+
+/// Time window ending at the aggregation time
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window {
+    /// Key of the window's stats, e.g. a TIME_WINDOWS value of `activity_keys.json`
+    pub key: String,
+    pub start: DateTime<Utc>,
+}
+
+impl Window {
+    /// Window covering `length` before `now`
+    pub fn trailing(key: String, now: DateTime<Utc>, length: Duration) -> Self {
+        Self {
+            key,
+            start: now - length,
+        }
+    }
+
+    /// Whether `at` falls within `[start, now]`
+    pub fn contains(&self, at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.start <= at && at <= now
+    }
+
+    /// Window of the same length and key right before this one, along with its end
+    pub fn preceding(&self, now: DateTime<Utc>) -> (Self, DateTime<Utc>) {
+        let previous = Self::trailing(self.key.clone(), self.start, now - self.start);
+        (previous, self.start)
+    }
+}
+
+/// Aggregated events of one window
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct WindowStats {
+    pub events: u64,
+    pub gas_used: u64,
+    /// Gas used per account that had events in the window
+    pub gas_by_account: HashMap<String, u64>,
+}
+
+impl WindowStats {
+    pub fn add(&mut self, event: &Event) {
+        self.events += 1;
+        self.gas_used = self.gas_used.saturating_add(event.gas_used);
+        let account_gas = self
+            .gas_by_account
+            .entry(event.account.to_string())
+            .or_insert(0);
+        *account_gas = account_gas.saturating_add(event.gas_used);
+    }
+
+    pub fn unique_accounts(&self) -> u64 {
+        self.gas_by_account.len() as u64
+    }
+
+    /// Up to `n` accounts with the most gas used, descending; ties are ordered by account
+    pub fn top_accounts(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut accounts: Vec<(&str, u64)> = self
+            .gas_by_account
+            .iter()
+            .map(|(account, gas_used)| (account.as_str(), *gas_used))
+            .collect();
+        accounts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        accounts.truncate(n);
+        accounts
+    }
+}
+
+/// Number of events per day of week and hour of day, in UTC
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Heatmap {
+    /// By day of week from Monday, then by hour of day
+    pub events: [[u64; 24]; 7],
+}
+
+impl Heatmap {
+    pub fn add(&mut self, at: DateTime<Utc>) {
+        let weekday = at.weekday().num_days_from_monday() as usize;
+        self.events[weekday][at.hour() as usize] += 1;
+    }
+
+    /// Events per hour of day, over every day of week
+    pub fn by_hour(&self) -> [u64; 24] {
+        let mut by_hour = [0; 24];
+        for hours in &self.events {
+            for (hour, events) in hours.iter().enumerate() {
+                by_hour[hour] += events;
+            }
+        }
+        by_hour
+    }
+
+    /// Events per day of week from Monday
+    pub fn by_weekday(&self) -> [u64; 7] {
+        self.events.map(|hours| hours.iter().sum())
+    }
+}
+
+/// Adds events to the stats of every window they fall in.
+///
+/// Stats are keyed by window key; windows without events get empty stats.
+pub fn aggregate<'a>(
+    stats: &mut HashMap<String, WindowStats>,
+    events: impl IntoIterator<Item = Event<'a>>,
+    windows: &[Window],
+    now: DateTime<Utc>,
+) {
+    for window in windows {
+        stats.entry(window.key.clone()).or_default();
+    }
+    for event in events {
+        for window in windows {
+            if window.contains(event.at, now) {
+                stats.entry(window.key.clone()).or_default().add(&event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aggregate, Event, Heatmap, Window, WindowStats};
+    use chrono::{Duration, TimeZone, Utc};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_window_boundaries() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let window = Window::trailing("24h".to_string(), now, Duration::days(1));
+
+        assert!(window.contains(now - Duration::days(1), now));
+        assert!(!window.contains(now - Duration::days(1) - Duration::nanoseconds(1), now));
+        assert!(window.contains(now, now));
+        assert!(!window.contains(now + Duration::nanoseconds(1), now));
+
+        let empty = Window::trailing("0s".to_string(), now, Duration::zero());
+        assert!(empty.contains(now, now));
+        assert!(!empty.contains(now - Duration::nanoseconds(1), now));
+
+        let (previous, end) = window.preceding(now);
+        assert_eq!(end, now - Duration::days(1));
+        assert_eq!(previous.start, now - Duration::days(2));
+        assert_eq!(previous.key, "24h");
+    }
+
+    #[test]
+    fn test_heatmap_buckets() {
+        // A Monday
+        let monday = Utc.with_ymd_and_hms(2025, 3, 10, 0, 0, 0).unwrap();
+        let mut heatmap = Heatmap::default();
+        heatmap.add(monday + Duration::hours(9));
+        heatmap.add(monday + Duration::hours(9) + Duration::minutes(59));
+        heatmap.add(monday + Duration::days(6) + Duration::hours(23));
+        heatmap.add(monday + Duration::days(7) + Duration::hours(9));
+
+        assert_eq!(heatmap.events[0][9], 3);
+        assert_eq!(heatmap.events[6][23], 1);
+        assert_eq!(heatmap.by_hour()[9], 3);
+        assert_eq!(heatmap.by_weekday(), [3, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_aggregate_overlapping_windows() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let windows = [
+            Window::trailing("24h".to_string(), now, Duration::days(1)),
+            Window::trailing("30d".to_string(), now, Duration::days(30)),
+            Window {
+                key: "ytd".to_string(),
+                start: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            },
+        ];
+        let event = |at, account, gas_used| Event {
+            at,
+            account,
+            gas_used,
+        };
+        let events = [
+            event(now - Duration::hours(1), "0xa", 100),
+            event(now - Duration::days(1), "0xb", 50),
+            event(now - Duration::days(2), "0xa", 10),
+            event(now - Duration::days(30), "0xc", 1),
+            event(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(), "0xd", 7),
+            event(
+                Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).unwrap(),
+                "0xe",
+                1,
+            ),
+            event(now + Duration::seconds(1), "0xf", 1),
+        ];
+
+        let mut stats = HashMap::new();
+        aggregate(&mut stats, events.iter().cloned(), &windows, now);
+
+        let summary = |key: &str| {
+            let stats = &stats[key];
+            (stats.events, stats.gas_used, stats.unique_accounts())
+        };
+        assert_eq!(summary("24h"), (2, 150, 2));
+        assert_eq!(summary("30d"), (4, 161, 3));
+        assert_eq!(summary("ytd"), (5, 168, 4));
+        assert_eq!(stats["ytd"].gas_by_account["0xa"], 110);
+    }
+
+    #[test]
+    fn test_aggregate_is_incremental() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let windows = [Window::trailing("24h".to_string(), now, Duration::days(1))];
+        let event = Event {
+            at: now,
+            account: "0xa",
+            gas_used: 5,
+        };
+
+        let mut stats = HashMap::new();
+        aggregate(&mut stats, [], &windows, now);
+        assert_eq!(stats["24h"], WindowStats::default());
+
+        aggregate(&mut stats, [event.clone()], &windows, now);
+        aggregate(&mut stats, [event], &windows, now);
+        assert_eq!((stats["24h"].events, stats["24h"].gas_used), (2, 10));
+    }
+
+    #[test]
+    fn test_top_accounts() {
+        let stats = WindowStats {
+            events: 4,
+            gas_used: 40,
+            gas_by_account: HashMap::from([
+                ("0xc".to_string(), 10),
+                ("0xa".to_string(), 10),
+                ("0xb".to_string(), 20),
+            ]),
+        };
+        assert_eq!(stats.top_accounts(2), vec![("0xb", 20), ("0xa", 10)]);
+        assert_eq!(stats.top_accounts(5).len(), 3);
+    }
+}
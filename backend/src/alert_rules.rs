@@ -0,0 +1,358 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::time::{interval, Duration};
+
+use crate::{
+    alerts::{Alerts, Severity},
+    bridge::{DepositStatus, ResponsivenessRating, WithdrawalStatus},
+    config::AlertRulesConfig,
+    network::Status,
+    tasks::{TaskRegistry, ALERT_RULES_TASK},
+    SharedStates,
+};
+
+/// Value derived from the shared states that alert rules can be declared on
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// 1 when online, 0 otherwise
+    BatchProducerOnline,
+    /// 1 when online, 0 otherwise
+    RpcEndpointOnline,
+    /// 1 when online, 0 otherwise
+    BundlerEndpointOnline,
+    BundlerPendingUserOps,
+    /// Seconds since the latest bundle
+    BundlerLastBundleAgeS,
+    /// Deepest duty queue among bridge operators
+    BridgeMaxDutyQueueDepth,
+    BridgeUnresponsiveOperators,
+    BridgePendingDeposits,
+    BridgePendingWithdrawals,
+    DepositPaymasterBalanceWei,
+    ValidatingPaymasterBalanceWei,
+}
+
+/// Comparison of a metric against a rule threshold
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Gte,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Lte,
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+}
+
+impl Comparison {
+    fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Gt => value > threshold,
+            Comparison::Gte => value >= threshold,
+            Comparison::Lt => value < threshold,
+            Comparison::Lte => value <= threshold,
+            Comparison::Eq => value == threshold,
+            Comparison::Ne => value != threshold,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Gt => ">",
+            Comparison::Gte => ">=",
+            Comparison::Lt => "<",
+            Comparison::Lte => "<=",
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+        }
+    }
+}
+
+fn default_severity() -> Severity {
+    Severity::Warning
+}
+
+/// Alert raised while a metric meets a condition for long enough
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Identifies the rule; alerts are raised as `rule:<id>`
+    id: String,
+    metric: Metric,
+    comparison: Comparison,
+    threshold: f64,
+    /// Seconds the condition must hold before the alert is raised
+    #[serde(default)]
+    for_s: u64,
+    #[serde(default = "default_severity")]
+    severity: Severity,
+}
+
+impl AlertRule {
+    fn alert_id(&self) -> String {
+        format!("rule:{}", self.id)
+    }
+}
+
+/// Outcome of evaluating a rule once
+#[derive(Debug, PartialEq)]
+enum RuleOutcome {
+    /// Condition held for the configured duration
+    Raise(String),
+    /// Condition holds, but not for long enough yet
+    Pending,
+    /// Condition does not hold
+    Resolve,
+    /// Metric unknown, e.g. before the first refresh; the alert is left as is
+    Unchanged,
+}
+
+/// Evaluates a rule against the current metric value.
+///
+/// `pending_since` tracks since when the condition has held across evaluations.
+fn evaluate(
+    rule: &AlertRule,
+    value: Option<f64>,
+    pending_since: &mut Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> RuleOutcome {
+    let Some(value) = value else {
+        return RuleOutcome::Unchanged;
+    };
+    if !rule.comparison.holds(value, rule.threshold) {
+        *pending_since = None;
+        return RuleOutcome::Resolve;
+    }
+
+    let since = *pending_since.get_or_insert(now);
+    let held_s = (now - since).num_seconds();
+    if held_s < rule.for_s as i64 {
+        return RuleOutcome::Pending;
+    }
+    RuleOutcome::Raise(format!(
+        "{:?} is {} ({} {}) for {}s",
+        rule.metric,
+        value,
+        rule.comparison.symbol(),
+        rule.threshold,
+        held_s
+    ))
+}
+
+fn online(status: &Status) -> f64 {
+    if *status == Status::Online {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Current value of every metric that is known
+async fn sample_metrics(states: &SharedStates, now: DateTime<Utc>) -> HashMap<Metric, f64> {
+    let mut metrics = HashMap::new();
+
+    let network = states.network.read().await;
+    metrics.insert(Metric::BatchProducerOnline, online(&network.batch_producer));
+    metrics.insert(Metric::RpcEndpointOnline, online(&network.rpc_endpoint));
+    metrics.insert(
+        Metric::BundlerEndpointOnline,
+        online(&network.bundler_endpoint),
+    );
+    drop(network);
+
+    let bundler = states.bundler.read().await;
+    if let Some(pending) = bundler.pending_user_ops {
+        metrics.insert(Metric::BundlerPendingUserOps, pending as f64);
+    }
+    if let Some(at) = bundler.last_bundle_at {
+        metrics.insert(
+            Metric::BundlerLastBundleAgeS,
+            (now - at).num_seconds() as f64,
+        );
+    }
+    drop(bundler);
+
+    let bridge = states.bridge.read().await;
+    if let Some(depth) = bridge
+        .operators
+        .iter()
+        .filter_map(|op| op.duty_queue_depth)
+        .max()
+    {
+        metrics.insert(Metric::BridgeMaxDutyQueueDepth, depth as f64);
+    }
+    let unresponsive = bridge
+        .operators
+        .iter()
+        .filter(|op| op.responsiveness.rating == ResponsivenessRating::Unresponsive)
+        .count();
+    metrics.insert(Metric::BridgeUnresponsiveOperators, unresponsive as f64);
+    let pending_deposits = bridge
+        .deposits
+        .iter()
+        .filter(|deposit| deposit.status == DepositStatus::InProgress)
+        .count();
+    metrics.insert(Metric::BridgePendingDeposits, pending_deposits as f64);
+    let pending_withdrawals = bridge
+        .withdrawals
+        .iter()
+        .filter(|withdrawal| withdrawal.status == WithdrawalStatus::InProgress)
+        .count();
+    metrics.insert(Metric::BridgePendingWithdrawals, pending_withdrawals as f64);
+    drop(bridge);
+
+    let wallets = states.wallets.read().await;
+    if let Some(balance) = wallets.deposit.balance_wei() {
+        metrics.insert(Metric::DepositPaymasterBalanceWei, balance as f64);
+    }
+    if let Some(balance) = wallets.validating.balance_wei() {
+        metrics.insert(Metric::ValidatingPaymasterBalanceWei, balance as f64);
+    }
+
+    metrics
+}
+
+/// Periodically evaluate the configured alert rules against the shared states
+pub async fn alert_rules_task(
+    states: SharedStates,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    config: &AlertRulesConfig,
+) {
+    if config.rules().is_empty() {
+        return;
+    }
+    tasks
+        .register(ALERT_RULES_TASK, config.evaluation_interval())
+        .await;
+    let mut interval = interval(Duration::from_secs(config.evaluation_interval()));
+    let mut pending_since: Vec<Option<DateTime<Utc>>> = vec![None; config.rules().len()];
+
+    loop {
+        interval.tick().await;
+        tasks.start_refresh(ALERT_RULES_TASK).await;
+        let now = Utc::now();
+        let metrics = sample_metrics(&states, now).await;
+
+        for (rule, pending_since) in config.rules().iter().zip(pending_since.iter_mut()) {
+            let value = metrics.get(&rule.metric).copied();
+            match evaluate(rule, value, pending_since, now) {
+                RuleOutcome::Raise(message) => {
+                    alerts.raise(rule.alert_id(), rule.severity, message).await
+                }
+                RuleOutcome::Resolve => alerts.resolve(&rule.alert_id()).await,
+                RuleOutcome::Pending | RuleOutcome::Unchanged => {}
+            }
+        }
+
+        tasks.record_refresh(ALERT_RULES_TASK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, AlertRule, Comparison, Metric, RuleOutcome};
+    use crate::alerts::Severity;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn test_rule_from_config() {
+        let rule: AlertRule = serde_json::from_value(json!({
+            "id": "mempool_backlog",
+            "metric": "bundler_pending_user_ops",
+            "comparison": ">=",
+            "threshold": 100,
+        }))
+        .unwrap();
+        assert_eq!(rule.metric, Metric::BundlerPendingUserOps);
+        assert_eq!(rule.comparison, Comparison::Gte);
+        assert_eq!((rule.for_s, rule.severity), (0, Severity::Warning));
+        assert_eq!(rule.alert_id(), "rule:mempool_backlog");
+
+        assert!(serde_json::from_value::<AlertRule>(json!({
+            "id": "x",
+            "metric": "unknown_metric",
+            "comparison": ">",
+            "threshold": 1,
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_rule_duration() {
+        let rule: AlertRule = serde_json::from_value(json!({
+            "id": "low_balance",
+            "metric": "deposit_paymaster_balance_wei",
+            "comparison": "<",
+            "threshold": 1000,
+            "for_s": 60,
+            "severity": "critical",
+        }))
+        .unwrap();
+        let start = Utc::now();
+        let mut pending_since = None;
+
+        assert_eq!(
+            evaluate(&rule, Some(10.0), &mut pending_since, start),
+            RuleOutcome::Pending
+        );
+        assert_eq!(
+            evaluate(
+                &rule,
+                Some(10.0),
+                &mut pending_since,
+                start + Duration::seconds(59)
+            ),
+            RuleOutcome::Pending
+        );
+        assert!(matches!(
+            evaluate(
+                &rule,
+                Some(10.0),
+                &mut pending_since,
+                start + Duration::seconds(60)
+            ),
+            RuleOutcome::Raise(_)
+        ));
+
+        // Unknown values neither raise nor resolve
+        assert_eq!(
+            evaluate(
+                &rule,
+                None,
+                &mut pending_since,
+                start + Duration::seconds(61)
+            ),
+            RuleOutcome::Unchanged
+        );
+        assert!(pending_since.is_some());
+
+        // Recovering resets the duration
+        assert_eq!(
+            evaluate(
+                &rule,
+                Some(5000.0),
+                &mut pending_since,
+                start + Duration::seconds(62)
+            ),
+            RuleOutcome::Resolve
+        );
+        assert_eq!(
+            evaluate(
+                &rule,
+                Some(10.0),
+                &mut pending_since,
+                start + Duration::seconds(63)
+            ),
+            RuleOutcome::Pending
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use axum::{
+    extract::Path,
+    http::{header::CONTENT_LANGUAGE, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::{
+    auth::AdminAuth,
+    events::{EventBus, MonitorEvent},
+    i18n::{Localizer, Translations},
+    push::PushNotifier,
+    utils::parse_duration,
+};
+
+/// Number of resolved incidents kept for `/api/incidents`
+const MAX_RESOLVED_INCIDENTS: usize = 200;
+/// Longest an alert can be silenced for at once
+const MAX_ACKNOWLEDGEMENT_DAYS: i64 = 7;
+
+/// Alert severity
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+/// Operator acknowledgement silencing the notifications of an alert
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Acknowledgement {
+    /// Operator who acknowledged the alert, as given in the request
+    pub by: Option<String>,
+    pub note: Option<String>,
+    pub at: DateTime<Utc>,
+    /// Notifications resume after this time if the alert is still firing
+    pub until: DateTime<Utc>,
+}
+
+/// A condition raised by a monitoring task that needs attention
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Alert {
+    /// Stable identifier of the condition, e.g. `bridge_duty_backlog:1`
+    pub id: String,
+    pub severity: Severity,
+    pub message: String,
+    /// When the condition was first raised
+    pub since: DateTime<Utc>,
+    /// Set while an operator has silenced the alert
+    #[serde(default)]
+    pub acknowledgement: Option<Acknowledgement>,
+}
+
+/// An alert from the time it was raised until it was resolved
+#[derive(Serialize, Clone, Debug)]
+pub struct Incident {
+    #[serde(flatten)]
+    alert: Alert,
+    /// Unset while the alert is firing
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Currently active alerts, keyed by alert id
+#[derive(Clone, Debug, Default)]
+pub struct Alerts {
+    active: Arc<RwLock<BTreeMap<String, Alert>>>,
+    /// Recently resolved alerts, oldest first
+    resolved: Arc<RwLock<VecDeque<Incident>>>,
+    /// Browser notifications for critical alerts, if configured
+    push: Option<PushNotifier>,
+    /// Bus alerts are published to when raised and resolved, if any
+    events: Option<EventBus>,
+}
+
+impl Alerts {
+    pub fn with_push(push: Option<PushNotifier>) -> Self {
+        Self {
+            push,
+            ..Default::default()
+        }
+    }
+
+    /// Publishes raised and resolved alerts on `events`
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish(&self, event: MonitorEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event);
+        }
+    }
+
+    /// Pushes an alert in the background, so that slow push services do not hold up
+    /// the monitoring task raising it
+    fn push(&self, alert: &Alert, resolved: bool) {
+        if let Some(push) = self.push.clone() {
+            let alert = alert.clone();
+            tokio::spawn(async move { push.notify(&alert, resolved).await });
+        }
+    }
+
+    /// Raises an alert, or updates its message if it is already active.
+    ///
+    /// Critical alerts are pushed when raised or escalated, unless acknowledged, and
+    /// again once their acknowledgement expires while they are still firing.
+    pub async fn raise(&self, id: String, severity: Severity, message: String) {
+        let mut active = self.active.write().await;
+        match active.get_mut(&id) {
+            Some(alert) => {
+                let escalated =
+                    alert.severity != Severity::Critical && severity == Severity::Critical;
+                let expired = alert
+                    .acknowledgement
+                    .as_ref()
+                    .is_some_and(|ack| ack.until <= Utc::now());
+                if expired {
+                    info!(%id, "Alert acknowledgement expired");
+                    alert.acknowledgement = None;
+                }
+                alert.severity = severity;
+                alert.message = message;
+                if severity == Severity::Critical
+                    && alert.acknowledgement.is_none()
+                    && (escalated || expired)
+                {
+                    self.push(alert, false);
+                }
+            }
+            None => {
+                warn!(%id, ?severity, %message, "Alert raised");
+                let alert = Alert {
+                    id: id.clone(),
+                    severity,
+                    message,
+                    since: Utc::now(),
+                    acknowledgement: None,
+                };
+                if severity == Severity::Critical {
+                    self.push(&alert, false);
+                }
+                self.publish(MonitorEvent::AlertRaised {
+                    at: alert.since,
+                    alert: alert.clone(),
+                });
+                active.insert(id, alert);
+            }
+        }
+    }
+
+    /// Clears an alert if it is active
+    pub async fn resolve(&self, id: &str) {
+        let Some(alert) = self.active.write().await.remove(id) else {
+            return;
+        };
+        info!(%id, "Alert resolved");
+        if alert.severity == Severity::Critical {
+            self.push(&alert, true);
+        }
+        let resolved_at = Utc::now();
+        self.publish(MonitorEvent::AlertResolved {
+            at: resolved_at,
+            alert: alert.clone(),
+        });
+
+        let mut resolved = self.resolved.write().await;
+        resolved.push_back(Incident {
+            alert,
+            resolved_at: Some(resolved_at),
+        });
+        while resolved.len() > MAX_RESOLVED_INCIDENTS {
+            resolved.pop_front();
+        }
+    }
+
+    /// Silences an active alert until `until`, `None` if it is not active
+    pub async fn acknowledge(
+        &self,
+        id: &str,
+        until: DateTime<Utc>,
+        by: Option<String>,
+        note: Option<String>,
+    ) -> Option<Alert> {
+        let mut active = self.active.write().await;
+        let alert = active.get_mut(id)?;
+        info!(%id, ?by, %until, "Alert acknowledged");
+        alert.acknowledgement = Some(Acknowledgement {
+            by,
+            note,
+            at: Utc::now(),
+            until,
+        });
+        Some(alert.clone())
+    }
+
+    /// Withdraws the acknowledgement of an active alert, `None` if it is not active
+    pub async fn unacknowledge(&self, id: &str) -> Option<Alert> {
+        let mut active = self.active.write().await;
+        let alert = active.get_mut(id)?;
+        if alert.acknowledgement.take().is_some() {
+            info!(%id, "Alert acknowledgement withdrawn");
+        }
+        Some(alert.clone())
+    }
+
+    /// Returns all active alerts
+    pub async fn active(&self) -> Vec<Alert> {
+        self.active.read().await.values().cloned().collect()
+    }
+
+    /// Active incidents, then resolved ones, most recent first
+    async fn incidents(&self) -> Vec<Incident> {
+        let mut incidents: Vec<Incident> = self
+            .active()
+            .await
+            .into_iter()
+            .map(|alert| Incident {
+                alert,
+                resolved_at: None,
+            })
+            .collect();
+        incidents.sort_by(|a, b| b.alert.since.cmp(&a.alert.since));
+        incidents.extend(self.resolved.read().await.iter().rev().cloned());
+        incidents
+    }
+}
+
+/// Active alerts passed to dashboard
+#[derive(Serialize, Debug)]
+pub struct AlertsResponse {
+    alerts: Vec<Alert>,
+}
+
+/// Return active alerts
+pub async fn get_alerts(alerts: Alerts) -> Json<AlertsResponse> {
+    Json(AlertsResponse {
+        alerts: alerts.active().await,
+    })
+}
+
+/// Incident with its display strings, in the language of the request
+#[derive(Serialize, Debug)]
+pub struct LocalizedIncident {
+    #[serde(flatten)]
+    incident: Incident,
+    /// Translated title of the alert kind, the alert message if it has none
+    title: String,
+    severity_display: String,
+    state_display: String,
+}
+
+impl LocalizedIncident {
+    fn new(incident: Incident, localizer: &Localizer) -> Self {
+        let title = localizer
+            .incident_title(&incident.alert.id)
+            .unwrap_or_else(|| incident.alert.message.clone());
+        let state_display = if incident.resolved_at.is_some() {
+            localizer.text("incident.resolved")
+        } else {
+            localizer.text("incident.ongoing")
+        };
+        Self {
+            title,
+            severity_display: localizer.severity(incident.alert.severity),
+            state_display,
+            incident,
+        }
+    }
+}
+
+/// Incidents passed to dashboard
+#[derive(Serialize, Debug)]
+pub struct IncidentsResponse {
+    incidents: Vec<LocalizedIncident>,
+}
+
+/// Return active and recently resolved incidents
+pub async fn get_incidents(
+    headers: HeaderMap,
+    alerts: Alerts,
+    translations: Translations,
+) -> impl IntoResponse {
+    let localizer = translations.localizer(&headers);
+    let incidents = alerts
+        .incidents()
+        .await
+        .into_iter()
+        .map(|incident| LocalizedIncident::new(incident, &localizer))
+        .collect();
+    (
+        [(CONTENT_LANGUAGE, localizer.language().to_string())],
+        Json(IncidentsResponse { incidents }),
+    )
+}
+
+/// Body of the alert acknowledgement endpoint
+#[derive(Deserialize, Debug)]
+pub struct AcknowledgeRequest {
+    /// How long to silence the alert for, e.g. `30m` or `4h`
+    duration: String,
+    by: Option<String>,
+    note: Option<String>,
+}
+
+/// Silence a firing alert for a given duration. Requires the admin token.
+pub async fn post_alert_acknowledgement(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<AcknowledgeRequest>,
+    auth: AdminAuth,
+    alerts: Alerts,
+) -> Result<Json<Alert>, StatusCode> {
+    auth.check(&headers)?;
+    let duration = parse_duration(&request.duration)
+        .filter(|duration| {
+            *duration > Duration::zero() && *duration <= Duration::days(MAX_ACKNOWLEDGEMENT_DAYS)
+        })
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    alerts
+        .acknowledge(&id, Utc::now() + duration, request.by, request.note)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Withdraw the acknowledgement of a firing alert. Requires the admin token.
+pub async fn delete_alert_acknowledgement(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    auth: AdminAuth,
+    alerts: Alerts,
+) -> Result<Json<Alert>, StatusCode> {
+    auth.check(&headers)?;
+    alerts
+        .unacknowledge(&id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alerts, Severity};
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn test_alert_lifecycle() {
+        let alerts = Alerts::default();
+        alerts
+            .raise("a".to_string(), Severity::Warning, "first".to_string())
+            .await;
+        let since = alerts.active().await[0].since;
+
+        alerts
+            .raise("a".to_string(), Severity::Critical, "second".to_string())
+            .await;
+        let active = alerts.active().await;
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].since, since);
+        assert_eq!(active[0].severity, Severity::Critical);
+        assert_eq!(active[0].message, "second");
+
+        alerts.resolve("a").await;
+        assert!(alerts.active().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledgement_and_incidents() {
+        let alerts = Alerts::default();
+        let until = Utc::now() + Duration::hours(1);
+        assert!(alerts.acknowledge("a", until, None, None).await.is_none());
+
+        alerts
+            .raise("a".to_string(), Severity::Critical, "down".to_string())
+            .await;
+        let acked = alerts
+            .acknowledge("a", until, Some("alice".to_string()), None)
+            .await
+            .unwrap();
+        assert_eq!(acked.acknowledgement.unwrap().until, until);
+
+        // The acknowledgement survives updates while it lasts
+        alerts
+            .raise(
+                "a".to_string(),
+                Severity::Critical,
+                "still down".to_string(),
+            )
+            .await;
+        assert!(alerts.active().await[0].acknowledgement.is_some());
+
+        alerts
+            .acknowledge("a", Utc::now() - Duration::seconds(1), None, None)
+            .await;
+        alerts
+            .raise(
+                "a".to_string(),
+                Severity::Critical,
+                "still down".to_string(),
+            )
+            .await;
+        assert!(alerts.active().await[0].acknowledgement.is_none());
+
+        alerts
+            .raise("b".to_string(), Severity::Warning, "slow".to_string())
+            .await;
+        alerts.resolve("a").await;
+        let incidents = alerts.incidents().await;
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].alert.id, "b");
+        assert!(incidents[0].resolved_at.is_none());
+        assert_eq!(incidents[1].alert.id, "a");
+        assert!(incidents[1].resolved_at.is_some());
+    }
+}
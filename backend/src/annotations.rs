@@ -0,0 +1,191 @@
+use axum::{
+    extract::Query,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::{auth::AdminAuth, checkpoint, utils::parse_duration};
+
+/// Max length of an annotation text
+const MAX_ANNOTATION_LEN: usize = 1_000;
+
+/// Timestamped operational note overlaid on the dashboard charts, e.g.
+/// "upgraded sequencer to v0.9"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub id: u64,
+    /// Time of the annotated event
+    pub at: DateTime<Utc>,
+    pub text: String,
+    pub by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Default, Debug)]
+struct AnnotationsState {
+    /// Sorted by `at`
+    annotations: Vec<Annotation>,
+    next_id: u64,
+}
+
+/// Annotations, persisted to `ANNOTATIONS_PATH` when configured
+#[derive(Clone, Debug, Default)]
+pub struct Annotations {
+    path: Option<Arc<str>>,
+    state: Arc<RwLock<AnnotationsState>>,
+}
+
+impl Annotations {
+    /// Loads the annotations stored at `path`, if any
+    pub fn load(path: Option<&str>) -> Self {
+        let annotations: Vec<Annotation> = path.and_then(checkpoint::load).unwrap_or_default();
+        if let Some(path) = path {
+            info!(%path, annotations = annotations.len(), "Loaded annotations");
+        }
+        let next_id = annotations.iter().map(|a| a.id + 1).max().unwrap_or(0);
+        Self {
+            path: path.map(Arc::from),
+            state: Arc::new(RwLock::new(AnnotationsState {
+                annotations,
+                next_id,
+            })),
+        }
+    }
+
+    /// Adds an annotation, returning it with its id
+    pub async fn add(&self, at: DateTime<Utc>, text: String, by: Option<String>) -> Annotation {
+        let mut state = self.state.write().await;
+        let annotation = Annotation {
+            id: state.next_id,
+            at,
+            text,
+            by,
+            created_at: Utc::now(),
+        };
+        state.next_id += 1;
+        let index = state.annotations.partition_point(|a| a.at <= at);
+        state.annotations.insert(index, annotation.clone());
+
+        if let Some(path) = &self.path {
+            if let Err(e) = checkpoint::save(path, &state.annotations) {
+                warn!(error = %e, "Failed to store annotations");
+            }
+        }
+        annotation
+    }
+
+    /// Annotations of events within `[start, end)`, oldest first
+    pub async fn between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Annotation> {
+        self.state
+            .read()
+            .await
+            .annotations
+            .iter()
+            .filter(|a| a.at >= start && a.at < end)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Body of the annotation creation endpoint
+#[derive(Deserialize, Debug)]
+pub struct AnnotationRequest {
+    /// Time of the annotated event, now when unset
+    at: Option<DateTime<Utc>>,
+    text: String,
+    by: Option<String>,
+}
+
+/// Create an annotation. Requires the admin token.
+pub async fn post_annotation(
+    headers: HeaderMap,
+    Json(request): Json<AnnotationRequest>,
+    auth: AdminAuth,
+    annotations: Annotations,
+) -> Result<Json<Annotation>, StatusCode> {
+    auth.check(&headers)?;
+    let text = request.text.trim();
+    if text.is_empty() || text.len() > MAX_ANNOTATION_LEN {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let at = request.at.unwrap_or_else(Utc::now);
+    Ok(Json(
+        annotations.add(at, text.to_string(), request.by).await,
+    ))
+}
+
+/// Query parameters of the annotations endpoint
+#[derive(Deserialize, Debug)]
+pub struct AnnotationsQuery {
+    /// How far back to list annotations, e.g. `7d`
+    window: Option<String>,
+}
+
+/// List the annotations of the last `window`, oldest first
+pub async fn get_annotations(
+    Query(query): Query<AnnotationsQuery>,
+    annotations: Annotations,
+) -> Result<Json<Vec<Annotation>>, StatusCode> {
+    let window = parse_duration(query.window.as_deref().unwrap_or("7d"))
+        .filter(|window| *window > Duration::zero())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let now = Utc::now();
+    // Annotations may be scheduled slightly ahead, e.g. for an announced upgrade
+    let end = DateTime::<Utc>::MAX_UTC;
+    Ok(Json(annotations.between(now - window, end).await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Annotations;
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn test_annotations_persistence() {
+        let path =
+            std::env::temp_dir().join(format!("annotations_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let now = Utc::now();
+        let annotations = Annotations::load(Some(&path));
+        annotations
+            .add(now, "upgraded sequencer to v0.9".to_string(), None)
+            .await;
+        annotations
+            .add(
+                now - Duration::hours(2),
+                "restarted bundler".to_string(),
+                Some("ops".to_string()),
+            )
+            .await;
+
+        let reloaded = Annotations::load(Some(&path));
+        let listed = reloaded
+            .between(now - Duration::hours(3), now + Duration::seconds(1))
+            .await;
+        let texts: Vec<_> = listed.iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["restarted bundler", "upgraded sequencer to v0.9"]
+        );
+        assert_eq!(
+            reloaded
+                .between(now - Duration::hours(1), now + Duration::seconds(1))
+                .await
+                .len(),
+            1
+        );
+        // Ids keep increasing across restarts
+        let added = reloaded.add(now, "note".to_string(), None).await;
+        assert_eq!(added.id, 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
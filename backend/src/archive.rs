@@ -0,0 +1,272 @@
+//! Archival of raw upstream responses, to replay the exact inputs of a refresh
+//! cycle through the aggregation when the aggregated numbers look wrong.
+//!
+//! Sampled responses are batched and written every `ARCHIVE_FLUSH_INTERVAL_S` as
+//! zstd-compressed JSON lines, one `ArchivedResponse` per line, to `ARCHIVE_DIR`
+//! and/or an S3 bucket; `zstd -dc <file>` restores the lines.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time::{interval, Duration},
+};
+use tracing::{error, info, warn};
+
+use crate::{config::ArchiveConfig, s3::S3Client};
+
+/// Max number of responses waiting to be batched; more are dropped
+const ARCHIVE_QUEUE_LEN: usize = 10_000;
+
+/// Max number of responses per archive file
+const MAX_BATCH_LEN: usize = 5_000;
+
+/// Raw upstream response, as archived
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ArchivedResponse {
+    at: DateTime<Utc>,
+    /// e.g. `explorer`, as named for the failure injection
+    upstream: &'static str,
+    /// Full URL with query parameters, or RPC method and parameters
+    request: Value,
+    response: Value,
+}
+
+/// Picks the responses to archive and queues them
+struct Sampler {
+    /// One response out of `every` is archived
+    every: u64,
+    seen: AtomicU64,
+    sender: mpsc::Sender<ArchivedResponse>,
+}
+
+impl Sampler {
+    fn sample(&self) -> bool {
+        self.seen.fetch_add(1, Ordering::Relaxed) % self.every == 0
+    }
+}
+
+/// Set once at startup. Upstream clients are created throughout the backend, so
+/// the archival is configured globally rather than passed to each of them.
+static SAMPLER: OnceLock<Sampler> = OnceLock::new();
+
+/// Enables the archival as configured, returning the queue of responses to pass to
+/// [`archive_task`]. `None` when archival is disabled.
+pub fn init(config: &ArchiveConfig) -> Option<mpsc::Receiver<ArchivedResponse>> {
+    if !config.enabled() {
+        return None;
+    }
+    let (sender, receiver) = mpsc::channel(ARCHIVE_QUEUE_LEN);
+    let sampler = Sampler {
+        every: config.sample_every(),
+        seen: AtomicU64::new(0),
+        sender,
+    };
+    if SAMPLER.set(sampler).is_err() {
+        warn!("Response archival already configured");
+        return None;
+    }
+    Some(receiver)
+}
+
+/// Whether the next upstream response is to be archived. Check before building the
+/// archived request.
+pub fn sample() -> bool {
+    SAMPLER.get().is_some_and(Sampler::sample)
+}
+
+/// Queues a sampled response for archival
+pub fn record(upstream: &'static str, request: Value, response: &Value) {
+    let Some(sampler) = SAMPLER.get() else {
+        return;
+    };
+    let archived = ArchivedResponse {
+        at: Utc::now(),
+        upstream,
+        request,
+        response: response.clone(),
+    };
+    if let Err(TrySendError::Full(_)) = sampler.sender.try_send(archived) {
+        warn!(upstream, "Archive queue full, dropping response");
+    }
+}
+
+/// Archived responses as JSON lines
+fn encode_batch(batch: &[ArchivedResponse]) -> Vec<u8> {
+    let mut lines = Vec::new();
+    for response in batch {
+        serde_json::to_writer(&mut lines, response).expect("archived responses to serialize");
+        lines.push(b'\n');
+    }
+    lines
+}
+
+/// Name of an archive file, grouped by date
+fn object_key(prefix: &str, at: DateTime<Utc>) -> String {
+    let name = format!(
+        "{}/responses-{}.jsonl.zst",
+        at.format("%Y-%m-%d"),
+        at.format("%Y%m%dT%H%M%S%.3fZ")
+    );
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+/// Compresses `data` as a zstd frame, off the runtime threads
+async fn compress(data: Vec<u8>, level: i32) -> Result<Vec<u8>, anyhow::Error> {
+    tokio::task::spawn_blocking(move || zstd::encode_all(data.as_slice(), level))
+        .await
+        .context("zstd compression task failed")?
+        .context("Failed to compress with zstd")
+}
+
+/// Destinations of the archive files
+struct ArchiveSinks {
+    dir: Option<String>,
+    s3: Option<(S3Client, String)>,
+}
+
+impl ArchiveSinks {
+    fn new(config: &ArchiveConfig) -> Self {
+        let s3 = config.s3_location().map(|(endpoint, bucket)| {
+            let client = S3Client::new(
+                endpoint,
+                bucket,
+                config.s3_region(),
+                config.s3_access_key_id(),
+                config.s3_secret_access_key(),
+            );
+            (client, config.s3_prefix().to_string())
+        });
+        Self {
+            dir: config.dir().map(str::to_string),
+            s3,
+        }
+    }
+
+    async fn write(&self, at: DateTime<Utc>, body: Vec<u8>) {
+        if let Some(dir) = &self.dir {
+            let path = Path::new(dir).join(object_key("", at));
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(&path, &body));
+            if let Err(e) = written {
+                error!(error = %e, path = %path.display(), "Failed to write archive");
+            }
+        }
+        if let Some((s3, prefix)) = &self.s3 {
+            let key = object_key(prefix, at);
+            if let Err(e) = s3.put_object(&key, body, "application/zstd").await {
+                error!(error = %e, %key, "Archive upload failed");
+            }
+        }
+    }
+}
+
+/// Compresses and writes the batched responses, if any
+async fn flush(batch: &mut Vec<ArchivedResponse>, sinks: &ArchiveSinks, config: &ArchiveConfig) {
+    if batch.is_empty() {
+        return;
+    }
+    let at = Utc::now();
+    let responses = batch.len();
+    match compress(encode_batch(batch), config.zstd_level()).await {
+        Ok(body) => {
+            info!(
+                responses,
+                bytes = body.len(),
+                "Archiving upstream responses"
+            );
+            sinks.write(at, body).await;
+        }
+        Err(e) => error!(error = %e, responses, "Failed to compress archive"),
+    }
+    batch.clear();
+}
+
+/// Batches the queued responses and writes them at every flush interval
+pub async fn archive_task(mut responses: mpsc::Receiver<ArchivedResponse>, config: &ArchiveConfig) {
+    let sinks = ArchiveSinks::new(config);
+    let mut interval = interval(Duration::from_secs(config.flush_interval_s()));
+    let mut batch = Vec::new();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => flush(&mut batch, &sinks, config).await,
+            response = responses.recv() => {
+                let Some(response) = response else {
+                    flush(&mut batch, &sinks, config).await;
+                    return;
+                };
+                batch.push(response);
+                if batch.len() >= MAX_BATCH_LEN {
+                    flush(&mut batch, &sinks, config).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, encode_batch, object_key, ArchivedResponse, Sampler};
+    use chrono::{TimeZone, Utc};
+    use serde_json::{json, Value};
+    use std::sync::atomic::AtomicU64;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_archive_batch() {
+        let (sender, _receiver) = mpsc::channel(1);
+        let sampler = Sampler {
+            every: 3,
+            seen: AtomicU64::new(0),
+            sender,
+        };
+        let sampled: Vec<bool> = (0..6).map(|_| sampler.sample()).collect();
+        assert_eq!(sampled, [true, false, false, true, false, false]);
+
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        assert_eq!(
+            object_key("archive", at),
+            "archive/2025-03-10/responses-20250310T120000.000Z.jsonl.zst"
+        );
+        assert_eq!(
+            object_key("", at),
+            "2025-03-10/responses-20250310T120000.000Z.jsonl.zst"
+        );
+
+        let response = |method: &str| ArchivedResponse {
+            at,
+            upstream: "strata",
+            request: json!({ "method": method, "params": [] }),
+            response: json!([1, 2]),
+        };
+        let lines = encode_batch(&[response("a"), response("b")]);
+        let lines: Vec<Value> = String::from_utf8(lines)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["request"]["method"], "b");
+        assert_eq!(lines[1]["upstream"], "strata");
+
+        let batch = encode_batch(&[response("a")]);
+        let compressed = compress(batch.clone(), 3).await.unwrap();
+        assert_eq!(zstd::decode_all(compressed.as_slice()).unwrap(), batch);
+    }
+}
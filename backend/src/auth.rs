@@ -0,0 +1,298 @@
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use bitcoin::hashes::{sha256, Hash};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Bearer token guarding admin endpoints
+#[derive(Clone, Debug)]
+pub struct AdminAuth {
+    /// Expected token. Admin endpoints are disabled when not configured.
+    token: Option<String>,
+}
+
+impl AdminAuth {
+    pub fn new(token: Option<String>) -> Self {
+        Self { token }
+    }
+
+    /// Checks the `Authorization: Bearer <token>` header of a request.
+    ///
+    /// Fails closed with `403 Forbidden` when no admin token is configured.
+    pub fn check(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let Some(token) = &self.token else {
+            return Err(StatusCode::FORBIDDEN);
+        };
+
+        match bearer_token(headers) {
+            Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => Ok(()),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+/// Number of hex digits of the token hash naming unnamed tokens
+const TOKEN_HASH_LEN: usize = 8;
+
+/// Group of API endpoints a token can be scoped to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointGroup {
+    /// Network status, its history, the health overview, SLOs and background task progress
+    Status,
+    /// Paymaster wallet balances
+    Wallets,
+    /// Activity stats, account exports and paymaster reports
+    Activity,
+    Bridge,
+    Bundler,
+    /// Active alerts and incidents
+    Alerts,
+    /// Live monitoring events
+    Events,
+    /// Admin endpoints, runtime diagnostics and the environment fingerprint
+    Admin,
+    /// Explorer proxy, which spends the explorer quota on behalf of its callers
+    Proxy,
+}
+
+impl EndpointGroup {
+    /// Groups served without a token when `API_PUBLIC_GROUPS` is unset, i.e. all
+    /// but the admin and proxy ones
+    pub const PUBLIC_BY_DEFAULT: [EndpointGroup; 7] = [
+        EndpointGroup::Status,
+        EndpointGroup::Wallets,
+        EndpointGroup::Activity,
+        EndpointGroup::Bridge,
+        EndpointGroup::Bundler,
+        EndpointGroup::Alerts,
+        EndpointGroup::Events,
+    ];
+
+    /// Group of an API path, `None` for paths outside `/api`
+    pub(crate) fn of_path(path: &str) -> Option<Self> {
+        let path = path.strip_prefix("/api/")?;
+        let group = match path.split('/').next()? {
+            "status" | "tasks" | "overview" | "slo" | "chain_info" => EndpointGroup::Status,
+            "balances" => EndpointGroup::Wallets,
+            "accounts" | "activity" | "activity_stats" | "paymasters" => EndpointGroup::Activity,
+            "proxy" => EndpointGroup::Proxy,
+            "bridge" | "bridge_status" => EndpointGroup::Bridge,
+            "bundler_stats" | "bundles" => EndpointGroup::Bundler,
+            "alerts" | "incidents" => EndpointGroup::Alerts,
+            "events" => EndpointGroup::Events,
+            "admin" | "diagnostics" | "version" => EndpointGroup::Admin,
+            _ => return None,
+        };
+        Some(group)
+    }
+}
+
+/// API token granting access to some endpoint groups of some networks
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ApiToken {
+    token: String,
+    /// Consumer name in the usage metering, a hash of the token when unset
+    #[serde(default)]
+    name: Option<String>,
+    /// Names of the networks the token is valid for, see `NETWORK_NAME`
+    networks: Vec<String>,
+    groups: Vec<EndpointGroup>,
+}
+
+/// Scoped API tokens guarding the endpoint groups that are not public
+#[derive(Clone, Debug)]
+pub struct ApiAuth {
+    /// Name of the network served by this backend
+    network: String,
+    tokens: Arc<Vec<ApiToken>>,
+    public_groups: Arc<Vec<EndpointGroup>>,
+}
+
+impl ApiToken {
+    /// Name of the token, without revealing it
+    fn consumer(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            let mut hash = sha256::Hash::hash(self.token.as_bytes()).to_string();
+            hash.truncate(TOKEN_HASH_LEN);
+            hash
+        })
+    }
+}
+
+impl ApiAuth {
+    pub fn new(network: String, tokens: Vec<ApiToken>, public_groups: Vec<EndpointGroup>) -> Self {
+        Self {
+            network,
+            tokens: Arc::new(tokens),
+            public_groups: Arc::new(public_groups),
+        }
+    }
+
+    /// Checks the `Authorization: Bearer <token>` header of a request to `path`.
+    ///
+    /// Known tokens not scoped to this network or the path's group are rejected
+    /// with `403 Forbidden`, missing or unknown tokens with `401 Unauthorized`.
+    pub fn check(&self, path: &str, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let Some(group) = EndpointGroup::of_path(path) else {
+            return Ok(());
+        };
+        if self.public_groups.contains(&group) {
+            return Ok(());
+        }
+        self.check_token(group, headers)
+    }
+
+    /// Checks that a request is sent with a token scoped to `group`, whether or not
+    /// the group is public, e.g. for the endpoints storing data sent by callers
+    pub fn check_token(&self, group: EndpointGroup, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let token = self.known_token(headers).ok_or(StatusCode::UNAUTHORIZED)?;
+
+        if token.networks.contains(&self.network) && token.groups.contains(&group) {
+            Ok(())
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    /// Known token of the `Authorization: Bearer <token>` header, if any
+    fn known_token(&self, headers: &HeaderMap) -> Option<&ApiToken> {
+        let provided = bearer_token(headers)?;
+        self.tokens
+            .iter()
+            .find(|token| constant_time_eq(provided.as_bytes(), token.token.as_bytes()))
+    }
+
+    /// Name of the known token a request is sent with, see `ApiToken::name`
+    pub(crate) fn consumer(&self, headers: &HeaderMap) -> Option<String> {
+        self.known_token(headers).map(ApiToken::consumer)
+    }
+}
+
+/// Middleware enforcing API token scopes on every endpoint group that is not public
+pub async fn require_api_token(
+    State(auth): State<ApiAuth>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match auth.check(request.uri().path(), request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(status) => status.into_response(),
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Compares two byte strings without short-circuiting on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdminAuth, ApiAuth, ApiToken, EndpointGroup};
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode};
+    use serde_json::json;
+
+    fn headers_with(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_admin_auth() {
+        let auth = AdminAuth::new(Some("s3cret".to_string()));
+
+        assert_eq!(auth.check(&headers_with("Bearer s3cret")), Ok(()));
+        assert_eq!(
+            auth.check(&headers_with("Bearer wrong")),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            auth.check(&headers_with("s3cret")),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(auth.check(&HeaderMap::new()), Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_admin_auth_disabled_without_token() {
+        let auth = AdminAuth::new(None);
+        assert_eq!(
+            auth.check(&headers_with("Bearer anything")),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn test_api_auth_scopes() {
+        let partner: ApiToken = serde_json::from_value(json!({
+            "token": "partner",
+            "networks": ["testnet"],
+            "groups": ["bridge"],
+        }))
+        .unwrap();
+        let auth = ApiAuth::new(
+            "testnet".to_string(),
+            vec![partner],
+            vec![EndpointGroup::Status],
+        );
+
+        // Public groups and paths outside the API need no token
+        assert_eq!(auth.check("/api/status", &HeaderMap::new()), Ok(()));
+        assert_eq!(auth.check("/healthz", &HeaderMap::new()), Ok(()));
+
+        let partner = headers_with("Bearer partner");
+        assert_eq!(auth.check("/api/bridge_status", &partner), Ok(()));
+        assert_eq!(auth.check("/api/bridge/changes", &partner), Ok(()));
+        assert_eq!(
+            auth.check("/api/balances", &partner),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            auth.check("/api/bridge_status", &HeaderMap::new()),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            auth.check("/api/bridge_status", &headers_with("Bearer other")),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+
+        // A token is required even for the public groups when checked explicitly
+        assert_eq!(
+            auth.check_token(EndpointGroup::Status, &HeaderMap::new()),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            auth.check_token(EndpointGroup::Status, &partner),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(auth.check_token(EndpointGroup::Bridge, &partner), Ok(()));
+    }
+
+    #[test]
+    fn test_api_auth_network_scope() {
+        let token: ApiToken = serde_json::from_value(json!({
+            "token": "partner",
+            "networks": ["testnet"],
+            "groups": ["bridge"],
+        }))
+        .unwrap();
+        let auth = ApiAuth::new("mainnet".to_string(), vec![token], vec![]);
+        assert_eq!(
+            auth.check("/api/bridge_status", &headers_with("Bearer partner")),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+}
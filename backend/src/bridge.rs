@@ -1,11 +1,19 @@
-use axum::Json;
-use bitcoin::{secp256k1::PublicKey, OutPoint, Txid};
-use jsonrpsee::core::client::ClientT;
-use jsonrpsee::core::ClientError;
-use jsonrpsee::http_client::HttpClient;
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bitcoin::{address::NetworkUnchecked, secp256k1::PublicKey, Address, OutPoint, Txid};
+use chrono::{DateTime, Utc};
+use jsonrpsee::{core::ClientError, http_client::HttpClient};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    str::FromStr,
+    sync::Arc,
+};
 use strata_bridge_primitives::types::PublickeyTable;
 use strata_bridge_rpc::types::{
     RpcClaimInfo, RpcDepositInfo, RpcDepositStatus, RpcOperatorStatus, RpcReimbursementStatus,
@@ -13,21 +21,180 @@ use strata_bridge_rpc::types::{
 };
 use tokio::{
     sync::RwLock,
-    time::{interval, Duration},
+    time::{Duration, Instant},
 };
 use tracing::{error, info, warn};
 
-use crate::{config::BridgeMonitoringConfig, utils::create_rpc_client};
+use crate::{
+    alerts::{Alerts, Severity},
+    bridge_changes::{diff_bridge_status, BridgeChange, SharedBridgeChanges},
+    bridge_liability::{total_supply_sats, BridgeLiability},
+    bridge_watchlist::SharedWatchlist,
+    btc_validation::{upstream_outpoint, upstream_txid},
+    checkpoint,
+    clients::{BridgeClient, StrataClient},
+    config::BridgeMonitoringConfig,
+    degradation,
+    events::{EventBus, MonitorEvent},
+    heartbeats::{Heartbeats, OperatorLiveness},
+    l1::{EsploraClient, TxOutput, TxStatus},
+    polling::AdaptiveInterval,
+    tasks::{TaskRegistry, BRIDGE_STATUS_TASK},
+    txid_format::{self, parse_txid},
+    utils::{create_rpc_client, push_bounded},
+    watched::WatchedContracts,
+};
+
+/// Number of recent operator status polls used to rate responsiveness
+const RESPONSIVENESS_WINDOW: usize = 20;
+/// Number of duty queue depth samples kept per operator
+const DUTY_QUEUE_HISTORY_LEN: usize = 30;
+
+/// Public details of an operator declared in `BRIDGE_OPERATORS`, shown on the bridge
+/// page to make the federation composition transparent
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct OperatorMetadata {
+    /// Where the operator runs, e.g. `eu-west`
+    region: Option<String>,
+    /// Website of the entity running the operator
+    url: Option<String>,
+    /// How to reach the entity running the operator, e.g. an email address
+    contact: Option<String>,
+    /// Bitcoin address the operator is reimbursed to, checked against the payouts
+    #[serde(default)]
+    payout_address: Option<String>,
+}
+
+impl OperatorMetadata {
+    /// Getter for `payout_address`
+    pub fn payout_address(&self) -> Option<&str> {
+        self.payout_address.as_deref()
+    }
+}
 
 /// Bridge operator status
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct OperatorStatus {
-    operator_id: String,
+    pub(crate) operator_id: String,
     operator_address: PublicKey,
+    pub(crate) status: String,
+    pub(crate) responsiveness: OperatorResponsiveness,
+    /// Number of unfulfilled duties, `None` if the query failed
+    pub(crate) duty_queue_depth: Option<usize>,
+    /// Recent duty queue depths, oldest first
+    duty_queue_history: Vec<usize>,
+    /// Last signed heartbeat of the operator process
+    #[serde(default)]
+    last_heartbeat: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub(crate) liveness: OperatorLiveness,
+    #[serde(default)]
+    metadata: OperatorMetadata,
+}
+
+/// Per-operator samples kept across refresh cycles
+#[derive(Default)]
+struct OperatorHistory {
+    /// Status poll latencies in ms, `None` for failed polls
+    latencies: VecDeque<Option<u64>>,
+    duty_queue_depths: VecDeque<usize>,
+    /// Last status fetched successfully, reused until it is due again
+    cached_status: Option<CachedOperatorStatus>,
+}
+
+/// Operator status and the duty queue depth it was fetched at
+struct CachedOperatorStatus {
     status: String,
+    duty_queue_depth: Option<usize>,
+    /// Refresh cycles the status was reused in since it was fetched
+    reused: u32,
+}
+
+impl OperatorHistory {
+    /// Returns the cached status if it is still fresh, i.e. it was fetched less than
+    /// `refresh_cycles` cycles ago and the operator duties did not change since
+    fn reuse_status(
+        &mut self,
+        duty_queue_depth: Option<usize>,
+        refresh_cycles: u32,
+    ) -> Option<String> {
+        let cached = self.cached_status.as_mut()?;
+        if cached.reused + 1 >= refresh_cycles || cached.duty_queue_depth != duty_queue_depth {
+            return None;
+        }
+        cached.reused += 1;
+        Some(cached.status.clone())
+    }
+
+    /// Caches a freshly fetched status, dropping the cache if the query failed
+    fn cache_status(
+        &mut self,
+        result: &Result<String, ClientError>,
+        duty_queue_depth: Option<usize>,
+    ) {
+        self.cached_status = result.as_ref().ok().map(|status| CachedOperatorStatus {
+            status: status.clone(),
+            duty_queue_depth,
+            reused: 0,
+        });
+    }
 }
 
+/// Rating of how responsive an operator's status RPC has been recently
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponsivenessRating {
+    Healthy,
+    /// Online, but answering slower than the configured threshold
+    Slow,
+    /// Last poll failed, or most recent polls did
+    Unresponsive,
+}
+
+/// Operator status RPC latency over the last [`RESPONSIVENESS_WINDOW`] polls
 #[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OperatorResponsiveness {
+    last_latency_ms: Option<u64>,
+    avg_latency_ms: Option<u64>,
+    /// Fraction of successful polls
+    success_rate: f64,
+    pub(crate) rating: ResponsivenessRating,
+}
+
+impl OperatorResponsiveness {
+    /// Rates recent poll outcomes, oldest first: latency in ms, or `None` for failed polls
+    fn from_samples(samples: &VecDeque<Option<u64>>, slow_threshold_ms: u64) -> Self {
+        let latencies: Vec<u64> = samples.iter().flatten().copied().collect();
+        let success_rate = if samples.is_empty() {
+            0.0
+        } else {
+            latencies.len() as f64 / samples.len() as f64
+        };
+        let avg_latency_ms = if latencies.is_empty() {
+            None
+        } else {
+            Some(latencies.iter().sum::<u64>() / latencies.len() as u64)
+        };
+        let last_latency_ms = samples.back().copied().flatten();
+
+        let rating = match (last_latency_ms, avg_latency_ms) {
+            (None, _) => ResponsivenessRating::Unresponsive,
+            _ if success_rate < 0.5 => ResponsivenessRating::Unresponsive,
+            (Some(_), Some(avg)) if avg > slow_threshold_ms => ResponsivenessRating::Slow,
+            _ => ResponsivenessRating::Healthy,
+        };
+
+        Self {
+            last_latency_ms,
+            avg_latency_ms,
+            success_rate,
+            rating,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum DepositStatus {
     #[serde(rename = "In progress")]
     InProgress,
@@ -35,12 +202,51 @@ pub enum DepositStatus {
     Complete,
 }
 
+/// Bitcoin status of a pending deposit request transaction (DRT)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DrtStatus {
+    /// Waiting in the mempool
+    Mempool,
+    /// Mined, the bridge has yet to process it
+    Confirmed,
+    /// Unknown to the node, i.e. evicted from the mempool or replaced (RBF)
+    Dropped,
+}
+
+impl DrtStatus {
+    fn from_tx_status(status: Option<TxStatus>) -> Self {
+        match status {
+            Some(TxStatus {
+                confirmed: true, ..
+            }) => DrtStatus::Confirmed,
+            Some(_) => DrtStatus::Mempool,
+            None => DrtStatus::Dropped,
+        }
+    }
+}
+
 /// Deposit information passed to dashboard
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DepositInfo {
+    #[serde(with = "txid_format::txid")]
     pub deposit_request_txid: Txid,
+    #[serde(with = "txid_format::option_txid")]
     pub deposit_txid: Option<Txid>,
     pub status: DepositStatus,
+    /// Bitcoin status of the DRT, only checked while the deposit is in progress
+    pub drt_status: Option<DrtStatus>,
+    /// Withdrawal request against the deposit, if any
+    #[serde(with = "txid_format::option_txid")]
+    pub withdrawal_request_txid: Option<Txid>,
+    /// Deposited amount in sats, from the deposit entry
+    pub amount_sats: Option<u64>,
+    /// Confirmation time of the deposit tx, only known once complete and when an
+    /// Esplora url is configured
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// Why the bridge rejected the deposit, only set once failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
 }
 
 impl From<RpcDepositInfo> for DepositInfo {
@@ -52,14 +258,24 @@ impl From<RpcDepositInfo> for DepositInfo {
                 deposit_request_txid,
                 deposit_txid: None,
                 status: DepositStatus::InProgress,
+                drt_status: None,
+                withdrawal_request_txid: None,
+                amount_sats: None,
+                confirmed_at: None,
+                failure_reason: None,
             },
             RpcDepositStatus::Failed {
                 deposit_request_txid,
-                failure_reason: _,
+                failure_reason,
             } => DepositInfo {
                 deposit_request_txid,
                 deposit_txid: None,
                 status: DepositStatus::Failed,
+                drt_status: None,
+                withdrawal_request_txid: None,
+                amount_sats: None,
+                confirmed_at: None,
+                failure_reason: Some(failure_reason),
             },
             RpcDepositStatus::Complete {
                 deposit_request_txid,
@@ -68,19 +284,90 @@ impl From<RpcDepositInfo> for DepositInfo {
                 deposit_request_txid,
                 deposit_txid: Some(deposit_txid),
                 status: DepositStatus::Complete,
+                drt_status: None,
+                withdrawal_request_txid: None,
+                amount_sats: None,
+                confirmed_at: None,
+                failure_reason: None,
             },
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct DepositToWithdrawal {
+    #[serde(with = "txid_format::outpoint")]
     deposit_outpoint: OutPoint,
+    #[serde(with = "txid_format::option_txid")]
     withdrawal_request_txid: Option<Txid>,
+    /// Operator assigned to front the withdrawal
+    assignee: Option<u32>,
 }
 
-/// Withdrawal status
+/// Deposit seen by the monitor, with its withdrawal once requested
 #[derive(Serialize, Deserialize, Clone, Debug)]
+struct KnownDeposit {
+    deposit: DepositInfo,
+    link: DepositToWithdrawal,
+    withdrawal: Option<WithdrawalInfo>,
+    /// When the deposit was first seen final, unset for checkpoints written before
+    /// this was kept
+    #[serde(default)]
+    finalized_at: Option<DateTime<Utc>>,
+    /// When the deposit was first seen, unset for checkpoints written before this
+    /// was kept until the deposit is fetched again
+    #[serde(default)]
+    first_seen_at: Option<DateTime<Utc>>,
+    /// When a full resync first missed the deposit, cleared if it is returned again
+    #[serde(default)]
+    tombstoned_at: Option<DateTime<Utc>>,
+}
+
+impl KnownDeposit {
+    /// Whether `txid` is the deposit request txid or deposit txid
+    fn has_txid(&self, txid: &Txid) -> bool {
+        self.deposit.deposit_request_txid == *txid || self.deposit.deposit_txid == Some(*txid)
+    }
+
+    /// Time since the deposit was first seen, while it is in progress
+    fn pending_age(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        if self.deposit.status != DepositStatus::InProgress || self.tombstoned_at.is_some() {
+            return None;
+        }
+        Some(now - self.first_seen_at?)
+    }
+
+    /// Failed deposits and completed withdrawals no longer change, so they are only
+    /// fetched again by full resyncs
+    fn is_final(&self) -> bool {
+        self.deposit.status == DepositStatus::Failed
+            || self
+                .withdrawal
+                .as_ref()
+                .is_some_and(|withdrawal| withdrawal.status == WithdrawalStatus::Complete)
+    }
+}
+
+/// Ids of the known deposits that may still change. Tombstoned deposits are only
+/// looked for again by full resyncs.
+fn pending_deposit_ids(deposits: &BTreeMap<u32, KnownDeposit>) -> Vec<u32> {
+    deposits
+        .iter()
+        .filter(|(_, known)| !known.is_final() && known.tombstoned_at.is_none())
+        .map(|(deposit_id, _)| *deposit_id)
+        .collect()
+}
+
+/// Known deposits persisted across restarts
+#[derive(Serialize, Deserialize)]
+struct DepositsCheckpoint {
+    /// Highest deposit id seen
+    high_water_mark: Option<u32>,
+    deposits: BTreeMap<u32, KnownDeposit>,
+}
+
+/// Withdrawal status
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum WithdrawalStatus {
     #[serde(rename = "In progress")]
     InProgress,
@@ -90,30 +377,222 @@ pub enum WithdrawalStatus {
 /// Withdrawal information passed to dashboard
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WithdrawalInfo {
+    #[serde(with = "txid_format::txid")]
     pub withdrawal_request_txid: Txid,
+    #[serde(with = "txid_format::option_txid")]
     pub fulfillment_txid: Option<Txid>,
     pub status: WithdrawalStatus,
+    /// Operator assigned to front the withdrawal
+    pub assignee: Option<u32>,
+    /// Bitcoin address paid by the fulfillment tx, only known once fulfilled and
+    /// when an Esplora url is configured
+    pub recipient_address: Option<String>,
+    /// Amount paid out to the user in sats, known under the same conditions as
+    /// `recipient_address`
+    pub amount_sats: Option<u64>,
+    /// Confirmation time of the fulfillment tx
+    pub fulfilled_at: Option<DateTime<Utc>>,
 }
 
 impl WithdrawalInfo {
-    pub fn from_rpc(rpc_info: &RpcWithdrawalInfo, withdrawal_request_txid: Txid) -> Self {
+    pub fn from_rpc(
+        rpc_info: &RpcWithdrawalInfo,
+        withdrawal_request_txid: Txid,
+        assignee: Option<u32>,
+    ) -> Self {
         match &rpc_info.status {
             RpcWithdrawalStatus::InProgress => Self {
                 withdrawal_request_txid,
                 fulfillment_txid: None,
                 status: WithdrawalStatus::InProgress,
+                assignee,
+                recipient_address: None,
+                amount_sats: None,
+                fulfilled_at: None,
             },
             RpcWithdrawalStatus::Complete { fulfillment_txid } => Self {
                 withdrawal_request_txid,
                 fulfillment_txid: Some(*fulfillment_txid),
                 status: WithdrawalStatus::Complete,
+                assignee,
+                recipient_address: None,
+                amount_sats: None,
+                fulfilled_at: None,
+            },
+        }
+    }
+}
+
+/// Liquidity an operator fronted to users for completed withdrawals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OperatorFrontPayments {
+    operator_id: String,
+    /// Number of fulfilled withdrawals
+    withdrawals: usize,
+    total_fronted_sats: u64,
+}
+
+/// L1 fees of the DRT and deposit tx of a deposit, unset until fetched
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DepositFees {
+    #[serde(with = "txid_format::txid")]
+    deposit_request_txid: Txid,
+    drt_fee_sats: Option<u64>,
+    deposit_tx_fee_sats: Option<u64>,
+}
+
+/// L1 fee of the fulfillment tx of a withdrawal
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WithdrawalFees {
+    #[serde(with = "txid_format::txid")]
+    withdrawal_request_txid: Txid,
+    fulfillment_fee_sats: Option<u64>,
+}
+
+/// L1 fee of the payout tx reimbursing an operator
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReimbursementFees {
+    #[serde(with = "txid_format::txid")]
+    claim_txid: Txid,
+    payout_fee_sats: Option<u64>,
+}
+
+/// L1 fees paid across the bridge transaction chains, empty when no Esplora url
+/// is configured
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BridgeFees {
+    deposits: Vec<DepositFees>,
+    withdrawals: Vec<WithdrawalFees>,
+    reimbursements: Vec<ReimbursementFees>,
+    /// Sum of the known fees above
+    total_fees_sats: u64,
+}
+
+impl BridgeFees {
+    fn new(status: &BridgeStatus, tx_fees: &HashMap<Txid, u64>) -> Self {
+        let fee = |txid: Option<Txid>| txid.and_then(|txid| tx_fees.get(&txid).copied());
+        let deposits: Vec<DepositFees> = status
+            .deposits
+            .iter()
+            .map(|deposit| DepositFees {
+                deposit_request_txid: deposit.deposit_request_txid,
+                drt_fee_sats: fee(Some(deposit.deposit_request_txid)),
+                deposit_tx_fee_sats: fee(deposit.deposit_txid),
+            })
+            .collect();
+        let withdrawals: Vec<WithdrawalFees> = status
+            .withdrawals
+            .iter()
+            .map(|withdrawal| WithdrawalFees {
+                withdrawal_request_txid: withdrawal.withdrawal_request_txid,
+                fulfillment_fee_sats: fee(withdrawal.fulfillment_txid),
+            })
+            .collect();
+        let reimbursements: Vec<ReimbursementFees> = status
+            .reimbursements
+            .iter()
+            .map(|reimbursement| ReimbursementFees {
+                claim_txid: reimbursement.claim_txid,
+                payout_fee_sats: fee(reimbursement.payout_txid),
+            })
+            .collect();
+
+        let total_fees_sats = deposits
+            .iter()
+            .flat_map(|fees| [fees.drt_fee_sats, fees.deposit_tx_fee_sats])
+            .chain(withdrawals.iter().map(|fees| fees.fulfillment_fee_sats))
+            .chain(reimbursements.iter().map(|fees| fees.payout_fee_sats))
+            .flatten()
+            .sum();
+        Self {
+            deposits,
+            withdrawals,
+            reimbursements,
+            total_fees_sats,
+        }
+    }
+
+    /// Txs whose fees are reported
+    fn txids(status: &BridgeStatus) -> Vec<Txid> {
+        status
+            .deposits
+            .iter()
+            .flat_map(|deposit| [Some(deposit.deposit_request_txid), deposit.deposit_txid])
+            .chain(status.withdrawals.iter().map(|wd| wd.fulfillment_txid))
+            .chain(status.reimbursements.iter().map(|r| r.payout_txid))
+            .flatten()
+            .collect()
+    }
+}
+
+/// User payout of a withdrawal fulfillment tx
+#[derive(Clone, Debug, PartialEq)]
+struct FulfillmentPayout {
+    address: Option<String>,
+    amount: u64,
+}
+
+impl FulfillmentPayout {
+    /// The user payout is the first output that is not OP_RETURN metadata; later ones are change.
+    fn from_outputs(outputs: &[TxOutput]) -> Self {
+        match outputs
+            .iter()
+            .find(|output| output.scriptpubkey_type != "op_return")
+        {
+            Some(output) => Self {
+                address: output.scriptpubkey_address.clone(),
+                amount: output.value,
+            },
+            None => Self {
+                address: None,
+                amount: 0,
             },
         }
     }
 }
 
+/// Completed reimbursement whose payout tx does not pay an operator as expected
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PayoutDiscrepancy {
+    #[serde(with = "txid_format::txid")]
+    pub claim_txid: Txid,
+    #[serde(with = "txid_format::txid")]
+    pub payout_txid: Txid,
+    /// What does not match
+    pub reason: String,
+}
+
+/// Checks that a payout tx pays one of the operator payout addresses, at most the
+/// bridge denomination as the payout spends the deposit less its fee
+fn verify_payout(
+    outputs: &[TxOutput],
+    payout_addresses: &[&str],
+    denomination_sats: Option<u64>,
+) -> Result<(), String> {
+    let paid_sats: u64 = outputs
+        .iter()
+        .filter(|output| {
+            output
+                .scriptpubkey_address
+                .as_deref()
+                .is_some_and(|address| payout_addresses.contains(&address))
+        })
+        .map(|output| output.value)
+        .sum();
+    if paid_sats == 0 {
+        return Err("pays none of the operator payout addresses".to_string());
+    }
+    match denomination_sats {
+        Some(denomination) if paid_sats > denomination => Err(format!(
+            "pays {} sats to operators, more than the {} sats denomination",
+            paid_sats, denomination
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Reimbursement status
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum ReimbursementStatus {
     #[serde(rename = "In progress")]
     InProgress,
@@ -125,8 +604,10 @@ pub enum ReimbursementStatus {
 /// Claim and reimbursement information passed to dashboard
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ReimbursementInfo {
+    #[serde(with = "txid_format::txid")]
     pub claim_txid: Txid,
     pub challenge_step: String,
+    #[serde(with = "txid_format::option_txid")]
     pub payout_txid: Option<Txid>,
     pub status: ReimbursementStatus,
 }
@@ -162,90 +643,919 @@ impl From<&RpcClaimInfo> for ReimbursementInfo {
     }
 }
 
+/// Bridge entry no longer returned by the bridge, kept for
+/// `BRIDGE_TOMBSTONE_RETENTION_DAYS` rather than silently dropped
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Tombstoned<T> {
+    #[serde(flatten)]
+    pub entry: T,
+    /// When the entry was first missing
+    pub tombstoned_at: DateTime<Utc>,
+    /// Whether the entry could still change when it vanished, which is unexpected
+    pub was_active: bool,
+}
+
+/// Number of in-progress deposits by time since they were first seen
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DepositAgeBuckets {
+    under_1h: usize,
+    from_1h_to_6h: usize,
+    from_6h_to_24h: usize,
+    over_24h: usize,
+}
+
+impl DepositAgeBuckets {
+    /// Counts a deposit in progress for `age`
+    fn add(&mut self, age: chrono::Duration) {
+        let bucket = match age.num_hours() {
+            ..1 => &mut self.under_1h,
+            1..6 => &mut self.from_1h_to_6h,
+            6..24 => &mut self.from_6h_to_24h,
+            _ => &mut self.over_24h,
+        };
+        *bucket += 1;
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct BridgeStatus {
-    operators: Vec<OperatorStatus>,
-    deposits: Vec<DepositInfo>,
-    withdrawals: Vec<WithdrawalInfo>,
-    reimbursements: Vec<ReimbursementInfo>,
+    pub(crate) operators: Vec<OperatorStatus>,
+    pub(crate) deposits: Vec<DepositInfo>,
+    pub(crate) withdrawals: Vec<WithdrawalInfo>,
+    pub(crate) reimbursements: Vec<ReimbursementInfo>,
+    /// Fronted liquidity per operator, empty when no Esplora url is configured
+    front_payments: Vec<OperatorFrontPayments>,
+    /// Outstanding liability and the L2 supply cross-check
+    pub(crate) liability: BridgeLiability,
+    #[serde(default)]
+    fees: BridgeFees,
+    /// Number of failed deposits per failure reason, to spot recurring failure modes
+    #[serde(default)]
+    pub(crate) deposit_failures: BTreeMap<String, usize>,
+    /// Number of completed deposits left out of the lists above, see
+    /// `BRIDGE_PRUNE_COMPLETED_AFTER_DAYS`
+    #[serde(default)]
+    pub(crate) pruned_deposits: usize,
+    /// Payouts not matching the operator payout addresses, only checked when some
+    /// are declared in `BRIDGE_OPERATORS` and an Esplora url is configured
+    #[serde(default)]
+    pub(crate) payout_discrepancies: Vec<PayoutDiscrepancy>,
+    /// In-progress deposits by age, to spot the ones the bridge is slow to process
+    #[serde(default)]
+    pub(crate) deposit_ages: DepositAgeBuckets,
+    /// Deposits no longer returned by the bridge, left out of the lists above
+    #[serde(default)]
+    pub(crate) tombstoned_deposits: Vec<Tombstoned<DepositInfo>>,
+    /// Claims no longer returned by the bridge, left out of the lists above
+    #[serde(default)]
+    pub(crate) tombstoned_reimbursements: Vec<Tombstoned<ReimbursementInfo>>,
+}
+
+impl BridgeStatus {
+    /// Drops the deposits with the given deposit request txids from the lists, with
+    /// their withdrawals and fee entries. Totals computed beforehand still count them.
+    fn prune_deposits(&mut self, deposit_request_txids: &HashSet<Txid>) {
+        let withdrawal_request_txids: HashSet<Txid> = self
+            .deposits
+            .iter()
+            .filter(|deposit| deposit_request_txids.contains(&deposit.deposit_request_txid))
+            .filter_map(|deposit| deposit.withdrawal_request_txid)
+            .collect();
+        let before = self.deposits.len();
+        self.deposits
+            .retain(|deposit| !deposit_request_txids.contains(&deposit.deposit_request_txid));
+        self.pruned_deposits += before - self.deposits.len();
+        self.withdrawals.retain(|withdrawal| {
+            !withdrawal_request_txids.contains(&withdrawal.withdrawal_request_txid)
+        });
+        self.fees
+            .deposits
+            .retain(|fees| !deposit_request_txids.contains(&fees.deposit_request_txid));
+        self.fees
+            .withdrawals
+            .retain(|fees| !withdrawal_request_txids.contains(&fees.withdrawal_request_txid));
+    }
+}
+
+/// Failure reason counted for failed deposits recorded without one, e.g. before
+/// reasons were kept
+const UNKNOWN_FAILURE_REASON: &str = "unknown";
+
+/// Number of failed deposits per failure reason
+pub(crate) fn deposit_failure_counts(deposits: &[DepositInfo]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for deposit in deposits {
+        if deposit.status != DepositStatus::Failed {
+            continue;
+        }
+        let reason = deposit
+            .failure_reason
+            .as_deref()
+            .unwrap_or(UNKNOWN_FAILURE_REASON);
+        *counts.entry(reason.to_string()).or_default() += 1;
+    }
+    counts
 }
 
 /// Shared bridge state
 pub type SharedBridgeState = Arc<RwLock<BridgeStatus>>;
 
 /// Periodically fetch bridge status and update shared bridge state
-pub async fn bridge_monitoring_task(state: SharedBridgeState, config: &BridgeMonitoringConfig) {
-    let mut interval = interval(Duration::from_secs(config.status_refetch_interval()));
-    let strata_rpc = create_rpc_client(config.strata_rpc_url());
-    let bridge_rpc = create_rpc_client(config.bridge_rpc_url());
+#[allow(clippy::too_many_arguments)]
+pub async fn bridge_monitoring_task(
+    state: SharedBridgeState,
+    changes: SharedBridgeChanges,
+    events: EventBus,
+    heartbeats: Heartbeats,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    watched: WatchedContracts,
+    watchlist: SharedWatchlist,
+    config: &BridgeMonitoringConfig,
+) {
+    tasks
+        .register(BRIDGE_STATUS_TASK, config.status_refetch_interval())
+        .await;
+    let mut interval = AdaptiveInterval::new(BRIDGE_STATUS_TASK, config.status_refetch_interval());
+    let mut watchlist_interval =
+        tokio::time::interval(Duration::from_secs(config.watchlist_interval()));
+    let has_watchlist = !config.watchlist().is_empty();
+    let mut monitor = BridgeMonitor::new(
+        create_rpc_client(config.strata_rpc_url()),
+        create_rpc_client(config.bridge_rpc_url()),
+        config
+            .esplora_url()
+            .map(|url| EsploraClient::new(url, config.bitcoin_network())),
+        Some(create_rpc_client(config.l2_rpc_url())),
+    )
+    .with_checkpoint(config.checkpoint_path())
+    .with_heartbeats(heartbeats)
+    .with_watched_contracts(watched);
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = watchlist_interval.tick(), if has_watchlist => {
+                // Check the watched deposits again between the regular refreshes
+                let txids = watchlist.read().await.pending_txids();
+                if !txids.is_empty() {
+                    monitor.refetch_watched(&txids).await;
+                    monitor.update_watchlist(&watchlist, &events).await;
+                }
+                continue;
+            }
+        }
+        tasks.start_refresh(BRIDGE_STATUS_TASK).await;
+        // Build the new state without holding the lock during RPC calls
+        let new_status = monitor.refresh(&alerts, config).await;
+
         let mut locked_state = state.write().await;
+        let bridge_changes = diff_bridge_status(&locked_state, &new_status);
+        *locked_state = new_status;
+        drop(locked_state);
+        let at = Utc::now();
+        for change in &bridge_changes {
+            if let BridgeChange::Deposit(deposit) = change {
+                events.publish(MonitorEvent::DepositUpdated {
+                    at,
+                    deposit: deposit.clone(),
+                });
+            }
+        }
+        changes.write().await.record(bridge_changes, at);
+        if has_watchlist {
+            monitor.update_watchlist(&watchlist, &events).await;
+        }
+
+        // The RPC clients record their failures for the cycle in progress
+        let upstream_healthy = degradation::cycle_failures(BRIDGE_STATUS_TASK).is_empty();
+        interval.adapt(upstream_healthy, &tasks).await;
+        tasks.record_refresh(BRIDGE_STATUS_TASK).await;
+    }
+}
+
+/// Runs a single refresh cycle against the configured RPCs, e.g. the `mock_rpc`
+/// server, without checkpoint, watch list or shared state
+pub async fn dry_run(config: &BridgeMonitoringConfig) -> BridgeStatus {
+    refresh_once(
+        create_rpc_client(config.strata_rpc_url()),
+        create_rpc_client(config.bridge_rpc_url()),
+        config
+            .esplora_url()
+            .map(|url| EsploraClient::new(url, config.bitcoin_network())),
+        Some(create_rpc_client(config.l2_rpc_url())),
+        config,
+    )
+    .await
+}
+
+/// Single refresh cycle of a fresh monitor
+async fn refresh_once(
+    strata_rpc: impl StrataClient,
+    bridge_rpc: impl BridgeClient,
+    esplora: Option<EsploraClient>,
+    l2_rpc: Option<HttpClient>,
+    config: &BridgeMonitoringConfig,
+) -> BridgeStatus {
+    BridgeMonitor::new(strata_rpc, bridge_rpc, esplora, l2_rpc)
+        .refresh(&Alerts::default(), config)
+        .await
+}
+
+/// Upstream clients and the per-operator samples kept across refresh cycles
+struct BridgeMonitor<S, B> {
+    strata_rpc: S,
+    bridge_rpc: B,
+    esplora: Option<EsploraClient>,
+    /// Reth RPC client reading the supply of the bridge contracts
+    l2_rpc: Option<HttpClient>,
+    operator_histories: HashMap<u32, OperatorHistory>,
+    /// Fulfillment txs are final, so their payouts are fetched only once
+    fulfillment_payouts: HashMap<Txid, FulfillmentPayout>,
+    /// Outputs of the reimbursement payout txs, which are final
+    payout_outputs: HashMap<Txid, Vec<TxOutput>>,
+    /// Confirmation times of deposit and fulfillment txs, fetched once confirmed
+    confirmation_times: HashMap<Txid, DateTime<Utc>>,
+    /// A txid commits to the fee, so fees are fetched only once
+    tx_fees: HashMap<Txid, u64>,
+    /// Deposits by id
+    deposits: BTreeMap<u32, KnownDeposit>,
+    /// Highest deposit id seen; new deposits are looked for above it
+    high_water_mark: Option<u32>,
+    last_full_resync: Option<Instant>,
+    /// File the known deposits are checkpointed to, if any
+    checkpoint_path: Option<String>,
+    /// Heartbeats posted by the operators
+    heartbeats: Heartbeats,
+    /// Bridge contracts whose supply is cross-checked, editable at runtime
+    watched: WatchedContracts,
+    /// Claims returned by the last successful reimbursements query
+    claims: BTreeMap<Txid, ReimbursementInfo>,
+    /// Claims no longer returned, by claim txid. Unlike the deposits they are not
+    /// checkpointed, so a restart forgets them.
+    tombstoned_claims: BTreeMap<Txid, Tombstoned<ReimbursementInfo>>,
+}
+
+impl<S: StrataClient, B: BridgeClient> BridgeMonitor<S, B> {
+    fn new(
+        strata_rpc: S,
+        bridge_rpc: B,
+        esplora: Option<EsploraClient>,
+        l2_rpc: Option<HttpClient>,
+    ) -> Self {
+        Self {
+            strata_rpc,
+            bridge_rpc,
+            esplora,
+            l2_rpc,
+            operator_histories: HashMap::new(),
+            fulfillment_payouts: HashMap::new(),
+            payout_outputs: HashMap::new(),
+            confirmation_times: HashMap::new(),
+            tx_fees: HashMap::new(),
+            deposits: BTreeMap::new(),
+            high_water_mark: None,
+            last_full_resync: None,
+            checkpoint_path: None,
+            heartbeats: Heartbeats::default(),
+            watched: WatchedContracts::default(),
+            claims: BTreeMap::new(),
+            tombstoned_claims: BTreeMap::new(),
+        }
+    }
+
+    fn with_heartbeats(mut self, heartbeats: Heartbeats) -> Self {
+        self.heartbeats = heartbeats;
+        self
+    }
+
+    fn with_watched_contracts(mut self, watched: WatchedContracts) -> Self {
+        self.watched = watched;
+        self
+    }
+
+    /// Resumes from the deposits checkpointed at `path`, if any. A restored checkpoint
+    /// counts as a full resync.
+    fn with_checkpoint(mut self, path: Option<&str>) -> Self {
+        let Some(path) = path else {
+            return self;
+        };
+        if let Some(checkpoint) = checkpoint::load::<DepositsCheckpoint>(path) {
+            info!(
+                deposits = checkpoint.deposits.len(),
+                high_water_mark = ?checkpoint.high_water_mark,
+                "Resuming bridge deposits from checkpoint"
+            );
+            self.deposits = checkpoint.deposits;
+            self.high_water_mark = checkpoint.high_water_mark;
+            self.last_full_resync = Some(Instant::now());
+        }
+        self.checkpoint_path = Some(path.to_string());
+        self
+    }
+
+    /// Fetch a deposit entry and its bridge status, `false` if there is no such deposit
+    async fn fetch_deposit(&mut self, deposit_id: u32) -> bool {
+        let Ok((Some(mut deposit), Some(link))) =
+            get_deposit_info(&self.strata_rpc, &self.bridge_rpc, deposit_id).await
+        else {
+            return false;
+        };
+        if let (DepositStatus::InProgress, Some(esplora)) = (&deposit.status, &self.esplora) {
+            deposit.drt_status = get_drt_status(esplora, &deposit.deposit_request_txid).await;
+        }
+
+        // Keep the fetched withdrawal as long as it belongs to the same request
+        let previous = self.deposits.remove(&deposit_id);
+        let first_seen_at = previous
+            .as_ref()
+            .and_then(|known| known.first_seen_at)
+            .unwrap_or_else(Utc::now);
+        let withdrawal = previous
+            .as_ref()
+            .and_then(|known| known.withdrawal.clone())
+            .filter(|withdrawal| {
+                Some(withdrawal.withdrawal_request_txid) == link.withdrawal_request_txid
+            });
+        let mut known = KnownDeposit {
+            deposit,
+            link,
+            withdrawal,
+            finalized_at: None,
+            first_seen_at: Some(first_seen_at),
+            tombstoned_at: None,
+        };
+        if known.is_final() {
+            known.finalized_at = previous.and_then(|previous| previous.finalized_at);
+        }
+        self.deposits.insert(deposit_id, known);
+        true
+    }
+
+    /// Tombstone the known deposits missing from the `current` ids, and drop the
+    /// ones tombstoned for longer than `retention`
+    fn tombstone_deposits(
+        &mut self,
+        current: &BTreeSet<u32>,
+        retention: chrono::Duration,
+        now: DateTime<Utc>,
+    ) {
+        for (deposit_id, known) in self.deposits.iter_mut() {
+            if !current.contains(deposit_id) && known.tombstoned_at.is_none() {
+                warn!(
+                    %deposit_id,
+                    deposit_request_txid = %known.deposit.deposit_request_txid,
+                    "Deposit no longer returned, tombstoning"
+                );
+                known.tombstoned_at = Some(now);
+            }
+        }
+        self.deposits.retain(|_, known| {
+            known
+                .tombstoned_at
+                .is_none_or(|tombstoned_at| tombstoned_at + retention > now)
+        });
+    }
+
+    /// Replace the known claims by the `current` ones, tombstoning the missing
+    /// ones and dropping the ones tombstoned for longer than `retention`
+    fn tombstone_claims(
+        &mut self,
+        current: &[ReimbursementInfo],
+        retention: chrono::Duration,
+        now: DateTime<Utc>,
+    ) {
+        let current: BTreeMap<Txid, ReimbursementInfo> = current
+            .iter()
+            .map(|claim| (claim.claim_txid, claim.clone()))
+            .collect();
+        for (claim_txid, claim) in std::mem::take(&mut self.claims) {
+            if current.contains_key(&claim_txid) {
+                continue;
+            }
+            warn!(%claim_txid, "Claim no longer returned, tombstoning");
+            let was_active = matches!(
+                claim.status,
+                ReimbursementStatus::InProgress | ReimbursementStatus::Challenged
+            );
+            self.tombstoned_claims.insert(
+                claim_txid,
+                Tombstoned {
+                    entry: claim,
+                    tombstoned_at: now,
+                    was_active,
+                },
+            );
+        }
+        self.tombstoned_claims.retain(|claim_txid, tombstoned| {
+            !current.contains_key(claim_txid) && tombstoned.tombstoned_at + retention > now
+        });
+        self.claims = current;
+    }
+
+    /// Deposits and claims that vanished while they could still change, by txid
+    fn vanished_active_entries(&self) -> Vec<String> {
+        let deposits = self
+            .deposits
+            .values()
+            .filter(|known| known.tombstoned_at.is_some() && !known.is_final())
+            .map(|known| known.deposit.deposit_request_txid.to_string());
+        let claims = self
+            .tombstoned_claims
+            .values()
+            .filter(|tombstoned| tombstoned.was_active)
+            .map(|tombstoned| tombstoned.entry.claim_txid.to_string());
+        deposits.chain(claims).collect()
+    }
+
+    /// Record when the known deposits became final
+    fn mark_finalized(&mut self, now: DateTime<Utc>) {
+        for known in self.deposits.values_mut() {
+            if known.is_final() && known.finalized_at.is_none() {
+                known.finalized_at = Some(now);
+            }
+        }
+    }
+
+    /// Deposit request txids of the deposits final for longer than `retention`
+    fn prunable_deposits(&self, retention: chrono::Duration, now: DateTime<Utc>) -> HashSet<Txid> {
+        self.deposits
+            .values()
+            .filter(|known| {
+                known
+                    .finalized_at
+                    .is_some_and(|finalized_at| finalized_at + retention <= now)
+            })
+            .map(|known| known.deposit.deposit_request_txid)
+            .collect()
+    }
+
+    /// Update the known deposits.
+    ///
+    /// Between full resyncs only the deposits that may still change are fetched again,
+    /// and new deposits are looked for in a window of ids above the high-water mark.
+    async fn sync_deposits(&mut self, config: &BridgeMonitoringConfig) {
+        let full_resync = match self.last_full_resync {
+            Some(at) => at.elapsed() >= Duration::from_secs(config.full_resync_interval()),
+            None => true,
+        };
+
+        if full_resync {
+            let Ok(deposit_ids) = get_current_deposits(&self.strata_rpc).await else {
+                return;
+            };
+            let current: BTreeSet<u32> = deposit_ids.iter().copied().collect();
+            self.tombstone_deposits(&current, config.tombstone_retention(), Utc::now());
+            for deposit_id in deposit_ids {
+                if !self.fetch_deposit(deposit_id).await {
+                    warn!(%deposit_id, "Missing deposit entry for id");
+                }
+            }
+            self.last_full_resync = Some(Instant::now());
+            info!(
+                deposits = self.deposits.len(),
+                "Full bridge deposits resync"
+            );
+        } else {
+            for deposit_id in pending_deposit_ids(&self.deposits) {
+                if !self.fetch_deposit(deposit_id).await {
+                    warn!(%deposit_id, "Missing deposit entry for id");
+                }
+            }
+            // Deposit ids are assigned sequentially
+            let mut next_id = self.high_water_mark.map_or(0, |id| id + 1);
+            for _ in 0..config.deposit_batch_size() {
+                if !self.fetch_deposit(next_id).await {
+                    break;
+                }
+                next_id += 1;
+            }
+        }
+        self.high_water_mark = self
+            .high_water_mark
+            .max(self.deposits.keys().next_back().copied());
+
+        if let Some(path) = &self.checkpoint_path {
+            let checkpoint = DepositsCheckpoint {
+                high_water_mark: self.high_water_mark,
+                deposits: self.deposits.clone(),
+            };
+            if let Err(e) = checkpoint::save(path, &checkpoint) {
+                warn!(error = %e, "Failed to checkpoint bridge deposits");
+            }
+        }
+    }
+
+    /// Attach the withdrawals to the known deposits they were requested against
+    fn record_withdrawals(&mut self, withdrawals: Vec<WithdrawalInfo>) {
+        for withdrawal in withdrawals {
+            let request_txid = Some(withdrawal.withdrawal_request_txid);
+            if let Some(known) = self
+                .deposits
+                .values_mut()
+                .find(|known| known.link.withdrawal_request_txid == request_txid)
+            {
+                known.withdrawal = Some(withdrawal);
+            }
+        }
+    }
+
+    /// Set the confirmation time of a deposit, if fetched
+    fn set_confirmation_time(&self, deposit: &mut DepositInfo) {
+        deposit.confirmed_at = deposit
+            .deposit_txid
+            .and_then(|txid| self.confirmation_times.get(&txid).copied());
+    }
+
+    /// Set the payout and confirmation time of a fulfilled withdrawal, if fetched
+    fn set_payout(&self, withdrawal: &mut WithdrawalInfo) {
+        let payout = withdrawal
+            .fulfillment_txid
+            .and_then(|txid| self.fulfillment_payouts.get(&txid));
+        withdrawal.recipient_address = payout.and_then(|payout| payout.address.clone());
+        withdrawal.amount_sats = payout.map(|payout| payout.amount);
+        withdrawal.fulfilled_at = withdrawal
+            .fulfillment_txid
+            .and_then(|txid| self.confirmation_times.get(&txid).copied());
+    }
+
+    /// Fetch the known deposits with one of `txids` again, along with their withdrawals
+    async fn refetch_watched(&mut self, txids: &[Txid]) {
+        let deposit_ids: Vec<u32> = self
+            .deposits
+            .iter()
+            .filter(|(_, known)| !known.is_final() && txids.iter().any(|txid| known.has_txid(txid)))
+            .map(|(deposit_id, _)| *deposit_id)
+            .collect();
+        for deposit_id in &deposit_ids {
+            if !self.fetch_deposit(*deposit_id).await {
+                warn!(%deposit_id, "Missing deposit entry for id");
+            }
+        }
+
+        let pending_withdrawals: Vec<DepositToWithdrawal> = deposit_ids
+            .iter()
+            .filter_map(|deposit_id| self.deposits.get(deposit_id))
+            .filter(|known| known.link.withdrawal_request_txid.is_some() && !known.is_final())
+            .map(|known| known.link.clone())
+            .collect();
+        if pending_withdrawals.is_empty() {
+            return;
+        }
+        match get_withdrawals(&self.bridge_rpc, pending_withdrawals).await {
+            Ok(withdrawals) => self.record_withdrawals(withdrawals),
+            Err(e) => error!(error = %e, "Bridge get watched withdrawal failed"),
+        }
+    }
+
+    /// Known deposit with `txid` and its withdrawal, as shown on the dashboard
+    fn watched_deposit(&self, txid: &Txid) -> Option<(DepositInfo, Option<WithdrawalInfo>)> {
+        let known = self.deposits.values().find(|known| known.has_txid(txid))?;
+        let mut deposit = known.deposit.clone();
+        self.set_confirmation_time(&mut deposit);
+        let withdrawal = known.withdrawal.clone().map(|mut withdrawal| {
+            self.set_payout(&mut withdrawal);
+            withdrawal
+        });
+        Some((deposit, withdrawal))
+    }
+
+    /// Update the watch list from the known deposits, publishing the changes
+    async fn update_watchlist(&self, watchlist: &SharedWatchlist, events: &EventBus) {
+        let at = Utc::now();
+        let changed = watchlist
+            .write()
+            .await
+            .update(|txid| self.watched_deposit(txid), at);
+        for watched in changed {
+            info!(
+                label = ?watched.entry.label,
+                txid = %watched.entry.txid,
+                "Watched deposit changed"
+            );
+            events.publish(MonitorEvent::WatchedDepositUpdated { at, watched });
+        }
+    }
+
+    /// Fetch the current bridge status, raising or resolving duty backlog alerts.
+    ///
+    /// Deposits and withdrawals are listed from the tracked entries on every cycle, so
+    /// the returned status replaces the previous one rather than being appended to it.
+    async fn refresh(&mut self, alerts: &Alerts, config: &BridgeMonitoringConfig) -> BridgeStatus {
+        let mut new_status = BridgeStatus::default();
 
         // Bridge operator status
-        let operators = get_bridge_operators(&bridge_rpc).await.unwrap();
+        let operators = get_bridge_operators(&self.bridge_rpc).await.unwrap();
+        self.heartbeats.set_operator_keys(operators.0.clone()).await;
         let mut operator_statuses = Vec::new();
         for (index, public_key) in operators.0.iter() {
             let operator_id = format!("Alpen Labs #{}", index);
-            let status = get_operator_status(&bridge_rpc, *index).await.unwrap();
+            let duty_queue_depth = get_operator_duty_count(&self.bridge_rpc, *index).await.ok();
+
+            let now = Utc::now();
+            let max_heartbeat_age = chrono::Duration::seconds(config.heartbeat_max_age() as i64);
+            let last_heartbeat = self.heartbeats.last(*index).await;
+            let heartbeat_fresh = last_heartbeat.is_some_and(|at| now - at <= max_heartbeat_age);
+
+            let history = self.operator_histories.entry(*index).or_default();
+            // Statuses rarely change, so they are only polled every few cycles or once
+            // the duties of the operator change. The status poll is the only liveness
+            // check of operators without recent heartbeats, so they are polled every
+            // cycle.
+            let reused = if heartbeat_fresh {
+                history.reuse_status(duty_queue_depth, config.operator_status_refresh_cycles())
+            } else {
+                None
+            };
+            let is_reused = reused.is_some();
+            let result = match reused {
+                Some(status) => Ok(status),
+                None => {
+                    let started = Instant::now();
+                    let result = get_operator_status(&self.bridge_rpc, *index).await;
+                    let latency_ms = result.is_ok().then(|| started.elapsed().as_millis() as u64);
+                    push_bounded(&mut history.latencies, latency_ms, RESPONSIVENESS_WINDOW);
+                    history.cache_status(&result, duty_queue_depth);
+                    result
+                }
+            };
+            if let Some(depth) = duty_queue_depth {
+                push_bounded(
+                    &mut history.duty_queue_depths,
+                    depth,
+                    DUTY_QUEUE_HISTORY_LEN,
+                );
+            }
+            let responsiveness = OperatorResponsiveness::from_samples(
+                &history.latencies,
+                config.operator_slow_threshold_ms(),
+            );
+
+            let alert_id = format!("bridge_duty_backlog:{}", index);
+            match duty_queue_depth {
+                Some(depth) if depth > config.duty_backlog_threshold() => {
+                    alerts
+                        .raise(
+                            alert_id,
+                            Severity::Warning,
+                            format!(
+                                "{} has {} unfulfilled duties (threshold {})",
+                                operator_id,
+                                depth,
+                                config.duty_backlog_threshold()
+                            ),
+                        )
+                        .await;
+                }
+                Some(_) => alerts.resolve(&alert_id).await,
+                // Keep the last known state when the query failed
+                None => {}
+            }
+
+            let liveness = if is_reused {
+                OperatorLiveness::Stale
+            } else {
+                OperatorLiveness::new(result.is_ok(), last_heartbeat, now, max_heartbeat_age)
+            };
 
             operator_statuses.push(OperatorStatus {
                 operator_id,
                 operator_address: *public_key,
-                status,
+                status: result.unwrap_or_else(|_| "Unknown".to_string()),
+                responsiveness,
+                duty_queue_depth,
+                duty_queue_history: history.duty_queue_depths.iter().copied().collect(),
+                last_heartbeat,
+                liveness,
+                metadata: config
+                    .operator_metadata(*index)
+                    .cloned()
+                    .unwrap_or_default(),
             });
         }
 
-        locked_state.operators = operator_statuses;
+        new_status.operators = operator_statuses;
 
         // Current deposits
-        let current_deposits = get_current_deposits(&strata_rpc).await.unwrap();
-        // Deposits with withdrawal requests
-        let mut deposits_to_withdrawals: Vec<DepositToWithdrawal> = Vec::new();
-
-        for deposit_id in current_deposits {
-            let (deposit_info, deposit_to_wd) =
-                get_deposit_info(&strata_rpc, &bridge_rpc, deposit_id)
-                    .await
-                    .unwrap();
-            if let Some(deposit) = deposit_info {
-                locked_state.deposits.push(deposit);
-                deposits_to_withdrawals.push(deposit_to_wd.unwrap());
-            } else {
-                warn!(%deposit_id, "Missing deposit entry for id");
+        self.sync_deposits(config).await;
+        let mut deposits: Vec<DepositInfo> = self
+            .deposits
+            .values()
+            .filter(|known| known.tombstoned_at.is_none())
+            .map(|known| known.deposit.clone())
+            .collect();
+        new_status.tombstoned_deposits = self
+            .deposits
+            .values()
+            .filter_map(|known| {
+                Some(Tombstoned {
+                    entry: known.deposit.clone(),
+                    tombstoned_at: known.tombstoned_at?,
+                    was_active: !known.is_final(),
+                })
+            })
+            .collect();
+        if let Some(esplora) = &self.esplora {
+            let deposit_txids: Vec<Txid> = deposits
+                .iter()
+                .filter_map(|deposit| deposit.deposit_txid)
+                .collect();
+            fetch_confirmation_times(esplora, deposit_txids, &mut self.confirmation_times).await;
+            for deposit in deposits.iter_mut() {
+                self.set_confirmation_time(deposit);
+            }
+        }
+        new_status.deposit_failures = deposit_failure_counts(&deposits);
+        new_status.deposits = deposits;
+
+        // Deposit ages
+        let now = Utc::now();
+        let stuck_after = chrono::Duration::seconds(config.stuck_deposit_age() as i64);
+        let mut stuck = Vec::new();
+        for known in self.deposits.values() {
+            let Some(age) = known.pending_age(now) else {
+                continue;
+            };
+            new_status.deposit_ages.add(age);
+            if age > stuck_after {
+                stuck.push(known.deposit.deposit_request_txid.to_string());
+            }
+        }
+        if stuck.is_empty() {
+            alerts.resolve("bridge_stuck_deposits").await;
+        } else {
+            alerts
+                .raise(
+                    "bridge_stuck_deposits".to_string(),
+                    Severity::Warning,
+                    format!(
+                        "{} deposits in progress for over {} s: {}",
+                        stuck.len(),
+                        config.stuck_deposit_age(),
+                        stuck.join(", ")
+                    ),
+                )
+                .await;
+        }
+
+        // Withdrawal fulfillment; completed withdrawals are final and not fetched again
+        let pending_withdrawals: Vec<DepositToWithdrawal> = self
+            .deposits
+            .values()
+            .filter(|known| {
+                known.link.withdrawal_request_txid.is_some()
+                    && !known.is_final()
+                    && known.tombstoned_at.is_none()
+            })
+            .map(|known| known.link.clone())
+            .collect();
+        match get_withdrawals(&self.bridge_rpc, pending_withdrawals).await {
+            Ok(withdrawals) => self.record_withdrawals(withdrawals),
+            // Keep the last known withdrawal states
+            Err(e) => error!(error = %e, "Bridge get withdrawal failed"),
+        }
+        self.mark_finalized(Utc::now());
+        let mut withdrawal_infos: Vec<WithdrawalInfo> = self
+            .deposits
+            .values()
+            .filter(|known| known.tombstoned_at.is_none())
+            .filter_map(|known| known.withdrawal.clone())
+            .collect();
+        if let Some(esplora) = &self.esplora {
+            fetch_fulfillment_payouts(esplora, &withdrawal_infos, &mut self.fulfillment_payouts)
+                .await;
+            let fulfillment_txids: Vec<Txid> = withdrawal_infos
+                .iter()
+                .filter_map(|withdrawal| withdrawal.fulfillment_txid)
+                .collect();
+            fetch_confirmation_times(esplora, fulfillment_txids, &mut self.confirmation_times)
+                .await;
+            for withdrawal in withdrawal_infos.iter_mut() {
+                self.set_payout(withdrawal);
             }
+            new_status.front_payments =
+                front_payments(&withdrawal_infos, &self.fulfillment_payouts);
         }
+        new_status.withdrawals = withdrawal_infos;
 
-        // Withdrawal fulfillment
-        let mut withdrawal_infos: Vec<WithdrawalInfo> =
-            match get_withdrawals(&bridge_rpc, deposits_to_withdrawals).await {
-                Ok(data) => data,
+        // Reimbursements
+        let reimbursements: Vec<ReimbursementInfo> =
+            match get_reimbursements(&self.bridge_rpc).await {
+                Ok(data) => {
+                    self.tombstone_claims(&data, config.tombstone_retention(), Utc::now());
+                    data
+                }
                 Err(e) => {
-                    error!(error = %e, "Bridge get withdrawal failed");
+                    error!(error = %e, "Bridge get reimbursement failed");
                     Vec::new()
                 }
             };
-        locked_state.withdrawals.append(&mut withdrawal_infos);
+        new_status.reimbursements = reimbursements;
+        new_status.tombstoned_reimbursements = self.tombstoned_claims.values().cloned().collect();
 
-        // Reimbursements
-        let reimbursements: Vec<ReimbursementInfo> = match get_reimbursements(&bridge_rpc).await {
-            Ok(data) => data,
-            Err(e) => {
-                error!(error = %e, "Bridge get reimbursement failed");
-                Vec::new()
+        // Entries are expected to vanish once final, not while in progress
+        let vanished = self.vanished_active_entries();
+        if vanished.is_empty() {
+            alerts.resolve("bridge_vanished_entries").await;
+        } else {
+            alerts
+                .raise(
+                    "bridge_vanished_entries".to_string(),
+                    Severity::Warning,
+                    format!(
+                        "{} active deposits or claims no longer returned by the bridge: {}",
+                        vanished.len(),
+                        vanished.join(", ")
+                    ),
+                )
+                .await;
+        }
+
+        // Payout verification
+        let payout_addresses = config.operator_payout_addresses();
+        if let Some(esplora) = self
+            .esplora
+            .as_ref()
+            .filter(|_| !payout_addresses.is_empty())
+        {
+            fetch_payout_outputs(
+                esplora,
+                &new_status.reimbursements,
+                &mut self.payout_outputs,
+            )
+            .await;
+            new_status.payout_discrepancies = new_status
+                .reimbursements
+                .iter()
+                .filter_map(|reimbursement| {
+                    let payout_txid = reimbursement.payout_txid?;
+                    let outputs = self.payout_outputs.get(&payout_txid)?;
+                    let reason =
+                        verify_payout(outputs, &payout_addresses, config.denomination_sats())
+                            .err()?;
+                    Some(PayoutDiscrepancy {
+                        claim_txid: reimbursement.claim_txid,
+                        payout_txid,
+                        reason,
+                    })
+                })
+                .collect();
+            for discrepancy in &new_status.payout_discrepancies {
+                // Payouts are final, so the alert stays until acknowledged
+                alerts
+                    .raise(
+                        format!("bridge_payout_mismatch:{}", discrepancy.claim_txid),
+                        Severity::Critical,
+                        format!(
+                            "Payout {} of claim {} {}",
+                            discrepancy.payout_txid, discrepancy.claim_txid, discrepancy.reason
+                        ),
+                    )
+                    .await;
             }
+        }
+
+        // L1 fees
+        if let Some(esplora) = &self.esplora {
+            fetch_tx_fees(esplora, BridgeFees::txids(&new_status), &mut self.tx_fees).await;
+            new_status.fees = BridgeFees::new(&new_status, &self.tx_fees);
+        }
+
+        // Outstanding liability
+        let l2_supply_sats = match &self.l2_rpc {
+            Some(l2_rpc) => total_supply_sats(l2_rpc, &self.watched.bridge_contracts().await).await,
+            None => None,
         };
-        locked_state.reimbursements = reimbursements;
+        let liability =
+            BridgeLiability::new(&new_status, l2_supply_sats, config.supply_tolerance_sats());
+        if liability.mismatch {
+            alerts
+                .raise(
+                    "bridge_supply_mismatch".to_string(),
+                    Severity::Critical,
+                    liability.mismatch_message(),
+                )
+                .await;
+        } else if liability.supply_checked() {
+            alerts.resolve("bridge_supply_mismatch").await;
+        }
+        new_status.liability = liability;
+
+        // Old completed entries stay in the change log and the checkpoint, but are
+        // left out of the served state once the totals above are computed
+        if let Some(retention) = config.prune_completed_after() {
+            new_status.prune_deposits(&self.prunable_deposits(retention, Utc::now()));
+        }
+
+        new_status
     }
 }
 
 /// Fetch operator idx and public keys
-async fn get_bridge_operators(rpc_client: &HttpClient) -> Result<PublickeyTable, ClientError> {
-    let operator_table: PublickeyTable = match rpc_client
-        .request("stratabridge_bridgeOperators", ((),))
-        .await
-    {
+async fn get_bridge_operators(
+    rpc_client: &impl BridgeClient,
+) -> Result<PublickeyTable, ClientError> {
+    let operator_table: PublickeyTable = match rpc_client.bridge_operators().await {
         Ok(data) => data,
         Err(e) => {
             error!(error = %e, "Bridge operators query failed");
@@ -258,13 +1568,10 @@ async fn get_bridge_operators(rpc_client: &HttpClient) -> Result<PublickeyTable,
 
 /// Fetch operator status
 async fn get_operator_status(
-    bridge_client: &HttpClient,
+    bridge_client: &impl BridgeClient,
     operator_idx: u32,
 ) -> Result<String, ClientError> {
-    let status: RpcOperatorStatus = match bridge_client
-        .request("stratabridge_operatorStatus", (operator_idx,))
-        .await
-    {
+    let status: RpcOperatorStatus = match bridge_client.operator_status(operator_idx).await {
         Ok(data) => data,
         Err(e) => {
             error!(error = %e, "Bridge operator status query");
@@ -275,12 +1582,26 @@ async fn get_operator_status(
     Ok(format!("{:?}", status))
 }
 
+/// Fetch the number of unfulfilled duties assigned to an operator
+async fn get_operator_duty_count(
+    bridge_client: &impl BridgeClient,
+    operator_idx: u32,
+) -> Result<usize, ClientError> {
+    // Only the number of duties is needed, so skip decoding their payloads
+    let duties: Vec<Value> = match bridge_client.duties_by_operator(operator_idx).await {
+        Ok(data) => data,
+        Err(e) => {
+            error!(error = %e, %operator_idx, "Bridge duties query failed");
+            return Err(e);
+        }
+    };
+
+    Ok(duties.len())
+}
+
 /// Fetch current deposits
-async fn get_current_deposits(strata_client: &HttpClient) -> Result<Vec<u32>, ClientError> {
-    let deposit_ids: Vec<u32> = match strata_client
-        .request("strata_getCurrentDeposits", ((),))
-        .await
-    {
+async fn get_current_deposits(strata_client: &impl StrataClient) -> Result<Vec<u32>, ClientError> {
+    let deposit_ids: Vec<u32> = match strata_client.current_deposits().await {
         Ok(data) => data,
         Err(e) => {
             error!(error = %e, "Current deposits query failed");
@@ -296,14 +1617,11 @@ async fn get_current_deposits(strata_client: &HttpClient) -> Result<Vec<u32>, Cl
 /// First get deposit entry, which may have withdrawal request txid.
 /// Return DepositInfo and DepositToWithdrawal (needed to fetch withdrawals)
 async fn get_deposit_info(
-    strata_rpc: &HttpClient,
-    bridge_rpc: &HttpClient,
+    strata_rpc: &impl StrataClient,
+    bridge_rpc: &impl BridgeClient,
     deposit_id: u32,
 ) -> Result<(Option<DepositInfo>, Option<DepositToWithdrawal>), ClientError> {
-    let response: Value = match strata_rpc
-        .request("strata_getCurrentDepositById", (deposit_id,))
-        .await
-    {
+    let response: Value = match strata_rpc.deposit_entry(deposit_id).await {
         Ok(resp) => {
             info!(?resp, "deposit entry");
             resp
@@ -318,25 +1636,35 @@ async fn get_deposit_info(
     let deposit_outpoint: Option<OutPoint> = response
         .get("output")
         .and_then(|v| v.as_str())
-        .and_then(|s| OutPoint::from_str(s).ok());
+        .and_then(|s| upstream_outpoint("strata_getCurrentDepositById", s));
     // Extract withdrawal_request_txid
     let withdrawal_request_txid: Option<Txid> = response
         .get("withdrawal_request_txid")
         .and_then(|v| v.as_str())
-        .and_then(|s| Txid::from_str(s).ok());
+        .and_then(|s| upstream_txid("strata_getCurrentDepositById", s));
 
     // Let caller decide what to do
     if deposit_outpoint.is_none() {
         return Ok((None, None));
     }
 
+    // Extract deposited amount in sats
+    let amount_sats: Option<u64> = response.get("amt").and_then(|v| v.as_u64());
+
+    // Extract assignee of the withdrawal
+    let assignee: Option<u32> = response
+        .get("assignee")
+        .and_then(|v| v.as_u64())
+        .and_then(|idx| u32::try_from(idx).ok());
+
     let deposit_to_withdrawal = DepositToWithdrawal {
         deposit_outpoint: deposit_outpoint.unwrap(),
         withdrawal_request_txid,
+        assignee,
     };
 
     let deposit_info: RpcDepositInfo = match bridge_rpc
-        .request("stratabridge_depositInfo", (deposit_outpoint,))
+        .deposit_info(deposit_to_withdrawal.deposit_outpoint)
         .await
     {
         Ok(data) => data,
@@ -346,15 +1674,27 @@ async fn get_deposit_info(
         }
     };
 
-    Ok((
-        Some(DepositInfo::from(deposit_info)),
-        Some(deposit_to_withdrawal),
-    ))
+    let mut deposit_info = DepositInfo::from(deposit_info);
+    deposit_info.withdrawal_request_txid = deposit_to_withdrawal.withdrawal_request_txid;
+    deposit_info.amount_sats = amount_sats;
+
+    Ok((Some(deposit_info), Some(deposit_to_withdrawal)))
+}
+
+/// Check whether a pending DRT is in the mempool, confirmed or dropped
+async fn get_drt_status(esplora: &EsploraClient, drt_txid: &Txid) -> Option<DrtStatus> {
+    match esplora.tx_status(drt_txid).await {
+        Ok(status) => Some(DrtStatus::from_tx_status(status)),
+        Err(e) => {
+            warn!(error = %e, %drt_txid, "DRT status query failed");
+            None
+        }
+    }
 }
 
 /// Fetch withdrawal infos
 async fn get_withdrawals(
-    bridge_rpc: &HttpClient,
+    bridge_rpc: &impl BridgeClient,
     deposit_to_withdrawals: Vec<DepositToWithdrawal>,
 ) -> Result<Vec<WithdrawalInfo>, ClientError> {
     let mut withdrawal_infos = Vec::new();
@@ -364,10 +1704,7 @@ async fn get_withdrawals(
         }
 
         let wd_info: RpcWithdrawalInfo = match bridge_rpc
-            .request(
-                "stratabridge_withdrawalInfo",
-                (deposit_to_wd.deposit_outpoint,),
-            )
+            .withdrawal_info(deposit_to_wd.deposit_outpoint)
             .await
         {
             Ok(data) => data,
@@ -380,17 +1717,130 @@ async fn get_withdrawals(
         withdrawal_infos.push(WithdrawalInfo::from_rpc(
             &wd_info,
             deposit_to_wd.withdrawal_request_txid.unwrap(),
+            deposit_to_wd.assignee,
         ));
     }
 
     Ok(withdrawal_infos)
 }
 
+/// Fetch the payouts of fulfillment txs that are not cached yet
+async fn fetch_fulfillment_payouts(
+    esplora: &EsploraClient,
+    withdrawals: &[WithdrawalInfo],
+    fulfillment_payouts: &mut HashMap<Txid, FulfillmentPayout>,
+) {
+    for fulfillment_txid in withdrawals.iter().filter_map(|wd| wd.fulfillment_txid) {
+        if fulfillment_payouts.contains_key(&fulfillment_txid) {
+            continue;
+        }
+        match esplora.tx_outputs(&fulfillment_txid).await {
+            Ok(outputs) => {
+                fulfillment_payouts
+                    .insert(fulfillment_txid, FulfillmentPayout::from_outputs(&outputs));
+            }
+            Err(e) => warn!(error = %e, %fulfillment_txid, "Fulfillment tx query failed"),
+        }
+    }
+}
+
+/// Fetch the outputs of the reimbursement payout txs that are not cached yet
+async fn fetch_payout_outputs(
+    esplora: &EsploraClient,
+    reimbursements: &[ReimbursementInfo],
+    payout_outputs: &mut HashMap<Txid, Vec<TxOutput>>,
+) {
+    for payout_txid in reimbursements.iter().filter_map(|r| r.payout_txid) {
+        if payout_outputs.contains_key(&payout_txid) {
+            continue;
+        }
+        match esplora.tx_outputs(&payout_txid).await {
+            Ok(outputs) => {
+                payout_outputs.insert(payout_txid, outputs);
+            }
+            Err(e) => warn!(error = %e, %payout_txid, "Payout tx query failed"),
+        }
+    }
+}
+
+/// Fetch the confirmation times of txs that are not cached yet. Unconfirmed txs
+/// are retried on the next refresh.
+async fn fetch_confirmation_times(
+    esplora: &EsploraClient,
+    txids: Vec<Txid>,
+    confirmation_times: &mut HashMap<Txid, DateTime<Utc>>,
+) {
+    for txid in txids {
+        if confirmation_times.contains_key(&txid) {
+            continue;
+        }
+        match esplora.tx_status(&txid).await {
+            Ok(status) => {
+                if let Some(at) = status.as_ref().and_then(TxStatus::confirmed_at) {
+                    confirmation_times.insert(txid, at);
+                }
+            }
+            Err(e) => warn!(error = %e, %txid, "Tx status query failed"),
+        }
+    }
+}
+
+/// Fetch the fees of txs that are not cached yet. Txs unknown to Esplora, e.g.
+/// dropped DRTs, are retried on the next refresh.
+async fn fetch_tx_fees(
+    esplora: &EsploraClient,
+    txids: Vec<Txid>,
+    tx_fees: &mut HashMap<Txid, u64>,
+) {
+    for txid in txids {
+        if tx_fees.contains_key(&txid) {
+            continue;
+        }
+        match esplora.tx_fee(&txid).await {
+            Ok(fee) => {
+                tx_fees.insert(txid, fee);
+            }
+            Err(e) => warn!(error = %e, %txid, "Tx fee query failed"),
+        }
+    }
+}
+
+/// Sum the payouts of completed withdrawals per assigned operator
+fn front_payments(
+    withdrawals: &[WithdrawalInfo],
+    fulfillment_payouts: &HashMap<Txid, FulfillmentPayout>,
+) -> Vec<OperatorFrontPayments> {
+    let mut per_operator: BTreeMap<u32, OperatorFrontPayments> = BTreeMap::new();
+    for withdrawal in withdrawals {
+        let (Some(operator_idx), Some(payout)) = (
+            withdrawal.assignee,
+            withdrawal
+                .fulfillment_txid
+                .and_then(|txid| fulfillment_payouts.get(&txid)),
+        ) else {
+            continue;
+        };
+
+        let front_payments =
+            per_operator
+                .entry(operator_idx)
+                .or_insert_with(|| OperatorFrontPayments {
+                    operator_id: format!("Alpen Labs #{}", operator_idx),
+                    withdrawals: 0,
+                    total_fronted_sats: 0,
+                });
+        front_payments.withdrawals += 1;
+        front_payments.total_fronted_sats += payout.amount;
+    }
+
+    per_operator.into_values().collect()
+}
+
 /// Fetch claim/reimbursement infos
 async fn get_reimbursements(
-    bridge_rpc: &HttpClient,
+    bridge_rpc: &impl BridgeClient,
 ) -> Result<Vec<ReimbursementInfo>, ClientError> {
-    let claim_txids: Vec<String> = match bridge_rpc.request("stratabridge_claims", ((),)).await {
+    let claim_txids: Vec<String> = match bridge_rpc.claims().await {
         Ok(data) => data,
         Err(e) => {
             error!(error = %e, "Get claims failed");
@@ -400,10 +1850,10 @@ async fn get_reimbursements(
 
     let mut reimbursement_infos = Vec::new();
     for txid in claim_txids.iter() {
-        let reimb_info: RpcClaimInfo = match bridge_rpc
-            .request("stratabridge_claimInfo", (txid.clone(),))
-            .await
-        {
+        if upstream_txid("stratabridge_claims", txid).is_none() {
+            continue;
+        }
+        let reimb_info: RpcClaimInfo = match bridge_rpc.claim_info(txid.clone()).await {
             Ok(data) => data,
             Err(e) => {
                 error!(error = %e, "Get claim info failed");
@@ -418,7 +1868,676 @@ async fn get_reimbursements(
 }
 
 /// Return latest bridge status
-pub async fn get_bridge_status(state: SharedBridgeState) -> Json<BridgeStatus> {
-    let data = state.read().await.clone();
-    Json(data)
+/// Query parameters of the bridge status endpoint
+#[derive(Deserialize, Debug)]
+pub struct BridgeStatusQuery {
+    /// RFC 3339 time to reconstruct the bridge state at, the current state when unset
+    at: Option<DateTime<Utc>>,
+}
+
+pub async fn get_bridge_status(
+    Query(query): Query<BridgeStatusQuery>,
+    state: SharedBridgeState,
+    changes: SharedBridgeChanges,
+) -> Result<Response, StatusCode> {
+    let Some(at) = query.at else {
+        let data = state.read().await.clone();
+        return Ok(Json(data).into_response());
+    };
+    changes
+        .read()
+        .await
+        .status_at(at)
+        .map(|status| Json(status).into_response())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Deposit and the withdrawal linked to it
+#[derive(Serialize, Debug)]
+pub struct DepositLookup {
+    pub(crate) deposit: DepositInfo,
+    pub(crate) withdrawal: Option<WithdrawalInfo>,
+}
+
+/// Find the deposit whose deposit request txid or deposit txid is `txid`
+pub(crate) fn find_deposit(status: &BridgeStatus, txid: &Txid) -> Option<DepositLookup> {
+    let deposit = status.deposits.iter().find(|deposit| {
+        deposit.deposit_request_txid == *txid || deposit.deposit_txid == Some(*txid)
+    })?;
+    let withdrawal = deposit.withdrawal_request_txid.and_then(|request_txid| {
+        status
+            .withdrawals
+            .iter()
+            .find(|withdrawal| withdrawal.withdrawal_request_txid == request_txid)
+            .cloned()
+    });
+
+    Some(DepositLookup {
+        deposit: deposit.clone(),
+        withdrawal,
+    })
+}
+
+/// Return the deposit with a given deposit request txid or deposit txid
+pub async fn get_deposit_by_txid(
+    Path(txid): Path<String>,
+    state: SharedBridgeState,
+) -> Result<Json<DepositLookup>, StatusCode> {
+    let txid = parse_txid(&txid).map_err(|_| StatusCode::BAD_REQUEST)?;
+    find_deposit(&state.read().await, &txid)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Withdrawals recorded in `withdrawals` that paid out to `address`
+fn withdrawals_to_address(
+    withdrawals: &[WithdrawalInfo],
+    address: &Address<NetworkUnchecked>,
+) -> Vec<WithdrawalInfo> {
+    withdrawals
+        .iter()
+        .filter(|withdrawal| {
+            withdrawal
+                .recipient_address
+                .as_deref()
+                .and_then(|recipient| Address::from_str(recipient).ok())
+                .is_some_and(|recipient| recipient == *address)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Return fulfilled withdrawals that paid out to a bitcoin address
+pub async fn get_withdrawals_by_address(
+    Path(address): Path<String>,
+    state: SharedBridgeState,
+) -> Result<Json<Vec<WithdrawalInfo>>, StatusCode> {
+    let address = Address::from_str(&address).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let state = state.read().await;
+    Ok(Json(withdrawals_to_address(&state.withdrawals, &address)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        deposit_failure_counts, find_deposit, pending_deposit_ids, refresh_once, verify_payout,
+        withdrawals_to_address, BridgeFees, BridgeMonitor, BridgeStatus, DepositAgeBuckets,
+        DepositInfo, DepositStatus, DepositToWithdrawal, DrtStatus, FulfillmentPayout,
+        KnownDeposit, OperatorResponsiveness, ResponsivenessRating, WithdrawalInfo,
+        WithdrawalStatus,
+    };
+    use crate::{
+        alerts::Alerts,
+        clients::fakes::{FakeBridgeClient, FakeStrataClient},
+        config::BridgeMonitoringConfig,
+        heartbeats::OperatorLiveness,
+        l1::{TxOutput, TxStatus},
+    };
+    use bitcoin::{secp256k1::PublicKey, Address, OutPoint, Txid};
+    use serde_json::{json, Value};
+    use std::{
+        collections::{BTreeMap, HashMap, HashSet, VecDeque},
+        path::Path,
+        str::FromStr,
+    };
+    use strata_bridge_primitives::types::PublickeyTable;
+    use strata_bridge_rpc::types::{RpcOperatorStatus, RpcReimbursementStatus};
+
+    #[test]
+    fn test_fulfillment_payout_skips_metadata() {
+        let output = |scriptpubkey_type: &str, address: Option<&str>, value| TxOutput {
+            scriptpubkey_type: scriptpubkey_type.to_string(),
+            scriptpubkey_address: address.map(str::to_string),
+            value,
+        };
+        let outputs = vec![
+            output("op_return", None, 0),
+            output("v1_p2tr", Some("bc1user"), 100_000),
+            output("v1_p2tr", Some("bc1change"), 5_000),
+        ];
+        assert_eq!(
+            FulfillmentPayout::from_outputs(&outputs),
+            FulfillmentPayout {
+                address: Some("bc1user".to_string()),
+                amount: 100_000
+            }
+        );
+        assert_eq!(FulfillmentPayout::from_outputs(&[]).amount, 0);
+    }
+
+    #[test]
+    fn test_verify_payout() {
+        let output = |address: &str, value| TxOutput {
+            scriptpubkey_type: "v1_p2tr".to_string(),
+            scriptpubkey_address: Some(address.to_string()),
+            value,
+        };
+        let operators = ["bc1operator0", "bc1operator1"];
+
+        let payout = vec![output("bc1operator1", 999_000_000)];
+        assert!(verify_payout(&payout, &operators, Some(1_000_000_000)).is_ok());
+        assert!(verify_payout(&payout, &operators, None).is_ok());
+        assert_eq!(
+            verify_payout(&payout, &operators, Some(500_000_000)),
+            Err(
+                "pays 999000000 sats to operators, more than the 500000000 sats denomination"
+                    .to_string()
+            )
+        );
+        let elsewhere = vec![output("bc1thief", 999_000_000)];
+        assert!(verify_payout(&elsewhere, &operators, Some(1_000_000_000)).is_err());
+    }
+
+    #[test]
+    fn test_find_deposit_by_either_txid() {
+        let txid = |byte: &str| Txid::from_str(&byte.repeat(32)).unwrap();
+        let deposit = |drt: &str, dt: Option<&str>, wrt: Option<&str>| DepositInfo {
+            deposit_request_txid: txid(drt),
+            deposit_txid: dt.map(txid),
+            status: DepositStatus::Complete,
+            drt_status: None,
+            withdrawal_request_txid: wrt.map(txid),
+            amount_sats: None,
+            confirmed_at: None,
+            failure_reason: None,
+        };
+        let status = BridgeStatus {
+            deposits: vec![
+                deposit("01", Some("02"), Some("03")),
+                deposit("04", None, None),
+            ],
+            withdrawals: vec![WithdrawalInfo {
+                withdrawal_request_txid: txid("03"),
+                fulfillment_txid: None,
+                status: WithdrawalStatus::InProgress,
+                assignee: None,
+                recipient_address: None,
+                amount_sats: None,
+                fulfilled_at: None,
+            }],
+            ..Default::default()
+        };
+
+        let by_drt = find_deposit(&status, &txid("01")).unwrap();
+        let by_dt = find_deposit(&status, &txid("02")).unwrap();
+        assert_eq!(
+            by_drt.deposit.deposit_request_txid,
+            by_dt.deposit.deposit_request_txid
+        );
+        assert_eq!(
+            by_dt.withdrawal.map(|wd| wd.withdrawal_request_txid),
+            Some(txid("03"))
+        );
+
+        assert!(find_deposit(&status, &txid("04"))
+            .unwrap()
+            .withdrawal
+            .is_none());
+        // Withdrawal request txids do not resolve to deposits
+        assert!(find_deposit(&status, &txid("03")).is_none());
+    }
+
+    #[test]
+    fn test_withdrawals_to_address() {
+        let withdrawal = |recipient_address: Option<&str>| WithdrawalInfo {
+            withdrawal_request_txid: Txid::from_str(
+                "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+            )
+            .unwrap(),
+            fulfillment_txid: None,
+            status: WithdrawalStatus::Complete,
+            assignee: Some(0),
+            recipient_address: recipient_address.map(str::to_string),
+            amount_sats: None,
+            fulfilled_at: None,
+        };
+        let withdrawals = vec![
+            withdrawal(Some("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")),
+            withdrawal(Some("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")),
+            withdrawal(None),
+        ];
+
+        // Bech32 addresses are case-insensitive
+        let address = Address::from_str("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        let found = withdrawals_to_address(&withdrawals, &address);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].recipient_address, withdrawals[0].recipient_address);
+    }
+
+    #[test]
+    fn test_drt_status_from_tx_status() {
+        let confirmed = TxStatus {
+            confirmed: true,
+            block_height: Some(100),
+            block_time: None,
+        };
+        let unconfirmed = TxStatus {
+            confirmed: false,
+            block_height: None,
+            block_time: None,
+        };
+        assert_eq!(
+            DrtStatus::from_tx_status(Some(confirmed)),
+            DrtStatus::Confirmed
+        );
+        assert_eq!(
+            DrtStatus::from_tx_status(Some(unconfirmed)),
+            DrtStatus::Mempool
+        );
+        assert_eq!(DrtStatus::from_tx_status(None), DrtStatus::Dropped);
+    }
+
+    #[test]
+    fn test_operator_responsiveness_rating() {
+        let healthy = VecDeque::from(vec![Some(100), Some(300), Some(200)]);
+        let responsiveness = OperatorResponsiveness::from_samples(&healthy, 1000);
+        assert_eq!(responsiveness.rating, ResponsivenessRating::Healthy);
+        assert_eq!(responsiveness.avg_latency_ms, Some(200));
+        assert_eq!(responsiveness.last_latency_ms, Some(200));
+        assert_eq!(responsiveness.success_rate, 1.0);
+
+        let slow = VecDeque::from(vec![Some(1500), None, Some(2500)]);
+        let responsiveness = OperatorResponsiveness::from_samples(&slow, 1000);
+        assert_eq!(responsiveness.rating, ResponsivenessRating::Slow);
+
+        let failing = VecDeque::from(vec![Some(100), Some(100), None]);
+        let responsiveness = OperatorResponsiveness::from_samples(&failing, 1000);
+        assert_eq!(responsiveness.rating, ResponsivenessRating::Unresponsive);
+        assert_eq!(responsiveness.last_latency_ms, None);
+
+        let empty = VecDeque::new();
+        let responsiveness = OperatorResponsiveness::from_samples(&empty, 1000);
+        assert_eq!(responsiveness.rating, ResponsivenessRating::Unresponsive);
+    }
+
+    #[test]
+    fn test_pending_deposit_ids() {
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        let known = |status: DepositStatus, withdrawal: Option<WithdrawalStatus>| KnownDeposit {
+            deposit: DepositInfo {
+                deposit_request_txid: txid,
+                deposit_txid: None,
+                status,
+                drt_status: None,
+                withdrawal_request_txid: withdrawal.as_ref().map(|_| txid),
+                amount_sats: None,
+                confirmed_at: None,
+                failure_reason: None,
+            },
+            link: DepositToWithdrawal {
+                deposit_outpoint: OutPoint::new(txid, 0),
+                withdrawal_request_txid: withdrawal.as_ref().map(|_| txid),
+                assignee: None,
+            },
+            withdrawal: withdrawal.map(|status| WithdrawalInfo {
+                withdrawal_request_txid: txid,
+                fulfillment_txid: None,
+                status,
+                assignee: None,
+                recipient_address: None,
+                amount_sats: None,
+                fulfilled_at: None,
+            }),
+            finalized_at: None,
+            first_seen_at: None,
+            tombstoned_at: None,
+        };
+        let deposits = BTreeMap::from([
+            (
+                0,
+                known(DepositStatus::Complete, Some(WithdrawalStatus::Complete)),
+            ),
+            (
+                1,
+                known(DepositStatus::Complete, Some(WithdrawalStatus::InProgress)),
+            ),
+            (2, known(DepositStatus::Complete, None)),
+            (3, known(DepositStatus::Failed, None)),
+            (4, known(DepositStatus::InProgress, None)),
+        ]);
+
+        assert_eq!(pending_deposit_ids(&deposits), vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_deposit_age_buckets() {
+        let mut buckets = DepositAgeBuckets::default();
+        for minutes in [30, 59, 60, 5 * 60, 6 * 60, 23 * 60, 24 * 60, 30 * 24 * 60] {
+            buckets.add(chrono::Duration::minutes(minutes));
+        }
+        assert_eq!(
+            buckets,
+            DepositAgeBuckets {
+                under_1h: 2,
+                from_1h_to_6h: 2,
+                from_6h_to_24h: 2,
+                over_24h: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_deposit_failure_counts() {
+        let txid = Txid::from_str(&"01".repeat(32)).unwrap();
+        let deposit = |status, failure_reason: Option<&str>| DepositInfo {
+            deposit_request_txid: txid,
+            deposit_txid: None,
+            status,
+            drt_status: None,
+            withdrawal_request_txid: None,
+            amount_sats: None,
+            confirmed_at: None,
+            failure_reason: failure_reason.map(str::to_string),
+        };
+        let deposits = [
+            deposit(DepositStatus::Failed, Some("invalid deposit request")),
+            deposit(DepositStatus::Failed, Some("invalid deposit request")),
+            deposit(DepositStatus::Failed, Some("insufficient amount")),
+            deposit(DepositStatus::Failed, None),
+            deposit(DepositStatus::Complete, None),
+        ];
+
+        let counts = deposit_failure_counts(&deposits);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts["invalid deposit request"], 2);
+        assert_eq!(counts["insufficient amount"], 1);
+        assert_eq!(counts["unknown"], 1);
+    }
+
+    #[test]
+    fn test_bridge_fees() {
+        let txid = |byte: &str| Txid::from_str(&byte.repeat(32)).unwrap();
+        let status = BridgeStatus {
+            deposits: vec![DepositInfo {
+                deposit_request_txid: txid("01"),
+                deposit_txid: Some(txid("02")),
+                status: DepositStatus::Complete,
+                drt_status: None,
+                withdrawal_request_txid: Some(txid("03")),
+                amount_sats: None,
+                confirmed_at: None,
+                failure_reason: None,
+            }],
+            withdrawals: vec![WithdrawalInfo {
+                withdrawal_request_txid: txid("03"),
+                fulfillment_txid: Some(txid("04")),
+                status: WithdrawalStatus::Complete,
+                assignee: Some(0),
+                recipient_address: None,
+                amount_sats: None,
+                fulfilled_at: None,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            BridgeFees::txids(&status),
+            vec![txid("01"), txid("02"), txid("04")]
+        );
+
+        // The deposit tx fee is not fetched yet
+        let tx_fees = HashMap::from([(txid("01"), 300), (txid("04"), 150)]);
+        let fees = BridgeFees::new(&status, &tx_fees);
+        assert_eq!(fees.deposits[0].drt_fee_sats, Some(300));
+        assert_eq!(fees.deposits[0].deposit_tx_fee_sats, None);
+        assert_eq!(fees.withdrawals[0].fulfillment_fee_sats, Some(150));
+        assert_eq!(fees.total_fees_sats, 450);
+    }
+
+    #[test]
+    fn test_prune_deposits() {
+        let txid = |byte: &str| Txid::from_str(&byte.repeat(32)).unwrap();
+        let deposit = |drt: &str, wrt: Option<&str>| DepositInfo {
+            deposit_request_txid: txid(drt),
+            deposit_txid: None,
+            status: DepositStatus::Complete,
+            drt_status: None,
+            withdrawal_request_txid: wrt.map(txid),
+            amount_sats: None,
+            confirmed_at: None,
+            failure_reason: None,
+        };
+        let withdrawal = |wrt: &str| WithdrawalInfo {
+            withdrawal_request_txid: txid(wrt),
+            fulfillment_txid: None,
+            status: WithdrawalStatus::Complete,
+            assignee: None,
+            recipient_address: None,
+            amount_sats: None,
+            fulfilled_at: None,
+        };
+        let mut status = BridgeStatus {
+            deposits: vec![deposit("01", Some("02")), deposit("03", Some("04"))],
+            withdrawals: vec![withdrawal("02"), withdrawal("04")],
+            ..Default::default()
+        };
+        status.fees = BridgeFees::new(&status, &HashMap::from([(txid("01"), 300)]));
+
+        status.prune_deposits(&HashSet::from([txid("01")]));
+        assert_eq!(status.deposits.len(), 1);
+        assert_eq!(status.deposits[0].deposit_request_txid, txid("03"));
+        assert_eq!(status.withdrawals.len(), 1);
+        assert_eq!(status.withdrawals[0].withdrawal_request_txid, txid("04"));
+        assert_eq!(status.fees.deposits.len(), 1);
+        assert_eq!(status.fees.withdrawals.len(), 1);
+        // Totals still count the pruned deposit
+        assert_eq!(status.fees.total_fees_sats, 300);
+        assert_eq!(status.pruned_deposits, 1);
+    }
+
+    #[test]
+    fn test_operator_metadata_by_index() {
+        let operators: BTreeMap<u32, OperatorMetadata> = serde_json::from_value(json!({
+            "0": { "region": "eu-west", "contact": "ops@example.com" },
+        }))
+        .unwrap();
+        assert_eq!(
+            operators[&0],
+            OperatorMetadata {
+                region: Some("eu-west".to_string()),
+                url: None,
+                contact: Some("ops@example.com".to_string()),
+                payout_address: None,
+            }
+        );
+        assert!(serde_json::from_value::<OperatorMetadata>(json!({ "owner": "x" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_with_fake_clients() {
+        let public_key = PublicKey::from_str(
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        )
+        .unwrap();
+        let mut bridge = FakeBridgeClient::new(PublickeyTable(BTreeMap::from([
+            (0, public_key),
+            (1, public_key),
+        ])));
+        // Operator 1 is unknown to the fake, so its queries fail
+        bridge
+            .operator_statuses
+            .insert(0, RpcOperatorStatus::Online);
+        bridge.duties.insert(0, vec![json!({}); 12]);
+
+        let config = BridgeMonitoringConfig::new();
+        let alerts = Alerts::default();
+        let mut monitor = BridgeMonitor::new(FakeStrataClient::default(), bridge, None, None);
+        let status = monitor.refresh(&alerts, &config).await;
+
+        assert_eq!(status.operators.len(), 2);
+        assert_eq!(status.operators[0].status, "Online");
+        assert_eq!(status.operators[0].duty_queue_depth, Some(12));
+        assert_eq!(status.operators[1].status, "Unknown");
+        assert_eq!(
+            status.operators[1].responsiveness.rating,
+            ResponsivenessRating::Unresponsive
+        );
+        assert!(status.deposits.is_empty() && status.reimbursements.is_empty());
+
+        let active = alerts.active().await;
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, "bridge_duty_backlog:0");
+
+        // Without heartbeats, the status is polled on every cycle
+        monitor.bridge_rpc.operator_statuses.clear();
+        let status = monitor.refresh(&alerts, &config).await;
+        assert_eq!(status.operators[0].status, "Unknown");
+        assert_eq!(status.operators[0].liveness, OperatorLiveness::Down);
+
+        // With heartbeats, it is reused while the duties are unchanged...
+        monitor.heartbeats.record(0, chrono::Utc::now()).await;
+        monitor
+            .bridge_rpc
+            .operator_statuses
+            .insert(0, RpcOperatorStatus::Online);
+        monitor.refresh(&alerts, &config).await;
+        monitor.bridge_rpc.operator_statuses.clear();
+        let status = monitor.refresh(&alerts, &config).await;
+        assert_eq!(status.operators[0].status, "Online");
+        assert_eq!(status.operators[0].liveness, OperatorLiveness::Stale);
+        // ...and polled again once they change
+        monitor.bridge_rpc.duties.insert(0, vec![json!({}); 3]);
+        let status = monitor.refresh(&alerts, &config).await;
+        assert_eq!(status.operators[0].status, "Unknown");
+        assert_eq!(
+            status.operators[0].liveness,
+            OperatorLiveness::RpcUnreachable
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_operator_is_down_despite_cached_status() {
+        let public_key = PublicKey::from_str(
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        )
+        .unwrap();
+        let mut bridge = FakeBridgeClient::new(PublickeyTable(BTreeMap::from([(0, public_key)])));
+        bridge
+            .operator_statuses
+            .insert(0, RpcOperatorStatus::Online);
+        bridge.duties.insert(0, Vec::new());
+
+        let config = BridgeMonitoringConfig::new();
+        let alerts = Alerts::default();
+        let mut monitor = BridgeMonitor::new(FakeStrataClient::default(), bridge, None, None);
+        // The last heartbeat is too old to count the operator as up
+        monitor
+            .heartbeats
+            .record(0, chrono::Utc::now() - chrono::Duration::days(1))
+            .await;
+        let status = monitor.refresh(&alerts, &config).await;
+        assert_eq!(status.operators[0].liveness, OperatorLiveness::Up);
+
+        // Unreachable with unchanged duties, while its status is cached
+        monitor.bridge_rpc.operator_statuses.clear();
+        let status = monitor.refresh(&alerts, &config).await;
+        assert_eq!(status.operators[0].status, "Unknown");
+        assert_eq!(status.operators[0].liveness, OperatorLiveness::Down);
+    }
+
+    #[tokio::test]
+    async fn test_vanished_entries_are_tombstoned() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../mock_rpc/mock_data");
+        let config = BridgeMonitoringConfig::new();
+        let alerts = Alerts::default();
+        let mut monitor = BridgeMonitor::new(
+            FakeStrataClient::from_fixtures(&fixtures.join("strata_rpc")),
+            FakeBridgeClient::from_fixtures(&fixtures.join("bridge_rpc")),
+            None,
+            None,
+        );
+        let status = monitor.refresh(&alerts, &config).await;
+        assert!(status.tombstoned_deposits.is_empty());
+        assert!(status.tombstoned_reimbursements.is_empty());
+        let deposits = status.deposits.len();
+
+        // Deposit 4 failed, deposit 7 is in progress
+        monitor.strata_rpc.deposit_entries.remove(&4);
+        monitor.strata_rpc.deposit_entries.remove(&7);
+        let claim_txid = monitor
+            .bridge_rpc
+            .claim_infos
+            .iter()
+            .find(|(_, claim)| matches!(claim.status, RpcReimbursementStatus::Cancelled))
+            .map(|(claim_txid, _)| claim_txid.clone())
+            .unwrap();
+        monitor.bridge_rpc.claim_infos.remove(&claim_txid);
+        monitor.last_full_resync = None;
+        let status = monitor.refresh(&alerts, &config).await;
+
+        assert_eq!(status.deposits.len(), deposits - 2);
+        let tombstoned: Vec<(DepositStatus, bool)> = status
+            .tombstoned_deposits
+            .iter()
+            .map(|tombstoned| (tombstoned.entry.status.clone(), tombstoned.was_active))
+            .collect();
+        assert_eq!(
+            tombstoned,
+            [
+                (DepositStatus::Failed, false),
+                (DepositStatus::InProgress, true)
+            ]
+        );
+        assert_eq!(status.tombstoned_reimbursements.len(), 1);
+        assert!(!status.tombstoned_reimbursements[0].was_active);
+        // Only the deposit in progress vanished unexpectedly
+        let vanished = alerts
+            .active()
+            .await
+            .into_iter()
+            .find(|alert| alert.id == "bridge_vanished_entries")
+            .unwrap();
+        assert!(vanished.message.starts_with("1 active"));
+
+        // A deposit returned again is no longer tombstoned
+        monitor.strata_rpc = FakeStrataClient::from_fixtures(&fixtures.join("strata_rpc"));
+        monitor.last_full_resync = None;
+        let status = monitor.refresh(&alerts, &config).await;
+        assert_eq!(status.deposits.len(), deposits);
+        assert!(status.tombstoned_deposits.is_empty());
+        assert_eq!(status.tombstoned_reimbursements.len(), 1);
+    }
+
+    /// Golden file of the status built from the `mock_rpc` fixtures. Set
+    /// `UPDATE_GOLDEN=1` to rewrite it after an intended change.
+    #[tokio::test]
+    async fn test_dry_run_matches_golden_file() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let fixtures = manifest_dir.join("../mock_rpc/mock_data");
+        let golden_path = manifest_dir.join("testdata/bridge_status.golden.json");
+
+        let status = refresh_once(
+            FakeStrataClient::from_fixtures(&fixtures.join("strata_rpc")),
+            FakeBridgeClient::from_fixtures(&fixtures.join("bridge_rpc")),
+            None,
+            None,
+            &BridgeMonitoringConfig::new(),
+        )
+        .await;
+        let mut status = serde_json::to_value(&status).unwrap();
+        // Poll latencies differ from run to run
+        for operator in status["operators"].as_array_mut().unwrap() {
+            operator["responsiveness"] = Value::Null;
+        }
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let mut data = serde_json::to_string_pretty(&status).unwrap();
+            data.push('\n');
+            std::fs::write(&golden_path, data).unwrap();
+            return;
+        }
+        let golden = std::fs::read_to_string(&golden_path).unwrap_or_else(|e| {
+            panic!(
+                "Failed to read {}, set UPDATE_GOLDEN=1 to create it: {}",
+                golden_path.display(),
+                e
+            )
+        });
+        let golden: Value = serde_json::from_str(&golden).unwrap();
+        assert!(
+            status == golden,
+            "Bridge status differs from {}, set UPDATE_GOLDEN=1 if intended:\n{}",
+            golden_path.display(),
+            serde_json::to_string_pretty(&status).unwrap()
+        );
+    }
 }
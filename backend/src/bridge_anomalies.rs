@@ -0,0 +1,120 @@
+//! Bridge entries breaking the expectations of the bridge protocol, flagged at
+//! `/api/bridge/anomalies` to catch malformed or unexpected deposits and payouts
+//! early.
+
+use axum::Json;
+use bitcoin::Txid;
+use serde::Serialize;
+
+use crate::{
+    bridge::{BridgeStatus, PayoutDiscrepancy, SharedBridgeState},
+    txid_format,
+};
+
+/// Entry breaking an expectation of the bridge protocol
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BridgeAnomaly {
+    /// Deposit whose amount differs from the fixed bridge denomination
+    DenominationMismatch {
+        #[serde(with = "txid_format::txid")]
+        deposit_request_txid: Txid,
+        #[serde(with = "txid_format::option_txid")]
+        deposit_txid: Option<Txid>,
+        expected_sats: u64,
+        amount_sats: u64,
+    },
+    /// Reimbursement payout not paying an operator as expected
+    PayoutMismatch(PayoutDiscrepancy),
+}
+
+/// Anomalies of the current bridge state. Deposit amounts are only checked
+/// against a known denomination; deposits of unknown amount are skipped.
+fn find_anomalies(status: &BridgeStatus, denomination_sats: Option<u64>) -> Vec<BridgeAnomaly> {
+    let payouts = status
+        .payout_discrepancies
+        .iter()
+        .cloned()
+        .map(BridgeAnomaly::PayoutMismatch);
+    let Some(expected_sats) = denomination_sats else {
+        return payouts.collect();
+    };
+    status
+        .deposits
+        .iter()
+        .filter_map(|deposit| {
+            let amount_sats = deposit
+                .amount_sats
+                .filter(|amount| *amount != expected_sats)?;
+            Some(BridgeAnomaly::DenominationMismatch {
+                deposit_request_txid: deposit.deposit_request_txid,
+                deposit_txid: deposit.deposit_txid,
+                expected_sats,
+                amount_sats,
+            })
+        })
+        .chain(payouts)
+        .collect()
+}
+
+/// Bridge anomalies passed to dashboard
+#[derive(Serialize, Debug)]
+pub struct BridgeAnomaliesResponse {
+    /// See `BRIDGE_DENOMINATION_SATS`, deposit amounts are not checked when unset
+    denomination_sats: Option<u64>,
+    anomalies: Vec<BridgeAnomaly>,
+}
+
+/// Return the anomalies of the current bridge state
+pub async fn get_bridge_anomalies(
+    state: SharedBridgeState,
+    denomination_sats: Option<u64>,
+) -> Json<BridgeAnomaliesResponse> {
+    let anomalies = find_anomalies(&state.read().await, denomination_sats);
+    Json(BridgeAnomaliesResponse {
+        denomination_sats,
+        anomalies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_anomalies, BridgeAnomaly};
+    use crate::bridge::{BridgeStatus, DepositInfo, DepositStatus};
+    use bitcoin::Txid;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_denomination_mismatch() {
+        let txid = |byte: &str| Txid::from_str(&byte.repeat(32)).unwrap();
+        let deposit = |byte, amount_sats| DepositInfo {
+            deposit_request_txid: txid(byte),
+            deposit_txid: None,
+            status: DepositStatus::InProgress,
+            drt_status: None,
+            withdrawal_request_txid: None,
+            amount_sats,
+            confirmed_at: None,
+            failure_reason: None,
+        };
+        let status = BridgeStatus {
+            deposits: vec![
+                deposit("01", Some(1_000_000_000)),
+                deposit("02", Some(999_000_000)),
+                deposit("03", None),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            find_anomalies(&status, Some(1_000_000_000)),
+            vec![BridgeAnomaly::DenominationMismatch {
+                deposit_request_txid: txid("02"),
+                deposit_txid: None,
+                expected_sats: 1_000_000_000,
+                amount_sats: 999_000_000,
+            }]
+        );
+        assert!(find_anomalies(&status, None).is_empty());
+    }
+}
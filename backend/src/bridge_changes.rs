@@ -0,0 +1,785 @@
+use axum::{
+    extract::{Path, Query},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bitcoin::Txid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    sync::{Notify, RwLock},
+    time::timeout,
+};
+
+use crate::{
+    bridge::{
+        deposit_failure_counts, BridgeStatus, DepositInfo, OperatorStatus, ReimbursementInfo,
+        Tombstoned, WithdrawalInfo, WithdrawalStatus,
+    },
+    sqlite::SqliteDb,
+    store::{open_store, MemoryStore, StatsStore, Timestamped},
+    txid_format::{self, format_txid, parse_txid},
+    utils::{parse_long_poll_wait, status_label, to_csv, txid_field},
+};
+
+/// Max number of changes kept; older cursors require a full refetch
+const MAX_CHANGE_RECORDS: usize = 10_000;
+
+/// Timeline status of a deposit no longer returned by the bridge
+const TOMBSTONED_STATUS: &str = "Tombstoned";
+
+/// Withdrawal as observed through the change log
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObservedWithdrawal {
+    /// First time the withdrawal was recorded, up to a refresh after its request
+    pub requested_at: DateTime<Utc>,
+    /// Confirmation time of the fulfillment, or when it was first recorded complete
+    pub fulfilled_at: Option<DateTime<Utc>>,
+}
+
+/// Bridge entry that was added, whose status changed or that vanished
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", content = "entry", rename_all = "lowercase")]
+pub enum BridgeChange {
+    Operator(OperatorStatus),
+    Deposit(DepositInfo),
+    Withdrawal(WithdrawalInfo),
+    Reimbursement(ReimbursementInfo),
+    /// Deposit no longer returned by the bridge, until it is recorded again
+    #[serde(rename = "deposit_tombstone")]
+    DepositTombstone(Tombstoned<DepositInfo>),
+    /// Claim no longer returned by the bridge, until it is recorded again
+    #[serde(rename = "reimbursement_tombstone")]
+    ReimbursementTombstone(Tombstoned<ReimbursementInfo>),
+}
+
+/// A change and the cursor it was recorded at
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChangeRecord {
+    cursor: u64,
+    at: DateTime<Utc>,
+    #[serde(flatten)]
+    change: BridgeChange,
+}
+
+impl Timestamped for ChangeRecord {
+    fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+}
+
+/// Entries of `new` that are not in `old` or whose state differs
+fn changed<'a, T, K: PartialEq, S: PartialEq>(
+    old: &[T],
+    new: &'a [T],
+    key: impl Fn(&T) -> K,
+    state: impl Fn(&T) -> S,
+) -> impl Iterator<Item = &'a T> {
+    let old: Vec<(K, S)> = old.iter().map(|entry| (key(entry), state(entry))).collect();
+    new.iter().filter(move |entry| {
+        let (entry_key, entry_state) = (key(entry), state(entry));
+        !old.iter()
+            .any(|(k, s)| *k == entry_key && *s == entry_state)
+    })
+}
+
+/// Changes between two consecutive bridge states.
+///
+/// Operator responsiveness and duty queue depth change on every poll and are not tracked.
+pub fn diff_bridge_status(old: &BridgeStatus, new: &BridgeStatus) -> Vec<BridgeChange> {
+    let operators = changed(
+        &old.operators,
+        &new.operators,
+        |op| op.operator_id.clone(),
+        |op| op.status.clone(),
+    )
+    .cloned()
+    .map(BridgeChange::Operator);
+    let deposits = changed(
+        &old.deposits,
+        &new.deposits,
+        |deposit| deposit.deposit_request_txid,
+        |deposit| {
+            (
+                deposit.status.clone(),
+                deposit.deposit_txid,
+                deposit.drt_status.clone(),
+                deposit.withdrawal_request_txid,
+            )
+        },
+    )
+    .cloned()
+    .map(BridgeChange::Deposit);
+    let withdrawals = changed(
+        &old.withdrawals,
+        &new.withdrawals,
+        |withdrawal| withdrawal.withdrawal_request_txid,
+        |withdrawal| (withdrawal.status.clone(), withdrawal.fulfillment_txid),
+    )
+    .cloned()
+    .map(BridgeChange::Withdrawal);
+    let reimbursements = changed(
+        &old.reimbursements,
+        &new.reimbursements,
+        |reimbursement| reimbursement.claim_txid,
+        |reimbursement| {
+            (
+                reimbursement.status.clone(),
+                reimbursement.challenge_step.clone(),
+                reimbursement.payout_txid,
+            )
+        },
+    )
+    .cloned()
+    .map(BridgeChange::Reimbursement);
+    let deposit_tombstones = changed(
+        &old.tombstoned_deposits,
+        &new.tombstoned_deposits,
+        |tombstoned| tombstoned.entry.deposit_request_txid,
+        |tombstoned| tombstoned.tombstoned_at,
+    )
+    .cloned()
+    .map(BridgeChange::DepositTombstone);
+    let reimbursement_tombstones = changed(
+        &old.tombstoned_reimbursements,
+        &new.tombstoned_reimbursements,
+        |tombstoned| tombstoned.entry.claim_txid,
+        |tombstoned| tombstoned.tombstoned_at,
+    )
+    .cloned()
+    .map(BridgeChange::ReimbursementTombstone);
+
+    operators
+        .chain(deposits)
+        .chain(withdrawals)
+        .chain(reimbursements)
+        .chain(deposit_tombstones)
+        .chain(reimbursement_tombstones)
+        .collect()
+}
+
+/// Recorded status transition of a deposit or of its withdrawal
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TimelineEntry {
+    at: DateTime<Utc>,
+    /// `deposit` or `withdrawal`
+    kind: &'static str,
+    /// Deposit request or withdrawal request txid
+    #[serde(with = "txid_format::txid")]
+    txid: Txid,
+    status: String,
+    /// Bitcoin status of the DRT, for deposits in progress
+    drt_status: Option<String>,
+    /// Deposit or fulfillment txid, once known
+    #[serde(with = "txid_format::option_txid")]
+    linked_txid: Option<Txid>,
+}
+
+impl TimelineEntry {
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.at.to_rfc3339(),
+            self.kind.to_string(),
+            format_txid(&self.txid),
+            self.status.clone(),
+            self.drt_status.clone().unwrap_or_default(),
+            txid_field(&self.linked_txid),
+        ]
+    }
+}
+
+/// Replaces the entry with the same key, or appends it
+fn upsert<T, K: PartialEq>(entries: &mut Vec<T>, entry: T, key: impl Fn(&T) -> K) {
+    match entries
+        .iter_mut()
+        .find(|existing| key(existing) == key(&entry))
+    {
+        Some(existing) => *existing = entry,
+        None => entries.push(entry),
+    }
+}
+
+/// Bounded log of bridge changes, addressed by monotonically increasing cursors.
+///
+/// The changes are persisted to the `bridge_changes` table of the SQLite database
+/// or to the JSON lines file at the configured path, and reloaded on startup.
+#[derive(Debug)]
+pub struct BridgeChangeLog {
+    next_cursor: u64,
+    store: Box<dyn StatsStore<ChangeRecord>>,
+    /// Wakes up the long-polling requests when changes are recorded
+    recorded: Arc<Notify>,
+}
+
+impl Default for BridgeChangeLog {
+    fn default() -> Self {
+        Self {
+            next_cursor: 0,
+            store: Box::new(MemoryStore::new(MAX_CHANGE_RECORDS)),
+            recorded: Arc::default(),
+        }
+    }
+}
+
+impl BridgeChangeLog {
+    /// Creates the log, loading the most recent changes persisted in `sqlite` or
+    /// else at `path`.
+    ///
+    /// Up to `write_buffer` changes wait to be persisted before new ones are dropped.
+    pub fn load(sqlite: Option<&SqliteDb>, path: Option<String>, write_buffer: usize) -> Self {
+        let mut store = open_store(
+            sqlite,
+            "bridge_changes",
+            path,
+            DateTime::<Utc>::MIN_UTC,
+            write_buffer,
+            MAX_CHANGE_RECORDS,
+        );
+        store.truncate(MAX_CHANGE_RECORDS);
+        store.compact();
+        let next_cursor = store.records().last().map_or(0, |record| record.cursor + 1);
+        Self {
+            next_cursor,
+            store,
+            recorded: Arc::default(),
+        }
+    }
+
+    /// Appends changes observed at `at`
+    pub fn record(&mut self, changes: Vec<BridgeChange>, at: DateTime<Utc>) {
+        for change in changes {
+            let record = ChangeRecord {
+                cursor: self.next_cursor,
+                at,
+                change,
+            };
+            self.store.save_snapshot(record);
+            self.next_cursor += 1;
+            self.recorded.notify_waiters();
+        }
+        self.store.truncate(MAX_CHANGE_RECORDS);
+    }
+
+    /// Time of the oldest retained change
+    pub fn available_since(&self) -> Option<DateTime<Utc>> {
+        self.store.oldest()
+    }
+
+    /// Withdrawals first recorded after the oldest retained change. The ones
+    /// recorded with it may have been requested long before, e.g. by the first
+    /// refresh after the log was created, so their request time is unknown.
+    pub fn observed_withdrawals(&self) -> Vec<ObservedWithdrawal> {
+        let Some(oldest) = self.available_since() else {
+            return Vec::new();
+        };
+        let mut withdrawals: HashMap<Txid, ObservedWithdrawal> = HashMap::new();
+        for record in self.store.records() {
+            let BridgeChange::Withdrawal(withdrawal) = &record.change else {
+                continue;
+            };
+            let observed = withdrawals
+                .entry(withdrawal.withdrawal_request_txid)
+                .or_insert(ObservedWithdrawal {
+                    requested_at: record.at,
+                    fulfilled_at: None,
+                });
+            if observed.fulfilled_at.is_none() && withdrawal.status == WithdrawalStatus::Complete {
+                observed.fulfilled_at = Some(withdrawal.fulfilled_at.unwrap_or(record.at));
+            }
+        }
+        withdrawals
+            .into_values()
+            .filter(|observed| observed.requested_at > oldest)
+            .collect()
+    }
+
+    /// Drops the changes no longer retained from the persisted ones, which
+    /// otherwise only shrink on restart
+    pub fn compact(&mut self) {
+        self.store.compact();
+    }
+
+    /// Changes recorded at or after `since`, all retained changes if unset
+    fn since(&self, since: Option<u64>) -> BridgeChangesResponse {
+        let oldest = self
+            .store
+            .records()
+            .next()
+            .map_or(self.next_cursor, |record| record.cursor);
+        let since = since.unwrap_or(oldest);
+
+        BridgeChangesResponse {
+            cursor: self.next_cursor,
+            reset: since < oldest,
+            changes: self
+                .store
+                .records()
+                .filter(|record| record.cursor >= since)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Bridge state as of `at`, replaying the changes recorded up to then.
+    ///
+    /// `None` if no change was recorded by then. Entries are only removed from the
+    /// bridge status by tombstones, so the latest change of each entry is its state at
+    /// the time.
+    pub fn status_at(&self, at: DateTime<Utc>) -> Option<BridgeStatusAt> {
+        if self.store.oldest().is_none_or(|oldest| oldest > at) {
+            return None;
+        }
+
+        let mut status = BridgeStatus::default();
+        for record in self.store.records().take_while(|record| record.at <= at) {
+            match record.change.clone() {
+                BridgeChange::Operator(operator) => {
+                    upsert(&mut status.operators, operator, |op| op.operator_id.clone())
+                }
+                BridgeChange::Deposit(deposit) => {
+                    let txid = deposit.deposit_request_txid;
+                    status
+                        .tombstoned_deposits
+                        .retain(|t| t.entry.deposit_request_txid != txid);
+                    upsert(&mut status.deposits, deposit, |d| d.deposit_request_txid)
+                }
+                BridgeChange::Withdrawal(withdrawal) => {
+                    upsert(&mut status.withdrawals, withdrawal, |wd| {
+                        wd.withdrawal_request_txid
+                    })
+                }
+                BridgeChange::Reimbursement(reimbursement) => {
+                    let txid = reimbursement.claim_txid;
+                    status
+                        .tombstoned_reimbursements
+                        .retain(|t| t.entry.claim_txid != txid);
+                    upsert(&mut status.reimbursements, reimbursement, |r| r.claim_txid)
+                }
+                BridgeChange::DepositTombstone(tombstoned) => {
+                    let txid = tombstoned.entry.deposit_request_txid;
+                    status.deposits.retain(|d| d.deposit_request_txid != txid);
+                    upsert(&mut status.tombstoned_deposits, tombstoned, |t| {
+                        t.entry.deposit_request_txid
+                    })
+                }
+                BridgeChange::ReimbursementTombstone(tombstoned) => {
+                    let txid = tombstoned.entry.claim_txid;
+                    status.reimbursements.retain(|r| r.claim_txid != txid);
+                    upsert(&mut status.tombstoned_reimbursements, tombstoned, |t| {
+                        t.entry.claim_txid
+                    })
+                }
+            }
+        }
+
+        status.deposit_failures = deposit_failure_counts(&status.deposits);
+
+        Some(BridgeStatusAt {
+            at,
+            truncated: self
+                .store
+                .records()
+                .next()
+                .is_some_and(|record| record.cursor > 0),
+            status,
+        })
+    }
+
+    /// Transitions of the deposit with a given deposit request txid or deposit txid
+    /// and of its withdrawal, oldest first. `None` if no change of the deposit is
+    /// retained.
+    fn timeline(&self, txid: &Txid) -> Option<Vec<TimelineEntry>> {
+        let deposit_request_txid =
+            self.store
+                .records()
+                .find_map(|record| match &record.change {
+                    BridgeChange::Deposit(deposit)
+                        if deposit.deposit_request_txid == *txid
+                            || deposit.deposit_txid == Some(*txid) =>
+                    {
+                        Some(deposit.deposit_request_txid)
+                    }
+                    _ => None,
+                })?;
+        let withdrawal_request_txids: Vec<Txid> = self
+            .store
+            .records()
+            .filter_map(|record| match &record.change {
+                BridgeChange::Deposit(deposit)
+                    if deposit.deposit_request_txid == deposit_request_txid =>
+                {
+                    deposit.withdrawal_request_txid
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut timeline: Vec<TimelineEntry> = Vec::new();
+        for record in self.store.records() {
+            let entry = match &record.change {
+                BridgeChange::Deposit(deposit)
+                    if deposit.deposit_request_txid == deposit_request_txid =>
+                {
+                    TimelineEntry {
+                        at: record.at,
+                        kind: "deposit",
+                        txid: deposit.deposit_request_txid,
+                        status: status_label(&deposit.status),
+                        drt_status: deposit.drt_status.as_ref().map(status_label),
+                        linked_txid: deposit.deposit_txid,
+                    }
+                }
+                BridgeChange::Withdrawal(withdrawal)
+                    if withdrawal_request_txids.contains(&withdrawal.withdrawal_request_txid) =>
+                {
+                    TimelineEntry {
+                        at: record.at,
+                        kind: "withdrawal",
+                        txid: withdrawal.withdrawal_request_txid,
+                        status: status_label(&withdrawal.status),
+                        drt_status: None,
+                        linked_txid: withdrawal.fulfillment_txid,
+                    }
+                }
+                BridgeChange::DepositTombstone(tombstoned)
+                    if tombstoned.entry.deposit_request_txid == deposit_request_txid =>
+                {
+                    TimelineEntry {
+                        at: record.at,
+                        kind: "deposit",
+                        txid: deposit_request_txid,
+                        status: TOMBSTONED_STATUS.to_string(),
+                        drt_status: None,
+                        linked_txid: tombstoned.entry.deposit_txid,
+                    }
+                }
+                _ => continue,
+            };
+            // Restarts record every entry again, without a transition
+            let unchanged = timeline
+                .iter()
+                .rev()
+                .find(|previous| previous.txid == entry.txid)
+                .is_some_and(|previous| {
+                    (&previous.status, &previous.drt_status, previous.linked_txid)
+                        == (&entry.status, &entry.drt_status, entry.linked_txid)
+                });
+            if !unchanged {
+                timeline.push(entry);
+            }
+        }
+        Some(timeline)
+    }
+}
+
+/// Bridge state reconstructed from the change log. Only the changes tracked by
+/// [`diff_bridge_status`] are replayed; derived fields such as the liability are
+/// left empty.
+#[derive(Serialize, Debug)]
+pub struct BridgeStatusAt {
+    at: DateTime<Utc>,
+    /// Older changes were dropped from the log, so entries last changed before the
+    /// oldest retained change are missing
+    truncated: bool,
+    #[serde(flatten)]
+    status: BridgeStatus,
+}
+
+/// Shared bridge change log
+pub type SharedBridgeChanges = Arc<RwLock<BridgeChangeLog>>;
+
+/// Query parameters of the bridge changes endpoint
+#[derive(Deserialize, Debug)]
+pub struct BridgeChangesQuery {
+    /// `cursor` returned by the previous call
+    since: Option<u64>,
+    /// Long-polling: when there are no changes since the cursor, hold the request
+    /// until some are recorded or this duration, e.g. `30s`, elapses
+    wait_for_change: Option<String>,
+}
+
+/// Bridge changes passed to dashboard
+#[derive(Serialize, Debug)]
+pub struct BridgeChangesResponse {
+    /// Cursor to pass as `since` on the next call
+    cursor: u64,
+    /// Changes since the given cursor were dropped; refetch the full bridge status
+    reset: bool,
+    changes: Vec<ChangeRecord>,
+}
+
+/// Output format of the deposit timeline endpoint
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Query parameters of the deposit timeline endpoint
+#[derive(Deserialize, Debug)]
+pub struct TimelineQuery {
+    #[serde(default)]
+    format: TimelineFormat,
+}
+
+/// Return the recorded status transitions of a deposit and its withdrawal, given
+/// its deposit request txid or deposit txid
+pub async fn get_deposit_timeline(
+    Path(txid): Path<String>,
+    Query(query): Query<TimelineQuery>,
+    changes: SharedBridgeChanges,
+) -> Result<Response, StatusCode> {
+    let txid = parse_txid(&txid).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let timeline = changes
+        .read()
+        .await
+        .timeline(&txid)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(match query.format {
+        TimelineFormat::Json => Json(timeline).into_response(),
+        TimelineFormat::Csv => {
+            let csv = to_csv(
+                &["at", "kind", "txid", "status", "drt_status", "linked_txid"],
+                timeline.iter().map(TimelineEntry::csv_row),
+            );
+            ([(CONTENT_TYPE, "text/csv")], csv).into_response()
+        }
+    })
+}
+
+/// Return bridge changes since a cursor
+pub async fn get_bridge_changes(
+    Query(query): Query<BridgeChangesQuery>,
+    changes: SharedBridgeChanges,
+) -> Result<Json<BridgeChangesResponse>, StatusCode> {
+    let wait = query
+        .wait_for_change
+        .as_deref()
+        .map(|wait| parse_long_poll_wait(wait).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    let log = changes.read().await;
+    let response = log.since(query.since);
+    let Some(wait) = wait.filter(|_| response.changes.is_empty()) else {
+        return Ok(Json(response));
+    };
+
+    // Registered before the lock is released, so that no change is missed
+    let recorded = Arc::clone(&log.recorded);
+    let notified = recorded.notified();
+    tokio::pin!(notified);
+    notified.as_mut().enable();
+    drop(log);
+    let _ = timeout(wait, notified).await;
+    Ok(Json(changes.read().await.since(query.since)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        changed, get_bridge_changes, BridgeChange, BridgeChangeLog, BridgeChangesQuery,
+        MAX_CHANGE_RECORDS,
+    };
+    use crate::bridge::{
+        DepositInfo, DepositStatus, DrtStatus, ReimbursementInfo, ReimbursementStatus, Tombstoned,
+        WithdrawalInfo, WithdrawalStatus,
+    };
+    use axum::extract::Query;
+    use bitcoin::Txid;
+    use chrono::{Duration, Utc};
+    use std::{str::FromStr, sync::Arc};
+    use tokio::sync::RwLock;
+
+    #[test]
+    fn test_changed_entries() {
+        let old = [(1, "pending"), (2, "pending")];
+        let new = [(1, "pending"), (2, "complete"), (3, "pending")];
+
+        let changes: Vec<_> = changed(&old, &new, |e| e.0, |e| e.1).collect();
+        assert_eq!(changes, vec![&(2, "complete"), &(3, "pending")]);
+    }
+
+    fn reimbursement(status: ReimbursementStatus) -> BridgeChange {
+        BridgeChange::Reimbursement(ReimbursementInfo {
+            claim_txid: Txid::from_str(
+                "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+            )
+            .unwrap(),
+            challenge_step: "N/A".to_string(),
+            payout_txid: None,
+            status,
+        })
+    }
+
+    fn change() -> BridgeChange {
+        reimbursement(ReimbursementStatus::Cancelled)
+    }
+
+    #[tokio::test]
+    async fn test_long_poll_changes() {
+        let changes = Arc::new(RwLock::new(BridgeChangeLog::default()));
+        let query = |wait: &str| {
+            Query(BridgeChangesQuery {
+                since: Some(0),
+                wait_for_change: Some(wait.to_string()),
+            })
+        };
+
+        let recorder = {
+            let changes = Arc::clone(&changes);
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                changes.write().await.record(vec![change()], Utc::now());
+            })
+        };
+        // Held until the change is recorded
+        let response = get_bridge_changes(query("30s"), Arc::clone(&changes))
+            .await
+            .unwrap();
+        assert_eq!(response.changes.len(), 1);
+        assert_eq!(response.cursor, 1);
+        recorder.await.unwrap();
+
+        // Returned right away when there are changes since the cursor
+        let response = get_bridge_changes(query("30s"), Arc::clone(&changes))
+            .await
+            .unwrap();
+        assert_eq!(response.changes.len(), 1);
+
+        assert!(get_bridge_changes(query("soon"), changes).await.is_err());
+    }
+
+    #[test]
+    fn test_status_at() {
+        let now = Utc::now();
+        let mut log = BridgeChangeLog::default();
+        log.record(
+            vec![reimbursement(ReimbursementStatus::InProgress)],
+            now - Duration::hours(2),
+        );
+        log.record(
+            vec![reimbursement(ReimbursementStatus::Complete)],
+            now - Duration::hours(1),
+        );
+
+        assert!(log.status_at(now - Duration::hours(3)).is_none());
+        let status = |at| log.status_at(at).unwrap().status.reimbursements;
+        let past = status(now - Duration::minutes(90));
+        assert_eq!(past.len(), 1);
+        assert!(matches!(past[0].status, ReimbursementStatus::InProgress));
+        let current = status(now);
+        assert_eq!(current.len(), 1);
+        assert!(matches!(current[0].status, ReimbursementStatus::Complete));
+        assert!(!log.status_at(now).unwrap().truncated);
+
+        // A tombstone moves the claim out of the reimbursements
+        let BridgeChange::Reimbursement(claim) = change() else {
+            unreachable!()
+        };
+        let tombstone = BridgeChange::ReimbursementTombstone(Tombstoned {
+            entry: claim,
+            tombstoned_at: now,
+            was_active: false,
+        });
+        log.record(vec![tombstone], now);
+        let status = log.status_at(now).unwrap().status;
+        assert!(status.reimbursements.is_empty());
+        assert_eq!(status.tombstoned_reimbursements.len(), 1);
+    }
+
+    #[test]
+    fn test_deposit_timeline() {
+        let txid = |byte: &str| Txid::from_str(&byte.repeat(32)).unwrap();
+        let deposit = |status, drt_status, deposit_txid: Option<Txid>| {
+            BridgeChange::Deposit(DepositInfo {
+                deposit_request_txid: txid("01"),
+                deposit_txid,
+                status,
+                drt_status,
+                withdrawal_request_txid: deposit_txid.map(|_| txid("03")),
+                amount_sats: None,
+                confirmed_at: None,
+                failure_reason: None,
+            })
+        };
+        let withdrawal = |status, fulfillment_txid| {
+            BridgeChange::Withdrawal(WithdrawalInfo {
+                withdrawal_request_txid: txid("03"),
+                fulfillment_txid,
+                status,
+                assignee: None,
+                recipient_address: None,
+                amount_sats: None,
+                fulfilled_at: None,
+            })
+        };
+
+        let now = Utc::now();
+        let mut log = BridgeChangeLog::default();
+        log.record(
+            vec![deposit(
+                DepositStatus::InProgress,
+                Some(DrtStatus::Mempool),
+                None,
+            )],
+            now - Duration::hours(3),
+        );
+        log.record(
+            vec![
+                deposit(DepositStatus::Complete, None, Some(txid("02"))),
+                withdrawal(WithdrawalStatus::InProgress, None),
+            ],
+            now - Duration::hours(2),
+        );
+        // Recorded again after a restart
+        log.record(
+            vec![deposit(DepositStatus::Complete, None, Some(txid("02")))],
+            now - Duration::hours(1),
+        );
+        log.record(
+            vec![withdrawal(WithdrawalStatus::Complete, Some(txid("04")))],
+            now,
+        );
+
+        let timeline = log.timeline(&txid("02")).unwrap();
+        let summary: Vec<_> = timeline
+            .iter()
+            .map(|entry| (entry.kind, entry.status.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("deposit", "In progress"),
+                ("deposit", "Complete"),
+                ("withdrawal", "In progress"),
+                ("withdrawal", "Complete"),
+            ]
+        );
+        assert_eq!(timeline[0].drt_status.as_deref(), Some("mempool"));
+        assert_eq!(timeline[3].csv_row()[5], txid("04").to_string());
+        assert!(log.timeline(&txid("03")).is_none());
+    }
+
+    #[test]
+    fn test_change_log_cursors() {
+        let mut log = BridgeChangeLog::default();
+        log.record(vec![change(), change()], Utc::now());
+
+        let page = log.since(None);
+        assert_eq!((page.cursor, page.changes.len(), page.reset), (2, 2, false));
+
+        log.record(vec![change()], Utc::now());
+        let page = log.since(Some(2));
+        assert_eq!((page.cursor, page.changes.len(), page.reset), (3, 1, false));
+        assert_eq!(page.changes[0].cursor, 2);
+
+        assert!(log.since(Some(3)).changes.is_empty());
+
+        log.record(vec![change(); MAX_CHANGE_RECORDS], Utc::now());
+        assert!(log.since(Some(0)).reset);
+    }
+}
@@ -0,0 +1,188 @@
+//! Transactions of a deposit and its withdrawal as a graph, for the trace view:
+//! DRT → deposit tx → withdrawal request → fulfillment.
+//!
+//! The bridge RPC does not attribute claims to deposits, so the claim and payout
+//! txs reimbursing the operator are not part of the graph.
+
+use axum::{extract::Path, http::StatusCode, Json};
+use bitcoin::Txid;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    bridge::{find_deposit, DepositInfo, SharedBridgeState, WithdrawalInfo},
+    txid_format::{self, parse_txid},
+};
+
+/// Role of a transaction in the bridge flow
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxKind {
+    DepositRequest,
+    Deposit,
+    WithdrawalRequest,
+    Fulfillment,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TxNode {
+    #[serde(with = "txid_format::txid")]
+    txid: Txid,
+    kind: TxKind,
+    /// Bitcoin amount moved by the tx, when known
+    amount_sats: Option<u64>,
+    /// Confirmation time, when known
+    confirmed_at: Option<DateTime<Utc>>,
+}
+
+/// `to` spends or was triggered by `from`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TxEdge {
+    #[serde(with = "txid_format::txid")]
+    from: Txid,
+    #[serde(with = "txid_format::txid")]
+    to: Txid,
+}
+
+/// Known transactions of a deposit in flow order, linked by edges. Later stages
+/// are missing until they happen.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BridgeGraph {
+    nodes: Vec<TxNode>,
+    edges: Vec<TxEdge>,
+}
+
+impl BridgeGraph {
+    fn new(deposit: &DepositInfo, withdrawal: Option<&WithdrawalInfo>) -> Self {
+        let node = |txid, kind, amount_sats, confirmed_at| TxNode {
+            txid,
+            kind,
+            amount_sats,
+            confirmed_at,
+        };
+        let nodes: Vec<TxNode> = [
+            Some(node(
+                deposit.deposit_request_txid,
+                TxKind::DepositRequest,
+                deposit.amount_sats,
+                None,
+            )),
+            deposit.deposit_txid.map(|txid| {
+                node(
+                    txid,
+                    TxKind::Deposit,
+                    deposit.amount_sats,
+                    deposit.confirmed_at,
+                )
+            }),
+            deposit
+                .withdrawal_request_txid
+                .map(|txid| node(txid, TxKind::WithdrawalRequest, None, None)),
+            withdrawal
+                .and_then(|withdrawal| Some((withdrawal, withdrawal.fulfillment_txid?)))
+                .map(|(withdrawal, txid)| {
+                    node(
+                        txid,
+                        TxKind::Fulfillment,
+                        withdrawal.amount_sats,
+                        withdrawal.fulfilled_at,
+                    )
+                }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let edges = nodes
+            .windows(2)
+            .map(|pair| TxEdge {
+                from: pair[0].txid,
+                to: pair[1].txid,
+            })
+            .collect();
+        Self { nodes, edges }
+    }
+}
+
+/// Return the transaction graph of the deposit with a given deposit request txid
+/// or deposit txid
+pub async fn get_bridge_graph(
+    Path(txid): Path<String>,
+    state: SharedBridgeState,
+) -> Result<Json<BridgeGraph>, StatusCode> {
+    let txid = parse_txid(&txid).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let state = state.read().await;
+    let lookup = find_deposit(&state, &txid).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(BridgeGraph::new(
+        &lookup.deposit,
+        lookup.withdrawal.as_ref(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BridgeGraph, TxKind};
+    use crate::bridge::{DepositInfo, DepositStatus, WithdrawalInfo, WithdrawalStatus};
+    use bitcoin::Txid;
+    use std::str::FromStr;
+
+    fn txid(byte: &str) -> Txid {
+        Txid::from_str(&byte.repeat(32)).unwrap()
+    }
+
+    #[test]
+    fn test_bridge_graph() {
+        let mut deposit = DepositInfo {
+            deposit_request_txid: txid("01"),
+            deposit_txid: None,
+            status: DepositStatus::InProgress,
+            drt_status: None,
+            withdrawal_request_txid: None,
+            amount_sats: Some(100_000),
+            confirmed_at: None,
+            failure_reason: None,
+        };
+        let graph = BridgeGraph::new(&deposit, None);
+        assert_eq!(graph.nodes.len(), 1);
+        assert!(graph.edges.is_empty());
+
+        deposit.deposit_txid = Some(txid("02"));
+        deposit.status = DepositStatus::Complete;
+        deposit.withdrawal_request_txid = Some(txid("03"));
+        let withdrawal = WithdrawalInfo {
+            withdrawal_request_txid: txid("03"),
+            fulfillment_txid: Some(txid("04")),
+            status: WithdrawalStatus::Complete,
+            assignee: Some(0),
+            recipient_address: None,
+            amount_sats: Some(99_000),
+            fulfilled_at: None,
+        };
+        let graph = BridgeGraph::new(&deposit, Some(&withdrawal));
+
+        let kinds: Vec<TxKind> = graph.nodes.iter().map(|node| node.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TxKind::DepositRequest,
+                TxKind::Deposit,
+                TxKind::WithdrawalRequest,
+                TxKind::Fulfillment,
+            ]
+        );
+        assert_eq!(graph.nodes[3].amount_sats, Some(99_000));
+        let edges: Vec<(Txid, Txid)> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (txid("01"), txid("02")),
+                (txid("02"), txid("03")),
+                (txid("03"), txid("04")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use axum::Json;
+use jsonrpsee::{core::client::ClientT, http_client::HttpClient};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::{
+    bridge::{BridgeStatus, DepositStatus, SharedBridgeState, WithdrawalStatus},
+    watched::BridgeContract,
+};
+
+/// Selector of the ERC-20 `totalSupply()` function
+const TOTAL_SUPPLY_SELECTOR: &str = "0x18160ddd";
+/// Decimals of an amount in sats
+const SATS_DECIMALS: u32 = 8;
+
+/// Parses a 32-byte `eth_call` return value, `None` if it does not fit in a u128
+fn parse_uint(hex: &str) -> Option<u128> {
+    let digits = hex.strip_prefix("0x")?.trim_start_matches('0');
+    if digits.is_empty() {
+        return Some(0);
+    }
+    if digits.len() > 32 {
+        return None;
+    }
+    u128::from_str_radix(digits, 16).ok()
+}
+
+/// Converts an amount with `decimals` decimals to sats, rounding down
+fn to_sats(amount: u128, decimals: u32) -> Option<u64> {
+    let sats = if decimals >= SATS_DECIMALS {
+        amount / 10u128.checked_pow(decimals - SATS_DECIMALS)?
+    } else {
+        amount.checked_mul(10u128.checked_pow(SATS_DECIMALS - decimals)?)?
+    };
+    u64::try_from(sats).ok()
+}
+
+/// Reads the total supply of the bridged asset on L2
+pub struct BridgedAssetClient {
+    rpc: HttpClient,
+    address: String,
+    decimals: u32,
+}
+
+impl BridgedAssetClient {
+    pub fn new(rpc: HttpClient, address: &str, decimals: u32) -> Self {
+        Self {
+            rpc,
+            address: address.to_string(),
+            decimals,
+        }
+    }
+
+    /// Total supply in sats, `None` if the call failed
+    pub async fn total_supply_sats(&self) -> Option<u64> {
+        let call = json!({ "to": self.address, "data": TOTAL_SUPPLY_SELECTOR });
+        let response: Result<Value, _> = self.rpc.request("eth_call", (call, "latest")).await;
+        match response {
+            Ok(value) => {
+                let supply = value
+                    .as_str()
+                    .and_then(parse_uint)
+                    .and_then(|supply| to_sats(supply, self.decimals));
+                if supply.is_none() {
+                    warn!(?value, "Unexpected bridged asset total supply");
+                }
+                supply
+            }
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    address = %self.address,
+                    "Bridged asset total supply query failed"
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Total supply in sats across the bridge contracts, `None` if there are none or
+/// any call failed
+pub async fn total_supply_sats(rpc: &HttpClient, contracts: &[BridgeContract]) -> Option<u64> {
+    if contracts.is_empty() {
+        return None;
+    }
+    let mut total: u64 = 0;
+    for contract in contracts {
+        let supply = BridgedAssetClient::new(rpc.clone(), &contract.address, contract.decimals)
+            .total_supply_sats()
+            .await?;
+        total = total.saturating_add(supply);
+    }
+    Some(total)
+}
+
+/// What the bridge owes to users, cross-checked against the L2 supply
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BridgeLiability {
+    /// Deposited sats not yet paid back out by a withdrawal fulfillment
+    outstanding_sats: u64,
+    /// Deposited sats still bridged on L2, as withdrawal requests burn their deposit on L2
+    expected_l2_supply_sats: u64,
+    /// Completed deposits left out because their amount is unknown
+    unaccounted_deposits: usize,
+    /// Total supply of the bridged asset on L2, unset if not configured or the query failed
+    l2_supply_sats: Option<u64>,
+    /// L2 supply minus the expected supply
+    discrepancy_sats: Option<i64>,
+    /// The discrepancy exceeds the configured tolerance
+    pub(crate) mismatch: bool,
+}
+
+impl BridgeLiability {
+    /// Outstanding liability of the completed deposits in `status`
+    pub fn new(status: &BridgeStatus, l2_supply_sats: Option<u64>, tolerance_sats: u64) -> Self {
+        let mut liability = Self {
+            l2_supply_sats,
+            ..Default::default()
+        };
+        for deposit in &status.deposits {
+            if deposit.status != DepositStatus::Complete {
+                continue;
+            }
+            let Some(amount) = deposit.amount_sats else {
+                liability.unaccounted_deposits += 1;
+                continue;
+            };
+
+            let withdrawal = deposit.withdrawal_request_txid.map(|request_txid| {
+                status
+                    .withdrawals
+                    .iter()
+                    .find(|withdrawal| withdrawal.withdrawal_request_txid == request_txid)
+            });
+            match withdrawal {
+                None => {
+                    liability.outstanding_sats += amount;
+                    liability.expected_l2_supply_sats += amount;
+                }
+                Some(Some(withdrawal)) if withdrawal.status == WithdrawalStatus::Complete => {}
+                // Requested, or the withdrawal info is unavailable: burnt on L2 but not paid out
+                Some(_) => liability.outstanding_sats += amount,
+            }
+        }
+
+        liability.discrepancy_sats =
+            l2_supply_sats.map(|supply| supply as i64 - liability.expected_l2_supply_sats as i64);
+        liability.mismatch = liability
+            .discrepancy_sats
+            .is_some_and(|discrepancy| discrepancy.unsigned_abs() > tolerance_sats);
+        liability
+    }
+
+    /// Summary for the discrepancy alert
+    pub fn mismatch_message(&self) -> String {
+        format!(
+            "L2 bridged supply of {} sats differs from the {} sats expected from deposits \
+             by {} sats",
+            self.l2_supply_sats.unwrap_or_default(),
+            self.expected_l2_supply_sats,
+            self.discrepancy_sats.unwrap_or_default()
+        )
+    }
+
+    /// Whether the L2 supply was checked in this refresh
+    pub fn supply_checked(&self) -> bool {
+        self.l2_supply_sats.is_some()
+    }
+}
+
+/// Return the outstanding bridge liability
+pub async fn get_bridge_liability(state: SharedBridgeState) -> Json<BridgeLiability> {
+    Json(state.read().await.liability.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_uint, to_sats, BridgeLiability};
+    use crate::bridge::{
+        BridgeStatus, DepositInfo, DepositStatus, WithdrawalInfo, WithdrawalStatus,
+    };
+    use bitcoin::Txid;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_total_supply_conversion() {
+        let one_btc_wei = format!("0x{:064x}", 10u128.pow(18));
+        assert_eq!(parse_uint(&one_btc_wei), Some(10u128.pow(18)));
+        assert_eq!(parse_uint("0x"), Some(0));
+        assert_eq!(parse_uint(&format!("0x{}", "f".repeat(64))), None);
+
+        assert_eq!(to_sats(10u128.pow(18), 18), Some(100_000_000));
+        assert_eq!(to_sats(100_000_000, 8), Some(100_000_000));
+        assert_eq!(to_sats(1, 6), Some(100));
+    }
+
+    #[test]
+    fn test_bridge_liability() {
+        let txid = |byte: &str| Txid::from_str(&byte.repeat(32)).unwrap();
+        let deposit = |amount_sats, withdrawal_request_txid: Option<&str>| DepositInfo {
+            deposit_request_txid: txid("01"),
+            deposit_txid: Some(txid("02")),
+            status: DepositStatus::Complete,
+            drt_status: None,
+            withdrawal_request_txid: withdrawal_request_txid.map(txid),
+            amount_sats,
+            confirmed_at: None,
+            failure_reason: None,
+        };
+        let withdrawal = |request_txid: &str, status| WithdrawalInfo {
+            withdrawal_request_txid: txid(request_txid),
+            fulfillment_txid: None,
+            status,
+            assignee: None,
+            recipient_address: None,
+            amount_sats: None,
+            fulfilled_at: None,
+        };
+
+        let mut status = BridgeStatus::default();
+        status.deposits.extend([
+            deposit(Some(1_000), None),
+            deposit(Some(2_000), Some("03")),
+            deposit(Some(4_000), Some("04")),
+            deposit(None, None),
+        ]);
+        status.withdrawals.extend([
+            withdrawal("03", WithdrawalStatus::InProgress),
+            withdrawal("04", WithdrawalStatus::Complete),
+        ]);
+
+        let liability = BridgeLiability::new(&status, None, 0);
+        assert_eq!(liability.outstanding_sats, 3_000);
+        assert_eq!(liability.expected_l2_supply_sats, 1_000);
+        assert_eq!(liability.unaccounted_deposits, 1);
+        assert!(!liability.mismatch);
+
+        let liability = BridgeLiability::new(&status, Some(1_500), 100);
+        assert_eq!(liability.discrepancy_sats, Some(500));
+        assert!(liability.mismatch);
+        assert!(!BridgeLiability::new(&status, Some(1_050), 100).mismatch);
+    }
+}
@@ -0,0 +1,248 @@
+use axum::{extract::Query, Json};
+use chrono::{DateTime, Datelike, Days, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::bridge::{BridgeStatus, DepositStatus, SharedBridgeState, WithdrawalStatus};
+
+/// Length of the volume buckets
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumePeriod {
+    #[default]
+    Day,
+    /// Weeks start on Monday
+    Week,
+    Month,
+}
+
+impl VolumePeriod {
+    /// Start of the bucket containing `at`, in UTC
+    fn bucket_start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let date = at.date_naive();
+        let start = match self {
+            VolumePeriod::Day => date,
+            VolumePeriod::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            VolumePeriod::Month => date.with_day(1).expect("first day of month to exist"),
+        };
+        start.and_time(NaiveTime::MIN).and_utc()
+    }
+}
+
+/// Bridged-in and bridged-out amounts within one period
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct VolumeBucket {
+    start: DateTime<Utc>,
+    deposits: usize,
+    deposited_sats: u64,
+    withdrawals: usize,
+    withdrawn_sats: u64,
+    /// Deposited minus withdrawn sats within the period
+    net_flow_sats: i64,
+    /// Net flow of this and all earlier periods
+    cumulative_net_flow_sats: i64,
+}
+
+impl VolumeBucket {
+    fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            deposits: 0,
+            deposited_sats: 0,
+            withdrawals: 0,
+            withdrawn_sats: 0,
+            net_flow_sats: 0,
+            cumulative_net_flow_sats: 0,
+        }
+    }
+}
+
+/// Bridge throughput passed to dashboard
+#[derive(Serialize, Debug)]
+pub struct BridgeVolume {
+    period: VolumePeriod,
+    /// Periods with bridge activity, oldest first
+    buckets: Vec<VolumeBucket>,
+    /// Completed deposits left out because their amount or confirmation time is unknown
+    unaccounted_deposits: usize,
+    /// Completed withdrawals left out because their payout or confirmation time is unknown
+    unaccounted_withdrawals: usize,
+}
+
+/// Bucket containing `at`, created empty if missing
+fn bucket_at(
+    buckets: &mut BTreeMap<DateTime<Utc>, VolumeBucket>,
+    period: VolumePeriod,
+    at: DateTime<Utc>,
+) -> &mut VolumeBucket {
+    let start = period.bucket_start(at);
+    buckets
+        .entry(start)
+        .or_insert_with(|| VolumeBucket::new(start))
+}
+
+/// Aggregate completed deposits and withdrawals per period, by confirmation time
+fn bridge_volume(status: &BridgeStatus, period: VolumePeriod) -> BridgeVolume {
+    let mut buckets: BTreeMap<DateTime<Utc>, VolumeBucket> = BTreeMap::new();
+
+    let mut unaccounted_deposits = 0;
+    for deposit in &status.deposits {
+        if deposit.status != DepositStatus::Complete {
+            continue;
+        }
+        match (deposit.amount_sats, deposit.confirmed_at) {
+            (Some(amount), Some(at)) => {
+                let bucket = bucket_at(&mut buckets, period, at);
+                bucket.deposits += 1;
+                bucket.deposited_sats += amount;
+            }
+            _ => unaccounted_deposits += 1,
+        }
+    }
+
+    let mut unaccounted_withdrawals = 0;
+    for withdrawal in &status.withdrawals {
+        if withdrawal.status != WithdrawalStatus::Complete {
+            continue;
+        }
+        match (withdrawal.amount_sats, withdrawal.fulfilled_at) {
+            (Some(amount), Some(at)) => {
+                let bucket = bucket_at(&mut buckets, period, at);
+                bucket.withdrawals += 1;
+                bucket.withdrawn_sats += amount;
+            }
+            _ => unaccounted_withdrawals += 1,
+        }
+    }
+
+    let mut cumulative_net_flow_sats = 0;
+    let buckets = buckets
+        .into_values()
+        .map(|mut bucket| {
+            bucket.net_flow_sats = bucket.deposited_sats as i64 - bucket.withdrawn_sats as i64;
+            cumulative_net_flow_sats += bucket.net_flow_sats;
+            bucket.cumulative_net_flow_sats = cumulative_net_flow_sats;
+            bucket
+        })
+        .collect();
+
+    BridgeVolume {
+        period,
+        buckets,
+        unaccounted_deposits,
+        unaccounted_withdrawals,
+    }
+}
+
+/// Query parameters of the bridge volume endpoint
+#[derive(Deserialize, Debug)]
+pub struct BridgeVolumeQuery {
+    /// Bucket length, daily if unset
+    #[serde(default)]
+    period: VolumePeriod,
+}
+
+/// Return bridged-in and bridged-out amounts and the net flow per day, week or month
+pub async fn get_bridge_volume(
+    Query(query): Query<BridgeVolumeQuery>,
+    state: SharedBridgeState,
+) -> Json<BridgeVolume> {
+    Json(bridge_volume(&state.read().await, query.period))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bridge_volume, VolumeBucket, VolumePeriod};
+    use crate::bridge::{
+        BridgeStatus, DepositInfo, DepositStatus, WithdrawalInfo, WithdrawalStatus,
+    };
+    use bitcoin::Txid;
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::str::FromStr;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        // 2025-03-12 is a Wednesday
+        let wednesday = Utc.with_ymd_and_hms(2025, 3, 12, 18, 30, 0).unwrap();
+        assert_eq!(VolumePeriod::Day.bucket_start(wednesday), at(12, 0));
+        assert_eq!(VolumePeriod::Week.bucket_start(wednesday), at(10, 0));
+        assert_eq!(VolumePeriod::Month.bucket_start(wednesday), at(1, 0));
+    }
+
+    #[test]
+    fn test_bridge_volume() {
+        let txid = Txid::from_str(TXID).unwrap();
+        let deposit = |status, amount_sats, confirmed_at| DepositInfo {
+            deposit_request_txid: txid,
+            deposit_txid: Some(txid),
+            status,
+            drt_status: None,
+            withdrawal_request_txid: None,
+            amount_sats,
+            confirmed_at,
+            failure_reason: None,
+        };
+        let withdrawal = |amount_sats, fulfilled_at| WithdrawalInfo {
+            withdrawal_request_txid: txid,
+            fulfillment_txid: Some(txid),
+            status: WithdrawalStatus::Complete,
+            assignee: Some(0),
+            recipient_address: None,
+            amount_sats,
+            fulfilled_at,
+        };
+
+        let mut status = BridgeStatus::default();
+        status.deposits.extend([
+            deposit(DepositStatus::Complete, Some(1_000), Some(at(10, 1))),
+            deposit(DepositStatus::Complete, Some(2_000), Some(at(10, 23))),
+            deposit(DepositStatus::Complete, Some(4_000), Some(at(12, 5))),
+            // Not bridged in yet
+            deposit(DepositStatus::InProgress, Some(8_000), None),
+            // Confirmation time unknown
+            deposit(DepositStatus::Complete, Some(8_000), None),
+        ]);
+        status.withdrawals.extend([
+            withdrawal(Some(500), Some(at(12, 6))),
+            withdrawal(None, None),
+        ]);
+
+        let daily = bridge_volume(&status, VolumePeriod::Day);
+        assert_eq!(
+            daily.buckets,
+            vec![
+                VolumeBucket {
+                    start: at(10, 0),
+                    deposits: 2,
+                    deposited_sats: 3_000,
+                    withdrawals: 0,
+                    withdrawn_sats: 0,
+                    net_flow_sats: 3_000,
+                    cumulative_net_flow_sats: 3_000,
+                },
+                VolumeBucket {
+                    start: at(12, 0),
+                    deposits: 1,
+                    deposited_sats: 4_000,
+                    withdrawals: 1,
+                    withdrawn_sats: 500,
+                    net_flow_sats: 3_500,
+                    cumulative_net_flow_sats: 6_500,
+                },
+            ]
+        );
+        assert_eq!(daily.unaccounted_deposits, 1);
+        assert_eq!(daily.unaccounted_withdrawals, 1);
+
+        let weekly = bridge_volume(&status, VolumePeriod::Week);
+        assert_eq!(weekly.buckets.len(), 1);
+        assert_eq!(weekly.buckets[0].deposited_sats, 7_000);
+        assert_eq!(weekly.buckets[0].withdrawn_sats, 500);
+    }
+}
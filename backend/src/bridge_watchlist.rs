@@ -0,0 +1,223 @@
+//! Deposits of interest, e.g. of VIP users or internal tests, configured in
+//! `BRIDGE_WATCHLIST`. They are checked again every `BRIDGE_WATCHLIST_INTERVAL_S`,
+//! between the regular bridge refreshes, and each of their changes is published as
+//! a dedicated `watched_deposit_updated` event.
+
+use axum::Json;
+use bitcoin::Txid;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    bridge::{DepositInfo, DepositStatus, DrtStatus, WithdrawalInfo, WithdrawalStatus},
+    txid_format::{self, parse_outpoint, parse_txid},
+};
+
+/// Deposit of the watch list, by its deposit request txid or deposit txid
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct WatchlistEntry {
+    /// Name shown on the dashboard and in notifications, e.g. `vip-1`
+    pub label: Option<String>,
+    #[serde(with = "txid_format::txid")]
+    pub txid: Txid,
+}
+
+/// Parses `[<label>=]<txid>` or `[<label>=]<txid>:<vout>`; the outpoint of a deposit
+/// is watched by its txid
+fn parse_entry(s: &str) -> Result<WatchlistEntry, String> {
+    let (label, target) = match s.split_once('=') {
+        Some((label, target)) => (Some(label.trim().to_string()), target.trim()),
+        None => (None, s),
+    };
+    let txid = if target.contains(':') {
+        parse_outpoint(target)?.txid
+    } else {
+        parse_txid(target)?
+    };
+    Ok(WatchlistEntry { label, txid })
+}
+
+/// Parses a comma-separated watch list
+pub fn parse_watchlist(s: &str) -> Result<Vec<WatchlistEntry>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_entry)
+        .collect()
+}
+
+/// Last known state of a watched deposit
+#[derive(Serialize, Clone, Debug)]
+pub struct WatchedDeposit {
+    #[serde(flatten)]
+    pub entry: WatchlistEntry,
+    /// Unset until the bridge reports a deposit with the txid
+    pub deposit: Option<DepositInfo>,
+    pub withdrawal: Option<WithdrawalInfo>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_changed: Option<DateTime<Utc>>,
+}
+
+impl WatchedDeposit {
+    /// Whether the deposit or its withdrawal no longer change
+    pub fn is_final(&self) -> bool {
+        self.deposit
+            .as_ref()
+            .is_some_and(|deposit| deposit.status == DepositStatus::Failed)
+            || self
+                .withdrawal
+                .as_ref()
+                .is_some_and(|withdrawal| withdrawal.status == WithdrawalStatus::Complete)
+    }
+}
+
+/// Statuses whose changes are notified
+type WatchedStatus = (
+    Option<DepositStatus>,
+    Option<DrtStatus>,
+    Option<Txid>,
+    Option<WithdrawalStatus>,
+);
+
+fn watched_status(
+    deposit: Option<&DepositInfo>,
+    withdrawal: Option<&WithdrawalInfo>,
+) -> WatchedStatus {
+    (
+        deposit.map(|deposit| deposit.status.clone()),
+        deposit.and_then(|deposit| deposit.drt_status.clone()),
+        deposit.and_then(|deposit| deposit.withdrawal_request_txid),
+        withdrawal.map(|withdrawal| withdrawal.status.clone()),
+    )
+}
+
+/// Watched deposits, in the order of `BRIDGE_WATCHLIST`
+#[derive(Debug, Default)]
+pub struct Watchlist {
+    deposits: Vec<WatchedDeposit>,
+}
+
+pub type SharedWatchlist = Arc<RwLock<Watchlist>>;
+
+impl Watchlist {
+    pub fn new(entries: Vec<WatchlistEntry>) -> Self {
+        let deposits = entries
+            .into_iter()
+            .map(|entry| WatchedDeposit {
+                entry,
+                deposit: None,
+                withdrawal: None,
+                last_checked: None,
+                last_changed: None,
+            })
+            .collect();
+        Self { deposits }
+    }
+
+    /// Txids of the watched deposits that may still change
+    pub fn pending_txids(&self) -> Vec<Txid> {
+        self.deposits
+            .iter()
+            .filter(|watched| !watched.is_final())
+            .map(|watched| watched.entry.txid)
+            .collect()
+    }
+
+    /// Updates the watched deposits from `lookup`, returning the ones whose status
+    /// changed. Deposits the lookup does not find keep their last known state.
+    pub fn update(
+        &mut self,
+        lookup: impl Fn(&Txid) -> Option<(DepositInfo, Option<WithdrawalInfo>)>,
+        at: DateTime<Utc>,
+    ) -> Vec<WatchedDeposit> {
+        let mut changed = Vec::new();
+        for watched in self.deposits.iter_mut() {
+            watched.last_checked = Some(at);
+            let Some((deposit, withdrawal)) = lookup(&watched.entry.txid) else {
+                continue;
+            };
+            let previous = watched_status(watched.deposit.as_ref(), watched.withdrawal.as_ref());
+            let current = watched_status(Some(&deposit), withdrawal.as_ref());
+            watched.deposit = Some(deposit);
+            watched.withdrawal = withdrawal;
+            if previous != current {
+                watched.last_changed = Some(at);
+                changed.push(watched.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Return the watched deposits and their last known state
+pub async fn get_bridge_watchlist(watchlist: SharedWatchlist) -> Json<Vec<WatchedDeposit>> {
+    Json(watchlist.read().await.deposits.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_watchlist, Watchlist, WatchlistEntry};
+    use crate::bridge::{DepositInfo, DepositStatus};
+    use bitcoin::Txid;
+    use chrono::{Duration, Utc};
+    use std::str::FromStr;
+
+    const TXID: &str = "ae86f8a1a6b7e3e1e0de5ef1b4bc8ad5fbaf6a4b3fbde5e1c0f52d7c1a9f4b3d";
+
+    fn deposit(txid: Txid, status: DepositStatus) -> DepositInfo {
+        DepositInfo {
+            deposit_request_txid: txid,
+            deposit_txid: None,
+            status,
+            drt_status: None,
+            withdrawal_request_txid: None,
+            amount_sats: None,
+            confirmed_at: None,
+            failure_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_watchlist() {
+        let txid = Txid::from_str(TXID).unwrap();
+        let entries = parse_watchlist(&format!("{}, vip-1={}:0,", TXID, TXID)).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                WatchlistEntry { label: None, txid },
+                WatchlistEntry {
+                    label: Some("vip-1".to_string()),
+                    txid,
+                },
+            ]
+        );
+        assert!(parse_watchlist("vip-1=0xabc").is_err());
+        assert_eq!(parse_watchlist(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_watchlist_reports_status_changes() {
+        let txid = Txid::from_str(TXID).unwrap();
+        let mut watchlist = Watchlist::new(vec![WatchlistEntry { label: None, txid }]);
+        let now = Utc::now();
+
+        // Not reported by the bridge yet
+        assert!(watchlist.update(|_| None, now).is_empty());
+        assert_eq!(watchlist.deposits[0].last_checked, Some(now));
+
+        let in_progress = |_: &Txid| Some((deposit(txid, DepositStatus::InProgress), None));
+        assert_eq!(watchlist.update(in_progress, now).len(), 1);
+        let later = now + Duration::seconds(15);
+        assert!(watchlist.update(in_progress, later).is_empty());
+        assert_eq!(watchlist.deposits[0].last_changed, Some(now));
+        assert_eq!(watchlist.pending_txids(), vec![txid]);
+
+        let failed = |_: &Txid| Some((deposit(txid, DepositStatus::Failed), None));
+        let changed = watchlist.update(failed, later);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].last_changed, Some(later));
+        assert!(watchlist.pending_txids().is_empty());
+    }
+}
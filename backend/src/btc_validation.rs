@@ -0,0 +1,127 @@
+//! Strict validation of the bitcoin txids, outpoints and addresses received from
+//! upstream RPCs, before they enter the shared state. Malformed values are logged
+//! and dropped rather than served by the API:
+//! - a txid is exactly 64 hex chars in display byte order, and not all zeros
+//! - an outpoint is `<txid>:<vout>`, with `vout` a decimal `u32` without sign or
+//!   leading zeros
+//! - an address must be valid for the bitcoin network of `BITCOIN_NETWORK`;
+//!   testnet and signet share their address formats
+
+use bitcoin::{address::NetworkUnchecked, hashes::Hash, Address, Network, OutPoint, Txid};
+use std::str::FromStr;
+use tracing::warn;
+
+fn parse_txid(s: &str) -> Result<Txid, String> {
+    if s.len() != 64 || !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err("expected 64 hex chars".to_string());
+    }
+    let txid = Txid::from_str(s).map_err(|e| e.to_string())?;
+    if txid == Txid::all_zeros() {
+        return Err("null txid".to_string());
+    }
+    Ok(txid)
+}
+
+fn parse_outpoint(s: &str) -> Result<OutPoint, String> {
+    let (txid, vout) = s
+        .split_once(':')
+        .ok_or_else(|| "expected <txid>:<vout>".to_string())?;
+    let canonical = !vout.is_empty()
+        && vout.bytes().all(|byte| byte.is_ascii_digit())
+        && (vout == "0" || !vout.starts_with('0'));
+    if !canonical {
+        return Err("expected a decimal vout".to_string());
+    }
+    let vout = vout.parse().map_err(|_| "vout out of range".to_string())?;
+    Ok(OutPoint::new(parse_txid(txid)?, vout))
+}
+
+fn parse_address(s: &str, network: Network) -> Result<Address, String> {
+    Address::<NetworkUnchecked>::from_str(s)
+        .map_err(|e| e.to_string())?
+        .require_network(network)
+        .map_err(|e| e.to_string())
+}
+
+/// Parses `value` with `parse`, logging it if malformed
+fn validate<T>(
+    source: &str,
+    kind: &'static str,
+    value: &str,
+    parse: impl FnOnce(&str) -> Result<T, String>,
+) -> Option<T> {
+    parse(value)
+        .inspect_err(|error| {
+            warn!(
+                source,
+                kind,
+                value,
+                %error,
+                "Rejected malformed bitcoin value from upstream"
+            );
+        })
+        .ok()
+}
+
+/// Validates a txid returned by `source`, e.g. an RPC method name
+pub fn upstream_txid(source: &str, value: &str) -> Option<Txid> {
+    validate(source, "txid", value, parse_txid)
+}
+
+/// Validates an outpoint returned by `source`, formatted as `<txid>:<vout>`
+pub fn upstream_outpoint(source: &str, value: &str) -> Option<OutPoint> {
+    validate(source, "outpoint", value, parse_outpoint)
+}
+
+/// Validates an address returned by `source` against `network`. Returns the
+/// address as given, so that it still compares equal to the upstream's.
+pub fn upstream_address(source: &str, value: &str, network: Network) -> Option<String> {
+    validate(source, "address", value, |value| {
+        parse_address(value, network).map(|_| value.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{upstream_address, upstream_outpoint, upstream_txid};
+    use bitcoin::Network;
+
+    const TXID: &str = "c229c28508eb3b4060682d75aa19a849027cccf9e6b1a3c5e3b28f8010025669";
+
+    #[test]
+    fn test_upstream_txid() {
+        assert!(upstream_txid("test", TXID).is_some());
+        assert!(upstream_txid("test", &TXID.to_uppercase()).is_some());
+        assert!(upstream_txid("test", &TXID[..62]).is_none());
+        assert!(upstream_txid("test", &format!("{}00", TXID)).is_none());
+        assert!(upstream_txid("test", &format!("0x{}", &TXID[2..])).is_none());
+        assert!(upstream_txid("test", &"0".repeat(64)).is_none());
+    }
+
+    #[test]
+    fn test_upstream_outpoint() {
+        let outpoint = upstream_outpoint("test", &format!("{}:1", TXID)).unwrap();
+        assert_eq!(outpoint.vout, 1);
+        assert!(upstream_outpoint("test", &format!("{}:0", TXID)).is_some());
+        assert!(upstream_outpoint("test", TXID).is_none());
+        for vout in ["", "01", "+1", "-1", "4294967296", "1:2"] {
+            assert!(upstream_outpoint("test", &format!("{}:{}", TXID, vout)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_upstream_address() {
+        let mainnet = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let testnet = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+        assert!(upstream_address("test", mainnet, Network::Bitcoin).is_some());
+        assert!(upstream_address("test", mainnet, Network::Signet).is_none());
+        assert_eq!(
+            upstream_address("test", testnet, Network::Signet).as_deref(),
+            Some(testnet)
+        );
+        assert!(upstream_address("test", testnet, Network::Testnet).is_some());
+        assert!(upstream_address("test", testnet, Network::Bitcoin).is_none());
+        assert!(upstream_address("test", "bc1p", Network::Bitcoin).is_none());
+    }
+}
@@ -0,0 +1,335 @@
+//! Accounting of the outbound upstream requests, per monitoring task and per day,
+//! with optional daily budgets per upstream. Third-party explorers are rate-limited
+//! by plan, so once an upstream exhausted its `UPSTREAM_DAILY_BUDGETS` entry, only
+//! the tasks of `BUDGET_CRITICAL_TASKS` may still call it until the next UTC day.
+//!
+//! Requests are attributed to the task whose future runs them, see [`in_task`];
+//! requests sent elsewhere, e.g. by API handlers, count as [`OTHER_TASK`].
+
+use chrono::{NaiveDate, Utc};
+use dotenvy::dotenv;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    sync::{Mutex, OnceLock},
+};
+use tracing::{info, warn};
+
+use crate::tasks::{BRIDGE_STATUS_TASK, NETWORK_STATUS_TASK};
+
+/// Task of the requests sent outside of any monitoring task
+pub const OTHER_TASK: &str = "other";
+
+/// Request budget configuration
+#[derive(Debug)]
+pub struct RequestBudgetConfig {
+    /// Max requests per UTC day by upstream, e.g. `explorer`; unbounded if missing
+    daily_budgets: BTreeMap<String, u64>,
+    /// Tasks exempt from the budgets
+    critical_tasks: Vec<String>,
+}
+
+impl RequestBudgetConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let daily_budgets: BTreeMap<String, u64> = std::env::var("UPSTREAM_DAILY_BUDGETS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                serde_json::from_str(&s)
+                    .expect("to parse UPSTREAM_DAILY_BUDGETS as JSON request counts by upstream")
+            })
+            .unwrap_or_default();
+
+        let critical_tasks: Vec<String> = std::env::var("BUDGET_CRITICAL_TASKS")
+            .unwrap_or(format!("{},{}", NETWORK_STATUS_TASK, BRIDGE_STATUS_TASK))
+            .split(',')
+            .map(str::trim)
+            .filter(|task| !task.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        info!(
+            ?daily_budgets,
+            ?critical_tasks,
+            "Request budget configuration"
+        );
+
+        RequestBudgetConfig {
+            daily_budgets,
+            critical_tasks,
+        }
+    }
+
+    fn daily_budget(&self, upstream: &str) -> Option<u64> {
+        self.daily_budgets.get(upstream).copied()
+    }
+
+    fn is_critical(&self, task: &str) -> bool {
+        self.critical_tasks.iter().any(|t| t == task)
+    }
+}
+
+tokio::task_local! {
+    /// Monitoring task the current future runs for
+    static CURRENT_TASK: &'static str;
+}
+
+/// Runs `future` attributing its upstream requests to the task `name`. Futures it
+/// spawns are not covered and count as [`OTHER_TASK`].
+pub async fn in_task<F: Future>(name: &'static str, future: F) -> F::Output {
+    CURRENT_TASK.scope(name, future).await
+}
+
+/// Task of the current future, [`OTHER_TASK`] outside of [`in_task`]
+pub fn current_task() -> &'static str {
+    CURRENT_TASK.try_with(|name| *name).unwrap_or(OTHER_TASK)
+}
+
+/// Requests by upstream
+type Counts = BTreeMap<&'static str, u64>;
+
+/// Request counts of the current UTC day
+#[derive(Debug)]
+struct RequestCounts {
+    day: NaiveDate,
+    /// By upstream, requests sent
+    sent: Counts,
+    /// By upstream, requests rejected for an exhausted budget
+    rejected: Counts,
+    /// By task, then upstream
+    by_task: BTreeMap<&'static str, Counts>,
+    /// Requests of the refresh cycle in progress, by task
+    current_cycle: BTreeMap<&'static str, Counts>,
+    /// Requests of the last completed refresh cycle, by task
+    last_cycle: BTreeMap<&'static str, Counts>,
+}
+
+impl RequestCounts {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            sent: Counts::new(),
+            rejected: Counts::new(),
+            by_task: BTreeMap::new(),
+            current_cycle: BTreeMap::new(),
+            last_cycle: BTreeMap::new(),
+        }
+    }
+
+    /// Starts over the daily counts on a new day, keeping the cycle counts
+    fn roll_over(&mut self, day: NaiveDate) {
+        if day != self.day {
+            self.day = day;
+            self.sent.clear();
+            self.rejected.clear();
+            self.by_task.clear();
+        }
+    }
+
+    /// Counts a request of `task` to `upstream`, returning whether it may be sent
+    fn admit(
+        &mut self,
+        config: &RequestBudgetConfig,
+        task: &'static str,
+        upstream: &'static str,
+    ) -> bool {
+        let sent = self.sent.entry(upstream).or_default();
+        let exhausted = config
+            .daily_budget(upstream)
+            .is_some_and(|budget| *sent >= budget);
+        if exhausted && !config.is_critical(task) {
+            let rejected = self.rejected.entry(upstream).or_default();
+            if *rejected == 0 {
+                warn!(
+                    upstream,
+                    "Daily request budget exhausted, pausing non-critical fetches"
+                );
+            }
+            *rejected += 1;
+            return false;
+        }
+        *sent += 1;
+        *self
+            .by_task
+            .entry(task)
+            .or_default()
+            .entry(upstream)
+            .or_default() += 1;
+        *self
+            .current_cycle
+            .entry(task)
+            .or_default()
+            .entry(upstream)
+            .or_default() += 1;
+        true
+    }
+
+    fn end_cycle(&mut self, task: &'static str) {
+        let counts = self.current_cycle.remove(task).unwrap_or_default();
+        self.last_cycle.insert(task, counts);
+    }
+}
+
+/// Budget and request counts, set once at startup
+struct RequestBudget {
+    config: RequestBudgetConfig,
+    counts: Mutex<RequestCounts>,
+}
+
+/// Upstream clients are created throughout the backend, so the accounting is
+/// global rather than passed to each of them, as for the failure injection.
+static BUDGET: OnceLock<RequestBudget> = OnceLock::new();
+
+/// Enables the request accounting with the budgets of `config`
+pub fn init(config: RequestBudgetConfig) {
+    let budget = RequestBudget {
+        config,
+        counts: Mutex::new(RequestCounts::new(Utc::now().date_naive())),
+    };
+    if BUDGET.set(budget).is_err() {
+        warn!("Request budget already configured");
+    }
+}
+
+/// Request not sent as the daily budget of its upstream is exhausted
+#[derive(Debug)]
+pub struct BudgetExceeded {
+    upstream: &'static str,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "daily request budget of the {} upstream exhausted",
+            self.upstream
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Counts a request to `upstream`, e.g. `explorer`, as named for the failure
+/// injection. Call before sending each request, retries included.
+pub fn admit(upstream: &'static str) -> Result<(), BudgetExceeded> {
+    let Some(budget) = BUDGET.get() else {
+        return Ok(());
+    };
+    let mut counts = budget.counts.lock().unwrap();
+    counts.roll_over(Utc::now().date_naive());
+    if counts.admit(&budget.config, current_task(), upstream) {
+        Ok(())
+    } else {
+        Err(BudgetExceeded { upstream })
+    }
+}
+
+/// Closes the refresh cycle of `task`, see [`RequestUsage`]
+pub fn end_cycle(task: &'static str) {
+    if let Some(budget) = BUDGET.get() {
+        budget.counts.lock().unwrap().end_cycle(task);
+    }
+}
+
+/// Requests of the day to an upstream
+#[derive(Serialize, Debug, PartialEq)]
+pub struct UpstreamUsage {
+    /// Requests sent
+    sent: u64,
+    /// Requests not sent for an exhausted budget
+    rejected: u64,
+    daily_budget: Option<u64>,
+    /// Whether non-critical fetches are paused
+    paused: bool,
+}
+
+/// Outbound requests of the backend
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RequestUsage {
+    /// UTC day the daily counts are for
+    day: NaiveDate,
+    /// Tasks still calling upstreams of exhausted budgets
+    critical_tasks: Vec<String>,
+    /// By upstream
+    upstreams: BTreeMap<&'static str, UpstreamUsage>,
+    /// Requests sent today, by task and upstream
+    today: BTreeMap<&'static str, Counts>,
+    /// Requests sent in the last completed refresh cycle, by task and upstream
+    last_cycle: BTreeMap<&'static str, Counts>,
+}
+
+impl RequestUsage {
+    fn new(config: &RequestBudgetConfig, counts: &RequestCounts) -> Self {
+        let upstreams = counts
+            .sent
+            .iter()
+            .map(|(upstream, sent)| {
+                let daily_budget = config.daily_budget(upstream);
+                let usage = UpstreamUsage {
+                    sent: *sent,
+                    rejected: counts.rejected.get(upstream).copied().unwrap_or(0),
+                    daily_budget,
+                    paused: daily_budget.is_some_and(|budget| *sent >= budget),
+                };
+                (*upstream, usage)
+            })
+            .collect();
+        Self {
+            day: counts.day,
+            critical_tasks: config.critical_tasks.clone(),
+            upstreams,
+            today: counts.by_task.clone(),
+            last_cycle: counts.last_cycle.clone(),
+        }
+    }
+}
+
+/// Current request counts, `None` before [`init`]
+pub fn usage() -> Option<RequestUsage> {
+    let budget = BUDGET.get()?;
+    let mut counts = budget.counts.lock().unwrap();
+    counts.roll_over(Utc::now().date_naive());
+    Some(RequestUsage::new(&budget.config, &counts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestBudgetConfig, RequestCounts, RequestUsage};
+    use chrono::NaiveDate;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_request_budget() {
+        let config = RequestBudgetConfig {
+            daily_budgets: BTreeMap::from([("explorer".to_string(), 2)]),
+            critical_tasks: vec!["bridge_status".to_string()],
+        };
+        let day = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let mut counts = RequestCounts::new(day);
+
+        assert!(counts.admit(&config, "activity_stats", "explorer"));
+        assert!(counts.admit(&config, "bundler_stats", "explorer"));
+        // Non-critical fetches pause once the budget is spent
+        assert!(!counts.admit(&config, "activity_stats", "explorer"));
+        assert!(counts.admit(&config, "bridge_status", "explorer"));
+        assert!(counts.admit(&config, "activity_stats", "esplora"));
+        counts.end_cycle("activity_stats");
+
+        let usage = RequestUsage::new(&config, &counts);
+        let explorer = &usage.upstreams["explorer"];
+        assert_eq!((explorer.sent, explorer.rejected), (3, 1));
+        assert!(explorer.paused);
+        assert!(!usage.upstreams["esplora"].paused);
+        assert_eq!(usage.today["activity_stats"]["explorer"], 1);
+        assert_eq!(usage.last_cycle["activity_stats"]["esplora"], 1);
+        assert!(!usage.last_cycle.contains_key("bridge_status"));
+
+        counts.roll_over(day.succ_opt().unwrap());
+        assert!(counts.admit(&config, "activity_stats", "explorer"));
+        assert!(counts.last_cycle.contains_key("activity_stats"));
+    }
+}
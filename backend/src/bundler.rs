@@ -0,0 +1,241 @@
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use jsonrpsee::{core::client::ClientT, http_client::HttpClient};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{error, warn};
+
+use crate::{
+    alerts::{Alerts, Severity},
+    bundles::{fetch_bundle_pages, BundleTracker, SharedBundleAnalytics},
+    checks::rpc_failure,
+    config::{BundlerMonitoringConfig, NetworkConfig},
+    degradation,
+    explorer::ExplorerClient,
+    polling::AdaptiveInterval,
+    tasks::{TaskRegistry, BUNDLER_STATS_TASK},
+    utils::create_rpc_client,
+    watched::WatchedContracts,
+};
+
+/// Alert raised while the bundler holds user ops without submitting bundles
+const BUNDLING_STALLED_ALERT: &str = "bundler_stalled";
+
+/// Bundler mempool and submission stats passed to dashboard
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BundlerStats {
+    /// User ops waiting in the bundler mempool across the watched entry points,
+    /// `None` if the debug RPC is unavailable or no entry point is watched
+    pub(crate) pending_user_ops: Option<usize>,
+    last_bundle_txid: Option<String>,
+    pub(crate) last_bundle_at: Option<DateTime<Utc>>,
+    /// User ops are pending but no bundle was submitted for too long
+    bundling_stalled: bool,
+}
+
+impl BundlerStats {
+    /// Getter for `bundling_stalled`
+    pub fn is_bundling_stalled(&self) -> bool {
+        self.bundling_stalled
+    }
+
+    /// Whether pending user ops have not been bundled within `threshold_s`
+    fn is_stalled(&self, now: DateTime<Utc>, threshold_s: u64) -> bool {
+        if self.pending_user_ops.unwrap_or(0) == 0 {
+            return false;
+        }
+        match self.last_bundle_at {
+            Some(at) => (now - at).num_seconds() > threshold_s as i64,
+            None => true,
+        }
+    }
+}
+
+/// Shared bundler stats
+pub type SharedBundlerStats = Arc<RwLock<BundlerStats>>;
+
+/// Periodically fetch bundler stats and update shared state
+#[allow(clippy::too_many_arguments)]
+pub async fn bundler_stats_task<E: ExplorerClient>(
+    state: SharedBundlerStats,
+    analytics: SharedBundleAnalytics,
+    explorer: E,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    watched: WatchedContracts,
+    network_config: &NetworkConfig,
+    config: &BundlerMonitoringConfig,
+) {
+    tasks
+        .register(BUNDLER_STATS_TASK, config.stats_refetch_interval())
+        .await;
+    let mut interval = AdaptiveInterval::new(BUNDLER_STATS_TASK, config.stats_refetch_interval());
+    let bundler_rpc = create_rpc_client(network_config.bundler_rpc_url());
+    let analytics_window = Duration::hours(config.analytics_window_h() as i64);
+    let mut tracker = BundleTracker::default();
+    // Latest bundle, kept while the bundles pages are unchanged
+    let mut last_bundle = None;
+    // Unchanged pages are only skipped once processed
+    let mut pages_processed = false;
+
+    loop {
+        interval.tick().await;
+        tasks.start_refresh(BUNDLER_STATS_TASK).await;
+
+        // Entry points may be edited at runtime
+        let entry_points = watched.entry_points().await;
+        let pending_user_ops = count_pending_user_ops(&bundler_rpc, &entry_points).await;
+        let now = Utc::now();
+        let bundles = fetch_bundle_pages(
+            &explorer,
+            config.bundles_query_url(),
+            now - analytics_window,
+            pages_processed,
+        )
+        .await;
+        let upstream_healthy = pending_user_ops.is_some() && bundles.is_ok();
+        pages_processed = bundles.is_ok();
+        let pages = match bundles {
+            Ok(Some(pages)) => {
+                last_bundle = pages.first().and_then(parse_last_bundle);
+                pages
+            }
+            // No new bundle, only the mempool and the window moved
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                error!(error = %e, "Bundles query failed");
+                last_bundle = None;
+                Vec::new()
+            }
+        };
+        if pages_processed {
+            let added = tracker.update(&pages, pending_user_ops, now, analytics_window);
+            analytics
+                .write()
+                .await
+                .update(&tracker, added, now, analytics_window);
+        }
+        let (last_bundle_txid, last_bundle_at) = last_bundle.clone().unzip();
+
+        let mut stats = BundlerStats {
+            pending_user_ops,
+            last_bundle_txid,
+            last_bundle_at,
+            bundling_stalled: false,
+        };
+        stats.bundling_stalled = stats.is_stalled(now, config.stall_threshold_s());
+
+        if stats.bundling_stalled {
+            alerts
+                .raise(
+                    BUNDLING_STALLED_ALERT.to_string(),
+                    Severity::Critical,
+                    format!(
+                        "Bundler holds {} user ops but submitted no bundle for over {}s",
+                        stats.pending_user_ops.unwrap_or(0),
+                        config.stall_threshold_s()
+                    ),
+                )
+                .await;
+        } else {
+            alerts.resolve(BUNDLING_STALLED_ALERT).await;
+        }
+
+        *state.write().await = stats;
+        interval.adapt(upstream_healthy, &tasks).await;
+        tasks.record_refresh(BUNDLER_STATS_TASK).await;
+    }
+}
+
+/// Fetch the number of user ops in the bundler mempool via the ERC-4337 debug API
+async fn get_pending_user_ops(bundler_rpc: &HttpClient, entry_point: &str) -> Option<usize> {
+    let user_ops: Result<Vec<Value>, _> = bundler_rpc
+        .request("debug_bundler_dumpMempool", (entry_point,))
+        .await;
+    match user_ops {
+        Ok(user_ops) => Some(user_ops.len()),
+        Err(e) => {
+            warn!(error = %e, "Bundler mempool query failed");
+            degradation::record_failure("bundler", rpc_failure(&e).reason);
+            None
+        }
+    }
+}
+
+/// Sum of the user ops pending for each entry point, `None` if any query failed
+async fn count_pending_user_ops(
+    bundler_rpc: &HttpClient,
+    entry_points: &[String],
+) -> Option<usize> {
+    if entry_points.is_empty() {
+        return None;
+    }
+    let mut total = 0;
+    for entry_point in entry_points {
+        total += get_pending_user_ops(bundler_rpc, entry_point).await?;
+    }
+    Some(total)
+}
+
+/// Extract tx hash and time of the latest bundle from an explorer bundles page
+fn parse_last_bundle(response: &Value) -> Option<(String, DateTime<Utc>)> {
+    let bundle = response.get("items")?.as_array()?.first()?;
+    let txid = bundle.get("transaction_hash")?.as_str()?.to_string();
+    let at = bundle
+        .get("timestamp")?
+        .as_str()?
+        .parse::<DateTime<Utc>>()
+        .ok()?;
+
+    Some((txid, at))
+}
+
+/// Return latest bundler stats
+pub async fn get_bundler_stats(state: SharedBundlerStats) -> Json<BundlerStats> {
+    let data = state.read().await.clone();
+    Json(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_last_bundle, BundlerStats};
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_last_bundle() {
+        let response = json!({
+            "items": [
+                {"transaction_hash": "0xbeef", "timestamp": "2025-01-01T00:00:00.000000Z", "total_ops": 2},
+                {"transaction_hash": "0xdead", "timestamp": "2024-12-31T00:00:00.000000Z", "total_ops": 1},
+            ]
+        });
+        let (txid, at) = parse_last_bundle(&response).unwrap();
+        assert_eq!(txid, "0xbeef");
+        assert_eq!(at.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+        assert_eq!(parse_last_bundle(&json!({ "items": [] })), None);
+    }
+
+    #[test]
+    fn test_bundling_stalled() {
+        let now = Utc::now();
+        let mut stats = BundlerStats {
+            pending_user_ops: Some(0),
+            last_bundle_at: Some(now - Duration::seconds(600)),
+            ..Default::default()
+        };
+        assert!(!stats.is_stalled(now, 300));
+
+        stats.pending_user_ops = Some(3);
+        assert!(stats.is_stalled(now, 300));
+
+        stats.last_bundle_at = Some(now - Duration::seconds(60));
+        assert!(!stats.is_stalled(now, 300));
+
+        stats.last_bundle_at = None;
+        assert!(stats.is_stalled(now, 300));
+    }
+}
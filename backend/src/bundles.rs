@@ -0,0 +1,546 @@
+use axum::{extract::Query, http::StatusCode, Json};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+use tracing::error;
+
+use crate::{
+    clickhouse::{ClickHouseTable, MAX_RANGE_ROWS},
+    explorer::ExplorerClient,
+    store::{StatsStore, Timestamped},
+    utils::{parse_duration, push_bounded},
+};
+
+/// Max number of explorer pages fetched per refresh, when catching up on a window
+const MAX_BUNDLE_PAGES: usize = 20;
+
+/// Bundle submitted to the entry point, as indexed from its `UserOperationEvent` logs
+#[derive(Clone, Debug, PartialEq)]
+struct Bundle {
+    transaction_hash: String,
+    bundler: Option<String>,
+    /// Time of the block including the bundle
+    at: DateTime<Utc>,
+    user_ops: u64,
+    /// Time between the bundler mempool holding user ops and their inclusion, if
+    /// they were seen waiting
+    inclusion_delay: Option<Duration>,
+}
+
+impl Bundle {
+    fn record(&self) -> BundleRecord {
+        BundleRecord {
+            transaction_hash: self.transaction_hash.clone(),
+            bundler: self.bundler.clone(),
+            at: self.at,
+            user_ops: self.user_ops,
+            inclusion_delay_s: self.inclusion_delay.map(|delay| delay.num_seconds()),
+        }
+    }
+}
+
+/// Bundle as stored for range queries, e.g. in ClickHouse
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BundleRecord {
+    transaction_hash: String,
+    bundler: Option<String>,
+    at: DateTime<Utc>,
+    user_ops: u64,
+    inclusion_delay_s: Option<i64>,
+}
+
+impl Timestamped for BundleRecord {
+    fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+}
+
+/// Parses the bundles of an explorer bundles page, latest first
+fn parse_bundles(page: &Value) -> Vec<Bundle> {
+    let Some(items) = page.get("items").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            // The bundler is an address object in recent explorer versions
+            let bundler = item.get("bundler").and_then(|bundler| {
+                bundler
+                    .as_str()
+                    .or_else(|| bundler.get("hash").and_then(Value::as_str))
+            });
+            Some(Bundle {
+                transaction_hash: item.get("transaction_hash")?.as_str()?.to_string(),
+                bundler: bundler.map(str::to_lowercase),
+                at: item.get("timestamp")?.as_str()?.parse().ok()?,
+                user_ops: item.get("total_ops").and_then(Value::as_u64).unwrap_or(0),
+                inclusion_delay: None,
+            })
+        })
+        .collect()
+}
+
+/// Query parameters of the page after those fetched with `next_page_params`,
+/// e.g. the block number and index of the last bundle
+fn query_params(next_page_params: &Map<String, Value>) -> HashMap<&str, String> {
+    next_page_params
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
+            (key.as_str(), value)
+        })
+        .collect()
+}
+
+/// Fetches the explorer bundles pages back to `since`, latest first.
+///
+/// With `skip_unchanged`, returns `None` when the latest page did not change since
+/// the previous fetch, as then neither did the older ones.
+pub async fn fetch_bundle_pages(
+    explorer: &impl ExplorerClient,
+    query_url: &str,
+    since: DateTime<Utc>,
+    skip_unchanged: bool,
+) -> Result<Option<Vec<Value>>, anyhow::Error> {
+    let mut pages = Vec::new();
+    let mut next_page_params = Map::new();
+    while pages.len() < MAX_BUNDLE_PAGES {
+        let params = query_params(&next_page_params);
+        let page = if pages.is_empty() && skip_unchanged {
+            match explorer.get_json_if_modified(query_url, &params).await? {
+                Some(page) => page,
+                None => return Ok(None),
+            }
+        } else {
+            explorer.get_json(query_url, &params).await?
+        };
+        let reached_since = parse_bundles(&page)
+            .last()
+            .is_none_or(|oldest| oldest.at < since);
+        let next = page
+            .get("next_page_params")
+            .and_then(Value::as_object)
+            .cloned();
+        pages.push(page);
+        match next {
+            Some(next) if !reached_since => next_page_params = next,
+            _ => break,
+        }
+    }
+    Ok(Some(pages))
+}
+
+/// Bundles per hour of the window
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct HourlyBundles {
+    start: DateTime<Utc>,
+    bundles: usize,
+    user_ops: u64,
+}
+
+/// Bundler efficiency over the trailing window, passed to dashboard
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct BundleAnalytics {
+    window_hours: u64,
+    bundles: usize,
+    user_ops: u64,
+    bundles_per_hour: f64,
+    /// `None` without bundles
+    avg_ops_per_bundle: Option<f64>,
+    /// Time between the bundler mempool first holding user ops, as sampled at
+    /// each refresh, and the next bundle; `None` if no bundle followed waiting ops
+    avg_inclusion_delay_s: Option<f64>,
+    max_inclusion_delay_s: Option<i64>,
+    /// Hours with bundles, oldest first
+    hourly: Vec<HourlyBundles>,
+    /// Number of bundles per bundler address
+    bundlers: BTreeMap<String, usize>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+/// User ops included within one minute
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MinuteOps {
+    start: DateTime<Utc>,
+    bundles: usize,
+    user_ops: u64,
+}
+
+/// Most recent minutes with bundles, oldest first. They are only kept in memory,
+/// so the series covers up to `HISTORY_BUFFER_LEN` minutes with activity.
+#[derive(Debug)]
+pub struct OpsPerMinute {
+    minutes: VecDeque<MinuteOps>,
+    max_minutes: usize,
+}
+
+impl OpsPerMinute {
+    pub fn new(max_minutes: usize) -> Self {
+        Self {
+            minutes: VecDeque::new(),
+            max_minutes,
+        }
+    }
+
+    /// Accounts a bundle of `user_ops` included at `at`. Bundles are expected
+    /// roughly in inclusion order; those older than the retained minutes are
+    /// dropped.
+    fn record(&mut self, at: DateTime<Utc>, user_ops: u64) {
+        let start = at.duration_trunc(Duration::minutes(1)).unwrap_or(at);
+        if self.minutes.back().is_none_or(|last| last.start < start) {
+            let minute = MinuteOps {
+                start,
+                bundles: 0,
+                user_ops: 0,
+            };
+            push_bounded(&mut self.minutes, minute, self.max_minutes);
+        }
+        if let Some(minute) = self
+            .minutes
+            .iter_mut()
+            .rev()
+            .find(|minute| minute.start == start)
+        {
+            minute.bundles += 1;
+            minute.user_ops += user_ops;
+        }
+    }
+}
+
+/// Bundle analytics, the user ops per minute and the indexed bundles, updated by
+/// the bundler task
+#[derive(Debug)]
+pub struct BundleHistory {
+    analytics: BundleAnalytics,
+    ops_per_minute: OpsPerMinute,
+    bundles: Box<dyn StatsStore<BundleRecord>>,
+}
+
+impl BundleHistory {
+    pub fn new(max_minutes: usize, bundles: Box<dyn StatsStore<BundleRecord>>) -> Self {
+        Self {
+            analytics: BundleAnalytics::default(),
+            ops_per_minute: OpsPerMinute::new(max_minutes),
+            bundles,
+        }
+    }
+
+    /// Publishes the analytics of `tracker` after an update added `bundles`
+    pub fn update(
+        &mut self,
+        tracker: &BundleTracker,
+        bundles: Vec<BundleRecord>,
+        now: DateTime<Utc>,
+        window: Duration,
+    ) {
+        self.analytics = tracker.analytics(now, window);
+        for bundle in &bundles {
+            self.ops_per_minute.record(bundle.at, bundle.user_ops);
+        }
+        self.bundles.save_events(bundles);
+    }
+
+    /// Number of bundles not stored because the store fell behind
+    pub fn dropped_writes(&self) -> u64 {
+        self.bundles.dropped_writes()
+    }
+}
+
+/// Shared bundle analytics
+pub type SharedBundleAnalytics = Arc<RwLock<BundleHistory>>;
+
+/// Bundles of the trailing window, accumulated across refreshes of the bundler task
+#[derive(Debug, Default)]
+pub struct BundleTracker {
+    /// By transaction hash
+    bundles: HashMap<String, Bundle>,
+    /// Since when the bundler mempool holds user ops not yet bundled
+    pending_since: Option<DateTime<Utc>>,
+}
+
+impl BundleTracker {
+    /// Records the bundles of the fetched pages and the mempool size at `now`,
+    /// forgetting the bundles older than `window`.
+    ///
+    /// Returns the new bundles within the window, oldest first.
+    pub fn update(
+        &mut self,
+        pages: &[Value],
+        pending_user_ops: Option<usize>,
+        now: DateTime<Utc>,
+        window: Duration,
+    ) -> Vec<BundleRecord> {
+        let mut new_bundles: Vec<Bundle> = pages
+            .iter()
+            .flat_map(parse_bundles)
+            .filter(|bundle| !self.bundles.contains_key(&bundle.transaction_hash))
+            .collect();
+        new_bundles.sort_by_key(|bundle| bundle.at);
+
+        let mut added = Vec::new();
+        for mut bundle in new_bundles {
+            // The first bundle after user ops started waiting includes them
+            if let Some(since) = self.pending_since.filter(|since| *since <= bundle.at) {
+                bundle.inclusion_delay = Some(bundle.at - since);
+                self.pending_since = None;
+            }
+            if now - bundle.at <= window {
+                added.push(bundle.record());
+            }
+            self.bundles.insert(bundle.transaction_hash.clone(), bundle);
+        }
+
+        match pending_user_ops {
+            Some(0) => self.pending_since = None,
+            Some(_) => self.pending_since = self.pending_since.or(Some(now)),
+            // Unknown mempool, keep the previous observation
+            None => {}
+        }
+        self.bundles.retain(|_, bundle| now - bundle.at <= window);
+        added
+    }
+
+    /// Analytics of the tracked bundles
+    pub fn analytics(&self, now: DateTime<Utc>, window: Duration) -> BundleAnalytics {
+        let mut hourly: BTreeMap<DateTime<Utc>, HourlyBundles> = BTreeMap::new();
+        let mut bundlers: BTreeMap<String, usize> = BTreeMap::new();
+        let mut user_ops = 0;
+        let mut delays = Vec::new();
+        for bundle in self.bundles.values() {
+            let start = bundle
+                .at
+                .duration_trunc(Duration::hours(1))
+                .unwrap_or(bundle.at);
+            let hour = hourly.entry(start).or_insert(HourlyBundles {
+                start,
+                bundles: 0,
+                user_ops: 0,
+            });
+            hour.bundles += 1;
+            hour.user_ops += bundle.user_ops;
+            user_ops += bundle.user_ops;
+            if let Some(bundler) = &bundle.bundler {
+                *bundlers.entry(bundler.clone()).or_default() += 1;
+            }
+            delays.extend(bundle.inclusion_delay.map(|delay| delay.num_seconds()));
+        }
+
+        let bundles = self.bundles.len();
+        let window_hours = window.num_hours().max(1) as u64;
+        BundleAnalytics {
+            window_hours,
+            bundles,
+            user_ops,
+            bundles_per_hour: bundles as f64 / window_hours as f64,
+            avg_ops_per_bundle: (bundles > 0).then(|| user_ops as f64 / bundles as f64),
+            avg_inclusion_delay_s: (!delays.is_empty())
+                .then(|| delays.iter().sum::<i64>() as f64 / delays.len() as f64),
+            max_inclusion_delay_s: delays.iter().max().copied(),
+            hourly: hourly.into_values().collect(),
+            bundlers,
+            updated_at: Some(now),
+        }
+    }
+}
+
+/// Return the bundle analytics
+pub async fn get_bundle_analytics(state: SharedBundleAnalytics) -> Json<BundleAnalytics> {
+    Json(state.read().await.analytics.clone())
+}
+
+/// Query parameters of the user ops per minute endpoint
+#[derive(Deserialize, Debug)]
+pub struct OpsPerMinuteQuery {
+    /// Length of the series, e.g. `6h`
+    window: Option<String>,
+}
+
+/// Minutes with bundles within the requested window, oldest first
+#[derive(Serialize, Debug)]
+pub struct OpsPerMinuteResponse {
+    window_s: i64,
+    /// Start of the oldest retained minute, later than the start of the window
+    /// once the series is full
+    available_since: Option<DateTime<Utc>>,
+    minutes: Vec<MinuteOps>,
+}
+
+/// Return the user ops included per minute
+pub async fn get_ops_per_minute(
+    Query(query): Query<OpsPerMinuteQuery>,
+    state: SharedBundleAnalytics,
+) -> Result<Json<OpsPerMinuteResponse>, StatusCode> {
+    let window = parse_duration(query.window.as_deref().unwrap_or("1h"))
+        .filter(|window| *window > Duration::zero())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let since = Utc::now() - window;
+    let state = state.read().await;
+    let minutes = &state.ops_per_minute.minutes;
+    Ok(Json(OpsPerMinuteResponse {
+        window_s: window.num_seconds(),
+        available_since: minutes.front().map(|minute| minute.start),
+        minutes: minutes
+            .iter()
+            .filter(|minute| minute.start >= since)
+            .cloned()
+            .collect(),
+    }))
+}
+
+/// Query parameters of the bundle range endpoint
+#[derive(Deserialize, Debug)]
+pub struct BundleRangeQuery {
+    /// RFC 3339 start of the range, an hour before `to` when unset
+    from: Option<DateTime<Utc>>,
+    /// RFC 3339 end of the range, excluded; now when unset
+    to: Option<DateTime<Utc>>,
+}
+
+/// Bundles included within the requested range, oldest first
+#[derive(Serialize, Debug)]
+pub struct BundleRangeResponse {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    /// Time of the oldest bundle kept in memory, `None` when served from ClickHouse,
+    /// which holds the full history
+    available_since: Option<DateTime<Utc>>,
+    /// The range holds more than `MAX_RANGE_ROWS` bundles, only the oldest are returned
+    truncated: bool,
+    bundles: Vec<BundleRecord>,
+}
+
+/// Return the bundles included within a range, queried from ClickHouse when
+/// configured, from the bundles kept in memory otherwise
+pub async fn get_bundle_range(
+    Query(query): Query<BundleRangeQuery>,
+    state: SharedBundleAnalytics,
+    clickhouse: Option<ClickHouseTable>,
+) -> Result<Json<BundleRangeResponse>, StatusCode> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::hours(1));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (available_since, mut bundles) = match clickhouse {
+        Some(table) => {
+            let bundles = table.query_range(from, to).await.map_err(|e| {
+                error!(error = %e, "Bundle range query failed");
+                StatusCode::BAD_GATEWAY
+            })?;
+            (None, bundles)
+        }
+        None => {
+            let state = state.read().await;
+            (state.bundles.oldest(), state.bundles.query_range(from, to))
+        }
+    };
+    let truncated = bundles.len() >= MAX_RANGE_ROWS;
+    bundles.truncate(MAX_RANGE_ROWS);
+    Ok(Json(BundleRangeResponse {
+        from,
+        to,
+        available_since,
+        truncated,
+        bundles,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_bundles, query_params, BundleTracker, OpsPerMinute};
+    use chrono::{Duration, TimeZone, Utc};
+    use serde_json::{json, Value};
+
+    fn page(bundles: &[(&str, &str, u64)]) -> Value {
+        let items: Vec<Value> = bundles
+            .iter()
+            .map(|(txid, timestamp, total_ops)| {
+                json!({
+                    "transaction_hash": txid,
+                    "bundler": {"hash": "0xB0B"},
+                    "timestamp": timestamp,
+                    "total_ops": total_ops,
+                })
+            })
+            .collect();
+        json!({ "items": items, "next_page_params": {"block_number": 10, "items_count": 50} })
+    }
+
+    #[test]
+    fn test_parse_bundles() {
+        let page = page(&[("0xbeef", "2025-01-01T00:00:00.000000Z", 2)]);
+        let bundles = parse_bundles(&page);
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].bundler.as_deref(), Some("0xb0b"));
+        assert_eq!(bundles[0].user_ops, 2);
+
+        let next_page_params = page["next_page_params"].as_object().unwrap();
+        assert_eq!(query_params(next_page_params)["block_number"], "10");
+    }
+
+    #[test]
+    fn test_bundle_analytics() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let window = Duration::hours(24);
+        let mut tracker = BundleTracker::default();
+
+        // User ops first seen waiting at 10:00
+        tracker.update(&[], Some(3), now - Duration::hours(2), window);
+        let pages = [page(&[
+            ("0x3", "2025-01-01T11:30:00Z", 1),
+            ("0x2", "2025-01-01T10:05:00Z", 3),
+            ("0x1", "2024-12-31T11:00:00Z", 4), // Outside of the window
+        ])];
+        let added = tracker.update(&pages, Some(0), now, window);
+        let added: Vec<_> = added
+            .iter()
+            .map(|bundle| (bundle.transaction_hash.as_str(), bundle.inclusion_delay_s))
+            .collect();
+        assert_eq!(added, vec![("0x2", Some(300)), ("0x3", None)]);
+        // Pages overlapping with the previous ones are not counted twice
+        assert!(tracker.update(&pages, Some(0), now, window).is_empty());
+
+        let analytics = tracker.analytics(now, window);
+        assert_eq!(analytics.bundles, 2);
+        assert_eq!(analytics.user_ops, 4);
+        assert_eq!(analytics.avg_ops_per_bundle, Some(2.0));
+        assert_eq!(analytics.bundles_per_hour, 2.0 / 24.0);
+        // Only the first bundle after the wait includes the waiting user ops
+        assert_eq!(analytics.avg_inclusion_delay_s, Some(300.0));
+        assert_eq!(analytics.max_inclusion_delay_s, Some(300));
+        assert_eq!(analytics.hourly.len(), 2);
+        assert_eq!(analytics.bundlers["0xb0b"], 2);
+    }
+
+    #[test]
+    fn test_ops_per_minute() {
+        let at = |minute, second| {
+            Utc.with_ymd_and_hms(2025, 1, 1, 12, minute, second)
+                .unwrap()
+        };
+        let mut ops_per_minute = OpsPerMinute::new(2);
+
+        ops_per_minute.record(at(0, 10), 2);
+        ops_per_minute.record(at(1, 10), 1);
+        ops_per_minute.record(at(1, 50), 3);
+        // Late bundle of a retained minute
+        ops_per_minute.record(at(0, 30), 1);
+        ops_per_minute.record(at(2, 0), 4);
+
+        let minutes: Vec<_> = ops_per_minute
+            .minutes
+            .iter()
+            .map(|minute| (minute.start, minute.bundles, minute.user_ops))
+            .collect();
+        assert_eq!(minutes, vec![(at(1, 0), 2, 4), (at(2, 0), 1, 4)]);
+    }
+}
@@ -0,0 +1,245 @@
+//! Canary mode: the API of other backends, e.g. one polling the mock RPC or a
+//! staging network, is exposed under `/api/<environment>/...` next to the live
+//! data, so that dashboard changes can be checked against controlled data
+//! alongside production.
+//!
+//! Responses keep the `network` field of the backend that produced them and are
+//! tagged with the `environment` they were fetched from. Callers need the same
+//! token scopes as for the path on this backend, e.g. `/api/mock/admin/...` is
+//! guarded like `/api/admin/...`. Their token is not forwarded, the requests to the
+//! environment are sent with its own token from `CANARY_TOKENS`, if any.
+
+use axum::{
+    body::Body,
+    extract::{Path, RawQuery},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::error;
+
+use crate::{
+    auth::{ApiAuth, EndpointGroup},
+    config::CanaryConfig,
+};
+
+/// Inserts the environment into a JSON object body. Returns `None` for other bodies.
+fn with_environment_field(body: &[u8], environment: &str) -> Option<Vec<u8>> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let object = value.as_object_mut()?;
+    object.insert(
+        "environment".to_string(),
+        Value::String(environment.to_string()),
+    );
+    serde_json::to_vec(&value).ok()
+}
+
+/// Whether `/api/<environment>/...` would shadow the endpoints of this backend
+fn is_reserved(environment: &str) -> bool {
+    EndpointGroup::of_path(&format!("/api/{}", environment)).is_some()
+        || environment.is_empty()
+        || environment.contains('/')
+}
+
+/// Backend of an environment
+#[derive(Clone, Debug)]
+struct CanaryEnvironment {
+    name: String,
+    base_url: String,
+    /// Token the requests to the backend are sent with
+    token: Option<String>,
+}
+
+/// Forwards a GET request to the backend of an environment, if the caller may
+/// access `path` on this backend
+async fn get_canary(
+    environment: CanaryEnvironment,
+    path: String,
+    query: Option<String>,
+    headers: HeaderMap,
+    auth: ApiAuth,
+    client: reqwest::Client,
+) -> Response {
+    if let Err(status) = auth.check(&format!("/api/{}", path), &headers) {
+        return status.into_response();
+    }
+
+    let CanaryEnvironment {
+        name: environment,
+        base_url,
+        token,
+    } = environment;
+    let url = match query {
+        Some(query) => format!("{}/api/{}?{}", base_url, path, query),
+        None => format!("{}/api/{}", base_url, path),
+    };
+    let mut request = client.get(&url);
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            error!(error = %e, %environment, %url, "Canary request failed");
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
+    let status = response.status();
+    let content_type = response.headers().get(CONTENT_TYPE).cloned();
+    let body = match response.bytes().await {
+        Ok(body) => body,
+        Err(e) => {
+            error!(error = %e, %environment, %url, "Canary response failed");
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
+
+    let body = with_environment_field(&body, &environment).unwrap_or_else(|| body.to_vec());
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    if let Some(content_type) = content_type {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+/// Routes of the environments declared in `CANARY_ENVIRONMENTS`, guarded by the
+/// token scopes of `auth`.
+///
+/// Responses are buffered, so the event stream of other environments is not
+/// available.
+pub fn canary_routes(config: &CanaryConfig, auth: ApiAuth) -> Router {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_s()))
+        .build()
+        .expect("Failed to create canary HTTP client");
+
+    let mut router = Router::new();
+    for (environment, base_url) in config.environments() {
+        assert!(
+            !is_reserved(environment),
+            "CANARY_ENVIRONMENTS environment `{}` clashes with an API path",
+            environment
+        );
+        let route = format!("/api/{}/*path", environment);
+        let environment = CanaryEnvironment {
+            name: environment.clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: config.tokens().get(environment).cloned(),
+        };
+        let auth = auth.clone();
+        let client = client.clone();
+        router = router.route(
+            &route,
+            get(
+                move |Path(path): Path<String>, RawQuery(query): RawQuery, headers: HeaderMap| {
+                    get_canary(environment, path, query, headers, auth, client)
+                },
+            ),
+        );
+    }
+    router
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_canary, is_reserved, with_environment_field, CanaryEnvironment};
+    use crate::auth::{ApiAuth, ApiToken, EndpointGroup};
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_with_environment_field() {
+        let body = json!({ "deposits": [], "network": { "name": "mock" } }).to_string();
+        let tagged = with_environment_field(body.as_bytes(), "mock").unwrap();
+        let tagged: Value = serde_json::from_slice(&tagged).unwrap();
+        assert_eq!(
+            tagged,
+            json!({ "deposits": [], "network": { "name": "mock" }, "environment": "mock" })
+        );
+        assert!(with_environment_field(b"[1, 2]", "mock").is_none());
+
+        assert!(!is_reserved("mock"));
+        assert!(!is_reserved("staging"));
+        assert!(is_reserved("bridge"));
+        assert!(is_reserved("status"));
+        assert!(is_reserved("a/b"));
+    }
+
+    #[tokio::test]
+    async fn test_canary_auth() {
+        let mut server = mockito::Server::new_async().await;
+        let status = server
+            .mock("GET", "/api/status")
+            .match_header("authorization", "Bearer staging-token")
+            .with_body(r#"{"ok": true}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let admin = server
+            .mock("GET", "/api/admin/state_dump")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let admin_token: ApiToken = serde_json::from_value(json!({
+            "token": "caller-token",
+            "networks": ["testnet"],
+            "groups": ["admin"],
+        }))
+        .unwrap();
+        let auth = ApiAuth::new(
+            "testnet".to_string(),
+            vec![admin_token],
+            vec![EndpointGroup::Status],
+        );
+        let environment = CanaryEnvironment {
+            name: "staging".to_string(),
+            base_url: server.url(),
+            token: Some("staging-token".to_string()),
+        };
+        let get = |path: &str, authorization: Option<&'static str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(authorization) = authorization {
+                headers.insert(AUTHORIZATION, HeaderValue::from_static(authorization));
+            }
+            get_canary(
+                environment.clone(),
+                path.to_string(),
+                None,
+                headers,
+                auth.clone(),
+                reqwest::Client::new(),
+            )
+        };
+
+        // Public groups stay public, the caller's token is replaced by the
+        // environment's
+        assert_eq!(get("status", None).await.status(), StatusCode::OK);
+        assert_eq!(
+            get("status", Some("Bearer caller-token")).await.status(),
+            StatusCode::OK
+        );
+        status.assert_async().await;
+
+        // The proxied path's group is guarded as on this backend
+        assert_eq!(
+            get("admin/state_dump", None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            get("admin/state_dump", Some("Bearer caller-token"))
+                .await
+                .status(),
+            StatusCode::OK
+        );
+        admin.assert_async().await;
+    }
+}
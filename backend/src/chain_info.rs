@@ -0,0 +1,145 @@
+//! Chain parameters of the monitored network at `/api/chain_info`, so that the
+//! frontends do not hardcode them per network.
+
+use axum::Json;
+use jsonrpsee::{core::client::ClientT, http_client::HttpClient};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+use crate::{
+    config::{BridgeMonitoringConfig, NetworkConfig},
+    drift::fetch_chain_id,
+    network::parse_hex_quantity,
+    utils::create_rpc_client,
+    wallets::NATIVE_TOKEN_DECIMALS,
+};
+
+/// How long the parameters read from the L2 node are served before being refetched
+const CHAIN_INFO_TTL: Duration = Duration::from_secs(300);
+
+/// Number of recent blocks the block time is averaged over
+const BLOCK_TIME_SAMPLE_BLOCKS: u64 = 100;
+
+/// Chain parameters passed to dashboard
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ChainInfo {
+    /// Chain id reported by the L2 node, `CHAIN_ID` if it did not answer
+    chain_id: Option<u64>,
+    native_token_symbol: String,
+    native_token_decimals: u32,
+    /// Average seconds between the recent blocks, unset if the L2 node did not answer
+    block_time_s: Option<f64>,
+    /// Amount of every bridge deposit, see `BRIDGE_DENOMINATION_SATS`
+    bridge_denomination_sats: Option<u64>,
+    /// See `BLOCK_EXPLORER_URLS`
+    explorer_urls: BTreeMap<String, String>,
+}
+
+/// Number and timestamp of a block
+fn block_header(block: &Value) -> Option<(u64, u64)> {
+    Some((
+        parse_hex_quantity(&block["number"])?,
+        parse_hex_quantity(&block["timestamp"])?,
+    ))
+}
+
+/// Average seconds per block between two blocks
+fn average_block_time(earlier: (u64, u64), latest: (u64, u64)) -> Option<f64> {
+    let blocks = latest
+        .0
+        .checked_sub(earlier.0)
+        .filter(|blocks| *blocks > 0)?;
+    let seconds = latest.1.checked_sub(earlier.1)?;
+    Some(seconds as f64 / blocks as f64)
+}
+
+async fn fetch_block(client: &HttpClient, block: &str) -> Option<(u64, u64)> {
+    let response: Result<Value, _> = client.request("eth_getBlockByNumber", (block, false)).await;
+    match response {
+        Ok(block) => block_header(&block),
+        Err(e) => {
+            warn!(error = %e, "Block query failed");
+            None
+        }
+    }
+}
+
+/// Average block time over the last [`BLOCK_TIME_SAMPLE_BLOCKS`] blocks
+async fn fetch_block_time(client: &HttpClient) -> Option<f64> {
+    let latest = fetch_block(client, "latest").await?;
+    let earlier_number = latest.0.saturating_sub(BLOCK_TIME_SAMPLE_BLOCKS);
+    let earlier = fetch_block(client, &format!("0x{:x}", earlier_number)).await?;
+    average_block_time(earlier, latest)
+}
+
+/// Chain parameters, refetched from the L2 node at most every [`CHAIN_INFO_TTL`]
+#[derive(Clone)]
+pub struct ChainInfoCache {
+    l2_rpc: Arc<HttpClient>,
+    /// Parameters taken from the configuration
+    configured: Arc<ChainInfo>,
+    cached: Arc<Mutex<Option<(Instant, ChainInfo)>>>,
+}
+
+impl ChainInfoCache {
+    pub fn new(config: &NetworkConfig, bridge_config: &BridgeMonitoringConfig) -> Self {
+        Self {
+            l2_rpc: Arc::new(create_rpc_client(config.reth_url())),
+            configured: Arc::new(ChainInfo {
+                chain_id: config.chain_id(),
+                native_token_symbol: config.native_token_symbol().to_string(),
+                native_token_decimals: NATIVE_TOKEN_DECIMALS,
+                block_time_s: None,
+                bridge_denomination_sats: bridge_config.denomination_sats(),
+                explorer_urls: config.block_explorer_urls().clone(),
+            }),
+            cached: Arc::default(),
+        }
+    }
+
+    async fn get(&self) -> ChainInfo {
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched_at, info)) = cached.as_ref() {
+            if fetched_at.elapsed() < CHAIN_INFO_TTL {
+                return info.clone();
+            }
+        }
+
+        let chain_id = fetch_chain_id(&self.l2_rpc, "reth").await;
+        let info = ChainInfo {
+            chain_id: chain_id.or(self.configured.chain_id),
+            block_time_s: fetch_block_time(&self.l2_rpc).await,
+            ..(*self.configured).clone()
+        };
+        *cached = Some((Instant::now(), info.clone()));
+        info
+    }
+}
+
+/// Return the chain parameters of the monitored network
+pub async fn get_chain_info(cache: ChainInfoCache) -> Json<ChainInfo> {
+    Json(cache.get().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{average_block_time, block_header};
+    use serde_json::json;
+
+    #[test]
+    fn test_average_block_time() {
+        let block = json!({ "number": "0x64", "timestamp": "0x3e8", "hash": "0x00" });
+        assert_eq!(block_header(&block), Some((100, 1000)));
+        assert!(block_header(&json!(null)).is_none());
+
+        assert_eq!(average_block_time((0, 500), (100, 1000)), Some(5.0));
+        // A single block has no block time
+        assert_eq!(average_block_time((0, 500), (0, 500)), None);
+    }
+}
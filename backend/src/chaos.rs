@@ -0,0 +1,200 @@
+//! Failure injection into upstream calls, to check that alerts, staleness flags
+//! and restarts behave under partial failure. Only compiled with the `chaos`
+//! feature, which must not be enabled in production builds.
+
+use dotenvy::dotenv;
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+};
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// Failure injection configuration
+#[derive(Debug)]
+pub struct ChaosConfig {
+    /// Probability that an upstream call fails without being sent
+    failure_probability: f64,
+    /// Probability that an upstream call is delayed
+    delay_probability: f64,
+    /// Max injected delay in milliseconds
+    max_delay_ms: u64,
+    /// Upstreams to inject into, e.g. `explorer`; all of them if empty
+    upstreams: Vec<String>,
+}
+
+impl ChaosConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let probability = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0)
+        };
+        let failure_probability = probability("CHAOS_FAILURE_PROBABILITY");
+        let delay_probability = probability("CHAOS_DELAY_PROBABILITY");
+
+        let max_delay_ms: u64 = std::env::var("CHAOS_MAX_DELAY_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(5_000);
+
+        let upstreams: Vec<String> = std::env::var("CHAOS_UPSTREAMS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|upstream| !upstream.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        info!(
+            failure_probability,
+            delay_probability,
+            max_delay_ms,
+            ?upstreams,
+            "Failure injection configuration"
+        );
+
+        ChaosConfig {
+            failure_probability,
+            delay_probability,
+            max_delay_ms,
+            upstreams,
+        }
+    }
+
+    /// Whether calls to `upstream` are injected into
+    fn targets(&self, upstream: &str) -> bool {
+        self.upstreams.is_empty() || self.upstreams.iter().any(|u| u == upstream)
+    }
+}
+
+/// Set once at startup. Upstream clients are created throughout the backend, so
+/// the injection is configured globally rather than passed to each of them.
+static CONFIG: OnceLock<ChaosConfig> = OnceLock::new();
+
+/// Enables failure injection with `config`
+pub fn init(config: ChaosConfig) {
+    if CONFIG.set(config).is_err() {
+        warn!("Failure injection already configured");
+    }
+}
+
+/// Failure injected instead of an upstream call
+#[derive(Debug)]
+pub struct ChaosError {
+    upstream: &'static str,
+}
+
+impl fmt::Display for ChaosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "injected failure of the {} upstream", self.upstream)
+    }
+}
+
+impl std::error::Error for ChaosError {}
+
+/// What happens to an upstream call
+#[derive(Debug, PartialEq)]
+enum Injection {
+    None,
+    Delay(Duration),
+    Fail,
+}
+
+/// Picks the injection from two uniform rolls in `[0, 1)`
+fn injection(config: &ChaosConfig, upstream: &str, fail_roll: f64, delay_roll: f64) -> Injection {
+    if !config.targets(upstream) {
+        return Injection::None;
+    }
+    if fail_roll < config.failure_probability {
+        return Injection::Fail;
+    }
+    if delay_roll < config.delay_probability {
+        // Reuse the roll for the duration, scaled back to [0, 1)
+        let fraction = delay_roll / config.delay_probability;
+        return Injection::Delay(Duration::from_millis(
+            (fraction * config.max_delay_ms as f64) as u64,
+        ));
+    }
+    Injection::None
+}
+
+/// Uniform pseudo-random number in `[0, 1)`, good enough to pick failures
+fn roll() -> f64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    static STATE: OnceLock<RandomState> = OnceLock::new();
+    let hash = STATE
+        .get_or_init(RandomState::new)
+        .hash_one(COUNTER.fetch_add(1, Ordering::Relaxed));
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Delays or fails a call to `upstream`, as configured. Call before sending the
+/// request.
+pub async fn inject(upstream: &'static str) -> Result<(), ChaosError> {
+    let Some(config) = CONFIG.get() else {
+        return Ok(());
+    };
+    match injection(config, upstream, roll(), roll()) {
+        Injection::None => Ok(()),
+        Injection::Delay(delay) => {
+            warn!(upstream, ?delay, "Injecting upstream delay");
+            sleep(delay).await;
+            Ok(())
+        }
+        Injection::Fail => {
+            warn!(upstream, "Injecting upstream failure");
+            Err(ChaosError { upstream })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{injection, roll, ChaosConfig, Injection};
+    use tokio::time::Duration;
+
+    #[test]
+    fn test_injection() {
+        let config = ChaosConfig {
+            failure_probability: 0.2,
+            delay_probability: 0.5,
+            max_delay_ms: 1_000,
+            upstreams: vec!["explorer".to_string()],
+        };
+
+        assert_eq!(injection(&config, "explorer", 0.1, 0.9), Injection::Fail);
+        assert_eq!(
+            injection(&config, "explorer", 0.3, 0.25),
+            Injection::Delay(Duration::from_millis(500))
+        );
+        assert_eq!(injection(&config, "explorer", 0.3, 0.9), Injection::None);
+        // Other upstreams are left alone
+        assert_eq!(injection(&config, "esplora", 0.0, 0.0), Injection::None);
+
+        let config = ChaosConfig {
+            delay_probability: 0.0,
+            upstreams: Vec::new(),
+            ..config
+        };
+        assert_eq!(injection(&config, "esplora", 0.1, 0.0), Injection::Fail);
+        assert_eq!(injection(&config, "esplora", 0.3, 0.0), Injection::None);
+    }
+
+    #[test]
+    fn test_roll() {
+        let rolls: Vec<f64> = (0..1_000).map(|_| roll()).collect();
+        assert!(rolls.iter().all(|r| (0.0..1.0).contains(r)));
+        let mean = rolls.iter().sum::<f64>() / rolls.len() as f64;
+        assert!((0.4..0.6).contains(&mean));
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+use tracing::warn;
+
+/// Loads a checkpoint previously written with [`save`].
+///
+/// Returns `None` if there is no checkpoint or it cannot be parsed.
+pub fn load<T: DeserializeOwned>(path: &str) -> Option<T> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!(%path, error = %e, "Failed to read checkpoint");
+            return None;
+        }
+    };
+
+    match serde_json::from_str(&data) {
+        Ok(checkpoint) => Some(checkpoint),
+        Err(e) => {
+            warn!(%path, error = %e, "Ignoring unparseable checkpoint");
+            None
+        }
+    }
+}
+
+/// Atomically writes a checkpoint, so that a crash mid-write never leaves a truncated file
+pub fn save<T: Serialize>(path: &str, checkpoint: &T) -> Result<(), anyhow::Error> {
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, serde_json::to_vec(checkpoint)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Removes a checkpoint once the work it tracks has completed
+pub fn clear(path: &str) {
+    if Path::new(path).exists() {
+        if let Err(e) = fs::remove_file(path) {
+            warn!(%path, error = %e, "Failed to remove checkpoint");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear, load, save};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let path = std::env::temp_dir().join(format!("checkpoint-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let checkpoint: HashMap<String, u64> = HashMap::from([("page".to_string(), 7)]);
+        save(path, &checkpoint).unwrap();
+        assert_eq!(load::<HashMap<String, u64>>(path), Some(checkpoint));
+
+        clear(path);
+        assert_eq!(load::<HashMap<String, u64>>(path), None);
+    }
+}
@@ -0,0 +1,730 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use jsonrpsee::{
+    core::{client::ClientT, ClientError},
+    http_client::HttpClient,
+};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use tokio::{
+    net::{lookup_host, TcpStream},
+    time::{sleep, timeout, Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    network::{FailureKind, Status, StatusReason},
+    retry_policy::{classify_rpc_error, ErrorClass, ExponentialBackoff},
+    status_rules::{FailureCounter, StatusRule},
+    utils::create_rpc_client,
+};
+
+/// Check deciding whether a monitored component is online
+#[async_trait]
+pub trait StatusCheck: Send + Sync {
+    /// Name of the component in the network status
+    fn name(&self) -> &str;
+
+    /// Consecutive failed checks before the component is reported offline
+    fn failure_threshold(&self) -> u32 {
+        1
+    }
+
+    /// Runs the check once, returning why it failed
+    async fn run(&self) -> Result<(), StatusReason>;
+}
+
+/// Kind of a failed JSON-RPC call
+pub fn rpc_failure(error: &ClientError) -> StatusReason {
+    let kind = match error {
+        ClientError::RequestTimeout => FailureKind::Timeout,
+        ClientError::Transport(_) => FailureKind::Unreachable,
+        ClientError::ParseError(_) => FailureKind::UnexpectedResponse,
+        _ => FailureKind::RpcError,
+    };
+    StatusReason::new(kind, error.to_string())
+}
+
+/// Kind of a failed HTTP request
+pub fn http_failure(error: &reqwest::Error) -> StatusReason {
+    let kind = if error.is_timeout() {
+        FailureKind::Timeout
+    } else if error.is_connect() {
+        FailureKind::Unreachable
+    } else {
+        FailureKind::UnexpectedResponse
+    };
+    StatusReason::new(kind, error.to_string())
+}
+
+/// Calls a JSON-RPC status method, returning the response if it passes `rule`
+pub async fn call_rpc_status(
+    client: &HttpClient,
+    method: &str,
+    retry_policy: ExponentialBackoff,
+    max_retries: u64,
+    rule: &StatusRule,
+) -> Result<serde_json::Value, StatusReason> {
+    let mut retry_count: u64 = 0;
+
+    loop {
+        if let Err(e) = crate::budget::admit("rpc") {
+            return Err(StatusReason::new(
+                FailureKind::BudgetExhausted,
+                e.to_string(),
+            ));
+        }
+        #[cfg(feature = "chaos")]
+        if let Err(e) = crate::chaos::inject("rpc").await {
+            return Err(StatusReason::new(FailureKind::Unreachable, e.to_string()));
+        }
+        let response: Result<serde_json::Value, _> = client.request(method, Vec::<()>::new()).await;
+        match response {
+            Ok(json) => {
+                info!(?json, %method, "RPC Response");
+                return rule
+                    .evaluate_json(&json, Utc::now())
+                    .map(|()| json)
+                    .map_err(StatusReason::from);
+            }
+            Err(e) => {
+                let class = classify_rpc_error(&e);
+                if class == ErrorClass::Retryable && retry_count < max_retries {
+                    let delay_seconds = retry_policy.get_delay(retry_count);
+                    if delay_seconds > 0 {
+                        info!(?delay_seconds, %method, "Retrying after");
+                        sleep(Duration::from_secs(delay_seconds)).await;
+                    }
+                    retry_count += 1;
+                } else {
+                    error!(error = %e, %method, ?class, retry_count, "Could not get status");
+                    return Err(rpc_failure(&e));
+                }
+            }
+        }
+    }
+}
+
+/// Calls a JSON-RPC method and checks the response against a rule
+pub struct RpcCheck {
+    name: String,
+    client: HttpClient,
+    method: String,
+    rule: StatusRule,
+    retry_policy: ExponentialBackoff,
+    max_retries: u64,
+}
+
+#[async_trait]
+impl StatusCheck for RpcCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.rule.failure_threshold()
+    }
+
+    async fn run(&self) -> Result<(), StatusReason> {
+        call_rpc_status(
+            &self.client,
+            &self.method,
+            self.retry_policy,
+            self.max_retries,
+            &self.rule,
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// Method of an HTTP probe
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ProbeMethod {
+    #[default]
+    Get,
+    Head,
+    Post,
+}
+
+impl From<ProbeMethod> for reqwest::Method {
+    fn from(method: ProbeMethod) -> Self {
+        match method {
+            ProbeMethod::Get => reqwest::Method::GET,
+            ProbeMethod::Head => reqwest::Method::HEAD,
+            ProbeMethod::Post => reqwest::Method::POST,
+        }
+    }
+}
+
+/// Regular expression a response body must match, compiled when config is loaded
+#[derive(Clone, Debug)]
+pub struct BodyRegex(Regex);
+
+impl PartialEq for BodyRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl<'de> Deserialize<'de> for BodyRegex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(BodyRegex)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Sends an HTTP request and checks the response against a rule
+pub struct HttpCheck {
+    name: String,
+    client: reqwest::Client,
+    method: ProbeMethod,
+    url: String,
+    rule: StatusRule,
+    body_regex: Option<BodyRegex>,
+}
+
+#[async_trait]
+impl StatusCheck for HttpCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.rule.failure_threshold()
+    }
+
+    async fn run(&self) -> Result<(), StatusReason> {
+        let resp = self
+            .client
+            .request(self.method.into(), &self.url)
+            .send()
+            .await
+            .map_err(|e| http_failure(&e))?;
+        let status_code = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        self.rule.evaluate_http(status_code, &body, Utc::now())?;
+
+        match &self.body_regex {
+            Some(BodyRegex(regex)) if !regex.is_match(&body) => Err(StatusReason::from(format!(
+                "body does not match {:?}",
+                regex.as_str()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Connects to a TCP port, e.g. of a database or p2p listener
+pub struct TcpCheck {
+    name: String,
+    /// `host:port` to connect to
+    address: String,
+    timeout: Duration,
+    failure_threshold: u32,
+}
+
+#[async_trait]
+impl StatusCheck for TcpCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    async fn run(&self) -> Result<(), StatusReason> {
+        match timeout(self.timeout, TcpStream::connect(&self.address)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(StatusReason::new(
+                FailureKind::Unreachable,
+                format!("cannot connect to {}: {}", self.address, e),
+            )),
+            Err(_) => Err(StatusReason::new(
+                FailureKind::Timeout,
+                format!("connecting to {} timed out", self.address),
+            )),
+        }
+    }
+}
+
+/// Resolves a host name with the system resolver
+pub struct DnsCheck {
+    name: String,
+    host: String,
+    timeout: Duration,
+    failure_threshold: u32,
+}
+
+#[async_trait]
+impl StatusCheck for DnsCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    async fn run(&self) -> Result<(), StatusReason> {
+        match timeout(self.timeout, lookup_host((self.host.as_str(), 0))).await {
+            Ok(Ok(mut addrs)) if addrs.next().is_some() => Ok(()),
+            Ok(Ok(_)) => Err(StatusReason::new(
+                FailureKind::Unreachable,
+                format!("{} has no addresses", self.host),
+            )),
+            Ok(Err(e)) => Err(StatusReason::new(
+                FailureKind::Unreachable,
+                format!("cannot resolve {}: {}", self.host, e),
+            )),
+            Err(_) => Err(StatusReason::new(
+                FailureKind::Timeout,
+                format!("resolving {} timed out", self.host),
+            )),
+        }
+    }
+}
+
+fn default_rpc_method() -> String {
+    "strata_syncStatus".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_failure_threshold() -> u32 {
+    1
+}
+
+/// Check declared in config, see `STATUS_CHECKS` in `.env.example`
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CheckSpec {
+    /// JSON-RPC method call, `strata_syncStatus` by default
+    Rpc {
+        name: String,
+        url: String,
+        #[serde(default = "default_rpc_method")]
+        method: String,
+        #[serde(default)]
+        rule: StatusRule,
+        /// Seconds between checks, every status refresh when unset
+        #[serde(default)]
+        interval_s: Option<u64>,
+    },
+    /// HTTP request, e.g. a custom probe of a health endpoint
+    Http {
+        name: String,
+        url: String,
+        #[serde(default)]
+        method: ProbeMethod,
+        #[serde(default)]
+        rule: StatusRule,
+        /// Regular expression the response body must match
+        #[serde(default)]
+        body_regex: Option<BodyRegex>,
+        /// Seconds between checks, every status refresh when unset
+        #[serde(default)]
+        interval_s: Option<u64>,
+    },
+    /// TCP connection, for services without an HTTP health endpoint
+    Tcp {
+        name: String,
+        /// `host:port` to connect to
+        address: String,
+        #[serde(default = "default_timeout_ms")]
+        timeout_ms: u64,
+        /// Consecutive failed checks before the component is reported offline
+        #[serde(default = "default_failure_threshold")]
+        failure_threshold: u32,
+        /// Seconds between checks, every status refresh when unset
+        #[serde(default)]
+        interval_s: Option<u64>,
+    },
+    /// DNS resolution of a host name
+    Dns {
+        name: String,
+        host: String,
+        #[serde(default = "default_timeout_ms")]
+        timeout_ms: u64,
+        /// Consecutive failed checks before the component is reported offline
+        #[serde(default = "default_failure_threshold")]
+        failure_threshold: u32,
+        /// Seconds between checks, every status refresh when unset
+        #[serde(default)]
+        interval_s: Option<u64>,
+    },
+}
+
+impl CheckSpec {
+    /// Name of the checked component
+    pub fn name(&self) -> &str {
+        match self {
+            CheckSpec::Rpc { name, .. }
+            | CheckSpec::Http { name, .. }
+            | CheckSpec::Tcp { name, .. }
+            | CheckSpec::Dns { name, .. } => name,
+        }
+    }
+
+    /// Seconds between checks, `None` to check on every status refresh
+    pub fn interval_s(&self) -> Option<u64> {
+        match self {
+            CheckSpec::Rpc { interval_s, .. }
+            | CheckSpec::Http { interval_s, .. }
+            | CheckSpec::Tcp { interval_s, .. }
+            | CheckSpec::Dns { interval_s, .. } => *interval_s,
+        }
+    }
+
+    /// Instantiates the check; JSON-RPC calls are retried with `retry_policy`
+    pub fn build(
+        &self,
+        http_client: &reqwest::Client,
+        retry_policy: ExponentialBackoff,
+        max_retries: u64,
+    ) -> Box<dyn StatusCheck> {
+        match self.clone() {
+            CheckSpec::Rpc {
+                name,
+                url,
+                method,
+                rule,
+                ..
+            } => Box::new(RpcCheck {
+                name,
+                client: create_rpc_client(&url),
+                method,
+                rule,
+                retry_policy,
+                max_retries,
+            }),
+            CheckSpec::Http {
+                name,
+                url,
+                method,
+                rule,
+                body_regex,
+                ..
+            } => Box::new(HttpCheck {
+                name,
+                client: http_client.clone(),
+                method,
+                url,
+                rule,
+                body_regex,
+            }),
+            CheckSpec::Tcp {
+                name,
+                address,
+                timeout_ms,
+                failure_threshold,
+                ..
+            } => Box::new(TcpCheck {
+                name,
+                address,
+                timeout: Duration::from_millis(timeout_ms),
+                failure_threshold: failure_threshold.max(1),
+            }),
+            CheckSpec::Dns {
+                name,
+                host,
+                timeout_ms,
+                failure_threshold,
+                ..
+            } => Box::new(DnsCheck {
+                name,
+                host,
+                timeout: Duration::from_millis(timeout_ms),
+                failure_threshold: failure_threshold.max(1),
+            }),
+        }
+    }
+}
+
+/// Check of a component along with its debouncing state
+struct Component {
+    check: Box<dyn StatusCheck>,
+    /// Min time between checks, `None` to check on every run
+    interval: Option<Duration>,
+    failures: FailureCounter,
+    /// Time and outcome of the latest check
+    last: Option<(Instant, Status)>,
+    /// Why the latest check failed, unset once one passes
+    last_failure: Option<StatusReason>,
+}
+
+impl Component {
+    fn is_due(&self, now: Instant) -> bool {
+        match (self.last.as_ref(), self.interval) {
+            (Some((at, _)), Some(interval)) => now.duration_since(*at) >= interval,
+            _ => true,
+        }
+    }
+}
+
+/// Runs a set of checks and debounces their outcome into component statuses
+#[derive(Default)]
+pub struct ComponentChecks {
+    components: Vec<Component>,
+}
+
+impl ComponentChecks {
+    /// Adds a check run at most once per `interval_s`, or on every run when unset
+    pub fn add(&mut self, check: Box<dyn StatusCheck>, interval_s: Option<u64>) {
+        self.components.push(Component {
+            check,
+            interval: interval_s.map(Duration::from_secs),
+            failures: FailureCounter::default(),
+            last: None,
+            last_failure: None,
+        });
+    }
+
+    /// Removes the check of a component
+    pub fn remove(&mut self, name: &str) {
+        self.components
+            .retain(|component| component.check.name() != name);
+    }
+
+    /// Runs every check that is due, returning the status of all components
+    pub async fn run(&mut self, now: Instant) -> BTreeMap<String, Status> {
+        let mut statuses = BTreeMap::new();
+        for component in &mut self.components {
+            let name = component.check.name().to_string();
+            if !component.is_due(now) {
+                if let Some((_, status)) = &component.last {
+                    statuses.insert(name, status.clone());
+                }
+                continue;
+            }
+
+            component.last_failure = component
+                .check
+                .run()
+                .await
+                .inspect_err(|reason| warn!(%reason, component = %name, "Status check failed"))
+                .err();
+            let passed = component.last_failure.is_none();
+            let was_online = matches!(component.last, Some((_, Status::Online)));
+            let online =
+                component
+                    .failures
+                    .observe(passed, was_online, component.check.failure_threshold());
+            let status = Status::from_online(online);
+            component.last = Some((now, status.clone()));
+            statuses.insert(name, status);
+        }
+        statuses
+    }
+
+    /// Why the components that are not online failed their latest check
+    pub fn reasons(&self) -> BTreeMap<String, StatusReason> {
+        self.components
+            .iter()
+            .filter(|component| !matches!(component.last, Some((_, Status::Online))))
+            .filter_map(|component| {
+                let reason = component.last_failure.clone()?;
+                Some((component.check.name().to_string(), reason))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CheckSpec, ComponentChecks, StatusCheck};
+    use crate::{
+        network::{FailureKind, Status, StatusReason},
+        retry_policy::ExponentialBackoff,
+    };
+    use async_trait::async_trait;
+    use mockito::Server;
+    use serde_json::json;
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    };
+    use tokio::{
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    struct FakeCheck {
+        name: &'static str,
+        passes: Arc<AtomicBool>,
+        runs: Arc<AtomicU32>,
+    }
+
+    impl FakeCheck {
+        fn new(name: &'static str) -> (Self, Arc<AtomicBool>, Arc<AtomicU32>) {
+            let passes = Arc::new(AtomicBool::new(true));
+            let runs = Arc::new(AtomicU32::new(0));
+            let check = Self {
+                name,
+                passes: Arc::clone(&passes),
+                runs: Arc::clone(&runs),
+            };
+            (check, passes, runs)
+        }
+    }
+
+    #[async_trait]
+    impl StatusCheck for FakeCheck {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn failure_threshold(&self) -> u32 {
+            2
+        }
+
+        async fn run(&self) -> Result<(), StatusReason> {
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            if self.passes.load(Ordering::Relaxed) {
+                Ok(())
+            } else {
+                Err(StatusReason::new(FailureKind::Timeout, "down"))
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_spec_from_config() {
+        let spec: CheckSpec = serde_json::from_value(json!({
+            "type": "rpc",
+            "name": "fullnode",
+            "url": "http://localhost:8432",
+        }))
+        .unwrap();
+        assert_eq!(spec.name(), "fullnode");
+        assert_eq!(spec.interval_s(), None);
+        assert!(matches!(spec, CheckSpec::Rpc { method, .. } if method == "strata_syncStatus"));
+
+        assert!(serde_json::from_value::<CheckSpec>(json!({
+            "type": "carrier_pigeon",
+            "name": "x",
+        }))
+        .is_err());
+        assert!(serde_json::from_value::<CheckSpec>(json!({
+            "type": "http",
+            "name": "x",
+            "url": "http://localhost",
+            "body_regex": "(unclosed",
+        }))
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_component_checks_debounce() {
+        let (check, passes, _) = FakeCheck::new("probe");
+        let mut checks = ComponentChecks::default();
+        checks.add(Box::new(check), None);
+
+        assert_eq!(checks.run(Instant::now()).await["probe"], Status::Online);
+
+        passes.store(false, Ordering::Relaxed);
+        assert_eq!(checks.run(Instant::now()).await["probe"], Status::Online);
+        // Failures are only reported once debounced
+        assert!(checks.reasons().is_empty());
+        assert_eq!(checks.run(Instant::now()).await["probe"], Status::Offline);
+        assert_eq!(
+            checks.reasons()["probe"],
+            StatusReason::new(FailureKind::Timeout, "down")
+        );
+
+        passes.store(true, Ordering::Relaxed);
+        checks.run(Instant::now()).await;
+        assert!(checks.reasons().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_component_checks_interval() {
+        let (check, _, runs) = FakeCheck::new("probe");
+        let mut checks = ComponentChecks::default();
+        checks.add(Box::new(check), Some(60));
+
+        let start = Instant::now();
+        checks.run(start).await;
+        let statuses = checks.run(start + Duration::from_secs(59)).await;
+        assert_eq!(statuses["probe"], Status::Online);
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+        checks.run(start + Duration::from_secs(60)).await;
+        assert_eq!(runs.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_http_probe() {
+        let mut server = Server::new_async().await;
+        let _ready = server
+            .mock("POST", "/ready")
+            .with_status(200)
+            .with_body(r#"{"db": "up", "queue": "up"}"#)
+            .create();
+        let _degraded = server
+            .mock("POST", "/degraded")
+            .with_status(200)
+            .with_body(r#"{"db": "up", "queue": "down"}"#)
+            .create();
+
+        let probe = |path: &str| {
+            let spec: CheckSpec = serde_json::from_value(json!({
+                "type": "http",
+                "name": "indexer",
+                "url": format!("{}{}", server.url(), path),
+                "method": "POST",
+                "rule": { "expected_status_codes": [200] },
+                "body_regex": r#""queue":\s*"up""#,
+                "interval_s": 60,
+            }))
+            .unwrap();
+            assert_eq!(spec.interval_s(), Some(60));
+            spec.build(
+                &reqwest::Client::new(),
+                ExponentialBackoff::new(0, 0, 1.5),
+                0,
+            )
+        };
+
+        assert_eq!(probe("/ready").run().await, Ok(()));
+        assert_eq!(
+            probe("/degraded")
+                .run()
+                .await
+                .map_err(|reason| reason.reason),
+            Err(FailureKind::UnexpectedResponse)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tcp_and_dns_probes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let build = |spec: serde_json::Value| {
+            serde_json::from_value::<CheckSpec>(spec).unwrap().build(
+                &reqwest::Client::new(),
+                ExponentialBackoff::new(0, 0, 1.5),
+                0,
+            )
+        };
+
+        let tcp = build(json!({ "type": "tcp", "name": "postgres", "address": address }));
+        assert_eq!(tcp.run().await, Ok(()));
+        assert_eq!(tcp.failure_threshold(), 1);
+        drop(listener);
+        assert_eq!(
+            tcp.run().await.map_err(|reason| reason.reason),
+            Err(FailureKind::Unreachable)
+        );
+
+        let dns = build(json!({ "type": "dns", "name": "resolver", "host": "localhost" }));
+        assert_eq!(dns.run().await, Ok(()));
+    }
+}
@@ -0,0 +1,338 @@
+//! Optional ClickHouse sink of the indexed bundles, for analytics over ranges
+//! longer than the in-memory history.
+//!
+//! Rows are inserted in batches every `CLICKHOUSE_FLUSH_INTERVAL_S` through the
+//! HTTP interface as `JSONEachRow`, into a table created beforehand, e.g.
+//!
+//! ```sql
+//! CREATE TABLE bundles (
+//!     transaction_hash String,
+//!     bundler Nullable(String),
+//!     at DateTime64(3, 'UTC'),
+//!     user_ops UInt64,
+//!     inclusion_delay_s Nullable(Int64)
+//! ) ENGINE = ReplacingMergeTree ORDER BY (at, transaction_hash)
+//! ```
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time::{interval, Duration},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::ClickHouseConfig,
+    store::{MemoryStore, StatsStore, Timestamped},
+};
+
+/// Max number of rows waiting to be inserted; more are dropped
+const INSERT_QUEUE_LEN: usize = 10_000;
+
+/// Max number of rows per insert
+const MAX_BATCH_LEN: usize = 5_000;
+
+/// Max number of rows returned by a range query
+pub const MAX_RANGE_ROWS: usize = 10_000;
+
+/// Client of the ClickHouse HTTP interface, see
+/// <https://clickhouse.com/docs/en/interfaces/http>
+#[derive(Clone)]
+pub struct ClickHouseClient {
+    http: reqwest::Client,
+    url: String,
+    database: String,
+    user: String,
+    password: String,
+}
+
+impl fmt::Debug for ClickHouseClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClickHouseClient")
+            .field("url", &self.url)
+            .field("database", &self.database)
+            .field("user", &self.user)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Time as a ClickHouse `DateTime64(3)` query parameter
+fn format_param_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+/// Rows as `JSONEachRow` lines
+fn encode_rows<T: Serialize>(rows: &[T]) -> Vec<u8> {
+    let mut lines = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut lines, row).expect("rows to serialize");
+        lines.push(b'\n');
+    }
+    lines
+}
+
+/// Parses `JSONEachRow` lines
+fn decode_rows<T: DeserializeOwned>(body: &str) -> Result<Vec<T>, anyhow::Error> {
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).context("Unexpected ClickHouse row"))
+        .collect()
+}
+
+impl ClickHouseClient {
+    pub fn new(url: &str, database: &str, user: &str, password: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            database: database.to_string(),
+            user: user.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// Runs `query` with the query `params`, sending `body` as its input data.
+    /// Returns the response body.
+    async fn execute(
+        &self,
+        query: &str,
+        params: &[(String, String)],
+        body: Vec<u8>,
+    ) -> Result<String, anyhow::Error> {
+        let response = self
+            .http
+            .post(&self.url)
+            .query(&[
+                ("query", query),
+                ("database", &self.database),
+                // Accepts the RFC 3339 times serialized by chrono
+                ("date_time_input_format", "best_effort"),
+                ("date_time_output_format", "iso"),
+            ])
+            .query(params)
+            .header("X-ClickHouse-User", &self.user)
+            .header("X-ClickHouse-Key", &self.password)
+            .body(body)
+            .send()
+            .await
+            .context("ClickHouse request failed")?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        anyhow::ensure!(
+            status.is_success(),
+            "ClickHouse query failed with {}: {}",
+            status,
+            text.trim()
+        );
+        Ok(text)
+    }
+
+    /// Inserts `rows` into `table`
+    pub async fn insert<T: Serialize>(&self, table: &str, rows: &[T]) -> Result<(), anyhow::Error> {
+        let query = format!("INSERT INTO {} FORMAT JSONEachRow", table);
+        self.execute(&query, &[], encode_rows(rows)).await?;
+        Ok(())
+    }
+
+    /// Rows of `table` with an `at` column at or after `from` and before `to`,
+    /// oldest first, up to [`MAX_RANGE_ROWS`]
+    pub async fn query_range<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<T>, anyhow::Error> {
+        let query = format!(
+            "SELECT * FROM {} FINAL \
+             WHERE at >= {{from:DateTime64(3, 'UTC')}} AND at < {{to:DateTime64(3, 'UTC')}} \
+             ORDER BY at LIMIT {} FORMAT JSONEachRow",
+            table, MAX_RANGE_ROWS
+        );
+        let params = [
+            ("param_from".to_string(), format_param_time(from)),
+            ("param_to".to_string(), format_param_time(to)),
+        ];
+        let body = self.execute(&query, &params, Vec::new()).await?;
+        decode_rows(&body)
+    }
+}
+
+/// Table of a ClickHouse database, to query ranges of the rows of a
+/// [`ClickHouseStore`]
+#[derive(Clone, Debug)]
+pub struct ClickHouseTable {
+    client: ClickHouseClient,
+    table: String,
+}
+
+impl ClickHouseTable {
+    /// Table `table` of the configured database, `None` when ClickHouse is disabled
+    pub fn new(config: &ClickHouseConfig, table: &str) -> Option<Self> {
+        let client = ClickHouseClient::new(
+            config.url()?,
+            config.database(),
+            config.user(),
+            config.password(),
+        );
+        Some(Self {
+            client,
+            table: table.to_string(),
+        })
+    }
+
+    /// See [`ClickHouseClient::query_range`]
+    pub async fn query_range<T: DeserializeOwned>(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<T>, anyhow::Error> {
+        self.client.query_range(&self.table, from, to).await
+    }
+}
+
+/// Records inserted into a ClickHouse table from a dedicated task, the most recent
+/// ones also kept in memory to serve synchronous queries.
+///
+/// As for the history files, rows are dropped and counted rather than stalling
+/// the monitoring task when ClickHouse falls behind.
+#[derive(Debug)]
+pub struct ClickHouseStore<T> {
+    recent: MemoryStore<T>,
+    sender: mpsc::Sender<T>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<T: Serialize + Send + 'static> ClickHouseStore<T> {
+    /// Spawns the task inserting into `table`, keeping up to `max_records` in memory
+    pub fn spawn(table: ClickHouseTable, max_records: usize, flush_interval: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel(INSERT_QUEUE_LEN);
+        tokio::spawn(insert_task(table, receiver, flush_interval));
+        Self {
+            recent: MemoryStore::new(max_records),
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<T> StatsStore<T> for ClickHouseStore<T>
+where
+    T: Timestamped + Serialize + Clone + fmt::Debug + Send + Sync + 'static,
+{
+    fn save_snapshot(&mut self, snapshot: T) {
+        match self.sender.try_send(snapshot.clone()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                warn!(%dropped, "ClickHouse insert queue full, dropping row");
+            }
+            Err(TrySendError::Closed(_)) => warn!("ClickHouse inserter stopped, dropping row"),
+        }
+        self.recent.save_snapshot(snapshot);
+    }
+
+    /// Only covers the records kept in memory, see [`ClickHouseTable::query_range`]
+    fn query_range(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<T> {
+        self.recent.query_range(from, to)
+    }
+
+    /// Only the records kept in memory
+    fn records(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        self.recent.records()
+    }
+
+    fn oldest(&self) -> Option<DateTime<Utc>> {
+        self.recent.oldest()
+    }
+
+    /// Only prunes the records kept in memory, ClickHouse tables expire rows by TTL
+    fn prune(&mut self, cutoff: DateTime<Utc>) {
+        self.recent.prune(cutoff);
+    }
+
+    /// Only truncates the records kept in memory
+    fn truncate(&mut self, max_records: usize) {
+        self.recent.truncate(max_records);
+    }
+
+    fn dropped_writes(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Inserts the batched rows, if any. Failed batches are dropped, as retrying them
+/// would hold back the newer rows.
+async fn flush<T: Serialize>(batch: &mut Vec<T>, table: &ClickHouseTable) {
+    if batch.is_empty() {
+        return;
+    }
+    let rows = batch.len();
+    match table.client.insert(&table.table, batch).await {
+        Ok(()) => info!(table = %table.table, rows, "Inserted rows into ClickHouse"),
+        Err(e) => error!(error = %e, table = %table.table, rows, "ClickHouse insert failed"),
+    }
+    batch.clear();
+}
+
+/// Batches the queued rows and inserts them at every flush interval
+async fn insert_task<T: Serialize>(
+    table: ClickHouseTable,
+    mut rows: mpsc::Receiver<T>,
+    flush_interval: Duration,
+) {
+    let mut interval = interval(flush_interval.max(Duration::from_secs(1)));
+    let mut batch = Vec::new();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => flush(&mut batch, &table).await,
+            row = rows.recv() => {
+                let Some(row) = row else {
+                    flush(&mut batch, &table).await;
+                    return;
+                };
+                batch.push(row);
+                if batch.len() >= MAX_BATCH_LEN {
+                    flush(&mut batch, &table).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_rows, encode_rows, format_param_time};
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        at: DateTime<Utc>,
+        user_ops: u64,
+    }
+
+    #[test]
+    fn test_clickhouse_rows() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        assert_eq!(format_param_time(at), "2025-03-10 12:00:00.000");
+
+        let rows = [Row { at, user_ops: 2 }, Row { at, user_ops: 3 }];
+        let lines = String::from_utf8(encode_rows(&rows)).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+
+        // Times as output with `date_time_output_format=iso`
+        let decoded: Vec<Row> =
+            decode_rows("{\"at\":\"2025-03-10T12:00:00.000Z\",\"user_ops\":2}\n\n").unwrap();
+        assert_eq!(decoded, vec![Row { at, user_ops: 2 }]);
+        assert!(decode_rows::<Row>("not json").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use async_trait::async_trait;
+use bitcoin::OutPoint;
+use jsonrpsee::{
+    core::{client::ClientT, traits::ToRpcParams, ClientError},
+    http_client::HttpClient,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use strata_bridge_primitives::types::PublickeyTable;
+use strata_bridge_rpc::types::{
+    RpcClaimInfo, RpcDepositInfo, RpcOperatorStatus, RpcWithdrawalInfo,
+};
+
+use crate::{archive, budget, checks::rpc_failure, degradation, network::FailureKind};
+
+/// Strata RPC methods used by the dashboard
+#[async_trait]
+pub trait StrataClient: Send + Sync {
+    /// Ids of the current deposits
+    async fn current_deposits(&self) -> Result<Vec<u32>, ClientError>;
+
+    /// Deposit entry with the deposit outpoint and withdrawal request, if any
+    async fn deposit_entry(&self, deposit_id: u32) -> Result<Value, ClientError>;
+}
+
+/// Strata bridge RPC methods used by the dashboard
+#[async_trait]
+pub trait BridgeClient: Send + Sync {
+    /// Operator indexes and public keys
+    async fn bridge_operators(&self) -> Result<PublickeyTable, ClientError>;
+
+    async fn operator_status(&self, operator_idx: u32) -> Result<RpcOperatorStatus, ClientError>;
+
+    /// Duties assigned to an operator, left undecoded
+    async fn duties_by_operator(&self, operator_idx: u32) -> Result<Vec<Value>, ClientError>;
+
+    async fn deposit_info(&self, deposit_outpoint: OutPoint)
+        -> Result<RpcDepositInfo, ClientError>;
+
+    async fn withdrawal_info(
+        &self,
+        deposit_outpoint: OutPoint,
+    ) -> Result<RpcWithdrawalInfo, ClientError>;
+
+    /// Txids of all claims
+    async fn claims(&self) -> Result<Vec<String>, ClientError>;
+
+    async fn claim_info(&self, claim_txid: String) -> Result<RpcClaimInfo, ClientError>;
+}
+
+/// Sends a request to the `upstream` RPC within its request budget, after the
+/// failure injection if enabled, and archives the response if sampled. Failures
+/// degrade the task sending the request.
+async fn request<R: DeserializeOwned, P: ToRpcParams + Serialize + Send>(
+    client: &HttpClient,
+    upstream: &'static str,
+    method: &str,
+    params: P,
+) -> Result<R, ClientError> {
+    budget::admit(upstream).map_err(|e| {
+        degradation::record_failure(upstream, FailureKind::BudgetExhausted);
+        ClientError::Custom(e.to_string())
+    })?;
+    #[cfg(feature = "chaos")]
+    crate::chaos::inject(upstream).await.map_err(|e| {
+        degradation::record_failure(upstream, FailureKind::Unreachable);
+        ClientError::Custom(e.to_string())
+    })?;
+    let archived = archive::sample().then(|| json!({ "method": method, "params": &params }));
+    let response: Value = client
+        .request(method, params)
+        .await
+        .inspect_err(|e| degradation::record_failure(upstream, rpc_failure(e).reason))?;
+    if let Some(request) = archived {
+        archive::record(upstream, request, &response);
+    }
+    serde_json::from_value(response).map_err(|e| {
+        degradation::record_failure(upstream, FailureKind::UnexpectedResponse);
+        ClientError::ParseError(e)
+    })
+}
+
+#[async_trait]
+impl StrataClient for HttpClient {
+    async fn current_deposits(&self) -> Result<Vec<u32>, ClientError> {
+        request(self, "strata", "strata_getCurrentDeposits", ((),)).await
+    }
+
+    async fn deposit_entry(&self, deposit_id: u32) -> Result<Value, ClientError> {
+        request(
+            self,
+            "strata",
+            "strata_getCurrentDepositById",
+            (deposit_id,),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl BridgeClient for HttpClient {
+    async fn bridge_operators(&self) -> Result<PublickeyTable, ClientError> {
+        request(self, "bridge", "stratabridge_bridgeOperators", ((),)).await
+    }
+
+    async fn operator_status(&self, operator_idx: u32) -> Result<RpcOperatorStatus, ClientError> {
+        request(
+            self,
+            "bridge",
+            "stratabridge_operatorStatus",
+            (operator_idx,),
+        )
+        .await
+    }
+
+    async fn duties_by_operator(&self, operator_idx: u32) -> Result<Vec<Value>, ClientError> {
+        request(
+            self,
+            "bridge",
+            "stratabridge_bridgeDutiesByOperatorId",
+            (operator_idx,),
+        )
+        .await
+    }
+
+    async fn deposit_info(
+        &self,
+        deposit_outpoint: OutPoint,
+    ) -> Result<RpcDepositInfo, ClientError> {
+        request(
+            self,
+            "bridge",
+            "stratabridge_depositInfo",
+            (deposit_outpoint,),
+        )
+        .await
+    }
+
+    async fn withdrawal_info(
+        &self,
+        deposit_outpoint: OutPoint,
+    ) -> Result<RpcWithdrawalInfo, ClientError> {
+        request(
+            self,
+            "bridge",
+            "stratabridge_withdrawalInfo",
+            (deposit_outpoint,),
+        )
+        .await
+    }
+
+    async fn claims(&self) -> Result<Vec<String>, ClientError> {
+        request(self, "bridge", "stratabridge_claims", ((),)).await
+    }
+
+    async fn claim_info(&self, claim_txid: String) -> Result<RpcClaimInfo, ClientError> {
+        request(self, "bridge", "stratabridge_claimInfo", (claim_txid,)).await
+    }
+}
+
+/// In-memory clients serving canned data, for tests
+#[cfg(test)]
+pub mod fakes {
+    use super::{BridgeClient, StrataClient};
+    use async_trait::async_trait;
+    use bitcoin::OutPoint;
+    use jsonrpsee::core::ClientError;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        path::Path,
+    };
+    use strata_bridge_primitives::types::PublickeyTable;
+    use strata_bridge_rpc::types::{
+        RpcClaimInfo, RpcDepositInfo, RpcOperatorStatus, RpcWithdrawalInfo,
+    };
+
+    fn not_found(what: String) -> ClientError {
+        ClientError::Custom(format!("{} not found", what))
+    }
+
+    /// Reads `<name>.json` of a fixtures directory of the `mock_rpc` server
+    fn read_fixture<T: DeserializeOwned>(dir: &Path, name: &str) -> T {
+        let path = dir.join(format!("{}.json", name));
+        let data = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("to read {}: {}", path.display(), e));
+        serde_json::from_str(&data).unwrap_or_else(|e| panic!("to parse {}: {}", path.display(), e))
+    }
+
+    /// Fixtures keyed by outpoint, as the `mock_rpc` server stores them
+    fn by_outpoint<T>(fixtures: HashMap<String, T>) -> HashMap<OutPoint, T> {
+        fixtures
+            .into_iter()
+            .map(|(outpoint, value)| (outpoint.parse().expect("fixture outpoint"), value))
+            .collect()
+    }
+
+    #[derive(Default)]
+    pub struct FakeStrataClient {
+        pub deposit_entries: BTreeMap<u32, Value>,
+    }
+
+    impl FakeStrataClient {
+        /// Serves the fixtures of the `mock_rpc` Strata RPC, e.g. `mock_data/strata_rpc`
+        pub fn from_fixtures(dir: &Path) -> Self {
+            Self {
+                deposit_entries: read_fixture(dir, "deposit_entries"),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl StrataClient for FakeStrataClient {
+        async fn current_deposits(&self) -> Result<Vec<u32>, ClientError> {
+            Ok(self.deposit_entries.keys().copied().collect())
+        }
+
+        async fn deposit_entry(&self, deposit_id: u32) -> Result<Value, ClientError> {
+            self.deposit_entries
+                .get(&deposit_id)
+                .cloned()
+                .ok_or_else(|| not_found(format!("deposit {}", deposit_id)))
+        }
+    }
+
+    /// Fake bridge; queries for operators or entries it does not know fail
+    pub struct FakeBridgeClient {
+        pub operators: PublickeyTable,
+        pub operator_statuses: HashMap<u32, RpcOperatorStatus>,
+        pub duties: HashMap<u32, Vec<Value>>,
+        pub deposit_infos: HashMap<OutPoint, RpcDepositInfo>,
+        pub withdrawal_infos: HashMap<OutPoint, RpcWithdrawalInfo>,
+        pub claim_infos: BTreeMap<String, RpcClaimInfo>,
+    }
+
+    impl FakeBridgeClient {
+        pub fn new(operators: PublickeyTable) -> Self {
+            Self {
+                operators,
+                operator_statuses: HashMap::new(),
+                duties: HashMap::new(),
+                deposit_infos: HashMap::new(),
+                withdrawal_infos: HashMap::new(),
+                claim_infos: BTreeMap::new(),
+            }
+        }
+
+        /// Serves the fixtures of the `mock_rpc` bridge RPC, e.g. `mock_data/bridge_rpc`.
+        /// Like the mock, every operator has an empty duty queue.
+        pub fn from_fixtures(dir: &Path) -> Self {
+            let operators: PublickeyTable = read_fixture(dir, "bridge_operators");
+            let duties = operators.0.keys().map(|idx| (*idx, Vec::new())).collect();
+            Self {
+                operators,
+                operator_statuses: read_fixture(dir, "operator_status"),
+                duties,
+                deposit_infos: by_outpoint(read_fixture(dir, "deposit_infos")),
+                withdrawal_infos: by_outpoint(read_fixture(dir, "withdrawal_infos")),
+                claim_infos: read_fixture(dir, "claim_infos"),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl BridgeClient for FakeBridgeClient {
+        async fn bridge_operators(&self) -> Result<PublickeyTable, ClientError> {
+            Ok(self.operators.clone())
+        }
+
+        async fn operator_status(
+            &self,
+            operator_idx: u32,
+        ) -> Result<RpcOperatorStatus, ClientError> {
+            self.operator_statuses
+                .get(&operator_idx)
+                .cloned()
+                .ok_or_else(|| not_found(format!("operator {}", operator_idx)))
+        }
+
+        async fn duties_by_operator(&self, operator_idx: u32) -> Result<Vec<Value>, ClientError> {
+            self.duties
+                .get(&operator_idx)
+                .cloned()
+                .ok_or_else(|| not_found(format!("duties of operator {}", operator_idx)))
+        }
+
+        async fn deposit_info(
+            &self,
+            deposit_outpoint: OutPoint,
+        ) -> Result<RpcDepositInfo, ClientError> {
+            self.deposit_infos
+                .get(&deposit_outpoint)
+                .cloned()
+                .ok_or_else(|| not_found(format!("deposit {}", deposit_outpoint)))
+        }
+
+        async fn withdrawal_info(
+            &self,
+            deposit_outpoint: OutPoint,
+        ) -> Result<RpcWithdrawalInfo, ClientError> {
+            self.withdrawal_infos
+                .get(&deposit_outpoint)
+                .cloned()
+                .ok_or_else(|| not_found(format!("withdrawal of {}", deposit_outpoint)))
+        }
+
+        async fn claims(&self) -> Result<Vec<String>, ClientError> {
+            Ok(self.claim_infos.keys().cloned().collect())
+        }
+
+        async fn claim_info(&self, claim_txid: String) -> Result<RpcClaimInfo, ClientError> {
+            self.claim_infos
+                .get(&claim_txid)
+                .cloned()
+                .ok_or_else(|| not_found(format!("claim {}", claim_txid)))
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Comparison of the local clock with the upstream services, from the `Date`
+//! header of their HTTP responses and the timestamp of the latest L2 block. A
+//! skewed clock silently shifts every window-based aggregation, so skew beyond
+//! `CLOCK_SKEW_THRESHOLD_S` is flagged in the diagnostics and alerted on.
+
+use axum::Json;
+use chrono::{DateTime, Utc};
+use jsonrpsee::{core::client::ClientT, http_client::HttpClient};
+use reqwest::header::DATE;
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::{
+    sync::RwLock,
+    time::{interval, Duration},
+};
+use tracing::{info, warn};
+
+use crate::{
+    alerts::{Alerts, Severity},
+    config::{ClockSkewConfig, NetworkConfig},
+    network::parse_hex_quantity,
+    tasks::{TaskRegistry, CLOCK_SKEW_TASK},
+    utils::create_rpc_client,
+};
+
+/// Max time a clock query may take; slower answers measure the network instead
+const CLOCK_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where an upstream time was read from
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockSource {
+    /// `Date` header of an HTTP response, with a one second resolution
+    HttpDate,
+    /// Timestamp of the latest block. Blocks lag behind by up to the block time and
+    /// more while the chain stalls, so only blocks from the future reveal skew.
+    BlockTimestamp,
+}
+
+/// Offset of the local clock from an upstream one
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ClockSample {
+    source: ClockSource,
+    /// Milliseconds the local clock is ahead of the upstream one, negative if behind
+    skew_ms: i64,
+    measured_at: DateTime<Utc>,
+}
+
+impl ClockSample {
+    /// Whether the sample shows a skew beyond the threshold
+    fn is_skewed(&self, threshold_ms: i64) -> bool {
+        match self.source {
+            ClockSource::HttpDate => self.skew_ms.abs() > threshold_ms,
+            ClockSource::BlockTimestamp => self.skew_ms < -threshold_ms,
+        }
+    }
+}
+
+/// Latest clock comparisons passed to dashboard
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ClockSkewStatus {
+    threshold_s: u64,
+    /// By upstream service, unset when the last query failed
+    samples: BTreeMap<String, Option<ClockSample>>,
+    /// Whether any sample shows a skew beyond the threshold
+    skewed: bool,
+}
+
+/// Shared clock comparisons
+pub type SharedClockSkew = Arc<RwLock<ClockSkewStatus>>;
+
+/// Skew of the local clock from an HTTP `Date` header, taking the middle of the
+/// request as the local time it was produced at
+fn http_date_skew_ms(sent: DateTime<Utc>, received: DateTime<Utc>, date: &str) -> Option<i64> {
+    let date = DateTime::parse_from_rfc2822(date).ok()?.with_timezone(&Utc);
+    let local = sent + (received - sent) / 2;
+    Some((local - date).num_milliseconds())
+}
+
+async fn fetch_http_date_skew(client: &reqwest::Client, url: &str) -> Option<i64> {
+    let sent = Utc::now();
+    // Any answer carries a date, even an error status
+    let response = match client.get(url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(error = %e, %url, "Clock query failed");
+            return None;
+        }
+    };
+    let received = Utc::now();
+    let date = response.headers().get(DATE)?.to_str().ok()?;
+    http_date_skew_ms(sent, received, date)
+}
+
+async fn fetch_block_skew(client: &HttpClient) -> Option<i64> {
+    let response: Result<Value, _> = client
+        .request("eth_getBlockByNumber", ("latest", false))
+        .await;
+    let block = match response {
+        Ok(block) => block,
+        Err(e) => {
+            warn!(error = %e, "Latest block query failed");
+            return None;
+        }
+    };
+    let timestamp = parse_hex_quantity(&block["timestamp"])?;
+    let timestamp = DateTime::from_timestamp(i64::try_from(timestamp).ok()?, 0)?;
+    Some((Utc::now() - timestamp).num_milliseconds())
+}
+
+/// Periodically compares the local clock with the upstream services, raising a
+/// `clock_skew` alert while any is off by more than the threshold
+pub async fn clock_skew_task(
+    state: SharedClockSkew,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    network_config: &NetworkConfig,
+    config: &ClockSkewConfig,
+) {
+    tasks
+        .register(CLOCK_SKEW_TASK, config.check_interval())
+        .await;
+    let mut interval = interval(Duration::from_secs(config.check_interval()));
+    let http_client = reqwest::Client::builder()
+        .timeout(CLOCK_QUERY_TIMEOUT)
+        .build()
+        .expect("Failed to create clock HTTP client");
+    let reth_client = create_rpc_client(network_config.reth_url());
+    let threshold_ms = (config.threshold_s() * 1000) as i64;
+
+    loop {
+        interval.tick().await;
+        tasks.start_refresh(CLOCK_SKEW_TASK).await;
+
+        let sample = |source, skew_ms: Option<i64>| {
+            skew_ms.map(|skew_ms| ClockSample {
+                source,
+                skew_ms,
+                measured_at: Utc::now(),
+            })
+        };
+        let samples = BTreeMap::from([
+            (
+                "rpc_endpoint".to_string(),
+                sample(
+                    ClockSource::HttpDate,
+                    fetch_http_date_skew(&http_client, network_config.rpc_url()).await,
+                ),
+            ),
+            (
+                "bundler_endpoint".to_string(),
+                sample(
+                    ClockSource::HttpDate,
+                    fetch_http_date_skew(&http_client, network_config.bundler_url()).await,
+                ),
+            ),
+            (
+                "reth".to_string(),
+                sample(
+                    ClockSource::BlockTimestamp,
+                    fetch_block_skew(&reth_client).await,
+                ),
+            ),
+        ]);
+
+        let skewed: Vec<String> = samples
+            .iter()
+            .filter_map(|(service, sample)| {
+                let sample = sample.as_ref().filter(|s| s.is_skewed(threshold_ms))?;
+                Some(format!("{} by {} ms", service, sample.skew_ms))
+            })
+            .collect();
+        if !skewed.is_empty() {
+            info!(?skewed, "Clock skew");
+            alerts
+                .raise(
+                    "clock_skew".to_string(),
+                    Severity::Warning,
+                    format!(
+                        "Local clock is off from {} (threshold {} s)",
+                        skewed.join(", "),
+                        config.threshold_s()
+                    ),
+                )
+                .await;
+        } else if samples.values().any(Option::is_some) {
+            alerts.resolve("clock_skew").await;
+        }
+
+        *state.write().await = ClockSkewStatus {
+            threshold_s: config.threshold_s(),
+            samples,
+            skewed: !skewed.is_empty(),
+        };
+        tasks.record_refresh(CLOCK_SKEW_TASK).await;
+    }
+}
+
+/// Handler returning the latest comparisons of the local and upstream clocks
+pub async fn get_clock_skew(state: SharedClockSkew) -> Json<ClockSkewStatus> {
+    Json(state.read().await.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{http_date_skew_ms, ClockSample, ClockSource};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_clock_skew() {
+        let sent = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let received = sent + Duration::seconds(2);
+        // The local clock reads 12:00:01 mid-request
+        assert_eq!(
+            http_date_skew_ms(sent, received, "Mon, 10 Mar 2025 11:59:31 GMT"),
+            Some(30_000)
+        );
+        assert_eq!(http_date_skew_ms(sent, received, "yesterday"), None);
+
+        let sample = |source, skew_ms| ClockSample {
+            source,
+            skew_ms,
+            measured_at: sent,
+        };
+        assert!(sample(ClockSource::HttpDate, 30_000).is_skewed(10_000));
+        assert!(sample(ClockSource::HttpDate, -30_000).is_skewed(10_000));
+        assert!(!sample(ClockSource::HttpDate, 5_000).is_skewed(10_000));
+        // Old blocks only mean the chain is slow
+        assert!(!sample(ClockSource::BlockTimestamp, 30_000).is_skewed(10_000));
+        assert!(sample(ClockSource::BlockTimestamp, -30_000).is_skewed(10_000));
+    }
+}
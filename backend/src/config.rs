@@ -1,7 +1,26 @@
+use bitcoin::Network;
 use dotenvy::dotenv;
+use reqwest::header::HeaderMap;
+use std::{
+    collections::{BTreeMap, HashSet},
+    net::SocketAddr,
+};
 use tracing::info;
 
-use crate::activity::ActivityStatsKeys;
+use crate::{
+    activity::ActivityStatsKeys,
+    alert_rules::AlertRule,
+    auth::{ApiToken, EndpointGroup},
+    bridge::OperatorMetadata,
+    bridge_watchlist::{parse_watchlist, WatchlistEntry},
+    checks::CheckSpec,
+    cron::{CronSchedule, Schedule},
+    explorer::parse_extra_headers,
+    network::BUILTIN_COMPONENTS,
+    slo::SloSpec,
+    status_rules::StatusRules,
+    txid_format::TxidByteOrder,
+};
 
 #[derive(Debug, Clone)]
 pub(crate) struct NetworkConfig {
@@ -14,6 +33,9 @@ pub(crate) struct NetworkConfig {
     /// Bundler health check URL (overrides `.env`)
     bundler_url: String,
 
+    /// JSON-RPC Endpoint for the bundler
+    bundler_rpc_url: String,
+
     /// Max retries in querying status
     max_retries: u64,
 
@@ -25,6 +47,82 @@ pub(crate) struct NetworkConfig {
 
     /// Validating paymaster wallet
     validating_wallet: String,
+
+    /// Other hot wallets we operate, e.g. bridge operator EOAs, by name
+    operator_wallets: BTreeMap<String, String>,
+
+    /// Seconds a gap between the pending and latest nonce of a wallet may last,
+    /// without the latest nonce advancing, before its transactions are stuck
+    stuck_nonce_threshold_s: u64,
+
+    /// Name of the monitored network, reported in every API response
+    network_name: String,
+
+    /// EVM chain id of the monitored network
+    chain_id: Option<u64>,
+
+    /// Symbol of the native token, used in display strings of balances
+    native_token_symbol: String,
+
+    /// Block explorer URLs by name, e.g. the L2 and bitcoin explorers, served to the
+    /// frontends
+    block_explorer_urls: BTreeMap<String, String>,
+
+    /// Rules deciding whether each endpoint is online
+    status_rules: StatusRules,
+
+    /// Additional components to check, declared in config
+    status_checks: Vec<CheckSpec>,
+
+    /// URL of a JSON list of further checks, refetched periodically
+    status_registry_url: Option<String>,
+
+    /// Seconds between fetches of the status registry
+    status_registry_refresh_s: u64,
+
+    /// Consecutive polls with the same tip height after which the batch producer is stalled
+    stall_threshold_polls: usize,
+
+    /// File network status samples are persisted to, if any
+    status_history_path: Option<String>,
+
+    /// Number of days network status samples are kept
+    status_history_retention_days: u64,
+
+    /// Number of history records waiting to be persisted before new ones are dropped
+    history_write_buffer: usize,
+
+    /// Max number of records of each history kept in memory only, i.e. the status
+    /// history without `STATUS_HISTORY_PATH`, the balance history and the user ops
+    /// per minute
+    history_buffer_len: usize,
+
+    /// When the persisted histories are compacted
+    janitor_schedule: CronSchedule,
+
+    /// File dashboard annotations are persisted to, if any
+    annotations_path: Option<String>,
+
+    /// File the monitoring events are logged to for export, kept in memory only if
+    /// unset
+    event_log_path: Option<String>,
+
+    /// SQLite database the status history, event log, bridge changes and user ops
+    /// are stored in instead of their files, if any
+    database_url: Option<String>,
+
+    /// Read-only replica of the SQLite database serving the history range queries,
+    /// if any
+    database_replica_url: Option<String>,
+
+    /// File the admin-edited watched contracts are persisted to, if any
+    watched_contracts_path: Option<String>,
+
+    /// Paymaster wallet balance in Wei below which a `BalanceLow` event is published
+    low_balance_threshold_wei: Option<u128>,
+
+    /// Paymaster wallet balance in Wei below which a top-up is requested
+    critical_balance_threshold_wei: Option<u128>,
 }
 
 impl NetworkConfig {
@@ -39,6 +137,11 @@ impl NetworkConfig {
             .ok()
             .unwrap_or_else(|| "http://localhost:8433".to_string());
 
+        // The health check is served next to the JSON-RPC endpoint by default
+        let bundler_rpc_url = std::env::var("BUNDLER_RPC_URL")
+            .ok()
+            .unwrap_or_else(|| bundler_url.trim_end_matches("/health").to_string());
+
         let reth_url = std::env::var("RETH_URL")
             .ok()
             .unwrap_or_else(|| "http://localhost:8434".to_string());
@@ -61,16 +164,194 @@ impl NetworkConfig {
             .ok()
             .unwrap_or_else(|| "0xC0FFEE".to_string());
 
-        info!(%rpc_url, bundler_url, "Loaded Config");
+        // JSON object of wallet names to addresses
+        let operator_wallets: BTreeMap<String, String> = std::env::var("OPERATOR_WALLETS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse OPERATOR_WALLETS as JSON"))
+            .unwrap_or_default();
+
+        let stuck_nonce_threshold_s: u64 = std::env::var("STUCK_NONCE_THRESHOLD_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(300);
+
+        let network_name = std::env::var("NETWORK_NAME")
+            .ok()
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let chain_id: Option<u64> = std::env::var("CHAIN_ID")
+            .ok()
+            .map(|s| s.parse::<u64>().expect("to parse CHAIN_ID as u64"));
+
+        let native_token_symbol = std::env::var("NATIVE_TOKEN_SYMBOL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "ETH".to_string());
+
+        // e.g. `{"l2": "https://explorer.example.org", "bitcoin": "https://mempool.space/signet"}`
+        let block_explorer_urls: BTreeMap<String, String> = std::env::var("BLOCK_EXPLORER_URLS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                serde_json::from_str(&s).expect("to parse BLOCK_EXPLORER_URLS as JSON URLs by name")
+            })
+            .unwrap_or_default();
+
+        // e.g. `{"bundler_endpoint": {"expected_status_codes": [200], "failure_threshold": 3}}`
+        let status_rules: StatusRules = std::env::var("STATUS_RULES")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse STATUS_RULES as JSON rules"))
+            .unwrap_or_default();
+
+        // JSON list of checks, see `CheckSpec` and `.env.example`
+        let status_checks: Vec<CheckSpec> = std::env::var("STATUS_CHECKS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse STATUS_CHECKS as JSON checks"))
+            .unwrap_or_default();
+        for (i, check) in status_checks.iter().enumerate() {
+            assert!(
+                !BUILTIN_COMPONENTS.contains(&check.name()),
+                "STATUS_CHECKS must not redefine {}",
+                check.name()
+            );
+            assert!(
+                status_checks[..i]
+                    .iter()
+                    .all(|other| other.name() != check.name()),
+                "STATUS_CHECKS declares {} twice",
+                check.name()
+            );
+        }
+
+        // Serves a JSON list of checks in the `STATUS_CHECKS` format
+        let status_registry_url = std::env::var("STATUS_REGISTRY_URL")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let status_registry_refresh_s: u64 = std::env::var("STATUS_REGISTRY_REFRESH_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(300);
+
+        let stall_threshold_polls: usize = std::env::var("BATCH_PRODUCER_STALL_POLLS")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(6);
+
+        let status_history_path = std::env::var("STATUS_HISTORY_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let status_history_retention_days: u64 = std::env::var("STATUS_HISTORY_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        let history_write_buffer: usize = std::env::var("HISTORY_WRITE_BUFFER")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(1_024);
+
+        // A day of samples at the balance refresh interval
+        let history_buffer_len: usize = std::env::var("HISTORY_BUFFER_LEN")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|len| *len > 0)
+            .unwrap_or(8_640);
+
+        // Daily, off-peak by default
+        let janitor_schedule: CronSchedule = std::env::var("JANITOR_SCHEDULE")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or("0 3 * * *".to_string())
+            .parse()
+            .expect("to parse JANITOR_SCHEDULE as a cron expression");
+
+        let annotations_path = std::env::var("ANNOTATIONS_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let event_log_path = std::env::var("EVENT_LOG_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        // `SQLITE_PATH` predates `DATABASE_URL`, still honoured when the latter is unset
+        let database_url = std::env::var("DATABASE_URL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .or_else(|| std::env::var("SQLITE_PATH").ok().filter(|s| !s.is_empty()));
+
+        let database_replica_url = std::env::var("DATABASE_REPLICA_URL")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let watched_contracts_path = std::env::var("WATCHED_CONTRACTS_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let low_balance_threshold_wei: Option<u128> = std::env::var("PAYMASTER_LOW_BALANCE_WEI")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<u128>()
+                    .expect("to parse PAYMASTER_LOW_BALANCE_WEI as u128")
+            });
+
+        let critical_balance_threshold_wei: Option<u128> =
+            std::env::var("PAYMASTER_CRITICAL_BALANCE_WEI")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .map(|s| {
+                    s.parse::<u128>()
+                        .expect("to parse PAYMASTER_CRITICAL_BALANCE_WEI as u128")
+                });
+
+        info!(
+            %rpc_url,
+            bundler_url,
+            %network_name,
+            ?chain_id,
+            ?status_rules,
+            status_checks = status_checks.len(),
+            ?status_registry_url,
+            "Loaded Config"
+        );
 
         NetworkConfig {
             rpc_url,
             bundler_url,
+            bundler_rpc_url,
             reth_url,
             max_retries,
             total_retry_time,
             deposit_wallet,
             validating_wallet,
+            operator_wallets,
+            stuck_nonce_threshold_s,
+            network_name,
+            chain_id,
+            native_token_symbol,
+            block_explorer_urls,
+            status_rules,
+            status_checks,
+            status_registry_url,
+            status_registry_refresh_s,
+            stall_threshold_polls,
+            status_history_path,
+            status_history_retention_days,
+            history_write_buffer,
+            history_buffer_len,
+            janitor_schedule,
+            annotations_path,
+            event_log_path,
+            database_url,
+            database_replica_url,
+            watched_contracts_path,
+            low_balance_threshold_wei,
+            critical_balance_threshold_wei,
         }
     }
 
@@ -84,6 +365,11 @@ impl NetworkConfig {
         &self.bundler_url
     }
 
+    /// Getter for `bundler_rpc_url`
+    pub fn bundler_rpc_url(&self) -> &str {
+        &self.bundler_rpc_url
+    }
+
     pub fn reth_url(&self) -> &str {
         &self.reth_url
     }
@@ -102,9 +388,124 @@ impl NetworkConfig {
         &self.deposit_wallet
     }
 
+    /// Getter for `operator_wallets`
+    pub fn operator_wallets(&self) -> &BTreeMap<String, String> {
+        &self.operator_wallets
+    }
+
+    /// Getter for `stuck_nonce_threshold_s`
+    pub fn stuck_nonce_threshold_s(&self) -> u64 {
+        self.stuck_nonce_threshold_s
+    }
+
     pub fn validating_wallet(&self) -> &str {
         &self.validating_wallet
     }
+
+    /// Getter for `network_name`
+    pub fn network_name(&self) -> &str {
+        &self.network_name
+    }
+
+    /// Getter for `native_token_symbol`
+    pub fn native_token_symbol(&self) -> &str {
+        &self.native_token_symbol
+    }
+
+    /// Getter for `chain_id`
+    pub fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    /// Getter for `block_explorer_urls`
+    pub fn block_explorer_urls(&self) -> &BTreeMap<String, String> {
+        &self.block_explorer_urls
+    }
+
+    /// Getter for `status_rules`
+    pub fn status_rules(&self) -> &StatusRules {
+        &self.status_rules
+    }
+
+    /// Getter for `status_checks`
+    pub fn status_checks(&self) -> &[CheckSpec] {
+        &self.status_checks
+    }
+
+    /// Getter for `status_registry_url`
+    pub fn status_registry_url(&self) -> Option<&str> {
+        self.status_registry_url.as_deref()
+    }
+
+    /// Getter for `status_registry_refresh_s`
+    pub fn status_registry_refresh_s(&self) -> u64 {
+        self.status_registry_refresh_s
+    }
+
+    /// Getter for `stall_threshold_polls`
+    pub fn stall_threshold_polls(&self) -> usize {
+        self.stall_threshold_polls
+    }
+
+    /// Getter for `status_history_path`
+    pub fn status_history_path(&self) -> Option<&str> {
+        self.status_history_path.as_deref()
+    }
+
+    /// Getter for `status_history_retention_days`
+    pub fn status_history_retention_days(&self) -> u64 {
+        self.status_history_retention_days
+    }
+
+    /// Getter for `history_write_buffer`
+    pub fn history_write_buffer(&self) -> usize {
+        self.history_write_buffer
+    }
+
+    /// Getter for `history_buffer_len`
+    pub fn history_buffer_len(&self) -> usize {
+        self.history_buffer_len
+    }
+
+    /// Getter for `janitor_schedule`
+    pub fn janitor_schedule(&self) -> &CronSchedule {
+        &self.janitor_schedule
+    }
+
+    /// Getter for `annotations_path`
+    pub fn annotations_path(&self) -> Option<&str> {
+        self.annotations_path.as_deref()
+    }
+
+    /// Getter for `event_log_path`
+    pub fn event_log_path(&self) -> Option<&str> {
+        self.event_log_path.as_deref()
+    }
+
+    /// Getter for `database_url`
+    pub fn database_url(&self) -> Option<&str> {
+        self.database_url.as_deref()
+    }
+
+    /// Getter for `database_replica_url`
+    pub fn database_replica_url(&self) -> Option<&str> {
+        self.database_replica_url.as_deref()
+    }
+
+    /// Getter for `watched_contracts_path`
+    pub fn watched_contracts_path(&self) -> Option<&str> {
+        self.watched_contracts_path.as_deref()
+    }
+
+    /// Getter for `low_balance_threshold_wei`
+    pub fn low_balance_threshold_wei(&self) -> Option<u128> {
+        self.low_balance_threshold_wei
+    }
+
+    /// Getter for `critical_balance_threshold_wei`
+    pub fn critical_balance_threshold_wei(&self) -> Option<u128> {
+        self.critical_balance_threshold_wei
+    }
 }
 
 pub(crate) struct ActivityMonitoringConfig {
@@ -113,6 +514,37 @@ pub(crate) struct ActivityMonitoringConfig {
     stats_refetch_interval_s: u64,
     query_page_size: u64,
     activity_stats_keys: ActivityStatsKeys,
+    /// Max requests per second sent to each explorer host. `0` disables the limit.
+    explorer_max_rps: f64,
+    /// Number of explorer requests allowed back to back before rate limiting kicks in
+    explorer_rate_limit_burst: u32,
+    /// Max retries of a throttled explorer request
+    explorer_max_retries: u64,
+    /// Total time in seconds to spend retrying a throttled explorer request
+    explorer_total_retry_time: u64,
+    /// File the user ops scan progress is checkpointed to, if any
+    checkpoint_path: Option<String>,
+    /// File the scanned user ops are stored to for stats recomputations, kept in
+    /// memory only if unset
+    user_ops_path: Option<String>,
+    /// Extra headers (API keys, user agent) sent with every explorer request
+    explorer_headers: HeaderMap,
+    /// Lowercased addresses left out of the account lists, e.g. internal test accounts
+    account_denylist: HashSet<String>,
+    /// Whether to redact the middle characters of displayed account addresses
+    redact_addresses: bool,
+    /// When set, account lists are only published as aggregates, and gas totals of
+    /// lists with fewer accounts than this are withheld
+    accounts_k_anonymity: Option<usize>,
+    /// Seconds explorer responses are served from cache by the explorer proxy
+    proxy_cache_ttl_s: u64,
+    /// Explorer requests per second allowed to the explorer proxy, apart from those
+    /// of the monitoring tasks
+    proxy_max_rps: f64,
+    /// Number of proxy requests allowed to go out back to back
+    proxy_rate_limit_burst: u32,
+    /// Lowercased target contract addresses of each dApp, by dApp name
+    dapp_groups: BTreeMap<String, HashSet<String>>,
 }
 
 impl ActivityMonitoringConfig {
@@ -139,12 +571,124 @@ impl ActivityMonitoringConfig {
 
         let activity_stats_keys = ActivityMonitoringConfig::load_activity_keys();
 
+        let explorer_max_rps: f64 = std::env::var("EXPLORER_MAX_RPS")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(5.0);
+
+        let explorer_rate_limit_burst: u32 = std::env::var("EXPLORER_RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(5);
+
+        let explorer_max_retries: u64 = std::env::var("EXPLORER_MAX_RETRIES")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(3);
+
+        let explorer_total_retry_time: u64 = std::env::var("EXPLORER_TOTAL_RETRY_TIME")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        let checkpoint_path = std::env::var("ACTIVITY_CHECKPOINT_PATH").ok();
+
+        let user_ops_path = std::env::var("USER_OPS_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let explorer_headers = std::env::var("EXPLORER_HEADERS")
+            .ok()
+            .map(|raw| parse_extra_headers(&raw).expect("to parse EXPLORER_HEADERS"))
+            .unwrap_or_default();
+        // Only log header names, values may hold secrets
+        let explorer_header_names: Vec<&str> =
+            explorer_headers.keys().map(|name| name.as_str()).collect();
+        info!(?explorer_header_names, "Explorer extra headers");
+
+        let account_denylist: HashSet<String> = std::env::var("ACCOUNT_DENYLIST")
+            .ok()
+            .map(|addresses| {
+                addresses
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|address| !address.is_empty())
+                    .map(str::to_lowercase)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let redact_addresses: bool = std::env::var("REDACT_ADDRESSES")
+            .ok()
+            .and_then(|s| s.parse::<bool>().ok())
+            .unwrap_or(false);
+
+        let accounts_k_anonymity: Option<usize> = std::env::var("ACCOUNTS_K_ANONYMITY")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<usize>()
+                    .expect("to parse ACCOUNTS_K_ANONYMITY as usize")
+            });
+        info!(
+            denied_accounts = account_denylist.len(),
+            redact_addresses,
+            ?accounts_k_anonymity,
+            "Account display configuration"
+        );
+
+        let proxy_cache_ttl_s: u64 = std::env::var("EXPLORER_PROXY_CACHE_TTL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        let proxy_max_rps: f64 = std::env::var("EXPLORER_PROXY_MAX_RPS")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|rps| *rps > 0.0)
+            .unwrap_or(0.5);
+
+        let proxy_rate_limit_burst: u32 = std::env::var("EXPLORER_PROXY_RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(2);
+
+        let dapp_groups: BTreeMap<String, HashSet<String>> = std::env::var("DAPP_GROUPS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                let groups: BTreeMap<String, Vec<String>> = serde_json::from_str(&s)
+                    .expect("to parse DAPP_GROUPS as JSON contract addresses by dApp name");
+                groups
+                    .into_iter()
+                    .map(|(name, contracts)| {
+                        let contracts = contracts.iter().map(|c| c.to_lowercase()).collect();
+                        (name, contracts)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         ActivityMonitoringConfig {
             user_ops_query_url,
             accounts_query_url,
             stats_refetch_interval_s,
             query_page_size,
             activity_stats_keys,
+            explorer_max_rps,
+            explorer_rate_limit_burst,
+            explorer_max_retries,
+            explorer_total_retry_time,
+            checkpoint_path,
+            user_ops_path,
+            explorer_headers,
+            account_denylist,
+            redact_addresses,
+            accounts_k_anonymity,
+            proxy_cache_ttl_s,
+            proxy_max_rps,
+            proxy_rate_limit_burst,
+            dapp_groups,
         }
     }
 
@@ -152,7 +696,7 @@ impl ActivityMonitoringConfig {
     fn load_activity_keys() -> ActivityStatsKeys {
         // Path relative to backend
         let data = std::fs::read_to_string("activity_keys.json").expect("Unable to read file");
-        serde_json::from_str(&data).expect("JSON parsing failed")
+        ActivityStatsKeys::parse(&data).unwrap_or_else(|e| panic!("{:#}", e))
     }
 
     /// Getter for `user_ops_query_url`
@@ -179,6 +723,76 @@ impl ActivityMonitoringConfig {
     pub fn activity_stats_keys(&self) -> &ActivityStatsKeys {
         &self.activity_stats_keys
     }
+
+    /// Getter for `explorer_max_rps`
+    pub fn explorer_max_rps(&self) -> f64 {
+        self.explorer_max_rps
+    }
+
+    /// Getter for `explorer_rate_limit_burst`
+    pub fn explorer_rate_limit_burst(&self) -> u32 {
+        self.explorer_rate_limit_burst
+    }
+
+    /// Getter for `explorer_max_retries`
+    pub fn explorer_max_retries(&self) -> u64 {
+        self.explorer_max_retries
+    }
+
+    /// Getter for `explorer_total_retry_time`
+    pub fn explorer_total_retry_time(&self) -> u64 {
+        self.explorer_total_retry_time
+    }
+
+    /// Getter for `checkpoint_path`
+    pub fn checkpoint_path(&self) -> Option<&str> {
+        self.checkpoint_path.as_deref()
+    }
+
+    /// Getter for `user_ops_path`
+    pub fn user_ops_path(&self) -> Option<&str> {
+        self.user_ops_path.as_deref()
+    }
+
+    /// Getter for `explorer_headers`
+    pub fn explorer_headers(&self) -> &HeaderMap {
+        &self.explorer_headers
+    }
+
+    /// Whether `address` is on the account denylist
+    pub fn is_account_denied(&self, address: &str) -> bool {
+        self.account_denylist.contains(&address.to_lowercase())
+    }
+
+    /// Getter for `redact_addresses`
+    pub fn redact_addresses(&self) -> bool {
+        self.redact_addresses
+    }
+
+    /// Getter for `accounts_k_anonymity`
+    pub fn accounts_k_anonymity(&self) -> Option<usize> {
+        self.accounts_k_anonymity
+    }
+
+    /// Getter for `proxy_cache_ttl_s`
+    pub fn proxy_cache_ttl(&self) -> u64 {
+        self.proxy_cache_ttl_s
+    }
+
+    /// Getter for `proxy_max_rps`
+    pub fn proxy_max_rps(&self) -> f64 {
+        self.proxy_max_rps
+    }
+
+    /// Getter for `proxy_rate_limit_burst`
+    pub fn proxy_rate_limit_burst(&self) -> u32 {
+        self.proxy_rate_limit_burst
+    }
+
+    /// Getter for `dapp_groups`
+    pub fn dapp_groups(&self) -> &BTreeMap<String, HashSet<String>> {
+        &self.dapp_groups
+    }
 }
 
 /// Default bridge status refetch interval in seconds
@@ -192,6 +806,52 @@ pub struct BridgeMonitoringConfig {
     bridge_rpc_url: String,
     /// Bridge status refetch interval in seconds
     status_refetch_interval_s: u64,
+    /// Average operator status latency above which an operator is rated slow
+    operator_slow_threshold_ms: u64,
+    /// Number of unfulfilled duties per operator above which an alert is raised
+    duty_backlog_threshold: usize,
+    /// Esplora API url of a bitcoin node, used to check pending deposit requests
+    esplora_url: Option<String>,
+    /// Bitcoin network the bridge runs on, which upstream addresses must belong to
+    bitcoin_network: Network,
+    /// Reth RPC url, used to read the L2 bridged asset supply
+    l2_rpc_url: String,
+    /// Contract of the bridged asset on L2, until the watched contracts are edited.
+    /// The supply cross-check is skipped without any.
+    bridged_asset_address: Option<String>,
+    /// Decimals of the bridged asset
+    bridged_asset_decimals: u32,
+    /// Difference between L2 supply and outstanding deposits tolerated before alerting
+    supply_tolerance_sats: u64,
+    /// Deposit ids above the high-water mark probed per refresh for new deposits
+    deposit_batch_size: u32,
+    /// Seconds between full refetches of all deposits
+    full_resync_interval_s: u64,
+    /// File the known deposits are checkpointed to, kept in memory only if unset
+    checkpoint_path: Option<String>,
+    /// Seconds after which an operator heartbeat no longer counts it as up
+    heartbeat_max_age_s: u64,
+    /// Refresh cycles between polls of the statuses of the operators sending
+    /// heartbeats, unless their duties change in between. The others are polled on
+    /// every cycle.
+    operator_status_refresh_cycles: u32,
+    /// File bridge changes are persisted to, for reconstructing past bridge states
+    changes_path: Option<String>,
+    /// Deposits checked with priority and notified on every change
+    watchlist: Vec<WatchlistEntry>,
+    /// Seconds between checks of the watched deposits
+    watchlist_interval_s: u64,
+    /// Public details of the operators, by operator index
+    operators: BTreeMap<u32, OperatorMetadata>,
+    /// Days after which completed deposits are left out of the bridge status, kept
+    /// indefinitely if unset
+    prune_completed_after_days: Option<u64>,
+    /// Amount of every bridge deposit, served to the frontends
+    denomination_sats: Option<u64>,
+    /// Seconds a deposit may stay in progress before it is alerted on as stuck
+    stuck_deposit_age_s: u64,
+    /// Days deposits and claims no longer returned by the bridge are kept
+    tombstone_retention_days: u64,
 }
 
 impl BridgeMonitoringConfig {
@@ -211,12 +871,152 @@ impl BridgeMonitoringConfig {
             .and_then(|s| s.parse::<u64>().ok())
             .unwrap_or(DEFAULT_BRIDGE_STATUS_REFETCH_INTERVAL_S);
 
-        info!(%strata_rpc_url, %bridge_rpc_url, "Bridge monitoring configuration");
+        let operator_slow_threshold_ms: u64 = std::env::var("OPERATOR_SLOW_THRESHOLD_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(2_000);
+
+        let duty_backlog_threshold: usize = std::env::var("BRIDGE_DUTY_BACKLOG_THRESHOLD")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(10);
+
+        let esplora_url = std::env::var("ESPLORA_URL").ok().filter(|s| !s.is_empty());
+
+        // `mainnet` is accepted as an alias of the bitcoin crate's `bitcoin`
+        let bitcoin_network: Network = match std::env::var("BITCOIN_NETWORK").ok().as_deref() {
+            None | Some("") => Network::Signet,
+            Some("mainnet") => Network::Bitcoin,
+            Some(network) => network
+                .parse()
+                .expect("to parse BITCOIN_NETWORK as mainnet, testnet, signet or regtest"),
+        };
+
+        let l2_rpc_url = std::env::var("RETH_URL")
+            .ok()
+            .unwrap_or_else(|| "http://localhost:8434".to_string());
+
+        let bridged_asset_address = std::env::var("BRIDGED_ASSET_ADDRESS")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let bridged_asset_decimals: u32 = std::env::var("BRIDGED_ASSET_DECIMALS")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(18);
+
+        let supply_tolerance_sats: u64 = std::env::var("BRIDGE_SUPPLY_TOLERANCE_SATS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        let deposit_batch_size: u32 = std::env::var("BRIDGE_DEPOSIT_BATCH_SIZE")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(50);
+
+        let full_resync_interval_s: u64 = std::env::var("BRIDGE_FULL_RESYNC_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(3_600);
+
+        let checkpoint_path = std::env::var("BRIDGE_CHECKPOINT_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let heartbeat_max_age_s: u64 = std::env::var("OPERATOR_HEARTBEAT_MAX_AGE_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(300);
+
+        let operator_status_refresh_cycles: u32 =
+            std::env::var("BRIDGE_OPERATOR_STATUS_REFRESH_CYCLES")
+                .ok()
+                .and_then(|s| s.parse::<u32>().ok())
+                .unwrap_or(4)
+                .max(1);
+
+        let changes_path = std::env::var("BRIDGE_CHANGES_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let watchlist = parse_watchlist(&std::env::var("BRIDGE_WATCHLIST").unwrap_or_default())
+            .expect("to parse BRIDGE_WATCHLIST as comma-separated [<label>=]<txid>[:<vout>]");
+
+        let watchlist_interval_s: u64 = std::env::var("BRIDGE_WATCHLIST_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(15);
+
+        let operators: BTreeMap<u32, OperatorMetadata> = std::env::var("BRIDGE_OPERATORS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                serde_json::from_str(&s)
+                    .expect("to parse BRIDGE_OPERATORS as JSON operator metadata by index")
+            })
+            .unwrap_or_default();
+
+        let prune_completed_after_days: Option<u64> =
+            std::env::var("BRIDGE_PRUNE_COMPLETED_AFTER_DAYS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|days| *days > 0);
+
+        let denomination_sats: Option<u64> = std::env::var("BRIDGE_DENOMINATION_SATS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<u64>()
+                    .expect("to parse BRIDGE_DENOMINATION_SATS as u64")
+            });
+
+        let stuck_deposit_age_s: u64 = std::env::var("BRIDGE_STUCK_DEPOSIT_AGE_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(86_400);
+
+        let tombstone_retention_days: u64 = std::env::var("BRIDGE_TOMBSTONE_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        info!(
+            %strata_rpc_url,
+            %bridge_rpc_url,
+            ?esplora_url,
+            %bitcoin_network,
+            ?bridged_asset_address,
+            watched_deposits = watchlist.len(),
+            "Bridge monitoring configuration"
+        );
 
         BridgeMonitoringConfig {
             strata_rpc_url,
             bridge_rpc_url,
             status_refetch_interval_s: refresh_interval_s,
+            operator_slow_threshold_ms,
+            duty_backlog_threshold,
+            esplora_url,
+            bitcoin_network,
+            l2_rpc_url,
+            bridged_asset_address,
+            bridged_asset_decimals,
+            supply_tolerance_sats,
+            deposit_batch_size,
+            full_resync_interval_s,
+            checkpoint_path,
+            heartbeat_max_age_s,
+            operator_status_refresh_cycles,
+            changes_path,
+            watchlist,
+            watchlist_interval_s,
+            operators,
+            prune_completed_after_days,
+            denomination_sats,
+            stuck_deposit_age_s,
+            tombstone_retention_days,
         }
     }
 
@@ -234,4 +1034,1144 @@ impl BridgeMonitoringConfig {
     pub fn status_refetch_interval(&self) -> u64 {
         self.status_refetch_interval_s
     }
+
+    /// Getter for `operator_slow_threshold_ms`
+    pub fn operator_slow_threshold_ms(&self) -> u64 {
+        self.operator_slow_threshold_ms
+    }
+
+    /// Getter for `duty_backlog_threshold`
+    pub fn duty_backlog_threshold(&self) -> usize {
+        self.duty_backlog_threshold
+    }
+
+    /// Getter for `esplora_url`
+    pub fn esplora_url(&self) -> Option<&str> {
+        self.esplora_url.as_deref()
+    }
+
+    /// Getter for `bitcoin_network`
+    pub fn bitcoin_network(&self) -> Network {
+        self.bitcoin_network
+    }
+
+    /// Getter for `l2_rpc_url`
+    pub fn l2_rpc_url(&self) -> &str {
+        &self.l2_rpc_url
+    }
+
+    /// Getter for `bridged_asset_address`
+    pub fn bridged_asset_address(&self) -> Option<&str> {
+        self.bridged_asset_address.as_deref()
+    }
+
+    /// Getter for `bridged_asset_decimals`
+    pub fn bridged_asset_decimals(&self) -> u32 {
+        self.bridged_asset_decimals
+    }
+
+    /// Getter for `supply_tolerance_sats`
+    pub fn supply_tolerance_sats(&self) -> u64 {
+        self.supply_tolerance_sats
+    }
+
+    /// Getter for `deposit_batch_size`
+    pub fn deposit_batch_size(&self) -> u32 {
+        self.deposit_batch_size
+    }
+
+    /// Getter for `full_resync_interval_s`
+    pub fn full_resync_interval(&self) -> u64 {
+        self.full_resync_interval_s
+    }
+
+    /// Getter for `checkpoint_path`
+    pub fn checkpoint_path(&self) -> Option<&str> {
+        self.checkpoint_path.as_deref()
+    }
+
+    /// Getter for `heartbeat_max_age_s`
+    pub fn heartbeat_max_age(&self) -> u64 {
+        self.heartbeat_max_age_s
+    }
+
+    /// Getter for `operator_status_refresh_cycles`
+    pub fn operator_status_refresh_cycles(&self) -> u32 {
+        self.operator_status_refresh_cycles
+    }
+
+    /// Getter for `changes_path`
+    pub fn changes_path(&self) -> Option<&str> {
+        self.changes_path.as_deref()
+    }
+
+    /// Getter for `watchlist`
+    pub fn watchlist(&self) -> &[WatchlistEntry] {
+        &self.watchlist
+    }
+
+    /// Getter for `watchlist_interval_s`
+    pub fn watchlist_interval(&self) -> u64 {
+        self.watchlist_interval_s
+    }
+
+    /// Public details of the operator with index `index`, if declared
+    pub fn operator_metadata(&self, index: u32) -> Option<&OperatorMetadata> {
+        self.operators.get(&index)
+    }
+
+    /// Payout addresses declared for the operators
+    pub fn operator_payout_addresses(&self) -> Vec<&str> {
+        self.operators
+            .values()
+            .filter_map(OperatorMetadata::payout_address)
+            .collect()
+    }
+
+    /// Getter for `tombstone_retention_days`
+    pub fn tombstone_retention(&self) -> chrono::Duration {
+        chrono::Duration::days(self.tombstone_retention_days as i64)
+    }
+
+    /// Getter for `prune_completed_after_days`
+    pub fn prune_completed_after(&self) -> Option<chrono::Duration> {
+        self.prune_completed_after_days
+            .map(|days| chrono::Duration::days(days as i64))
+    }
+
+    /// Getter for `denomination_sats`
+    pub fn denomination_sats(&self) -> Option<u64> {
+        self.denomination_sats
+    }
+
+    /// Getter for `stuck_deposit_age_s`
+    pub fn stuck_deposit_age(&self) -> u64 {
+        self.stuck_deposit_age_s
+    }
+}
+
+/// ERC-4337 v0.7 entry point
+const DEFAULT_ENTRY_POINT: &str = "0x0000000071727De22E5E9d8BAf0edAc6f37da032";
+
+/// Bundler monitoring configuration
+pub struct BundlerMonitoringConfig {
+    /// Entry point whose bundler mempool is inspected, until the watched contracts
+    /// are edited
+    entry_point: String,
+    /// Explorer endpoint listing bundles, latest first
+    bundles_query_url: String,
+    /// Bundler stats refetch interval in seconds
+    stats_refetch_interval_s: u64,
+    /// Seconds without a new bundle, while user ops are pending, before bundling is stalled
+    stall_threshold_s: u64,
+    /// Trailing window of the bundle analytics in hours
+    analytics_window_h: u64,
+}
+
+impl BundlerMonitoringConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let entry_point = std::env::var("BUNDLER_ENTRY_POINT")
+            .ok()
+            .unwrap_or_else(|| DEFAULT_ENTRY_POINT.to_string());
+
+        let bundles_query_url = std::env::var("BUNDLES_QUERY_URL").ok().unwrap_or_else(|| {
+            "http://localhost/api/v2/proxy/account-abstraction/bundles".to_string()
+        });
+
+        let stats_refetch_interval_s: u64 = std::env::var("BUNDLER_STATS_REFETCH_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        let stall_threshold_s: u64 = std::env::var("BUNDLER_STALL_THRESHOLD_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(300);
+
+        let analytics_window_h: u64 = std::env::var("BUNDLE_ANALYTICS_WINDOW_H")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24);
+
+        info!(%entry_point, %bundles_query_url, "Bundler monitoring configuration");
+
+        BundlerMonitoringConfig {
+            entry_point,
+            bundles_query_url,
+            stats_refetch_interval_s,
+            stall_threshold_s,
+            analytics_window_h,
+        }
+    }
+
+    /// Getter for `entry_point`
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    /// Getter for `bundles_query_url`
+    pub fn bundles_query_url(&self) -> &str {
+        &self.bundles_query_url
+    }
+
+    /// Getter for `stats_refetch_interval_s`
+    pub fn stats_refetch_interval(&self) -> u64 {
+        self.stats_refetch_interval_s
+    }
+
+    /// Getter for `stall_threshold_s`
+    pub fn stall_threshold_s(&self) -> u64 {
+        self.stall_threshold_s
+    }
+
+    /// Getter for `analytics_window_h`
+    pub fn analytics_window_h(&self) -> u64 {
+        self.analytics_window_h
+    }
+}
+
+/// Alert rules configuration
+pub struct AlertRulesConfig {
+    /// Rules evaluated against the shared states
+    rules: Vec<AlertRule>,
+    /// Rule evaluation interval in seconds
+    evaluation_interval_s: u64,
+}
+
+impl AlertRulesConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        // JSON list of rules, see `AlertRule` and `.env.example`
+        let rules: Vec<AlertRule> = std::env::var("ALERT_RULES")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse ALERT_RULES as JSON rules"))
+            .unwrap_or_default();
+
+        let evaluation_interval_s: u64 = std::env::var("ALERT_RULES_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        info!(rules = rules.len(), "Alert rules configuration");
+
+        AlertRulesConfig {
+            rules,
+            evaluation_interval_s,
+        }
+    }
+
+    /// Getter for `rules`
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Getter for `evaluation_interval_s`
+    pub fn evaluation_interval(&self) -> u64 {
+        self.evaluation_interval_s
+    }
+}
+
+/// Other environments exposed under `/api/<environment>/...`
+pub struct CanaryConfig {
+    /// Base url of the backend of each environment, by environment name
+    environments: BTreeMap<String, String>,
+    /// API token sent to the backend of each environment, by environment name
+    tokens: BTreeMap<String, String>,
+    /// Seconds before a request to the backend of an environment times out
+    timeout_s: u64,
+}
+
+impl CanaryConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let environments: BTreeMap<String, String> = std::env::var("CANARY_ENVIRONMENTS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                serde_json::from_str(&s)
+                    .expect("to parse CANARY_ENVIRONMENTS as JSON backend urls by environment")
+            })
+            .unwrap_or_default();
+
+        // e.g. `{"staging": "<token>"}`, checked by the backend of the environment
+        let tokens: BTreeMap<String, String> = std::env::var("CANARY_TOKENS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                serde_json::from_str(&s)
+                    .expect("to parse CANARY_TOKENS as JSON API tokens by environment")
+            })
+            .unwrap_or_default();
+
+        let timeout_s: u64 = std::env::var("CANARY_TIMEOUT_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(10);
+
+        info!(
+            environments = ?environments.keys().collect::<Vec<_>>(),
+            "Canary configuration"
+        );
+
+        CanaryConfig {
+            environments,
+            tokens,
+            timeout_s,
+        }
+    }
+
+    /// Getter for `environments`
+    pub fn environments(&self) -> &BTreeMap<String, String> {
+        &self.environments
+    }
+
+    /// Getter for `tokens`
+    pub fn tokens(&self) -> &BTreeMap<String, String> {
+        &self.tokens
+    }
+
+    /// Getter for `timeout_s`
+    pub fn timeout_s(&self) -> u64 {
+        self.timeout_s
+    }
+}
+
+/// Configuration drift detection
+pub struct DriftConfig {
+    /// Expected size of the bridge operator set, not checked if unset
+    expected_operator_count: Option<usize>,
+    /// Seconds between comparisons of the live and configured parameters
+    check_interval_s: u64,
+}
+
+impl DriftConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let expected_operator_count: Option<usize> = std::env::var("EXPECTED_BRIDGE_OPERATORS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<usize>()
+                    .expect("to parse EXPECTED_BRIDGE_OPERATORS as usize")
+            });
+
+        let check_interval_s: u64 = std::env::var("DRIFT_CHECK_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(300);
+
+        info!(
+            ?expected_operator_count,
+            check_interval_s, "Drift detection configuration"
+        );
+
+        DriftConfig {
+            expected_operator_count,
+            check_interval_s,
+        }
+    }
+
+    /// Getter for `expected_operator_count`
+    pub fn expected_operator_count(&self) -> Option<usize> {
+        self.expected_operator_count
+    }
+
+    /// Getter for `check_interval_s`
+    pub fn check_interval(&self) -> u64 {
+        self.check_interval_s
+    }
+}
+
+/// Clock skew monitoring configuration
+pub struct ClockSkewConfig {
+    /// Seconds the local clock may be off from the upstream services before alerting
+    threshold_s: u64,
+    /// Seconds between comparisons of the local and upstream clocks
+    check_interval_s: u64,
+}
+
+impl ClockSkewConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let threshold_s: u64 = std::env::var("CLOCK_SKEW_THRESHOLD_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        let check_interval_s: u64 = std::env::var("CLOCK_SKEW_CHECK_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(300);
+
+        info!(
+            threshold_s,
+            check_interval_s, "Clock skew monitoring configuration"
+        );
+
+        ClockSkewConfig {
+            threshold_s,
+            check_interval_s,
+        }
+    }
+
+    /// Getter for `threshold_s`
+    pub fn threshold_s(&self) -> u64 {
+        self.threshold_s
+    }
+
+    /// Getter for `check_interval_s`
+    pub fn check_interval(&self) -> u64 {
+        self.check_interval_s
+    }
+}
+
+/// Service level objectives configuration
+pub struct SloConfig {
+    /// Objectives whose error budget is tracked
+    slos: Vec<SloSpec>,
+    /// Trailing window of the burn rate in seconds
+    burn_rate_window_s: u64,
+}
+
+impl SloConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        // JSON list of objectives, see `SloSpec` and `.env.example`
+        let slos: Vec<SloSpec> = std::env::var("SLOS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse SLOS as JSON objectives"))
+            .unwrap_or_default();
+        for slo in &slos {
+            assert!(
+                slo.target() > 0.0 && slo.target() < 1.0,
+                "SLOS target of {} must be between 0 and 1",
+                slo.name()
+            );
+        }
+
+        let burn_rate_window_s: u64 = std::env::var("SLO_BURN_RATE_WINDOW_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(3_600);
+
+        info!(slos = slos.len(), burn_rate_window_s, "SLO configuration");
+
+        SloConfig {
+            slos,
+            burn_rate_window_s,
+        }
+    }
+
+    /// Getter for `slos`
+    pub fn slos(&self) -> &[SloSpec] {
+        &self.slos
+    }
+
+    /// Getter for `burn_rate_window_s`
+    pub fn burn_rate_window_s(&self) -> u64 {
+        self.burn_rate_window_s
+    }
+}
+
+/// Scheduled reports configuration
+pub struct ReportsConfig {
+    /// S3-compatible endpoint, e.g. `https://s3.eu-central-1.amazonaws.com`;
+    /// reports are disabled unless both the endpoint and bucket are set
+    s3_endpoint: Option<String>,
+    s3_bucket: Option<String>,
+    s3_region: String,
+    s3_access_key_id: String,
+    s3_secret_access_key: String,
+    /// Key prefix of uploaded reports
+    s3_prefix: String,
+    /// When reports are generated, a cron expression or an interval in seconds
+    schedule: Schedule,
+}
+
+impl ReportsConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+
+        let s3_endpoint = non_empty("REPORTS_S3_ENDPOINT");
+        let s3_bucket = non_empty("REPORTS_S3_BUCKET");
+        let s3_region = non_empty("REPORTS_S3_REGION").unwrap_or("us-east-1".to_string());
+        let s3_access_key_id = non_empty("REPORTS_S3_ACCESS_KEY_ID").unwrap_or_default();
+        let s3_secret_access_key = non_empty("REPORTS_S3_SECRET_ACCESS_KEY").unwrap_or_default();
+        let s3_prefix = non_empty("REPORTS_S3_PREFIX")
+            .map(|prefix| prefix.trim_matches('/').to_string())
+            .unwrap_or("reports".to_string());
+
+        // Weekly by default
+        let interval_s: u64 = std::env::var("REPORTS_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(7 * 24 * 3600);
+        // A calendar schedule takes precedence over the interval
+        let schedule = match non_empty("REPORTS_SCHEDULE") {
+            Some(expression) => Schedule::Cron(
+                expression
+                    .parse()
+                    .expect("to parse REPORTS_SCHEDULE as a cron expression"),
+            ),
+            None => Schedule::Every(interval_s),
+        };
+
+        info!(
+            enabled = s3_endpoint.is_some() && s3_bucket.is_some(),
+            ?s3_bucket,
+            ?schedule,
+            "Reports configuration"
+        );
+
+        ReportsConfig {
+            s3_endpoint,
+            s3_bucket,
+            s3_region,
+            s3_access_key_id,
+            s3_secret_access_key,
+            s3_prefix,
+            schedule,
+        }
+    }
+
+    /// Getter for `s3_endpoint` and `s3_bucket`, `None` when reports are disabled
+    pub fn s3_location(&self) -> Option<(&str, &str)> {
+        self.s3_endpoint.as_deref().zip(self.s3_bucket.as_deref())
+    }
+
+    /// Getter for `s3_region`
+    pub fn s3_region(&self) -> &str {
+        &self.s3_region
+    }
+
+    /// Getter for `s3_access_key_id`
+    pub fn s3_access_key_id(&self) -> &str {
+        &self.s3_access_key_id
+    }
+
+    /// Getter for `s3_secret_access_key`
+    pub fn s3_secret_access_key(&self) -> &str {
+        &self.s3_secret_access_key
+    }
+
+    /// Getter for `s3_prefix`
+    pub fn s3_prefix(&self) -> &str {
+        &self.s3_prefix
+    }
+
+    /// Getter for `schedule`
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+}
+
+/// Raw upstream response archival configuration, see `archive`
+pub struct ArchiveConfig {
+    /// Directory archive files are written to
+    dir: Option<String>,
+    /// S3-compatible endpoint archive files are uploaded to, along with the bucket
+    s3_endpoint: Option<String>,
+    s3_bucket: Option<String>,
+    s3_region: String,
+    s3_access_key_id: String,
+    s3_secret_access_key: String,
+    /// Key prefix of uploaded archive files
+    s3_prefix: String,
+    /// One response out of `sample_every` is archived
+    sample_every: u64,
+    /// Seconds between archive files
+    flush_interval_s: u64,
+    zstd_level: i32,
+}
+
+impl ArchiveConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+
+        let dir = non_empty("ARCHIVE_DIR");
+        let s3_endpoint = non_empty("ARCHIVE_S3_ENDPOINT");
+        let s3_bucket = non_empty("ARCHIVE_S3_BUCKET");
+        let s3_region = non_empty("ARCHIVE_S3_REGION").unwrap_or("us-east-1".to_string());
+        let s3_access_key_id = non_empty("ARCHIVE_S3_ACCESS_KEY_ID").unwrap_or_default();
+        let s3_secret_access_key = non_empty("ARCHIVE_S3_SECRET_ACCESS_KEY").unwrap_or_default();
+        let s3_prefix = non_empty("ARCHIVE_S3_PREFIX")
+            .map(|prefix| prefix.trim_matches('/').to_string())
+            .unwrap_or("archive".to_string());
+
+        let sample_every: u64 = std::env::var("ARCHIVE_SAMPLE_EVERY")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|every| *every > 0)
+            .unwrap_or(1);
+        let flush_interval_s: u64 = std::env::var("ARCHIVE_FLUSH_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(300);
+        let zstd_level: i32 = std::env::var("ARCHIVE_ZSTD_LEVEL")
+            .ok()
+            .and_then(|s| s.parse::<i32>().ok())
+            .unwrap_or(3)
+            .clamp(1, 19);
+
+        let config = ArchiveConfig {
+            dir,
+            s3_endpoint,
+            s3_bucket,
+            s3_region,
+            s3_access_key_id,
+            s3_secret_access_key,
+            s3_prefix,
+            sample_every,
+            flush_interval_s,
+            zstd_level,
+        };
+        info!(
+            enabled = config.enabled(),
+            dir = ?config.dir,
+            s3_bucket = ?config.s3_bucket,
+            sample_every,
+            flush_interval_s,
+            "Response archive configuration"
+        );
+        config
+    }
+
+    /// Whether responses are archived, to a directory or bucket
+    pub fn enabled(&self) -> bool {
+        self.dir.is_some() || self.s3_location().is_some()
+    }
+
+    /// Getter for `dir`
+    pub fn dir(&self) -> Option<&str> {
+        self.dir.as_deref()
+    }
+
+    /// Getter for `s3_endpoint` and `s3_bucket`, `None` when not uploading
+    pub fn s3_location(&self) -> Option<(&str, &str)> {
+        self.s3_endpoint.as_deref().zip(self.s3_bucket.as_deref())
+    }
+
+    /// Getter for `s3_region`
+    pub fn s3_region(&self) -> &str {
+        &self.s3_region
+    }
+
+    /// Getter for `s3_access_key_id`
+    pub fn s3_access_key_id(&self) -> &str {
+        &self.s3_access_key_id
+    }
+
+    /// Getter for `s3_secret_access_key`
+    pub fn s3_secret_access_key(&self) -> &str {
+        &self.s3_secret_access_key
+    }
+
+    /// Getter for `s3_prefix`
+    pub fn s3_prefix(&self) -> &str {
+        &self.s3_prefix
+    }
+
+    /// Getter for `sample_every`
+    pub fn sample_every(&self) -> u64 {
+        self.sample_every
+    }
+
+    /// Getter for `flush_interval_s`
+    pub fn flush_interval_s(&self) -> u64 {
+        self.flush_interval_s
+    }
+
+    /// Getter for `zstd_level`
+    pub fn zstd_level(&self) -> i32 {
+        self.zstd_level
+    }
+}
+
+/// ClickHouse sink of the bundle analytics, see `clickhouse`
+pub struct ClickHouseConfig {
+    /// HTTP interface URL, e.g. `http://localhost:8123`; the sink is disabled if unset
+    url: Option<String>,
+    database: String,
+    user: String,
+    password: String,
+    /// Table the indexed bundles are inserted into
+    bundles_table: String,
+    /// Seconds between inserts
+    flush_interval_s: u64,
+}
+
+impl ClickHouseConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+
+        let url = non_empty("CLICKHOUSE_URL");
+        let database = non_empty("CLICKHOUSE_DATABASE").unwrap_or("default".to_string());
+        let user = non_empty("CLICKHOUSE_USER").unwrap_or("default".to_string());
+        let password = non_empty("CLICKHOUSE_PASSWORD").unwrap_or_default();
+        let bundles_table = non_empty("CLICKHOUSE_BUNDLES_TABLE").unwrap_or("bundles".to_string());
+        let flush_interval_s: u64 = std::env::var("CLICKHOUSE_FLUSH_INTERVAL_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(10);
+
+        info!(
+            enabled = url.is_some(),
+            %database,
+            %bundles_table,
+            flush_interval_s,
+            "ClickHouse configuration"
+        );
+
+        ClickHouseConfig {
+            url,
+            database,
+            user,
+            password,
+            bundles_table,
+            flush_interval_s,
+        }
+    }
+
+    /// Getter for `url`
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Getter for `database`
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+
+    /// Getter for `user`
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Getter for `password`
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Getter for `bundles_table`
+    pub fn bundles_table(&self) -> &str {
+        &self.bundles_table
+    }
+
+    /// Getter for `flush_interval_s`
+    pub fn flush_interval_s(&self) -> u64 {
+        self.flush_interval_s
+    }
+}
+
+/// Web Push configuration, see <https://datatracker.ietf.org/doc/html/rfc8292>
+pub struct PushConfig {
+    /// Base64url-encoded VAPID private key; push notifications are disabled if unset
+    vapid_private_key: Option<String>,
+    /// Base64url-encoded VAPID public key, passed to browsers subscribing
+    vapid_public_key: String,
+    /// Contact of the dashboard operator sent to push services, e.g. `mailto:ops@example.com`
+    vapid_subject: String,
+    /// File push subscriptions are stored in
+    subscriptions_path: String,
+    /// Template file of the notification titles, see `templates`
+    title_template_path: Option<String>,
+    /// Template file of the notification bodies, the alert message if unset
+    body_template_path: Option<String>,
+}
+
+impl PushConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+
+        let vapid_private_key = non_empty("WEB_PUSH_VAPID_PRIVATE_KEY");
+        let vapid_public_key = non_empty("WEB_PUSH_VAPID_PUBLIC_KEY").unwrap_or_default();
+        let vapid_subject =
+            non_empty("WEB_PUSH_SUBJECT").unwrap_or("mailto:admin@localhost".to_string());
+        let subscriptions_path = non_empty("WEB_PUSH_SUBSCRIPTIONS_PATH")
+            .unwrap_or("push_subscriptions.json".to_string());
+        let title_template_path = non_empty("WEB_PUSH_TITLE_TEMPLATE_PATH");
+        let body_template_path = non_empty("WEB_PUSH_BODY_TEMPLATE_PATH");
+
+        assert!(
+            vapid_private_key.is_none() || !vapid_public_key.is_empty(),
+            "WEB_PUSH_VAPID_PUBLIC_KEY is required with WEB_PUSH_VAPID_PRIVATE_KEY"
+        );
+        info!(
+            enabled = vapid_private_key.is_some(),
+            %subscriptions_path,
+            ?title_template_path,
+            ?body_template_path,
+            "Web Push configuration"
+        );
+
+        PushConfig {
+            vapid_private_key,
+            vapid_public_key,
+            vapid_subject,
+            subscriptions_path,
+            title_template_path,
+            body_template_path,
+        }
+    }
+
+    /// Getter for `vapid_private_key`
+    pub fn vapid_private_key(&self) -> Option<&str> {
+        self.vapid_private_key.as_deref()
+    }
+
+    /// Getter for `vapid_public_key`
+    pub fn vapid_public_key(&self) -> &str {
+        &self.vapid_public_key
+    }
+
+    /// Getter for `vapid_subject`
+    pub fn vapid_subject(&self) -> &str {
+        &self.vapid_subject
+    }
+
+    /// Getter for `subscriptions_path`
+    pub fn subscriptions_path(&self) -> &str {
+        &self.subscriptions_path
+    }
+
+    /// Getter for `title_template_path`
+    pub fn title_template_path(&self) -> Option<&str> {
+        self.title_template_path.as_deref()
+    }
+
+    /// Getter for `body_template_path`
+    pub fn body_template_path(&self) -> Option<&str> {
+        self.body_template_path.as_deref()
+    }
+}
+
+/// Treasury automation hooks, called when a paymaster balance drops below
+/// `PAYMASTER_CRITICAL_BALANCE_WEI`
+pub struct TopUpConfig {
+    /// URL the top-up request is POSTed to as JSON, if any
+    webhook_url: Option<String>,
+    /// Shell command run with the top-up request in `TOP_UP_*` env vars, if any
+    command: Option<String>,
+    /// Min time in seconds between two top-ups of the same wallet
+    cooldown_s: u64,
+    /// Max time in seconds a hook may take before it is considered failed
+    timeout_s: u64,
+    /// JSON lines file the top-up audit trail is appended to, if any
+    audit_path: Option<String>,
+}
+
+impl TopUpConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+
+        let webhook_url = non_empty("TOP_UP_WEBHOOK_URL");
+        let command = non_empty("TOP_UP_COMMAND");
+        let audit_path = non_empty("TOP_UP_AUDIT_PATH");
+
+        let cooldown_s: u64 = std::env::var("TOP_UP_COOLDOWN_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(3_600);
+
+        let timeout_s: u64 = std::env::var("TOP_UP_TIMEOUT_S")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30);
+
+        info!(
+            webhook = webhook_url.is_some(),
+            command = command.is_some(),
+            cooldown_s,
+            timeout_s,
+            ?audit_path,
+            "Top-up configuration"
+        );
+
+        TopUpConfig {
+            webhook_url,
+            command,
+            cooldown_s,
+            timeout_s,
+            audit_path,
+        }
+    }
+
+    /// Whether any hook is configured
+    pub fn enabled(&self) -> bool {
+        self.webhook_url.is_some() || self.command.is_some()
+    }
+
+    /// Getter for `webhook_url`
+    pub fn webhook_url(&self) -> Option<&str> {
+        self.webhook_url.as_deref()
+    }
+
+    /// Getter for `command`
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    /// Getter for `cooldown_s`
+    pub fn cooldown_s(&self) -> u64 {
+        self.cooldown_s
+    }
+
+    /// Getter for `timeout_s`
+    pub fn timeout_s(&self) -> u64 {
+        self.timeout_s
+    }
+
+    /// Getter for `audit_path`
+    pub fn audit_path(&self) -> Option<&str> {
+        self.audit_path.as_deref()
+    }
+}
+
+/// Default address the HTTP server listens on
+const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:3000";
+
+/// Default max wait for the first refresh of the monitoring tasks, in seconds
+const DEFAULT_WARM_UP_TIMEOUT_S: u64 = 30;
+
+/// Default status page translations file, next to the binary in the image
+const DEFAULT_TRANSLATIONS_PATH: &str = "translations.json";
+
+/// HTTP server configuration
+pub struct ServerConfig {
+    /// Addresses to listen on. On Linux `[::]:3000` alone accepts both IPv6
+    /// and IPv4 connections (dual-stack) unless `net.ipv6.bindv6only` is set.
+    listen_addrs: Vec<SocketAddr>,
+    /// Bearer token for admin endpoints; they are disabled when unset
+    admin_token: Option<String>,
+    /// Tokens granting access to endpoint groups that are not public
+    api_tokens: Vec<ApiToken>,
+    /// Endpoint groups reachable without an API token
+    public_endpoint_groups: Vec<EndpointGroup>,
+    /// Byte order of the txids in API responses and request paths
+    txid_byte_order: TxidByteOrder,
+    /// Max time to wait for the first refresh of the monitoring tasks before
+    /// listening, in seconds; 0 listens right away
+    warm_up_timeout_s: u64,
+    /// JSON file of the status page messages in other languages than English, if
+    /// any; `translations.json` in the working directory unless set
+    translations_path: Option<String>,
+    /// External uptime monitor URLs pinged after each refresh cycle, by task name
+    uptime_pings: BTreeMap<String, String>,
+    /// File the admin-edited uptime pings are persisted to, if any
+    uptime_pings_path: Option<String>,
+    /// Address of the JSON-RPC interface to the dashboard data; it is disabled when unset
+    rpc_listen_addr: Option<SocketAddr>,
+    /// Header holding the client address set by a reverse proxy, e.g.
+    /// `x-forwarded-for`; API consumers are told apart by peer address when unset
+    consumer_ip_header: Option<String>,
+    /// Max API requests per consumer and UTC day, unbounded if unset
+    consumer_daily_quota: Option<u64>,
+}
+
+impl ServerConfig {
+    pub fn new() -> Self {
+        dotenv().ok(); // Load `.env` file if present
+
+        let listen_addrs: Vec<SocketAddr> = std::env::var("LISTEN_ADDRS")
+            .unwrap_or(DEFAULT_LISTEN_ADDR.to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|addr| !addr.is_empty())
+            .map(|addr| {
+                addr.parse()
+                    .expect("to parse LISTEN_ADDRS as socket addresses")
+            })
+            .collect();
+        assert!(!listen_addrs.is_empty(), "LISTEN_ADDRS must not be empty");
+
+        let admin_token = std::env::var("ADMIN_API_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+
+        // JSON list of tokens, see `ApiToken` and `.env.example`
+        let api_tokens: Vec<ApiToken> = std::env::var("API_TOKENS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse API_TOKENS as JSON tokens"))
+            .unwrap_or_default();
+
+        // Every endpoint group but the admin and proxy ones is public unless restricted
+        let public_endpoint_groups: Vec<EndpointGroup> = std::env::var("API_PUBLIC_GROUPS")
+            .ok()
+            .map(|groups| {
+                groups
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|group| !group.is_empty())
+                    .map(|group| {
+                        serde_json::from_value(serde_json::Value::String(group.to_string()))
+                            .expect("to parse API_PUBLIC_GROUPS as endpoint groups")
+                    })
+                    .collect()
+            })
+            .unwrap_or(EndpointGroup::PUBLIC_BY_DEFAULT.to_vec());
+
+        let txid_byte_order: TxidByteOrder = std::env::var("API_TXID_BYTE_ORDER")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                serde_json::from_value(serde_json::Value::String(s))
+                    .expect("to parse API_TXID_BYTE_ORDER as display or internal")
+            })
+            .unwrap_or_default();
+
+        let warm_up_timeout_s: u64 = std::env::var("WARM_UP_TIMEOUT_S")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_WARM_UP_TIMEOUT_S);
+
+        // Set empty to serve the status page in English only
+        let translations_path = match std::env::var("STATUS_PAGE_TRANSLATIONS_PATH") {
+            Ok(path) => Some(path).filter(|s| !s.is_empty()),
+            Err(_) => Some(DEFAULT_TRANSLATIONS_PATH.to_string()),
+        };
+
+        // e.g. `{"bridge_status": "https://hc-ping.com/<uuid>"}`
+        let uptime_pings: BTreeMap<String, String> = std::env::var("UPTIME_PINGS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).expect("to parse UPTIME_PINGS as JSON URLs by task"))
+            .unwrap_or_default();
+
+        let uptime_pings_path = std::env::var("UPTIME_PINGS_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let rpc_listen_addr: Option<SocketAddr> = std::env::var("RPC_LISTEN_ADDR")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|addr| {
+                addr.parse()
+                    .expect("to parse RPC_LISTEN_ADDR as a socket address")
+            });
+
+        let consumer_ip_header = std::env::var("CONSUMER_IP_HEADER")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase());
+        let consumer_daily_quota: Option<u64> = std::env::var("CONSUMER_DAILY_QUOTA")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|quota| *quota > 0);
+
+        info!(
+            ?listen_addrs,
+            admin_enabled = admin_token.is_some(),
+            api_tokens = api_tokens.len(),
+            ?public_endpoint_groups,
+            ?txid_byte_order,
+            warm_up_timeout_s,
+            uptime_pings = uptime_pings.len(),
+            ?rpc_listen_addr,
+            ?consumer_ip_header,
+            ?consumer_daily_quota,
+            "Server configuration"
+        );
+
+        ServerConfig {
+            listen_addrs,
+            admin_token,
+            api_tokens,
+            public_endpoint_groups,
+            txid_byte_order,
+            warm_up_timeout_s,
+            translations_path,
+            uptime_pings,
+            uptime_pings_path,
+            rpc_listen_addr,
+            consumer_ip_header,
+            consumer_daily_quota,
+        }
+    }
+
+    /// Getter for `listen_addrs`
+    pub fn listen_addrs(&self) -> &[SocketAddr] {
+        &self.listen_addrs
+    }
+
+    /// Getter for `admin_token`
+    pub fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
+
+    /// Getter for `api_tokens`
+    pub fn api_tokens(&self) -> &[ApiToken] {
+        &self.api_tokens
+    }
+
+    /// Getter for `public_endpoint_groups`
+    pub fn public_endpoint_groups(&self) -> &[EndpointGroup] {
+        &self.public_endpoint_groups
+    }
+
+    /// Getter for `txid_byte_order`
+    pub fn txid_byte_order(&self) -> TxidByteOrder {
+        self.txid_byte_order
+    }
+
+    /// Getter for `warm_up_timeout_s`
+    pub fn warm_up_timeout_s(&self) -> u64 {
+        self.warm_up_timeout_s
+    }
+
+    /// Getter for `translations_path`
+    pub fn translations_path(&self) -> Option<&str> {
+        self.translations_path.as_deref()
+    }
+
+    /// Getter for `uptime_pings`
+    pub fn uptime_pings(&self) -> &BTreeMap<String, String> {
+        &self.uptime_pings
+    }
+
+    /// Getter for `uptime_pings_path`
+    pub fn uptime_pings_path(&self) -> Option<&str> {
+        self.uptime_pings_path.as_deref()
+    }
+
+    /// Getter for `rpc_listen_addr`
+    pub fn rpc_listen_addr(&self) -> Option<SocketAddr> {
+        self.rpc_listen_addr
+    }
+
+    /// Getter for `consumer_ip_header`
+    pub fn consumer_ip_header(&self) -> Option<&str> {
+        self.consumer_ip_header.as_deref()
+    }
+
+    /// Getter for `consumer_daily_quota`
+    pub fn consumer_daily_quota(&self) -> Option<u64> {
+        self.consumer_daily_quota
+    }
 }
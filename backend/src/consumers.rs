@@ -0,0 +1,284 @@
+//! Usage metering of the API consumers, to spot noisy integrators and enforce
+//! the optional `CONSUMER_DAILY_QUOTA`.
+//!
+//! Requests carrying a known API token are counted for that token, see
+//! `ApiToken::name`, others for the client IP address: the first address of
+//! `CONSUMER_IP_HEADER` behind a proxy, the peer address otherwise.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tracing::warn;
+
+use crate::auth::{AdminAuth, ApiAuth, EndpointGroup};
+
+/// Max number of consumers tracked; the least recently seen are forgotten first
+const MAX_CONSUMERS: usize = 10_000;
+
+/// Requests of a consumer
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ConsumerUsage {
+    /// e.g. `token:partner` or `ip:203.0.113.7`
+    consumer: String,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    /// Requests since first seen
+    requests_total: u64,
+    /// Requests of the current UTC day, by endpoint group
+    today: BTreeMap<EndpointGroup, u64>,
+    /// Requests of the current UTC day rejected for exceeding the quota
+    rejected_today: u64,
+}
+
+impl ConsumerUsage {
+    fn requests_today(&self) -> u64 {
+        self.today.values().sum()
+    }
+}
+
+#[derive(Debug)]
+struct Consumers {
+    day: NaiveDate,
+    by_consumer: HashMap<String, ConsumerUsage>,
+}
+
+impl Consumers {
+    /// Counts a request of `consumer` to `group` at `now`, returning whether it is
+    /// within the daily quota
+    fn record(
+        &mut self,
+        consumer: &str,
+        group: EndpointGroup,
+        daily_quota: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        if now.date_naive() != self.day {
+            self.day = now.date_naive();
+            for usage in self.by_consumer.values_mut() {
+                usage.today.clear();
+                usage.rejected_today = 0;
+            }
+        }
+        if !self.by_consumer.contains_key(consumer) && self.by_consumer.len() >= MAX_CONSUMERS {
+            self.forget_least_recent();
+        }
+
+        let usage = self
+            .by_consumer
+            .entry(consumer.to_string())
+            .or_insert_with(|| ConsumerUsage {
+                consumer: consumer.to_string(),
+                first_seen: now,
+                last_seen: now,
+                requests_total: 0,
+                today: BTreeMap::new(),
+                rejected_today: 0,
+            });
+        usage.last_seen = now;
+        if daily_quota.is_some_and(|quota| usage.requests_today() >= quota) {
+            if usage.rejected_today == 0 {
+                warn!(consumer, "API consumer exceeded its daily quota");
+            }
+            usage.rejected_today += 1;
+            return false;
+        }
+        usage.requests_total += 1;
+        *usage.today.entry(group).or_default() += 1;
+        true
+    }
+
+    fn forget_least_recent(&mut self) {
+        let least_recent = self
+            .by_consumer
+            .values()
+            .min_by_key(|usage| usage.last_seen)
+            .map(|usage| usage.consumer.clone());
+        if let Some(consumer) = least_recent {
+            self.by_consumer.remove(&consumer);
+        }
+    }
+}
+
+/// Request counts by API consumer
+#[derive(Clone, Debug)]
+pub struct ConsumerMeter {
+    auth: ApiAuth,
+    /// Header holding the client address set by a reverse proxy, e.g. `x-forwarded-for`
+    ip_header: Option<String>,
+    /// Max requests per consumer and UTC day, unbounded if unset
+    daily_quota: Option<u64>,
+    consumers: Arc<Mutex<Consumers>>,
+}
+
+impl ConsumerMeter {
+    pub fn new(auth: ApiAuth, ip_header: Option<String>, daily_quota: Option<u64>) -> Self {
+        Self {
+            auth,
+            ip_header,
+            daily_quota,
+            consumers: Arc::new(Mutex::new(Consumers {
+                day: Utc::now().date_naive(),
+                by_consumer: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Consumer sending a request with `headers` from `peer`
+    fn consumer(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> String {
+        if let Some(name) = self.auth.consumer(headers) {
+            return format!("token:{}", name);
+        }
+        let forwarded = self
+            .ip_header
+            .as_deref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty());
+        match (forwarded, peer) {
+            (Some(ip), _) => format!("ip:{}", ip),
+            (None, Some(peer)) => format!("ip:{}", peer.ip()),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    /// Counts a request of `consumer`, returning whether it is within the quota
+    fn record(&self, consumer: &str, group: EndpointGroup) -> bool {
+        self.consumers
+            .lock()
+            .unwrap()
+            .record(consumer, group, self.daily_quota, Utc::now())
+    }
+
+    /// Usage of every tracked consumer, the busiest today first
+    fn usage(&self) -> Vec<ConsumerUsage> {
+        let today = Utc::now().date_naive();
+        let consumers = self.consumers.lock().unwrap();
+        let mut usage: Vec<ConsumerUsage> = consumers
+            .by_consumer
+            .values()
+            .map(|usage| {
+                let mut usage = usage.clone();
+                // Counts of a previous day not reset yet
+                if usage.last_seen.date_naive() != today {
+                    usage.today.clear();
+                    usage.rejected_today = 0;
+                }
+                usage
+            })
+            .collect();
+        usage.sort_by(|a, b| {
+            b.requests_today()
+                .cmp(&a.requests_today())
+                .then_with(|| a.consumer.cmp(&b.consumer))
+        });
+        usage
+    }
+}
+
+/// Middleware counting the API requests of each consumer, rejecting those over
+/// the daily quota with `429 Too Many Requests`
+pub async fn meter_consumers(
+    State(meter): State<ConsumerMeter>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(group) = EndpointGroup::of_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let consumer = meter.consumer(request.headers(), peer);
+    if meter.record(&consumer, group) {
+        next.run(request).await
+    } else {
+        StatusCode::TOO_MANY_REQUESTS.into_response()
+    }
+}
+
+/// Usage of the API consumers
+#[derive(Serialize, Debug)]
+pub struct ConsumersResponse {
+    daily_quota: Option<u64>,
+    consumers: Vec<ConsumerUsage>,
+}
+
+/// Return the request counts of the API consumers. Requires the admin token.
+pub async fn get_consumers(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    meter: ConsumerMeter,
+) -> Result<Json<ConsumersResponse>, StatusCode> {
+    auth.check(&headers)?;
+    Ok(Json(ConsumersResponse {
+        daily_quota: meter.daily_quota,
+        consumers: meter.usage(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConsumerMeter, Consumers};
+    use crate::auth::{ApiAuth, ApiToken, EndpointGroup};
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+    use chrono::{Duration, TimeZone, Utc};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_consumer_metering() {
+        let token: ApiToken = serde_json::from_value(json!({
+            "token": "s3cret",
+            "name": "partner",
+            "networks": ["testnet"],
+            "groups": ["bridge"],
+        }))
+        .unwrap();
+        let auth = ApiAuth::new("testnet".to_string(), vec![token], vec![]);
+        let meter = ConsumerMeter::new(auth, Some("x-forwarded-for".to_string()), Some(2));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer s3cret"));
+        assert_eq!(meter.consumer(&headers, None), "token:partner");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.1"),
+        );
+        assert_eq!(meter.consumer(&headers, None), "ip:203.0.113.7");
+        let peer = "192.0.2.1:4000".parse().ok();
+        assert_eq!(meter.consumer(&HeaderMap::new(), peer), "ip:192.0.2.1");
+
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let mut consumers = Consumers {
+            day: now.date_naive(),
+            by_consumer: HashMap::new(),
+        };
+        assert!(consumers.record("ip:a", EndpointGroup::Status, Some(2), now));
+        assert!(consumers.record("ip:a", EndpointGroup::Bridge, Some(2), now));
+        assert!(!consumers.record("ip:a", EndpointGroup::Status, Some(2), now));
+        let usage = &consumers.by_consumer["ip:a"];
+        assert_eq!((usage.requests_total, usage.rejected_today), (2, 1));
+        assert_eq!(usage.today[&EndpointGroup::Bridge], 1);
+
+        // The quota starts over on the next day
+        let tomorrow = now + Duration::days(1);
+        assert!(consumers.record("ip:a", EndpointGroup::Status, Some(2), tomorrow));
+        assert_eq!(consumers.by_consumer["ip:a"].requests_total, 3);
+        assert_eq!(consumers.by_consumer["ip:a"].rejected_today, 0);
+    }
+}
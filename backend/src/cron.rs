@@ -0,0 +1,240 @@
+//! Calendar schedules of periodic jobs, e.g. a daily digest at 08:00 UTC
+//! (`0 8 * * *`) rather than every 24h from whenever the backend started
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use std::{fmt, str::FromStr};
+
+/// How far ahead to look for a matching time, e.g. `0 0 29 2 *` fires only on
+/// leap years
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+/// Parses one field of a cron expression into a bitmask of the allowed values.
+///
+/// A field is a comma-separated list of `*`, `n` or `a-b` items with an optional
+/// `/step`, e.g. `*/15` or `1-5,10`.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step in {:?}", item))?;
+                if step == 0 {
+                    return Err(format!("zero step in {:?}", item));
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let parse = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("{:?} is not within {}-{}", value, min, max))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                None => {
+                    let value = parse(range)?;
+                    // `n/step` starts at n and runs to the end of the field
+                    (value, if step > 1 { max } else { value })
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("empty range {:?}", item));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Cron expression with the five standard fields, in UTC:
+/// minute, hour, day of month, month and day of week (0 or 7 for Sunday)
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether the day of month or day of week field is `*`. As in cron, a day
+    /// matches either restricted field when both are.
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields in {:?}, got {}",
+                expression,
+                fields.len()
+            ));
+        };
+        let mut days_of_week_mask = parse_field(days_of_week, 0, 7)?;
+        if days_of_week_mask & (1 << 7) != 0 {
+            days_of_week_mask |= 1;
+        }
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week: days_of_week_mask,
+            any_day_of_month: days_of_month == "*",
+            any_day_of_week: days_of_week == "*",
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl CronSchedule {
+    fn matches_day(&self, at: DateTime<Utc>) -> bool {
+        let day_of_month = self.days_of_month & (1 << at.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << at.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    /// First matching minute strictly after `after`, `None` if there is none in the
+    /// next few years, e.g. for `0 0 31 2 *`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut at = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let limit = at + Duration::days(MAX_LOOKAHEAD_DAYS);
+
+        while at < limit {
+            let start_of_day = at.duration_trunc(Duration::days(1)).ok()?;
+            if self.months & (1 << at.month()) == 0 || !self.matches_day(at) {
+                at = start_of_day + Duration::days(1);
+            } else if self.hours & (1 << at.hour()) == 0 {
+                at = at.duration_trunc(Duration::hours(1)).ok()? + Duration::hours(1);
+            } else if self.minutes & (1 << at.minute()) == 0 {
+                at += Duration::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+}
+
+/// When a periodic job runs
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schedule {
+    /// At a fixed interval in seconds
+    Every(u64),
+    Cron(CronSchedule),
+}
+
+impl Schedule {
+    /// Next run strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(interval_s) => Some(after + Duration::seconds(*interval_s as i64)),
+            Schedule::Cron(cron) => cron.next_after(after),
+        }
+    }
+
+    /// Time between the next two runs in seconds, to judge whether the job is stale
+    pub fn interval_s(&self, now: DateTime<Utc>) -> u64 {
+        let first = self.next_after(now);
+        let second = first.and_then(|first| self.next_after(first));
+        match first.zip(second) {
+            Some((first, second)) => (second - first).num_seconds().max(1) as u64,
+            None => (MAX_LOOKAHEAD_DAYS * 24 * 3600) as u64,
+        }
+    }
+}
+
+/// Sleeps until `at`, returning right away if it is past
+pub async fn sleep_until(at: DateTime<Utc>) {
+    let duration = (at - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CronSchedule, Schedule};
+    use chrono::{TimeZone, Utc};
+
+    fn cron(expression: &str) -> CronSchedule {
+        expression.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cron_parsing() {
+        assert_eq!(cron("*/15  8 * * 1-5").to_string(), "*/15 8 * * 1-5");
+        assert!("* * * *".parse::<CronSchedule>().is_err());
+        assert!("60 * * * *".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("5-1 * * * *".parse::<CronSchedule>().is_err());
+        assert!("0 0 0 * *".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        // Monday 2025-03-10 12:34:56
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 12, 34, 56).unwrap();
+        let next = |expression: &str| cron(expression).next_after(at).unwrap();
+
+        assert_eq!(
+            next("* * * * *"),
+            Utc.with_ymd_and_hms(2025, 3, 10, 12, 35, 0).unwrap()
+        );
+        assert_eq!(
+            next("*/15 * * * *"),
+            Utc.with_ymd_and_hms(2025, 3, 10, 12, 45, 0).unwrap()
+        );
+        assert_eq!(
+            next("0 8 * * *"),
+            Utc.with_ymd_and_hms(2025, 3, 11, 8, 0, 0).unwrap()
+        );
+        // Sunday, as 0 or 7
+        assert_eq!(
+            next("30 6 * * 0"),
+            Utc.with_ymd_and_hms(2025, 3, 16, 6, 30, 0).unwrap()
+        );
+        assert_eq!(next("30 6 * * 7"), next("30 6 * * 0"));
+        assert_eq!(
+            next("0 0 1 1,7 *"),
+            Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap()
+        );
+        // Either day field matches when both are restricted
+        assert_eq!(
+            next("0 0 20 * 3"),
+            Utc.with_ymd_and_hms(2025, 3, 12, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            next("0 0 29 2 *"),
+            Utc.with_ymd_and_hms(2028, 2, 29, 0, 0, 0).unwrap()
+        );
+        assert_eq!(cron("0 0 31 2 *").next_after(at), None);
+    }
+
+    #[test]
+    fn test_schedule_interval() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 12, 34, 56).unwrap();
+        assert_eq!(Schedule::Every(600).interval_s(at), 600);
+        assert_eq!(Schedule::Cron(cron("0 8 * * *")).interval_s(at), 24 * 3600);
+    }
+}
@@ -0,0 +1,175 @@
+//! Upstream failures degrading the state served by the monitoring tasks, so that
+//! responses can say which upstream is failing rather than silently serving stale
+//! data.
+//!
+//! Failures are attributed to the task whose future sends the request, as for the
+//! request budgets, see [`budget::in_task`]. A task is degraded from the first
+//! refresh cycle in which one of its upstreams failed, until a cycle in which it
+//! did not. Failures outside of the monitoring tasks, e.g. in API handlers, are not
+//! tracked.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{
+    budget::{self, BudgetExceeded, OTHER_TASK},
+    checks::http_failure,
+    network::FailureKind,
+};
+
+/// Upstream failing during the last refresh cycle of a task
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Degradation {
+    /// Upstream, as named for the request budgets, e.g. `explorer`
+    pub upstream: &'static str,
+    /// Start of the first failed refresh cycle in a row
+    pub since: DateTime<Utc>,
+    /// Kind of the latest error
+    pub error_class: FailureKind,
+}
+
+/// First failure time and latest failure kind, by upstream
+type CycleFailures = BTreeMap<&'static str, (DateTime<Utc>, FailureKind)>;
+
+#[derive(Debug)]
+struct Degradations {
+    /// Failures of the refresh cycle in progress, by task
+    current_cycle: BTreeMap<&'static str, CycleFailures>,
+    /// Failures of the last completed refresh cycle, by task
+    last_cycle: BTreeMap<&'static str, Vec<Degradation>>,
+}
+
+impl Degradations {
+    const fn new() -> Self {
+        Self {
+            current_cycle: BTreeMap::new(),
+            last_cycle: BTreeMap::new(),
+        }
+    }
+
+    fn record_failure(
+        &mut self,
+        task: &'static str,
+        upstream: &'static str,
+        kind: FailureKind,
+        at: DateTime<Utc>,
+    ) {
+        self.current_cycle
+            .entry(task)
+            .or_default()
+            .entry(upstream)
+            .and_modify(|(_, latest)| *latest = kind)
+            .or_insert((at, kind));
+    }
+
+    /// Replaces the failures of `task` with those of its cycle in progress, keeping
+    /// the start of the upstreams still failing
+    fn end_cycle(&mut self, task: &'static str) {
+        let failures = self.current_cycle.remove(task).unwrap_or_default();
+        let previous = self.last_cycle.remove(task).unwrap_or_default();
+        let degradations: Vec<Degradation> = failures
+            .into_iter()
+            .map(|(upstream, (at, error_class))| Degradation {
+                upstream,
+                since: previous
+                    .iter()
+                    .find(|degradation| degradation.upstream == upstream)
+                    .map_or(at, |degradation| degradation.since),
+                error_class,
+            })
+            .collect();
+        if !degradations.is_empty() {
+            self.last_cycle.insert(task, degradations);
+        }
+    }
+}
+
+/// Upstream clients are created throughout the backend, so the failures are
+/// recorded globally, next to the request accounting of [`budget`].
+static DEGRADATIONS: Mutex<Degradations> = Mutex::new(Degradations::new());
+
+/// Records a failed request to `upstream` for the task of the current future
+pub fn record_failure(upstream: &'static str, kind: FailureKind) {
+    let task = budget::current_task();
+    if task == OTHER_TASK {
+        return;
+    }
+    DEGRADATIONS
+        .lock()
+        .unwrap()
+        .record_failure(task, upstream, kind, Utc::now());
+}
+
+/// Closes the refresh cycle of `task`, see [`degradations`]
+pub fn end_cycle(task: &'static str) {
+    DEGRADATIONS.lock().unwrap().end_cycle(task);
+}
+
+/// Latest failure kind of each upstream that failed so far during the refresh cycle
+/// of `task` in progress
+pub fn cycle_failures(task: &str) -> Vec<FailureKind> {
+    DEGRADATIONS
+        .lock()
+        .unwrap()
+        .current_cycle
+        .get(task)
+        .map(|failures| failures.values().map(|(_, kind)| *kind).collect())
+        .unwrap_or_default()
+}
+
+/// Upstreams that failed during the last completed refresh cycle of `task`
+pub fn degradations(task: &str) -> Vec<Degradation> {
+    DEGRADATIONS
+        .lock()
+        .unwrap()
+        .last_cycle
+        .get(task)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Kind of a failed HTTP request, once converted into an `anyhow` error
+pub fn failure_kind(error: &anyhow::Error) -> FailureKind {
+    if error.downcast_ref::<BudgetExceeded>().is_some() {
+        FailureKind::BudgetExhausted
+    } else if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        http_failure(error).reason
+    } else {
+        FailureKind::UnexpectedResponse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Degradations;
+    use crate::network::FailureKind;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_degradations() {
+        let start = Utc::now();
+        let mut degradations = Degradations::new();
+        degradations.record_failure("bridge_status", "bridge", FailureKind::Timeout, start);
+        // Not reported before the cycle completes
+        assert!(!degradations.last_cycle.contains_key("bridge_status"));
+
+        degradations.end_cycle("bridge_status");
+        let degraded = &degradations.last_cycle["bridge_status"];
+        assert_eq!(degraded.len(), 1);
+        assert_eq!(degraded[0].since, start);
+
+        // Still failing, the start is kept while the class follows the latest error
+        let later = start + Duration::seconds(30);
+        degradations.record_failure("bridge_status", "bridge", FailureKind::Timeout, later);
+        degradations.record_failure("bridge_status", "bridge", FailureKind::RpcError, later);
+        degradations.end_cycle("bridge_status");
+        let degraded = &degradations.last_cycle["bridge_status"];
+        assert_eq!(degraded[0].since, start);
+        assert_eq!(degraded[0].error_class, FailureKind::RpcError);
+
+        // Recovered
+        degradations.end_cycle("bridge_status");
+        assert!(!degradations.last_cycle.contains_key("bridge_status"));
+    }
+}
@@ -0,0 +1,176 @@
+use axum::Json;
+use serde::Serialize;
+use std::{collections::BTreeMap, fs};
+use tokio::{runtime::Handle, time::Instant};
+use tokio_metrics::TaskMetrics;
+
+use crate::{
+    budget::{self, RequestUsage},
+    clock_skew::{ClockSkewStatus, SharedClockSkew},
+    tasks::TaskRegistry,
+};
+
+/// Resident memory of the process, from `/proc/self/status`; unset on other
+/// platforms than Linux
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct MemoryUsage {
+    rss_bytes: Option<u64>,
+    /// Highest resident memory since startup
+    peak_rss_bytes: Option<u64>,
+}
+
+impl MemoryUsage {
+    /// Parses the `VmRSS` and `VmHWM` lines of a `/proc/<pid>/status` file
+    fn from_proc_status(status: &str) -> Self {
+        let kib = |key: &str| {
+            status.lines().find_map(|line| {
+                let value = line.strip_prefix(key)?.strip_prefix(':')?;
+                let kib: u64 = value.trim().strip_suffix("kB")?.trim().parse().ok()?;
+                Some(kib * 1024)
+            })
+        };
+        Self {
+            rss_bytes: kib("VmRSS"),
+            peak_rss_bytes: kib("VmHWM"),
+        }
+    }
+
+    fn current() -> Self {
+        fs::read_to_string("/proc/self/status")
+            .map(|status| Self::from_proc_status(&status))
+            .unwrap_or_default()
+    }
+}
+
+/// Load of the tokio runtime
+#[derive(Serialize, Debug)]
+pub struct RuntimeUsage {
+    workers: usize,
+    /// Spawned tasks that have not completed, including the request handlers
+    alive_tasks: usize,
+    /// Tasks scheduled but not picked up by a worker yet
+    global_queue_depth: usize,
+    /// Time each worker spent busy since startup in milliseconds
+    worker_busy_ms: Vec<u64>,
+}
+
+impl RuntimeUsage {
+    fn current() -> Self {
+        let metrics = Handle::current().metrics();
+        let workers = metrics.num_workers();
+        Self {
+            workers,
+            alive_tasks: metrics.num_alive_tasks(),
+            global_queue_depth: metrics.global_queue_depth(),
+            worker_busy_ms: (0..workers)
+                .map(|worker| metrics.worker_total_busy_duration(worker).as_millis() as u64)
+                .collect(),
+        }
+    }
+}
+
+/// Polls of the future of a monitoring task since startup
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TaskPolls {
+    polls: u64,
+    /// Time spent in polls in milliseconds
+    poll_ms: u64,
+    mean_poll_us: u64,
+    /// Polls that took over 50µs, holding up a runtime thread
+    slow_polls: u64,
+    slow_poll_ms: u64,
+    /// Mean time between the task being woken and polled in microseconds
+    mean_scheduled_us: u64,
+}
+
+impl From<&TaskMetrics> for TaskPolls {
+    fn from(metrics: &TaskMetrics) -> Self {
+        Self {
+            polls: metrics.total_poll_count,
+            poll_ms: metrics.total_poll_duration.as_millis() as u64,
+            mean_poll_us: metrics.mean_poll_duration().as_micros() as u64,
+            slow_polls: metrics.total_slow_poll_count,
+            slow_poll_ms: metrics.total_slow_poll_duration.as_millis() as u64,
+            mean_scheduled_us: metrics.mean_scheduled_duration().as_micros() as u64,
+        }
+    }
+}
+
+/// Time a monitoring task spends in its refresh cycles
+#[derive(Serialize, Debug)]
+pub struct TaskRuntime {
+    last_duration_ms: Option<u64>,
+    avg_duration_ms: Option<u64>,
+    /// Share of the interval the average refresh cycle takes
+    busy_ratio: Option<f64>,
+    /// Unset if the task is not instrumented
+    polls: Option<TaskPolls>,
+}
+
+/// Resource usage of the backend itself
+#[derive(Serialize, Debug)]
+pub struct RuntimeDiagnostics {
+    uptime_s: u64,
+    memory: MemoryUsage,
+    runtime: RuntimeUsage,
+    /// By task name
+    tasks: BTreeMap<&'static str, TaskRuntime>,
+    /// Offset of the local clock from the upstream services
+    clock: ClockSkewStatus,
+    /// Outbound upstream requests and their daily budgets
+    requests: Option<RequestUsage>,
+}
+
+/// Handler returning the memory and runtime usage of the backend
+pub async fn get_runtime_diagnostics(
+    tasks: TaskRegistry,
+    clock_skew: SharedClockSkew,
+    started_at: Instant,
+) -> Json<RuntimeDiagnostics> {
+    let poll_metrics = tasks.poll_metrics();
+    let tasks = tasks
+        .snapshot()
+        .await
+        .into_iter()
+        .map(|(name, task)| {
+            let busy_ratio = task
+                .avg_duration_ms()
+                .filter(|_| task.interval_s() > 0)
+                .map(|avg_ms| avg_ms as f64 / (task.interval_s() * 1000) as f64);
+            let runtime = TaskRuntime {
+                last_duration_ms: task.last_duration_ms(),
+                avg_duration_ms: task.avg_duration_ms(),
+                busy_ratio,
+                polls: poll_metrics.get(name).map(TaskPolls::from),
+            };
+            (name, runtime)
+        })
+        .collect();
+
+    Json(RuntimeDiagnostics {
+        uptime_s: started_at.elapsed().as_secs(),
+        memory: MemoryUsage::current(),
+        runtime: RuntimeUsage::current(),
+        tasks,
+        clock: clock_skew.read().await.clone(),
+        requests: budget::usage(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryUsage;
+
+    #[test]
+    fn test_memory_usage_from_proc_status() {
+        let status = "Name:\tbackend\nVmHWM:\t   20480 kB\nVmRSS:\t   10240 kB\nThreads:\t8\n";
+        assert_eq!(
+            MemoryUsage::from_proc_status(status),
+            MemoryUsage {
+                rss_bytes: Some(10 * 1024 * 1024),
+                peak_rss_bytes: Some(20 * 1024 * 1024),
+            }
+        );
+        assert_eq!(MemoryUsage::from_proc_status(""), MemoryUsage::default());
+    }
+}
@@ -0,0 +1,94 @@
+//! Display strings sent next to raw numbers, so that every frontend renders them
+//! identically
+
+/// Fractional digits shown for token amounts
+const AMOUNT_DISPLAY_DECIMALS: u32 = 4;
+
+/// `12.0` as `12`, `12.3` as `12.3`, from a number of tenths
+fn tenths_to_string(tenths: u128) -> String {
+    if tenths % 10 == 0 {
+        (tenths / 10).to_string()
+    } else {
+        format!("{}.{}", tenths / 10, tenths % 10)
+    }
+}
+
+/// Count with a `K`, `M`, `B` or `T` suffix and one decimal, e.g. `1.2M`
+pub fn compact_count(n: u128) -> String {
+    if n < 1_000 {
+        return n.to_string();
+    }
+    let mut scale: u128 = 1;
+    for suffix in ["K", "M", "B"] {
+        scale *= 1_000;
+        let tenths = (n * 10 + scale / 2) / scale;
+        // Rounding may carry over to the next suffix, e.g. 999_950 is `1M`
+        if tenths < 10_000 {
+            return format!("{}{}", tenths_to_string(tenths), suffix);
+        }
+    }
+    // Larger counts stay in trillions
+    scale *= 1_000;
+    let tenths = (n * 10 + scale / 2) / scale;
+    format!("{}T", tenths_to_string(tenths))
+}
+
+/// Token amount in base units as whole tokens, e.g. `3.4 ETH` for 3.4e18 Wei
+pub fn format_amount(amount: u128, decimals: u32, symbol: &str) -> String {
+    let whole = amount / 10u128.pow(decimals);
+    if whole >= 1_000 {
+        return format!("{} {}", compact_count(whole), symbol);
+    }
+
+    let shown_decimals = decimals.min(AMOUNT_DISPLAY_DECIMALS);
+    let step = 10u128.pow(decimals - shown_decimals);
+    let scaled = (amount + step / 2) / step;
+    if amount > 0 && scaled == 0 {
+        return format!(
+            "<0.{:0>width$} {}",
+            1,
+            symbol,
+            width = shown_decimals as usize
+        );
+    }
+
+    let unit = 10u128.pow(shown_decimals);
+    let fraction = format!(
+        "{:0>width$}",
+        scaled % unit,
+        width = shown_decimals as usize
+    );
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{} {}", scaled / unit, symbol)
+    } else {
+        format!("{}.{} {}", scaled / unit, fraction, symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact_count, format_amount};
+
+    #[test]
+    fn test_compact_count() {
+        assert_eq!(compact_count(999), "999");
+        assert_eq!(compact_count(1_000), "1K");
+        assert_eq!(compact_count(1_234_567), "1.2M");
+        assert_eq!(compact_count(999_950), "1M");
+        assert_eq!(compact_count(3_450_000_000), "3.5B");
+        assert_eq!(compact_count(999_950_000_000), "1T");
+        assert_eq!(compact_count(12_000_000_000_000_000), "12000T");
+    }
+
+    #[test]
+    fn test_format_amount() {
+        let wei = 10u128.pow(18);
+        assert_eq!(format_amount(34 * wei / 10, 18, "ETH"), "3.4 ETH");
+        assert_eq!(format_amount(0, 18, "ETH"), "0 ETH");
+        assert_eq!(format_amount(wei / 3, 18, "ETH"), "0.3333 ETH");
+        assert_eq!(format_amount(1, 18, "ETH"), "<0.0001 ETH");
+        assert_eq!(format_amount(2_500 * wei, 18, "ETH"), "2.5K ETH");
+        assert_eq!(format_amount(150_000_000, 8, "BTC"), "1.5 BTC");
+    }
+}
@@ -0,0 +1,241 @@
+use jsonrpsee::{core::client::ClientT, http_client::HttpClient};
+use std::fmt::Display;
+use tokio::time::{interval, Duration};
+use tracing::{info, warn};
+
+use crate::{
+    alerts::{Alerts, Severity},
+    clients::BridgeClient,
+    config::{DriftConfig, NetworkConfig},
+    tasks::{TaskRegistry, DRIFT_CHECK_TASK},
+    utils::create_rpc_client,
+    watched::WatchedContracts,
+};
+
+/// Parameters of the deployment as configured
+#[derive(Debug, Default)]
+struct ExpectedParameters {
+    /// See `CHAIN_ID`
+    chain_id: Option<u64>,
+    /// Watched entry points
+    entry_points: Vec<String>,
+    /// See `EXPECTED_BRIDGE_OPERATORS`
+    operator_count: Option<usize>,
+}
+
+/// Parameters reported by the live services, unset when the query failed
+#[derive(Debug, Default)]
+struct ObservedParameters {
+    /// Chain id of the L2 node
+    chain_id: Option<u64>,
+    /// Chain id of the bundler
+    bundler_chain_id: Option<u64>,
+    /// Entry points supported by the bundler
+    entry_points: Option<Vec<String>>,
+    /// Size of the bridge operator set
+    operator_count: Option<usize>,
+}
+
+/// Comparison of a parameter with its expected value
+#[derive(Debug, PartialEq)]
+enum Check {
+    Matches,
+    Drifted(String),
+}
+
+fn compare<T: PartialEq + Display>(expected: Option<T>, observed: Option<T>) -> Option<Check> {
+    let (expected, observed) = expected.zip(observed)?;
+    Some(if expected == observed {
+        Check::Matches
+    } else {
+        Check::Drifted(format!("expected {}, found {}", expected, observed))
+    })
+}
+
+/// Checks of the parameters known on both sides, by parameter name
+fn check_parameters(
+    expected: &ExpectedParameters,
+    observed: &ObservedParameters,
+) -> Vec<(&'static str, Check)> {
+    let entry_points = observed
+        .entry_points
+        .as_ref()
+        .filter(|_| !expected.entry_points.is_empty())
+        .map(|supported| {
+            let missing: Vec<&str> = expected
+                .entry_points
+                .iter()
+                .filter(|entry_point| {
+                    !supported
+                        .iter()
+                        .any(|supported| supported.eq_ignore_ascii_case(entry_point))
+                })
+                .map(String::as_str)
+                .collect();
+            if missing.is_empty() {
+                Check::Matches
+            } else {
+                Check::Drifted(format!(
+                    "{} not supported, the bundler supports [{}]",
+                    missing.join(", "),
+                    supported.join(", ")
+                ))
+            }
+        });
+
+    [
+        ("chain_id", compare(expected.chain_id, observed.chain_id)),
+        (
+            "bundler_chain_id",
+            compare(expected.chain_id, observed.bundler_chain_id),
+        ),
+        ("entry_points", entry_points),
+        (
+            "operator_count",
+            compare(expected.operator_count, observed.operator_count),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(parameter, check)| Some((parameter, check?)))
+    .collect()
+}
+
+/// Fetches the chain id of an Ethereum JSON-RPC endpoint
+pub(crate) async fn fetch_chain_id(client: &HttpClient, service: &str) -> Option<u64> {
+    let response: Result<String, _> = client.request("eth_chainId", Vec::<()>::new()).await;
+    match response {
+        Ok(chain_id) => chain_id
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok()),
+        Err(e) => {
+            warn!(error = %e, service, "Chain id query failed");
+            None
+        }
+    }
+}
+
+async fn fetch_parameters(
+    l2_rpc: &HttpClient,
+    bundler_rpc: &HttpClient,
+    bridge_rpc: &impl BridgeClient,
+) -> ObservedParameters {
+    let entry_points: Result<Vec<String>, _> = bundler_rpc
+        .request("eth_supportedEntryPoints", Vec::<()>::new())
+        .await;
+    let operators = bridge_rpc.bridge_operators().await;
+    if let Err(e) = &entry_points {
+        warn!(error = %e, "Supported entry points query failed");
+    }
+    if let Err(e) = &operators {
+        warn!(error = %e, "Bridge operators query failed");
+    }
+
+    ObservedParameters {
+        chain_id: fetch_chain_id(l2_rpc, "reth").await,
+        bundler_chain_id: fetch_chain_id(bundler_rpc, "bundler").await,
+        entry_points: entry_points.ok(),
+        operator_count: operators.ok().map(|operators| operators.0.len()),
+    }
+}
+
+/// Periodically compares the live chain, bundler and bridge parameters with the
+/// configured ones, raising a `config_drift` alert per drifted parameter. This
+/// catches deployments pointed at the wrong network.
+pub async fn drift_check_task(
+    watched: WatchedContracts,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    bridge_rpc_url: String,
+    network_config: &NetworkConfig,
+    config: &DriftConfig,
+) {
+    tasks
+        .register(DRIFT_CHECK_TASK, config.check_interval())
+        .await;
+    let mut interval = interval(Duration::from_secs(config.check_interval()));
+    let l2_rpc = create_rpc_client(network_config.reth_url());
+    let bundler_rpc = create_rpc_client(network_config.bundler_rpc_url());
+    let bridge_rpc = create_rpc_client(&bridge_rpc_url);
+
+    loop {
+        interval.tick().await;
+        tasks.start_refresh(DRIFT_CHECK_TASK).await;
+
+        let expected = ExpectedParameters {
+            chain_id: network_config.chain_id(),
+            entry_points: watched.entry_points().await,
+            operator_count: config.expected_operator_count(),
+        };
+        let observed = fetch_parameters(&l2_rpc, &bundler_rpc, &bridge_rpc).await;
+
+        // Parameters that could not be compared keep their last known state
+        for (parameter, check) in check_parameters(&expected, &observed) {
+            let alert_id = format!("config_drift:{}", parameter);
+            match check {
+                Check::Matches => alerts.resolve(&alert_id).await,
+                Check::Drifted(details) => {
+                    info!(parameter, %details, "Configuration drift");
+                    alerts
+                        .raise(
+                            alert_id,
+                            Severity::Critical,
+                            format!("{} drifted from the configuration: {}", parameter, details),
+                        )
+                        .await;
+                }
+            }
+        }
+
+        tasks.record_refresh(DRIFT_CHECK_TASK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_parameters, Check, ExpectedParameters, ObservedParameters};
+
+    #[test]
+    fn test_check_parameters() {
+        let expected = ExpectedParameters {
+            chain_id: Some(2892),
+            entry_points: vec!["0x0000000071727De22E5E9d8BAf0edAc6f37da032".to_string()],
+            operator_count: None,
+        };
+        let observed = ObservedParameters {
+            chain_id: Some(1),
+            bundler_chain_id: Some(2892),
+            entry_points: Some(vec![
+                "0x0000000071727de22e5e9d8baf0edac6f37da032".to_string()
+            ]),
+            operator_count: Some(3),
+        };
+        assert_eq!(
+            check_parameters(&expected, &observed),
+            vec![
+                (
+                    "chain_id",
+                    Check::Drifted("expected 2892, found 1".to_string())
+                ),
+                ("bundler_chain_id", Check::Matches),
+                ("entry_points", Check::Matches),
+            ]
+        );
+
+        // Failed queries are not compared
+        let observed = ObservedParameters {
+            entry_points: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert_eq!(
+            check_parameters(&expected, &observed),
+            vec![(
+                "entry_points",
+                Check::Drifted(
+                    "0x0000000071727De22E5E9d8BAf0edAc6f37da032 not supported, the bundler \
+                     supports []"
+                        .to_string()
+                )
+            )]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Append-only log of the monitoring events, so that downstream systems can build
+//! their own views by replaying it from a cursor through `/api/events/export`
+//! rather than staying connected to the live stream.
+
+use axum::{extract::Query, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{broadcast::Receiver, RwLock};
+use tracing::warn;
+
+use crate::{
+    events::{next_event, MonitorEvent},
+    sqlite::SqliteDb,
+    store::{open_store, MemoryStore, StatsStore, Timestamped},
+};
+
+/// Max number of events kept; older cursors can no longer be replayed
+const MAX_EVENT_RECORDS: usize = 50_000;
+
+/// Events returned per export call by default
+const DEFAULT_EXPORT_LIMIT: usize = 1_000;
+
+/// Max number of events returned per export call
+const MAX_EXPORT_LIMIT: usize = 10_000;
+
+/// Event with its position in the log
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EventRecord {
+    cursor: u64,
+    /// When the event was logged
+    at: DateTime<Utc>,
+    /// The event as streamed by `/api/events`, with its `type`
+    event: Value,
+}
+
+impl Timestamped for EventRecord {
+    fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+}
+
+/// Bounded log of monitoring events, addressed by monotonically increasing cursors.
+///
+/// Network status polls are left out, only their changes are logged. The events
+/// are persisted to the `event_log` table of the SQLite database or to the JSON
+/// lines file at the configured path, and reloaded on startup.
+#[derive(Debug)]
+pub struct EventLog {
+    next_cursor: u64,
+    store: Box<dyn StatsStore<EventRecord>>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            next_cursor: 0,
+            store: Box::new(MemoryStore::new(MAX_EVENT_RECORDS)),
+        }
+    }
+}
+
+impl EventLog {
+    /// Creates the log, loading the most recent events persisted in `sqlite` or
+    /// else at `path`.
+    ///
+    /// Up to `write_buffer` events wait to be persisted before new ones are dropped.
+    pub fn load(sqlite: Option<&SqliteDb>, path: Option<String>, write_buffer: usize) -> Self {
+        let mut store = open_store(
+            sqlite,
+            "event_log",
+            path,
+            DateTime::<Utc>::MIN_UTC,
+            write_buffer,
+            MAX_EVENT_RECORDS,
+        );
+        store.truncate(MAX_EVENT_RECORDS);
+        store.compact();
+        let next_cursor = store.records().last().map_or(0, |record| record.cursor + 1);
+        Self { next_cursor, store }
+    }
+
+    /// Appends an event logged at `at`
+    pub fn record(&mut self, event: &MonitorEvent, at: DateTime<Utc>) {
+        if matches!(event, MonitorEvent::StatusRefreshed { .. }) {
+            return;
+        }
+        let event = match serde_json::to_value(event) {
+            Ok(event) => event,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize event");
+                return;
+            }
+        };
+        let record = EventRecord {
+            cursor: self.next_cursor,
+            at,
+            event,
+        };
+        self.store.save_snapshot(record);
+        self.store.truncate(MAX_EVENT_RECORDS);
+        self.next_cursor += 1;
+    }
+
+    /// Drops the events no longer retained from the persisted ones, which
+    /// otherwise only shrink on restart
+    pub fn compact(&mut self) {
+        self.store.compact();
+    }
+
+    /// Up to `limit` events from cursor `since` on, from the oldest retained one
+    /// if unset
+    fn export(&self, since: Option<u64>, limit: usize) -> EventExportResponse {
+        let oldest = self
+            .store
+            .records()
+            .next()
+            .map_or(self.next_cursor, |record| record.cursor);
+        let since = since.unwrap_or(oldest);
+
+        let events: Vec<EventRecord> = self
+            .store
+            .records()
+            .filter(|record| record.cursor >= since)
+            .take(limit)
+            .cloned()
+            .collect();
+        let cursor = events
+            .last()
+            .map_or(since.max(oldest), |record| record.cursor + 1);
+        EventExportResponse {
+            cursor,
+            more: cursor < self.next_cursor,
+            reset: since < oldest,
+            events,
+        }
+    }
+}
+
+/// Shared event log
+pub type SharedEventLog = Arc<RwLock<EventLog>>;
+
+/// Records every event published on the bus into the event log
+pub async fn event_log_writer(mut events: Receiver<MonitorEvent>, log: SharedEventLog) {
+    while let Some(event) = next_event(&mut events, "event_log").await {
+        log.write().await.record(&event, Utc::now());
+    }
+}
+
+/// Query parameters of the event export endpoint
+#[derive(Deserialize, Debug)]
+pub struct EventExportQuery {
+    /// `cursor` returned by the previous call
+    since: Option<u64>,
+    /// Max number of events returned, up to 10000
+    limit: Option<usize>,
+}
+
+/// Page of the event log
+#[derive(Serialize, Debug)]
+pub struct EventExportResponse {
+    /// Cursor to pass as `since` on the next call
+    cursor: u64,
+    /// More events are logged past this page, fetch the next one right away
+    more: bool,
+    /// Events since the given cursor were dropped from the log, so replaying it
+    /// misses some
+    reset: bool,
+    events: Vec<EventRecord>,
+}
+
+/// Return a page of the event log from a cursor
+pub async fn get_event_export(
+    Query(query): Query<EventExportQuery>,
+    log: SharedEventLog,
+) -> Json<EventExportResponse> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_EXPORT_LIMIT)
+        .clamp(1, MAX_EXPORT_LIMIT);
+    Json(log.read().await.export(query.since, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventLog, MAX_EVENT_RECORDS};
+    use crate::{events::MonitorEvent, network::NetworkStatus};
+    use chrono::Utc;
+
+    fn balance_recovered(wallet: &str) -> MonitorEvent {
+        MonitorEvent::BalanceRecovered {
+            wallet: wallet.to_string(),
+            address: "0xCAFE".to_string(),
+            balance_wei: "1000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_event_log_export() {
+        let mut log = EventLog::default();
+        let now = Utc::now();
+        log.record(
+            &MonitorEvent::StatusRefreshed {
+                at: now,
+                status: NetworkStatus::default(),
+            },
+            now,
+        );
+        for wallet in ["deposit", "validating", "operator-0"] {
+            log.record(&balance_recovered(wallet), now);
+        }
+
+        // Status polls are not logged
+        let page = log.export(None, 2);
+        assert_eq!(page.events.len(), 2);
+        assert_eq!(page.events[0].cursor, 0);
+        assert_eq!(page.events[0].event["type"], "balance_recovered");
+        assert_eq!(page.events[0].event["wallet"], "deposit");
+        assert_eq!(page.cursor, 2);
+        assert!(page.more);
+        assert!(!page.reset);
+
+        let page = log.export(Some(page.cursor), 2);
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.cursor, 3);
+        assert!(!page.more);
+
+        // Caught up
+        let page = log.export(Some(page.cursor), 2);
+        assert!(page.events.is_empty());
+        assert_eq!(page.cursor, 3);
+
+        for _ in 0..MAX_EVENT_RECORDS {
+            log.record(&balance_recovered("deposit"), now);
+        }
+        let page = log.export(Some(0), 1);
+        assert!(page.reset);
+        assert_eq!(page.events[0].cursor, 3);
+    }
+}
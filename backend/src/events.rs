@@ -0,0 +1,224 @@
+use axum::response::sse::{Event, KeepAlive, Sse};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError, Receiver};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tracing::warn;
+
+use crate::{
+    alerts::{Alert, Alerts, Severity},
+    bridge::DepositInfo,
+    bridge_watchlist::WatchedDeposit,
+    network::NetworkStatus,
+    status_history::{SharedStatusHistory, StatusSample},
+};
+
+/// Events buffered per subscriber; slower subscribers miss the oldest events
+const EVENT_BUS_CAPACITY: usize = 1_024;
+
+/// Event published by a monitoring task
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MonitorEvent {
+    /// Network status polled, whether or not it changed
+    StatusRefreshed {
+        at: DateTime<Utc>,
+        status: NetworkStatus,
+    },
+    /// Network status differs from the previous poll
+    StatusChanged {
+        at: DateTime<Utc>,
+        previous: NetworkStatus,
+        status: NetworkStatus,
+    },
+    /// Deposit seen for the first time or whose status changed
+    DepositUpdated {
+        at: DateTime<Utc>,
+        deposit: DepositInfo,
+    },
+    /// Deposit of `BRIDGE_WATCHLIST` seen for the first time or whose status changed
+    WatchedDepositUpdated {
+        at: DateTime<Utc>,
+        watched: WatchedDeposit,
+    },
+    /// Paymaster wallet balance dropped below `PAYMASTER_LOW_BALANCE_WEI`
+    BalanceLow {
+        wallet: String,
+        address: String,
+        balance_wei: String,
+        threshold_wei: String,
+    },
+    /// Paymaster wallet balance dropped below `PAYMASTER_CRITICAL_BALANCE_WEI`
+    BalanceCritical {
+        wallet: String,
+        address: String,
+        balance_wei: String,
+        threshold_wei: String,
+    },
+    /// Paymaster wallet balance is back above `PAYMASTER_LOW_BALANCE_WEI`
+    BalanceRecovered {
+        wallet: String,
+        address: String,
+        balance_wei: String,
+    },
+    /// Alert raised for the first time since it was last resolved
+    AlertRaised { at: DateTime<Utc>, alert: Alert },
+    /// Active alert cleared
+    AlertResolved { at: DateTime<Utc>, alert: Alert },
+}
+
+impl MonitorEvent {
+    /// Name of the event type, as in its `type` field
+    fn kind(&self) -> &'static str {
+        match self {
+            MonitorEvent::StatusRefreshed { .. } => "status_refreshed",
+            MonitorEvent::StatusChanged { .. } => "status_changed",
+            MonitorEvent::DepositUpdated { .. } => "deposit_updated",
+            MonitorEvent::WatchedDepositUpdated { .. } => "watched_deposit_updated",
+            MonitorEvent::BalanceLow { .. } => "balance_low",
+            MonitorEvent::BalanceCritical { .. } => "balance_critical",
+            MonitorEvent::BalanceRecovered { .. } => "balance_recovered",
+            MonitorEvent::AlertRaised { .. } => "alert_raised",
+            MonitorEvent::AlertResolved { .. } => "alert_resolved",
+        }
+    }
+}
+
+/// Broadcast channel from monitoring tasks to the consumers of their events
+#[derive(Clone, Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<MonitorEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    /// Publishes an event to the current subscribers, if any
+    pub fn publish(&self, event: MonitorEvent) {
+        // No subscribers is not an error, e.g. no SSE client is connected
+        let _ = self.sender.send(event);
+    }
+
+    /// Receives the events published from now on
+    pub fn subscribe(&self) -> Receiver<MonitorEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Next event, skipping over the ones missed while `consumer` lagged behind.
+///
+/// `None` once every publisher is gone.
+pub async fn next_event(
+    receiver: &mut Receiver<MonitorEvent>,
+    consumer: &str,
+) -> Option<MonitorEvent> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(missed)) => warn!(%consumer, %missed, "Event consumer lagged"),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Records every network status poll into the status history
+pub async fn status_history_writer(
+    mut events: Receiver<MonitorEvent>,
+    history: SharedStatusHistory,
+) {
+    while let Some(event) = next_event(&mut events, "status_history").await {
+        if let MonitorEvent::StatusRefreshed { at, status } = event {
+            history.write().await.record(StatusSample::new(at, &status));
+        }
+    }
+}
+
+/// Raises and resolves the alerts derived from events
+pub async fn event_alerts(mut events: Receiver<MonitorEvent>, alerts: Alerts) {
+    while let Some(event) = next_event(&mut events, "alerts").await {
+        match event {
+            MonitorEvent::BalanceLow {
+                wallet,
+                address,
+                balance_wei,
+                threshold_wei,
+            } => {
+                alerts
+                    .raise(
+                        format!("paymaster_balance_low:{}", wallet),
+                        Severity::Warning,
+                        format!(
+                            "{} paymaster {} has {} Wei left (threshold {})",
+                            wallet, address, balance_wei, threshold_wei
+                        ),
+                    )
+                    .await;
+            }
+            MonitorEvent::BalanceRecovered { wallet, .. } => {
+                alerts
+                    .resolve(&format!("paymaster_balance_low:{}", wallet))
+                    .await;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Stream monitoring events to the dashboard as server-sent events
+pub async fn get_events(events: EventBus) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Events missed by a lagging client are dropped, it can refetch the full state
+    let stream = BroadcastStream::new(events.subscribe()).filter_map(|event| {
+        let event = event.ok()?;
+        Event::default()
+            .event(event.kind())
+            .json_data(&event)
+            .ok()
+            .map(Ok)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{event_alerts, EventBus, MonitorEvent};
+    use crate::alerts::Alerts;
+
+    #[tokio::test]
+    async fn test_balance_events_raise_and_resolve_alerts() {
+        let events = EventBus::default();
+        let alerts = Alerts::default();
+        let consumer = tokio::spawn(event_alerts(events.subscribe(), alerts.clone()));
+
+        events.publish(MonitorEvent::BalanceLow {
+            wallet: "deposit".to_string(),
+            address: "0xCAFE".to_string(),
+            balance_wei: "10".to_string(),
+            threshold_wei: "100".to_string(),
+        });
+        let balance_recovered = MonitorEvent::BalanceRecovered {
+            wallet: "deposit".to_string(),
+            address: "0xCAFE".to_string(),
+            balance_wei: "1000".to_string(),
+        };
+        assert_eq!(balance_recovered.kind(), "balance_recovered");
+        // Dropping the bus closes the channel once the consumer has caught up
+        drop(events);
+        consumer.await.unwrap();
+        let active = alerts.active().await;
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, "paymaster_balance_low:deposit");
+
+        let events = EventBus::default();
+        let consumer = tokio::spawn(event_alerts(events.subscribe(), alerts.clone()));
+        events.publish(balance_recovered);
+        drop(events);
+        consumer.await.unwrap();
+        assert!(alerts.active().await.is_empty());
+    }
+}
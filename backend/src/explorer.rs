@@ -0,0 +1,554 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+        RETRY_AFTER, USER_AGENT,
+    },
+    StatusCode,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, error, warn};
+
+use crate::{
+    archive, budget, degradation,
+    rate_limit::HostRateLimiters,
+    retry_policy::{classify_http_error, classify_status, ErrorClass, ExponentialBackoff},
+};
+
+/// Upper bound on how long a single `Retry-After` may delay a request
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Max number of responses kept for conditional requests
+const MAX_CACHED_RESPONSES: usize = 1024;
+
+/// Response kept to answer `304 Not Modified` replies
+#[derive(Debug)]
+struct CachedResponse {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: serde_json::Value,
+    last_used: Instant,
+}
+
+/// Cached responses keyed by full request URL, including query parameters
+type ResponseCache = Arc<Mutex<HashMap<String, CachedResponse>>>;
+
+/// Body of an explorer response
+#[derive(Debug)]
+struct Fetched {
+    body: serde_json::Value,
+    /// False when answered `304 Not Modified`, i.e. the body is the cached one
+    modified: bool,
+}
+
+/// Source of block explorer API responses
+#[async_trait]
+pub trait ExplorerClient: Send + Sync {
+    /// Sends a GET request with query parameters and parses the JSON response
+    async fn get_json(
+        &self,
+        url: &str,
+        query_params: &HashMap<&str, String>,
+    ) -> Result<serde_json::Value, anyhow::Error>;
+
+    /// Like [`get_json`](Self::get_json), but returns `None` when the explorer
+    /// answers that the response did not change since the previous one to the same
+    /// request, so that callers can skip processing it again
+    async fn get_json_if_modified(
+        &self,
+        url: &str,
+        query_params: &HashMap<&str, String>,
+    ) -> Result<Option<serde_json::Value>, anyhow::Error> {
+        self.get_json(url, query_params).await.map(Some)
+    }
+}
+
+/// HTTP client for the block explorer API.
+///
+/// Cheap to clone; clones share the underlying connection pool and rate limiters.
+#[derive(Clone, Debug)]
+pub struct HttpExplorerClient {
+    http: reqwest::Client,
+    rate_limiters: HostRateLimiters,
+    /// Backoff used on throttled responses without a usable `Retry-After`
+    retry_policy: ExponentialBackoff,
+    /// Validators and bodies of previous responses, for conditional requests
+    cache: ResponseCache,
+}
+
+impl HttpExplorerClient {
+    /// Creates a client sending `extra_headers` (e.g. API keys) with every request
+    pub fn new(
+        rate_limiters: HostRateLimiters,
+        retry_policy: ExponentialBackoff,
+        extra_headers: HeaderMap,
+    ) -> Self {
+        let http = reqwest::Client::builder()
+            .default_headers(extra_headers)
+            .build()
+            .expect("Failed to create explorer HTTP client");
+
+        Self {
+            http,
+            rate_limiters,
+            retry_policy,
+            cache: ResponseCache::default(),
+        }
+    }
+
+    /// Adds `If-None-Match`/`If-Modified-Since` from a cached response to the same URL
+    fn add_conditional_headers(&self, cache_key: &str, headers: &mut HeaderMap) {
+        let cache = self.cache.lock().expect("explorer cache lock poisoned");
+        if let Some(cached) = cache.get(cache_key) {
+            if let Some(etag) = &cached.etag {
+                headers.insert(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+            }
+        }
+    }
+
+    /// Returns the cached body for a URL, marking it as recently used
+    fn cached_body(&self, cache_key: &str) -> Option<serde_json::Value> {
+        let mut cache = self.cache.lock().expect("explorer cache lock poisoned");
+        cache.get_mut(cache_key).map(|cached| {
+            cached.last_used = Instant::now();
+            cached.body.clone()
+        })
+    }
+
+    /// Stores a response, evicting the least recently used one when the cache is full
+    fn store(&self, cache_key: String, response: CachedResponse) {
+        let mut cache = self.cache.lock().expect("explorer cache lock poisoned");
+        if cache.len() >= MAX_CACHED_RESPONSES && !cache.contains_key(&cache_key) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(cache_key, response);
+    }
+
+    /// Sends a GET request with query parameters and parses the JSON response.
+    ///
+    /// Retryable failures (timeouts, connection errors, 5xx, 429 and 408) are
+    /// retried after the delay requested by the `Retry-After` header, falling back
+    /// to the exponential backoff policy. Other failures are returned right away.
+    ///
+    /// When a previous response to the same URL carried `ETag`/`Last-Modified`,
+    /// the request is sent conditionally and a `304 Not Modified` reply is
+    /// answered from the cached body without downloading or parsing it again. If
+    /// that body was evicted in the meantime, the request is sent again without
+    /// the conditional headers.
+    async fn fetch_json(
+        &self,
+        url: &str,
+        query_params: &HashMap<&str, String>,
+    ) -> Result<Fetched, anyhow::Error> {
+        let mut retry_count: u64 = 0;
+        let mut conditional = true;
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject("explorer").await?;
+
+        loop {
+            budget::admit("explorer")?;
+            self.rate_limiters.acquire(url).await;
+
+            let mut request = self
+                .http
+                .get(url)
+                .query(query_params) // Use query parameters instead of JSON body
+                .build()?;
+            let cache_key = request.url().to_string();
+            if conditional {
+                self.add_conditional_headers(&cache_key, request.headers_mut());
+            }
+
+            let response = match self.http.execute(request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    let class = classify_http_error(&e);
+                    if class == ErrorClass::Retryable
+                        && retry_count < self.retry_policy.max_retries()
+                    {
+                        retry_count += 1;
+                        let delay = Duration::from_secs(self.retry_policy.get_delay(retry_count));
+                        warn!(%url, error = %e, ?delay, retry_count, "Explorer request failed, retrying after");
+                        sleep(delay).await;
+                        continue;
+                    }
+                    error!(%url, error = %e, ?class, retry_count, "Explorer request failed");
+                    return Err(e.into());
+                }
+            };
+
+            let status = response.status();
+            if status == StatusCode::NOT_MODIFIED {
+                if let Some(body) = self.cached_body(&cache_key) {
+                    debug!(%url, "Explorer response not modified, using cached body");
+                    archive_response(&cache_key, &body);
+                    return Ok(Fetched {
+                        body,
+                        modified: false,
+                    });
+                }
+                if !conditional {
+                    error!(%url, "Explorer answered an unconditional request as not modified");
+                    anyhow::bail!("unexpected 304 Not Modified from {}", url);
+                }
+                debug!(%url, "Cached explorer response evicted, requesting it again");
+                conditional = false;
+                continue;
+            }
+
+            let retryable = (status.is_client_error() || status.is_server_error())
+                && classify_status(status) == ErrorClass::Retryable;
+            if retryable && retry_count < self.retry_policy.max_retries() {
+                retry_count += 1;
+                let delay = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| parse_retry_after(value, Utc::now()))
+                    .unwrap_or_else(|| {
+                        Duration::from_secs(self.retry_policy.get_delay(retry_count))
+                    })
+                    .min(MAX_RETRY_AFTER);
+
+                warn!(%url, %status, ?delay, retry_count, "Explorer request failed, retrying after");
+                sleep(delay).await;
+                continue;
+            }
+
+            // Converts HTTP errors into Rust errors
+            let response = response.error_for_status().inspect_err(|e| {
+                error!(%url, error = %e, class = ?classify_status(status), retry_count, "Explorer request failed");
+            })?;
+            let etag = response.headers().get(ETAG).cloned();
+            let last_modified = response.headers().get(LAST_MODIFIED).cloned();
+            let json = response.json::<serde_json::Value>().await?;
+            archive_response(&cache_key, &json);
+
+            if etag.is_some() || last_modified.is_some() {
+                self.store(
+                    cache_key,
+                    CachedResponse {
+                        etag,
+                        last_modified,
+                        body: json.clone(),
+                        last_used: Instant::now(),
+                    },
+                );
+            }
+
+            return Ok(Fetched {
+                body: json,
+                modified: true,
+            });
+        }
+    }
+
+    /// See [`HttpExplorerClient::fetch_json`]. Failures degrade the task sending it.
+    async fn get_fetched(
+        &self,
+        url: &str,
+        query_params: &HashMap<&str, String>,
+    ) -> Result<Fetched, anyhow::Error> {
+        self.fetch_json(url, query_params).await.inspect_err(|e| {
+            degradation::record_failure("explorer", degradation::failure_kind(e));
+        })
+    }
+}
+
+#[async_trait]
+impl ExplorerClient for HttpExplorerClient {
+    /// Sends a GET request with query parameters and parses the JSON response, see
+    /// [`HttpExplorerClient::fetch_json`]. Failures degrade the task sending it.
+    async fn get_json(
+        &self,
+        url: &str,
+        query_params: &HashMap<&str, String>,
+    ) -> Result<serde_json::Value, anyhow::Error> {
+        self.get_fetched(url, query_params)
+            .await
+            .map(|fetched| fetched.body)
+    }
+
+    async fn get_json_if_modified(
+        &self,
+        url: &str,
+        query_params: &HashMap<&str, String>,
+    ) -> Result<Option<serde_json::Value>, anyhow::Error> {
+        self.get_fetched(url, query_params)
+            .await
+            .map(|fetched| fetched.modified.then_some(fetched.body))
+    }
+}
+
+/// Archives an explorer response if sampled, including those answered from the cache
+fn archive_response(cache_key: &str, body: &serde_json::Value) {
+    if archive::sample() {
+        archive::record("explorer", json!({ "url": cache_key }), body);
+    }
+}
+
+/// Parses extra explorer request headers given as a JSON object of names to values.
+///
+/// All values except the user agent are marked sensitive, which keeps API keys
+/// out of `Debug` output and therefore out of logs.
+pub fn parse_extra_headers(raw: &str) -> Result<HeaderMap, anyhow::Error> {
+    let entries: HashMap<String, String> = serde_json::from_str(raw)?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in entries {
+        let name = HeaderName::from_bytes(name.as_bytes())?;
+        let mut value = HeaderValue::from_str(&value)?;
+        value.set_sensitive(name != USER_AGENT);
+        headers.insert(name, value);
+    }
+
+    Ok(headers)
+}
+
+/// Parses a `Retry-After` header value, given either as delay seconds or as an HTTP date
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let retry_at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    // A date in the past means the request can be retried right away
+    Some((retry_at - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// In-memory explorer serving canned responses, for tests
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct FakeExplorerClient {
+    /// Responses per URL, served in order
+    responses: Mutex<HashMap<String, std::collections::VecDeque<serde_json::Value>>>,
+}
+
+#[cfg(test)]
+impl FakeExplorerClient {
+    /// Queues a response to the next request to `url`
+    pub fn push_response(&self, url: &str, response: serde_json::Value) {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(url.to_string())
+            .or_default()
+            .push_back(response);
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl ExplorerClient for FakeExplorerClient {
+    async fn get_json(
+        &self,
+        url: &str,
+        _query_params: &HashMap<&str, String>,
+    ) -> Result<serde_json::Value, anyhow::Error> {
+        self.responses
+            .lock()
+            .unwrap()
+            .get_mut(url)
+            .and_then(|responses| responses.pop_front())
+            .ok_or_else(|| anyhow::anyhow!("No response queued for {}", url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_extra_headers, parse_retry_after, ExplorerClient, HttpExplorerClient};
+    use crate::{rate_limit::HostRateLimiters, retry_policy::ExponentialBackoff};
+    use chrono::{TimeZone, Utc};
+    use mockito::{Matcher, Server};
+    use reqwest::header::HeaderMap;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tokio::time::Duration;
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        let now = Utc::now();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 3 ", now), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 30).unwrap();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+
+        // Dates in the past retry immediately
+        let later = Utc.with_ymd_and_hms(2015, 10, 21, 8, 0, 0).unwrap();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", later),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after_invalid() {
+        assert_eq!(parse_retry_after("soon", Utc::now()), None);
+        assert_eq!(parse_retry_after("-5", Utc::now()), None);
+    }
+
+    #[test]
+    fn test_parse_extra_headers_redacts_secrets() {
+        let headers =
+            parse_extra_headers(r#"{"X-Api-Key": "secret", "User-Agent": "strata-dashboards"}"#)
+                .unwrap();
+
+        assert_eq!(headers["x-api-key"], "secret");
+        assert!(headers["x-api-key"].is_sensitive());
+        assert!(!format!("{:?}", headers).contains("secret"));
+        assert!(!headers["user-agent"].is_sensitive());
+
+        assert!(parse_extra_headers(r#"{"bad header": "x"}"#).is_err());
+        assert!(parse_extra_headers("not json").is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_extra_headers_are_sent() {
+        let mut server = Server::new_async().await;
+        let mock_endpoint = server
+            .mock("GET", "/items")
+            .match_header("x-api-key", "secret")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{}")
+            .create();
+
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(0, 0, 1.5),
+            parse_extra_headers(r#"{"X-Api-Key": "secret"}"#).unwrap(),
+        );
+        client
+            .get_json(&format!("{}/items", server.url()), &HashMap::new())
+            .await
+            .unwrap();
+
+        mock_endpoint.assert();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_not_modified_uses_cached_body() {
+        let mut server = Server::new_async().await;
+
+        let fresh = server
+            .mock("GET", "/items")
+            .match_header("if-none-match", Matcher::Missing)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", "\"v1\"")
+            .with_body(json!({ "items": [1, 2, 3] }).to_string())
+            .expect(1)
+            .create();
+        let not_modified = server
+            .mock("GET", "/items")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create();
+
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(0, 0, 1.5),
+            HeaderMap::new(),
+        );
+        let url = format!("{}/items", server.url());
+
+        let first = client.get_json(&url, &HashMap::new()).await.unwrap();
+        let second = client.get_json(&url, &HashMap::new()).await.unwrap();
+        assert_eq!(first, second);
+        // Callers can tell the response did not change
+        assert_eq!(
+            client
+                .get_json_if_modified(&url, &HashMap::new())
+                .await
+                .unwrap(),
+            None
+        );
+
+        fresh.assert();
+        not_modified.assert();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_not_modified_without_cached_body() {
+        let mut server = Server::new_async().await;
+        // Answers 304 to any request, e.g. after the cached body was evicted
+        let not_modified = server
+            .mock("GET", "/items")
+            .with_status(304)
+            .expect(2)
+            .create();
+
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(0, 0, 1.5),
+            HeaderMap::new(),
+        );
+        let url = format!("{}/items", server.url());
+        // Sent again unconditionally, then failed rather than parsing an empty body
+        assert!(client.get_json(&url, &HashMap::new()).await.is_err());
+
+        not_modified.assert();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_only_retryable_failures_are_retried() {
+        let mut server = Server::new_async().await;
+        let failing = server
+            .mock("GET", "/failing")
+            .with_status(500)
+            .expect(3)
+            .create();
+        let missing = server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .expect(1)
+            .create();
+
+        let client = HttpExplorerClient::new(
+            HostRateLimiters::default(),
+            ExponentialBackoff::new(2, 0, 1.5),
+            HeaderMap::new(),
+        );
+        let url = |path: &str| format!("{}{}", server.url(), path);
+        assert!(client
+            .get_json(&url("/failing"), &HashMap::new())
+            .await
+            .is_err());
+        assert!(client
+            .get_json(&url("/missing"), &HashMap::new())
+            .await
+            .is_err());
+
+        failing.assert();
+        missing.assert();
+    }
+}
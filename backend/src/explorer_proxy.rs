@@ -0,0 +1,270 @@
+//! Read-through proxy of the explorer user ops and accounts endpoints, so the
+//! frontend never needs direct access to the explorer or its API keys, which the
+//! explorer client adds from `EXPLORER_HEADERS`.
+//!
+//! Only the paging and time range parameters are forwarded, and requests missing
+//! the cache are rate limited apart from the monitoring tasks, so that callers
+//! cannot spend their explorer quota.
+
+use axum::{extract::Query, http::StatusCode, Json};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+use tokio::time::{Duration, Instant};
+use tracing::{error, warn};
+
+use crate::{config::ActivityMonitoringConfig, explorer::ExplorerClient, rate_limit::TokenBucket};
+
+/// Max number of proxied responses kept
+const MAX_PROXY_CACHE_ENTRIES: usize = 256;
+
+/// Max length of a forwarded page token
+const MAX_PAGE_TOKEN_LEN: usize = 256;
+
+/// Time format of the explorer time range parameters
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Explorer endpoint exposed through the proxy
+#[derive(Clone, Copy, Debug)]
+pub enum ProxiedEndpoint {
+    UserOps,
+    Accounts,
+}
+
+impl ProxiedEndpoint {
+    fn url(self, config: &ActivityMonitoringConfig) -> &str {
+        match self {
+            ProxiedEndpoint::UserOps => config.user_ops_query_url(),
+            ProxiedEndpoint::Accounts => config.accounts_query_url(),
+        }
+    }
+}
+
+/// Query parameters accepted by the proxy, any other one is refused
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyQuery {
+    page_token: Option<String>,
+    page_size: Option<u64>,
+    /// Start of the time range, as `YYYY-MM-DD HH:MM:SS` in UTC
+    start_time: Option<String>,
+    /// End of the time range, as `YYYY-MM-DD HH:MM:SS` in UTC
+    end_time: Option<String>,
+}
+
+impl ProxyQuery {
+    /// Validated query parameters, in a canonical form so that equivalent requests
+    /// share a cache entry
+    fn params(&self, max_page_size: u64) -> Result<BTreeMap<&'static str, String>, StatusCode> {
+        let mut params = BTreeMap::new();
+        if let Some(token) = &self.page_token {
+            if token.is_empty() || token.len() > MAX_PAGE_TOKEN_LEN {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            params.insert("page_token", token.clone());
+        }
+        if let Some(size) = self.page_size {
+            if size == 0 || size > max_page_size {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            params.insert("page_size", size.to_string());
+        }
+        let parse_time = |time: &str| {
+            NaiveDateTime::parse_from_str(time.trim(), TIME_FORMAT)
+                .map_err(|_| StatusCode::BAD_REQUEST)
+        };
+        let start_time = self.start_time.as_deref().map(parse_time).transpose()?;
+        let end_time = self.end_time.as_deref().map(parse_time).transpose()?;
+        if let (Some(start), Some(end)) = (start_time, end_time) {
+            if start > end {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+        if let Some(start) = start_time {
+            params.insert("start_time", start.format(TIME_FORMAT).to_string());
+        }
+        if let Some(end) = end_time {
+            params.insert("end_time", end.format(TIME_FORMAT).to_string());
+        }
+        Ok(params)
+    }
+}
+
+/// Explorer responses by URL and query parameters, fresh for `ttl`
+#[derive(Clone, Debug)]
+pub struct ProxyCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, (Instant, Value)>>>,
+    /// Budget of the requests missing the cache
+    limiter: Arc<TokenBucket>,
+}
+
+impl ProxyCache {
+    pub fn new(ttl: Duration, max_rps: f64, burst: u32) -> Self {
+        Self {
+            ttl,
+            entries: Arc::default(),
+            limiter: Arc::new(TokenBucket::new(max_rps, burst)),
+        }
+    }
+
+    /// Returns the cached response to the request, fetching it if missing or stale.
+    ///
+    /// Fails with `429 Too Many Requests` when the proxy budget is spent, and with
+    /// `502 Bad Gateway` when the explorer request fails.
+    async fn fetch(
+        &self,
+        explorer: &impl ExplorerClient,
+        url: &str,
+        params: &BTreeMap<&'static str, String>,
+    ) -> Result<Value, StatusCode> {
+        let key = format!("{}?{:?}", url, params);
+        {
+            let entries = self.entries.lock().expect("proxy cache lock poisoned");
+            if let Some((fetched_at, body)) = entries.get(&key) {
+                if fetched_at.elapsed() < self.ttl {
+                    return Ok(body.clone());
+                }
+            }
+        }
+
+        if !self.limiter.try_acquire().await {
+            warn!(url, "Explorer proxy budget spent");
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+        let query_params: HashMap<&str, String> = params
+            .iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .collect();
+        let body = explorer.get_json(url, &query_params).await.map_err(|e| {
+            error!(error = %e, url, "Explorer proxy request failed");
+            StatusCode::BAD_GATEWAY
+        })?;
+
+        let mut entries = self.entries.lock().expect("proxy cache lock poisoned");
+        let ttl = self.ttl;
+        entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < ttl);
+        if entries.len() >= MAX_PROXY_CACHE_ENTRIES {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (fetched_at, _))| *fetched_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (Instant::now(), body.clone()));
+        Ok(body)
+    }
+}
+
+/// Handler forwarding a request to the explorer, with its paging and time range
+/// parameters. Page sizes are capped by `ACTIVITY_QUERY_PAGE_SIZE`.
+///
+/// Refused when account addresses are redacted or only published as aggregates, as
+/// the explorer responses list them in full.
+pub async fn get_explorer_proxy<E: ExplorerClient>(
+    endpoint: ProxiedEndpoint,
+    Query(query): Query<ProxyQuery>,
+    explorer: E,
+    cache: ProxyCache,
+    config: Arc<ActivityMonitoringConfig>,
+) -> Result<Json<Value>, StatusCode> {
+    if config.redact_addresses() || config.accounts_k_anonymity().is_some() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let params = query.params(config.query_page_size())?;
+    let url = endpoint.url(&config);
+    cache.fetch(&explorer, url, &params).await.map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProxyCache, ProxyQuery};
+    use crate::explorer::FakeExplorerClient;
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use tokio::time::Duration;
+
+    #[test]
+    fn test_proxy_query() {
+        let time = |time: &str| Some(time.to_string());
+        let query = ProxyQuery {
+            page_size: Some(50),
+            start_time: time(" 2025-01-01 00:00:00"),
+            ..Default::default()
+        };
+        let params = query.params(100).unwrap();
+        assert_eq!(params["page_size"], "50");
+        assert_eq!(params["start_time"], "2025-01-01 00:00:00");
+
+        // Only the paging and time range parameters are forwarded
+        assert!(serde_json::from_value::<ProxyQuery>(json!({ "address": "0xabc" })).is_err());
+
+        for query in [
+            ProxyQuery {
+                page_size: Some(500),
+                ..Default::default()
+            },
+            ProxyQuery {
+                page_size: Some(0),
+                ..Default::default()
+            },
+            ProxyQuery {
+                page_token: Some("a".repeat(300)),
+                ..Default::default()
+            },
+            ProxyQuery {
+                start_time: time("yesterday"),
+                ..Default::default()
+            },
+            ProxyQuery {
+                start_time: time("2025-01-02 00:00:00"),
+                end_time: time("2025-01-01 00:00:00"),
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(query.params(100), Err(StatusCode::BAD_REQUEST));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_cache() {
+        let url = "http://explorer/api/v2/operations";
+        let explorer = FakeExplorerClient::default();
+        explorer.push_response(url, json!({ "items": [1] }));
+        explorer.push_response(url, json!({ "items": [2] }));
+        let cache = ProxyCache::new(Duration::from_secs(60), 100.0, 10);
+        let page = |token: &str| BTreeMap::from([("page_token", token.to_string())]);
+
+        let body = cache.fetch(&explorer, url, &page("1")).await.unwrap();
+        assert_eq!(body, json!({ "items": [1] }));
+        // Served from the cache, without querying the explorer
+        let body = cache.fetch(&explorer, url, &page("1")).await.unwrap();
+        assert_eq!(body, json!({ "items": [1] }));
+        // Other parameters are a different request
+        let body = cache.fetch(&explorer, url, &page("2")).await.unwrap();
+        assert_eq!(body, json!({ "items": [2] }));
+
+        let stale = ProxyCache::new(Duration::ZERO, 100.0, 10);
+        assert_eq!(
+            stale.fetch(&explorer, url, &page("1")).await,
+            Err(StatusCode::BAD_GATEWAY)
+        );
+
+        // Requests missing the cache are refused once the budget is spent
+        explorer.push_response(url, json!({ "items": [3] }));
+        let limited = ProxyCache::new(Duration::from_secs(60), 0.001, 1);
+        assert!(limited.fetch(&explorer, url, &page("3")).await.is_ok());
+        assert!(limited.fetch(&explorer, url, &page("3")).await.is_ok());
+        assert_eq!(
+            limited.fetch(&explorer, url, &page("4")).await,
+            Err(StatusCode::TOO_MANY_REQUESTS)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    auth::AdminAuth,
+    network::{NetworkStatus, Status},
+    tasks::{TaskHealth, TaskRegistry},
+    SharedStates,
+};
+
+/// Memory used by the backend process
+#[derive(Serialize, Debug)]
+struct MemoryUsage {
+    /// Resident set size in bytes, if the platform exposes it
+    resident_bytes: Option<u64>,
+}
+
+/// Detailed health report
+#[derive(Serialize, Debug)]
+pub struct HealthDetails {
+    /// `true` when all dependencies are online and all tasks are fresh
+    healthy: bool,
+    checked_at: DateTime<Utc>,
+    /// Reachability of monitored dependencies
+    dependencies: NetworkStatus,
+    tasks: BTreeMap<&'static str, TaskHealth>,
+    memory: MemoryUsage,
+}
+
+/// Liveness probe
+pub async fn get_health() -> &'static str {
+    "ok"
+}
+
+/// Handler for `/healthz/details`.
+///
+/// Responds with `503 Service Unavailable` when unhealthy, so it can back a deep
+/// container health check, e.g. `curl -f -H "Authorization: Bearer $TOKEN" .../healthz/details`.
+pub async fn get_health_details(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    states: SharedStates,
+    tasks: TaskRegistry,
+) -> Result<(StatusCode, Json<HealthDetails>), StatusCode> {
+    auth.check(&headers)?;
+
+    let now = Utc::now();
+    let dependencies = states.network.read().await.clone();
+    let tasks: BTreeMap<&'static str, TaskHealth> = tasks
+        .snapshot()
+        .await
+        .into_iter()
+        .map(|(name, status)| (name, TaskHealth::new(status, now)))
+        .collect();
+
+    let dependencies_online = [
+        &dependencies.batch_producer,
+        &dependencies.rpc_endpoint,
+        &dependencies.bundler_endpoint,
+    ]
+    .iter()
+    .all(|status| matches!(status, Status::Online));
+    let healthy = dependencies_online && tasks.values().all(|task| !task.is_stale());
+
+    let details = HealthDetails {
+        healthy,
+        checked_at: now,
+        dependencies,
+        tasks,
+        memory: MemoryUsage {
+            resident_bytes: resident_memory_bytes(),
+        },
+    };
+
+    let code = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((code, Json(details)))
+}
+
+/// Reads the resident set size of this process from `/proc` (Linux only)
+pub fn resident_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_rss(&status)
+}
+
+/// Extracts `VmRSS` from the contents of `/proc/self/status`
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_vm_rss;
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tbackend\nVmPeak:\t  20000 kB\nVmRSS:\t   12345 kB\nThreads:\t8\n";
+        assert_eq!(parse_vm_rss(status), Some(12345 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tbackend\n"), None);
+    }
+}
@@ -0,0 +1,240 @@
+use axum::{http::StatusCode, Json};
+use bitcoin::{
+    hashes::{sha256, Hash},
+    secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// How far a heartbeat timestamp may be from the backend clock
+const MAX_HEARTBEAT_SKEW_S: i64 = 300;
+
+/// Heartbeat posted by a bridge operator process
+#[derive(Deserialize, Debug)]
+pub struct HeartbeatRequest {
+    operator_index: u32,
+    /// Unix time at which the heartbeat was signed
+    timestamp: i64,
+    /// DER hex ECDSA signature of [`heartbeat_message`] by the operator key
+    signature: String,
+}
+
+/// Message an operator signs for a heartbeat: the SHA-256 of
+/// `strata-bridge-heartbeat:<operator index>:<unix timestamp>`
+pub fn heartbeat_message(operator_index: u32, timestamp: i64) -> Message {
+    let preimage = format!("strata-bridge-heartbeat:{}:{}", operator_index, timestamp);
+    Message::from_digest(sha256::Hash::hash(preimage.as_bytes()).to_byte_array())
+}
+
+/// Whether an operator is up, combining its heartbeats with the bridge RPC status
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorLiveness {
+    /// The bridge RPC answers for the operator
+    Up,
+    /// The operator process sends heartbeats, but the bridge RPC does not answer for it
+    RpcUnreachable,
+    /// Neither the bridge RPC nor recent heartbeats
+    #[default]
+    Down,
+    /// The operator sends heartbeats and the bridge RPC answered for it when last
+    /// polled, but its status was reused rather than polled again since
+    Stale,
+}
+
+impl OperatorLiveness {
+    pub fn new(
+        rpc_online: bool,
+        last_heartbeat: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+        max_heartbeat_age: Duration,
+    ) -> Self {
+        if rpc_online {
+            OperatorLiveness::Up
+        } else if last_heartbeat.is_some_and(|at| now - at <= max_heartbeat_age) {
+            OperatorLiveness::RpcUnreachable
+        } else {
+            OperatorLiveness::Down
+        }
+    }
+}
+
+/// Last verified heartbeat of each bridge operator
+#[derive(Clone, Debug, Default)]
+pub struct Heartbeats {
+    /// Operator keys heartbeats are verified against, as reported by the bridge RPC
+    operator_keys: Arc<RwLock<BTreeMap<u32, PublicKey>>>,
+    last_seen: Arc<RwLock<BTreeMap<u32, DateTime<Utc>>>>,
+}
+
+impl Heartbeats {
+    pub async fn set_operator_keys(&self, keys: BTreeMap<u32, PublicKey>) {
+        *self.operator_keys.write().await = keys;
+    }
+
+    /// Time of the last heartbeat of an operator
+    pub async fn last(&self, operator_index: u32) -> Option<DateTime<Utc>> {
+        self.last_seen.read().await.get(&operator_index).copied()
+    }
+
+    /// Records a heartbeat without verifying it
+    #[cfg(test)]
+    pub async fn record(&self, operator_index: u32, at: DateTime<Utc>) {
+        self.last_seen.write().await.insert(operator_index, at);
+    }
+
+    /// Verifies and records a heartbeat.
+    ///
+    /// Heartbeats of unknown operators are rejected with `404 Not Found`, bad
+    /// signatures with `401 Unauthorized`, and timestamps too far from `now` or not
+    /// newer than the last heartbeat with `400 Bad Request`, so that captured
+    /// heartbeats cannot be replayed.
+    async fn receive(
+        &self,
+        request: &HeartbeatRequest,
+        now: DateTime<Utc>,
+    ) -> Result<(), StatusCode> {
+        let key = self
+            .operator_keys
+            .read()
+            .await
+            .get(&request.operator_index)
+            .copied()
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let signature =
+            Signature::from_str(&request.signature).map_err(|_| StatusCode::BAD_REQUEST)?;
+        let message = heartbeat_message(request.operator_index, request.timestamp);
+        Secp256k1::verification_only()
+            .verify_ecdsa(&message, &signature, &key)
+            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+        let at = DateTime::from_timestamp(request.timestamp, 0).ok_or(StatusCode::BAD_REQUEST)?;
+        if (now - at).num_seconds().abs() > MAX_HEARTBEAT_SKEW_S {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let mut last_seen = self.last_seen.write().await;
+        if last_seen
+            .get(&request.operator_index)
+            .is_some_and(|last| *last >= at)
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        if last_seen.insert(request.operator_index, at).is_none() {
+            info!(
+                operator_index = request.operator_index,
+                "First operator heartbeat"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Record a signed heartbeat of a bridge operator
+pub async fn post_operator_heartbeat(
+    Json(request): Json<HeartbeatRequest>,
+    heartbeats: Heartbeats,
+) -> StatusCode {
+    match heartbeats.receive(&request, Utc::now()).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => {
+            warn!(
+                operator_index = request.operator_index,
+                %status,
+                "Rejected operator heartbeat"
+            );
+            status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{heartbeat_message, HeartbeatRequest, Heartbeats, OperatorLiveness};
+    use axum::http::StatusCode;
+    use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+    use chrono::{Duration, Utc};
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_heartbeat_verification() {
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let heartbeats = Heartbeats::default();
+        heartbeats
+            .set_operator_keys(BTreeMap::from([(
+                0,
+                PublicKey::from_secret_key(&secp, &secret_key),
+            )]))
+            .await;
+
+        let now = Utc::now();
+        let heartbeat = |operator_index, timestamp: i64| HeartbeatRequest {
+            operator_index,
+            timestamp,
+            signature: secp
+                .sign_ecdsa(&heartbeat_message(operator_index, timestamp), &secret_key)
+                .to_string(),
+        };
+
+        assert_eq!(
+            heartbeats
+                .receive(&heartbeat(0, now.timestamp()), now)
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            heartbeats.last(0).await.map(|at| at.timestamp()),
+            Some(now.timestamp())
+        );
+        // Replayed heartbeats are rejected
+        assert_eq!(
+            heartbeats
+                .receive(&heartbeat(0, now.timestamp()), now)
+                .await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            heartbeats
+                .receive(&heartbeat(0, now.timestamp() + 3_600), now)
+                .await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            heartbeats
+                .receive(&heartbeat(1, now.timestamp()), now)
+                .await,
+            Err(StatusCode::NOT_FOUND)
+        );
+
+        // Signed for another timestamp
+        let mut forged = heartbeat(0, now.timestamp() + 1);
+        forged.timestamp += 1;
+        assert_eq!(
+            heartbeats.receive(&forged, now).await,
+            Err(StatusCode::UNAUTHORIZED)
+        );
+    }
+
+    #[test]
+    fn test_operator_liveness() {
+        let now = Utc::now();
+        let max_age = Duration::minutes(5);
+        let liveness = |rpc_online, last_heartbeat| {
+            OperatorLiveness::new(rpc_online, last_heartbeat, now, max_age)
+        };
+
+        assert_eq!(liveness(true, None), OperatorLiveness::Up);
+        assert_eq!(
+            liveness(false, Some(now - Duration::minutes(1))),
+            OperatorLiveness::RpcUnreachable
+        );
+        assert_eq!(
+            liveness(false, Some(now - Duration::minutes(10))),
+            OperatorLiveness::Down
+        );
+        assert_eq!(liveness(false, None), OperatorLiveness::Down);
+    }
+}
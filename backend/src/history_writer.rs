@@ -0,0 +1,262 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::mpsc::{self, error::TrySendError};
+#[cfg(test)]
+use tokio::sync::oneshot;
+use tracing::warn;
+
+/// Max number of records appended to the file at once
+const MAX_BATCH_SIZE: usize = 256;
+
+enum WriterMessage {
+    Line(Vec<u8>),
+    /// Replaces the file content, superseding the lines sent before it
+    Rewrite(Vec<u8>),
+    /// Answered once every record sent before it is written
+    #[cfg(test)]
+    Flush(oneshot::Sender<()>),
+}
+
+/// Appends history records to a JSON lines file from a dedicated task.
+///
+/// Records are buffered in a bounded channel and written in batches. When the
+/// buffer is full, e.g. because the disk is slow, records are dropped and counted
+/// rather than stalling the monitoring task recording them.
+#[derive(Clone, Debug)]
+pub struct HistoryWriter {
+    sender: mpsc::Sender<WriterMessage>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl HistoryWriter {
+    /// Spawns the writer task appending to `path`, buffering up to `capacity` records
+    pub fn spawn(path: String, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        tokio::spawn(write_batches(path, receiver));
+        Self {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Queues a record, dropping it if the buffer is full
+    pub fn write(&self, record: &impl Serialize) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize history record");
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        match self.sender.try_send(WriterMessage::Line(line)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                warn!(%dropped, "History write buffer full, dropping record");
+            }
+            Err(TrySendError::Closed(_)) => warn!("History writer stopped, dropping record"),
+        }
+    }
+
+    /// Queues the replacement of the file with `records`, e.g. to drop expired
+    /// ones. They must include every record written so far that is to be kept.
+    pub fn rewrite<'a, T: Serialize + 'a>(&self, records: impl IntoIterator<Item = &'a T>) {
+        let data = match serialize_records(records) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize history records");
+                return;
+            }
+        };
+        if self.sender.try_send(WriterMessage::Rewrite(data)).is_err() {
+            warn!("History write buffer full, skipping rewrite");
+        }
+    }
+
+    /// Number of records dropped because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Waits until the records queued so far are written
+    #[cfg(test)]
+    pub async fn flush(&self) {
+        let (done, written) = oneshot::channel();
+        if self.sender.send(WriterMessage::Flush(done)).await.is_ok() {
+            let _ = written.await;
+        }
+    }
+}
+
+async fn write_batches(path: String, mut receiver: mpsc::Receiver<WriterMessage>) {
+    let mut messages = Vec::with_capacity(MAX_BATCH_SIZE);
+    while receiver.recv_many(&mut messages, MAX_BATCH_SIZE).await > 0 {
+        let mut data = Vec::new();
+        let mut rewrite = false;
+        #[cfg(test)]
+        let mut flushes = Vec::new();
+        for message in messages.drain(..) {
+            match message {
+                WriterMessage::Line(line) => data.extend(line),
+                WriterMessage::Rewrite(records) => {
+                    data = records;
+                    rewrite = true;
+                }
+                #[cfg(test)]
+                WriterMessage::Flush(done) => flushes.push(done),
+            }
+        }
+
+        // File IO blocks, keep it off the runtime threads
+        let file_path = path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            if rewrite {
+                replace(&file_path, &data)
+            } else {
+                append(&file_path, &data)
+            }
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!(%path, error = %e, "Failed to persist history records"),
+            Err(e) => warn!(%path, error = %e, "History writer task failed"),
+        }
+
+        #[cfg(test)]
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Reads the records of a JSON lines file, skipping unparseable lines.
+///
+/// `None` if the file cannot be read, e.g. on first start.
+pub fn read_records<T: DeserializeOwned>(path: &str) -> Option<Vec<T>> {
+    let data = fs::read_to_string(path).ok()?;
+    Some(
+        data.lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+    )
+}
+
+/// Atomically replaces a JSON lines file with `records`, so that expired records
+/// do not accumulate across restarts
+pub fn rewrite_records<'a, T: Serialize + 'a>(
+    path: &str,
+    records: impl IntoIterator<Item = &'a T>,
+) -> Result<(), anyhow::Error> {
+    replace(path, &serialize_records(records)?)
+}
+
+fn serialize_records<'a, T: Serialize + 'a>(
+    records: impl IntoIterator<Item = &'a T>,
+) -> Result<Vec<u8>, serde_json::Error> {
+    let mut data = Vec::new();
+    for record in records {
+        data.extend(serde_json::to_vec(record)?);
+        data.push(b'\n');
+    }
+    Ok(data)
+}
+
+fn replace(path: &str, data: &[u8]) -> Result<(), anyhow::Error> {
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, data)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn append(path: &str, data: &[u8]) -> Result<(), anyhow::Error> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HistoryWriter;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_history_writer_batches_records() {
+        let path =
+            std::env::temp_dir().join(format!("history_writer_test_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let writer = HistoryWriter::spawn(path.clone(), 16);
+        for i in 0..3 {
+            writer.write(&json!({ "i": i }));
+        }
+        writer.flush().await;
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(data, "{\"i\":0}\n{\"i\":1}\n{\"i\":2}\n");
+        assert_eq!(writer.dropped(), 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history_writer_drops_on_overflow() {
+        let path =
+            std::env::temp_dir().join(format!("history_writer_full_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        // The writer task does not run before this test yields
+        let writer = HistoryWriter::spawn(path.clone(), 1);
+        for i in 0..3 {
+            writer.write(&json!({ "i": i }));
+        }
+        assert_eq!(writer.dropped(), 2);
+
+        writer.flush().await;
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(data, "{\"i\":0}\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history_writer_rewrite() {
+        let path = std::env::temp_dir().join(format!(
+            "history_writer_rewrite_{}.jsonl",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let writer = HistoryWriter::spawn(path.clone(), 16);
+        for i in 0..3 {
+            writer.write(&json!({ "i": i }));
+        }
+        // Records written after the rewrite are appended to it
+        writer.rewrite(&[json!({ "i": 2 })]);
+        writer.write(&json!({ "i": 3 }));
+        writer.flush().await;
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(data, "{\"i\":2}\n{\"i\":3}\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,264 @@
+//! Translations of the human-readable strings of the status page: status and
+//! component names, and incident titles.
+//!
+//! English is built in; other languages are loaded from the JSON file at
+//! `STATUS_PAGE_TRANSLATIONS_PATH`, keyed by language then message key. The
+//! language is negotiated per request from `Accept-Language`, and missing messages
+//! fall back to English.
+
+use axum::http::{header::ACCEPT_LANGUAGE, HeaderMap};
+use std::{collections::HashMap, sync::Arc};
+use tracing::{info, warn};
+
+use crate::{alerts::Severity, network::Status, overview::OverallStatus};
+
+/// Language of the built-in messages
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Built-in English messages. Incident titles are keyed by the alert id up to the
+/// first `:`, and `{subject}` is replaced with the rest of the id, e.g. the wallet.
+const ENGLISH: &[(&str, &str)] = &[
+    ("status.online", "Operational"),
+    ("status.offline", "Down"),
+    ("status.stalled", "Stalled"),
+    ("overall.healthy", "All systems operational"),
+    ("overall.degraded", "Degraded performance"),
+    ("overall.unhealthy", "Major outage"),
+    ("component.batch_producer", "Batch producer"),
+    ("component.rpc_endpoint", "RPC endpoint"),
+    ("component.bundler_endpoint", "Bundler endpoint"),
+    ("severity.warning", "Warning"),
+    ("severity.critical", "Critical"),
+    ("incident.resolved", "Resolved"),
+    ("incident.ongoing", "Ongoing"),
+    (
+        "incident.batch_producer_stalled",
+        "Batch production stalled",
+    ),
+    ("incident.bundler_stalled", "Bundling stalled"),
+    (
+        "incident.bridge_duty_backlog",
+        "Bridge operator {subject} is falling behind",
+    ),
+    ("incident.bridge_supply_mismatch", "Bridged supply mismatch"),
+    (
+        "incident.paymaster_balance_low",
+        "Low {subject} paymaster balance",
+    ),
+    (
+        "incident.wallet_stuck_transactions",
+        "Stuck transactions of the {subject} wallet",
+    ),
+    ("incident.rule", "Alert rule {subject} firing"),
+    ("incident.config_drift", "Configuration drift of {subject}"),
+];
+
+/// Messages by language, then by key
+#[derive(Clone, Debug)]
+pub struct Translations {
+    languages: Arc<HashMap<String, HashMap<String, String>>>,
+}
+
+impl Default for Translations {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+impl Translations {
+    /// Adds the built-in English messages to `languages`, keeping overrides of them
+    fn new(mut languages: HashMap<String, HashMap<String, String>>) -> Self {
+        let english = languages.entry(DEFAULT_LANGUAGE.to_string()).or_default();
+        for (key, message) in ENGLISH {
+            english
+                .entry(key.to_string())
+                .or_insert_with(|| message.to_string());
+        }
+        Self {
+            languages: Arc::new(languages),
+        }
+    }
+
+    /// Loads the translations at `path`, only the built-in English messages if unset
+    /// or unreadable
+    pub fn load(path: Option<&str>) -> Self {
+        let Some(path) = path else {
+            return Self::default();
+        };
+        let loaded = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                serde_json::from_str::<HashMap<String, HashMap<String, String>>>(&data)
+                    .map_err(|e| e.to_string())
+            });
+        match loaded {
+            Ok(languages) => {
+                let mut names: Vec<&String> = languages.keys().collect();
+                names.sort();
+                info!(%path, languages = ?names, "Loaded status page translations");
+                Self::new(languages)
+            }
+            Err(e) => {
+                warn!(%path, error = %e, "Failed to load status page translations");
+                Self::default()
+            }
+        }
+    }
+
+    /// Best supported language of an `Accept-Language` header, e.g. `de` for
+    /// `fr-CH;q=0.9, de;q=0.8`, English if none is supported
+    fn negotiate(&self, accept_language: &str) -> &str {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let tag = parts.next().filter(|tag| !tag.is_empty())?;
+                let quality = parts
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+                Some((tag, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        // Stable, so that equally preferred languages keep the order of the header
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .iter()
+            .find_map(|(tag, _)| {
+                let tag = tag.to_lowercase();
+                let primary = tag.split('-').next().unwrap_or_default();
+                [tag.as_str(), primary]
+                    .into_iter()
+                    .find_map(|candidate| self.languages.get_key_value(candidate))
+                    .map(|(language, _)| language.as_str())
+            })
+            .unwrap_or(DEFAULT_LANGUAGE)
+    }
+
+    /// Messages in the language requested by `headers`
+    pub fn localizer(&self, headers: &HeaderMap) -> Localizer<'_> {
+        let accept_language = headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        Localizer {
+            translations: self,
+            language: self.negotiate(accept_language),
+        }
+    }
+}
+
+/// Messages of one language
+#[derive(Clone, Copy, Debug)]
+pub struct Localizer<'a> {
+    translations: &'a Translations,
+    language: &'a str,
+}
+
+impl Localizer<'_> {
+    /// Language of the messages, for the `Content-Language` header
+    pub fn language(&self) -> &str {
+        self.language
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        [self.language, DEFAULT_LANGUAGE]
+            .into_iter()
+            .find_map(|language| self.translations.languages.get(language)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// Message `key`, or the key itself if no language has it
+    pub fn text(&self, key: &str) -> String {
+        self.lookup(key).unwrap_or(key).to_string()
+    }
+
+    pub fn status(&self, status: &Status) -> String {
+        self.text(match status {
+            Status::Online => "status.online",
+            Status::Offline => "status.offline",
+            Status::Stalled => "status.stalled",
+        })
+    }
+
+    pub fn overall_status(&self, status: &OverallStatus) -> String {
+        self.text(match status {
+            OverallStatus::Healthy => "overall.healthy",
+            OverallStatus::Degraded => "overall.degraded",
+            OverallStatus::Unhealthy => "overall.unhealthy",
+        })
+    }
+
+    pub fn severity(&self, severity: Severity) -> String {
+        self.text(match severity {
+            Severity::Warning => "severity.warning",
+            Severity::Critical => "severity.critical",
+        })
+    }
+
+    /// Display name of a component; checks declared in config keep their name
+    pub fn component(&self, name: &str) -> String {
+        self.lookup(&format!("component.{}", name))
+            .unwrap_or(name)
+            .to_string()
+    }
+
+    /// Title of the incident of an alert, `None` for alerts without a template
+    pub fn incident_title(&self, alert_id: &str) -> Option<String> {
+        let (kind, subject) = alert_id.split_once(':').unwrap_or((alert_id, ""));
+        let template = self.lookup(&format!("incident.{}", kind))?;
+        Some(template.replace("{subject}", subject))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Translations, DEFAULT_LANGUAGE};
+    use crate::network::Status;
+    use axum::http::{header::ACCEPT_LANGUAGE, HeaderMap, HeaderValue};
+    use std::collections::HashMap;
+
+    fn translations() -> Translations {
+        Translations::new(HashMap::from([(
+            "de".to_string(),
+            HashMap::from([
+                ("status.online".to_string(), "Betriebsbereit".to_string()),
+                (
+                    "incident.paymaster_balance_low".to_string(),
+                    "Niedriges Guthaben des {subject}-Paymasters".to_string(),
+                ),
+            ]),
+        )]))
+    }
+
+    #[test]
+    fn test_negotiate_language() {
+        let translations = translations();
+        assert_eq!(translations.negotiate(""), DEFAULT_LANGUAGE);
+        assert_eq!(translations.negotiate("de-CH"), "de");
+        assert_eq!(translations.negotiate("fr-CH, de;q=0.8, en;q=0.9"), "en");
+        assert_eq!(translations.negotiate("fr, de;q=0.5"), "de");
+        assert_eq!(translations.negotiate("de;q=0, *"), DEFAULT_LANGUAGE);
+    }
+
+    #[test]
+    fn test_localized_messages() {
+        let translations = translations();
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("de"));
+        let localizer = translations.localizer(&headers);
+
+        assert_eq!(localizer.language(), "de");
+        assert_eq!(localizer.status(&Status::Online), "Betriebsbereit");
+        // Falls back to English
+        assert_eq!(localizer.status(&Status::Stalled), "Stalled");
+        assert_eq!(localizer.component("rpc_endpoint"), "RPC endpoint");
+        assert_eq!(localizer.component("fullnode"), "fullnode");
+        assert_eq!(
+            localizer.incident_title("paymaster_balance_low:deposit"),
+            Some("Niedriges Guthaben des deposit-Paymasters".to_string())
+        );
+        assert_eq!(localizer.incident_title("unknown"), None);
+    }
+}
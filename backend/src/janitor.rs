@@ -0,0 +1,42 @@
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::{
+    bridge_changes::SharedBridgeChanges,
+    cron::{sleep_until, CronSchedule, Schedule},
+    event_log::SharedEventLog,
+    status_history::SharedStatusHistory,
+    tasks::{TaskRegistry, JANITOR_TASK},
+    user_op_history::SharedUserOpHistory,
+};
+
+/// Compacts the persisted status history, bridge changes, event log and user ops on
+/// `schedule`, so that their files do not grow with expired records between restarts
+pub async fn janitor_task(
+    history: SharedStatusHistory,
+    changes: SharedBridgeChanges,
+    event_log: SharedEventLog,
+    user_ops: SharedUserOpHistory,
+    tasks: TaskRegistry,
+    schedule: &CronSchedule,
+) {
+    let interval_s = Schedule::Cron(schedule.clone()).interval_s(Utc::now());
+    tasks.register(JANITOR_TASK, interval_s).await;
+
+    loop {
+        let Some(at) = schedule.next_after(Utc::now()) else {
+            warn!(%schedule, "Janitor schedule has no upcoming run");
+            return;
+        };
+        sleep_until(at).await;
+        tasks.start_refresh(JANITOR_TASK).await;
+
+        history.write().await.compact(Utc::now());
+        changes.write().await.compact();
+        event_log.write().await.compact();
+        user_ops.write().await.compact();
+        info!("Compacted persisted histories");
+
+        tasks.record_refresh(JANITOR_TASK).await;
+    }
+}
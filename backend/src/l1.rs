@@ -0,0 +1,235 @@
+use anyhow::Context;
+use bitcoin::{Network, Txid};
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use crate::{archive, btc_validation::upstream_address, degradation};
+
+/// Confirmation status of a bitcoin transaction as reported by Esplora
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TxStatus {
+    pub confirmed: bool,
+    pub block_height: Option<u64>,
+    /// Unix timestamp of the confirming block
+    #[serde(default)]
+    pub block_time: Option<i64>,
+}
+
+impl TxStatus {
+    /// Time of the confirming block, `None` while unconfirmed
+    pub fn confirmed_at(&self) -> Option<DateTime<Utc>> {
+        self.block_time
+            .filter(|_| self.confirmed)
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+    }
+}
+
+/// Output of a bitcoin transaction as reported by Esplora
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TxOutput {
+    pub scriptpubkey_type: String,
+    /// Address paid by the output, unset for non-standard scripts
+    #[serde(default)]
+    pub scriptpubkey_address: Option<String>,
+    /// Amount in sats
+    pub value: u64,
+}
+
+#[derive(Deserialize)]
+struct Tx {
+    vout: Vec<TxOutput>,
+    /// Fee paid in sats
+    fee: u64,
+}
+
+/// Parses a response body, archiving it if sampled
+async fn parse_json<T: DeserializeOwned>(
+    url: &str,
+    response: reqwest::Response,
+) -> Result<T, anyhow::Error> {
+    let body: Value = response.json().await?;
+    if archive::sample() {
+        archive::record("esplora", json!({ "url": url }), &body);
+    }
+    Ok(serde_json::from_value(body)?)
+}
+
+/// Minimal client for the Esplora REST API of a bitcoin node
+#[derive(Clone, Debug)]
+pub struct EsploraClient {
+    http: reqwest::Client,
+    base_url: String,
+    /// Network of the node, which output addresses must belong to
+    network: Network,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: &str, network: Network) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            network,
+        }
+    }
+
+    /// Fetch the status of a transaction, `None` if neither the mempool nor
+    /// the chain knows about it
+    pub async fn tx_status(&self, txid: &Txid) -> Result<Option<TxStatus>, anyhow::Error> {
+        self.fetch_tx_status(txid).await.inspect_err(record_failure)
+    }
+
+    async fn fetch_tx_status(&self, txid: &Txid) -> Result<Option<TxStatus>, anyhow::Error> {
+        let url = format!("{}/tx/{}/status", self.base_url, txid);
+        crate::budget::admit("esplora")?;
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject("esplora").await?;
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {}", url))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let status: TxStatus = parse_json(&url, response.error_for_status()?)
+            .await
+            .context("Failed to parse transaction status")?;
+
+        Ok(Some(status))
+    }
+
+    async fn tx(&self, txid: &Txid) -> Result<Tx, anyhow::Error> {
+        self.fetch_tx(txid).await.inspect_err(record_failure)
+    }
+
+    async fn fetch_tx(&self, txid: &Txid) -> Result<Tx, anyhow::Error> {
+        let url = format!("{}/tx/{}", self.base_url, txid);
+        crate::budget::admit("esplora")?;
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject("esplora").await?;
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {}", url))?
+            .error_for_status()?;
+        parse_json(&url, response)
+            .await
+            .context("Failed to parse transaction")
+    }
+
+    /// Fetch the outputs of a transaction. Malformed addresses, or addresses of
+    /// another network, are dropped.
+    pub async fn tx_outputs(&self, txid: &Txid) -> Result<Vec<TxOutput>, anyhow::Error> {
+        let mut outputs = self.tx(txid).await?.vout;
+        for output in &mut outputs {
+            output.scriptpubkey_address = output
+                .scriptpubkey_address
+                .take()
+                .and_then(|address| upstream_address("esplora", &address, self.network));
+        }
+        Ok(outputs)
+    }
+
+    /// Fetch the fee paid by a transaction, in sats
+    pub async fn tx_fee(&self, txid: &Txid) -> Result<u64, anyhow::Error> {
+        Ok(self.tx(txid).await?.fee)
+    }
+}
+
+/// Degrades the task whose request to Esplora failed
+fn record_failure(error: &anyhow::Error) {
+    degradation::record_failure("esplora", degradation::failure_kind(error));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EsploraClient, TxOutput, TxStatus};
+    use bitcoin::{Network, Txid};
+    use chrono::{TimeZone, Utc};
+    use mockito::Server;
+    use std::str::FromStr;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_tx_status() {
+        let mut server = Server::new_async().await;
+        let txid = Txid::from_str(TXID).unwrap();
+        let client = EsploraClient::new(&server.url(), Network::Bitcoin);
+
+        let confirmed = server
+            .mock("GET", format!("/tx/{}/status", TXID).as_str())
+            .with_status(200)
+            .with_body(
+                r#"{"confirmed": true, "block_height": 100, "block_hash": "00", "block_time": 1741608000}"#,
+            )
+            .create();
+        assert_eq!(
+            client.tx_status(&txid).await.unwrap(),
+            Some(TxStatus {
+                confirmed: true,
+                block_height: Some(100),
+                block_time: Some(1741608000),
+            })
+        );
+        assert_eq!(
+            client
+                .tx_status(&txid)
+                .await
+                .unwrap()
+                .unwrap()
+                .confirmed_at(),
+            Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).single()
+        );
+        confirmed.remove();
+
+        let _missing = server
+            .mock("GET", format!("/tx/{}/status", TXID).as_str())
+            .with_status(404)
+            .with_body("Transaction not found")
+            .create();
+        assert_eq!(client.tx_status(&txid).await.unwrap(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_tx_outputs() {
+        let mut server = Server::new_async().await;
+        let txid = Txid::from_str(TXID).unwrap();
+        let client = EsploraClient::new(&server.url(), Network::Bitcoin);
+
+        let _tx = server
+            .mock("GET", format!("/tx/{}", TXID).as_str())
+            .with_status(200)
+            .with_body(
+                r#"{"txid": "00", "fee": 141, "vout": [
+                    {"scriptpubkey": "0014", "scriptpubkey_type": "v0_p2wpkh", "scriptpubkey_address": "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "value": 1000},
+                    {"scriptpubkey": "6a", "scriptpubkey_type": "op_return", "value": 0},
+                    {"scriptpubkey": "0014", "scriptpubkey_type": "v0_p2wpkh", "scriptpubkey_address": "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", "value": 500}
+                ]}"#,
+            )
+            .create();
+
+        let outputs = client.tx_outputs(&txid).await.unwrap();
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(
+            outputs[0],
+            TxOutput {
+                scriptpubkey_type: "v0_p2wpkh".to_string(),
+                scriptpubkey_address: Some(
+                    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string()
+                ),
+                value: 1000,
+            }
+        );
+        // Not a mainnet address
+        assert_eq!(outputs[2].scriptpubkey_address, None);
+        assert_eq!(client.tx_fee(&txid).await.unwrap(), 141);
+    }
+}
@@ -0,0 +1,145 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry,
+};
+
+use crate::auth::AdminAuth;
+
+/// Log filter of the process, adjustable at runtime, e.g. to turn on debug logs of
+/// the bridge monitor during an incident with `backend::bridge=debug`
+#[derive(Clone)]
+pub struct LogLevels {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// Directives of `RUST_LOG` at startup, restored on reset
+    startup_directives: Arc<str>,
+}
+
+impl LogLevels {
+    /// Installs the global subscriber, filtered by `RUST_LOG` until changed
+    pub fn init() -> Self {
+        let filter = EnvFilter::from_default_env();
+        let startup_directives = Arc::from(filter.to_string());
+        let (filter, handle) = reload::Layer::new(filter);
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer())
+            .init();
+        Self {
+            handle,
+            startup_directives,
+        }
+    }
+
+    /// Current filter directives, comma-separated
+    pub fn directives(&self) -> String {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the filter with `directives`, in the `RUST_LOG` syntax
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.handle.reload(filter).map_err(|e| e.to_string())
+    }
+
+    /// Restores the filter of `RUST_LOG` at startup
+    pub fn reset(&self) -> Result<(), String> {
+        self.set(&self.startup_directives)
+    }
+}
+
+/// Current and startup log filters
+#[derive(Serialize, Debug)]
+pub struct LogLevelsResponse {
+    directives: String,
+    startup_directives: String,
+}
+
+impl From<&LogLevels> for LogLevelsResponse {
+    fn from(log_levels: &LogLevels) -> Self {
+        Self {
+            directives: log_levels.directives(),
+            startup_directives: log_levels.startup_directives.to_string(),
+        }
+    }
+}
+
+/// Body of the log levels endpoint
+#[derive(Deserialize, Debug)]
+pub struct LogLevelsRequest {
+    /// Filter directives, e.g. `info,backend::bridge=debug`
+    directives: String,
+}
+
+/// Get the log filter. Requires the admin token.
+pub async fn get_log_levels(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    log_levels: LogLevels,
+) -> Result<Json<LogLevelsResponse>, StatusCode> {
+    auth.check(&headers)?;
+    Ok(Json(LogLevelsResponse::from(&log_levels)))
+}
+
+/// Replace the log filter until the next restart or reset. Requires the admin token.
+pub async fn post_log_levels(
+    headers: HeaderMap,
+    Json(request): Json<LogLevelsRequest>,
+    auth: AdminAuth,
+    log_levels: LogLevels,
+) -> Result<Json<LogLevelsResponse>, StatusCode> {
+    auth.check(&headers)?;
+    if let Err(e) = log_levels.set(&request.directives) {
+        warn!(directives = %request.directives, error = %e, "Rejected log filter");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    info!(directives = %request.directives, "Changed log filter");
+    Ok(Json(LogLevelsResponse::from(&log_levels)))
+}
+
+/// Restore the log filter of startup. Requires the admin token.
+pub async fn delete_log_levels(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    log_levels: LogLevels,
+) -> Result<Json<LogLevelsResponse>, StatusCode> {
+    auth.check(&headers)?;
+    log_levels.reset().map_err(|e| {
+        warn!(error = %e, "Failed to reset log filter");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("Reset log filter");
+    Ok(Json(LogLevelsResponse::from(&log_levels)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogLevels;
+    use tracing_subscriber::{reload, EnvFilter};
+
+    #[test]
+    fn test_log_levels_reload() {
+        // The layer must outlive the handle; it is not installed as the global subscriber
+        let (_layer, handle) = reload::Layer::new(EnvFilter::new("info"));
+        let log_levels = LogLevels {
+            handle,
+            startup_directives: "info".into(),
+        };
+
+        log_levels.set("info,backend::bridge=debug").unwrap();
+        assert!(log_levels.directives().contains("backend::bridge=debug"));
+        // Invalid directives leave the filter unchanged
+        assert!(log_levels.set("backend::bridge=loud").is_err());
+        assert!(log_levels.directives().contains("backend::bridge=debug"));
+
+        log_levels.reset().unwrap();
+        assert_eq!(log_levels.directives(), "info");
+    }
+}
@@ -1,218 +1,1257 @@
 mod activity;
+mod admin;
+mod aggregator;
+mod alert_rules;
+mod alerts;
+mod annotations;
+mod archive;
+mod auth;
 mod bridge;
+mod bridge_anomalies;
+mod bridge_changes;
+mod bridge_graph;
+mod bridge_liability;
+mod bridge_volume;
+mod bridge_watchlist;
+mod btc_validation;
+mod budget;
+mod bundler;
+mod bundles;
+mod canary;
+mod chain_info;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod checkpoint;
+mod checks;
+mod clickhouse;
+mod clients;
+mod clock_skew;
 mod config;
+mod consumers;
+mod cron;
+mod degradation;
+mod diagnostics;
+mod display;
+mod drift;
+mod event_log;
+mod events;
+mod explorer;
+mod explorer_proxy;
+mod fingerprint;
+mod health;
+mod heartbeats;
+mod history_writer;
+mod i18n;
+mod janitor;
+mod l1;
+mod log_levels;
+mod metrics;
+mod network;
+mod overview;
+mod paymaster_report;
+mod polling;
+mod push;
+mod rate_limit;
+mod registry;
+mod reports;
+mod response;
 mod retry_policy;
+mod rpc_api;
+mod s3;
+mod slo;
+mod smoke_test;
+#[cfg(feature = "snapshot-diff")]
+mod snapshot_diff;
+mod sqlite;
+mod status_history;
+mod status_rules;
+mod store;
+mod tasks;
+mod templates;
+mod top_up;
+mod txid_format;
+mod uptime_pings;
+mod user_op_history;
 mod utils;
 mod wallets;
+mod watched;
 
-use axum::{routing::get, Json, Router};
-use dotenvy::dotenv;
-use jsonrpsee::core::client::ClientT;
-use jsonrpsee::http_client::HttpClient;
-use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, sync::Arc};
-use tokio::{
-    net::TcpListener,
-    sync::RwLock,
-    time::{interval, sleep, Duration},
+use axum::{
+    extract::{Path, Query},
+    http::HeaderMap,
+    middleware,
+    routing::{delete, get, post, put},
+    Json, Router,
 };
+use clap::{Parser, Subcommand};
+use dotenvy::dotenv;
+use std::{collections::BTreeMap, future::IntoFuture, net::SocketAddr, sync::Arc};
+use tokio::{net::TcpListener, sync::RwLock, task::JoinSet, time::Duration};
 use tower_http::cors::{Any, CorsLayer};
-use tracing::{error, info};
+use tracing::{info, warn};
+use web_push::SubscriptionInfo;
 
 use crate::{
-    activity::{activity_monitoring_task, get_activity_stats, ActivityStats},
-    bridge::{bridge_monitoring_task, get_bridge_status, SharedBridgeState},
-    config::{ActivityMonitoringConfig, BridgeMonitoringConfig, NetworkConfig},
+    activity::{
+        activity_monitoring_task, get_accounts_export, get_activity_heatmap, get_activity_stats,
+        AccountsExportQuery, ActivityStats, SharedActivityStats,
+    },
+    admin::get_state_dump,
+    alert_rules::alert_rules_task,
+    alerts::{
+        delete_alert_acknowledgement, get_alerts, get_incidents, post_alert_acknowledgement,
+        AcknowledgeRequest, Alerts,
+    },
+    annotations::{
+        get_annotations, post_annotation, AnnotationRequest, Annotations, AnnotationsQuery,
+    },
+    archive::archive_task,
+    auth::{require_api_token, AdminAuth, ApiAuth},
+    bridge::{
+        bridge_monitoring_task, get_bridge_status, get_deposit_by_txid, get_withdrawals_by_address,
+        BridgeStatusQuery, SharedBridgeState,
+    },
+    bridge_anomalies::get_bridge_anomalies,
+    bridge_changes::{
+        get_bridge_changes, get_deposit_timeline, BridgeChangeLog, BridgeChangesQuery,
+        SharedBridgeChanges, TimelineQuery,
+    },
+    bridge_graph::get_bridge_graph,
+    bridge_liability::get_bridge_liability,
+    bridge_volume::{get_bridge_volume, BridgeVolumeQuery},
+    bridge_watchlist::{get_bridge_watchlist, SharedWatchlist, Watchlist},
+    bundler::{bundler_stats_task, get_bundler_stats, SharedBundlerStats},
+    bundles::{
+        get_bundle_analytics, get_bundle_range, get_ops_per_minute, BundleHistory,
+        BundleRangeQuery, BundleRecord, OpsPerMinuteQuery, SharedBundleAnalytics,
+    },
+    canary::canary_routes,
+    chain_info::{get_chain_info, ChainInfoCache},
+    clickhouse::{ClickHouseStore, ClickHouseTable},
+    clock_skew::{clock_skew_task, get_clock_skew, SharedClockSkew},
+    config::{
+        ActivityMonitoringConfig, AlertRulesConfig, ArchiveConfig, BridgeMonitoringConfig,
+        BundlerMonitoringConfig, CanaryConfig, ClickHouseConfig, ClockSkewConfig, DriftConfig,
+        PushConfig, ReportsConfig, ServerConfig, SloConfig, TopUpConfig,
+    },
+    consumers::{get_consumers, meter_consumers, ConsumerMeter},
+    diagnostics::get_runtime_diagnostics,
+    drift::drift_check_task,
+    event_log::{event_log_writer, get_event_export, EventExportQuery, EventLog, SharedEventLog},
+    events::{event_alerts, get_events, status_history_writer, EventBus},
+    explorer::HttpExplorerClient,
+    explorer_proxy::{get_explorer_proxy, ProxiedEndpoint, ProxyCache, ProxyQuery},
+    fingerprint::{get_version, EnvironmentFingerprint},
+    health::{get_health, get_health_details},
+    heartbeats::{post_operator_heartbeat, HeartbeatRequest, Heartbeats},
+    i18n::Translations,
+    janitor::janitor_task,
+    log_levels::{delete_log_levels, get_log_levels, post_log_levels, LogLevels, LogLevelsRequest},
+    metrics::get_metrics,
+    network::{fetch_statuses_task, get_network_status, NetworkStatusQuery, SharedNetworkState},
+    overview::get_overview,
+    paymaster_report::{get_paymaster_report, PaymasterReportQuery},
+    push::{
+        get_push_public_key, post_push_subscription, post_push_unsubscribe, PushNotifier,
+        PushUnsubscribeRequest,
+    },
+    rate_limit::HostRateLimiters,
+    reports::reports_task,
+    response::{add_degraded_field, add_network_field, select_fields, NetworkId},
     retry_policy::ExponentialBackoff,
-    utils::create_rpc_client,
+    slo::get_slos,
+    sqlite::SqliteDb,
+    status_history::{get_status_history, StatusHistory, StatusHistoryQuery},
+    store::{MemoryStore, StatsStore},
+    tasks::{
+        get_tasks, require_max_age, TaskRegistry, ACTIVITY_STATS_TASK, ALERT_RULES_TASK,
+        BRIDGE_STATUS_TASK, BUNDLER_STATS_TASK, CLOCK_SKEW_TASK, DRIFT_CHECK_TASK, JANITOR_TASK,
+        NETWORK_STATUS_TASK, REPORTS_TASK, WALLET_BALANCES_TASK, WARM_UP_TASKS,
+    },
+    top_up::{get_top_ups, top_up_hooks, TopUpAudit},
+    txid_format::with_txid_byte_order,
+    uptime_pings::{
+        delete_uptime_ping, get_uptime_pings, put_uptime_ping, UptimePingRequest, UptimePings,
+    },
+    user_op_history::{
+        post_recompute_stats, RecomputeStatsQuery, SharedUserOpHistory, UserOpHistory,
+    },
     wallets::{
-        fetch_balances_task, get_wallets_with_balances, init_paymaster_wallets, SharedWallets,
+        fetch_balances_task, get_balance_history, get_wallets_with_balances,
+        init_paymaster_wallets, BalanceHistory, BalanceHistoryQuery, SharedBalanceHistory,
+        SharedWallets,
+    },
+    watched::{
+        delete_bridge_contract, delete_entry_point, get_watched_contracts, post_bridge_contract,
+        post_entry_point, BridgeContract, EntryPointRequest, WatchedContracts,
+        WatchedContractsState,
     },
 };
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "lowercase")]
-enum Status {
-    Online,
-    Offline,
+#[derive(Parser)]
+#[command(about = "Strata dashboards backend")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Serialize, Clone, Debug)]
-struct NetworkStatus {
-    batch_producer: Status,
-    rpc_endpoint: Status,
-    bundler_endpoint: Status,
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the dashboard API (default)
+    Serve,
+    /// Print the semantic differences between two state dump snapshots
+    #[cfg(feature = "snapshot-diff")]
+    DiffSnapshots {
+        old: std::path::PathBuf,
+        new: std::path::PathBuf,
+    },
+    /// Check every API route of a running backend, e.g. after a deploy
+    SmokeTest {
+        /// Base URL of the backend, e.g. `http://localhost:3000`
+        base_url: String,
+        /// API token sent along, for the endpoint groups that are not public
+        #[arg(long)]
+        token: Option<String>,
+        /// Max age of the monitored states, e.g. `5m`
+        #[arg(long)]
+        max_age: Option<String>,
+    },
+    /// Run a single bridge monitoring cycle against the configured RPCs, e.g. the
+    /// `mock_rpc` server, and print the resulting bridge status
+    BridgeDryRun,
 }
 
-/// Shared Network State
-type SharedNetworkState = Arc<RwLock<NetworkStatus>>;
-
-/// Calls `strata_syncStatus` using `jsonrpsee`
-async fn call_rpc_status(
-    config: &NetworkConfig,
-    client: &HttpClient,
-    retry_policy: ExponentialBackoff,
-) -> Status {
-    let mut retry_count: u64 = 0;
-
-    loop {
-        let response: Result<serde_json::Value, _> =
-            client.request("strata_syncStatus", Vec::<()>::new()).await;
-        match response {
-            Ok(json) => {
-                info!(?json, "RPC Response");
-                if json.get("tip_height").is_some() {
-                    return Status::Online;
-                } else {
-                    return Status::Offline;
-                }
-            }
-            Err(e) => {
-                if retry_count < config.max_retries() {
-                    let delay_seconds = retry_policy.get_delay(retry_count);
-                    if delay_seconds > 0 {
-                        info!(?delay_seconds, "Retrying `strata_syncStatus` after");
-                        sleep(Duration::from_secs(delay_seconds)).await;
-                    }
-                    retry_count += 1;
-                } else {
-                    error!(error = %e, "Could not get status");
-                    return Status::Offline;
-                }
-            }
-        }
-    }
+/// Handles to all shared states, for endpoints that need more than one of them
+#[derive(Clone)]
+struct SharedStates {
+    network: SharedNetworkState,
+    wallets: SharedWallets,
+    activity: SharedActivityStats,
+    bridge: SharedBridgeState,
+    bundler: SharedBundlerStats,
 }
 
-/// Checks bundler health (`/health`)
-async fn check_bundler_health(client: &reqwest::Client, config: &NetworkConfig) -> Status {
-    let url = config.bundler_url();
-    if let Ok(resp) = client.get(url).send().await {
-        let body = resp.text().await.unwrap_or_default();
-        if body.contains("ok") {
-            return Status::Online;
+#[tokio::main]
+async fn main() {
+    match Cli::parse().command.unwrap_or(Command::Serve) {
+        Command::Serve => {}
+        #[cfg(feature = "snapshot-diff")]
+        Command::DiffSnapshots { old, new } => std::process::exit(snapshot_diff::run(&old, &new)),
+        Command::SmokeTest {
+            base_url,
+            token,
+            max_age,
+        } => std::process::exit(
+            smoke_test::run(&base_url, token.as_deref(), max_age.as_deref()).await,
+        ),
+        Command::BridgeDryRun => {
+            let status = bridge::dry_run(&BridgeMonitoringConfig::new()).await;
+            let status = serde_json::to_string_pretty(&status).expect("to serialize bridge status");
+            println!("{}", status);
+            return;
         }
     }
-    Status::Offline
-}
+    let started_at = tokio::time::Instant::now();
 
-/// Periodically fetches real statuses
-async fn fetch_statuses_task(state: SharedNetworkState, config: &NetworkConfig) {
-    info!("Fetching statuses...");
-    let mut interval = interval(Duration::from_secs(10));
-    let rpc_client = create_rpc_client(config.rpc_url());
-    let http_client = reqwest::Client::new();
-    let retry_policy =
-        ExponentialBackoff::new(config.max_retries(), config.total_retry_time(), 1.5);
+    let log_levels = LogLevels::init();
 
-    loop {
-        interval.tick().await;
-
-        let batch_producer = call_rpc_status(config, &rpc_client, retry_policy).await;
-        let rpc_endpoint = call_rpc_status(config, &rpc_client, retry_policy).await;
-        let bundler_endpoint = check_bundler_health(&http_client, config).await;
+    dotenv().ok();
 
-        let new_status = NetworkStatus {
-            batch_producer,
-            rpc_endpoint,
-            bundler_endpoint,
-        };
+    #[cfg(feature = "chaos")]
+    chaos::init(chaos::ChaosConfig::new());
+    budget::init(budget::RequestBudgetConfig::new());
 
-        info!(?new_status, "Updated Status");
+    // Optional subsystems and upstream URLs, for the environment fingerprint
+    let mut subsystems = BTreeMap::new();
+    let mut upstreams = BTreeMap::new();
 
-        let mut locked_state = state.write().await;
-        *locked_state = new_status;
+    // Archival of the raw upstream responses, enabled before the clients send any
+    let archive_config = ArchiveConfig::new();
+    subsystems.insert("archive", archive_config.enabled());
+    if let Some(responses) = archive::init(&archive_config) {
+        tokio::spawn(async move { archive_task(responses, &archive_config).await });
     }
-}
-
-/// Handler to get the current network status
-async fn get_network_status(state: SharedNetworkState) -> Json<NetworkStatus> {
-    let data = state.read().await.clone();
-    Json(data)
-}
-
-#[tokio::main]
-async fn main() {
-    tracing_subscriber::fmt::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
-
-    dotenv().ok();
 
     let config = Arc::new(config::NetworkConfig::new());
+    let server_config = ServerConfig::new();
+    upstreams.insert("rpc_endpoint", config.rpc_url().to_string());
+    upstreams.insert("bundler_endpoint", config.bundler_url().to_string());
+    upstreams.insert("reth", config.reth_url().to_string());
+    let admin_auth = AdminAuth::new(server_config.admin_token().map(str::to_string));
+    let translations = Translations::load(server_config.translations_path());
+    let uptime_pings = UptimePings::load(
+        server_config.uptime_pings_path(),
+        server_config.uptime_pings().clone(),
+    );
+    let tasks = TaskRegistry::default().with_uptime_pings(uptime_pings.clone());
+    let push = PushNotifier::new(&PushConfig::new());
+    subsystems.insert("push", push.is_some());
+    let events = EventBus::default();
+    let alerts = Alerts::with_push(push.clone()).with_events(events.clone());
 
     let cors = CorsLayer::new().allow_origin(Any);
 
     // Shared state for network status
-    let shared_state = Arc::new(RwLock::new(NetworkStatus {
-        batch_producer: Status::Offline, // Default state
-        rpc_endpoint: Status::Offline,
-        bundler_endpoint: Status::Offline,
-    }));
+    let shared_state = SharedNetworkState::default();
+    let sqlite = config.database_url().map(|url| {
+        SqliteDb::open(url, config.database_replica_url())
+            .expect("to open the DATABASE_URL database")
+    });
+    subsystems.insert("sqlite", sqlite.is_some());
+    let status_history_replica = sqlite
+        .as_ref()
+        .and_then(|db| db.replica_table("status_history"));
+    let user_ops_replica = sqlite.as_ref().and_then(|db| db.replica_table("user_ops"));
+    subsystems.insert("sqlite_replica", status_history_replica.is_some());
+    let status_history = Arc::new(RwLock::new(StatusHistory::load(
+        sqlite.as_ref(),
+        config.status_history_path().map(str::to_string),
+        chrono::Duration::days(config.status_history_retention_days() as i64),
+        config.history_write_buffer(),
+        config.history_buffer_len(),
+    )));
+    let annotations = Annotations::load(config.annotations_path());
 
     let paymaster_wallets: SharedWallets = init_paymaster_wallets(&config.clone());
+    let balance_history: SharedBalanceHistory = Arc::new(RwLock::new(BalanceHistory::new(
+        config.history_buffer_len(),
+    )));
+
+    // Event consumers subscribe before the monitoring tasks start publishing
+    tokio::spawn(status_history_writer(
+        events.subscribe(),
+        Arc::clone(&status_history),
+    ));
+    tokio::spawn(event_alerts(events.subscribe(), alerts.clone()));
+    let event_log: SharedEventLog = Arc::new(RwLock::new(EventLog::load(
+        sqlite.as_ref(),
+        config.event_log_path().map(str::to_string),
+        config.history_write_buffer(),
+    )));
+    tokio::spawn(event_log_writer(events.subscribe(), Arc::clone(&event_log)));
+    let top_up_config = TopUpConfig::new();
+    let top_up_audit = Arc::new(RwLock::new(TopUpAudit::load(
+        top_up_config.audit_path(),
+        config.history_write_buffer(),
+    )));
+    subsystems.insert("top_up", top_up_config.enabled());
+    if top_up_config.enabled() {
+        tokio::spawn({
+            let events = events.subscribe();
+            let top_up_audit = Arc::clone(&top_up_audit);
+            async move { top_up_hooks(events, top_up_audit, &top_up_config).await }
+        });
+    }
 
     // Spawn a background task to fetch real statuses
     let state_clone = Arc::clone(&shared_state);
     let paymaster_wallets_clone = Arc::clone(&paymaster_wallets);
-    tokio::spawn({
+    tokio::spawn(tasks.instrument(NETWORK_STATUS_TASK, {
         let config = Arc::clone(&config);
+        let events = events.clone();
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
         async move {
-            fetch_statuses_task(state_clone, &config).await;
+            let task = fetch_statuses_task(state_clone, events, alerts, tasks, &config);
+            budget::in_task(NETWORK_STATUS_TASK, task).await;
         }
-    });
-    tokio::spawn({
+    }));
+    tokio::spawn(tasks.instrument(WALLET_BALANCES_TASK, {
         let config = Arc::clone(&config.clone());
+        let balance_history = Arc::clone(&balance_history);
+        let events = events.clone();
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
         async move {
-            fetch_balances_task(paymaster_wallets_clone, &config).await;
+            let task = fetch_balances_task(
+                paymaster_wallets_clone,
+                balance_history,
+                events,
+                alerts,
+                tasks,
+                &config,
+            );
+            budget::in_task(WALLET_BALANCES_TASK, task).await;
         }
-    });
+    }));
 
     // Activity monitoring
-    let activity_monitoring_config = ActivityMonitoringConfig::new();
+    let activity_monitoring_config = Arc::new(ActivityMonitoringConfig::new());
     let activity_stats = ActivityStats::default(&activity_monitoring_config);
+    // Explorer requests share per-host rate limits across all tasks
+    let explorer_client = HttpExplorerClient::new(
+        HostRateLimiters::new(
+            activity_monitoring_config.explorer_max_rps(),
+            activity_monitoring_config.explorer_rate_limit_burst(),
+        ),
+        ExponentialBackoff::new(
+            activity_monitoring_config.explorer_max_retries(),
+            activity_monitoring_config.explorer_total_retry_time(),
+            1.5,
+        ),
+        activity_monitoring_config.explorer_headers().clone(),
+    );
+    // The proxy has its own budget, so it does not share the rate limits and retries
+    // of the monitoring tasks
+    let proxy_explorer_client = HttpExplorerClient::new(
+        HostRateLimiters::default(),
+        ExponentialBackoff::new(0, 0, 1.5),
+        activity_monitoring_config.explorer_headers().clone(),
+    );
+    let proxy_cache = ProxyCache::new(
+        Duration::from_secs(activity_monitoring_config.proxy_cache_ttl()),
+        activity_monitoring_config.proxy_max_rps(),
+        activity_monitoring_config.proxy_rate_limit_burst(),
+    );
     // Shared state for activity stats
     let shared_activity_stats = Arc::new(RwLock::new(activity_stats));
-    tokio::spawn({
+    let user_op_history: SharedUserOpHistory = Arc::new(RwLock::new(UserOpHistory::load(
+        sqlite.as_ref(),
+        activity_monitoring_config
+            .user_ops_path()
+            .map(str::to_string),
+        config.history_write_buffer(),
+    )));
+    tokio::spawn(tasks.instrument(ACTIVITY_STATS_TASK, {
         let activity_stats_clone = Arc::clone(&shared_activity_stats);
+        let user_op_history = Arc::clone(&user_op_history);
+        let explorer_client = explorer_client.clone();
+        let activity_monitoring_config = Arc::clone(&activity_monitoring_config);
+        let tasks = tasks.clone();
         async move {
-            activity_monitoring_task(activity_stats_clone, &activity_monitoring_config).await;
+            let task = activity_monitoring_task(
+                activity_stats_clone,
+                user_op_history,
+                explorer_client,
+                tasks,
+                &activity_monitoring_config,
+            );
+            budget::in_task(ACTIVITY_STATS_TASK, task).await;
         }
-    });
+    }));
 
-    // bridge monitoring
     let bridge_monitoring_config = BridgeMonitoringConfig::new();
+    let bridge_rpc_url = bridge_monitoring_config.bridge_rpc_url().to_string();
+    let bundler_monitoring_config = BundlerMonitoringConfig::new();
+    upstreams.insert(
+        "explorer",
+        activity_monitoring_config.user_ops_query_url().to_string(),
+    );
+    upstreams.insert(
+        "strata",
+        bridge_monitoring_config.strata_rpc_url().to_string(),
+    );
+    upstreams.insert("bridge", bridge_rpc_url.clone());
+    if let Some(url) = bridge_monitoring_config.esplora_url() {
+        upstreams.insert("esplora", url.to_string());
+    }
+    let chain_info = ChainInfoCache::new(&config, &bridge_monitoring_config);
+    let bridge_denomination_sats = bridge_monitoring_config.denomination_sats();
+    // Contracts watched by the bundler and bridge tasks, editable by the admin
+    let watched = WatchedContracts::load(
+        config.watched_contracts_path(),
+        WatchedContractsState {
+            entry_points: vec![bundler_monitoring_config.entry_point().to_string()],
+            bridge_contracts: bridge_monitoring_config
+                .bridged_asset_address()
+                .map(|address| BridgeContract {
+                    address: address.to_string(),
+                    decimals: bridge_monitoring_config.bridged_asset_decimals(),
+                })
+                .into_iter()
+                .collect(),
+        },
+    );
+
+    // bridge monitoring
     // Shared state for bridge status
     let bridge_state = SharedBridgeState::default();
-    tokio::spawn({
+    let bridge_changes: SharedBridgeChanges = Arc::new(RwLock::new(BridgeChangeLog::load(
+        sqlite.as_ref(),
+        bridge_monitoring_config.changes_path().map(str::to_string),
+        config.history_write_buffer(),
+    )));
+    let heartbeats = Heartbeats::default();
+    let watchlist: SharedWatchlist = Arc::new(RwLock::new(Watchlist::new(
+        bridge_monitoring_config.watchlist().to_vec(),
+    )));
+    tokio::spawn(tasks.instrument(BRIDGE_STATUS_TASK, {
         let bridge_state_clone = Arc::clone(&bridge_state);
+        let bridge_changes = Arc::clone(&bridge_changes);
+        let events = events.clone();
+        let heartbeats = heartbeats.clone();
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
+        let watched = watched.clone();
+        let watchlist = Arc::clone(&watchlist);
         async move {
-            bridge_monitoring_task(bridge_state_clone, &bridge_monitoring_config).await;
+            let task = bridge_monitoring_task(
+                bridge_state_clone,
+                bridge_changes,
+                events,
+                heartbeats,
+                alerts,
+                tasks,
+                watched,
+                watchlist,
+                &bridge_monitoring_config,
+            );
+            budget::in_task(BRIDGE_STATUS_TASK, task).await;
         }
-    });
+    }));
+
+    // bundler monitoring
+    let bundler_stats = SharedBundlerStats::default();
+    // Indexed bundles, also inserted into ClickHouse when configured
+    let clickhouse_config = ClickHouseConfig::new();
+    subsystems.insert("clickhouse", clickhouse_config.url().is_some());
+    let bundles_table = ClickHouseTable::new(&clickhouse_config, clickhouse_config.bundles_table());
+    let bundle_store: Box<dyn StatsStore<BundleRecord>> = match bundles_table.clone() {
+        Some(table) => Box::new(ClickHouseStore::spawn(
+            table,
+            config.history_buffer_len(),
+            Duration::from_secs(clickhouse_config.flush_interval_s()),
+        )),
+        None => Box::new(MemoryStore::new(config.history_buffer_len())),
+    };
+    let bundle_analytics: SharedBundleAnalytics = Arc::new(RwLock::new(BundleHistory::new(
+        config.history_buffer_len(),
+        bundle_store,
+    )));
+    tokio::spawn(tasks.instrument(BUNDLER_STATS_TASK, {
+        let bundler_stats_clone = Arc::clone(&bundler_stats);
+        let bundle_analytics = Arc::clone(&bundle_analytics);
+        let explorer_client = explorer_client.clone();
+        let config = Arc::clone(&config);
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
+        let watched = watched.clone();
+        async move {
+            let task = bundler_stats_task(
+                bundler_stats_clone,
+                bundle_analytics,
+                explorer_client,
+                alerts,
+                tasks,
+                watched,
+                &config,
+                &bundler_monitoring_config,
+            );
+            budget::in_task(BUNDLER_STATS_TASK, task).await;
+        }
+    }));
+
+    let network_id = NetworkId::new(config.network_name().to_string(), config.chain_id());
+    let api_auth = ApiAuth::new(
+        config.network_name().to_string(),
+        server_config.api_tokens().to_vec(),
+        server_config.public_endpoint_groups().to_vec(),
+    );
+    let consumer_meter = ConsumerMeter::new(
+        api_auth.clone(),
+        server_config.consumer_ip_header().map(str::to_string),
+        server_config.consumer_daily_quota(),
+    );
+
+    let shared_states = SharedStates {
+        network: Arc::clone(&shared_state),
+        wallets: Arc::clone(&paymaster_wallets),
+        activity: Arc::clone(&shared_activity_stats),
+        bridge: Arc::clone(&bridge_state),
+        bundler: Arc::clone(&bundler_stats),
+    };
+
+    // Live parameters compared against the configured ones
+    let drift_config = DriftConfig::new();
+    tokio::spawn(tasks.instrument(DRIFT_CHECK_TASK, {
+        let watched = watched.clone();
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
+        let config = Arc::clone(&config);
+        async move {
+            drift_check_task(
+                watched,
+                alerts,
+                tasks,
+                bridge_rpc_url,
+                &config,
+                &drift_config,
+            )
+            .await;
+        }
+    }));
+
+    // Local clock compared against the upstream services
+    let clock_skew_config = ClockSkewConfig::new();
+    let clock_skew = SharedClockSkew::default();
+    tokio::spawn(tasks.instrument(CLOCK_SKEW_TASK, {
+        let clock_skew = Arc::clone(&clock_skew);
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
+        let config = Arc::clone(&config);
+        async move {
+            clock_skew_task(clock_skew, alerts, tasks, &config, &clock_skew_config).await;
+        }
+    }));
+
+    // user-defined alert rules
+    let alert_rules_config = AlertRulesConfig::new();
+    tokio::spawn(tasks.instrument(ALERT_RULES_TASK, {
+        let shared_states = shared_states.clone();
+        let alerts = alerts.clone();
+        let tasks = tasks.clone();
+        async move {
+            alert_rules_task(shared_states, alerts, tasks, &alert_rules_config).await;
+        }
+    }));
+
+    // Scheduled compaction of the persisted histories
+    tokio::spawn(tasks.instrument(JANITOR_TASK, {
+        let status_history = Arc::clone(&status_history);
+        let bridge_changes = Arc::clone(&bridge_changes);
+        let event_log = Arc::clone(&event_log);
+        let user_op_history = Arc::clone(&user_op_history);
+        let config = Arc::clone(&config);
+        let tasks = tasks.clone();
+        async move {
+            janitor_task(
+                status_history,
+                bridge_changes,
+                event_log,
+                user_op_history,
+                tasks,
+                config.janitor_schedule(),
+            )
+            .await;
+        }
+    }));
+
+    // Scheduled usage and bridge reports
+    let reports_config = ReportsConfig::new();
+    subsystems.insert("reports", reports_config.s3_location().is_some());
+    tokio::spawn(tasks.instrument(REPORTS_TASK, {
+        let shared_states = shared_states.clone();
+        let tasks = tasks.clone();
+        async move {
+            reports_task(shared_states, tasks, &reports_config).await;
+        }
+    }));
+
+    let slo_config = Arc::new(SloConfig::new());
+
+    subsystems.insert("chaos", cfg!(feature = "chaos"));
+    subsystems.insert("rpc_api", server_config.rpc_listen_addr().is_some());
+    subsystems.insert("status_registry", config.status_registry_url().is_some());
+    let fingerprint = Arc::new(EnvironmentFingerprint::new(
+        config.network_name(),
+        subsystems,
+        upstreams,
+    ));
+    fingerprint.log();
 
     let app = Router::new()
         .route(
             "/api/status",
-            get(move || get_network_status(Arc::clone(&shared_state))),
+            get({
+                let events = events.clone();
+                let translations = translations.clone();
+                move |headers: HeaderMap, query: Query<NetworkStatusQuery>| {
+                    get_network_status(
+                        headers,
+                        query,
+                        Arc::clone(&shared_state),
+                        events,
+                        translations,
+                    )
+                }
+            })
+            .layer(middleware::from_fn_with_state(
+                (tasks.clone(), NETWORK_STATUS_TASK),
+                require_max_age,
+            )),
+        )
+        .route(
+            "/api/status/history",
+            get({
+                let status_history = Arc::clone(&status_history);
+                let annotations = annotations.clone();
+                move |query: Query<StatusHistoryQuery>| {
+                    get_status_history(
+                        query,
+                        Arc::clone(&status_history),
+                        status_history_replica,
+                        annotations,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/api/status/annotations",
+            get({
+                let annotations = annotations.clone();
+                move |query: Query<AnnotationsQuery>| get_annotations(query, annotations)
+            }),
+        )
+        .route("/api/chain_info", get(move || get_chain_info(chain_info)))
+        .route(
+            "/api/slo",
+            get({
+                let status_history = Arc::clone(&status_history);
+                let bridge_changes = Arc::clone(&bridge_changes);
+                move || {
+                    get_slos(
+                        Arc::clone(&status_history),
+                        Arc::clone(&bridge_changes),
+                        Arc::clone(&slo_config),
+                    )
+                }
+            }),
         )
         .route(
             "/api/balances",
-            get(move || get_wallets_with_balances(paymaster_wallets)),
+            get(move || get_wallets_with_balances(paymaster_wallets))
+                .layer(middleware::from_fn_with_state(
+                    (tasks.clone(), WALLET_BALANCES_TASK),
+                    require_max_age,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    WALLET_BALANCES_TASK,
+                    add_degraded_field,
+                )),
+        )
+        .route(
+            "/api/balances/history",
+            get(move |query: Query<BalanceHistoryQuery>| {
+                get_balance_history(query, Arc::clone(&balance_history))
+            }),
+        )
+        .route(
+            "/api/bridge/deposits/by_txid/:txid",
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                move |txid: Path<String>| get_deposit_by_txid(txid, bridge_state)
+            }),
+        )
+        .route(
+            "/api/bridge/graph/:deposit_txid",
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                move |txid: Path<String>| get_bridge_graph(txid, bridge_state)
+            }),
+        )
+        .route(
+            "/api/bridge/withdrawals/by_address/:address",
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                move |address: Path<String>| get_withdrawals_by_address(address, bridge_state)
+            }),
+        )
+        .route(
+            "/api/bridge/liability",
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                move || get_bridge_liability(bridge_state)
+            }),
+        )
+        .route(
+            "/api/bridge/anomalies",
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                move || get_bridge_anomalies(bridge_state, bridge_denomination_sats)
+            }),
+        )
+        .route(
+            "/api/bridge/volume",
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                move |query: Query<BridgeVolumeQuery>| get_bridge_volume(query, bridge_state)
+            }),
+        )
+        .route(
+            "/api/bridge/watchlist",
+            get(move || get_bridge_watchlist(Arc::clone(&watchlist))),
         )
         .route(
             "/api/bridge_status",
-            get(move || get_bridge_status(Arc::clone(&bridge_state))),
+            get({
+                let bridge_state = Arc::clone(&bridge_state);
+                let bridge_changes = Arc::clone(&bridge_changes);
+                move |query: Query<BridgeStatusQuery>| {
+                    get_bridge_status(
+                        query,
+                        Arc::clone(&bridge_state),
+                        Arc::clone(&bridge_changes),
+                    )
+                }
+            })
+            .layer(middleware::from_fn(select_fields))
+            .layer(middleware::from_fn_with_state(
+                (tasks.clone(), BRIDGE_STATUS_TASK),
+                require_max_age,
+            ))
+            .layer(middleware::from_fn_with_state(
+                BRIDGE_STATUS_TASK,
+                add_degraded_field,
+            )),
+        )
+        .route(
+            "/api/bundler_stats",
+            get(move || get_bundler_stats(Arc::clone(&bundler_stats)))
+                .layer(middleware::from_fn_with_state(
+                    (tasks.clone(), BUNDLER_STATS_TASK),
+                    require_max_age,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    BUNDLER_STATS_TASK,
+                    add_degraded_field,
+                )),
+        )
+        .route(
+            "/api/bundles",
+            get({
+                let bundle_analytics = Arc::clone(&bundle_analytics);
+                move || get_bundle_analytics(bundle_analytics)
+            }),
+        )
+        .route(
+            "/api/bundles/range",
+            get({
+                let bundle_analytics = Arc::clone(&bundle_analytics);
+                move |query: Query<BundleRangeQuery>| {
+                    get_bundle_range(query, Arc::clone(&bundle_analytics), bundles_table.clone())
+                }
+            }),
+        )
+        .route(
+            "/api/bundles/ops_per_minute",
+            get(move |query: Query<OpsPerMinuteQuery>| {
+                get_ops_per_minute(query, Arc::clone(&bundle_analytics))
+            }),
+        )
+        .route(
+            "/api/bridge/deposits/:txid/timeline",
+            get({
+                let bridge_changes = Arc::clone(&bridge_changes);
+                move |txid: Path<String>, query: Query<TimelineQuery>| {
+                    get_deposit_timeline(txid, query, Arc::clone(&bridge_changes))
+                }
+            }),
+        )
+        .route(
+            "/api/bridge/changes",
+            get(move |query: Query<BridgeChangesQuery>| {
+                get_bridge_changes(query, Arc::clone(&bridge_changes))
+            }),
+        )
+        .route(
+            "/api/accounts/export",
+            get({
+                let shared_activity_stats = Arc::clone(&shared_activity_stats);
+                let activity_monitoring_config = Arc::clone(&activity_monitoring_config);
+                move |query: Query<AccountsExportQuery>| {
+                    get_accounts_export(
+                        query,
+                        Arc::clone(&shared_activity_stats),
+                        Arc::clone(&activity_monitoring_config),
+                    )
+                }
+            }),
+        )
+        .route(
+            "/api/proxy/user_ops",
+            get({
+                let proxy_explorer_client = proxy_explorer_client.clone();
+                let proxy_cache = proxy_cache.clone();
+                let activity_monitoring_config = Arc::clone(&activity_monitoring_config);
+                move |query: Query<ProxyQuery>| {
+                    get_explorer_proxy(
+                        ProxiedEndpoint::UserOps,
+                        query,
+                        proxy_explorer_client,
+                        proxy_cache,
+                        activity_monitoring_config,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/api/proxy/accounts",
+            get({
+                let proxy_explorer_client = proxy_explorer_client.clone();
+                let activity_monitoring_config = Arc::clone(&activity_monitoring_config);
+                move |query: Query<ProxyQuery>| {
+                    get_explorer_proxy(
+                        ProxiedEndpoint::Accounts,
+                        query,
+                        proxy_explorer_client,
+                        proxy_cache,
+                        activity_monitoring_config,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/api/paymasters/:address/report",
+            get(
+                move |address: Path<String>, query: Query<PaymasterReportQuery>| {
+                    get_paymaster_report(
+                        address,
+                        query,
+                        explorer_client,
+                        activity_monitoring_config,
+                    )
+                },
+            ),
+        )
+        .route(
+            "/api/activity/heatmap",
+            get({
+                let shared_activity_stats = Arc::clone(&shared_activity_stats);
+                move || get_activity_heatmap(shared_activity_stats)
+            }),
         )
         .route(
             "/api/activity_stats",
-            get(move || get_activity_stats(Arc::clone(&shared_activity_stats))),
+            get(move || get_activity_stats(Arc::clone(&shared_activity_stats)))
+                .layer(middleware::from_fn(select_fields))
+                .layer(middleware::from_fn_with_state(
+                    (tasks.clone(), ACTIVITY_STATS_TASK),
+                    require_max_age,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    ACTIVITY_STATS_TASK,
+                    add_degraded_field,
+                )),
         )
+        .route(
+            "/api/alerts",
+            get({
+                let alerts = alerts.clone();
+                move || get_alerts(alerts)
+            }),
+        )
+        .route(
+            "/api/incidents",
+            get({
+                let alerts = alerts.clone();
+                let translations = translations.clone();
+                move |headers: HeaderMap| get_incidents(headers, alerts, translations)
+            }),
+        )
+        .route(
+            "/api/alerts/push/public_key",
+            get({
+                let push = push.clone();
+                move || get_push_public_key(push)
+            }),
+        )
+        .route(
+            "/api/alerts/push/subscribe",
+            post({
+                let push = push.clone();
+                let api_auth = api_auth.clone();
+                move |headers: HeaderMap, subscription: Json<SubscriptionInfo>| {
+                    post_push_subscription(headers, api_auth, push, subscription)
+                }
+            }),
+        )
+        .route(
+            "/api/alerts/push/unsubscribe",
+            post({
+                let api_auth = api_auth.clone();
+                move |headers: HeaderMap, request: Json<PushUnsubscribeRequest>| {
+                    post_push_unsubscribe(headers, api_auth, push, request)
+                }
+            }),
+        )
+        .route(
+            "/api/events/export",
+            get(move |query: Query<EventExportQuery>| {
+                get_event_export(query, Arc::clone(&event_log))
+            }),
+        )
+        .route("/api/events", get(move || get_events(events)))
+        .route(
+            "/api/overview",
+            get({
+                let shared_states = shared_states.clone();
+                let tasks = tasks.clone();
+                let translations = translations.clone();
+                move |headers: HeaderMap| get_overview(headers, shared_states, tasks, translations)
+            }),
+        )
+        .route(
+            "/api/tasks",
+            get({
+                let tasks = tasks.clone();
+                move || get_tasks(tasks)
+            }),
+        )
+        .route(
+            "/api/diagnostics/runtime",
+            get({
+                let tasks = tasks.clone();
+                let clock_skew = Arc::clone(&clock_skew);
+                move || get_runtime_diagnostics(tasks, Arc::clone(&clock_skew), started_at)
+            }),
+        )
+        .route(
+            "/api/diagnostics/clock",
+            get(move || get_clock_skew(Arc::clone(&clock_skew))),
+        )
+        .route(
+            "/api/version",
+            get(move || get_version(Arc::clone(&fingerprint))),
+        )
+        .route(
+            "/metrics",
+            get({
+                let tasks = tasks.clone();
+                let bundle_analytics = Arc::clone(&bundle_analytics);
+                move || {
+                    get_metrics(
+                        tasks,
+                        Arc::clone(&status_history),
+                        Arc::clone(&bundle_analytics),
+                    )
+                }
+            }),
+        )
+        .route(
+            "/api/admin/consumers",
+            get({
+                let admin_auth = admin_auth.clone();
+                let consumer_meter = consumer_meter.clone();
+                move |headers: HeaderMap| get_consumers(headers, admin_auth, consumer_meter)
+            }),
+        )
+        .route(
+            "/api/admin/state_dump",
+            get({
+                let admin_auth = admin_auth.clone();
+                let shared_states = shared_states.clone();
+                move |headers: HeaderMap| {
+                    get_state_dump(headers, admin_auth.clone(), shared_states.clone())
+                }
+            }),
+        )
+        .route(
+            "/api/admin/recompute_stats",
+            post({
+                let admin_auth = admin_auth.clone();
+                move |headers: HeaderMap, query: Query<RecomputeStatsQuery>| {
+                    post_recompute_stats(
+                        headers,
+                        query,
+                        admin_auth,
+                        user_op_history,
+                        user_ops_replica,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/api/admin/alerts/:id/ack",
+            post({
+                let admin_auth = admin_auth.clone();
+                let alerts = alerts.clone();
+                move |id: Path<String>, headers: HeaderMap, request: Json<AcknowledgeRequest>| {
+                    post_alert_acknowledgement(id, headers, request, admin_auth, alerts)
+                }
+            })
+            .delete({
+                let admin_auth = admin_auth.clone();
+                move |id: Path<String>, headers: HeaderMap| {
+                    delete_alert_acknowledgement(id, headers, admin_auth, alerts)
+                }
+            }),
+        )
+        .route(
+            "/api/admin/annotations",
+            post({
+                let admin_auth = admin_auth.clone();
+                move |headers: HeaderMap, request: Json<AnnotationRequest>| {
+                    post_annotation(headers, request, admin_auth, annotations)
+                }
+            }),
+        )
+        .route(
+            "/api/admin/watched",
+            get({
+                let admin_auth = admin_auth.clone();
+                let watched = watched.clone();
+                move |headers: HeaderMap| get_watched_contracts(headers, admin_auth, watched)
+            }),
+        )
+        .route(
+            "/api/admin/watched/entry_points",
+            post({
+                let admin_auth = admin_auth.clone();
+                let watched = watched.clone();
+                move |headers: HeaderMap, request: Json<EntryPointRequest>| {
+                    post_entry_point(headers, request, admin_auth, watched)
+                }
+            }),
+        )
+        .route(
+            "/api/admin/watched/entry_points/:address",
+            delete({
+                let admin_auth = admin_auth.clone();
+                let watched = watched.clone();
+                move |address: Path<String>, headers: HeaderMap| {
+                    delete_entry_point(address, headers, admin_auth, watched)
+                }
+            }),
+        )
+        .route(
+            "/api/admin/watched/bridge_contracts",
+            post({
+                let admin_auth = admin_auth.clone();
+                let watched = watched.clone();
+                move |headers: HeaderMap, contract: Json<BridgeContract>| {
+                    post_bridge_contract(headers, contract, admin_auth, watched)
+                }
+            }),
+        )
+        .route(
+            "/api/admin/watched/bridge_contracts/:address",
+            delete({
+                let admin_auth = admin_auth.clone();
+                move |address: Path<String>, headers: HeaderMap| {
+                    delete_bridge_contract(address, headers, admin_auth, watched)
+                }
+            }),
+        )
+        // Guarded by the admin token like `/healthz/details`, rather than by API token scopes
+        .route(
+            "/admin/log_levels",
+            get({
+                let admin_auth = admin_auth.clone();
+                let log_levels = log_levels.clone();
+                move |headers: HeaderMap| get_log_levels(headers, admin_auth, log_levels)
+            })
+            .post({
+                let admin_auth = admin_auth.clone();
+                let log_levels = log_levels.clone();
+                move |headers: HeaderMap, request: Json<LogLevelsRequest>| {
+                    post_log_levels(headers, request, admin_auth, log_levels)
+                }
+            })
+            .delete({
+                let admin_auth = admin_auth.clone();
+                move |headers: HeaderMap| delete_log_levels(headers, admin_auth, log_levels)
+            }),
+        )
+        .route(
+            "/admin/top_ups",
+            get({
+                let admin_auth = admin_auth.clone();
+                move |headers: HeaderMap| get_top_ups(headers, admin_auth, top_up_audit)
+            }),
+        )
+        .route(
+            "/admin/uptime_pings",
+            get({
+                let admin_auth = admin_auth.clone();
+                let uptime_pings = uptime_pings.clone();
+                move |headers: HeaderMap| get_uptime_pings(headers, admin_auth, uptime_pings)
+            }),
+        )
+        .route(
+            "/admin/uptime_pings/:task",
+            put({
+                let admin_auth = admin_auth.clone();
+                let uptime_pings = uptime_pings.clone();
+                let tasks = tasks.clone();
+                move |task: Path<String>, headers: HeaderMap, request: Json<UptimePingRequest>| {
+                    put_uptime_ping(task, headers, request, admin_auth, uptime_pings, tasks)
+                }
+            })
+            .delete({
+                let admin_auth = admin_auth.clone();
+                move |task: Path<String>, headers: HeaderMap| {
+                    delete_uptime_ping(task, headers, admin_auth, uptime_pings)
+                }
+            }),
+        )
+        // Authenticated by the operator signature rather than by API token scopes
+        .route(
+            "/ingest/bridge/heartbeat",
+            post(move |request: Json<HeartbeatRequest>| {
+                post_operator_heartbeat(request, heartbeats)
+            }),
+        )
+        .route("/healthz", get(get_health))
+        .route(
+            "/healthz/details",
+            get({
+                let tasks = tasks.clone();
+                let shared_states = shared_states.clone();
+                move |headers: HeaderMap| {
+                    get_health_details(
+                        headers,
+                        admin_auth.clone(),
+                        shared_states.clone(),
+                        tasks.clone(),
+                    )
+                }
+            }),
+        )
+        .merge(canary_routes(&CanaryConfig::new(), api_auth.clone()))
+        .layer(middleware::from_fn_with_state(
+            network_id,
+            add_network_field,
+        ))
+        .layer(middleware::from_fn_with_state(
+            server_config.txid_byte_order(),
+            with_txid_byte_order,
+        ))
+        .layer(middleware::from_fn_with_state(api_auth, require_api_token))
+        .layer(middleware::from_fn_with_state(
+            consumer_meter,
+            meter_consumers,
+        ))
         .layer(cors);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    info!(%addr, "Server running at http://");
+    // Show fetched data from the first request rather than everything offline.
+    // The monitoring tasks are already running their first refresh in parallel.
+    if server_config.warm_up_timeout_s() > 0 {
+        let warm_up_timeout = Duration::from_secs(server_config.warm_up_timeout_s());
+        let pending = tasks
+            .wait_for_first_refresh(&WARM_UP_TASKS, warm_up_timeout)
+            .await;
+        if pending.is_empty() {
+            info!("Warm-up complete");
+        } else {
+            warn!(
+                ?pending,
+                "Warm-up timed out, serving before the first refresh"
+            );
+        }
+    }
+
+    // One server per configured address, e.g. separate IPv4 and IPv6 listeners
+    let mut servers = JoinSet::new();
+    for addr in server_config.listen_addrs() {
+        let listener = TcpListener::bind(addr).await.unwrap();
+        info!(%addr, "Server running at http://");
+        // Peer addresses identify the API consumers without a token
+        let app = app
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>();
+        servers.spawn(axum::serve(listener, app).into_future());
+    }
+    if let Some(addr) = server_config.rpc_listen_addr() {
+        let handle = rpc_api::start_rpc_server(addr, shared_states)
+            .await
+            .unwrap();
+        servers.spawn(async move {
+            handle.stopped().await;
+            Ok(())
+        });
+    }
 
-    let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    while let Some(result) = servers.join_next().await {
+        result.expect("server task panicked").unwrap();
+    }
 }
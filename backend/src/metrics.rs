@@ -0,0 +1,128 @@
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse};
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    bundles::SharedBundleAnalytics,
+    status_history::SharedStatusHistory,
+    tasks::{TaskRegistry, TaskStatus},
+};
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Appends a gauge or counter with one sample per task
+fn write_task_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    tasks: &BTreeMap<&'static str, TaskStatus>,
+    value: impl Fn(&TaskStatus) -> Option<f64>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (task, status) in tasks {
+        if let Some(value) = value(status) {
+            let _ = writeln!(out, "{}{{task=\"{}\"}} {}", name, task, value);
+        }
+    }
+}
+
+/// Renders the refresh bookkeeping of every task in the Prometheus text format
+fn render_task_metrics(tasks: &BTreeMap<&'static str, TaskStatus>) -> String {
+    let mut out = String::new();
+    write_task_metric(
+        &mut out,
+        "dashboard_task_interval_seconds",
+        "gauge",
+        "Expected time between refresh cycles.",
+        tasks,
+        |task| Some(task.interval_s() as f64),
+    );
+    write_task_metric(
+        &mut out,
+        "dashboard_task_refreshes_total",
+        "counter",
+        "Completed refresh cycles.",
+        tasks,
+        |task| Some(task.refreshes() as f64),
+    );
+    write_task_metric(
+        &mut out,
+        "dashboard_task_last_refresh_duration_seconds",
+        "gauge",
+        "Duration of the last refresh cycle.",
+        tasks,
+        |task| task.last_duration_ms().map(|ms| ms as f64 / 1000.0),
+    );
+    write_task_metric(
+        &mut out,
+        "dashboard_task_avg_refresh_duration_seconds",
+        "gauge",
+        "Average duration of the recent refresh cycles.",
+        tasks,
+        |task| task.avg_duration_ms().map(|ms| ms as f64 / 1000.0),
+    );
+    out
+}
+
+/// Appends the number of history records dropped by the history writer
+fn render_history_metrics(out: &mut String, dropped_writes: u64) {
+    let name = "dashboard_history_writes_dropped_total";
+    let _ = writeln!(
+        out,
+        "# HELP {} History records not persisted because the write buffer was full.",
+        name
+    );
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, dropped_writes);
+}
+
+/// Appends the number of bundles dropped by the bundle store, e.g. ClickHouse
+fn render_bundle_metrics(out: &mut String, dropped_writes: u64) {
+    let name = "dashboard_bundle_writes_dropped_total";
+    let _ = writeln!(
+        out,
+        "# HELP {} Bundles not stored because the store fell behind.",
+        name
+    );
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, dropped_writes);
+}
+
+/// Handler for `/metrics`, scraped by Prometheus
+pub async fn get_metrics(
+    tasks: TaskRegistry,
+    history: SharedStatusHistory,
+    bundles: SharedBundleAnalytics,
+) -> impl IntoResponse {
+    let mut body = render_task_metrics(&tasks.snapshot().await);
+    render_history_metrics(&mut body, history.read().await.dropped_writes());
+    render_bundle_metrics(&mut body, bundles.read().await.dropped_writes());
+    ([(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_task_metrics;
+    use crate::tasks::TaskRegistry;
+
+    #[tokio::test]
+    async fn test_render_task_metrics() {
+        let tasks = TaskRegistry::default();
+        tasks.register("bridge_status", 120).await;
+        tasks.register("network_status", 10).await;
+        tasks.start_refresh("network_status").await;
+        tasks.record_refresh("network_status").await;
+
+        let metrics = render_task_metrics(&tasks.snapshot().await);
+        assert!(metrics.contains("# TYPE dashboard_task_refreshes_total counter\n"));
+        assert!(metrics.contains("dashboard_task_interval_seconds{task=\"bridge_status\"} 120\n"));
+        assert!(metrics.contains("dashboard_task_refreshes_total{task=\"network_status\"} 1\n"));
+        assert!(metrics
+            .contains("dashboard_task_last_refresh_duration_seconds{task=\"network_status\"}"));
+        // Tasks that never refreshed have no duration
+        assert!(!metrics
+            .contains("dashboard_task_last_refresh_duration_seconds{task=\"bridge_status\"}"));
+    }
+}
@@ -0,0 +1,599 @@
+use axum::{
+    extract::Query,
+    http::{header::CONTENT_LANGUAGE, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::http_client::HttpClient;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    sync::Arc,
+};
+use tokio::{
+    sync::RwLock,
+    time::{timeout, Instant},
+};
+use tracing::{info, warn};
+
+use crate::{
+    alerts::{Alerts, Severity},
+    checks::{call_rpc_status, CheckSpec, ComponentChecks},
+    config::NetworkConfig,
+    events::{next_event, EventBus, MonitorEvent},
+    i18n::Translations,
+    polling::AdaptiveInterval,
+    registry::StatusRegistry,
+    retry_policy::ExponentialBackoff,
+    status_rules::FailureCounter,
+    tasks::{TaskRegistry, NETWORK_STATUS_TASK},
+    utils::{create_rpc_client, parse_long_poll_wait},
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Online,
+    Offline,
+    /// Online, but the chain tip has not advanced for a while
+    Stalled,
+}
+
+impl Status {
+    pub(crate) fn from_online(online: bool) -> Self {
+        if online {
+            Status::Online
+        } else {
+            Status::Offline
+        }
+    }
+}
+
+/// Machine-readable kind of a status check failure
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The component did not answer in time
+    Timeout,
+    /// The component could not be connected to or resolved
+    Unreachable,
+    /// The component answered with an error
+    RpcError,
+    /// The component answered, but not as its status rule expects
+    UnexpectedResponse,
+    /// The chain tip stopped advancing
+    Stalled,
+    /// Not checked, the daily request budget of the upstream is exhausted
+    BudgetExhausted,
+}
+
+/// Why a component is not online
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatusReason {
+    pub reason: FailureKind,
+    /// Error of the latest failed check
+    pub last_error: String,
+}
+
+impl StatusReason {
+    pub fn new(reason: FailureKind, last_error: impl Into<String>) -> Self {
+        Self {
+            reason,
+            last_error: last_error.into(),
+        }
+    }
+}
+
+impl fmt::Display for StatusReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.last_error)
+    }
+}
+
+/// Status rules report the expectation the response broke
+impl From<String> for StatusReason {
+    fn from(last_error: String) -> Self {
+        Self::new(FailureKind::UnexpectedResponse, last_error)
+    }
+}
+
+/// Components with a dedicated `NetworkStatus` field; checks declared in
+/// config cannot use these names
+pub const BUILTIN_COMPONENTS: [&str; 3] = ["batch_producer", "rpc_endpoint", "bundler_endpoint"];
+
+/// Alert raised while the batch producer is stalled
+const BATCH_PRODUCER_STALLED_ALERT: &str = "batch_producer_stalled";
+
+/// Detects a chain tip that stopped advancing
+#[derive(Debug)]
+struct StallDetector {
+    /// Number of polls without progress after which the tip is stalled
+    threshold_polls: usize,
+    /// Tip heights of the most recent polls
+    tip_heights: VecDeque<u64>,
+}
+
+impl StallDetector {
+    fn new(threshold_polls: usize) -> Self {
+        Self {
+            threshold_polls: threshold_polls.max(2),
+            tip_heights: VecDeque::new(),
+        }
+    }
+
+    /// Records the tip height of a poll, returning whether the tip is stalled
+    fn observe(&mut self, tip_height: Option<u64>) -> bool {
+        if let Some(height) = tip_height {
+            self.tip_heights.push_back(height);
+            while self.tip_heights.len() > self.threshold_polls {
+                self.tip_heights.pop_front();
+            }
+        }
+
+        self.tip_heights.len() == self.threshold_polls
+            && self
+                .tip_heights
+                .iter()
+                .all(|height| Some(height) == self.tip_heights.front())
+    }
+
+    fn last_tip_height(&self) -> Option<u64> {
+        self.tip_heights.back().copied()
+    }
+}
+
+/// Version strings reported by each deployed client, `None` if unavailable
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClientVersions {
+    sequencer: Option<String>,
+    reth: Option<String>,
+    bundler: Option<String>,
+}
+
+/// Peer and sync state of the reth node, `None` fields were unavailable
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RethSyncStatus {
+    peer_count: Option<u64>,
+    syncing: Option<bool>,
+    current_block: Option<u64>,
+    highest_block: Option<u64>,
+}
+
+impl RethSyncStatus {
+    /// Number of blocks the node is behind its peers, `None` if unknown
+    pub fn lag_blocks(&self) -> Option<u64> {
+        match (self.syncing, self.current_block, self.highest_block) {
+            (Some(false), _, _) => Some(0),
+            (_, Some(current), Some(highest)) => Some(highest.saturating_sub(current)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NetworkStatus {
+    pub batch_producer: Status,
+    pub rpc_endpoint: Status,
+    pub bundler_endpoint: Status,
+    pub versions: ClientVersions,
+    pub reth_sync: RethSyncStatus,
+    /// Status of the checks declared in `STATUS_CHECKS`, by name
+    pub components: BTreeMap<String, Status>,
+    /// Why components are not online, by name, the dedicated ones included
+    #[serde(default)]
+    pub reasons: BTreeMap<String, StatusReason>,
+}
+
+impl Default for NetworkStatus {
+    fn default() -> Self {
+        Self {
+            batch_producer: Status::Offline,
+            rpc_endpoint: Status::Offline,
+            bundler_endpoint: Status::Offline,
+            versions: ClientVersions::default(),
+            reth_sync: RethSyncStatus::default(),
+            components: BTreeMap::new(),
+            reasons: BTreeMap::new(),
+        }
+    }
+}
+
+/// Shared Network State
+pub type SharedNetworkState = Arc<RwLock<NetworkStatus>>;
+
+/// Queries a client version, trying each method in turn until one succeeds
+async fn get_client_version(client: &HttpClient, methods: &[&str]) -> Option<String> {
+    for method in methods {
+        let response: Result<serde_json::Value, _> = client.request(method, Vec::<()>::new()).await;
+        match response {
+            Ok(serde_json::Value::String(version)) => return Some(version),
+            // Structured version info is passed on as is
+            Ok(version) => return Some(version.to_string()),
+            Err(e) => {
+                warn!(error = %e, %method, "Client version query failed");
+            }
+        }
+    }
+    None
+}
+
+/// Parses a hex quantity such as `0x1a`
+pub(crate) fn parse_hex_quantity(value: &serde_json::Value) -> Option<u64> {
+    value
+        .as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .and_then(|s| u64::from_str_radix(s, 16).ok())
+}
+
+/// Parses an `eth_syncing` response, which is `false` once the node caught up
+fn parse_eth_syncing(response: &serde_json::Value) -> RethSyncStatus {
+    match response {
+        serde_json::Value::Bool(syncing) => RethSyncStatus {
+            syncing: Some(*syncing),
+            ..Default::default()
+        },
+        progress => RethSyncStatus {
+            syncing: Some(true),
+            current_block: progress.get("currentBlock").and_then(parse_hex_quantity),
+            highest_block: progress.get("highestBlock").and_then(parse_hex_quantity),
+            ..Default::default()
+        },
+    }
+}
+
+/// Queries peer count and sync progress of the reth node
+async fn get_reth_sync_status(client: &HttpClient) -> RethSyncStatus {
+    let syncing: Result<serde_json::Value, _> =
+        client.request("eth_syncing", Vec::<()>::new()).await;
+    let mut status = match syncing {
+        Ok(response) => parse_eth_syncing(&response),
+        Err(e) => {
+            warn!(error = %e, "`eth_syncing` query failed");
+            RethSyncStatus::default()
+        }
+    };
+
+    let peer_count: Result<serde_json::Value, _> =
+        client.request("net_peerCount", Vec::<()>::new()).await;
+    match peer_count {
+        Ok(count) => status.peer_count = parse_hex_quantity(&count),
+        Err(e) => warn!(error = %e, "`net_peerCount` query failed"),
+    }
+
+    status
+}
+
+/// Checks of the RPC and bundler endpoints, run alongside the checks declared in config
+fn builtin_checks(config: &NetworkConfig) -> [CheckSpec; 2] {
+    let rules = config.status_rules();
+    [
+        CheckSpec::Rpc {
+            name: "rpc_endpoint".to_string(),
+            url: config.rpc_url().to_string(),
+            method: "strata_syncStatus".to_string(),
+            rule: rules.rpc_endpoint.clone(),
+        },
+        CheckSpec::Http {
+            name: "bundler_endpoint".to_string(),
+            url: config.bundler_url().to_string(),
+            rule: rules.bundler_endpoint.clone(),
+        },
+    ]
+}
+
+/// Status refresh interval in seconds
+const STATUS_REFETCH_INTERVAL_S: u64 = 10;
+
+/// Periodically fetches real statuses
+pub async fn fetch_statuses_task(
+    state: SharedNetworkState,
+    events: EventBus,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    config: &NetworkConfig,
+) {
+    info!("Fetching statuses...");
+    tasks
+        .register(NETWORK_STATUS_TASK, STATUS_REFETCH_INTERVAL_S)
+        .await;
+    let mut interval = AdaptiveInterval::new(NETWORK_STATUS_TASK, STATUS_REFETCH_INTERVAL_S);
+    let rpc_client = create_rpc_client(config.rpc_url());
+    let reth_client = create_rpc_client(config.reth_url());
+    let bundler_client = create_rpc_client(config.bundler_rpc_url());
+    let http_client = reqwest::Client::new();
+    let retry_policy =
+        ExponentialBackoff::new(config.max_retries(), config.total_retry_time(), 1.5);
+    let rules = config.status_rules();
+    let mut batch_producer_failures = FailureCounter::default();
+    let build_check =
+        |spec: &CheckSpec| spec.build(&http_client, retry_policy, config.max_retries());
+    let mut checks = ComponentChecks::default();
+    for spec in builtin_checks(config).iter().chain(config.status_checks()) {
+        checks.add(build_check(spec), spec.interval_s());
+    }
+    let mut registry = config.status_registry_url().map(|url| {
+        StatusRegistry::new(
+            url,
+            config.status_registry_refresh_s(),
+            config.status_checks(),
+        )
+    });
+    let mut batch_producer_stalls = StallDetector::new(config.stall_threshold_polls());
+    // Kept while the failures are debounced, to report why once offline
+    let mut batch_producer_failure: Option<StatusReason> = None;
+
+    loop {
+        interval.tick().await;
+        tasks.start_refresh(NETWORK_STATUS_TASK).await;
+
+        let previous = state.read().await.clone();
+        let sync_status = match call_rpc_status(
+            &rpc_client,
+            "strata_syncStatus",
+            retry_policy,
+            config.max_retries(),
+            &rules.batch_producer,
+        )
+        .await
+        {
+            Ok(status) => Some(status),
+            Err(reason) => {
+                warn!(%reason, "`strata_syncStatus` check failed");
+                batch_producer_failure = Some(reason);
+                None
+            }
+        };
+        let batch_producer_online = batch_producer_failures.observe(
+            sync_status.is_some(),
+            previous.batch_producer != Status::Offline,
+            rules.batch_producer.failure_threshold(),
+        );
+        let tip_height = sync_status
+            .as_ref()
+            .and_then(|status| status.get("tip_height"))
+            .and_then(serde_json::Value::as_u64);
+        let batch_producer = if !batch_producer_online {
+            Status::Offline
+        } else if batch_producer_stalls.observe(tip_height) {
+            Status::Stalled
+        } else {
+            Status::Online
+        };
+        let batch_producer_reason = match batch_producer {
+            Status::Online => {
+                batch_producer_failure = None;
+                None
+            }
+            Status::Offline => batch_producer_failure.clone(),
+            Status::Stalled => Some(StatusReason::new(
+                FailureKind::Stalled,
+                format!(
+                    "tip height stuck at {}",
+                    batch_producer_stalls.last_tip_height().unwrap_or_default()
+                ),
+            )),
+        };
+
+        if batch_producer == Status::Stalled {
+            alerts
+                .raise(
+                    BATCH_PRODUCER_STALLED_ALERT.to_string(),
+                    Severity::Critical,
+                    format!(
+                        "Tip height stuck at {} for {} polls",
+                        batch_producer_stalls.last_tip_height().unwrap_or_default(),
+                        config.stall_threshold_polls()
+                    ),
+                )
+                .await;
+        } else {
+            alerts.resolve(BATCH_PRODUCER_STALLED_ALERT).await;
+        }
+
+        if let Some(registry) = registry.as_mut() {
+            registry
+                .refresh(&http_client, &mut checks, build_check, Instant::now())
+                .await;
+        }
+        let components = checks.run(Instant::now()).await;
+        let mut reasons = checks.reasons();
+        if let Some(reason) = batch_producer_reason {
+            reasons.insert("batch_producer".to_string(), reason);
+        }
+        let component = |name: &str| components.get(name).cloned().unwrap_or(Status::Offline);
+        let rpc_endpoint = component("rpc_endpoint");
+        let bundler_endpoint = component("bundler_endpoint");
+        let versions = ClientVersions {
+            sequencer: get_client_version(
+                &rpc_client,
+                &["strata_clientVersion", "web3_clientVersion"],
+            )
+            .await,
+            reth: get_client_version(&reth_client, &["web3_clientVersion"]).await,
+            bundler: get_client_version(&bundler_client, &["web3_clientVersion"]).await,
+        };
+        let reth_sync = get_reth_sync_status(&reth_client).await;
+
+        let new_status = NetworkStatus {
+            batch_producer,
+            rpc_endpoint,
+            bundler_endpoint,
+            versions,
+            reth_sync,
+            components: components
+                .iter()
+                .filter(|(name, _)| !BUILTIN_COMPONENTS.contains(&name.as_str()))
+                .map(|(name, status)| (name.clone(), status.clone()))
+                .collect(),
+            reasons,
+        };
+
+        info!(?new_status, "Updated Status");
+
+        let at = Utc::now();
+        events.publish(MonitorEvent::StatusRefreshed {
+            at,
+            status: new_status.clone(),
+        });
+        if new_status != previous {
+            events.publish(MonitorEvent::StatusChanged {
+                at,
+                previous,
+                status: new_status.clone(),
+            });
+        }
+
+        let mut locked_state = state.write().await;
+        *locked_state = new_status;
+        drop(locked_state);
+
+        // Back off while the sequencer fails to answer
+        interval.adapt(sync_status.is_some(), &tasks).await;
+        tasks.record_refresh(NETWORK_STATUS_TASK).await;
+    }
+}
+
+/// Name and status of a component, in the language of the request
+#[derive(Serialize, Debug)]
+pub struct ComponentDisplay {
+    name: String,
+    status: String,
+}
+
+/// Network status along with display strings of its components
+#[derive(Serialize, Debug)]
+pub struct NetworkStatusResponse {
+    #[serde(flatten)]
+    status: NetworkStatus,
+    /// By component, the dedicated ones included
+    display: BTreeMap<String, ComponentDisplay>,
+}
+
+/// Query parameters of the network status endpoint
+#[derive(Deserialize, Debug)]
+pub struct NetworkStatusQuery {
+    /// Long-polling: hold the request until the status changes or this duration,
+    /// e.g. `30s`, elapses
+    wait_for_change: Option<String>,
+}
+
+/// Status published by the next change within `wait`, if any
+async fn wait_for_status_change(
+    events: &EventBus,
+    wait: std::time::Duration,
+) -> Option<NetworkStatus> {
+    let mut events = events.subscribe();
+    let change = async {
+        while let Some(event) = next_event(&mut events, "status_long_poll").await {
+            if let MonitorEvent::StatusChanged { status, .. } = event {
+                return Some(status);
+            }
+        }
+        None
+    };
+    timeout(wait, change).await.ok().flatten()
+}
+
+/// Handler to get the current network status
+pub async fn get_network_status(
+    headers: HeaderMap,
+    Query(query): Query<NetworkStatusQuery>,
+    state: SharedNetworkState,
+    events: EventBus,
+    translations: Translations,
+) -> Result<impl IntoResponse, StatusCode> {
+    let wait = query
+        .wait_for_change
+        .as_deref()
+        .map(|wait| parse_long_poll_wait(wait).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    // The change is published before the shared state is updated
+    let changed = match wait {
+        Some(wait) => wait_for_status_change(&events, wait).await,
+        None => None,
+    };
+    let status = match changed {
+        Some(status) => status,
+        None => state.read().await.clone(),
+    };
+    let localizer = translations.localizer(&headers);
+    let builtin = [
+        ("batch_producer", &status.batch_producer),
+        ("rpc_endpoint", &status.rpc_endpoint),
+        ("bundler_endpoint", &status.bundler_endpoint),
+    ];
+    let display = builtin
+        .into_iter()
+        .chain(
+            status
+                .components
+                .iter()
+                .map(|(name, status)| (name.as_str(), status)),
+        )
+        .map(|(name, component_status)| {
+            let display = ComponentDisplay {
+                name: localizer.component(name),
+                status: localizer.status(component_status),
+            };
+            (name.to_string(), display)
+        })
+        .collect();
+
+    Ok((
+        [(CONTENT_LANGUAGE, localizer.language().to_string())],
+        Json(NetworkStatusResponse { status, display }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_eth_syncing, parse_hex_quantity, RethSyncStatus, StallDetector};
+    use serde_json::json;
+
+    #[test]
+    fn test_stall_detector() {
+        let mut detector = StallDetector::new(3);
+        assert!(!detector.observe(Some(10)));
+        assert!(!detector.observe(Some(10)));
+        assert!(detector.observe(Some(10)));
+        // Failed polls neither confirm nor clear a stall
+        assert!(detector.observe(None));
+        assert!(!detector.observe(Some(11)));
+        assert_eq!(detector.last_tip_height(), Some(11));
+    }
+
+    #[test]
+    fn test_parse_hex_quantity() {
+        assert_eq!(parse_hex_quantity(&json!("0x1a")), Some(26));
+        assert_eq!(parse_hex_quantity(&json!("0x0")), Some(0));
+        assert_eq!(parse_hex_quantity(&json!("26")), None);
+        assert_eq!(parse_hex_quantity(&json!(26)), None);
+    }
+
+    #[test]
+    fn test_parse_eth_syncing() {
+        assert_eq!(
+            parse_eth_syncing(&json!(false)),
+            RethSyncStatus {
+                syncing: Some(false),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            parse_eth_syncing(&json!({
+                "startingBlock": "0x0",
+                "currentBlock": "0x10",
+                "highestBlock": "0x100",
+            })),
+            RethSyncStatus {
+                syncing: Some(true),
+                current_block: Some(16),
+                highest_block: Some(256),
+                ..Default::default()
+            }
+        );
+    }
+}
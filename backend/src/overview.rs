@@ -0,0 +1,260 @@
+use axum::{
+    http::{header::CONTENT_LANGUAGE, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    bridge::{BridgeStatus, ResponsivenessRating},
+    bundler::BundlerStats,
+    i18n::Translations,
+    network::{NetworkStatus, Status},
+    tasks::{TaskRegistry, TaskStatus},
+    SharedStates,
+};
+
+/// Reth lag in blocks at which the sync factor scores 0
+const MAX_RETH_LAG_BLOCKS: u64 = 100;
+
+/// Score at or above which the network is reported healthy
+const HEALTHY_SCORE: u8 = 90;
+
+/// Score at or above which the network is reported degraded rather than unhealthy
+const DEGRADED_SCORE: u8 = 60;
+
+/// Headline state derived from the health score
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OverallStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+/// One input of the health score, for the drill-down
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct HealthFactor {
+    name: &'static str,
+    /// Relative weight in the composite score
+    weight: u32,
+    /// From 0 (down) to 1 (fully healthy)
+    score: f64,
+    detail: String,
+}
+
+/// Composite health of the monitored network
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Overview {
+    /// Weighted average of the factor scores, from 0 to 100
+    score: u8,
+    status: OverallStatus,
+    /// Factors with available data; the others do not count towards the score
+    factors: Vec<HealthFactor>,
+    computed_at: DateTime<Utc>,
+}
+
+fn status_score(status: &Status) -> f64 {
+    match status {
+        Status::Online => 1.0,
+        Status::Stalled => 0.5,
+        Status::Offline => 0.0,
+    }
+}
+
+fn component_factor(name: &'static str, weight: u32, status: &Status) -> HealthFactor {
+    HealthFactor {
+        name,
+        weight,
+        score: status_score(status),
+        detail: format!("{:?}", status).to_lowercase(),
+    }
+}
+
+/// Scores the current state of the network.
+///
+/// Core components weigh the most, then lag metrics (reth sync, operator
+/// responsiveness, bundling) and the staleness of the monitoring tasks themselves.
+pub fn health_overview(
+    network: &NetworkStatus,
+    bridge: &BridgeStatus,
+    bundler: &BundlerStats,
+    tasks: &BTreeMap<&'static str, TaskStatus>,
+    now: DateTime<Utc>,
+) -> Overview {
+    let mut factors = vec![
+        component_factor("batch_producer", 30, &network.batch_producer),
+        component_factor("rpc_endpoint", 20, &network.rpc_endpoint),
+        component_factor("bundler_endpoint", 15, &network.bundler_endpoint),
+    ];
+
+    if !network.components.is_empty() {
+        let online = network
+            .components
+            .values()
+            .filter(|status| **status == Status::Online)
+            .count();
+        factors.push(HealthFactor {
+            name: "components",
+            weight: 10,
+            score: network.components.values().map(status_score).sum::<f64>()
+                / network.components.len() as f64,
+            detail: format!("{}/{} online", online, network.components.len()),
+        });
+    }
+
+    if let Some(lag) = network.reth_sync.lag_blocks() {
+        factors.push(HealthFactor {
+            name: "reth_sync",
+            weight: 10,
+            score: 1.0 - lag.min(MAX_RETH_LAG_BLOCKS) as f64 / MAX_RETH_LAG_BLOCKS as f64,
+            detail: format!("{} blocks behind", lag),
+        });
+    }
+
+    if !bridge.operators.is_empty() {
+        let ratings = bridge
+            .operators
+            .iter()
+            .map(|operator| &operator.responsiveness.rating);
+        let score = ratings
+            .clone()
+            .map(|rating| match rating {
+                ResponsivenessRating::Healthy => 1.0,
+                ResponsivenessRating::Slow => 0.5,
+                ResponsivenessRating::Unresponsive => 0.0,
+            })
+            .sum::<f64>()
+            / bridge.operators.len() as f64;
+        let healthy = ratings
+            .filter(|rating| **rating == ResponsivenessRating::Healthy)
+            .count();
+        factors.push(HealthFactor {
+            name: "bridge_operators",
+            weight: 10,
+            score,
+            detail: format!("{}/{} responsive", healthy, bridge.operators.len()),
+        });
+    }
+
+    let stalled = bundler.is_bundling_stalled();
+    factors.push(HealthFactor {
+        name: "bundling",
+        weight: 5,
+        score: if stalled { 0.0 } else { 1.0 },
+        detail: if stalled { "stalled" } else { "ok" }.to_string(),
+    });
+
+    if !tasks.is_empty() {
+        let fresh = tasks.values().filter(|task| !task.is_stale(now)).count();
+        factors.push(HealthFactor {
+            name: "task_freshness",
+            weight: 10,
+            score: fresh as f64 / tasks.len() as f64,
+            detail: format!("{}/{} fresh", fresh, tasks.len()),
+        });
+    }
+
+    let total_weight: u32 = factors.iter().map(|factor| factor.weight).sum();
+    let weighted: f64 = factors
+        .iter()
+        .map(|factor| factor.weight as f64 * factor.score)
+        .sum();
+    let score = (100.0 * weighted / total_weight as f64).round() as u8;
+    let status = if score >= HEALTHY_SCORE {
+        OverallStatus::Healthy
+    } else if score >= DEGRADED_SCORE {
+        OverallStatus::Degraded
+    } else {
+        OverallStatus::Unhealthy
+    };
+
+    Overview {
+        score,
+        status,
+        factors,
+        computed_at: now,
+    }
+}
+
+/// Health overview along with the headline in the language of the request
+#[derive(Serialize, Debug)]
+pub struct OverviewResponse {
+    #[serde(flatten)]
+    overview: Overview,
+    status_display: String,
+}
+
+/// Handler returning the composite health score with its factors
+pub async fn get_overview(
+    headers: HeaderMap,
+    states: SharedStates,
+    tasks: TaskRegistry,
+    translations: Translations,
+) -> impl IntoResponse {
+    let network = states.network.read().await.clone();
+    let bridge = states.bridge.read().await.clone();
+    let bundler = states.bundler.read().await.clone();
+    let tasks = tasks.snapshot().await;
+
+    let overview = health_overview(&network, &bridge, &bundler, &tasks, Utc::now());
+    let localizer = translations.localizer(&headers);
+    let status_display = localizer.overall_status(&overview.status);
+    (
+        [(CONTENT_LANGUAGE, localizer.language().to_string())],
+        Json(OverviewResponse {
+            overview,
+            status_display,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{health_overview, OverallStatus};
+    use crate::{
+        bridge::BridgeStatus,
+        bundler::BundlerStats,
+        network::{NetworkStatus, Status},
+    };
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_health_overview() {
+        let now = Utc::now();
+        let mut network = NetworkStatus {
+            batch_producer: Status::Online,
+            rpc_endpoint: Status::Online,
+            bundler_endpoint: Status::Online,
+            ..Default::default()
+        };
+        let (bridge, bundler) = (BridgeStatus::default(), BundlerStats::default());
+
+        let overview = health_overview(&network, &bridge, &bundler, &BTreeMap::new(), now);
+        assert_eq!(overview.score, 100);
+        assert_eq!(overview.status, OverallStatus::Healthy);
+        let names: Vec<_> = overview.factors.iter().map(|factor| factor.name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "batch_producer",
+                "rpc_endpoint",
+                "bundler_endpoint",
+                "bundling"
+            ]
+        );
+
+        // 30 of the 80 weight points are down, 15 are halved
+        network.batch_producer = Status::Offline;
+        network.bundler_endpoint = Status::Stalled;
+        network
+            .components
+            .insert("fullnode".to_string(), Status::Online);
+        let overview = health_overview(&network, &bridge, &bundler, &BTreeMap::new(), now);
+        assert_eq!(overview.score, 53);
+        assert_eq!(overview.status, OverallStatus::Unhealthy);
+    }
+}
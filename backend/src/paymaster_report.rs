@@ -0,0 +1,202 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::error;
+
+use crate::{
+    activity::fetch_user_ops,
+    aggregator::{aggregate, Event, Window},
+    config::ActivityMonitoringConfig,
+    explorer::ExplorerClient,
+};
+
+/// Max number of user ops pages scanned per report
+const MAX_REPORT_PAGES: usize = 200;
+
+/// Period covered when `from` is not given
+const DEFAULT_REPORT_DAYS: i64 = 30;
+
+/// Query parameters of the paymaster report endpoint
+#[derive(Deserialize, Debug)]
+pub struct PaymasterReportQuery {
+    /// Start of the period, RFC 3339 or `YYYY-MM-DD`
+    from: Option<String>,
+    /// End of the period, RFC 3339 or `YYYY-MM-DD`; defaults to now
+    to: Option<String>,
+}
+
+/// Gas sponsored by a paymaster over a period
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PaymasterReport {
+    paymaster: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    sponsored_user_ops: u64,
+    /// Sum of the fees of sponsored user ops, in Wei
+    total_gas_paid: u64,
+    /// Number of distinct senders whose user ops were sponsored
+    unique_beneficiaries: u64,
+    /// The page limit was reached, so totals only cover part of the period
+    truncated: bool,
+}
+
+/// Parses an RFC 3339 timestamp or a date, taken as 00:00 UTC
+pub(crate) fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+fn is_evm_address(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Scan the user ops sponsored by `paymaster` within `[from, to]`
+async fn build_report(
+    explorer: &impl ExplorerClient,
+    config: &ActivityMonitoringConfig,
+    paymaster: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<PaymasterReport, anyhow::Error> {
+    let filters = [("paymaster", paymaster.to_string())];
+    let windows = [Window {
+        key: paymaster.to_string(),
+        start: from,
+    }];
+    let mut stats = HashMap::new();
+    let mut page_token = None;
+    let mut truncated = true;
+
+    for _ in 0..MAX_REPORT_PAGES {
+        let page = fetch_user_ops(
+            explorer,
+            config.user_ops_query_url(),
+            &filters,
+            from,
+            Some(to),
+            Some(config.query_page_size()),
+            page_token,
+        )
+        .await?;
+        let events: Vec<Event> = page.user_ops.iter().filter_map(|op| op.event()).collect();
+        aggregate(&mut stats, events, &windows, to);
+
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            truncated = false;
+            break;
+        }
+    }
+
+    let stats = stats.remove(paymaster).unwrap_or_default();
+    Ok(PaymasterReport {
+        paymaster: paymaster.to_string(),
+        from,
+        to,
+        sponsored_user_ops: stats.events,
+        total_gas_paid: stats.gas_used,
+        unique_beneficiaries: stats.unique_accounts(),
+        truncated,
+    })
+}
+
+/// Handler summarizing the user ops a paymaster sponsored over a period
+pub async fn get_paymaster_report<E: ExplorerClient>(
+    Path(address): Path<String>,
+    Query(query): Query<PaymasterReportQuery>,
+    explorer: E,
+    config: Arc<ActivityMonitoringConfig>,
+) -> Result<Json<PaymasterReport>, StatusCode> {
+    if !is_evm_address(&address) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let to = match query.to.as_deref() {
+        Some(to) => parse_time(to).ok_or(StatusCode::BAD_REQUEST)?,
+        None => Utc::now(),
+    };
+    let from = match query.from.as_deref() {
+        Some(from) => parse_time(from).ok_or(StatusCode::BAD_REQUEST)?,
+        None => to - Duration::days(DEFAULT_REPORT_DAYS),
+    };
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let paymaster = address.to_lowercase();
+    match build_report(&explorer, &config, &paymaster, from, to).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!(error = %e, %paymaster, "Paymaster report failed");
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_report, is_evm_address, parse_time};
+    use crate::{config::ActivityMonitoringConfig, explorer::FakeExplorerClient};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_time() {
+        let midnight = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(parse_time("2025-03-01"), Some(midnight));
+        assert_eq!(parse_time("2025-03-01T02:00:00+02:00"), Some(midnight));
+        assert_eq!(parse_time("March 1st"), None);
+
+        assert!(is_evm_address("0x0000000071727De22E5E9d8BAf0edAc6f37da032"));
+        assert!(!is_evm_address("0x1234"));
+    }
+
+    #[tokio::test]
+    async fn test_build_report() {
+        let config = ActivityMonitoringConfig::new();
+        let explorer = FakeExplorerClient::default();
+        let user_op = |sender: &str, fee: &str, timestamp: &str| {
+            json!({
+                "address": { "hash": sender },
+                "fee": fee,
+                "timestamp": timestamp,
+            })
+        };
+        explorer.push_response(
+            config.user_ops_query_url(),
+            json!({
+                "items": [
+                    user_op("0xa", "100", "2025-03-02T00:00:00Z"),
+                    user_op("0xb", "50", "2025-03-03T00:00:00Z"),
+                ],
+                "next_page_params": { "page_token": "2" },
+            }),
+        );
+        explorer.push_response(
+            config.user_ops_query_url(),
+            json!({ "items": [user_op("0xa", "25", "2025-03-04T00:00:00Z")] }),
+        );
+
+        let from = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 4, 1, 0, 0, 0).unwrap();
+        let report = build_report(&explorer, &config, "0xpaymaster", from, to)
+            .await
+            .unwrap();
+
+        assert_eq!(report.sponsored_user_ops, 3);
+        assert_eq!(report.total_gas_paid, 175);
+        assert_eq!(report.unique_beneficiaries, 2);
+        assert!(!report.truncated);
+    }
+}
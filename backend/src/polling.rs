@@ -0,0 +1,134 @@
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::{info, warn};
+
+use crate::{degradation, network::FailureKind, tasks::TaskRegistry};
+
+/// Max factor by which a polling interval is stretched while its upstream fails
+const MAX_BACKOFF_FACTOR: u32 = 8;
+
+/// Polling interval that backs off while an upstream is failing or rate limiting,
+/// and speeds back up once it responds again.
+///
+/// The interval doubles after every failed cycle, up to `MAX_BACKOFF_FACTOR` times
+/// the base interval, and halves after every healthy cycle until it is back to base.
+///
+/// An exhausted request budget acts as the circuit breaker of its upstream: requests
+/// are refused without being sent until the next UTC day. Cycles that only failed
+/// that way keep the interval, as the upstream is not under pressure and backing
+/// off would only delay noticing that the budget was renewed.
+#[derive(Debug)]
+pub struct AdaptiveInterval {
+    /// Task name, for logs
+    name: &'static str,
+    base: Duration,
+    current: Duration,
+    /// Start of the last cycle
+    last_tick: Option<Instant>,
+}
+
+impl AdaptiveInterval {
+    pub fn new(name: &'static str, base_s: u64) -> Self {
+        let base = Duration::from_secs(base_s);
+        Self {
+            name,
+            base,
+            current: base,
+            last_tick: None,
+        }
+    }
+
+    /// Current interval between the start of two cycles
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    fn max(&self) -> Duration {
+        self.base * MAX_BACKOFF_FACTOR
+    }
+
+    /// Waits until the next cycle is due. The first tick completes immediately.
+    pub async fn tick(&mut self) {
+        if let Some(last_tick) = self.last_tick {
+            sleep_until(last_tick + self.current).await;
+        }
+        self.last_tick = Some(Instant::now());
+    }
+
+    /// Adapts the interval to the outcome of a cycle, unless its requests were
+    /// short-circuited by an open breaker. Returns the new interval if it changed.
+    pub fn record(&mut self, upstream_healthy: bool, short_circuited: bool) -> Option<Duration> {
+        if !upstream_healthy && short_circuited {
+            return None;
+        }
+        let next = if upstream_healthy {
+            (self.current / 2).max(self.base)
+        } else {
+            (self.current * 2).min(self.max())
+        };
+        if next == self.current {
+            return None;
+        }
+
+        if upstream_healthy {
+            info!(task = self.name, interval = ?next, "Upstream recovered, speeding up polling");
+        } else {
+            warn!(task = self.name, interval = ?next, "Upstream failing, backing off polling");
+        }
+        self.current = next;
+        Some(next)
+    }
+
+    /// Adapts the interval to the outcome of a cycle, keeping the interval the
+    /// task registry expects in sync so that backing off does not flag the task stale
+    pub async fn adapt(&mut self, upstream_healthy: bool, tasks: &TaskRegistry) {
+        let failures = degradation::cycle_failures(self.name);
+        let short_circuited = !failures.is_empty()
+            && failures
+                .iter()
+                .all(|kind| *kind == FailureKind::BudgetExhausted);
+        if let Some(next) = self.record(upstream_healthy, short_circuited) {
+            tasks.set_interval(self.name, next.as_secs()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdaptiveInterval, MAX_BACKOFF_FACTOR};
+    use tokio::time::Duration;
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let mut interval = AdaptiveInterval::new("task", 10);
+        assert_eq!(interval.record(true, false), None);
+        assert_eq!(interval.record(false, false), Some(Duration::from_secs(20)));
+        assert_eq!(interval.record(false, false), Some(Duration::from_secs(40)));
+        for _ in 0..10 {
+            interval.record(false, false);
+        }
+        assert_eq!(
+            interval.current(),
+            Duration::from_secs(10) * MAX_BACKOFF_FACTOR
+        );
+
+        // Recovery halves the interval on each healthy cycle
+        assert_eq!(interval.record(true, false), Some(Duration::from_secs(40)));
+        interval.record(true, false);
+        interval.record(true, false);
+        assert_eq!(interval.current(), Duration::from_secs(10));
+        assert_eq!(interval.record(true, false), None);
+    }
+
+    #[test]
+    fn test_no_backoff_while_breaker_open() {
+        let mut interval = AdaptiveInterval::new("task", 10);
+        assert_eq!(interval.record(false, true), None);
+        assert_eq!(interval.current(), Duration::from_secs(10));
+
+        // Failures before the breaker opened are kept, not reset
+        interval.record(false, false);
+        assert_eq!(interval.record(false, true), None);
+        assert_eq!(interval.current(), Duration::from_secs(20));
+        assert_eq!(interval.record(true, false), Some(Duration::from_secs(10)));
+    }
+}
@@ -0,0 +1,442 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fmt, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use web_push::{
+    ContentEncoding, IsahcWebPushClient, PartialVapidSignatureBuilder, SubscriptionInfo,
+    VapidSignatureBuilder, WebPushClient, WebPushError, WebPushMessageBuilder,
+};
+
+use crate::{
+    alerts::Alert,
+    auth::{ApiAuth, EndpointGroup},
+    checkpoint,
+    config::PushConfig,
+    templates::Template,
+};
+
+/// Seconds a push service keeps a notification for an offline browser
+const PUSH_TTL_S: u32 = 24 * 3600;
+
+/// Max number of stored subscriptions
+const MAX_PUSH_SUBSCRIPTIONS: usize = 1000;
+
+/// Max length of a subscription endpoint URL
+const MAX_ENDPOINT_LEN: usize = 1024;
+
+/// Max length of the base64 encoded keys of a subscription
+const MAX_KEY_LEN: usize = 256;
+
+/// Notification shown by the dashboard's service worker
+#[derive(Serialize)]
+struct PushPayload<'a> {
+    title: String,
+    body: String,
+    alert_id: &'a str,
+    resolved: bool,
+    /// Operator who acknowledged the alert, if any
+    acknowledged_by: Option<&'a str>,
+}
+
+/// Custom wording of the notifications, the built-in one where unset
+#[derive(Debug, Default)]
+struct PushTemplates {
+    title: Option<Template>,
+    body: Option<Template>,
+}
+
+impl PushTemplates {
+    fn load(config: &PushConfig) -> Self {
+        let load = |path: Option<&str>, name: &str| {
+            path.map(|path| {
+                let template = Template::load(path)
+                    .unwrap_or_else(|e| panic!("to load {} template {}: {}", name, path, e));
+                info!(%path, "Loaded push notification {} template", name);
+                template
+            })
+        };
+        Self {
+            title: load(config.title_template_path(), "title"),
+            body: load(config.body_template_path(), "body"),
+        }
+    }
+}
+
+/// Fields of an alert the templates can refer to
+fn template_context(alert: &Alert, resolved: bool) -> Value {
+    let (kind, subject) = alert.id.split_once(':').unwrap_or((&alert.id, ""));
+    json!({
+        "id": alert.id,
+        "kind": kind,
+        "subject": subject,
+        "severity": alert.severity,
+        "message": alert.message,
+        "since": alert.since,
+        "resolved": resolved,
+        "acknowledgement": alert.acknowledgement,
+    })
+}
+
+fn push_payload(alert: &Alert, resolved: bool, templates: &PushTemplates) -> Vec<u8> {
+    let context = template_context(alert, resolved);
+    // Falls back to the built-in wording when a template fails to render
+    let render = |template: &Option<Template>| {
+        template.as_ref().and_then(|template| {
+            template
+                .render(&context)
+                .map_err(|e| warn!(alert = %alert.id, error = %e, "Failed to render template"))
+                .ok()
+        })
+    };
+    let title = match render(&templates.title) {
+        Some(title) => title,
+        None if resolved => format!("Resolved: {}", alert.id),
+        None => format!("Critical: {}", alert.id),
+    };
+    let body = render(&templates.body).unwrap_or_else(|| alert.message.clone());
+    let payload = PushPayload {
+        title,
+        body,
+        alert_id: &alert.id,
+        resolved,
+        acknowledged_by: alert
+            .acknowledgement
+            .as_ref()
+            .and_then(|ack| ack.by.as_deref()),
+    };
+    serde_json::to_vec(&payload).expect("to serialize push payload")
+}
+
+/// A subscription is sent to a push service over https, with keys of sane lengths
+fn is_valid_subscription(subscription: &SubscriptionInfo) -> bool {
+    let endpoint = &subscription.endpoint;
+    let valid_endpoint = endpoint.len() <= MAX_ENDPOINT_LEN
+        && reqwest::Url::parse(endpoint)
+            .is_ok_and(|url| url.scheme() == "https" && url.host_str().is_some());
+    let valid_key = |key: &str| !key.is_empty() && key.len() <= MAX_KEY_LEN;
+    valid_endpoint && valid_key(&subscription.keys.p256dh) && valid_key(&subscription.keys.auth)
+}
+
+/// Adds `subscription` to `subscriptions`, replacing any previous one of the same
+/// browser. Returns whether they changed.
+fn add_subscription(
+    subscriptions: &mut Vec<SubscriptionInfo>,
+    subscription: SubscriptionInfo,
+) -> Result<bool, StatusCode> {
+    if !is_valid_subscription(&subscription) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    match subscriptions
+        .iter_mut()
+        .find(|existing| existing.endpoint == subscription.endpoint)
+    {
+        Some(existing)
+            if existing.keys.p256dh == subscription.keys.p256dh
+                && existing.keys.auth == subscription.keys.auth =>
+        {
+            Ok(false)
+        }
+        Some(existing) => {
+            *existing = subscription;
+            Ok(true)
+        }
+        None if subscriptions.len() >= MAX_PUSH_SUBSCRIPTIONS => {
+            Err(StatusCode::INSUFFICIENT_STORAGE)
+        }
+        None => {
+            subscriptions.push(subscription);
+            Ok(true)
+        }
+    }
+}
+
+/// The subscription is expired or was revoked by the user
+fn is_gone(e: &WebPushError) -> bool {
+    matches!(
+        e.short_description(),
+        "endpoint_not_valid" | "endpoint_not_found"
+    )
+}
+
+struct PushState {
+    client: IsahcWebPushClient,
+    signer: PartialVapidSignatureBuilder,
+    subject: String,
+    public_key: String,
+    subscriptions_path: String,
+    subscriptions: RwLock<Vec<SubscriptionInfo>>,
+    templates: PushTemplates,
+}
+
+/// Sends browser notifications to the dashboard users who opted in
+#[derive(Clone)]
+pub struct PushNotifier {
+    state: Arc<PushState>,
+}
+
+impl fmt::Debug for PushNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PushNotifier")
+            .field("subscriptions_path", &self.state.subscriptions_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PushNotifier {
+    /// Loads the stored subscriptions, `None` if Web Push is not configured
+    pub fn new(config: &PushConfig) -> Option<Self> {
+        let private_key = config.vapid_private_key()?;
+        let signer = VapidSignatureBuilder::from_base64_no_sub(private_key)
+            .expect("to parse WEB_PUSH_VAPID_PRIVATE_KEY");
+        let client = IsahcWebPushClient::new().expect("to create Web Push client");
+        let subscriptions: Vec<SubscriptionInfo> =
+            checkpoint::load(config.subscriptions_path()).unwrap_or_default();
+        info!(
+            subscriptions = subscriptions.len(),
+            "Loaded push subscriptions"
+        );
+
+        Some(Self {
+            state: Arc::new(PushState {
+                client,
+                signer,
+                subject: config.vapid_subject().to_string(),
+                public_key: config.vapid_public_key().to_string(),
+                subscriptions_path: config.subscriptions_path().to_string(),
+                subscriptions: RwLock::new(subscriptions),
+                templates: PushTemplates::load(config),
+            }),
+        })
+    }
+
+    fn persist(&self, subscriptions: &[SubscriptionInfo]) {
+        if let Err(e) = checkpoint::save(&self.state.subscriptions_path, &subscriptions) {
+            warn!(error = %e, "Failed to store push subscriptions");
+        }
+    }
+
+    /// Adds a subscription, see [`add_subscription`]. Stored only when new or changed.
+    async fn subscribe(&self, subscription: SubscriptionInfo) -> Result<(), StatusCode> {
+        let mut subscriptions = self.state.subscriptions.write().await;
+        if add_subscription(&mut subscriptions, subscription)? {
+            self.persist(&subscriptions);
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, endpoint: &str) {
+        let mut subscriptions = self.state.subscriptions.write().await;
+        let count = subscriptions.len();
+        subscriptions.retain(|existing| existing.endpoint != endpoint);
+        if subscriptions.len() != count {
+            self.persist(&subscriptions);
+        }
+    }
+
+    async fn send(
+        &self,
+        subscription: &SubscriptionInfo,
+        payload: &[u8],
+    ) -> Result<(), WebPushError> {
+        let mut signature = self.state.signer.clone().add_sub_info(subscription);
+        signature.add_claim("sub", self.state.subject.as_str());
+
+        let mut message = WebPushMessageBuilder::new(subscription);
+        message.set_ttl(PUSH_TTL_S);
+        message.set_payload(ContentEncoding::Aes128Gcm, payload);
+        message.set_vapid_signature(signature.build()?);
+        self.state.client.send(message.build()?).await
+    }
+
+    /// Pushes a critical alert, or its resolution, to every subscriber.
+    ///
+    /// Subscriptions the push service reports as gone are dropped.
+    pub async fn notify(&self, alert: &Alert, resolved: bool) {
+        let payload = push_payload(alert, resolved, &self.state.templates);
+        let subscriptions = self.state.subscriptions.read().await.clone();
+
+        let mut gone = Vec::new();
+        for subscription in &subscriptions {
+            match self.send(subscription, &payload).await {
+                Ok(()) => {}
+                Err(e) if is_gone(&e) => gone.push(subscription.endpoint.clone()),
+                Err(e) => warn!(error = %e, alert_id = %alert.id, "Push notification failed"),
+            }
+        }
+
+        if !gone.is_empty() {
+            info!(count = gone.len(), "Dropping expired push subscriptions");
+            let mut subscriptions = self.state.subscriptions.write().await;
+            subscriptions.retain(|subscription| !gone.contains(&subscription.endpoint));
+            self.persist(&subscriptions);
+        }
+    }
+}
+
+/// Return the VAPID public key browsers subscribe with
+pub async fn get_push_public_key(push: Option<PushNotifier>) -> Result<Json<Value>, StatusCode> {
+    let push = push.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(json!({ "public_key": push.state.public_key })))
+}
+
+/// Store a browser push subscription, as returned by `PushSubscription.toJSON()`.
+///
+/// Requires a token scoped to the alerts group, even when alerts are public.
+pub async fn post_push_subscription(
+    headers: HeaderMap,
+    auth: ApiAuth,
+    push: Option<PushNotifier>,
+    Json(subscription): Json<SubscriptionInfo>,
+) -> Result<StatusCode, StatusCode> {
+    auth.check_token(EndpointGroup::Alerts, &headers)?;
+    let push = push.ok_or(StatusCode::NOT_FOUND)?;
+    push.subscribe(subscription).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Body of the unsubscribe endpoint
+#[derive(Deserialize, Debug)]
+pub struct PushUnsubscribeRequest {
+    endpoint: String,
+}
+
+/// Remove a browser push subscription, with the same token as to subscribe
+pub async fn post_push_unsubscribe(
+    headers: HeaderMap,
+    auth: ApiAuth,
+    push: Option<PushNotifier>,
+    Json(request): Json<PushUnsubscribeRequest>,
+) -> Result<StatusCode, StatusCode> {
+    auth.check_token(EndpointGroup::Alerts, &headers)?;
+    let push = push.ok_or(StatusCode::NOT_FOUND)?;
+    push.unsubscribe(&request.endpoint).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_subscription, push_payload, PushTemplates, MAX_PUSH_SUBSCRIPTIONS};
+    use crate::{
+        alerts::{Alert, Severity},
+        templates::Template,
+    };
+    use axum::http::StatusCode;
+    use chrono::Utc;
+    use web_push::SubscriptionInfo;
+
+    fn subscription(endpoint: &str, auth: &str) -> SubscriptionInfo {
+        SubscriptionInfo::new(endpoint, "BNcRdreALRFXTkOOUHK1EtK2wtaz5Ry4YfYCA", auth)
+    }
+
+    #[test]
+    fn test_add_subscription() {
+        let mut subscriptions = Vec::new();
+        let endpoint = "https://push.example.com/send/1";
+        assert_eq!(
+            add_subscription(&mut subscriptions, subscription(endpoint, "a")),
+            Ok(true)
+        );
+        // Unchanged, nothing to store
+        assert_eq!(
+            add_subscription(&mut subscriptions, subscription(endpoint, "a")),
+            Ok(false)
+        );
+        // Renewed keys replace the previous subscription of the browser
+        assert_eq!(
+            add_subscription(&mut subscriptions, subscription(endpoint, "b")),
+            Ok(true)
+        );
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].keys.auth, "b");
+
+        let long = format!("https://push.example.com/{}", "a".repeat(1024));
+        for endpoint in ["http://push.example.com/send/2", "not a url", &long] {
+            assert_eq!(
+                add_subscription(&mut subscriptions, subscription(endpoint, "a")),
+                Err(StatusCode::UNPROCESSABLE_ENTITY)
+            );
+        }
+        assert_eq!(
+            add_subscription(&mut subscriptions, subscription(endpoint, "")),
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        );
+
+        for i in subscriptions.len()..MAX_PUSH_SUBSCRIPTIONS {
+            let endpoint = format!("https://push.example.com/send/{}", i + 2);
+            add_subscription(&mut subscriptions, subscription(&endpoint, "a")).unwrap();
+        }
+        assert_eq!(
+            add_subscription(
+                &mut subscriptions,
+                subscription("https://push.example.com/send/0", "a")
+            ),
+            Err(StatusCode::INSUFFICIENT_STORAGE)
+        );
+        // Known browsers can still renew their subscription
+        assert_eq!(
+            add_subscription(&mut subscriptions, subscription(endpoint, "c")),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_push_payload() {
+        let alert = Alert {
+            id: "bridge_supply_mismatch".to_string(),
+            severity: Severity::Critical,
+            message: "L2 supply differs".to_string(),
+            since: Utc::now(),
+            acknowledgement: None,
+        };
+
+        let payload: serde_json::Value =
+            serde_json::from_slice(&push_payload(&alert, false, &PushTemplates::default()))
+                .unwrap();
+        assert_eq!(payload["title"], "Critical: bridge_supply_mismatch");
+        assert_eq!(payload["body"], "L2 supply differs");
+        assert_eq!(payload["resolved"], false);
+
+        let payload: serde_json::Value =
+            serde_json::from_slice(&push_payload(&alert, true, &PushTemplates::default())).unwrap();
+        assert_eq!(payload["title"], "Resolved: bridge_supply_mismatch");
+    }
+
+    #[test]
+    fn test_templated_push_payload() {
+        let alert = Alert {
+            id: "paymaster_balance_low:deposit".to_string(),
+            severity: Severity::Critical,
+            message: "Balance below 1 ETH".to_string(),
+            since: Utc::now(),
+            acknowledgement: None,
+        };
+        let templates = PushTemplates {
+            title: Some(Template::parse("[{{severity}}] {{subject}} paymaster").unwrap()),
+            body: Some(
+                Template::parse("{{message}}, see https://runbooks.example.com/{{kind}}").unwrap(),
+            ),
+        };
+
+        let payload: serde_json::Value =
+            serde_json::from_slice(&push_payload(&alert, false, &templates)).unwrap();
+        assert_eq!(payload["title"], "[critical] deposit paymaster");
+        assert_eq!(
+            payload["body"],
+            "Balance below 1 ETH, see https://runbooks.example.com/paymaster_balance_low"
+        );
+
+        // The alert message stands in for a body failing to render
+        let templates = PushTemplates {
+            title: None,
+            body: Some(Template::parse("{{ message | round }}").unwrap()),
+        };
+        let payload: serde_json::Value =
+            serde_json::from_slice(&push_payload(&alert, false, &templates)).unwrap();
+        assert_eq!(payload["body"], "Balance below 1 ETH");
+    }
+}
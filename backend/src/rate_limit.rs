@@ -0,0 +1,186 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as StdMutex},
+};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Duration, Instant},
+};
+
+/// Token bucket limiting the rate of outbound requests
+#[derive(Debug)]
+pub struct TokenBucket {
+    /// Maximum number of tokens, i.e. the allowed burst
+    capacity: f64,
+    /// Tokens added per second
+    refill_rate: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl BucketState {
+    /// Refills tokens for the time elapsed since the last call and takes one if available.
+    /// Otherwise returns how long to wait until a token becomes available.
+    fn try_take(&mut self, capacity: f64, refill_rate: f64, now: Instant) -> Option<Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_rate).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / refill_rate))
+        }
+    }
+}
+
+impl TokenBucket {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            capacity,
+            refill_rate: requests_per_second,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token if one is available, without waiting
+    pub async fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().await;
+        state
+            .try_take(self.capacity, self.refill_rate, Instant::now())
+            .is_none()
+    }
+
+    /// Waits until a request is allowed to go out
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                match state.try_take(self.capacity, self.refill_rate, Instant::now()) {
+                    None => return,
+                    Some(wait) => wait,
+                }
+            };
+            sleep(wait).await;
+        }
+    }
+}
+
+/// Token buckets keyed by upstream host, so that all tasks hitting the same
+/// host share one request budget.
+#[derive(Clone, Debug, Default)]
+pub struct HostRateLimiters {
+    /// Allowed requests per second per host. `0` disables rate limiting.
+    requests_per_second: f64,
+    /// Number of requests allowed to go out back to back
+    burst: u32,
+    limiters: Arc<StdMutex<HashMap<String, Arc<TokenBucket>>>>,
+}
+
+impl HostRateLimiters {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+            limiters: Arc::default(),
+        }
+    }
+
+    /// Returns the token bucket for the host of `url`, or `None` if rate limiting is disabled
+    fn for_url(&self, url: &str) -> Option<Arc<TokenBucket>> {
+        if self.requests_per_second <= 0.0 {
+            return None;
+        }
+
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_default();
+
+        let mut limiters = self.limiters.lock().expect("rate limiters lock poisoned");
+        let bucket = limiters
+            .entry(host)
+            .or_insert_with(|| Arc::new(TokenBucket::new(self.requests_per_second, self.burst)));
+        Some(Arc::clone(bucket))
+    }
+
+    /// Waits until a request to `url` is allowed to go out
+    pub async fn acquire(&self, url: &str) {
+        if let Some(bucket) = self.for_url(url) {
+            bucket.acquire().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BucketState, HostRateLimiters};
+    use std::sync::Arc;
+    use tokio::time::{Duration, Instant};
+
+    #[test]
+    fn test_bucket_allows_burst_then_waits() {
+        let now = Instant::now();
+        let mut state = BucketState {
+            tokens: 2.0,
+            last_refill: now,
+        };
+
+        assert!(state.try_take(2.0, 1.0, now).is_none());
+        assert!(state.try_take(2.0, 1.0, now).is_none());
+        let wait = state.try_take(2.0, 1.0, now).expect("bucket to be empty");
+        assert_eq!(wait, Duration::from_secs(1));
+
+        // Half a token refilled after 500ms at 1 rps
+        let wait = state
+            .try_take(2.0, 1.0, now + Duration::from_millis(500))
+            .expect("bucket to still be empty");
+        assert_eq!(wait, Duration::from_millis(500));
+        assert!(state
+            .try_take(2.0, 1.0, now + Duration::from_secs(1))
+            .is_none());
+    }
+
+    #[test]
+    fn test_refill_is_capped_at_capacity() {
+        let now = Instant::now();
+        let mut state = BucketState {
+            tokens: 0.0,
+            last_refill: now,
+        };
+
+        assert!(state
+            .try_take(3.0, 10.0, now + Duration::from_secs(60))
+            .is_none());
+        assert_eq!(state.tokens, 2.0);
+    }
+
+    #[test]
+    fn test_limiters_are_shared_per_host() {
+        let limiters = HostRateLimiters::new(5.0, 1);
+        let a = limiters.for_url("http://explorer.local/api/v2/a").unwrap();
+        let b = limiters.for_url("http://explorer.local/api/v2/b").unwrap();
+        let c = limiters.for_url("http://other.local/api").unwrap();
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[test]
+    fn test_zero_rate_disables_limiting() {
+        let limiters = HostRateLimiters::new(0.0, 1);
+        assert!(limiters.for_url("http://explorer.local").is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::{
+    checks::{CheckSpec, ComponentChecks, StatusCheck},
+    network::BUILTIN_COMPONENTS,
+};
+
+/// Checks listed by the remote registry at `STATUS_REGISTRY_URL`, so that
+/// endpoints can be added to the fleet without redeploying the backend
+pub struct StatusRegistry {
+    url: String,
+    refresh_interval: Duration,
+    /// Names of the checks declared in config, which the registry cannot redefine
+    static_names: Vec<String>,
+    /// Checks of the last valid registry listing
+    specs: Vec<CheckSpec>,
+    last_refresh: Option<Instant>,
+}
+
+impl StatusRegistry {
+    pub fn new(url: &str, refresh_interval_s: u64, static_checks: &[CheckSpec]) -> Self {
+        Self {
+            url: url.to_string(),
+            refresh_interval: Duration::from_secs(refresh_interval_s),
+            static_names: static_checks
+                .iter()
+                .map(|spec| spec.name().to_string())
+                .collect(),
+            specs: Vec::new(),
+            last_refresh: None,
+        }
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        self.last_refresh
+            .is_none_or(|at| now.duration_since(at) >= self.refresh_interval)
+    }
+
+    /// Refetches the registry when due and updates `checks` to match it.
+    ///
+    /// The last valid listing is kept when the registry is unreachable or invalid,
+    /// and checks unchanged in the listing keep their debouncing state.
+    pub async fn refresh(
+        &mut self,
+        http_client: &reqwest::Client,
+        checks: &mut ComponentChecks,
+        build: impl Fn(&CheckSpec) -> Box<dyn StatusCheck>,
+        now: Instant,
+    ) {
+        if !self.is_due(now) {
+            return;
+        }
+        self.last_refresh = Some(now);
+
+        let specs = match self.fetch(http_client).await {
+            Ok(specs) => specs,
+            Err(reason) => {
+                warn!(url = %self.url, %reason, "Failed to refresh status registry");
+                return;
+            }
+        };
+        let (removed, added) = changes(&self.specs, &specs);
+        if removed.is_empty() && added.is_empty() {
+            return;
+        }
+        let added_names: Vec<&str> = added.iter().map(|spec| spec.name()).collect();
+        info!(?removed, added = ?added_names, "Status registry changed");
+        for name in removed {
+            checks.remove(name);
+        }
+        for spec in added {
+            checks.add(build(spec), spec.interval_s());
+        }
+        self.specs = specs;
+    }
+
+    async fn fetch(&self, http_client: &reqwest::Client) -> Result<Vec<CheckSpec>, String> {
+        let specs = http_client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json::<Vec<CheckSpec>>()
+            .await
+            .map_err(|e| e.to_string())?;
+        validate(&specs, &self.static_names)?;
+        Ok(specs)
+    }
+}
+
+/// Rejects listings with checks named like builtin or config checks, or twice
+fn validate(specs: &[CheckSpec], static_names: &[String]) -> Result<(), String> {
+    for (i, spec) in specs.iter().enumerate() {
+        let name = spec.name();
+        if BUILTIN_COMPONENTS.contains(&name) || static_names.iter().any(|other| other == name) {
+            return Err(format!("registry must not redefine {}", name));
+        }
+        if specs[..i].iter().any(|other| other.name() == name) {
+            return Err(format!("registry declares {} twice", name));
+        }
+    }
+    Ok(())
+}
+
+/// Names of the checks to remove and the checks to add to go from `old` to `new`;
+/// a modified check is both removed and added
+fn changes<'a>(old: &'a [CheckSpec], new: &'a [CheckSpec]) -> (Vec<&'a str>, Vec<&'a CheckSpec>) {
+    let removed = old
+        .iter()
+        .filter(|spec| !new.contains(spec))
+        .map(CheckSpec::name)
+        .collect();
+    let added = new.iter().filter(|spec| !old.contains(spec)).collect();
+    (removed, added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changes, validate};
+    use crate::checks::CheckSpec;
+    use serde_json::json;
+
+    fn spec(name: &str, url: &str) -> CheckSpec {
+        serde_json::from_value(json!({ "type": "rpc", "name": name, "url": url })).unwrap()
+    }
+
+    #[test]
+    fn test_registry_validation() {
+        let static_names = vec!["fullnode".to_string()];
+        assert!(validate(&[spec("bundler_eu", "http://eu")], &static_names).is_ok());
+        assert!(validate(&[spec("rpc_endpoint", "http://eu")], &static_names).is_err());
+        assert!(validate(&[spec("fullnode", "http://eu")], &static_names).is_err());
+        assert!(validate(
+            &[
+                spec("bundler_eu", "http://eu"),
+                spec("bundler_eu", "http://us")
+            ],
+            &static_names
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_registry_changes() {
+        let old = [
+            spec("bundler_eu", "http://eu"),
+            spec("bundler_us", "http://us"),
+        ];
+        let new = [
+            spec("bundler_eu", "http://eu"),
+            spec("bundler_us", "http://us-2"),
+            spec("bundler_ap", "http://ap"),
+        ];
+
+        let (removed, added) = changes(&old, &new);
+        assert_eq!(removed, vec!["bundler_us"]);
+        assert_eq!(
+            added.iter().map(|spec| spec.name()).collect::<Vec<_>>(),
+            vec!["bundler_us", "bundler_ap"]
+        );
+        assert_eq!(changes(&new, &new), (vec![], vec![]));
+    }
+}
@@ -0,0 +1,236 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::{
+    activity::ActivityStats,
+    bridge::BridgeStatus,
+    config::ReportsConfig,
+    cron::sleep_until,
+    s3::S3Client,
+    tasks::{TaskRegistry, REPORTS_TASK},
+    txid_format::format_txid,
+    utils::{status_label, to_csv, txid_field},
+    SharedStates,
+};
+
+/// Rendered report object
+#[derive(Debug, PartialEq)]
+struct ReportFile {
+    /// Object name within the report's folder, e.g. `usage.csv`
+    name: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct Report<'a, T> {
+    generated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+fn json_file<T: Serialize>(
+    name: &'static str,
+    data: &T,
+    generated_at: DateTime<Utc>,
+) -> ReportFile {
+    let report = Report { generated_at, data };
+    ReportFile {
+        name,
+        content_type: "application/json",
+        body: serde_json::to_vec_pretty(&report).expect("to serialize report"),
+    }
+}
+
+fn csv_file(name: &'static str, csv: String) -> ReportFile {
+    ReportFile {
+        name,
+        content_type: "text/csv",
+        body: csv.into_bytes(),
+    }
+}
+
+/// Name a status serializes to, e.g. `In progress`
+/// Usage report: every activity stat per time window
+fn usage_report(stats: &ActivityStats, generated_at: DateTime<Utc>) -> Vec<ReportFile> {
+    let rows = stats
+        .stat_rows()
+        .into_iter()
+        .map(|(stat, window, value)| vec![stat.to_string(), window.to_string(), value.to_string()]);
+    vec![
+        json_file("usage.json", stats, generated_at),
+        csv_file("usage.csv", to_csv(&["stat", "time_window", "value"], rows)),
+    ]
+}
+
+/// Bridge report: deposits, withdrawals and reimbursements with their status
+fn bridge_report(status: &BridgeStatus, generated_at: DateTime<Utc>) -> Vec<ReportFile> {
+    let deposits = status.deposits.iter().map(|deposit| {
+        vec![
+            "deposit".to_string(),
+            format_txid(&deposit.deposit_request_txid),
+            status_label(&deposit.status),
+            txid_field(&deposit.deposit_txid),
+        ]
+    });
+    let withdrawals = status.withdrawals.iter().map(|withdrawal| {
+        vec![
+            "withdrawal".to_string(),
+            format_txid(&withdrawal.withdrawal_request_txid),
+            status_label(&withdrawal.status),
+            txid_field(&withdrawal.fulfillment_txid),
+        ]
+    });
+    let reimbursements = status.reimbursements.iter().map(|reimbursement| {
+        vec![
+            "reimbursement".to_string(),
+            format_txid(&reimbursement.claim_txid),
+            status_label(&reimbursement.status),
+            txid_field(&reimbursement.payout_txid),
+        ]
+    });
+    let rows = deposits.chain(withdrawals).chain(reimbursements);
+
+    vec![
+        json_file("bridge.json", status, generated_at),
+        csv_file(
+            "bridge.csv",
+            to_csv(&["kind", "txid", "status", "linked_txid"], rows),
+        ),
+    ]
+}
+
+/// Render all reports from the current shared states
+async fn render_reports(states: &SharedStates, generated_at: DateTime<Utc>) -> Vec<ReportFile> {
+    let mut files = usage_report(&*states.activity.read().await, generated_at);
+    files.extend(bridge_report(&*states.bridge.read().await, generated_at));
+    files
+}
+
+/// Key of a report object, grouped by generation date
+fn object_key(prefix: &str, generated_at: DateTime<Utc>, name: &str) -> String {
+    let folder = generated_at.format("%Y-%m-%d");
+    if prefix.is_empty() {
+        format!("{}/{}", folder, name)
+    } else {
+        format!("{}/{}/{}", prefix, folder, name)
+    }
+}
+
+/// Periodically render usage and bridge reports and upload them to S3
+pub async fn reports_task(states: SharedStates, tasks: TaskRegistry, config: &ReportsConfig) {
+    let Some((endpoint, bucket)) = config.s3_location() else {
+        return;
+    };
+    let s3 = S3Client::new(
+        endpoint,
+        bucket,
+        config.s3_region(),
+        config.s3_access_key_id(),
+        config.s3_secret_access_key(),
+    );
+    let schedule = config.schedule();
+    tasks
+        .register(REPORTS_TASK, schedule.interval_s(Utc::now()))
+        .await;
+
+    // The first reports are generated at the first scheduled time after startup,
+    // once the monitoring tasks have filled the shared states
+    let mut next_run = schedule.next_after(Utc::now());
+
+    loop {
+        let Some(at) = next_run else {
+            warn!(?schedule, "Reports schedule has no upcoming run");
+            return;
+        };
+        sleep_until(at).await;
+        tasks.start_refresh(REPORTS_TASK).await;
+        let generated_at = Utc::now();
+
+        let mut uploaded = 0;
+        for file in render_reports(&states, generated_at).await {
+            let key = object_key(config.s3_prefix(), generated_at, file.name);
+            match s3.put_object(&key, file.body, file.content_type).await {
+                Ok(()) => uploaded += 1,
+                Err(e) => error!(error = %e, %key, "Report upload failed"),
+            }
+        }
+        info!(uploaded, "Reports uploaded");
+
+        tasks.record_refresh(REPORTS_TASK).await;
+        // Skip the runs missed while generating, rather than catching up
+        next_run = schedule
+            .next_after(at)
+            .filter(|next| *next > Utc::now())
+            .or_else(|| schedule.next_after(Utc::now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bridge_report, object_key, usage_report};
+    use crate::{
+        activity::ActivityStats,
+        bridge::{BridgeStatus, DepositInfo, DepositStatus},
+        config::ActivityMonitoringConfig,
+    };
+    use bitcoin::Txid;
+    use chrono::{TimeZone, Utc};
+    use std::str::FromStr;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    #[test]
+    fn test_object_key() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        assert_eq!(
+            object_key("reports", at, "usage.csv"),
+            "reports/2025-03-10/usage.csv"
+        );
+        assert_eq!(object_key("", at, "usage.csv"), "2025-03-10/usage.csv");
+    }
+
+    #[test]
+    fn test_usage_report() {
+        let stats = ActivityStats::default(&ActivityMonitoringConfig::new());
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let files = usage_report(&stats, at);
+
+        assert_eq!(files[0].name, "usage.json");
+        let json: serde_json::Value = serde_json::from_slice(&files[0].body).unwrap();
+        assert_eq!(json["generated_at"], "2025-03-10T12:00:00Z");
+        assert!(json["stats"].is_object());
+
+        let csv = String::from_utf8(files[1].body.clone()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("stat,time_window,value"));
+        assert_eq!(lines.count(), stats.stat_rows().len());
+    }
+
+    #[test]
+    fn test_bridge_report() {
+        let txid = Txid::from_str(TXID).unwrap();
+        let mut status = BridgeStatus::default();
+        status.deposits.push(DepositInfo {
+            deposit_request_txid: txid,
+            deposit_txid: None,
+            status: DepositStatus::InProgress,
+            drt_status: None,
+            withdrawal_request_txid: None,
+            amount_sats: None,
+            confirmed_at: None,
+            failure_reason: None,
+        });
+        let files = bridge_report(&status, Utc::now());
+
+        assert_eq!(files[1].content_type, "text/csv");
+        assert_eq!(
+            String::from_utf8(files[1].body.clone()).unwrap(),
+            format!(
+                "kind,txid,status,linked_txid\ndeposit,{},In progress,\n",
+                TXID
+            )
+        );
+    }
+}
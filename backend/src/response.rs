@@ -0,0 +1,224 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Query, Request, State},
+    http::header::{CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::error;
+
+use crate::degradation::{self, Degradation};
+
+/// Network the backend serves data for
+#[derive(Serialize, Clone, Debug)]
+pub struct NetworkId {
+    /// Human readable network name, e.g. `testnet`
+    name: String,
+    /// EVM chain id of the network, if configured
+    chain_id: Option<u64>,
+}
+
+impl NetworkId {
+    pub fn new(name: String, chain_id: Option<u64>) -> Self {
+        Self { name, chain_id }
+    }
+}
+
+/// Middleware adding a `network` field to every JSON object response, so that a
+/// frontend pointed at the wrong backend cannot silently show another chain's data.
+pub async fn add_network_field(
+    State(network): State<NetworkId>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    map_json_body(response, |body| with_network_field(body, &network)).await
+}
+
+/// Middleware adding a `degraded` field to the JSON object responses built from the
+/// state refreshed by `task`, listing the upstreams that failed during its last
+/// refresh cycle, so that frontends can show which part of the data is stale and
+/// since when. The field is left out while the upstreams of the task are healthy.
+pub async fn add_degraded_field(
+    State(task): State<&'static str>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let degradations = degradation::degradations(task);
+    if degradations.is_empty() || !response.status().is_success() {
+        return response;
+    }
+    map_json_body(response, |body| with_degraded_field(body, &degradations)).await
+}
+
+/// Rewrites the body of a JSON response with `rewrite`, leaving it as is when
+/// `rewrite` returns `None`
+async fn map_json_body(
+    response: Response,
+    rewrite: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) -> Response {
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!(error = %e, "Failed to buffer response body");
+            return axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    match rewrite(&bytes) {
+        Some(body) => {
+            // Length changed, let the server recompute it
+            parts.headers.remove(CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// Query parameters read by [`select_fields`]
+#[derive(Deserialize, Debug)]
+struct FieldsQuery {
+    /// Comma-separated top-level fields to keep
+    fields: Option<String>,
+}
+
+/// Middleware pruning JSON object responses to the top-level fields listed in the
+/// `fields` query parameter, e.g. `?fields=deposits,withdrawals`, so that widgets
+/// needing one section of a large response do not download all of it. Unknown
+/// fields are ignored.
+pub async fn select_fields(request: Request, next: Next) -> Response {
+    let fields: Option<Vec<String>> = Query::<FieldsQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.fields)
+        .map(|fields| {
+            fields
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(str::to_string)
+                .collect()
+        });
+    let response = next.run(request).await;
+    match fields {
+        Some(fields) if response.status().is_success() => {
+            map_json_body(response, |body| with_fields(body, &fields)).await
+        }
+        _ => response,
+    }
+}
+
+/// Keeps the given top-level fields of a JSON object body. Returns `None` for other
+/// bodies.
+fn with_fields(body: &[u8], fields: &[String]) -> Option<Vec<u8>> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let object = value.as_object_mut()?;
+    object.retain(|name, _| fields.contains(name));
+    serde_json::to_vec(&value).ok()
+}
+
+/// Inserts the upstream failures into a JSON object body. Returns `None` for other
+/// bodies.
+fn with_degraded_field(body: &[u8], degradations: &[Degradation]) -> Option<Vec<u8>> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let object = value.as_object_mut()?;
+    object.insert(
+        "degraded".to_string(),
+        serde_json::to_value(degradations).ok()?,
+    );
+    serde_json::to_vec(&value).ok()
+}
+
+/// Inserts the network into a JSON object body, unless it already names one, e.g.
+/// as fetched from the backend of a canary environment. Returns `None` for other
+/// bodies.
+fn with_network_field(body: &[u8], network: &NetworkId) -> Option<Vec<u8>> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let object = value.as_object_mut()?;
+    if !object.contains_key("network") {
+        object.insert("network".to_string(), serde_json::to_value(network).ok()?);
+    }
+    serde_json::to_vec(&value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{with_degraded_field, with_fields, with_network_field, NetworkId};
+    use crate::{degradation::Degradation, network::FailureKind};
+    use chrono::{TimeZone, Utc};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_with_network_field() {
+        let network = NetworkId::new("testnet".to_string(), Some(2892));
+        let body = json!({ "deposits": [] }).to_string();
+
+        let updated = with_network_field(body.as_bytes(), &network).unwrap();
+        let updated: Value = serde_json::from_slice(&updated).unwrap();
+        assert_eq!(
+            updated,
+            json!({ "deposits": [], "network": { "name": "testnet", "chain_id": 2892 } })
+        );
+
+        // Already tagged by the backend it was fetched from
+        let body = json!({ "network": { "name": "mock", "chain_id": null } }).to_string();
+        let updated = with_network_field(body.as_bytes(), &network).unwrap();
+        let updated: Value = serde_json::from_slice(&updated).unwrap();
+        assert_eq!(updated["network"]["name"], "mock");
+    }
+
+    #[test]
+    fn test_with_fields() {
+        let body = json!({ "deposits": [1], "withdrawals": [2], "operators": [] }).to_string();
+        let fields = ["deposits".to_string(), "unknown".to_string()];
+
+        let pruned = with_fields(body.as_bytes(), &fields).unwrap();
+        let pruned: Value = serde_json::from_slice(&pruned).unwrap();
+        assert_eq!(pruned, json!({ "deposits": [1] }));
+        assert!(with_fields(b"[1, 2]", &fields).is_none());
+    }
+
+    #[test]
+    fn test_with_degraded_field() {
+        let degradations = [Degradation {
+            upstream: "explorer",
+            since: Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap(),
+            error_class: FailureKind::Timeout,
+        }];
+        let body = json!({ "deposits": [] }).to_string();
+
+        let updated = with_degraded_field(body.as_bytes(), &degradations).unwrap();
+        let updated: Value = serde_json::from_slice(&updated).unwrap();
+        assert_eq!(
+            updated,
+            json!({
+                "deposits": [],
+                "degraded": [{
+                    "upstream": "explorer",
+                    "since": "2025-03-10T12:00:00Z",
+                    "error_class": "timeout",
+                }],
+            })
+        );
+        assert!(with_degraded_field(b"[1, 2]", &degradations).is_none());
+    }
+
+    #[test]
+    fn test_non_object_bodies_are_untouched() {
+        let network = NetworkId::new("testnet".to_string(), None);
+        assert!(with_network_field(b"[1, 2]", &network).is_none());
+        assert!(with_network_field(b"not json", &network).is_none());
+    }
+}
@@ -1,3 +1,60 @@
+use jsonrpsee::{core::ClientError, http_client::transport::Error as HttpTransportError};
+use reqwest::StatusCode;
+
+/// Whether a failed upstream request may succeed if sent again
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Timeouts, refused connections and server-side failures
+    Retryable,
+    /// Requests the upstream rejected or whose response cannot be parsed, which fail
+    /// the same way every time
+    Fatal,
+}
+
+/// Classifies an HTTP response status: 5xx, 429 and 408 are worth retrying
+pub fn classify_status(status: StatusCode) -> ErrorClass {
+    if status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+    {
+        ErrorClass::Retryable
+    } else {
+        ErrorClass::Fatal
+    }
+}
+
+/// Classifies a failed HTTP request
+pub fn classify_http_error(e: &reqwest::Error) -> ErrorClass {
+    if let Some(status) = e.status() {
+        return classify_status(status);
+    }
+    if e.is_decode() || e.is_builder() || e.is_redirect() {
+        ErrorClass::Fatal
+    } else {
+        // Timeouts, connection and body errors
+        ErrorClass::Retryable
+    }
+}
+
+/// Classifies a failed JSON-RPC call. Errors returned by the server in its response
+/// are fatal, the request reached it and was answered.
+pub fn classify_rpc_error(e: &ClientError) -> ErrorClass {
+    match e {
+        ClientError::Transport(e) => match e.downcast_ref::<HttpTransportError>() {
+            Some(HttpTransportError::Rejected { status_code }) => {
+                StatusCode::from_u16(*status_code).map_or(ErrorClass::Fatal, classify_status)
+            }
+            Some(HttpTransportError::Url(_)) | Some(HttpTransportError::RequestTooLarge) => {
+                ErrorClass::Fatal
+            }
+            // Connection refused or reset, DNS failures
+            _ => ErrorClass::Retryable,
+        },
+        ClientError::RequestTimeout | ClientError::RestartNeeded(_) => ErrorClass::Retryable,
+        _ => ErrorClass::Fatal,
+    }
+}
+
 /// Exponential backoff for rpc requests
 ///
 /// NOTE: This is borrowed from prover code.
@@ -20,6 +77,11 @@ impl ExponentialBackoff {
         }
     }
 
+    /// Getter for `max_retries`
+    pub fn max_retries(&self) -> u64 {
+        self.max_retries
+    }
+
     /// Returns the delay in seconds.
     pub fn get_delay(&self, retry_counter: u64) -> u64 {
         if retry_counter == 0 {
@@ -38,7 +100,38 @@ impl ExponentialBackoff {
 
 #[cfg(test)]
 mod tests {
-    use super::ExponentialBackoff;
+    use super::{classify_rpc_error, classify_status, ErrorClass, ExponentialBackoff};
+    use jsonrpsee::{core::ClientError, types::ErrorObjectOwned};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_classify_errors() {
+        assert_eq!(
+            classify_status(StatusCode::BAD_GATEWAY),
+            ErrorClass::Retryable
+        );
+        assert_eq!(
+            classify_status(StatusCode::TOO_MANY_REQUESTS),
+            ErrorClass::Retryable
+        );
+        assert_eq!(classify_status(StatusCode::NOT_FOUND), ErrorClass::Fatal);
+        assert_eq!(classify_status(StatusCode::UNAUTHORIZED), ErrorClass::Fatal);
+
+        assert_eq!(
+            classify_rpc_error(&ClientError::RequestTimeout),
+            ErrorClass::Retryable
+        );
+        let method_not_found = ErrorObjectOwned::owned(-32601, "Method not found", None::<()>);
+        assert_eq!(
+            classify_rpc_error(&ClientError::Call(method_not_found)),
+            ErrorClass::Fatal
+        );
+        let parse_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(
+            classify_rpc_error(&ClientError::ParseError(parse_error)),
+            ErrorClass::Fatal
+        );
+    }
 
     #[test]
     fn test_total_time() {
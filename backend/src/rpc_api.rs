@@ -0,0 +1,142 @@
+//! JSON-RPC interface to the dashboard data, for internal tools that already speak
+//! JSON-RPC, served on `RPC_LISTEN_ADDR` next to the REST API.
+//!
+//! Methods are in the `dashboard` namespace, e.g. `dashboard_getBridgeStatus`, and
+//! return the shared states as the monitoring tasks last refreshed them: status
+//! messages are not translated and txids keep the display byte order. API tokens
+//! are not checked, so the server is meant for an internal interface only.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    server::{ServerBuilder, ServerHandle},
+};
+use std::net::SocketAddr;
+use tracing::info;
+
+use crate::{
+    activity::ActivityStats, bridge::BridgeStatus, bundler::BundlerStats, network::NetworkStatus,
+    wallets::PaymasterWallets, SharedStates,
+};
+
+#[rpc(server, namespace = "dashboard")]
+pub trait DashboardApi {
+    /// Latest network status, as at `/api/status`
+    #[method(name = "getNetworkStatus")]
+    async fn get_network_status(&self) -> RpcResult<NetworkStatus>;
+
+    /// Latest balances of the paymaster wallets, as at `/api/balances`
+    #[method(name = "getPaymasterWallets")]
+    async fn get_paymaster_wallets(&self) -> RpcResult<PaymasterWallets>;
+
+    /// Latest activity stats, as at `/api/activity_stats`
+    #[method(name = "getActivityStats")]
+    async fn get_activity_stats(&self) -> RpcResult<ActivityStats>;
+
+    /// Latest bridge status, as at `/api/bridge_status`
+    #[method(name = "getBridgeStatus")]
+    async fn get_bridge_status(&self) -> RpcResult<BridgeStatus>;
+
+    /// Latest bundler stats, as at `/api/bundler_stats`
+    #[method(name = "getBundlerStats")]
+    async fn get_bundler_stats(&self) -> RpcResult<BundlerStats>;
+}
+
+/// Serves the shared states over JSON-RPC
+pub struct DashboardRpc {
+    states: SharedStates,
+}
+
+impl DashboardRpc {
+    pub fn new(states: SharedStates) -> Self {
+        Self { states }
+    }
+}
+
+#[async_trait]
+impl DashboardApiServer for DashboardRpc {
+    async fn get_network_status(&self) -> RpcResult<NetworkStatus> {
+        Ok(self.states.network.read().await.clone())
+    }
+
+    async fn get_paymaster_wallets(&self) -> RpcResult<PaymasterWallets> {
+        Ok(self.states.wallets.read().await.clone())
+    }
+
+    async fn get_activity_stats(&self) -> RpcResult<ActivityStats> {
+        Ok(self.states.activity.read().await.clone())
+    }
+
+    async fn get_bridge_status(&self) -> RpcResult<BridgeStatus> {
+        Ok(self.states.bridge.read().await.clone())
+    }
+
+    async fn get_bundler_stats(&self) -> RpcResult<BundlerStats> {
+        Ok(self.states.bundler.read().await.clone())
+    }
+}
+
+/// Starts the JSON-RPC server on `addr`. It runs until the returned handle is
+/// stopped or dropped.
+pub async fn start_rpc_server(
+    addr: SocketAddr,
+    states: SharedStates,
+) -> anyhow::Result<ServerHandle> {
+    let server = ServerBuilder::default()
+        .build(addr)
+        .await
+        .context("failed to build JSON-RPC server")?;
+    let handle = server.start(DashboardRpc::new(states).into_rpc());
+    info!(%addr, "JSON-RPC server running");
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DashboardApiServer, DashboardRpc};
+    use crate::{
+        activity::ActivityStats,
+        config::ActivityMonitoringConfig,
+        wallets::{PaymasterWallets, Wallet},
+        SharedStates,
+    };
+    use serde_json::Value;
+    use std::{collections::BTreeMap, sync::Arc};
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_dashboard_rpc() {
+        let wallet = || Wallet::new("0x01".to_string(), "0".to_string(), "BTC");
+        let states = SharedStates {
+            network: Arc::default(),
+            wallets: Arc::new(RwLock::new(PaymasterWallets::new(
+                wallet(),
+                wallet(),
+                BTreeMap::new(),
+            ))),
+            activity: Arc::new(RwLock::new(ActivityStats::default(
+                &ActivityMonitoringConfig::new(),
+            ))),
+            bridge: Arc::default(),
+            bundler: Arc::default(),
+        };
+        let module = DashboardRpc::new(states).into_rpc();
+
+        let bridge_status: Value = module
+            .call("dashboard_getBridgeStatus", Vec::<()>::new())
+            .await
+            .unwrap();
+        assert!(bridge_status["deposits"].is_array());
+        let wallets: Value = module
+            .call("dashboard_getPaymasterWallets", Vec::<()>::new())
+            .await
+            .unwrap();
+        assert_eq!(wallets["deposit"]["address"], "0x01");
+        assert!(module
+            .call::<_, Value>("dashboard_getStatus", Vec::<()>::new())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Service level objectives declared in `SLOS`, and the error budget left of each
+//! over its window, computed from the status history and the bridge change log.
+//!
+//! The burn rate is the share of bad events over the last `SLO_BURN_RATE_WINDOW_S`
+//! divided by the error budget: at 1 the budget lasts exactly the window, at 10
+//! during an incident it is exhausted in a tenth of it.
+
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{
+    bridge_changes::{ObservedWithdrawal, SharedBridgeChanges},
+    config::SloConfig,
+    network::Status,
+    status_history::{SharedStatusHistory, StatusSample},
+};
+
+fn default_window_days() -> u64 {
+    30
+}
+
+/// Component of the status history
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    BatchProducer,
+    RpcEndpoint,
+    BundlerEndpoint,
+}
+
+impl Component {
+    fn status(self, sample: &StatusSample) -> &Status {
+        match self {
+            Component::BatchProducer => &sample.batch_producer,
+            Component::RpcEndpoint => &sample.rpc_endpoint,
+            Component::BundlerEndpoint => &sample.bundler_endpoint,
+        }
+    }
+}
+
+/// Objective declared in config, see `SLOS` in `.env.example`
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SloSpec {
+    /// Share of status polls with the component online
+    Uptime {
+        name: String,
+        component: Component,
+        /// Share of good events, e.g. `0.995`
+        target: f64,
+        #[serde(default = "default_window_days")]
+        window_days: u64,
+    },
+    /// Share of withdrawals fulfilled within `within_s` of being requested
+    WithdrawalFulfillment {
+        name: String,
+        within_s: u64,
+        /// Share of good events, e.g. `0.99`
+        target: f64,
+        #[serde(default = "default_window_days")]
+        window_days: u64,
+    },
+}
+
+impl SloSpec {
+    pub fn name(&self) -> &str {
+        match self {
+            SloSpec::Uptime { name, .. } | SloSpec::WithdrawalFulfillment { name, .. } => name,
+        }
+    }
+
+    pub fn target(&self) -> f64 {
+        match self {
+            SloSpec::Uptime { target, .. } | SloSpec::WithdrawalFulfillment { target, .. } => {
+                *target
+            }
+        }
+    }
+
+    fn window(&self) -> Duration {
+        match self {
+            SloSpec::Uptime { window_days, .. }
+            | SloSpec::WithdrawalFulfillment { window_days, .. } => {
+                Duration::days(*window_days as i64)
+            }
+        }
+    }
+}
+
+/// Good or bad outcome of an event, at the time it was decided
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SloEvent {
+    at: DateTime<Utc>,
+    good: bool,
+}
+
+/// A status poll is good while the component is online
+fn uptime_events<'a>(
+    samples: impl Iterator<Item = &'a StatusSample> + 'a,
+    component: Component,
+) -> impl Iterator<Item = SloEvent> + 'a {
+    samples.map(move |sample| SloEvent {
+        at: sample.at,
+        good: *component.status(sample) == Status::Online,
+    })
+}
+
+/// A withdrawal is good once fulfilled within `within` of its request, and bad
+/// once fulfilled later or still pending past that; pending ones are undecided
+fn fulfillment_events(
+    withdrawals: &[ObservedWithdrawal],
+    within: Duration,
+    now: DateTime<Utc>,
+) -> Vec<SloEvent> {
+    withdrawals
+        .iter()
+        .filter_map(|withdrawal| {
+            let deadline = withdrawal.requested_at + within;
+            match withdrawal.fulfilled_at {
+                Some(fulfilled_at) if fulfilled_at <= deadline => Some(SloEvent {
+                    at: fulfilled_at,
+                    good: true,
+                }),
+                _ if deadline <= now => Some(SloEvent {
+                    at: deadline,
+                    good: false,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Compliance with an objective and the error budget left
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SloReport {
+    name: String,
+    target: f64,
+    window_days: i64,
+    /// Events decided within the window
+    events: u64,
+    bad_events: u64,
+    /// Share of good events, unset without events
+    sli: Option<f64>,
+    /// Share of bad events allowed, `1 - target`
+    error_budget: f64,
+    /// Share of the error budget left, negative once exceeded
+    budget_remaining: Option<f64>,
+    /// Pace at which the budget is spent over the burn rate window, unset without
+    /// events in it
+    burn_rate: Option<f64>,
+    /// Start of the history the events are computed from, when later than the
+    /// start of the window
+    covered_since: Option<DateTime<Utc>>,
+}
+
+/// Share of bad events, unset without events
+fn bad_ratio(events: &[SloEvent]) -> Option<f64> {
+    let bad = events.iter().filter(|event| !event.good).count();
+    (!events.is_empty()).then(|| bad as f64 / events.len() as f64)
+}
+
+fn slo_report(
+    spec: &SloSpec,
+    events: Vec<SloEvent>,
+    history_since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    burn_rate_window: Duration,
+) -> SloReport {
+    let window_start = now - spec.window();
+    let events: Vec<SloEvent> = events
+        .into_iter()
+        .filter(|event| event.at > window_start && event.at <= now)
+        .collect();
+    let recent: Vec<SloEvent> = events
+        .iter()
+        .copied()
+        .filter(|event| event.at > now - burn_rate_window)
+        .collect();
+
+    let error_budget = 1.0 - spec.target();
+    let bad_events = events.iter().filter(|event| !event.good).count() as u64;
+    let bad = bad_ratio(&events);
+    SloReport {
+        name: spec.name().to_string(),
+        target: spec.target(),
+        window_days: spec.window().num_days(),
+        events: events.len() as u64,
+        bad_events,
+        sli: bad.map(|bad| 1.0 - bad),
+        error_budget,
+        budget_remaining: bad.map(|bad| 1.0 - bad / error_budget),
+        burn_rate: bad_ratio(&recent).map(|bad| bad / error_budget),
+        covered_since: history_since.filter(|since| *since > window_start),
+    }
+}
+
+/// Reports of every declared objective
+#[derive(Serialize, Debug)]
+pub struct SloResponse {
+    burn_rate_window_s: i64,
+    slos: Vec<SloReport>,
+    computed_at: DateTime<Utc>,
+}
+
+/// Handler returning the error budget left of each objective
+pub async fn get_slos(
+    history: SharedStatusHistory,
+    changes: SharedBridgeChanges,
+    config: Arc<SloConfig>,
+) -> Json<SloResponse> {
+    let now = Utc::now();
+    let burn_rate_window = Duration::seconds(config.burn_rate_window_s() as i64);
+    let history = history.read().await;
+    let changes = changes.read().await;
+    let withdrawals = changes.observed_withdrawals();
+
+    let slos = config
+        .slos()
+        .iter()
+        .map(|spec| {
+            let (events, history_since) = match spec {
+                SloSpec::Uptime { component, .. } => {
+                    let samples = history.since(now - spec.window());
+                    (
+                        uptime_events(samples.iter(), *component).collect(),
+                        history.available_since(),
+                    )
+                }
+                SloSpec::WithdrawalFulfillment { within_s, .. } => (
+                    fulfillment_events(&withdrawals, Duration::seconds(*within_s as i64), now),
+                    changes.available_since(),
+                ),
+            };
+            slo_report(spec, events, history_since, now, burn_rate_window)
+        })
+        .collect();
+
+    Json(SloResponse {
+        burn_rate_window_s: burn_rate_window.num_seconds(),
+        slos,
+        computed_at: now,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fulfillment_events, slo_report, uptime_events, Component, SloEvent, SloSpec};
+    use crate::{
+        bridge_changes::ObservedWithdrawal, network::Status, status_history::StatusSample,
+    };
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn test_uptime_error_budget() {
+        let spec: SloSpec = serde_json::from_value(json!({
+            "type": "uptime",
+            "name": "rpc",
+            "component": "rpc_endpoint",
+            "target": 0.99,
+        }))
+        .unwrap();
+        let now = Utc::now();
+        // 200 polls a minute apart, the 2 most recent ones offline
+        let samples: Vec<StatusSample> = (0..200)
+            .map(|i| StatusSample {
+                at: now - Duration::minutes(199 - i),
+                batch_producer: Status::Online,
+                rpc_endpoint: if i >= 198 {
+                    Status::Offline
+                } else {
+                    Status::Online
+                },
+                bundler_endpoint: Status::Online,
+            })
+            .collect();
+
+        let events = uptime_events(samples.iter(), Component::RpcEndpoint).collect();
+        let report = slo_report(
+            &spec,
+            events,
+            samples.first().map(|s| s.at),
+            now,
+            Duration::hours(1),
+        );
+
+        assert_eq!(report.events, 200);
+        assert_eq!(report.bad_events, 2);
+        assert_eq!(report.window_days, 30);
+        assert!((report.sli.unwrap() - 0.99).abs() < 1e-9);
+        // The whole budget is spent
+        assert!(report.budget_remaining.unwrap().abs() < 1e-9);
+        // 2 bad polls out of the last 60, against 1% allowed
+        assert!((report.burn_rate.unwrap() - 2.0 / 60.0 / 0.01).abs() < 1e-6);
+        assert!(report.covered_since.is_some());
+    }
+
+    #[test]
+    fn test_fulfillment_events() {
+        let now = Utc::now();
+        let within = Duration::hours(24);
+        let withdrawal = |requested_h: i64, fulfilled_h: Option<i64>| ObservedWithdrawal {
+            requested_at: now - Duration::hours(requested_h),
+            fulfilled_at: fulfilled_h.map(|h| now - Duration::hours(h)),
+        };
+        let events = fulfillment_events(
+            &[
+                // Fulfilled in 2h
+                withdrawal(10, Some(8)),
+                // Fulfilled in 30h
+                withdrawal(40, Some(10)),
+                // Pending past the deadline
+                withdrawal(30, None),
+                // Pending, still within the deadline
+                withdrawal(2, None),
+            ],
+            within,
+            now,
+        );
+        assert_eq!(
+            events,
+            vec![
+                SloEvent {
+                    at: now - Duration::hours(8),
+                    good: true,
+                },
+                SloEvent {
+                    at: now - Duration::hours(16),
+                    good: false,
+                },
+                SloEvent {
+                    at: now - Duration::hours(6),
+                    good: false,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! `smoke-test` subcommand verifying a running backend, e.g. after a deploy. Every
+//! API route taking no path parameters is fetched and its body parsed into the
+//! response type it is served from, and the monitoring tasks must not be stale.
+//! With `--max-age`, the monitored states are also requested with `?max_age=` so
+//! that a backend serving old data fails the run.
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    activity::ActivityStats, bridge::BridgeStatus, bundler::BundlerStats, network::NetworkStatus,
+};
+
+/// Max time a route may take to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Validation of a response body
+type BodyCheck = fn(&[u8]) -> Result<(), String>;
+
+/// Route fetched by the smoke test
+struct Route {
+    path: &'static str,
+    check: BodyCheck,
+    /// Whether the route serves a monitored state honoring `?max_age=`
+    monitored: bool,
+}
+
+/// Routes fetched by the smoke test. Streams, exports and the explorer proxy are
+/// left out, as are routes needing path parameters.
+const ROUTES: &[Route] = &[
+    Route {
+        path: "/healthz",
+        check: is_ok_text,
+        monitored: false,
+    },
+    Route {
+        path: "/api/status",
+        check: parses_as::<NetworkStatus>,
+        monitored: true,
+    },
+    Route {
+        path: "/api/status/history",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/status/annotations",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/chain_info",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/slo",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/balances",
+        check: parses_as::<Map<String, Value>>,
+        monitored: true,
+    },
+    Route {
+        path: "/api/balances/history",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bridge_status",
+        check: parses_as::<BridgeStatus>,
+        monitored: true,
+    },
+    Route {
+        path: "/api/bridge/liability",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bridge/anomalies",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bridge/volume",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bridge/watchlist",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bridge/changes",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bundler_stats",
+        check: parses_as::<BundlerStats>,
+        monitored: true,
+    },
+    Route {
+        path: "/api/bundles",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bundles/ops_per_minute",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/bundles/range",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/activity_stats",
+        check: parses_as::<ActivityStats>,
+        monitored: true,
+    },
+    Route {
+        path: "/api/activity/heatmap",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/alerts",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/incidents",
+        check: parses_as::<Value>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/overview",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/tasks",
+        check: has_no_stale_task,
+        monitored: false,
+    },
+    Route {
+        path: "/api/diagnostics/runtime",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/diagnostics/clock",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+    Route {
+        path: "/api/version",
+        check: parses_as::<Map<String, Value>>,
+        monitored: false,
+    },
+];
+
+fn parses_as<T: DeserializeOwned>(body: &[u8]) -> Result<(), String> {
+    serde_json::from_slice::<T>(body)
+        .map(|_| ())
+        .map_err(|e| format!("unexpected body: {}", e))
+}
+
+fn is_ok_text(body: &[u8]) -> Result<(), String> {
+    match body {
+        b"ok" => Ok(()),
+        _ => Err(format!(
+            "expected `ok`, got `{}`",
+            String::from_utf8_lossy(body)
+        )),
+    }
+}
+
+/// Freshness of a task as listed at `/api/tasks`
+#[derive(Deserialize, Debug)]
+struct TaskFreshness {
+    stale: bool,
+    age_s: Option<i64>,
+}
+
+fn has_no_stale_task(body: &[u8]) -> Result<(), String> {
+    let tasks: BTreeMap<String, TaskFreshness> =
+        serde_json::from_slice(body).map_err(|e| format!("unexpected body: {}", e))?;
+    let stale: Vec<String> = tasks
+        .iter()
+        .filter(|(_, task)| task.stale)
+        .map(|(name, task)| match task.age_s {
+            Some(age_s) => format!("{} ({} s old)", name, age_s),
+            None => format!("{} (never refreshed)", name),
+        })
+        .collect();
+    if stale.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("stale tasks: {}", stale.join(", ")))
+    }
+}
+
+async fn check_route(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+    check: BodyCheck,
+) -> Result<(), String> {
+    let mut request = client.get(url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, String::from_utf8_lossy(&body)));
+    }
+    check(&body)
+}
+
+/// Checks every route of the backend at `base_url`, printing the outcome of each.
+/// Returns the process exit code, non-zero if any check failed.
+pub async fn run(base_url: &str, token: Option<&str>, max_age: Option<&str>) -> i32 {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to create HTTP client: {}", e);
+            return 2;
+        }
+    };
+
+    let base_url = base_url.trim_end_matches('/');
+    let mut failures = 0;
+    for route in ROUTES {
+        let url = match max_age {
+            Some(max_age) if route.monitored => {
+                format!("{}{}?max_age={}", base_url, route.path, max_age)
+            }
+            _ => format!("{}{}", base_url, route.path),
+        };
+        match check_route(&client, &url, token, route.check).await {
+            Ok(()) => println!("ok   {}", route.path),
+            Err(e) => {
+                println!("FAIL {}: {}", route.path, e);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        eprintln!("{} of {} routes failed", failures, ROUTES.len());
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_no_stale_task, is_ok_text, parses_as};
+    use crate::bridge::BridgeStatus;
+    use serde_json::json;
+
+    #[test]
+    fn test_body_checks() {
+        let status = serde_json::to_vec(&BridgeStatus::default()).unwrap();
+        assert!(parses_as::<BridgeStatus>(&status).is_ok());
+        assert!(parses_as::<BridgeStatus>(b"[]").is_err());
+        assert!(is_ok_text(b"ok").is_ok());
+        assert!(is_ok_text(b"<html>").is_err());
+
+        let tasks = json!({
+            "bridge_status": { "interval_s": 60, "stale": false, "age_s": 12 },
+            "activity_stats": { "interval_s": 60, "stale": true, "age_s": null },
+        });
+        assert_eq!(
+            has_no_stale_task(tasks.to_string().as_bytes()),
+            Err("stale tasks: activity_stats (never refreshed)".to_string())
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Semantic diff of two `/api/admin/state_dump` snapshots, for triaging reports of
+//! the dashboard changing unexpectedly.
+
+use anyhow::Context;
+use serde_json::{Map, Value};
+use std::{fmt, fs, path::Path};
+
+/// Lists whose entries are matched by a key field rather than by position
+const KEYED_LISTS: [(&str, &str); 7] = [
+    ("bridge_status.operators", "operator_id"),
+    ("bridge_status.deposits", "deposit_request_txid"),
+    ("bridge_status.withdrawals", "withdrawal_request_txid"),
+    ("bridge_status.reimbursements", "claim_txid"),
+    ("bridge_status.fees.deposits", "deposit_request_txid"),
+    ("bridge_status.fees.withdrawals", "withdrawal_request_txid"),
+    ("bridge_status.fees.reimbursements", "claim_txid"),
+];
+
+/// Fields that change on every poll, as in `diff_bridge_status`
+const IGNORED_FIELDS: [&str; 5] = [
+    "captured_at",
+    "responsiveness",
+    "duty_queue_depth",
+    "duty_queue_history",
+    "deposit_ages",
+];
+
+/// Difference between two snapshots, at a path like `bridge_status.deposits[<txid>].status`
+#[derive(Debug, PartialEq)]
+pub enum Difference {
+    Added(String),
+    Removed(String),
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Added(path) => write!(f, "+ {}", path),
+            Difference::Removed(path) => write!(f, "- {}", path),
+            Difference::Changed { path, old, new } => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+fn child_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn diff_objects(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    out: &mut Vec<Difference>,
+) {
+    for (field, old_value) in old {
+        if IGNORED_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+        let field_path = child_path(path, field);
+        match new.get(field) {
+            Some(new_value) => diff_values(&field_path, old_value, new_value, out),
+            None => out.push(Difference::Removed(field_path)),
+        }
+    }
+    for field in new.keys() {
+        if !old.contains_key(field) && !IGNORED_FIELDS.contains(&field.as_str()) {
+            out.push(Difference::Added(child_path(path, field)));
+        }
+    }
+}
+
+/// Entries of a keyed list by key, in list order
+fn keyed<'a>(entries: &'a [Value], key: &str) -> Vec<(String, &'a Value)> {
+    entries
+        .iter()
+        .map(|entry| {
+            let key = match entry.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => "?".to_string(),
+            };
+            (key, entry)
+        })
+        .collect()
+}
+
+fn diff_keyed_lists(
+    path: &str,
+    key: &str,
+    old: &[Value],
+    new: &[Value],
+    out: &mut Vec<Difference>,
+) {
+    let (old, new) = (keyed(old, key), keyed(new, key));
+    for (entry_key, old_entry) in &old {
+        let entry_path = format!("{}[{}]", path, entry_key);
+        match new.iter().find(|(k, _)| k == entry_key) {
+            Some((_, new_entry)) => diff_values(&entry_path, old_entry, new_entry, out),
+            None => out.push(Difference::Removed(entry_path)),
+        }
+    }
+    for (entry_key, _) in &new {
+        if !old.iter().any(|(k, _)| k == entry_key) {
+            out.push(Difference::Added(format!("{}[{}]", path, entry_key)));
+        }
+    }
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, out: &mut Vec<Difference>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_objects(path, old, new, out),
+        (Value::Array(old), Value::Array(new)) => {
+            match KEYED_LISTS.iter().find(|(list, _)| *list == path) {
+                Some((_, key)) => diff_keyed_lists(path, key, old, new, out),
+                None if old != new => out.push(Difference::Changed {
+                    path: path.to_string(),
+                    old: Value::Array(old.clone()),
+                    new: Value::Array(new.clone()),
+                }),
+                None => {}
+            }
+        }
+        _ if old != new => out.push(Difference::Changed {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Differences from the `old` snapshot to the `new` one
+pub fn diff_snapshots(old: &Value, new: &Value) -> Vec<Difference> {
+    let mut out = Vec::new();
+    diff_values("", old, new, &mut out);
+    out
+}
+
+fn load(path: &Path) -> Result<Value, anyhow::Error> {
+    let data = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    serde_json::from_str(&data).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+/// Prints the differences between two snapshot files.
+///
+/// Returns the exit code, like `diff`: 0 if identical, 1 if different, 2 on error.
+pub fn run(old: &Path, new: &Path) -> i32 {
+    let snapshots = load(old).and_then(|old| Ok((old, load(new)?)));
+    let (old, new) = match snapshots {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return 2;
+        }
+    };
+
+    let differences = diff_snapshots(&old, &new);
+    for difference in &differences {
+        println!("{}", difference);
+    }
+    if differences.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_snapshots, Difference};
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let old = json!({
+            "captured_at": "2025-03-10T12:00:00Z",
+            "network_status": { "batch_producer": "online", "rpc_endpoint": "online" },
+            "bridge_status": {
+                "operators": [
+                    { "operator_id": "Alpen Labs #0", "status": "Online", "duty_queue_depth": 1 },
+                ],
+                "deposits": [
+                    { "deposit_request_txid": "aa", "status": "In progress" },
+                    { "deposit_request_txid": "bb", "status": "Complete" },
+                ],
+            },
+        });
+        let new = json!({
+            "captured_at": "2025-03-10T13:00:00Z",
+            "network_status": { "batch_producer": "stalled", "rpc_endpoint": "online" },
+            "bridge_status": {
+                "operators": [
+                    { "operator_id": "Alpen Labs #0", "status": "Online", "duty_queue_depth": 7 },
+                ],
+                "deposits": [
+                    { "deposit_request_txid": "cc", "status": "In progress" },
+                    { "deposit_request_txid": "aa", "status": "Complete" },
+                ],
+            },
+        });
+
+        assert_eq!(
+            diff_snapshots(&old, &new),
+            vec![
+                Difference::Changed {
+                    path: "bridge_status.deposits[aa].status".to_string(),
+                    old: json!("In progress"),
+                    new: json!("Complete"),
+                },
+                Difference::Removed("bridge_status.deposits[bb]".to_string()),
+                Difference::Added("bridge_status.deposits[cc]".to_string()),
+                Difference::Changed {
+                    path: "network_status.batch_producer".to_string(),
+                    old: json!("online"),
+                    new: json!("stalled"),
+                },
+            ]
+        );
+        assert_eq!(
+            Difference::Added("bridge_status.deposits[cc]".to_string()).to_string(),
+            "+ bridge_status.deposits[cc]"
+        );
+        assert!(diff_snapshots(&old, &old).is_empty());
+    }
+}
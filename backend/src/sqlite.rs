@@ -0,0 +1,475 @@
+//! Optional SQLite storage of the persisted histories, keeping them in one
+//! database file rather than in one JSON lines file each.
+//!
+//! Each history is a table of its records serialized as JSON, created on startup:
+//!
+//! ```sql
+//! CREATE TABLE status_history (at TEXT NOT NULL, record TEXT NOT NULL)
+//! ```
+//!
+//! Times are RFC 3339 with a fixed number of fractional digits, so that they sort
+//! as text.
+//!
+//! Writes always go to the primary database. When a read replica is configured,
+//! e.g. a copy of the database file kept up to date by litestream or LiteFS, the
+//! range queries of the dashboards are served from it through [`ReplicaTable`], so
+//! that they neither wait on the writers nor add to the load of the primary.
+
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+use tokio::sync::mpsc::{self, error::TrySendError};
+#[cfg(test)]
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::store::{prune, range, truncate, StatsStore, Timestamped};
+
+/// Max number of writes applied in one transaction
+const MAX_BATCH_SIZE: usize = 256;
+
+/// Time as stored in the `at` column
+fn format_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+enum SqliteWrite {
+    Insert {
+        at: String,
+        record: String,
+    },
+    /// Deletes the records before `before`, or all of them if unset
+    Delete {
+        before: Option<String>,
+    },
+    /// Answered once every write sent before it is applied
+    #[cfg(test)]
+    Flush(oneshot::Sender<()>),
+}
+
+/// Path of the database file of a `sqlite://` URL, `url` itself if it is a path
+fn database_path(url: &str) -> Result<&str, anyhow::Error> {
+    let path = match url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+    {
+        Some(path) => path,
+        None if url.contains("://") => anyhow::bail!("Unsupported database URL {}", url),
+        None => url,
+    };
+    anyhow::ensure!(!path.is_empty(), "No database path in {}", url);
+    Ok(path)
+}
+
+fn lock(connection: &Mutex<Connection>) -> Result<MutexGuard<'_, Connection>, anyhow::Error> {
+    connection
+        .lock()
+        .map_err(|_| anyhow::anyhow!("SQLite connection poisoned"))
+}
+
+/// SQLite database the histories are stored in, shared by their stores
+#[derive(Clone, Debug)]
+pub struct SqliteDb {
+    connection: Arc<Mutex<Connection>>,
+    /// Read-only connection to the replica, if any
+    replica: Option<Arc<Mutex<Connection>>>,
+}
+
+impl SqliteDb {
+    /// Opens the database at `url`, a path or `sqlite://` URL, creating it if
+    /// needed, and its read replica at `replica_url` if set, which must exist
+    pub fn open(url: &str, replica_url: Option<&str>) -> Result<Self, anyhow::Error> {
+        let connection = Connection::open(database_path(url)?)
+            .with_context(|| format!("Failed to open SQLite database {}", url))?;
+        let replica = match replica_url {
+            Some(replica_url) => {
+                let replica = Connection::open_with_flags(
+                    database_path(replica_url)?,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY
+                        | OpenFlags::SQLITE_OPEN_URI
+                        | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                )
+                .with_context(|| format!("Failed to open SQLite replica {}", replica_url))?;
+                Some(Arc::new(Mutex::new(replica)))
+            }
+            None => None,
+        };
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            replica,
+        })
+    }
+
+    fn connection(&self) -> Result<MutexGuard<'_, Connection>, anyhow::Error> {
+        lock(&self.connection)
+    }
+
+    /// Table `table` of the read replica, `None` without a replica
+    pub fn replica_table(&self, table: &str) -> Option<ReplicaTable> {
+        Some(ReplicaTable {
+            replica: Arc::clone(self.replica.as_ref()?),
+            table: table.to_string(),
+        })
+    }
+
+    /// Creates `table` if needed, deletes its records older than `cutoff` and
+    /// returns the other ones, oldest first. Unparseable records are skipped.
+    fn load_table<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<T>, anyhow::Error> {
+        let connection = self.connection()?;
+        connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {0} (at TEXT NOT NULL, record TEXT NOT NULL); \
+             CREATE INDEX IF NOT EXISTS {0}_at ON {0} (at);",
+            table
+        ))?;
+        connection.execute(
+            &format!("DELETE FROM {} WHERE at < ?1", table),
+            params![format_time(cutoff)],
+        )?;
+
+        let mut statement =
+            connection.prepare(&format!("SELECT record FROM {} ORDER BY at, rowid", table))?;
+        let records = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|record| serde_json::from_str(&record.ok()?).ok())
+            .collect();
+        Ok(records)
+    }
+
+    /// Applies `writes` to `table` in a single transaction
+    fn apply(&self, table: &str, writes: Vec<SqliteWrite>) -> Result<(), anyhow::Error> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        for write in writes {
+            match write {
+                SqliteWrite::Insert { at, record } => {
+                    transaction.execute(
+                        &format!("INSERT INTO {} (at, record) VALUES (?1, ?2)", table),
+                        params![at, record],
+                    )?;
+                }
+                SqliteWrite::Delete {
+                    before: Some(before),
+                } => {
+                    transaction.execute(
+                        &format!("DELETE FROM {} WHERE at < ?1", table),
+                        params![before],
+                    )?;
+                }
+                SqliteWrite::Delete { before: None } => {
+                    transaction.execute(&format!("DELETE FROM {}", table), [])?;
+                }
+                #[cfg(test)]
+                SqliteWrite::Flush(_) => {}
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// Records of `table` at or after `from` and before `to`, oldest first.
+/// Unparseable records are skipped.
+fn query_range<T: DeserializeOwned>(
+    connection: &Connection,
+    table: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<T>, anyhow::Error> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT record FROM {} WHERE at >= ?1 AND at < ?2 ORDER BY at, rowid",
+        table
+    ))?;
+    let records = statement
+        .query_map(params![format_time(from), format_time(to)], |row| {
+            row.get::<_, String>(0)
+        })?
+        .filter_map(|record| serde_json::from_str(&record.ok()?).ok())
+        .collect();
+    Ok(records)
+}
+
+/// Table of the read replica of a [`SqliteDb`], to query ranges of the records of
+/// a [`SqliteStore`] off the primary. The replica may lag behind the records kept
+/// in memory.
+#[derive(Clone, Debug)]
+pub struct ReplicaTable {
+    replica: Arc<Mutex<Connection>>,
+    table: String,
+}
+
+impl ReplicaTable {
+    /// Records at or after `from` and before `to`, oldest first
+    pub async fn query_range<T: DeserializeOwned + Send + 'static>(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<T>, anyhow::Error> {
+        let table = self.clone();
+        // SQLite blocks, keep it off the runtime threads
+        tokio::task::spawn_blocking(move || {
+            query_range(&*lock(&table.replica)?, &table.table, from, to)
+        })
+        .await?
+    }
+}
+
+/// Records kept in memory and written to a table of a [`SqliteDb`] from a
+/// dedicated task, reloaded on startup.
+///
+/// As for the history files, records are dropped and counted rather than stalling
+/// the monitoring task when the database falls behind.
+#[derive(Debug)]
+pub struct SqliteStore<T> {
+    records: VecDeque<T>,
+    sender: mpsc::Sender<SqliteWrite>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<T: DeserializeOwned> SqliteStore<T> {
+    /// Loads the records of `table` from `cutoff` on, deleting the older ones, and
+    /// spawns the task writing to it. Up to `write_buffer` records wait to be
+    /// written before new ones are dropped.
+    pub fn load(
+        db: &SqliteDb,
+        table: &str,
+        cutoff: DateTime<Utc>,
+        write_buffer: usize,
+    ) -> Result<Self, anyhow::Error> {
+        let records: VecDeque<T> = db.load_table(table, cutoff)?.into();
+        info!(%table, records = records.len(), "Loaded history records from SQLite");
+
+        let (sender, receiver) = mpsc::channel(write_buffer.max(1));
+        tokio::spawn(write_batches(db.clone(), table.to_string(), receiver));
+        Ok(Self {
+            records,
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+}
+
+impl<T> SqliteStore<T> {
+    /// Waits until the writes queued so far are applied
+    #[cfg(test)]
+    pub async fn flush(&self) {
+        let (done, applied) = oneshot::channel();
+        if self.sender.send(SqliteWrite::Flush(done)).await.is_ok() {
+            let _ = applied.await;
+        }
+    }
+}
+
+impl<T> StatsStore<T> for SqliteStore<T>
+where
+    T: Timestamped + Serialize + Clone + fmt::Debug + Send + Sync,
+{
+    fn save_snapshot(&mut self, snapshot: T) {
+        match serde_json::to_string(&snapshot) {
+            Ok(record) => {
+                let write = SqliteWrite::Insert {
+                    at: format_time(snapshot.at()),
+                    record,
+                };
+                match self.sender.try_send(write) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => {
+                        let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                        warn!(%dropped, "SQLite write buffer full, dropping record");
+                    }
+                    Err(TrySendError::Closed(_)) => {
+                        warn!("SQLite writer stopped, dropping record")
+                    }
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to serialize history record"),
+        }
+        self.records.push_back(snapshot);
+    }
+
+    fn query_range(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<T> {
+        range(&self.records, from, to)
+    }
+
+    fn records(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.records.iter())
+    }
+
+    fn oldest(&self) -> Option<DateTime<Utc>> {
+        self.records.front().map(Timestamped::at)
+    }
+
+    /// Pruned records stay in the table until it is compacted
+    fn prune(&mut self, cutoff: DateTime<Utc>) {
+        prune(&mut self.records, cutoff);
+    }
+
+    /// Truncated records stay in the table until it is compacted
+    fn truncate(&mut self, max_records: usize) {
+        truncate(&mut self.records, max_records);
+    }
+
+    /// Deletes the records older than the ones kept in memory. Records written at
+    /// the same time as the oldest kept one stay in the table.
+    fn compact(&mut self) {
+        let before = self.records.front().map(|record| format_time(record.at()));
+        if self
+            .sender
+            .try_send(SqliteWrite::Delete { before })
+            .is_err()
+        {
+            warn!("SQLite write buffer full, skipping compaction");
+        }
+    }
+
+    fn dropped_writes(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Applies the queued writes to `table` in batches
+async fn write_batches(db: SqliteDb, table: String, mut receiver: mpsc::Receiver<SqliteWrite>) {
+    let mut messages = Vec::with_capacity(MAX_BATCH_SIZE);
+    while receiver.recv_many(&mut messages, MAX_BATCH_SIZE).await > 0 {
+        let mut writes = Vec::new();
+        #[cfg(test)]
+        let mut flushes = Vec::new();
+        for message in messages.drain(..) {
+            match message {
+                write @ (SqliteWrite::Insert { .. } | SqliteWrite::Delete { .. }) => {
+                    writes.push(write)
+                }
+                #[cfg(test)]
+                SqliteWrite::Flush(done) => flushes.push(done),
+            }
+        }
+
+        // SQLite blocks, keep it off the runtime threads
+        let (batch_db, batch_table) = (db.clone(), table.clone());
+        let result =
+            tokio::task::spawn_blocking(move || batch_db.apply(&batch_table, writes)).await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!(%table, error = %e, "Failed to write history records to SQLite"),
+            Err(e) => warn!(%table, error = %e, "SQLite writer task failed"),
+        }
+
+        #[cfg(test)]
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{database_path, SqliteDb, SqliteStore};
+    use crate::store::{StatsStore, Timestamped};
+    use chrono::{DateTime, Duration, Utc};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Sample {
+        at: DateTime<Utc>,
+        value: u64,
+    }
+
+    impl Timestamped for Sample {
+        fn at(&self) -> DateTime<Utc> {
+            self.at
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_persistence() {
+        let path =
+            std::env::temp_dir().join(format!("sqlite_store_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let now = Utc::now();
+        let sample = |hours, value| Sample {
+            at: now - Duration::hours(hours),
+            value,
+        };
+        let db = SqliteDb::open(&path, None).unwrap();
+        let mut store = SqliteStore::load(&db, "samples", now - Duration::days(1), 16).unwrap();
+        store.save_events(vec![sample(48, 0), sample(2, 1), sample(1, 2)]);
+        store.flush().await;
+
+        // The expired sample is still in the table but dropped on load
+        let mut reloaded: SqliteStore<Sample> =
+            SqliteStore::load(&db, "samples", now - Duration::days(1), 16).unwrap();
+        assert_eq!(
+            reloaded.records().cloned().collect::<Vec<_>>(),
+            vec![sample(2, 1), sample(1, 2)]
+        );
+
+        // Truncated records are deleted by compaction
+        reloaded.truncate(1);
+        reloaded.compact();
+        reloaded.flush().await;
+        let reloaded: SqliteStore<Sample> =
+            SqliteStore::load(&db, "samples", DateTime::<Utc>::MIN_UTC, 16).unwrap();
+        assert_eq!(
+            reloaded.query_range(DateTime::<Utc>::MIN_UTC, now),
+            vec![sample(1, 2)]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replica_range_queries() {
+        assert_eq!(
+            database_path("sqlite:///data/db.sqlite").unwrap(),
+            "/data/db.sqlite"
+        );
+        assert_eq!(database_path("sqlite:db.sqlite").unwrap(), "db.sqlite");
+        assert_eq!(database_path("db.sqlite").unwrap(), "db.sqlite");
+        assert!(database_path("postgres://localhost/db").is_err());
+
+        let path =
+            std::env::temp_dir().join(format!("sqlite_replica_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+        let url = format!("sqlite://{}", path);
+
+        // The replica must exist
+        assert!(SqliteDb::open(&url, Some("sqlite:///nonexistent/replica.db")).is_err());
+        let db = SqliteDb::open(&url, None).unwrap();
+        assert!(db.replica_table("samples").is_none());
+
+        let now = Utc::now();
+        let sample = |hours, value| Sample {
+            at: now - Duration::hours(hours),
+            value,
+        };
+        let mut store = SqliteStore::load(&db, "samples", now - Duration::days(1), 16).unwrap();
+        store.save_events(vec![sample(3, 0), sample(2, 1), sample(1, 2)]);
+        store.flush().await;
+
+        // The database file stands in for its replica
+        let db = SqliteDb::open(&url, Some(&path)).unwrap();
+        let replica = db.replica_table("samples").unwrap();
+        let samples: Vec<Sample> = replica
+            .query_range(now - Duration::hours(3), now - Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(samples, vec![sample(3, 0), sample(2, 1)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
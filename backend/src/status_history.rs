@@ -0,0 +1,348 @@
+use axum::{extract::Query, http::StatusCode, Json};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::error;
+
+use crate::{
+    annotations::{Annotation, Annotations},
+    network::{NetworkStatus, Status},
+    sqlite::{ReplicaTable, SqliteDb},
+    store::{open_store, StatsStore, Timestamped},
+    utils::parse_duration,
+};
+
+/// Max number of buckets a history query may return per component
+const MAX_BUCKETS: i64 = 5_000;
+
+/// Status of each component at one point in time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatusSample {
+    pub at: DateTime<Utc>,
+    pub batch_producer: Status,
+    pub rpc_endpoint: Status,
+    pub bundler_endpoint: Status,
+}
+
+impl Timestamped for StatusSample {
+    fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+}
+
+impl StatusSample {
+    pub fn new(at: DateTime<Utc>, status: &NetworkStatus) -> Self {
+        Self {
+            at,
+            batch_producer: status.batch_producer.clone(),
+            rpc_endpoint: status.rpc_endpoint.clone(),
+            bundler_endpoint: status.bundler_endpoint.clone(),
+        }
+    }
+}
+
+/// Network status samples within the retention period, oldest first.
+///
+/// The samples are persisted to the `status_history` table of the SQLite database
+/// or to the JSON lines file at the configured path, and reloaded on startup.
+/// Otherwise only the most recent samples are kept in memory, so the history may
+/// be shorter than the retention.
+#[derive(Debug)]
+pub struct StatusHistory {
+    store: Box<dyn StatsStore<StatusSample>>,
+    retention: Duration,
+}
+
+impl StatusHistory {
+    /// Creates the history, loading samples persisted in `sqlite` or else at `path`
+    /// within `retention`.
+    ///
+    /// Up to `write_buffer` samples wait to be persisted before new ones are
+    /// dropped. Without persistence, up to `buffer_len` samples are kept in memory.
+    pub fn load(
+        sqlite: Option<&SqliteDb>,
+        path: Option<String>,
+        retention: Duration,
+        write_buffer: usize,
+        buffer_len: usize,
+    ) -> Self {
+        let store = open_store(
+            sqlite,
+            "status_history",
+            path,
+            Utc::now() - retention,
+            write_buffer,
+            buffer_len,
+        );
+        Self { store, retention }
+    }
+
+    /// Records a sample, dropping samples past the retention period
+    pub fn record(&mut self, sample: StatusSample) {
+        let cutoff = sample.at - self.retention;
+        self.store.save_snapshot(sample);
+        self.store.prune(cutoff);
+    }
+
+    /// Time of the oldest retained sample
+    pub fn available_since(&self) -> Option<DateTime<Utc>> {
+        self.store.oldest()
+    }
+
+    /// Samples taken at or after `since`, oldest first
+    pub fn since(&self, since: DateTime<Utc>) -> Vec<StatusSample> {
+        self.store.query_range(since, DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Drops the samples past the retention period at `now`, including from the
+    /// persisted file, which otherwise only shrinks on restart
+    pub fn compact(&mut self, now: DateTime<Utc>) {
+        self.store.prune(now - self.retention);
+        self.store.compact();
+    }
+
+    /// Number of samples not persisted because the write buffer was full
+    pub fn dropped_writes(&self) -> u64 {
+        self.store.dropped_writes()
+    }
+}
+
+/// Shared network status history
+pub type SharedStatusHistory = Arc<RwLock<StatusHistory>>;
+
+/// Overall state of a component within a bucket, for colouring uptime bars
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UptimeStatus {
+    /// Online in every sample
+    Up,
+    /// Offline or stalled in some samples
+    Degraded,
+    /// Never online
+    Down,
+    /// No samples
+    Unknown,
+}
+
+/// Downsampled uptime of a component over one bucket
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct UptimeBucket {
+    pub start: DateTime<Utc>,
+    pub samples: usize,
+    /// Fraction of samples in which the component was online
+    pub uptime: Option<f64>,
+    pub status: UptimeStatus,
+}
+
+/// Groups statuses into consecutive buckets of `resolution` covering `[start, end)`
+pub fn downsample<'a>(
+    statuses: impl Iterator<Item = (DateTime<Utc>, &'a Status)>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    resolution: Duration,
+) -> Vec<UptimeBucket> {
+    let resolution_s = resolution.num_seconds().max(1);
+    let num_buckets = ((end - start).num_seconds().max(0) + resolution_s - 1) / resolution_s;
+    let mut counts = vec![(0usize, 0usize); num_buckets as usize];
+
+    for (at, status) in statuses {
+        if at < start || at >= end {
+            continue;
+        }
+        let index = ((at - start).num_seconds() / resolution_s) as usize;
+        counts[index].0 += 1;
+        if *status == Status::Online {
+            counts[index].1 += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(index, (samples, online))| {
+            let status = match (samples, online) {
+                (0, _) => UptimeStatus::Unknown,
+                (_, 0) => UptimeStatus::Down,
+                (samples, online) if samples == online => UptimeStatus::Up,
+                _ => UptimeStatus::Degraded,
+            };
+            UptimeBucket {
+                start: start + Duration::seconds(index as i64 * resolution_s),
+                samples,
+                uptime: if samples > 0 {
+                    Some(online as f64 / samples as f64)
+                } else {
+                    None
+                },
+                status,
+            }
+        })
+        .collect()
+}
+
+/// Query parameters of the status history endpoint
+#[derive(Deserialize, Debug)]
+pub struct StatusHistoryQuery {
+    /// Length of the history, e.g. `7d`
+    window: Option<String>,
+    /// Bucket size, e.g. `5m`
+    resolution: Option<String>,
+}
+
+/// Downsampled uptime series of each component
+#[derive(Serialize, Debug)]
+pub struct StatusHistoryResponse {
+    window_s: i64,
+    resolution_s: i64,
+    /// Time of the oldest retained sample; buckets before it are unknown rather
+    /// than down, e.g. as in-memory histories are shorter
+    available_since: Option<DateTime<Utc>>,
+    batch_producer: Vec<UptimeBucket>,
+    rpc_endpoint: Vec<UptimeBucket>,
+    bundler_endpoint: Vec<UptimeBucket>,
+    /// Annotations within the window, for overlaying on the uptime bars
+    annotations: Vec<Annotation>,
+}
+
+/// Handler returning the uptime history of each component, with the samples
+/// queried from the database replica when configured
+pub async fn get_status_history(
+    Query(query): Query<StatusHistoryQuery>,
+    history: SharedStatusHistory,
+    replica: Option<ReplicaTable>,
+    annotations: Annotations,
+) -> Result<Json<StatusHistoryResponse>, StatusCode> {
+    let window = parse_duration(query.window.as_deref().unwrap_or("7d"))
+        .filter(|window| *window > Duration::zero())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let resolution = parse_duration(query.resolution.as_deref().unwrap_or("5m"))
+        .filter(|resolution| *resolution >= Duration::seconds(1))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if window.num_seconds() / resolution.num_seconds() > MAX_BUCKETS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Align buckets to the resolution so consecutive requests return stable buckets
+    let end = Utc::now()
+        .duration_trunc(resolution)
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+        + resolution;
+    let start = end - window;
+
+    let annotations = annotations.between(start, end).await;
+    let samples = match replica {
+        Some(replica) => replica.query_range(start, end).await.map_err(|e| {
+            error!(error = %e, "Status history range query failed");
+            StatusCode::BAD_GATEWAY
+        })?,
+        None => history.read().await.store.query_range(start, end),
+    };
+    let series = |component: fn(&StatusSample) -> &Status| {
+        downsample(
+            samples.iter().map(|sample| (sample.at, component(sample))),
+            start,
+            end,
+            resolution,
+        )
+    };
+
+    Ok(Json(StatusHistoryResponse {
+        window_s: window.num_seconds(),
+        resolution_s: resolution.num_seconds(),
+        available_since: history.read().await.available_since(),
+        batch_producer: series(|sample| &sample.batch_producer),
+        rpc_endpoint: series(|sample| &sample.rpc_endpoint),
+        bundler_endpoint: series(|sample| &sample.bundler_endpoint),
+        annotations,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{downsample, StatusHistory, StatusSample, UptimeStatus};
+    use crate::network::Status;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_downsample() {
+        let start = Utc.timestamp_opt(0, 0).unwrap();
+        let at = |s| start + Duration::seconds(s);
+        let statuses = [
+            (at(-1), Status::Offline),
+            (at(0), Status::Online),
+            (at(59), Status::Online),
+            (at(60), Status::Online),
+            (at(90), Status::Stalled),
+            (at(120), Status::Offline),
+            (at(240), Status::Online),
+        ];
+
+        let buckets = downsample(
+            statuses.iter().map(|(at, status)| (*at, status)),
+            start,
+            at(240),
+            Duration::minutes(1),
+        );
+
+        let summary: Vec<_> = buckets
+            .iter()
+            .map(|bucket| (bucket.samples, bucket.uptime, bucket.status.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (2, Some(1.0), UptimeStatus::Up),
+                (2, Some(0.5), UptimeStatus::Degraded),
+                (1, Some(0.0), UptimeStatus::Down),
+                (0, None, UptimeStatus::Unknown),
+            ]
+        );
+        assert_eq!(buckets[1].start, at(60));
+    }
+
+    #[tokio::test]
+    async fn test_history_persistence_and_retention() {
+        let path =
+            std::env::temp_dir().join(format!("status_history_test_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let now = Utc::now();
+        let sample = |at| StatusSample {
+            at,
+            batch_producer: Status::Online,
+            rpc_endpoint: Status::Online,
+            bundler_endpoint: Status::Offline,
+        };
+
+        let mut history = StatusHistory::load(None, Some(path.clone()), Duration::days(1), 16, 16);
+        history.record(sample(now - Duration::days(2)));
+        history.record(sample(now));
+        assert_eq!(history.since(DateTime::<Utc>::MIN_UTC).len(), 1);
+        history.store.writer().unwrap().flush().await;
+
+        // The expired sample is still in the file but dropped on load
+        let reloaded = StatusHistory::load(None, Some(path.clone()), Duration::days(1), 16, 16);
+        assert_eq!(reloaded.since(DateTime::<Utc>::MIN_UTC), vec![sample(now)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_in_memory_history_is_bounded() {
+        let now = Utc::now();
+        let mut history = StatusHistory::load(None, None, Duration::days(1), 16, 2);
+        for i in 0..3 {
+            history.record(StatusSample {
+                at: now + Duration::seconds(i),
+                batch_producer: Status::Online,
+                rpc_endpoint: Status::Online,
+                bundler_endpoint: Status::Online,
+            });
+        }
+
+        assert_eq!(history.since(DateTime::<Utc>::MIN_UTC).len(), 2);
+        assert_eq!(history.available_since(), Some(now + Duration::seconds(1)));
+    }
+}
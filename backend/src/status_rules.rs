@@ -0,0 +1,355 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Assertion on a value of a JSON response
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct JsonAssertion {
+    /// JSON pointer to the value, e.g. `/tip_height`
+    pointer: String,
+    /// Value it must equal; when unset it only has to be present
+    #[serde(default)]
+    equals: Option<Value>,
+}
+
+/// Conditions a response must meet for an endpoint to be considered online.
+///
+/// Parsed as a [`StatusRuleOverride`] of the default rule.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(try_from = "StatusRuleOverride")]
+pub struct StatusRule {
+    /// Accepted HTTP status codes; any code is accepted when empty
+    expected_status_codes: Vec<u16>,
+    /// Substring the raw response body must contain
+    body_contains: Option<String>,
+    /// Assertions on the JSON response
+    json_assertions: Vec<JsonAssertion>,
+    /// JSON pointer to the unix timestamp (seconds, number or hex) of the latest block
+    block_time_pointer: Option<String>,
+    /// Max age of the latest block, requires `block_time_pointer`
+    max_block_age_s: Option<u64>,
+    /// Consecutive failed checks before the endpoint is reported offline, at least 1
+    failure_threshold: Option<u32>,
+}
+
+/// Fields of a [`StatusRule`] set in config, the others are kept from the rule
+/// it applies to
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusRuleOverride {
+    expected_status_codes: Option<Vec<u16>>,
+    body_contains: Option<String>,
+    json_assertions: Option<Vec<JsonAssertion>>,
+    block_time_pointer: Option<String>,
+    max_block_age_s: Option<u64>,
+    failure_threshold: Option<u32>,
+}
+
+impl TryFrom<StatusRuleOverride> for StatusRule {
+    type Error = String;
+
+    fn try_from(rule: StatusRuleOverride) -> Result<Self, Self::Error> {
+        StatusRule::default().with_override(rule)
+    }
+}
+
+impl StatusRule {
+    /// Applies the fields set in `rule` onto this one, e.g. a failure threshold
+    /// keeps the default assertions
+    fn with_override(mut self, rule: StatusRuleOverride) -> Result<Self, String> {
+        if let Some(codes) = rule.expected_status_codes {
+            self.expected_status_codes = codes;
+        }
+        if let Some(needle) = rule.body_contains {
+            self.body_contains = Some(needle);
+        }
+        if let Some(assertions) = rule.json_assertions {
+            self.json_assertions = assertions;
+        }
+        if let Some(pointer) = rule.block_time_pointer {
+            self.block_time_pointer = Some(pointer);
+        }
+        if let Some(max_age_s) = rule.max_block_age_s {
+            self.max_block_age_s = Some(max_age_s);
+        }
+        if let Some(threshold) = rule.failure_threshold {
+            self.failure_threshold = Some(threshold);
+        }
+
+        if self.max_block_age_s.is_some() && self.block_time_pointer.is_none() {
+            return Err("max_block_age_s requires block_time_pointer".to_string());
+        }
+        Ok(self)
+    }
+
+    /// Default rule for `strata_syncStatus` checks
+    pub fn sync_status() -> Self {
+        Self {
+            json_assertions: vec![JsonAssertion {
+                pointer: "/tip_height".to_string(),
+                equals: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Default rule for bundler `/health` checks
+    pub fn bundler_health() -> Self {
+        Self {
+            body_contains: Some("ok".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Consecutive failed checks before the endpoint is reported offline
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold.unwrap_or(1).max(1)
+    }
+
+    /// Checks an HTTP response, returning the first unmet condition
+    pub fn evaluate_http(
+        &self,
+        status_code: u16,
+        body: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if !self.expected_status_codes.is_empty()
+            && !self.expected_status_codes.contains(&status_code)
+        {
+            return Err(format!("unexpected status code {}", status_code));
+        }
+        if let Some(needle) = &self.body_contains {
+            if !body.contains(needle.as_str()) {
+                return Err(format!("body does not contain {:?}", needle));
+            }
+        }
+        if self.json_assertions.is_empty() && self.block_time_pointer.is_none() {
+            return Ok(());
+        }
+
+        let json: Value =
+            serde_json::from_str(body).map_err(|e| format!("body is not JSON: {}", e))?;
+        self.evaluate_json(&json, now)
+    }
+
+    /// Checks a JSON(-RPC) response, returning the first unmet condition
+    pub fn evaluate_json(&self, json: &Value, now: DateTime<Utc>) -> Result<(), String> {
+        for assertion in &self.json_assertions {
+            match (json.pointer(&assertion.pointer), &assertion.equals) {
+                (None, _) => return Err(format!("{} is missing", assertion.pointer)),
+                (Some(actual), Some(expected)) if actual != expected => {
+                    return Err(format!(
+                        "{} is {}, expected {}",
+                        assertion.pointer, actual, expected
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        if let (Some(pointer), Some(max_age_s)) = (&self.block_time_pointer, self.max_block_age_s) {
+            let block_time = json
+                .pointer(pointer)
+                .and_then(parse_timestamp)
+                .ok_or_else(|| format!("{} is not a timestamp", pointer))?;
+            let age_s = now.timestamp() - block_time;
+            if age_s > max_age_s as i64 {
+                return Err(format!("latest block is {}s old", age_s));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a unix timestamp given as a number or hex quantity
+fn parse_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s
+            .strip_prefix("0x")
+            .and_then(|hex| i64::from_str_radix(hex, 16).ok()),
+        _ => None,
+    }
+}
+
+/// Status evaluation rules of each monitored endpoint.
+///
+/// Parsed as [`StatusRulesOverride`], applied onto the default rules.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "StatusRulesOverride")]
+pub struct StatusRules {
+    pub batch_producer: StatusRule,
+    pub rpc_endpoint: StatusRule,
+    pub bundler_endpoint: StatusRule,
+}
+
+/// Rule overrides of each monitored endpoint, see `STATUS_RULES`
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusRulesOverride {
+    batch_producer: StatusRuleOverride,
+    rpc_endpoint: StatusRuleOverride,
+    bundler_endpoint: StatusRuleOverride,
+}
+
+impl TryFrom<StatusRulesOverride> for StatusRules {
+    type Error = String;
+
+    fn try_from(rules: StatusRulesOverride) -> Result<Self, Self::Error> {
+        let defaults = StatusRules::default();
+        Ok(Self {
+            batch_producer: defaults
+                .batch_producer
+                .with_override(rules.batch_producer)
+                .map_err(|e| format!("batch_producer: {}", e))?,
+            rpc_endpoint: defaults
+                .rpc_endpoint
+                .with_override(rules.rpc_endpoint)
+                .map_err(|e| format!("rpc_endpoint: {}", e))?,
+            bundler_endpoint: defaults
+                .bundler_endpoint
+                .with_override(rules.bundler_endpoint)
+                .map_err(|e| format!("bundler_endpoint: {}", e))?,
+        })
+    }
+}
+
+impl Default for StatusRules {
+    fn default() -> Self {
+        Self {
+            batch_producer: StatusRule::sync_status(),
+            rpc_endpoint: StatusRule::sync_status(),
+            bundler_endpoint: StatusRule::bundler_health(),
+        }
+    }
+}
+
+/// Tracks consecutive failures of an endpoint to debounce status flips
+#[derive(Debug, Default)]
+pub struct FailureCounter {
+    consecutive_failures: u32,
+}
+
+impl FailureCounter {
+    /// Records a check outcome, returning whether the endpoint should be reported online.
+    ///
+    /// `was_online` is kept until `threshold` checks in a row failed.
+    pub fn observe(&mut self, check_passed: bool, was_online: bool, threshold: u32) -> bool {
+        if check_passed {
+            self.consecutive_failures = 0;
+            return true;
+        }
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        was_online && self.consecutive_failures < threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FailureCounter, StatusRule, StatusRules};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn test_default_rules_match_legacy_checks() {
+        let now = Utc::now();
+        let rules = StatusRules::default();
+
+        assert!(rules
+            .batch_producer
+            .evaluate_json(&json!({ "tip_height": 10 }), now)
+            .is_ok());
+        assert!(rules
+            .batch_producer
+            .evaluate_json(&json!({ "finalized": 5 }), now)
+            .is_err());
+
+        assert!(rules.bundler_endpoint.evaluate_http(200, "ok", now).is_ok());
+        assert!(rules
+            .bundler_endpoint
+            .evaluate_http(500, "down", now)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rule_from_config() {
+        let now = Utc.timestamp_opt(1_000, 0).unwrap();
+        let rule: StatusRule = serde_json::from_value(json!({
+            "expected_status_codes": [200],
+            "json_assertions": [{ "pointer": "/status", "equals": "ready" }],
+            "block_time_pointer": "/block/timestamp",
+            "max_block_age_s": 60,
+            "failure_threshold": 3,
+        }))
+        .unwrap();
+        assert_eq!(rule.failure_threshold(), 3);
+
+        let body = |status: &str, timestamp: &str| {
+            json!({ "status": status, "block": { "timestamp": timestamp } }).to_string()
+        };
+        assert!(rule
+            .evaluate_http(200, &body("ready", "0x3c0"), now)
+            .is_ok());
+        assert!(rule
+            .evaluate_http(204, &body("ready", "0x3c0"), now)
+            .is_err());
+        assert!(rule
+            .evaluate_http(200, &body("syncing", "0x3c0"), now)
+            .is_err());
+        // 0x384 = 900, 100s before `now`
+        assert!(rule
+            .evaluate_http(200, &body("ready", "0x384"), now)
+            .is_err());
+
+        assert!(serde_json::from_value::<StatusRule>(json!({ "unknown": 1 })).is_err());
+        assert!(serde_json::from_value::<StatusRule>(json!({ "max_block_age_s": 60 })).is_err());
+    }
+
+    #[test]
+    fn test_rules_override_defaults() {
+        let now = Utc::now();
+        let rules: StatusRules = serde_json::from_value(json!({
+            "batch_producer": { "failure_threshold": 3 },
+            "bundler_endpoint": { "expected_status_codes": [200] },
+        }))
+        .unwrap();
+
+        // The default tip height assertion is kept
+        assert_eq!(rules.batch_producer.failure_threshold(), 3);
+        assert!(rules
+            .batch_producer
+            .evaluate_json(&json!({ "finalized": 5 }), now)
+            .is_err());
+        assert_eq!(rules.rpc_endpoint, StatusRule::sync_status());
+        // As is the default body check
+        assert!(rules.bundler_endpoint.evaluate_http(200, "ok", now).is_ok());
+        assert!(rules
+            .bundler_endpoint
+            .evaluate_http(200, "down", now)
+            .is_err());
+
+        assert!(serde_json::from_value::<StatusRules>(json!({
+            "rpc_endpoint": { "max_block_age_s": 60 },
+        }))
+        .is_err());
+        assert!(serde_json::from_value::<StatusRules>(json!({
+            "rpc_endpoint": { "block_time_pointer": "/timestamp", "max_block_age_s": 60 },
+        }))
+        .is_ok());
+    }
+
+    #[test]
+    fn test_failure_counter_debounces_offline() {
+        let mut counter = FailureCounter::default();
+        assert!(counter.observe(false, true, 3));
+        assert!(counter.observe(false, true, 3));
+        assert!(!counter.observe(false, true, 3));
+        assert!(!counter.observe(false, false, 3));
+        assert!(counter.observe(true, false, 3));
+
+        // Endpoints that never came online stay offline
+        let mut counter = FailureCounter::default();
+        assert!(!counter.observe(false, false, 3));
+    }
+}
@@ -0,0 +1,270 @@
+//! Storage of the timestamped records behind the histories. The histories only
+//! depend on [`StatsStore`], so that backends other than the ones below and the
+//! [`SqliteStore`], e.g. another database, can be dropped in without changing them.
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::VecDeque, fmt};
+use tracing::{info, warn};
+
+use crate::{
+    history_writer::{read_records, rewrite_records, HistoryWriter},
+    sqlite::{SqliteDb, SqliteStore},
+    utils::push_bounded,
+};
+
+/// Record stored by time
+pub trait Timestamped {
+    fn at(&self) -> DateTime<Utc>;
+}
+
+/// Storage of timestamped records, saved in time order
+pub trait StatsStore<T>: fmt::Debug + Send + Sync {
+    /// Saves a periodic snapshot, e.g. a status sample
+    fn save_snapshot(&mut self, snapshot: T);
+
+    /// Saves events observed together, in order
+    fn save_events(&mut self, events: Vec<T>) {
+        for event in events {
+            self.save_snapshot(event);
+        }
+    }
+
+    /// Records at or after `from` and before `to`, oldest first
+    fn query_range(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<T>;
+
+    /// Stored records, oldest first
+    fn records(&self) -> Box<dyn Iterator<Item = &T> + '_>;
+
+    /// Time of the oldest stored record
+    fn oldest(&self) -> Option<DateTime<Utc>>;
+
+    /// Drops the records older than `cutoff` from the queried ones
+    fn prune(&mut self, cutoff: DateTime<Utc>);
+
+    /// Drops all but the `max_records` most recent records from the queried ones
+    fn truncate(&mut self, max_records: usize);
+
+    /// Reclaims the space of the pruned records, e.g. by rewriting a file
+    fn compact(&mut self) {}
+
+    /// Number of records not persisted because the backend fell behind
+    fn dropped_writes(&self) -> u64 {
+        0
+    }
+
+    /// Writer persisting the records, to wait for it in tests
+    #[cfg(test)]
+    fn writer(&self) -> Option<&HistoryWriter> {
+        None
+    }
+}
+
+/// Records kept in memory only, up to `max_records` of the most recent ones
+#[derive(Debug)]
+pub struct MemoryStore<T> {
+    records: VecDeque<T>,
+    max_records: usize,
+}
+
+impl<T> MemoryStore<T> {
+    pub fn new(max_records: usize) -> Self {
+        Self {
+            records: VecDeque::new(),
+            max_records,
+        }
+    }
+}
+
+/// Records at or after `from` and before `to` of a time-ordered slice
+pub(crate) fn range<T: Timestamped + Clone>(
+    records: &VecDeque<T>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<T> {
+    records
+        .iter()
+        .filter(|record| record.at() >= from && record.at() < to)
+        .cloned()
+        .collect()
+}
+
+pub(crate) fn prune<T: Timestamped>(records: &mut VecDeque<T>, cutoff: DateTime<Utc>) {
+    while records.front().is_some_and(|record| record.at() < cutoff) {
+        records.pop_front();
+    }
+}
+
+pub(crate) fn truncate<T>(records: &mut VecDeque<T>, max_records: usize) {
+    let excess = records.len().saturating_sub(max_records);
+    records.drain(..excess);
+}
+
+impl<T: Timestamped + Clone + fmt::Debug + Send + Sync> StatsStore<T> for MemoryStore<T> {
+    fn save_snapshot(&mut self, snapshot: T) {
+        push_bounded(&mut self.records, snapshot, self.max_records);
+    }
+
+    fn query_range(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<T> {
+        range(&self.records, from, to)
+    }
+
+    fn records(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.records.iter())
+    }
+
+    fn oldest(&self) -> Option<DateTime<Utc>> {
+        self.records.front().map(Timestamped::at)
+    }
+
+    fn prune(&mut self, cutoff: DateTime<Utc>) {
+        prune(&mut self.records, cutoff);
+    }
+
+    fn truncate(&mut self, max_records: usize) {
+        truncate(&mut self.records, max_records);
+    }
+}
+
+/// Records kept in memory and appended to a JSON lines file by a
+/// [`HistoryWriter`], reloaded on startup
+#[derive(Debug)]
+pub struct JsonLinesStore<T> {
+    records: VecDeque<T>,
+    writer: HistoryWriter,
+}
+
+impl<T: Timestamped + Serialize + DeserializeOwned> JsonLinesStore<T> {
+    /// Loads the records persisted at `path` from `cutoff` on, dropping the older
+    /// ones from the file. Up to `write_buffer` records wait to be persisted before
+    /// new ones are dropped.
+    pub fn load(path: String, cutoff: DateTime<Utc>, write_buffer: usize) -> Self {
+        let mut records = VecDeque::new();
+        if let Some(loaded) = read_records::<T>(&path) {
+            records = loaded
+                .into_iter()
+                .filter(|record| record.at() >= cutoff)
+                .collect();
+            info!(%path, records = records.len(), "Loaded history records");
+        }
+
+        if let Err(e) = rewrite_records(&path, &records) {
+            warn!(%path, error = %e, "Failed to compact history records");
+        }
+
+        Self {
+            records,
+            writer: HistoryWriter::spawn(path, write_buffer),
+        }
+    }
+}
+
+impl<T> StatsStore<T> for JsonLinesStore<T>
+where
+    T: Timestamped + Serialize + Clone + fmt::Debug + Send + Sync,
+{
+    fn save_snapshot(&mut self, snapshot: T) {
+        self.writer.write(&snapshot);
+        self.records.push_back(snapshot);
+    }
+
+    fn query_range(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<T> {
+        range(&self.records, from, to)
+    }
+
+    fn records(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.records.iter())
+    }
+
+    fn oldest(&self) -> Option<DateTime<Utc>> {
+        self.records.front().map(Timestamped::at)
+    }
+
+    /// Pruned records stay in the file until it is compacted
+    fn prune(&mut self, cutoff: DateTime<Utc>) {
+        prune(&mut self.records, cutoff);
+    }
+
+    /// Truncated records stay in the file until it is compacted
+    fn truncate(&mut self, max_records: usize) {
+        truncate(&mut self.records, max_records);
+    }
+
+    fn compact(&mut self) {
+        self.writer.rewrite(&self.records);
+    }
+
+    fn dropped_writes(&self) -> u64 {
+        self.writer.dropped()
+    }
+
+    #[cfg(test)]
+    fn writer(&self) -> Option<&HistoryWriter> {
+        Some(&self.writer)
+    }
+}
+
+/// Opens the store of a persisted history: its `table` of the SQLite database when
+/// one is configured, else its JSON lines file at `path` if set, else up to
+/// `max_records` in memory only.
+///
+/// Persisted records older than `cutoff` are dropped on load, and up to
+/// `write_buffer` records wait to be persisted before new ones are dropped.
+pub fn open_store<T>(
+    sqlite: Option<&SqliteDb>,
+    table: &str,
+    path: Option<String>,
+    cutoff: DateTime<Utc>,
+    write_buffer: usize,
+    max_records: usize,
+) -> Box<dyn StatsStore<T>>
+where
+    T: Timestamped + Serialize + DeserializeOwned + Clone + fmt::Debug + Send + Sync + 'static,
+{
+    if let Some(db) = sqlite {
+        return Box::new(
+            SqliteStore::load(db, table, cutoff, write_buffer)
+                .expect("to load the history table from DATABASE_URL"),
+        );
+    }
+    match path {
+        Some(path) => Box::new(JsonLinesStore::load(path, cutoff, write_buffer)),
+        None => Box::new(MemoryStore::new(max_records)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryStore, StatsStore, Timestamped};
+    use chrono::{DateTime, Duration, Utc};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Sample(DateTime<Utc>);
+
+    impl Timestamped for Sample {
+        fn at(&self) -> DateTime<Utc> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let now = Utc::now();
+        let at = |s| now + Duration::seconds(s);
+        let mut store = MemoryStore::new(3);
+        store.save_events((0..4).map(|s| Sample(at(s))).collect());
+
+        assert_eq!(store.oldest(), Some(at(1)));
+        assert_eq!(
+            store.query_range(at(2), at(3)),
+            vec![Sample(at(2))],
+            "the end of the range is excluded"
+        );
+        store.prune(at(3));
+        assert_eq!(store.query_range(at(0), at(10)), vec![Sample(at(3))]);
+
+        store.save_snapshot(Sample(at(4)));
+        store.truncate(1);
+        assert_eq!(store.records().collect::<Vec<_>>(), vec![&Sample(at(4))]);
+    }
+}
@@ -0,0 +1,459 @@
+use axum::{
+    extract::{Query, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    sync::RwLock,
+    time::{sleep, Duration, Instant},
+};
+use tokio_metrics::{Instrumented, TaskMetrics, TaskMonitor};
+use tracing::warn;
+
+use crate::{budget, degradation, uptime_pings::UptimePings, utils::parse_duration};
+
+/// Name of the network status task
+pub const NETWORK_STATUS_TASK: &str = "network_status";
+/// Name of the paymaster wallet balances task
+pub const WALLET_BALANCES_TASK: &str = "wallet_balances";
+/// Name of the activity stats task
+pub const ACTIVITY_STATS_TASK: &str = "activity_stats";
+/// Name of the bridge status task
+pub const BRIDGE_STATUS_TASK: &str = "bridge_status";
+/// Name of the bundler stats task
+pub const BUNDLER_STATS_TASK: &str = "bundler_stats";
+/// Name of the alert rules task
+pub const ALERT_RULES_TASK: &str = "alert_rules";
+/// Name of the scheduled reports task
+pub const REPORTS_TASK: &str = "reports";
+/// Name of the task compacting the persisted histories
+pub const JANITOR_TASK: &str = "janitor";
+/// Name of the configuration drift task
+pub const DRIFT_CHECK_TASK: &str = "drift_check";
+/// Name of the clock skew task
+pub const CLOCK_SKEW_TASK: &str = "clock_skew";
+
+/// Monitoring tasks whose first refresh is awaited before serving traffic
+pub const WARM_UP_TASKS: [&str; 5] = [
+    NETWORK_STATUS_TASK,
+    WALLET_BALANCES_TASK,
+    ACTIVITY_STATS_TASK,
+    BRIDGE_STATUS_TASK,
+    BUNDLER_STATS_TASK,
+];
+
+/// Time between checks of the warm-up progress
+const WARM_UP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A task is considered stale after missing this many refresh intervals
+const STALE_AFTER_INTERVALS: i64 = 3;
+
+/// Number of recent refresh cycles the average duration is taken over
+const DURATION_WINDOW: usize = 20;
+
+/// Share of the interval a refresh cycle may take before a warning is logged
+const SLOW_REFRESH_RATIO: f64 = 0.8;
+
+/// Refresh bookkeeping of a background monitoring task
+#[derive(Serialize, Clone, Debug)]
+pub struct TaskStatus {
+    /// Expected time between refreshes in seconds
+    interval_s: u64,
+    /// Completion time of the last refresh cycle
+    last_refresh: Option<DateTime<Utc>>,
+    /// Number of completed refresh cycles
+    refreshes: u64,
+    /// Start time of the current refresh cycle
+    #[serde(skip)]
+    refresh_started: Option<DateTime<Utc>>,
+    /// Durations of the most recent refresh cycles in milliseconds, oldest first
+    #[serde(skip)]
+    recent_durations_ms: VecDeque<u64>,
+    /// Duration of the last refresh cycle in milliseconds
+    last_duration_ms: Option<u64>,
+    /// Average duration of the recent refresh cycles in milliseconds
+    avg_duration_ms: Option<u64>,
+}
+
+impl TaskStatus {
+    fn new(interval_s: u64) -> Self {
+        Self {
+            interval_s,
+            last_refresh: None,
+            refreshes: 0,
+            refresh_started: None,
+            recent_durations_ms: VecDeque::new(),
+            last_duration_ms: None,
+            avg_duration_ms: None,
+        }
+    }
+
+    /// Getter for `interval_s`
+    pub fn interval_s(&self) -> u64 {
+        self.interval_s
+    }
+
+    /// Getter for `refreshes`
+    pub fn refreshes(&self) -> u64 {
+        self.refreshes
+    }
+
+    /// Getter for `last_duration_ms`
+    pub fn last_duration_ms(&self) -> Option<u64> {
+        self.last_duration_ms
+    }
+
+    /// Getter for `avg_duration_ms`
+    pub fn avg_duration_ms(&self) -> Option<u64> {
+        self.avg_duration_ms
+    }
+
+    /// Records the duration of a completed refresh cycle
+    fn record_duration(&mut self, duration_ms: u64) {
+        self.recent_durations_ms.push_back(duration_ms);
+        while self.recent_durations_ms.len() > DURATION_WINDOW {
+            self.recent_durations_ms.pop_front();
+        }
+        self.last_duration_ms = Some(duration_ms);
+        self.avg_duration_ms = Some(
+            self.recent_durations_ms.iter().sum::<u64>() / self.recent_durations_ms.len() as u64,
+        );
+    }
+
+    /// Seconds since the last refresh, if any
+    pub fn age_s(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.last_refresh.map(|at| (now - at).num_seconds())
+    }
+
+    /// Whether the task has not refreshed for several intervals, or never did
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        match self.age_s(now) {
+            Some(age) => age > STALE_AFTER_INTERVALS * self.interval_s as i64,
+            None => true,
+        }
+    }
+}
+
+/// Registry of background tasks and their refresh progress
+#[derive(Clone, Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Arc<RwLock<BTreeMap<&'static str, TaskStatus>>>,
+    /// Poll metrics of the task futures, see [`TaskRegistry::instrument`]
+    monitors: Arc<Mutex<BTreeMap<&'static str, TaskMonitor>>>,
+    /// External uptime monitors pinged after each refresh cycle
+    uptime_pings: Option<UptimePings>,
+}
+
+impl TaskRegistry {
+    /// Pings the uptime monitors of the tasks after each of their refresh cycles
+    pub fn with_uptime_pings(mut self, uptime_pings: UptimePings) -> Self {
+        self.uptime_pings = Some(uptime_pings);
+        self
+    }
+
+    /// Registers a task refreshing every `interval_s` seconds
+    pub async fn register(&self, name: &'static str, interval_s: u64) {
+        self.tasks
+            .write()
+            .await
+            .insert(name, TaskStatus::new(interval_s));
+    }
+
+    /// Updates the expected time between refreshes, e.g. while a task backs off
+    pub async fn set_interval(&self, name: &'static str, interval_s: u64) {
+        if let Some(task) = self.tasks.write().await.get_mut(name) {
+            task.interval_s = interval_s;
+        }
+    }
+
+    /// Records the start of a refresh cycle, to measure its duration
+    pub async fn start_refresh(&self, name: &'static str) {
+        if let Some(task) = self.tasks.write().await.get_mut(name) {
+            task.refresh_started = Some(Utc::now());
+        }
+    }
+
+    /// Records the completion of a refresh cycle and pings its uptime monitor. The
+    /// upstream failures of the cycle replace those reported for the task.
+    pub async fn record_refresh(&self, name: &'static str) {
+        let now = Utc::now();
+        budget::end_cycle(name);
+        degradation::end_cycle(name);
+        if let Some(uptime_pings) = &self.uptime_pings {
+            uptime_pings.ping(name).await;
+        }
+        if let Some(task) = self.tasks.write().await.get_mut(name) {
+            task.last_refresh = Some(now);
+            task.refreshes += 1;
+
+            if let Some(started) = task.refresh_started.take() {
+                let duration_ms = (now - started).num_milliseconds().max(0) as u64;
+                task.record_duration(duration_ms);
+                if duration_ms as f64 > SLOW_REFRESH_RATIO * (task.interval_s * 1000) as f64 {
+                    warn!(
+                        task = name,
+                        duration_ms,
+                        interval_s = task.interval_s,
+                        "Refresh cycle is approaching its interval"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the status of all registered tasks
+    pub async fn snapshot(&self) -> BTreeMap<&'static str, TaskStatus> {
+        self.tasks.read().await.clone()
+    }
+
+    /// Wraps the future of the task `name` to measure its polls, e.g. to spot a task
+    /// blocking the runtime threads. Futures it spawns are not covered.
+    pub fn instrument<F: Future>(&self, name: &'static str, future: F) -> Instrumented<F> {
+        let mut monitors = self.monitors.lock().unwrap();
+        monitors.entry(name).or_default().instrument(future)
+    }
+
+    /// Poll metrics of the instrumented tasks since startup
+    pub fn poll_metrics(&self) -> BTreeMap<&'static str, TaskMetrics> {
+        let monitors = self.monitors.lock().unwrap();
+        monitors
+            .iter()
+            .map(|(name, monitor)| (*name, monitor.cumulative()))
+            .collect()
+    }
+
+    /// Waits for every task of `names` to complete a refresh cycle, for at most
+    /// `timeout`. Returns the tasks that did not, including unregistered ones.
+    pub async fn wait_for_first_refresh(
+        &self,
+        names: &[&'static str],
+        timeout: Duration,
+    ) -> Vec<&'static str> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let tasks = self.tasks.read().await;
+            let pending: Vec<_> = names
+                .iter()
+                .copied()
+                .filter(|name| tasks.get(name).is_none_or(|task| task.refreshes == 0))
+                .collect();
+            drop(tasks);
+
+            if pending.is_empty() || Instant::now() >= deadline {
+                return pending;
+            }
+            sleep(WARM_UP_POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
+    }
+}
+
+/// Refresh state of a task along with its freshness
+#[derive(Serialize, Debug)]
+pub struct TaskHealth {
+    #[serde(flatten)]
+    status: TaskStatus,
+    /// Seconds since the last refresh
+    age_s: Option<i64>,
+    stale: bool,
+}
+
+impl TaskHealth {
+    pub fn new(status: TaskStatus, now: DateTime<Utc>) -> Self {
+        Self {
+            age_s: status.age_s(now),
+            stale: status.is_stale(now),
+            status,
+        }
+    }
+
+    /// Getter for `stale`
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+}
+
+/// Handler to get the refresh state of every background task
+pub async fn get_tasks(tasks: TaskRegistry) -> Json<BTreeMap<&'static str, TaskHealth>> {
+    let now = Utc::now();
+    let health = tasks
+        .snapshot()
+        .await
+        .into_iter()
+        .map(|(name, status)| (name, TaskHealth::new(status, now)))
+        .collect();
+    Json(health)
+}
+
+/// Query parameters read by [`require_max_age`]
+#[derive(Deserialize, Debug)]
+struct FreshnessQuery {
+    /// Max age of the served state, e.g. `30s`
+    max_age: Option<String>,
+}
+
+/// Body of the responses rejected by [`require_max_age`]
+#[derive(Serialize, Debug, PartialEq)]
+struct StalenessDetails {
+    /// Task refreshing the requested state
+    task: &'static str,
+    max_age_s: i64,
+    /// Seconds since the last refresh, unset if the task never refreshed
+    age_s: Option<i64>,
+    last_refresh: Option<DateTime<Utc>>,
+}
+
+/// Errs with the staleness details if `task` last refreshed longer than `max_age` ago
+fn check_freshness(
+    task: &'static str,
+    status: Option<&TaskStatus>,
+    max_age: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Result<(), StalenessDetails> {
+    let last_refresh = status.and_then(|status| status.last_refresh);
+    let age = last_refresh.map(|at| now - at);
+    if age.is_some_and(|age| age <= max_age) {
+        return Ok(());
+    }
+    Err(StalenessDetails {
+        task,
+        max_age_s: max_age.num_seconds(),
+        age_s: age.map(|age| age.num_seconds()),
+        last_refresh,
+    })
+}
+
+/// Middleware honoring the `max_age` query parameter, e.g. `?max_age=30s`: when the
+/// state refreshed by `task` is older, the request fails with 503 and the staleness
+/// details rather than serving stale data, so that automation can tell a lagging
+/// dashboard from a healthy network. An invalid age fails with 400.
+pub async fn require_max_age(
+    State((tasks, task)): State<(TaskRegistry, &'static str)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let max_age = Query::<FreshnessQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.max_age);
+    if let Some(max_age) = max_age {
+        let Some(max_age) = parse_duration(&max_age) else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+        let snapshot = tasks.snapshot().await;
+        if let Err(details) = check_freshness(task, snapshot.get(task), max_age, Utc::now()) {
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(details)).into_response();
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_freshness, TaskRegistry, TaskStatus, DURATION_WINDOW};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_task_staleness() {
+        let now = Utc::now();
+        let mut task = TaskStatus::new(10);
+        assert!(task.is_stale(now));
+
+        task.last_refresh = Some(now - Duration::seconds(25));
+        assert_eq!(task.age_s(now), Some(25));
+        assert!(!task.is_stale(now));
+
+        task.last_refresh = Some(now - Duration::seconds(31));
+        assert!(task.is_stale(now));
+    }
+
+    #[test]
+    fn test_check_freshness() {
+        let now = Utc::now();
+        let max_age = Duration::seconds(30);
+        let mut task = TaskStatus::new(10);
+        let details = check_freshness("bridge_status", Some(&task), max_age, now).unwrap_err();
+        assert_eq!(details.age_s, None);
+        assert!(check_freshness("bridge_status", None, max_age, now).is_err());
+
+        task.last_refresh = Some(now - Duration::seconds(20));
+        assert!(check_freshness("bridge_status", Some(&task), max_age, now).is_ok());
+
+        task.last_refresh = Some(now - Duration::seconds(45));
+        let details = check_freshness("bridge_status", Some(&task), max_age, now).unwrap_err();
+        assert_eq!(details.max_age_s, 30);
+        assert_eq!(details.age_s, Some(45));
+    }
+
+    #[test]
+    fn test_refresh_durations() {
+        let mut task = TaskStatus::new(10);
+        task.record_duration(100);
+        task.record_duration(300);
+        assert_eq!(task.last_duration_ms(), Some(300));
+        assert_eq!(task.avg_duration_ms(), Some(200));
+
+        // Only the most recent cycles are averaged
+        for _ in 0..DURATION_WINDOW {
+            task.record_duration(50);
+        }
+        assert_eq!(task.avg_duration_ms(), Some(50));
+    }
+
+    #[tokio::test]
+    async fn test_registry_measures_refresh() {
+        let tasks = TaskRegistry::default();
+        tasks.register("task", 10).await;
+        tasks.record_refresh("task").await;
+        // Refreshes without a recorded start have no duration
+        assert_eq!(tasks.snapshot().await["task"].last_duration_ms(), None);
+
+        tasks.start_refresh("task").await;
+        tasks.record_refresh("task").await;
+        let task = &tasks.snapshot().await["task"];
+        assert_eq!(task.refreshes(), 2);
+        assert!(task.last_duration_ms().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_instrumented_task_polls() {
+        let tasks = TaskRegistry::default();
+        assert!(tasks.poll_metrics().is_empty());
+
+        // Yielding takes a second poll
+        tasks.instrument("task", tokio::task::yield_now()).await;
+        let metrics = &tasks.poll_metrics()["task"];
+        assert_eq!(metrics.instrumented_count, 1);
+        assert_eq!(metrics.total_poll_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_first_refresh() {
+        let tasks = TaskRegistry::default();
+        tasks.register("fast", 10).await;
+        tasks.register("slow", 10).await;
+        tasks.record_refresh("fast").await;
+
+        let timeout = std::time::Duration::from_millis(250);
+        let pending = tasks
+            .wait_for_first_refresh(&["fast", "slow", "unregistered"], timeout)
+            .await;
+        assert_eq!(pending, vec!["slow", "unregistered"]);
+
+        tokio::spawn({
+            let tasks = tasks.clone();
+            async move { tasks.record_refresh("slow").await }
+        });
+        let pending = tasks
+            .wait_for_first_refresh(&["fast", "slow"], timeout)
+            .await;
+        assert!(pending.is_empty());
+    }
+}
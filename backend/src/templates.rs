@@ -0,0 +1,102 @@
+//! Text templates for notifications, so deployments can word them and link their
+//! runbooks without code changes.
+//!
+//! Templates are rendered with minijinja, with the fields of the context as
+//! variables, e.g. `{% if resolved %}Resolved{% else %}{{ severity }}{% endif %}:
+//! {{ message }}`. Unknown fields, including fields of unset ones such as
+//! `acknowledgement.by`, render empty. Notifications are plain text, so values are
+//! not HTML-escaped.
+
+use minijinja::{Environment, UndefinedBehavior};
+use serde_json::Value;
+
+const TEMPLATE_NAME: &str = "template";
+
+/// Parsed template
+#[derive(Clone, Debug)]
+pub struct Template {
+    env: Environment<'static>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Chainable);
+        env.add_template_owned(TEMPLATE_NAME, s.to_string())
+            .map_err(|e| e.to_string())?;
+        Ok(Self { env })
+    }
+
+    /// Reads and parses the template file at `path`
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&source)
+    }
+
+    /// Fails on errors only detected when rendering, e.g. a filter applied to a
+    /// value of the wrong type
+    pub fn render(&self, context: &Value) -> Result<String, String> {
+        self.env
+            .get_template(TEMPLATE_NAME)
+            .and_then(|template| template.render(context))
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_template() {
+        let template = Template::parse(
+            "{% if resolved %}Resolved{% else %}{{ severity }}{% endif %}: {{ message }} \
+             (see https://runbooks.example.com/{{ kind }}){% if acknowledgement.by %}, \
+             acknowledged by {{ acknowledgement.by }}{% endif %}",
+        )
+        .unwrap();
+
+        let context = json!({
+            "kind": "bridge_duty_backlog",
+            "severity": "critical",
+            "message": "Operator #1 is <behind>",
+            "resolved": false,
+            "acknowledgement": { "by": "alice" },
+        });
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "critical: Operator #1 is <behind> (see https://runbooks.example.com/\
+             bridge_duty_backlog), acknowledged by alice"
+        );
+
+        let context = json!({
+            "kind": "bundler_stalled",
+            "message": "No bundles",
+            "resolved": true,
+            "acknowledgement": null,
+        });
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "Resolved: No bundles (see https://runbooks.example.com/bundler_stalled)"
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        assert!(Template::parse("{{ message").is_err());
+        assert!(Template::parse("{% if resolved %}done").is_err());
+        assert!(Template::parse("done{% endif %}").is_err());
+        assert!(Template::parse("{{ }}").is_err());
+        assert_eq!(
+            Template::parse("plain")
+                .unwrap()
+                .render(&json!({}))
+                .unwrap(),
+            "plain"
+        );
+
+        let template = Template::parse("{{ message | round }}").unwrap();
+        assert!(template.render(&json!({ "message": "text" })).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Treasury automation hooks, requesting a top-up of a paymaster wallet whose
+//! balance dropped below `PAYMASTER_CRITICAL_BALANCE_WEI`.
+//!
+//! A request is POSTed to `TOP_UP_WEBHOOK_URL` and/or passed to `TOP_UP_COMMAND`,
+//! at most once per wallet per `TOP_UP_COOLDOWN_S` so that a flapping balance
+//! does not drain the treasury. Every attempt, including the ones skipped during
+//! the cooldown, is kept in an audit trail.
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
+use tokio::{
+    process::Command,
+    sync::{broadcast::Receiver, RwLock},
+};
+use tracing::{info, warn};
+
+use crate::{
+    auth::AdminAuth,
+    config::TopUpConfig,
+    events::{next_event, MonitorEvent},
+    history_writer::{read_records, rewrite_records, HistoryWriter},
+};
+
+/// Max number of top-up attempts kept in the audit trail
+const MAX_TOP_UP_RECORDS: usize = 1_000;
+
+/// Top-up requested from the treasury automation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TopUpRequest {
+    /// Name of the wallet, e.g. `deposit`
+    wallet: String,
+    address: String,
+    balance_wei: String,
+    threshold_wei: String,
+}
+
+/// Result of a top-up attempt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TopUpOutcome {
+    /// Every configured hook accepted the request
+    Triggered,
+    /// A hook failed or timed out
+    Failed { error: String },
+    /// Skipped, as a top-up of the wallet was attempted less than the cooldown ago
+    Cooldown { until: DateTime<Utc> },
+}
+
+/// Entry of the top-up audit trail
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TopUpRecord {
+    at: DateTime<Utc>,
+    request: TopUpRequest,
+    outcome: TopUpOutcome,
+}
+
+/// Bounded audit trail of the top-up attempts.
+///
+/// When a path is configured, attempts are appended to it as JSON lines by a
+/// [`HistoryWriter`] and reloaded on startup, so that the cooldowns survive
+/// restarts.
+#[derive(Debug, Default)]
+pub struct TopUpAudit {
+    records: VecDeque<TopUpRecord>,
+    writer: Option<HistoryWriter>,
+}
+
+pub type SharedTopUpAudit = Arc<RwLock<TopUpAudit>>;
+
+impl TopUpAudit {
+    /// Creates the audit trail, loading the most recent attempts persisted at `path`
+    pub fn load(path: Option<&str>, write_buffer: usize) -> Self {
+        let mut audit = Self::default();
+        let Some(path) = path else {
+            return audit;
+        };
+
+        if let Some(records) = read_records::<TopUpRecord>(path) {
+            let skip = records.len().saturating_sub(MAX_TOP_UP_RECORDS);
+            audit.records = records.into_iter().skip(skip).collect();
+            info!(%path, records = audit.records.len(), "Loaded top-up audit trail");
+        }
+
+        if let Err(e) = rewrite_records(path, &audit.records) {
+            warn!(%path, error = %e, "Failed to compact top-up audit trail");
+        }
+
+        audit.writer = Some(HistoryWriter::spawn(path.to_string(), write_buffer));
+        audit
+    }
+
+    fn record(&mut self, record: TopUpRecord) {
+        if let Some(writer) = &self.writer {
+            writer.write(&record);
+        }
+        self.records.push_back(record);
+        while self.records.len() > MAX_TOP_UP_RECORDS {
+            self.records.pop_front();
+        }
+    }
+
+    /// Time of the last top-up attempted for each wallet, skipped ones excluded
+    fn last_attempts(&self) -> BTreeMap<String, DateTime<Utc>> {
+        self.records
+            .iter()
+            .filter(|record| !matches!(record.outcome, TopUpOutcome::Cooldown { .. }))
+            .map(|record| (record.request.wallet.clone(), record.at))
+            .collect()
+    }
+}
+
+/// End of the cooldown of a wallet last topped up at `last`, if still running at `now`
+fn cooldown_until(
+    last: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    cooldown: Duration,
+) -> Option<DateTime<Utc>> {
+    let until = last? + cooldown;
+    (until > now).then_some(until)
+}
+
+/// Webhook and command requesting the top-ups
+struct TopUpHooks {
+    http: reqwest::Client,
+    webhook_url: Option<String>,
+    command: Option<String>,
+    timeout: std::time::Duration,
+}
+
+impl TopUpHooks {
+    fn new(config: &TopUpConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            webhook_url: config.webhook_url().map(str::to_string),
+            command: config.command().map(str::to_string),
+            timeout: std::time::Duration::from_secs(config.timeout_s()),
+        }
+    }
+
+    /// Calls the configured hooks, failing on the first one that fails
+    async fn call(&self, request: &TopUpRequest) -> Result<(), String> {
+        if let Some(url) = &self.webhook_url {
+            self.http
+                .post(url)
+                .timeout(self.timeout)
+                .json(request)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| format!("webhook failed: {}", e))?;
+        }
+
+        if let Some(command) = &self.command {
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("TOP_UP_WALLET", &request.wallet)
+                .env("TOP_UP_ADDRESS", &request.address)
+                .env("TOP_UP_BALANCE_WEI", &request.balance_wei)
+                .env("TOP_UP_THRESHOLD_WEI", &request.threshold_wei)
+                .kill_on_drop(true)
+                .status();
+            let status = tokio::time::timeout(self.timeout, status)
+                .await
+                .map_err(|_| "command timed out".to_string())?
+                .map_err(|e| format!("command failed to start: {}", e))?;
+            if !status.success() {
+                return Err(format!("command failed with {}", status));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Requests a top-up of the wallets whose balance becomes critical
+pub async fn top_up_hooks(
+    mut events: Receiver<MonitorEvent>,
+    audit: SharedTopUpAudit,
+    config: &TopUpConfig,
+) {
+    let hooks = TopUpHooks::new(config);
+    let cooldown = Duration::seconds(config.cooldown_s() as i64);
+    let mut last_attempts = audit.read().await.last_attempts();
+
+    while let Some(event) = next_event(&mut events, "top_up").await {
+        let MonitorEvent::BalanceCritical {
+            wallet,
+            address,
+            balance_wei,
+            threshold_wei,
+        } = event
+        else {
+            continue;
+        };
+        let request = TopUpRequest {
+            wallet,
+            address,
+            balance_wei,
+            threshold_wei,
+        };
+
+        let now = Utc::now();
+        let last = last_attempts.get(&request.wallet).copied();
+        let outcome = match cooldown_until(last, now, cooldown) {
+            Some(until) => {
+                warn!(wallet = %request.wallet, %until, "Top-up skipped during cooldown");
+                TopUpOutcome::Cooldown { until }
+            }
+            None => {
+                last_attempts.insert(request.wallet.clone(), now);
+                match hooks.call(&request).await {
+                    Ok(()) => {
+                        info!(
+                            wallet = %request.wallet,
+                            balance_wei = %request.balance_wei,
+                            "Top-up requested"
+                        );
+                        TopUpOutcome::Triggered
+                    }
+                    Err(error) => {
+                        warn!(wallet = %request.wallet, %error, "Top-up request failed");
+                        TopUpOutcome::Failed { error }
+                    }
+                }
+            }
+        };
+
+        audit.write().await.record(TopUpRecord {
+            at: now,
+            request,
+            outcome,
+        });
+    }
+}
+
+/// List the top-up attempts, oldest first. Requires the admin token.
+pub async fn get_top_ups(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    audit: SharedTopUpAudit,
+) -> Result<Json<Vec<TopUpRecord>>, StatusCode> {
+    auth.check(&headers)?;
+    Ok(Json(audit.read().await.records.iter().cloned().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cooldown_until, TopUpAudit, TopUpOutcome, TopUpRecord, TopUpRequest};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_top_up_cooldown() {
+        let now = Utc::now();
+        let cooldown = Duration::hours(1);
+
+        assert_eq!(cooldown_until(None, now, cooldown), None);
+        assert_eq!(
+            cooldown_until(Some(now - Duration::minutes(10)), now, cooldown),
+            Some(now + Duration::minutes(50))
+        );
+        assert_eq!(
+            cooldown_until(Some(now - Duration::hours(2)), now, cooldown),
+            None
+        );
+
+        // Skipped attempts do not extend the cooldown
+        let mut audit = TopUpAudit::default();
+        let record = |wallet: &str, at, outcome| TopUpRecord {
+            at,
+            request: TopUpRequest {
+                wallet: wallet.to_string(),
+                address: "0xCAFE".to_string(),
+                balance_wei: "1".to_string(),
+                threshold_wei: "10".to_string(),
+            },
+            outcome,
+        };
+        let triggered_at = now - Duration::minutes(10);
+        audit.record(record("deposit", triggered_at, TopUpOutcome::Triggered));
+        audit.record(record(
+            "deposit",
+            now,
+            TopUpOutcome::Cooldown {
+                until: now + Duration::minutes(50),
+            },
+        ));
+        audit.record(record(
+            "validating",
+            now,
+            TopUpOutcome::Failed {
+                error: "webhook failed".to_string(),
+            },
+        ));
+        let last_attempts = audit.last_attempts();
+        assert_eq!(last_attempts.get("deposit"), Some(&triggered_at));
+        assert_eq!(last_attempts.get("validating"), Some(&now));
+    }
+}
@@ -0,0 +1,176 @@
+//! Explicit JSON formats of the bitcoin txids and outpoints in bridge data, so that
+//! downstream parsers do not depend on the `Display` impls of the bitcoin crate:
+//! - a txid is 64 lowercase hex chars, by default in display byte order, i.e. as
+//!   shown by bitcoind and block explorers
+//! - an outpoint is `<txid>:<vout>`, with `vout` in decimal
+//!
+//! API responses and txids in request paths use the byte order of
+//! `API_TXID_BYTE_ORDER`, while persisted state always uses the display order so
+//! that changing the setting does not invalidate checkpoints. Server-sent event
+//! streams are written after the request completes and keep the display order.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use bitcoin::{hashes::Hash, OutPoint, Txid};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Byte order of the hex txids
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TxidByteOrder {
+    /// Reversed hash bytes, as shown by bitcoind RPCs and block explorers
+    #[default]
+    Display,
+    /// Hash bytes as serialized in transactions
+    Internal,
+}
+
+tokio::task_local! {
+    /// Byte order used while handling an API request
+    static BYTE_ORDER: TxidByteOrder;
+}
+
+/// Byte order of the current API request, the display order outside of one
+fn byte_order() -> TxidByteOrder {
+    BYTE_ORDER.try_with(|order| *order).unwrap_or_default()
+}
+
+/// Middleware formatting the txids of the request and response in `order`
+pub async fn with_txid_byte_order(
+    State(order): State<TxidByteOrder>,
+    request: Request,
+    next: Next,
+) -> Response {
+    BYTE_ORDER.scope(order, next.run(request)).await
+}
+
+fn txid_to_hex(txid: &Txid, order: TxidByteOrder) -> String {
+    let mut bytes = txid.to_byte_array();
+    if order == TxidByteOrder::Display {
+        bytes.reverse();
+    }
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn txid_from_hex(s: &str, order: TxidByteOrder) -> Result<Txid, String> {
+    if s.len() != 64 || !s.is_ascii() {
+        return Err(format!("expected 64 hex chars, got {:?}", s));
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+            .map_err(|_| format!("invalid hex txid {:?}", s))?;
+    }
+    if order == TxidByteOrder::Display {
+        bytes.reverse();
+    }
+    Ok(Txid::from_byte_array(bytes))
+}
+
+/// Formats a txid in the byte order of the current request
+pub fn format_txid(txid: &Txid) -> String {
+    txid_to_hex(txid, byte_order())
+}
+
+/// Parses a txid in the byte order of the current request, e.g. from a path
+pub fn parse_txid(s: &str) -> Result<Txid, String> {
+    txid_from_hex(s, byte_order())
+}
+
+/// Formats an outpoint as `<txid>:<vout>`
+pub fn format_outpoint(outpoint: &OutPoint) -> String {
+    format!("{}:{}", format_txid(&outpoint.txid), outpoint.vout)
+}
+
+/// Parses an outpoint formatted as `<txid>:<vout>`
+pub fn parse_outpoint(s: &str) -> Result<OutPoint, String> {
+    let (txid, vout) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected <txid>:<vout>, got {:?}", s))?;
+    let vout = vout
+        .parse()
+        .map_err(|_| format!("invalid vout in outpoint {:?}", s))?;
+    Ok(OutPoint::new(parse_txid(txid)?, vout))
+}
+
+/// `#[serde(with = "txid_format::txid")]` for `Txid` fields
+pub mod txid {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(txid: &Txid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_txid(txid))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Txid, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_txid(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// `#[serde(with = "txid_format::option_txid")]` for `Option<Txid>` fields
+pub mod option_txid {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(txid: &Option<Txid>, serializer: S) -> Result<S::Ok, S::Error> {
+        match txid {
+            Some(txid) => serializer.serialize_some(&format_txid(txid)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Txid>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| parse_txid(&s).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// `#[serde(with = "txid_format::outpoint")]` for `OutPoint` fields
+pub mod outpoint {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(outpoint: &OutPoint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_outpoint(outpoint))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OutPoint, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_outpoint(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_outpoint, format_txid, parse_outpoint, parse_txid, TxidByteOrder};
+    use bitcoin::{OutPoint, Txid};
+    use std::str::FromStr;
+
+    const TXID: &str = "0102030405060708091011121314151617181920212223242526272829303132";
+    const REVERSED: &str = "3231302928272625242322212019181716151413121110090807060504030201";
+
+    #[tokio::test]
+    async fn test_txid_byte_order() {
+        let txid = Txid::from_str(TXID).unwrap();
+        assert_eq!(format_txid(&txid), TXID);
+        assert_eq!(parse_txid(TXID), Ok(txid));
+        assert!(parse_txid("01").is_err());
+
+        let outpoint = OutPoint::new(txid, 3);
+        assert_eq!(format_outpoint(&outpoint), format!("{}:3", TXID));
+        assert_eq!(parse_outpoint(&format!("{}:3", TXID)), Ok(outpoint));
+        assert!(parse_outpoint(TXID).is_err());
+
+        super::BYTE_ORDER
+            .scope(TxidByteOrder::Internal, async {
+                assert_eq!(format_txid(&txid), REVERSED);
+                assert_eq!(parse_txid(REVERSED), Ok(txid));
+                assert_eq!(format_outpoint(&outpoint), format!("{}:3", REVERSED));
+            })
+            .await;
+    }
+}
@@ -0,0 +1,203 @@
+//! Pings of external uptime monitors, e.g. healthchecks.io ping URLs, after each
+//! refresh cycle of a monitoring task. The monitors alert once the pings stop, so
+//! that even a fully wedged backend gets noticed.
+
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::{sync::RwLock, time::Duration};
+use tracing::{info, warn};
+
+use crate::{auth::AdminAuth, checkpoint, tasks::TaskRegistry};
+
+/// Max time a ping may take
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ping URLs by task name, editable at runtime through the admin endpoints. They
+/// are seeded from `UPTIME_PINGS` until first edited, then persisted to
+/// `UPTIME_PINGS_PATH` when configured.
+#[derive(Clone, Debug)]
+pub struct UptimePings {
+    path: Option<Arc<str>>,
+    urls: Arc<RwLock<BTreeMap<String, String>>>,
+    client: reqwest::Client,
+}
+
+/// Whether `url` is an absolute HTTP(S) URL
+fn is_ping_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+impl UptimePings {
+    /// Loads the ping URLs stored at `path`, or `defaults` if none are
+    pub fn load(path: Option<&str>, defaults: BTreeMap<String, String>) -> Self {
+        let urls = match path.and_then(checkpoint::load::<BTreeMap<String, String>>) {
+            Some(urls) => {
+                info!(
+                    path = path.unwrap_or_default(),
+                    pings = urls.len(),
+                    "Loaded uptime pings"
+                );
+                urls
+            }
+            None => defaults,
+        };
+        Self {
+            path: path.map(Arc::from),
+            urls: Arc::new(RwLock::new(urls)),
+            client: reqwest::Client::builder()
+                .timeout(PING_TIMEOUT)
+                .build()
+                .expect("Failed to create uptime ping HTTP client"),
+        }
+    }
+
+    /// Returns the ping URLs by task name
+    pub async fn snapshot(&self) -> BTreeMap<String, String> {
+        self.urls.read().await.clone()
+    }
+
+    /// Applies `edit` to the ping URLs and persists them, `None` if `edit` returns
+    /// false as nothing changed
+    async fn update(
+        &self,
+        edit: impl FnOnce(&mut BTreeMap<String, String>) -> bool,
+    ) -> Option<BTreeMap<String, String>> {
+        let mut urls = self.urls.write().await;
+        if !edit(&mut urls) {
+            return None;
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = checkpoint::save(path, &*urls) {
+                warn!(error = %e, "Failed to store uptime pings");
+            }
+        }
+        Some(urls.clone())
+    }
+
+    /// Pings `url` after each refresh cycle of `task`, instead of its previous URL
+    pub async fn set(&self, task: &str, url: &str) -> BTreeMap<String, String> {
+        self.update(|urls| {
+            urls.insert(task.to_string(), url.to_string());
+            true
+        })
+        .await
+        .expect("setting a ping to change the pings")
+    }
+
+    /// Stops pinging after the refresh cycles of `task`, `None` if it was not
+    pub async fn remove(&self, task: &str) -> Option<BTreeMap<String, String>> {
+        self.update(|urls| urls.remove(task).is_some()).await
+    }
+
+    /// Pings the monitor of `task` in the background, if any
+    pub async fn ping(&self, task: &str) {
+        let Some(url) = self.urls.read().await.get(task).cloned() else {
+            return;
+        };
+        let client = self.client.clone();
+        let task = task.to_string();
+        tokio::spawn(async move {
+            let result = client
+                .get(&url)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                warn!(error = %e, %task, "Uptime ping failed");
+            }
+        });
+    }
+}
+
+/// Body of the ping registration endpoint
+#[derive(Deserialize, Debug)]
+pub struct UptimePingRequest {
+    url: String,
+}
+
+/// List the ping URLs by task name. Requires the admin token.
+pub async fn get_uptime_pings(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    pings: UptimePings,
+) -> Result<Json<BTreeMap<String, String>>, StatusCode> {
+    auth.check(&headers)?;
+    Ok(Json(pings.snapshot().await))
+}
+
+/// Ping a URL after each refresh cycle of a task. Requires the admin token.
+pub async fn put_uptime_ping(
+    Path(task): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<UptimePingRequest>,
+    auth: AdminAuth,
+    pings: UptimePings,
+    tasks: TaskRegistry,
+) -> Result<Json<BTreeMap<String, String>>, StatusCode> {
+    auth.check(&headers)?;
+    if !tasks.snapshot().await.contains_key(task.as_str()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if !is_ping_url(&request.url) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let urls = pings.set(&task, &request.url).await;
+    info!(%task, "Registered uptime ping");
+    Ok(Json(urls))
+}
+
+/// Stop pinging after the refresh cycles of a task. Requires the admin token.
+pub async fn delete_uptime_ping(
+    Path(task): Path<String>,
+    headers: HeaderMap,
+    auth: AdminAuth,
+    pings: UptimePings,
+) -> Result<Json<BTreeMap<String, String>>, StatusCode> {
+    auth.check(&headers)?;
+    let urls = pings.remove(&task).await.ok_or(StatusCode::NOT_FOUND)?;
+    info!(%task, "Removed uptime ping");
+    Ok(Json(urls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_ping_url, UptimePings};
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_uptime_pings_persistence() {
+        let path =
+            std::env::temp_dir().join(format!("uptime_pings_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let defaults = BTreeMap::from([(
+            "network_status".to_string(),
+            "https://hc-ping.com/1".to_string(),
+        )]);
+
+        let pings = UptimePings::load(Some(path), defaults.clone());
+        assert_eq!(pings.snapshot().await, defaults);
+        pings.set("bridge_status", "https://hc-ping.com/2").await;
+        assert!(pings.remove("network_status").await.is_some());
+        assert!(pings.remove("network_status").await.is_none());
+
+        // Edits outlive the defaults
+        let reloaded = UptimePings::load(Some(path), defaults);
+        assert_eq!(
+            reloaded.snapshot().await,
+            BTreeMap::from([(
+                "bridge_status".to_string(),
+                "https://hc-ping.com/2".to_string()
+            )])
+        );
+        std::fs::remove_file(path).unwrap();
+
+        assert!(is_ping_url("https://hc-ping.com/1"));
+        assert!(!is_ping_url("ftp://hc-ping.com/1"));
+        assert!(!is_ping_url("hc-ping.com/1"));
+    }
+}
@@ -0,0 +1,276 @@
+//! User operations stored as they are scanned, so that the activity aggregates of
+//! a period can be rebuilt from them through `/api/admin/recompute_stats`, e.g.
+//! after an aggregation bug fix, without refetching them from the explorer.
+
+use axum::{
+    extract::Query,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::error;
+
+use crate::{
+    aggregator::{Event, WindowStats},
+    auth::AdminAuth,
+    paymaster_report::parse_time,
+    sqlite::{ReplicaTable, SqliteDb},
+    store::{open_store, StatsStore, Timestamped},
+};
+
+/// Max number of user ops kept when they are not persisted
+const MAX_STORED_USER_OPS: usize = 100_000;
+
+/// User operation as stored, see `UserOp` of the activity scan
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StoredUserOp {
+    pub at: DateTime<Utc>,
+    pub sender: String,
+    pub gas_used: u64,
+    pub hash: Option<String>,
+    pub target: Option<String>,
+}
+
+impl Timestamped for StoredUserOp {
+    fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+}
+
+impl StoredUserOp {
+    fn event(&self) -> Event<'_> {
+        Event {
+            at: self.at,
+            account: &self.sender,
+            gas_used: self.gas_used,
+        }
+    }
+}
+
+/// User operations of the scanned interval, oldest first.
+///
+/// They are persisted to the `user_ops` table of the SQLite database or to the JSON
+/// lines file at `USER_OPS_PATH`, and reloaded on startup. Otherwise only the most
+/// recent ones are kept in memory.
+#[derive(Debug)]
+pub struct UserOpHistory {
+    store: Box<dyn StatsStore<StoredUserOp>>,
+    /// Time of the newest stored user op; the next scan only stores newer ones
+    stored_until: Option<DateTime<Utc>>,
+}
+
+impl UserOpHistory {
+    /// Creates the history, loading the user ops persisted in `sqlite` or else at
+    /// `path`.
+    ///
+    /// Up to `write_buffer` user ops wait to be persisted before new ones are dropped.
+    pub fn load(sqlite: Option<&SqliteDb>, path: Option<String>, write_buffer: usize) -> Self {
+        let store = open_store(
+            sqlite,
+            "user_ops",
+            path,
+            DateTime::<Utc>::MIN_UTC,
+            write_buffer,
+            MAX_STORED_USER_OPS,
+        );
+        let stored_until = store.records().last().map(Timestamped::at);
+        Self {
+            store,
+            stored_until,
+        }
+    }
+
+    /// Getter for `stored_until`
+    pub fn stored_until(&self) -> Option<DateTime<Utc>> {
+        self.stored_until
+    }
+
+    /// Stores the user ops of a completed scan and drops the ones from before the
+    /// scanned interval, starting at `since`
+    pub fn save(&mut self, mut user_ops: Vec<StoredUserOp>, since: DateTime<Utc>) {
+        user_ops.sort_by_key(|op| op.at);
+        if let Some(newest) = user_ops.last() {
+            self.stored_until = self.stored_until.max(Some(newest.at));
+        }
+        self.store.save_events(user_ops);
+        self.store.prune(since);
+    }
+
+    /// Drops the user ops no longer retained from the persisted ones, which
+    /// otherwise only shrink on restart
+    pub fn compact(&mut self) {
+        self.store.compact();
+    }
+
+    /// Aggregates of the user ops stored within `[from, to)`, by UTC day and in total
+    fn recompute(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> RecomputedStats {
+        aggregate(
+            from,
+            to,
+            self.store.oldest(),
+            &self.store.query_range(from, to),
+        )
+    }
+}
+
+/// Aggregates of `user_ops`, the stored ones within `[from, to)`
+fn aggregate(
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    stored_since: Option<DateTime<Utc>>,
+    user_ops: &[StoredUserOp],
+) -> RecomputedStats {
+    let mut days: BTreeMap<NaiveDate, WindowStats> = BTreeMap::new();
+    let mut total = WindowStats::default();
+    for op in user_ops {
+        let event = op.event();
+        days.entry(op.at.date_naive()).or_default().add(&event);
+        total.add(&event);
+    }
+
+    RecomputedStats {
+        from,
+        to,
+        stored_since,
+        days: days
+            .into_iter()
+            .map(|(date, stats)| (date, PeriodStats::from(&stats)))
+            .collect(),
+        total: PeriodStats::from(&total),
+    }
+}
+
+/// Shared user op history
+pub type SharedUserOpHistory = Arc<RwLock<UserOpHistory>>;
+
+/// Activity aggregates of a period
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PeriodStats {
+    user_ops: u64,
+    gas_used: u64,
+    unique_active_accounts: u64,
+}
+
+impl From<&WindowStats> for PeriodStats {
+    fn from(stats: &WindowStats) -> Self {
+        Self {
+            user_ops: stats.events,
+            gas_used: stats.gas_used,
+            unique_active_accounts: stats.unique_accounts(),
+        }
+    }
+}
+
+/// Activity aggregates rebuilt from the stored user ops
+#[derive(Serialize, Debug)]
+pub struct RecomputedStats {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    /// Time of the oldest stored user op; the aggregates of earlier days are
+    /// incomplete
+    stored_since: Option<DateTime<Utc>>,
+    /// By UTC day, days without user ops left out
+    days: BTreeMap<NaiveDate, PeriodStats>,
+    total: PeriodStats,
+}
+
+/// Query parameters of the stats recomputation endpoint
+#[derive(Deserialize, Debug)]
+pub struct RecomputeStatsQuery {
+    /// Start of the period, RFC 3339 or `YYYY-MM-DD`
+    from: String,
+    /// End of the period, excluded, RFC 3339 or `YYYY-MM-DD`; defaults to now
+    to: Option<String>,
+}
+
+/// Rebuild the activity aggregates of a period from the stored user ops, queried
+/// from the database replica when configured. Requires the admin token.
+pub async fn post_recompute_stats(
+    headers: HeaderMap,
+    Query(query): Query<RecomputeStatsQuery>,
+    auth: AdminAuth,
+    history: SharedUserOpHistory,
+    replica: Option<ReplicaTable>,
+) -> Result<Json<RecomputedStats>, StatusCode> {
+    auth.check(&headers)?;
+    let from = parse_time(&query.from).ok_or(StatusCode::BAD_REQUEST)?;
+    let to = match &query.to {
+        Some(to) => parse_time(to).ok_or(StatusCode::BAD_REQUEST)?,
+        None => Utc::now(),
+    };
+    if from >= to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let stats = match replica {
+        Some(replica) => {
+            let user_ops = replica.query_range(from, to).await.map_err(|e| {
+                error!(error = %e, "User op range query failed");
+                StatusCode::BAD_GATEWAY
+            })?;
+            let stored_since = history.read().await.store.oldest();
+            aggregate(from, to, stored_since, &user_ops)
+        }
+        None => history.read().await.recompute(from, to),
+    };
+    Ok(Json(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PeriodStats, StoredUserOp, UserOpHistory};
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
+
+    #[test]
+    fn test_recompute_stats() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 10, 0, 0, 0).unwrap();
+        let op = |hours, sender: &str, gas_used| StoredUserOp {
+            at: start + Duration::hours(hours),
+            sender: sender.to_string(),
+            gas_used,
+            hash: None,
+            target: None,
+        };
+        let mut history = UserOpHistory::load(None, None, 16);
+        assert_eq!(history.stored_until(), None);
+
+        // Scans return their user ops in any order
+        history.save(
+            vec![
+                op(30, "0xbbb", 50),
+                op(1, "0xaaa", 100),
+                op(2, "0xaaa", 200),
+            ],
+            start,
+        );
+        // The scanned interval moved on
+        history.save(
+            vec![op(50, "0xccc", 10), op(40, "0xccc", 10)],
+            start + Duration::minutes(90),
+        );
+        assert_eq!(history.stored_until(), Some(start + Duration::hours(50)));
+
+        let stats = history.recompute(start, start + Duration::days(2));
+        assert_eq!(stats.stored_since, Some(start + Duration::hours(2)));
+        assert_eq!(
+            stats.days[&NaiveDate::from_ymd_opt(2025, 3, 10).unwrap()],
+            PeriodStats {
+                user_ops: 1,
+                gas_used: 200,
+                unique_active_accounts: 1,
+            }
+        );
+        assert_eq!(stats.days.len(), 2);
+        assert_eq!(
+            stats.total,
+            PeriodStats {
+                user_ops: 3,
+                gas_used: 260,
+                unique_active_accounts: 3,
+            }
+        );
+    }
+}
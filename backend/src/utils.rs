@@ -1,4 +1,10 @@
+use bitcoin::Txid;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+
+use crate::txid_format::format_txid;
 
 /// Creates a JSON-RPC client with a dynamic URL
 pub fn create_rpc_client(rpc_url: &str) -> HttpClient {
@@ -6,3 +12,152 @@ pub fn create_rpc_client(rpc_url: &str) -> HttpClient {
         .build(rpc_url)
         .expect("Failed to create JSON-RPC client")
 }
+
+/// Parses a duration such as `90s`, `5m`, `12h` or `7d`
+pub fn parse_duration(s: &str) -> Option<chrono::Duration> {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = s.split_at(unit_start);
+    let amount: i64 = amount.parse().ok()?;
+    match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        _ => None,
+    }
+}
+
+/// Longest a long-polling request is held, below the usual proxy idle timeouts
+const MAX_LONG_POLL_WAIT_S: i64 = 60;
+
+/// Parses the `wait_for_change` parameter of a long-polling request, e.g. `30s`,
+/// capped to a minute
+pub fn parse_long_poll_wait(s: &str) -> Option<std::time::Duration> {
+    parse_duration(s)
+        .filter(|wait| *wait > chrono::Duration::zero())?
+        .min(chrono::Duration::seconds(MAX_LONG_POLL_WAIT_S))
+        .to_std()
+        .ok()
+}
+
+/// Appends to a queue, dropping the oldest entries beyond `max_len`
+pub fn push_bounded<T>(queue: &mut VecDeque<T>, value: T, max_len: usize) {
+    queue.push_back(value);
+    while queue.len() > max_len {
+        queue.pop_front();
+    }
+}
+
+/// Number of characters kept at each end of a redacted address, after the `0x` prefix
+const REDACTED_ADDRESS_KEEP: usize = 4;
+
+/// Replaces the middle characters of an address, e.g. `0x1234…cdef`
+pub fn redact_address(address: &str) -> String {
+    let (prefix, digits) = address.split_at(if address.starts_with("0x") { 2 } else { 0 });
+    if !digits.is_ascii() || digits.len() <= 2 * REDACTED_ADDRESS_KEEP {
+        return address.to_string();
+    }
+    format!(
+        "{}{}…{}",
+        prefix,
+        &digits[..REDACTED_ADDRESS_KEEP],
+        &digits[digits.len() - REDACTED_ADDRESS_KEEP..]
+    )
+}
+
+/// Label a status enum serializes to, e.g. `In progress`
+pub fn status_label(status: &impl Serialize) -> String {
+    match serde_json::to_value(status) {
+        Ok(Value::String(label)) => label,
+        _ => String::new(),
+    }
+}
+
+/// CSV field of an optional txid, empty when unset
+pub fn txid_field(txid: &Option<Txid>) -> String {
+    txid.as_ref().map(format_txid).unwrap_or_default()
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Renders a row as a CSV line, including the line break
+pub fn csv_line<S: AsRef<str>>(row: &[S]) -> String {
+    let mut line = row
+        .iter()
+        .map(|f| csv_field(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// Renders a header and rows as CSV, one line per row
+pub fn to_csv(header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut csv = csv_line(header);
+    for row in rows {
+        csv.push_str(&csv_line(&row));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_duration, parse_long_poll_wait, redact_address, to_csv};
+    use chrono::Duration;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Some(Duration::seconds(90)));
+        assert_eq!(parse_duration("5m"), Some(Duration::minutes(5)));
+        assert_eq!(parse_duration("12h"), Some(Duration::hours(12)));
+        assert_eq!(parse_duration("7d"), Some(Duration::days(7)));
+        assert_eq!(parse_duration("7"), None);
+        assert_eq!(parse_duration("d"), None);
+        assert_eq!(parse_duration("7w"), None);
+        assert_eq!(parse_duration("-7d"), None);
+    }
+
+    #[test]
+    fn test_parse_long_poll_wait() {
+        assert_eq!(
+            parse_long_poll_wait("30s"),
+            Some(std::time::Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_long_poll_wait("1h"),
+            Some(std::time::Duration::from_secs(60))
+        );
+        assert_eq!(parse_long_poll_wait("0s"), None);
+        assert_eq!(parse_long_poll_wait("soon"), None);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let csv = to_csv(
+            &["name", "value"],
+            [
+                vec!["plain".to_string(), "1".to_string()],
+                vec!["a, \"quoted\"".to_string(), "2".to_string()],
+            ],
+        );
+        assert_eq!(csv, "name,value\nplain,1\n\"a, \"\"quoted\"\"\",2\n");
+    }
+
+    #[test]
+    fn test_redact_address() {
+        assert_eq!(
+            redact_address("0x1234567890abcdef1234567890abcdef12345678"),
+            "0x1234…5678"
+        );
+        // Too short to hide anything
+        assert_eq!(redact_address("0x12345678"), "0x12345678");
+    }
+}
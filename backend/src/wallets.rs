@@ -1,74 +1,395 @@
-use axum::Json;
-use jsonrpsee::core::client::ClientT;
+use axum::{extract::Query, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use jsonrpsee::core::{client::ClientT, params::BatchRequestBuilder};
 use jsonrpsee::http_client::HttpClient;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::Arc;
-use tokio::{
-    sync::RwLock,
-    time::{interval, Duration},
-};
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::sync::RwLock;
 use tracing::info;
 
+use crate::alerts::{Alerts, Severity};
+use crate::checks::rpc_failure;
 use crate::config::NetworkConfig;
-use crate::utils::create_rpc_client;
+use crate::degradation;
+use crate::display::format_amount;
+use crate::events::{EventBus, MonitorEvent};
+use crate::network::FailureKind;
+use crate::polling::AdaptiveInterval;
+use crate::store::{MemoryStore, StatsStore, Timestamped};
+use crate::tasks::{TaskRegistry, WALLET_BALANCES_TASK};
+use crate::utils::{create_rpc_client, parse_duration};
+
+/// Balance refresh interval in seconds
+const BALANCES_REFETCH_INTERVAL_S: u64 = 10;
+
+/// Decimals of the native token, i.e. Wei per token
+pub(crate) const NATIVE_TOKEN_DECIMALS: u32 = 18;
 
 pub type SharedWallets = Arc<RwLock<PaymasterWallets>>;
+
+/// Latest and pending nonces of a wallet, to notice stuck transactions
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct NonceStatus {
+    /// Number of mined transactions
+    latest: u64,
+    /// Number of transactions including those in the mempool
+    pending: u64,
+    /// Since when transactions are pending without any being mined
+    gap_since: Option<DateTime<Utc>>,
+    /// Transactions stayed pending for longer than the threshold
+    stuck: bool,
+}
+
+impl NonceStatus {
+    /// Updates the status with nonces fetched at `now`
+    fn update(&mut self, latest: u64, pending: u64, now: DateTime<Utc>, threshold_s: u64) {
+        // A mined transaction restarts the clock, as the next one may be fine
+        let advanced = latest != self.latest;
+        self.gap_since = match self.gap_since {
+            _ if pending <= latest => None,
+            Some(since) if !advanced => Some(since),
+            _ => Some(now),
+        };
+        self.latest = latest;
+        self.pending = pending;
+        self.stuck = self
+            .gap_since
+            .is_some_and(|since| (now - since).num_seconds() > threshold_s as i64);
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct Wallet {
     /// Wallet address
     address: String,
     /// Wallet balance in Wei
     balance: String,
+    /// Balance in whole tokens, e.g. `3.4 ETH`
+    balance_display: String,
+    /// Unset until the nonces are first fetched
+    nonce: Option<NonceStatus>,
+    #[serde(skip)]
+    symbol: String,
 }
 
 impl Wallet {
-    pub fn new(address: String, balance: String) -> Self {
-        Self { address, balance }
+    pub fn new(address: String, balance: String, symbol: &str) -> Self {
+        let mut wallet = Self {
+            address,
+            balance: String::new(),
+            balance_display: String::new(),
+            nonce: None,
+            symbol: symbol.to_string(),
+        };
+        wallet.update_balance(balance);
+        wallet
     }
 
     pub fn update_balance(&mut self, balance: String) {
         self.balance = balance;
+        self.balance_display = self
+            .balance_wei()
+            .map(|wei| format_amount(wei, NATIVE_TOKEN_DECIMALS, &self.symbol))
+            .unwrap_or_default();
+    }
+
+    /// Balance in Wei, `None` if it is not a valid integer
+    pub fn balance_wei(&self) -> Option<u128> {
+        self.balance.parse().ok()
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct PaymasterWallets {
     /// Deposit paymaster wallet
-    deposit: Wallet,
+    pub(crate) deposit: Wallet,
     /// Validating paymaster wallet
-    validating: Wallet,
+    pub(crate) validating: Wallet,
+    /// Other hot wallets we operate, e.g. operator EOAs, by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) operators: BTreeMap<String, Wallet>,
 }
 impl PaymasterWallets {
-    pub fn new(deposit: Wallet, validating: Wallet) -> Self {
+    pub fn new(deposit: Wallet, validating: Wallet, operators: BTreeMap<String, Wallet>) -> Self {
         Self {
             deposit,
             validating,
+            operators,
         }
     }
+
+    /// Every monitored wallet with its name
+    fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Wallet)> {
+        [
+            ("deposit", &mut self.deposit),
+            ("validating", &mut self.validating),
+        ]
+        .into_iter()
+        .chain(
+            self.operators
+                .iter_mut()
+                .map(|(name, wallet)| (name.as_str(), wallet)),
+        )
+    }
+}
+
+/// Balances of the monitored wallets at one point in time
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BalanceSample {
+    at: DateTime<Utc>,
+    /// Balance in Wei by wallet name, missing for the wallets whose query failed
+    balances: BTreeMap<String, String>,
+}
+
+impl Timestamped for BalanceSample {
+    fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+}
+
+/// Most recent balance samples, oldest first. They are only kept in memory, so
+/// the history covers up to `HISTORY_BUFFER_LEN` refreshes.
+#[derive(Debug)]
+pub struct BalanceHistory {
+    store: Box<dyn StatsStore<BalanceSample>>,
+}
+
+/// Shared balance history
+pub type SharedBalanceHistory = Arc<RwLock<BalanceHistory>>;
+
+impl BalanceHistory {
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            store: Box::new(MemoryStore::new(max_samples)),
+        }
+    }
+
+    fn record(&mut self, sample: BalanceSample) {
+        self.store.save_snapshot(sample);
+    }
+
+    /// Samples taken at or after `since`
+    fn since(&self, since: DateTime<Utc>) -> Vec<BalanceSample> {
+        self.store.query_range(since, DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// Event for a wallet balance crossing `threshold_wei`, `low` tracks the side it was on
+fn balance_crossing(
+    name: &str,
+    wallet: &Wallet,
+    threshold_wei: u128,
+    low: &mut bool,
+) -> Option<MonitorEvent> {
+    let balance_wei = wallet.balance_wei()?;
+    let is_low = balance_wei < threshold_wei;
+    if is_low == *low {
+        return None;
+    }
+    *low = is_low;
+
+    let event = if is_low {
+        MonitorEvent::BalanceLow {
+            wallet: name.to_string(),
+            address: wallet.address.clone(),
+            balance_wei: wallet.balance.clone(),
+            threshold_wei: threshold_wei.to_string(),
+        }
+    } else {
+        MonitorEvent::BalanceRecovered {
+            wallet: name.to_string(),
+            address: wallet.address.clone(),
+            balance_wei: wallet.balance.clone(),
+        }
+    };
+    Some(event)
+}
+
+/// Event for a wallet balance dropping below the critical `threshold_wei`, for
+/// the top-up hooks. `critical` tracks the side it was on.
+fn critical_crossing(
+    name: &str,
+    wallet: &Wallet,
+    threshold_wei: u128,
+    critical: &mut bool,
+) -> Option<MonitorEvent> {
+    match balance_crossing(name, wallet, threshold_wei, critical)? {
+        MonitorEvent::BalanceLow {
+            wallet,
+            address,
+            balance_wei,
+            threshold_wei,
+        } => Some(MonitorEvent::BalanceCritical {
+            wallet,
+            address,
+            balance_wei,
+            threshold_wei,
+        }),
+        // Recoveries are published for the low balance threshold
+        _ => None,
+    }
 }
 
 /// Periodically fetches wallet balances
-pub async fn fetch_balances_task(wallets: SharedWallets, config: &NetworkConfig) {
+pub async fn fetch_balances_task(
+    wallets: SharedWallets,
+    history: SharedBalanceHistory,
+    events: EventBus,
+    alerts: Alerts,
+    tasks: TaskRegistry,
+    config: &NetworkConfig,
+) {
     info!("Fetching balances...");
-    let mut interval = interval(Duration::from_secs(10));
+    tasks
+        .register(WALLET_BALANCES_TASK, BALANCES_REFETCH_INTERVAL_S)
+        .await;
+    let mut interval = AdaptiveInterval::new(WALLET_BALANCES_TASK, BALANCES_REFETCH_INTERVAL_S);
     let rpc_client = create_rpc_client(config.reth_url());
+    let (mut deposit_low, mut validating_low) = (false, false);
+    let (mut deposit_critical, mut validating_critical) = (false, false);
 
     loop {
         interval.tick().await;
+        tasks.start_refresh(WALLET_BALANCES_TASK).await;
 
         let mut locked_wallets = wallets.write().await;
 
+        let addresses: Vec<String> = locked_wallets
+            .iter_mut()
+            .map(|(_, wallet)| wallet.address.clone())
+            .collect();
+        // In the order of `iter_mut`, the paymaster wallets first
+        let mut fetched = fetch_wallet_balances(&rpc_client, &addresses)
+            .await
+            .into_iter();
+        let balance_dep = fetched.next().flatten();
+        let balance_val = fetched.next().flatten();
+
         let deposit_wallet = &mut locked_wallets.deposit;
-        let balance_dep = fetch_wallet_balance(&rpc_client, &deposit_wallet.address).await;
         deposit_wallet.update_balance(balance_dep.clone().unwrap_or_else(|| "0".to_string()));
 
         let validating_wallet = &mut locked_wallets.validating;
-        let balance_val = fetch_wallet_balance(&rpc_client, &validating_wallet.address).await;
         validating_wallet.update_balance(balance_val.clone().unwrap_or_else(|| "0".to_string()));
+
+        // Failed queries leave a zero balance, which is not a crossing
+        if let Some(threshold_wei) = config.low_balance_threshold_wei() {
+            let crossings = [
+                balance_dep.as_ref().and_then(|_| {
+                    balance_crossing(
+                        "deposit",
+                        &locked_wallets.deposit,
+                        threshold_wei,
+                        &mut deposit_low,
+                    )
+                }),
+                balance_val.as_ref().and_then(|_| {
+                    balance_crossing(
+                        "validating",
+                        &locked_wallets.validating,
+                        threshold_wei,
+                        &mut validating_low,
+                    )
+                }),
+            ];
+            for event in crossings.into_iter().flatten() {
+                events.publish(event);
+            }
+        }
+        if let Some(threshold_wei) = config.critical_balance_threshold_wei() {
+            let crossings = [
+                balance_dep.as_ref().and_then(|_| {
+                    critical_crossing(
+                        "deposit",
+                        &locked_wallets.deposit,
+                        threshold_wei,
+                        &mut deposit_critical,
+                    )
+                }),
+                balance_val.as_ref().and_then(|_| {
+                    critical_crossing(
+                        "validating",
+                        &locked_wallets.validating,
+                        threshold_wei,
+                        &mut validating_critical,
+                    )
+                }),
+            ];
+            for event in crossings.into_iter().flatten() {
+                events.publish(event);
+            }
+        }
+
+        let mut nonces_fetched = true;
+        let now = Utc::now();
+        let mut balances = BTreeMap::new();
+        for (name, wallet) in locked_wallets.iter_mut() {
+            // Paymaster balances are fetched above, with their crossings
+            let balance = match name {
+                "deposit" => balance_dep.clone(),
+                "validating" => balance_val.clone(),
+                _ => {
+                    let balance = fetched.next().flatten();
+                    wallet.update_balance(balance.clone().unwrap_or_else(|| "0".to_string()));
+                    balance
+                }
+            };
+            if let Some(balance) = balance {
+                balances.insert(name.to_string(), balance);
+            }
+
+            let latest = fetch_nonce(&rpc_client, &wallet.address, "latest").await;
+            let pending = fetch_nonce(&rpc_client, &wallet.address, "pending").await;
+            let Some((latest, pending)) = latest.zip(pending) else {
+                nonces_fetched = false;
+                continue;
+            };
+            let nonce = wallet.nonce.get_or_insert_with(NonceStatus::default);
+            nonce.update(latest, pending, now, config.stuck_nonce_threshold_s());
+
+            let alert_id = format!("wallet_stuck_transactions:{}", name);
+            if nonce.stuck {
+                alerts
+                    .raise(
+                        alert_id,
+                        Severity::Warning,
+                        format!(
+                            "{} wallet {} has {} transactions pending for over {}s",
+                            name,
+                            wallet.address,
+                            pending - latest,
+                            config.stuck_nonce_threshold_s()
+                        ),
+                    )
+                    .await;
+            } else {
+                alerts.resolve(&alert_id).await;
+            }
+        }
+        drop(locked_wallets);
+        history
+            .write()
+            .await
+            .record(BalanceSample { at: now, balances });
+
+        interval
+            .adapt(
+                balance_dep.is_some() && balance_val.is_some() && nonces_fetched,
+                &tasks,
+            )
+            .await;
+        tasks.record_refresh(WALLET_BALANCES_TASK).await;
     }
 }
 
+/// Parses a hex `eth_getBalance` result into Wei (integer)
+fn parse_balance(balance_hex: &str) -> Option<String> {
+    balance_hex
+        .strip_prefix("0x")
+        .and_then(|s| u128::from_str_radix(s, 16).ok())
+        .map(|wei| wei.to_string())
+}
+
 /// Fetches the ETH balance of a given wallet address in Wei (integer)
 pub async fn fetch_wallet_balance(client: &HttpClient, wallet_address: &str) -> Option<String> {
     info!(%wallet_address, "Fetching balance for wallet");
@@ -78,29 +399,240 @@ pub async fn fetch_wallet_balance(client: &HttpClient, wallet_address: &str) ->
 
     match response {
         Ok(json) => {
-            if let Some(balance_hex) = json
-                .as_str()
-                .and_then(|s| s.strip_prefix("0x"))
-                .and_then(|s| u128::from_str_radix(s, 16).ok())
-            {
-                return Some(balance_hex.to_string());
+            if let Some(balance) = json.as_str().and_then(parse_balance) {
+                return Some(balance);
             }
         }
         Err(e) => {
             info!(%e, "Error fetching balance");
+            degradation::record_failure("reth", rpc_failure(&e).reason);
         }
     }
     None
 }
 
+/// Fetches the ETH balances of `addresses` in Wei in one JSON-RPC batch, in the same
+/// order. Falls back to one query per address if the node rejects the batch.
+pub async fn fetch_wallet_balances(
+    client: &HttpClient,
+    addresses: &[String],
+) -> Vec<Option<String>> {
+    let mut batch = BatchRequestBuilder::new();
+    for address in addresses {
+        batch
+            .insert("eth_getBalance", (address.as_str(), "latest"))
+            .expect("to serialize eth_getBalance params");
+    }
+
+    match client.batch_request::<String>(batch).await {
+        Ok(responses) => responses
+            .into_iter()
+            .zip(addresses)
+            .map(|(response, address)| match response {
+                Ok(balance_hex) => parse_balance(&balance_hex),
+                Err(e) => {
+                    info!(%e, wallet_address = %address, "Error fetching balance");
+                    degradation::record_failure("reth", FailureKind::RpcError);
+                    None
+                }
+            })
+            .collect(),
+        Err(e) => {
+            info!(%e, wallets = addresses.len(), "Error fetching balances in a batch");
+            let mut balances = Vec::with_capacity(addresses.len());
+            for address in addresses {
+                balances.push(fetch_wallet_balance(client, address).await);
+            }
+            balances
+        }
+    }
+}
+
+/// Fetches the number of transactions sent by a wallet, as of the `latest` block
+/// or including the `pending` ones in the mempool
+async fn fetch_nonce(client: &HttpClient, wallet_address: &str, block: &str) -> Option<u64> {
+    let response: Result<String, _> = client
+        .request("eth_getTransactionCount", (wallet_address, block))
+        .await;
+    match response {
+        Ok(nonce) => nonce
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok()),
+        Err(e) => {
+            info!(%e, %wallet_address, block, "Error fetching nonce");
+            degradation::record_failure("reth", rpc_failure(&e).reason);
+            None
+        }
+    }
+}
+
 /// Handler to fetch ETH wallet balances
 pub async fn get_wallets_with_balances(wallets: SharedWallets) -> Json<serde_json::Value> {
     let locked_wallets = wallets.read().await;
     Json(json!({ "wallets": *locked_wallets }))
 }
 
+/// Query parameters of the balance history endpoint
+#[derive(Deserialize, Debug)]
+pub struct BalanceHistoryQuery {
+    /// Length of the history, e.g. `6h`
+    window: Option<String>,
+}
+
+/// Balance samples within the requested window
+#[derive(Serialize, Debug)]
+pub struct BalanceHistoryResponse {
+    window_s: i64,
+    /// Time of the oldest retained sample, later than the start of the window
+    /// once the history is full
+    available_since: Option<DateTime<Utc>>,
+    samples: Vec<BalanceSample>,
+}
+
+/// Handler returning the recent balances of the monitored wallets
+pub async fn get_balance_history(
+    Query(query): Query<BalanceHistoryQuery>,
+    history: SharedBalanceHistory,
+) -> Result<Json<BalanceHistoryResponse>, StatusCode> {
+    let window = parse_duration(query.window.as_deref().unwrap_or("24h"))
+        .filter(|window| *window > Duration::zero())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let history = history.read().await;
+    Ok(Json(BalanceHistoryResponse {
+        window_s: window.num_seconds(),
+        available_since: history.store.oldest(),
+        samples: history.since(Utc::now() - window),
+    }))
+}
+
 pub fn init_paymaster_wallets(config: &NetworkConfig) -> SharedWallets {
-    let deposit = Wallet::new(config.deposit_wallet().to_string(), "0".to_string());
-    let validating = Wallet::new(config.validating_wallet().to_string(), "0".to_string());
-    Arc::new(RwLock::new(PaymasterWallets::new(deposit, validating))) // ✅ Returns tokio::sync::Mutex
+    let symbol = config.native_token_symbol();
+    let deposit = Wallet::new(config.deposit_wallet().to_string(), "0".to_string(), symbol);
+    let validating = Wallet::new(
+        config.validating_wallet().to_string(),
+        "0".to_string(),
+        symbol,
+    );
+    let operators = config
+        .operator_wallets()
+        .iter()
+        .map(|(name, address)| {
+            (
+                name.clone(),
+                Wallet::new(address.clone(), "0".to_string(), symbol),
+            )
+        })
+        .collect();
+    Arc::new(RwLock::new(PaymasterWallets::new(
+        deposit, validating, operators,
+    ))) // ✅ Returns tokio::sync::Mutex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        balance_crossing, critical_crossing, parse_balance, BalanceHistory, BalanceSample,
+        NonceStatus, Wallet,
+    };
+    use crate::events::MonitorEvent;
+    use chrono::{Duration, Utc};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_balance_display() {
+        let wallet = Wallet::new(
+            "0xCAFE".to_string(),
+            "3400000000000000000".to_string(),
+            "ETH",
+        );
+        assert_eq!(wallet.balance_display, "3.4 ETH");
+        assert_eq!(
+            Wallet::new("0xCAFE".to_string(), "bad".to_string(), "ETH").balance_display,
+            ""
+        );
+    }
+
+    #[test]
+    fn test_parse_balance() {
+        assert_eq!(
+            parse_balance("0x2f2f39fc6c540000"),
+            Some("3400000000000000000".to_string())
+        );
+        assert_eq!(parse_balance("0x0"), Some("0".to_string()));
+        assert_eq!(parse_balance("12"), None);
+    }
+
+    #[test]
+    fn test_balance_crossing() {
+        let wallet = |balance: &str| Wallet::new("0xCAFE".to_string(), balance.to_string(), "ETH");
+        let mut low = false;
+
+        assert!(balance_crossing("deposit", &wallet("200"), 100, &mut low).is_none());
+        assert!(matches!(
+            balance_crossing("deposit", &wallet("99"), 100, &mut low),
+            Some(MonitorEvent::BalanceLow { .. })
+        ));
+        // Only crossings are published
+        assert!(balance_crossing("deposit", &wallet("50"), 100, &mut low).is_none());
+        assert!(matches!(
+            balance_crossing("deposit", &wallet("100"), 100, &mut low),
+            Some(MonitorEvent::BalanceRecovered { .. })
+        ));
+        assert!(!low);
+
+        let mut critical = false;
+        assert!(matches!(
+            critical_crossing("deposit", &wallet("9"), 10, &mut critical),
+            Some(MonitorEvent::BalanceCritical { .. })
+        ));
+        // Back above the threshold, but nothing to top up
+        assert!(critical_crossing("deposit", &wallet("20"), 10, &mut critical).is_none());
+        assert!(!critical);
+    }
+
+    #[test]
+    fn test_stuck_nonce() {
+        let start = Utc::now();
+        let at = |s| start + Duration::seconds(s);
+        let mut nonce = NonceStatus::default();
+
+        nonce.update(5, 5, at(0), 60);
+        assert_eq!(nonce.gap_since, None);
+        nonce.update(5, 7, at(10), 60);
+        assert_eq!(nonce.gap_since, Some(at(10)));
+        nonce.update(5, 7, at(71), 60);
+        assert!(nonce.stuck);
+
+        // A mined transaction restarts the clock
+        nonce.update(6, 7, at(80), 60);
+        assert_eq!(nonce.gap_since, Some(at(80)));
+        assert!(!nonce.stuck);
+        nonce.update(7, 7, at(90), 60);
+        assert_eq!(
+            nonce,
+            NonceStatus {
+                latest: 7,
+                pending: 7,
+                gap_since: None,
+                stuck: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_balance_history_is_bounded() {
+        let now = Utc::now();
+        let mut history = BalanceHistory::new(2);
+        for i in 0..3 {
+            history.record(BalanceSample {
+                at: now + Duration::seconds(i),
+                balances: BTreeMap::from([("deposit".to_string(), i.to_string())]),
+            });
+        }
+
+        assert_eq!(history.since(now).len(), 2);
+        let recent = history.since(now + Duration::seconds(2));
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].balances["deposit"], "2");
+    }
 }
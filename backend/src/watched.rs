@@ -0,0 +1,302 @@
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::{auth::AdminAuth, checkpoint};
+
+/// Bridged asset contract on L2, whose total supply is cross-checked against the
+/// outstanding deposits
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BridgeContract {
+    pub address: String,
+    /// Decimals of the asset
+    pub decimals: u32,
+}
+
+/// Contracts inspected by the monitoring tasks
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct WatchedContractsState {
+    /// ERC-4337 entry points whose bundler mempool is inspected
+    pub entry_points: Vec<String>,
+    /// The L2 supply is the sum of the supplies of these contracts
+    pub bridge_contracts: Vec<BridgeContract>,
+}
+
+/// Contracts watched by the bundler and bridge tasks, editable at runtime through
+/// the admin endpoints. They are seeded from the env configuration until first
+/// edited, then persisted to `WATCHED_CONTRACTS_PATH` when configured.
+#[derive(Clone, Debug, Default)]
+pub struct WatchedContracts {
+    path: Option<Arc<str>>,
+    state: Arc<RwLock<WatchedContractsState>>,
+}
+
+/// Whether `address` is a 0x-prefixed 20-byte hex address
+fn is_evm_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl WatchedContracts {
+    /// Loads the contracts stored at `path`, or `defaults` if none are
+    pub fn load(path: Option<&str>, defaults: WatchedContractsState) -> Self {
+        let state = match path.and_then(checkpoint::load::<WatchedContractsState>) {
+            Some(state) => {
+                info!(
+                    path = path.unwrap_or_default(),
+                    entry_points = state.entry_points.len(),
+                    bridge_contracts = state.bridge_contracts.len(),
+                    "Loaded watched contracts"
+                );
+                state
+            }
+            None => defaults,
+        };
+        Self {
+            path: path.map(Arc::from),
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    /// Returns the watched contracts
+    pub async fn snapshot(&self) -> WatchedContractsState {
+        self.state.read().await.clone()
+    }
+
+    /// Getter for the watched entry points
+    pub async fn entry_points(&self) -> Vec<String> {
+        self.state.read().await.entry_points.clone()
+    }
+
+    /// Getter for the watched bridge contracts
+    pub async fn bridge_contracts(&self) -> Vec<BridgeContract> {
+        self.state.read().await.bridge_contracts.clone()
+    }
+
+    /// Applies `edit` to the watched contracts and persists them, `None` if `edit`
+    /// returns false as nothing changed
+    async fn update(
+        &self,
+        edit: impl FnOnce(&mut WatchedContractsState) -> bool,
+    ) -> Option<WatchedContractsState> {
+        let mut state = self.state.write().await;
+        if !edit(&mut state) {
+            return None;
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = checkpoint::save(path, &*state) {
+                warn!(error = %e, "Failed to store watched contracts");
+            }
+        }
+        Some(state.clone())
+    }
+
+    /// Watches an entry point, `None` if it was already watched
+    pub async fn add_entry_point(&self, address: &str) -> Option<WatchedContractsState> {
+        self.update(|state| {
+            if state
+                .entry_points
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(address))
+            {
+                return false;
+            }
+            state.entry_points.push(address.to_string());
+            true
+        })
+        .await
+    }
+
+    /// Stops watching an entry point, `None` if it was not watched
+    pub async fn remove_entry_point(&self, address: &str) -> Option<WatchedContractsState> {
+        self.update(|state| {
+            let len = state.entry_points.len();
+            state
+                .entry_points
+                .retain(|e| !e.eq_ignore_ascii_case(address));
+            state.entry_points.len() < len
+        })
+        .await
+    }
+
+    /// Watches a bridge contract or updates its decimals, `None` if it was
+    /// already watched as is
+    pub async fn add_bridge_contract(
+        &self,
+        contract: BridgeContract,
+    ) -> Option<WatchedContractsState> {
+        self.update(|state| {
+            match state
+                .bridge_contracts
+                .iter_mut()
+                .find(|c| c.address.eq_ignore_ascii_case(&contract.address))
+            {
+                Some(watched) if watched.decimals == contract.decimals => return false,
+                Some(watched) => watched.decimals = contract.decimals,
+                None => state.bridge_contracts.push(contract),
+            }
+            true
+        })
+        .await
+    }
+
+    /// Stops watching a bridge contract, `None` if it was not watched
+    pub async fn remove_bridge_contract(&self, address: &str) -> Option<WatchedContractsState> {
+        self.update(|state| {
+            let len = state.bridge_contracts.len();
+            state
+                .bridge_contracts
+                .retain(|c| !c.address.eq_ignore_ascii_case(address));
+            state.bridge_contracts.len() < len
+        })
+        .await
+    }
+}
+
+/// Body of the entry point creation endpoint
+#[derive(Deserialize, Debug)]
+pub struct EntryPointRequest {
+    address: String,
+}
+
+/// List the watched contracts. Requires the admin token.
+pub async fn get_watched_contracts(
+    headers: HeaderMap,
+    auth: AdminAuth,
+    watched: WatchedContracts,
+) -> Result<Json<WatchedContractsState>, StatusCode> {
+    auth.check(&headers)?;
+    Ok(Json(watched.snapshot().await))
+}
+
+/// Watch an entry point. Requires the admin token.
+pub async fn post_entry_point(
+    headers: HeaderMap,
+    Json(request): Json<EntryPointRequest>,
+    auth: AdminAuth,
+    watched: WatchedContracts,
+) -> Result<Json<WatchedContractsState>, StatusCode> {
+    auth.check(&headers)?;
+    if !is_evm_address(&request.address) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let state = watched
+        .add_entry_point(&request.address)
+        .await
+        .ok_or(StatusCode::CONFLICT)?;
+    info!(address = %request.address, "Watching entry point");
+    Ok(Json(state))
+}
+
+/// Stop watching an entry point. Requires the admin token.
+pub async fn delete_entry_point(
+    Path(address): Path<String>,
+    headers: HeaderMap,
+    auth: AdminAuth,
+    watched: WatchedContracts,
+) -> Result<Json<WatchedContractsState>, StatusCode> {
+    auth.check(&headers)?;
+    let state = watched
+        .remove_entry_point(&address)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    info!(%address, "Stopped watching entry point");
+    Ok(Json(state))
+}
+
+/// Watch a bridge contract. Requires the admin token.
+pub async fn post_bridge_contract(
+    headers: HeaderMap,
+    Json(contract): Json<BridgeContract>,
+    auth: AdminAuth,
+    watched: WatchedContracts,
+) -> Result<Json<WatchedContractsState>, StatusCode> {
+    auth.check(&headers)?;
+    // 10^38 is the largest power of 10 fitting in the u128 supply
+    if !is_evm_address(&contract.address) || contract.decimals > 38 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (address, decimals) = (contract.address.clone(), contract.decimals);
+    let state = watched
+        .add_bridge_contract(contract)
+        .await
+        .ok_or(StatusCode::CONFLICT)?;
+    info!(%address, decimals, "Watching bridge contract");
+    Ok(Json(state))
+}
+
+/// Stop watching a bridge contract. Requires the admin token.
+pub async fn delete_bridge_contract(
+    Path(address): Path<String>,
+    headers: HeaderMap,
+    auth: AdminAuth,
+    watched: WatchedContracts,
+) -> Result<Json<WatchedContractsState>, StatusCode> {
+    auth.check(&headers)?;
+    let state = watched
+        .remove_bridge_contract(&address)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    info!(%address, "Stopped watching bridge contract");
+    Ok(Json(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_evm_address, BridgeContract, WatchedContracts, WatchedContractsState};
+
+    const ENTRY_POINT: &str = "0x0000000071727De22E5E9d8BAf0edAc6f37da032";
+    const ASSET: &str = "0x1111111111111111111111111111111111111111";
+
+    #[tokio::test]
+    async fn test_watched_contracts_persistence() {
+        let path = std::env::temp_dir().join(format!("watched_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let defaults = WatchedContractsState {
+            entry_points: vec![ENTRY_POINT.to_string()],
+            bridge_contracts: Vec::new(),
+        };
+        let watched = WatchedContracts::load(Some(&path), defaults.clone());
+        assert_eq!(watched.snapshot().await, defaults);
+
+        // Entry points are matched case-insensitively
+        assert!(watched
+            .add_entry_point(&ENTRY_POINT.to_lowercase())
+            .await
+            .is_none());
+        let contract = |decimals| BridgeContract {
+            address: ASSET.to_string(),
+            decimals,
+        };
+        assert!(watched.add_bridge_contract(contract(8)).await.is_some());
+        assert!(watched.add_bridge_contract(contract(18)).await.is_some());
+        assert!(watched.add_bridge_contract(contract(18)).await.is_none());
+        assert_eq!(watched.bridge_contracts().await, vec![contract(18)]);
+        assert!(watched.remove_entry_point(ENTRY_POINT).await.is_some());
+        assert!(watched.remove_entry_point(ENTRY_POINT).await.is_none());
+
+        // The persisted contracts take precedence over the defaults
+        let reloaded = WatchedContracts::load(Some(&path), defaults);
+        assert!(reloaded.entry_points().await.is_empty());
+        assert_eq!(reloaded.bridge_contracts().await, vec![contract(18)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_is_evm_address() {
+        assert!(is_evm_address(ENTRY_POINT));
+        assert!(!is_evm_address("0x1234"));
+        assert!(!is_evm_address(&ENTRY_POINT[2..]));
+        assert!(!is_evm_address(&ENTRY_POINT.replace('D', "G")));
+    }
+}